@@ -60,6 +60,7 @@ fn benchmark_scheduler_policies(c: &mut Criterion) {
                     pipeline: Pipeline {
                         ops: vec![Op::Decode],
                         mutation_tag: None,
+                        id: None,
                     },
                     qos: QoS::Balanced,
                     deadline_ms: 1000 - (i * 10),
@@ -72,15 +73,6 @@ fn benchmark_scheduler_policies(c: &mut Criterion) {
                 (Entity::from_raw(0), &Worker {
                     id: 0,
                     class: WorkClass::Cpu,
-                    skill_cpu: 1.0,
-                    skill_gpu: 0.0,
-                    skill_io: 0.0,
-                    discipline: 1.0,
-                    focus: 1.0,
-                    corruption: 0.0,
-                    state: WorkerState::Idle,
-                    retry: RetryPolicy::default(),
-                    sticky_faults: 0,
                 }),
             ];
             
@@ -120,6 +112,7 @@ fn benchmark_gpu_batching(c: &mut Criterion) {
                     pipeline: Pipeline {
                         ops: vec![Op::GpuPreprocess, Op::Yolo, Op::GpuExport],
                         mutation_tag: None,
+                        id: None,
                     },
                     qos: QoS::Balanced,
                     deadline_ms: 100,
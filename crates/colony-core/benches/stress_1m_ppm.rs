@@ -0,0 +1,34 @@
+//! Performance envelope: 1M packets/minute stress profile.
+//! Writes the report to target/perf/stress_1m_ppm.json for `xtask perf`.
+
+use colony_core::{run_stress, CountingAllocator, StressProfile};
+
+#[global_allocator]
+static ALLOC: CountingAllocator = CountingAllocator;
+
+fn main() {
+    // COLONY_STRESS_TICKS shortens the run for quick local checks
+    let mut profile = StressProfile::million_per_minute();
+    if let Some(ticks) = std::env::var("COLONY_STRESS_TICKS").ok().and_then(|t| t.parse().ok()) {
+        profile.ticks = ticks;
+    }
+
+    let report = run_stress(&profile).expect("stress run failed");
+
+    println!("stress profile {}", report.profile);
+    println!("  ticks:              {}", report.ticks);
+    println!("  ticks/sec:          {:.1}", report.ticks_per_sec);
+    println!("  packets injected:   {} ({} dropped)", report.packets_injected, report.packets_dropped);
+    println!("  jobs completed:     {}", report.jobs_completed);
+    println!("  max queue depth:    {}", report.max_queue_depth);
+    println!("  queue latency:      mean {:.1} ticks, p99 {} ticks", report.mean_queue_latency_ticks, report.p99_queue_latency_ticks);
+    println!("  allocations/tick:   {:.1} ({} bytes total)", report.allocations_per_tick, report.allocated_bytes);
+
+    let out_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../target/perf");
+    std::fs::create_dir_all(&out_dir).expect("create target/perf");
+    std::fs::write(
+        out_dir.join("stress_1m_ppm.json"),
+        serde_json::to_string_pretty(&report).expect("serialize report"),
+    )
+    .expect("write stress report");
+}
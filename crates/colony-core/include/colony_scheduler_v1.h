@@ -0,0 +1,47 @@
+/* Colony external scheduler ABI, version 1.
+ *
+ * Build a shared library that exports colony_scheduler_v1() and pass it to colony-headless with
+ * --scheduler-lib (needs the ffi_scheduler feature). It is registered as "ffi/<name>".
+ *
+ * pick() runs on a thread of its own with copies of the queue. Write the indices of the jobs to
+ * run, best first, into out[0..out_cap) and return how many you wrote, or a negative value on
+ * error. Naming a job twice or out of range, or running past the timeout (5 ms by default),
+ * keeps the queue order for that tick; a library that keeps failing, or hangs once, is no longer
+ * called.
+ */
+#ifndef COLONY_SCHEDULER_V1_H
+#define COLONY_SCHEDULER_V1_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define COLONY_SCHEDULER_ABI_V1 1
+
+typedef struct ColonyJobV1 {
+    uint64_t id;
+    uint64_t deadline_ms;
+    uint32_t cost_ms;    /* sum of the pipeline's op costs */
+    uint32_t payload_sz;
+    uint32_t qos;        /* 0 throughput, 1 latency, 2 balanced */
+    uint32_t op_count;
+} ColonyJobV1;
+
+typedef struct ColonyYardV1 {
+    uint32_t kind;       /* 0 CPU array, 1 GPU farm, 2 signal hub, 3 storage array */
+    uint32_t slots;
+    float heat;
+    float heat_cap;
+} ColonyYardV1;
+
+typedef int64_t (*ColonyPickV1)(const ColonyYardV1 *yard, const ColonyJobV1 *jobs, size_t job_count,
+                                uint32_t *out, size_t out_cap);
+
+typedef struct ColonySchedulerV1 {
+    uint32_t abi_version; /* COLONY_SCHEDULER_ABI_V1 */
+    const char *name;     /* letters, digits, '_', '-' or '.'; static */
+    ColonyPickV1 pick;
+} ColonySchedulerV1;
+
+const ColonySchedulerV1 *colony_scheduler_v1(void);
+
+#endif
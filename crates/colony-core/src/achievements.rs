@@ -0,0 +1,522 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use crate::{SimClock, BlackSwanIndex, RitualRuns, SlaTracker, WinLossState, Digest};
+
+const MAX_JOURNAL_ENTRIES: usize = 10_000;
+const TICKS_PER_DAY: u64 = 86_400_000 / 16;
+
+pub const DEFAULT_PROFILE_PATH: &str = "saves/profile.json";
+
+/// Notable things that happened during a run, in the order they happened
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum JournalEvent {
+    BlackSwanFired { swan_id: String },
+    RitualCompleted { ritual_id: String, outcome: String },
+    DayClosed { sla_hit_pct: f32 },
+    Victory,
+    Loss { reason: String },
+}
+
+impl JournalEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JournalEvent::BlackSwanFired { .. } => "BlackSwanFired",
+            JournalEvent::RitualCompleted { .. } => "RitualCompleted",
+            JournalEvent::DayClosed { .. } => "DayClosed",
+            JournalEvent::Victory => "Victory",
+            JournalEvent::Loss { .. } => "Loss",
+        }
+    }
+
+    /// Secondary key rules can match on: swan id, ritual outcome, loss reason
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            JournalEvent::BlackSwanFired { swan_id } => Some(swan_id),
+            JournalEvent::RitualCompleted { outcome, .. } => Some(outcome),
+            JournalEvent::Loss { reason } => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub tick: u64,
+    pub day: u64,
+    pub event: JournalEvent,
+}
+
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EventJournal {
+    pub entries: VecDeque<JournalEntry>,
+    last_swan_tick: Option<u64>,
+    last_ritual_tick: Option<u64>,
+    last_day: Option<u64>,
+    last_sla: (u64, u64),  // (hits, total) of the tracker's current window when last seen
+    day_sla: (u64, u64),   // deadline results since the day opened
+    game_over_logged: bool,
+    #[serde(default)]
+    pub digests: VecDeque<Digest>,
+}
+
+impl EventJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, tick: u64, event: JournalEvent) {
+        self.entries.push_back(JournalEntry { tick, day: tick / TICKS_PER_DAY, event });
+        if self.entries.len() > MAX_JOURNAL_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+
+    fn matching<'a>(&'a self, event: &'a str, detail: Option<&'a str>) -> impl Iterator<Item = &'a JournalEntry> + 'a {
+        self.entries.iter().filter(move |e| {
+            e.event.name() == event && detail.is_none_or(|d| e.event.detail() == Some(d))
+        })
+    }
+
+    /// Fold in deadline results recorded since the last call; the tracker rolls its
+    /// window over every few days, so a shrinking total means the old one was archived
+    fn observe_sla(&mut self, sla: &SlaTracker) {
+        let current = (sla.current_window.hits, sla.current_window.total);
+        let (last_hits, last_total) = self.last_sla;
+        let (hits, total) = if current.1 >= last_total {
+            (current.0.saturating_sub(last_hits), current.1 - last_total)
+        } else {
+            let archived = sla.windows.back().map_or((0, 0), |w| (w.hits, w.total));
+            (archived.0.saturating_sub(last_hits) + current.0, archived.1.saturating_sub(last_total) + current.1)
+        };
+        self.day_sla.0 += hits;
+        self.day_sla.1 += total;
+        self.last_sla = current;
+    }
+
+    pub fn has_loss(&self) -> bool {
+        self.entries.iter().any(|e| matches!(e.event, JournalEvent::Loss { .. }))
+    }
+}
+
+/// Condition an achievement unlocks on, evaluated against the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AchievementRule {
+    /// At least `count` matching events within any `window_days` span, without losing the run
+    EventsWithin { event: String, detail: Option<String>, count: u32, window_days: u32 },
+    /// `days` consecutive closed days at or above the SLA hit percentage
+    SlaStreak { min_hit_pct: f32, days: u32 },
+    /// The first matching event unlocks it
+    FirstEvent { event: String, detail: Option<String> },
+}
+
+impl AchievementRule {
+    pub fn is_met(&self, journal: &EventJournal) -> bool {
+        match self {
+            AchievementRule::EventsWithin { event, detail, count, window_days } => {
+                if journal.has_loss() {
+                    return false;
+                }
+                let days: Vec<u64> = journal.matching(event, detail.as_deref()).map(|e| e.day).collect();
+                let needed = (*count).max(1) as usize;
+                days.windows(needed).any(|w| w[needed - 1] - w[0] < (*window_days).max(1) as u64)
+            }
+            AchievementRule::SlaStreak { min_hit_pct, days } => {
+                let mut streak = 0;
+                for entry in journal.matching("DayClosed", None) {
+                    if let JournalEvent::DayClosed { sla_hit_pct } = entry.event {
+                        streak = if sla_hit_pct >= *min_hit_pct { streak + 1 } else { 0 };
+                        if streak >= *days {
+                            return true;
+                        }
+                    }
+                }
+                false
+            }
+            AchievementRule::FirstEvent { event, detail } => {
+                journal.matching(event, detail.as_deref()).next().is_some()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AchievementDef {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub rule: AchievementRule,
+    #[serde(default)]
+    pub hidden: bool, // description withheld until unlocked
+}
+
+#[derive(Deserialize)]
+struct AchievementFile {
+    #[serde(default)]
+    achievement: Vec<AchievementDef>,
+}
+
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AchievementRegistry {
+    pub defs: Vec<AchievementDef>,
+}
+
+impl AchievementRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        for def in default_achievements() {
+            registry.defs.push(def);
+        }
+        registry
+    }
+
+    pub fn register(&mut self, def: AchievementDef) -> Result<()> {
+        if self.defs.iter().any(|d| d.id == def.id) {
+            return Err(anyhow::anyhow!("Achievement '{}' is already registered", def.id));
+        }
+        self.defs.push(def);
+        Ok(())
+    }
+
+    /// Register `[[achievement]]` tables from a mod's achievements.toml; returns how many were added
+    pub fn load_toml(&mut self, source: &str) -> Result<usize> {
+        let file: AchievementFile = toml::from_str(source)?;
+        let count = file.achievement.len();
+        for def in file.achievement {
+            self.register(def)?;
+        }
+        Ok(count)
+    }
+}
+
+pub fn default_achievements() -> Vec<AchievementDef> {
+    vec![
+        AchievementDef {
+            id: "storm_chaser".to_string(),
+            name: "Storm Chaser".to_string(),
+            description: "Survive 3 Black Swans in one day".to_string(),
+            rule: AchievementRule::EventsWithin {
+                event: "BlackSwanFired".to_string(),
+                detail: None,
+                count: 3,
+                window_days: 1,
+            },
+            hidden: false,
+        },
+        AchievementDef {
+            id: "three_nines_year".to_string(),
+            name: "Three Nines, One Year".to_string(),
+            description: "Finish a year with every day at 99.9% SLA".to_string(),
+            rule: AchievementRule::SlaStreak { min_hit_pct: 99.9, days: 365 },
+            hidden: false,
+        },
+        AchievementDef {
+            id: "first_rite".to_string(),
+            name: "First Rite".to_string(),
+            description: "Complete a ritual successfully".to_string(),
+            rule: AchievementRule::FirstEvent {
+                event: "RitualCompleted".to_string(),
+                detail: Some("Success".to_string()),
+            },
+            hidden: false,
+        },
+        AchievementDef {
+            id: "botched_rite".to_string(),
+            name: "Smoke and Mirrors".to_string(),
+            description: "Critically fail a ritual".to_string(),
+            rule: AchievementRule::FirstEvent {
+                event: "RitualCompleted".to_string(),
+                detail: Some("CriticalFailure".to_string()),
+            },
+            hidden: true,
+        },
+        AchievementDef {
+            id: "colony_victory".to_string(),
+            name: "Uptime Eternal".to_string(),
+            description: "Win a scenario".to_string(),
+            rule: AchievementRule::FirstEvent { event: "Victory".to_string(), detail: None },
+            hidden: false,
+        },
+    ]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnlockedAchievement {
+    pub id: String,
+    pub unlocked_at: chrono::DateTime<chrono::Utc>,
+    pub tick: u64,
+}
+
+/// Achievements earned across runs, stored outside any single save
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerProfile {
+    pub unlocked: Vec<UnlockedAchievement>,
+}
+
+impl PlayerProfile {
+    /// Load a profile, starting fresh if the file doesn't exist yet
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.iter().any(|u| u.id == id)
+    }
+}
+
+#[derive(Resource, Clone, Debug, Default)]
+pub struct Achievements {
+    pub profile: PlayerProfile,
+    pub profile_path: Option<PathBuf>, // None keeps unlocks in memory only (shadow worlds, tests)
+    pub newly_unlocked: Vec<String>,   // for UI toasts; drained by the reader
+}
+
+impl Achievements {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_profile(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        Ok(Self {
+            profile: PlayerProfile::load(&path)?,
+            profile_path: Some(path),
+            newly_unlocked: Vec::new(),
+        })
+    }
+
+    /// Unlock anything newly met; returns the ids unlocked by this call
+    pub fn evaluate(&mut self, registry: &AchievementRegistry, journal: &EventJournal, now: chrono::DateTime<chrono::Utc>, tick: u64) -> Vec<String> {
+        let unlocked: Vec<String> = registry.defs.iter()
+            .filter(|def| !self.profile.is_unlocked(&def.id) && def.rule.is_met(journal))
+            .map(|def| def.id.clone())
+            .collect();
+        for id in &unlocked {
+            self.profile.unlocked.push(UnlockedAchievement { id: id.clone(), unlocked_at: now, tick });
+            self.newly_unlocked.push(id.clone());
+        }
+        unlocked
+    }
+}
+
+/// Append journal entries for anything that changed since the last tick
+pub fn event_journal_system(
+    mut journal: ResMut<EventJournal>,
+    clock: Res<SimClock>,
+    black_swans: Option<Res<BlackSwanIndex>>,
+    rituals: Option<Res<RitualRuns>>,
+    win_loss: Option<Res<WinLossState>>,
+    sla: Option<Res<SlaTracker>>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+
+    if let Some(black_swans) = black_swans {
+        let mut fired: Vec<(String, u64)> = black_swans.meters.recently_fired.iter()
+            .filter(|(_, tick)| journal.last_swan_tick.is_none_or(|last| *tick > last))
+            .cloned()
+            .collect();
+        fired.sort_by_key(|(_, tick)| *tick);
+        for (swan_id, tick) in fired {
+            journal.push(tick, JournalEvent::BlackSwanFired { swan_id });
+            journal.last_swan_tick = Some(tick);
+        }
+    }
+
+    if let Some(rituals) = rituals {
+        let done: Vec<_> = rituals.results.iter()
+            .filter(|r| journal.last_ritual_tick.is_none_or(|last| r.tick > last))
+            .cloned()
+            .collect();
+        for result in done {
+            let outcome = match result.outcome {
+                crate::RitualOutcome::Success => "Success",
+                crate::RitualOutcome::PartialSuccess { .. } => "PartialSuccess",
+                crate::RitualOutcome::CriticalFailure => "CriticalFailure",
+            };
+            journal.push(result.tick, JournalEvent::RitualCompleted { ritual_id: result.ritual_id, outcome: outcome.to_string() });
+            journal.last_ritual_tick = Some(result.tick);
+        }
+    }
+
+    if let Some(sla) = sla {
+        journal.observe_sla(&sla);
+    }
+    let day = now_tick / TICKS_PER_DAY;
+    match journal.last_day {
+        Some(last) if day > last => {
+            let (hits, total) = journal.day_sla;
+            let sla_hit_pct = if total > 0 { hits as f32 / total as f32 * 100.0 } else { 100.0 };
+            journal.push(now_tick, JournalEvent::DayClosed { sla_hit_pct });
+            journal.last_day = Some(day);
+            journal.day_sla = (0, 0);
+        }
+        None => journal.last_day = Some(day),
+        _ => {}
+    }
+
+    if let Some(win_loss) = win_loss {
+        if !journal.game_over_logged && win_loss.is_game_over() {
+            let event = if win_loss.victory {
+                JournalEvent::Victory
+            } else {
+                JournalEvent::Loss { reason: win_loss.doom_reason.clone().unwrap_or_default() }
+            };
+            journal.push(now_tick, event);
+            journal.game_over_logged = true;
+        }
+    }
+}
+
+pub fn achievement_system(
+    mut achievements: ResMut<Achievements>,
+    registry: Res<AchievementRegistry>,
+    journal: Res<EventJournal>,
+    clock: Res<SimClock>,
+) {
+    if !journal.is_changed() {
+        return;
+    }
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    let unlocked = achievements.evaluate(&registry, &journal, chrono::Utc::now(), now_tick);
+    if unlocked.is_empty() {
+        return;
+    }
+
+    for id in &unlocked {
+        if let Some(def) = registry.defs.iter().find(|d| &d.id == id) {
+            println!("🏆 Achievement unlocked: {} - {}", def.name, def.description);
+        }
+    }
+    if let Some(path) = achievements.profile_path.clone() {
+        if let Err(e) = achievements.profile.save(&path) {
+            println!("Failed to save player profile: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swan_day(journal: &mut EventJournal, day: u64, n: u64) {
+        for i in 0..n {
+            journal.push(day * TICKS_PER_DAY + i * 100, JournalEvent::BlackSwanFired { swan_id: format!("swan_{}", i) });
+        }
+    }
+
+    #[test]
+    fn test_three_swans_in_one_day() {
+        let rule = AchievementRegistry::with_defaults().defs[0].rule.clone();
+
+        let mut journal = EventJournal::new();
+        swan_day(&mut journal, 0, 2);
+        swan_day(&mut journal, 1, 1);
+        assert!(!rule.is_met(&journal));
+
+        swan_day(&mut journal, 2, 3);
+        assert!(rule.is_met(&journal));
+
+        // Didn't survive
+        journal.push(3 * TICKS_PER_DAY, JournalEvent::Loss { reason: "SLA".to_string() });
+        assert!(!rule.is_met(&journal));
+    }
+
+    #[test]
+    fn test_sla_streak_resets_on_bad_day() {
+        let rule = AchievementRule::SlaStreak { min_hit_pct: 99.9, days: 3 };
+        let mut journal = EventJournal::new();
+        for (day, pct) in [99.95, 99.99, 98.0, 99.9, 99.95].iter().enumerate() {
+            journal.push(day as u64 * TICKS_PER_DAY, JournalEvent::DayClosed { sla_hit_pct: *pct });
+        }
+        assert!(!rule.is_met(&journal));
+        journal.push(5 * TICKS_PER_DAY, JournalEvent::DayClosed { sla_hit_pct: 100.0 });
+        assert!(rule.is_met(&journal));
+    }
+
+    #[test]
+    fn test_journal_closes_days_from_sla_tracker() {
+        let mut app = App::new();
+        app.insert_resource(SimClock {
+                tick_scale: crate::TickScale::RealTime,
+                now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+            })
+            .insert_resource(EventJournal::new())
+            .insert_resource(SlaTracker::new(7, TICKS_PER_DAY))
+            .add_systems(Update, event_journal_system);
+        app.update();
+
+        {
+            let mut sla = app.world_mut().resource_mut::<SlaTracker>();
+            for i in 0..10 {
+                sla.add_deadline_result(i != 0, 0);
+            }
+        }
+        app.world_mut().resource_mut::<SimClock>().now += chrono::Duration::days(1);
+        app.update();
+
+        let journal = app.world().resource::<EventJournal>();
+        assert_eq!(journal.entries.len(), 1);
+        assert_eq!(journal.entries[0].event, JournalEvent::DayClosed { sla_hit_pct: 90.0 });
+    }
+
+    #[test]
+    fn test_unlocks_once_and_persists() {
+        let registry = AchievementRegistry::with_defaults();
+        let mut journal = EventJournal::new();
+        journal.push(10, JournalEvent::Victory);
+
+        let mut achievements = Achievements::new();
+        assert_eq!(achievements.evaluate(&registry, &journal, chrono::Utc::now(), 10), vec!["colony_victory".to_string()]);
+        assert!(achievements.evaluate(&registry, &journal, chrono::Utc::now(), 20).is_empty());
+
+        let path = std::env::temp_dir().join(format!("colony_profile_{}.json", std::process::id()));
+        achievements.profile.save(&path).unwrap();
+        let loaded = PlayerProfile::load(&path).unwrap();
+        assert!(loaded.is_unlocked("colony_victory"));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_mod_achievements_from_toml() {
+        let mut registry = AchievementRegistry::with_defaults();
+        let added = registry.load_toml(r#"
+            [[achievement]]
+            id = "com.example.ash_walker"
+            name = "Ash Walker"
+            description = "Weather a Snow of Ash"
+            rule = { kind = "first_event", event = "BlackSwanFired", detail = "vram_ecc_propagation" }
+        "#).unwrap();
+        assert_eq!(added, 1);
+
+        let mut journal = EventJournal::new();
+        journal.push(5, JournalEvent::BlackSwanFired { swan_id: "vram_ecc_propagation".to_string() });
+        assert!(registry.defs.last().unwrap().rule.is_met(&journal));
+
+        // Ids must stay unique
+        assert!(registry.load_toml(r#"
+            [[achievement]]
+            id = "storm_chaser"
+            name = "Dupe"
+            description = "Dupe"
+            rule = { kind = "first_event", event = "Victory" }
+        "#).is_err());
+    }
+}
@@ -0,0 +1,348 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::VecDeque;
+use crate::{Op, SimClock};
+
+const MS_PER_TICK: u64 = 16;
+const GB: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageTierKind {
+    Hot,  // fast, small
+    Cold, // slow, large
+}
+
+/// An object written by ArchiveWrite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedObject {
+    pub job_id: u64,
+    pub bytes: u64,
+    pub written_tick: u64,
+}
+
+/// One storage tier: a capacity meter plus per-tick IOPS and throughput budgets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageTier {
+    pub kind: StorageTierKind,
+    pub iops_per_tick: u32,
+    pub bytes_per_tick: u64,
+    pub capacity_bytes: u64,
+    pub used_bytes: u64,
+    pub objects: VecDeque<ArchivedObject>, // oldest first
+    #[serde(skip)]
+    ops_this_tick: u32,
+    #[serde(skip)]
+    bytes_this_tick: u64,
+}
+
+impl StorageTier {
+    pub fn new(kind: StorageTierKind, iops_per_tick: u32, bytes_per_tick: u64, capacity_bytes: u64) -> Self {
+        Self {
+            kind,
+            iops_per_tick,
+            bytes_per_tick,
+            capacity_bytes,
+            used_bytes: 0,
+            objects: VecDeque::new(),
+            ops_this_tick: 0,
+            bytes_this_tick: 0,
+        }
+    }
+
+    pub fn fits(&self, bytes: u64) -> bool {
+        self.used_bytes + bytes <= self.capacity_bytes
+    }
+
+    pub fn fill_frac(&self) -> f32 {
+        if self.capacity_bytes == 0 { 1.0 } else { self.used_bytes as f32 / self.capacity_bytes as f32 }
+    }
+
+    /// Charge a write against this tick's budgets; returns ticks the write waits for IOPS or throughput
+    fn charge(&mut self, bytes: u64) -> u64 {
+        self.ops_this_tick += 1;
+        self.bytes_this_tick += bytes;
+        let iops_ticks = (self.ops_this_tick as u64).saturating_sub(1) / self.iops_per_tick.max(1) as u64;
+        let bytes_ticks = self.bytes_this_tick.saturating_sub(1) / self.bytes_per_tick.max(1);
+        iops_ticks.max(bytes_ticks)
+    }
+
+    fn store(&mut self, object: ArchivedObject) {
+        self.used_bytes += object.bytes;
+        self.objects.push_back(object);
+    }
+
+    fn evict_oldest(&mut self) -> Option<ArchivedObject> {
+        let object = self.objects.pop_front()?;
+        self.used_bytes -= object.bytes;
+        Some(object)
+    }
+
+    fn reset_budgets(&mut self) {
+        self.ops_this_tick = 0;
+        self.bytes_this_tick = 0;
+    }
+}
+
+/// What happens when both tiers are full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DeletionPolicy {
+    #[default]
+    RejectWrites,                  // keep everything; writes fail and their jobs miss the SLA
+    DeleteOldest,                  // evict the oldest cold objects to make room
+    Retention { max_age_ms: u64 }, // expire old objects; writes fail if that isn't enough
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveOutcome {
+    Stored(StorageTierKind),
+    Rejected, // storage full under the current policy
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveResult {
+    pub outcome: ArchiveOutcome,
+    pub wait_ticks: u64, // queued behind the tier's IOPS/throughput budget
+}
+
+/// Point-in-time storage metrics, as exported in run summaries and the API
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageMetrics {
+    pub hot_used_bytes: u64,
+    pub hot_capacity_bytes: u64,
+    pub cold_used_bytes: u64,
+    pub cold_capacity_bytes: u64,
+    pub objects: usize,
+    pub writes: u64,
+    pub rejected: u64,
+    pub throttled: u64,
+    pub demoted: u64,
+    pub deleted_objects: u64,
+    pub deleted_bytes: u64,
+}
+
+/// Two-tier object storage behind Op::ArchiveWrite. Writes land hot and demote to cold as they age.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct ArchiveStore {
+    pub hot: StorageTier,
+    pub cold: StorageTier,
+    pub policy: DeletionPolicy,
+    pub demote_after_ms: u64,
+    pub writes: u64,
+    pub rejected: u64,
+    pub throttled: u64,        // writes that waited on IOPS or throughput
+    pub demoted: u64,
+    pub deleted_objects: u64,
+    pub deleted_bytes: u64,
+}
+
+impl Default for ArchiveStore {
+    fn default() -> Self {
+        Self {
+            hot: StorageTier::new(StorageTierKind::Hot, 64, 8 * 1024 * 1024, 64 * GB),
+            cold: StorageTier::new(StorageTierKind::Cold, 4, 2 * 1024 * 1024, 1024 * GB),
+            policy: DeletionPolicy::default(),
+            demote_after_ms: 3_600_000,
+            writes: 0,
+            rejected: 0,
+            throttled: 0,
+            demoted: 0,
+            deleted_objects: 0,
+            deleted_bytes: 0,
+        }
+    }
+}
+
+impl ArchiveStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policy(policy: DeletionPolicy) -> Self {
+        Self { policy, ..Self::default() }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.hot.used_bytes >= self.hot.capacity_bytes && self.cold.used_bytes >= self.cold.capacity_bytes
+    }
+
+    fn delete(&mut self, object: ArchivedObject) {
+        self.deleted_objects += 1;
+        self.deleted_bytes += object.bytes;
+    }
+
+    /// Archive one payload; spills to cold when hot is full and falls back on the deletion policy when both are
+    pub fn write(&mut self, job_id: u64, bytes: u64, tick: u64) -> ArchiveResult {
+        if !self.hot.fits(bytes) && !self.cold.fits(bytes) && self.policy == DeletionPolicy::DeleteOldest {
+            while !self.cold.fits(bytes) {
+                match self.cold.evict_oldest() {
+                    Some(object) => self.delete(object),
+                    None => break,
+                }
+            }
+        }
+
+        let object = ArchivedObject { job_id, bytes, written_tick: tick };
+        let (tier, wait_ticks) = if self.hot.fits(bytes) {
+            let wait = self.hot.charge(bytes);
+            self.hot.store(object);
+            (StorageTierKind::Hot, wait)
+        } else if self.cold.fits(bytes) {
+            let wait = self.cold.charge(bytes);
+            self.cold.store(object);
+            (StorageTierKind::Cold, wait)
+        } else {
+            self.rejected += 1;
+            return ArchiveResult { outcome: ArchiveOutcome::Rejected, wait_ticks: 0 };
+        };
+
+        self.writes += 1;
+        if wait_ticks > 0 {
+            self.throttled += 1;
+        }
+        ArchiveResult { outcome: ArchiveOutcome::Stored(tier), wait_ticks }
+    }
+
+    /// Drop objects past the retention age, if the policy has one
+    pub fn expire(&mut self, now_tick: u64) {
+        let DeletionPolicy::Retention { max_age_ms } = self.policy else { return };
+        let max_age_ticks = max_age_ms / MS_PER_TICK;
+        for kind in [StorageTierKind::Cold, StorageTierKind::Hot] {
+            loop {
+                let tier = match kind {
+                    StorageTierKind::Hot => &mut self.hot,
+                    StorageTierKind::Cold => &mut self.cold,
+                };
+                let expired = tier.objects.front().is_some_and(|o| now_tick.saturating_sub(o.written_tick) > max_age_ticks);
+                if !expired {
+                    break;
+                }
+                if let Some(object) = tier.evict_oldest() {
+                    self.delete(object);
+                }
+            }
+        }
+    }
+
+    /// Move aged objects from hot to cold, within cold's per-tick write budget
+    pub fn demote(&mut self, now_tick: u64) {
+        let demote_ticks = self.demote_after_ms / MS_PER_TICK;
+        let mut moved = 0;
+        while let Some(front) = self.hot.objects.front() {
+            if now_tick.saturating_sub(front.written_tick) < demote_ticks
+                || !self.cold.fits(front.bytes)
+                || moved >= self.cold.iops_per_tick
+            {
+                break;
+            }
+            let object = self.hot.evict_oldest().unwrap();
+            self.cold.charge(object.bytes);
+            self.cold.store(object);
+            moved += 1;
+        }
+        self.demoted += moved as u64;
+    }
+
+    pub fn metrics(&self) -> StorageMetrics {
+        StorageMetrics {
+            hot_used_bytes: self.hot.used_bytes,
+            hot_capacity_bytes: self.hot.capacity_bytes,
+            cold_used_bytes: self.cold.used_bytes,
+            cold_capacity_bytes: self.cold.capacity_bytes,
+            objects: self.hot.objects.len() + self.cold.objects.len(),
+            writes: self.writes,
+            rejected: self.rejected,
+            throttled: self.throttled,
+            demoted: self.demoted,
+            deleted_objects: self.deleted_objects,
+            deleted_bytes: self.deleted_bytes,
+        }
+    }
+}
+
+pub fn has_archive_write(ops: &[Op]) -> bool {
+    ops.iter().any(|op| matches!(op, Op::ArchiveWrite))
+}
+
+pub fn archive_system(
+    mut store: ResMut<ArchiveStore>,
+    clock: Res<SimClock>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    let was_full = store.is_full();
+
+    store.hot.reset_budgets();
+    store.cold.reset_budgets();
+    store.expire(now_tick);
+    store.demote(now_tick);
+
+    if store.is_full() && !was_full {
+        println!("Archive storage is full; policy {:?}", store.policy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_store(policy: DeletionPolicy) -> ArchiveStore {
+        let mut store = ArchiveStore::with_policy(policy);
+        store.hot = StorageTier::new(StorageTierKind::Hot, 2, 1000, 200);
+        store.cold = StorageTier::new(StorageTierKind::Cold, 1, 1000, 200);
+        store
+    }
+
+    #[test]
+    fn test_spill_then_reject_when_full() {
+        let mut store = small_store(DeletionPolicy::RejectWrites);
+        for id in 0..2 {
+            assert_eq!(store.write(id, 100, 0).outcome, ArchiveOutcome::Stored(StorageTierKind::Hot));
+        }
+        assert_eq!(store.write(2, 100, 0).outcome, ArchiveOutcome::Stored(StorageTierKind::Cold));
+        assert_eq!(store.write(3, 100, 0).outcome, ArchiveOutcome::Stored(StorageTierKind::Cold));
+        assert!(store.is_full());
+        assert_eq!(store.write(4, 100, 0).outcome, ArchiveOutcome::Rejected);
+        assert_eq!(store.metrics().rejected, 1);
+    }
+
+    #[test]
+    fn test_delete_oldest_makes_room() {
+        let mut store = small_store(DeletionPolicy::DeleteOldest);
+        for id in 0..4 {
+            store.write(id, 100, id);
+        }
+        assert_eq!(store.write(4, 100, 4).outcome, ArchiveOutcome::Stored(StorageTierKind::Cold));
+        assert_eq!(store.deleted_objects, 1);
+        assert_eq!(store.cold.objects.front().unwrap().job_id, 3);
+    }
+
+    #[test]
+    fn test_iops_budget_queues_writes() {
+        let mut store = small_store(DeletionPolicy::RejectWrites);
+        assert_eq!(store.write(0, 10, 0).wait_ticks, 0);
+        assert_eq!(store.write(1, 10, 0).wait_ticks, 0);
+        // Third write in the tick exceeds hot's 2 IOPS
+        assert_eq!(store.write(2, 10, 0).wait_ticks, 1);
+        assert_eq!(store.throttled, 1);
+        store.hot.reset_budgets();
+        assert_eq!(store.write(3, 10, 1).wait_ticks, 0);
+    }
+
+    #[test]
+    fn test_retention_and_demotion() {
+        let mut store = small_store(DeletionPolicy::Retention { max_age_ms: 160 });
+        store.demote_after_ms = 32;
+        store.write(0, 50, 0);
+        store.write(1, 50, 5);
+
+        // Cold takes one object per tick
+        store.demote(4);
+        assert_eq!((store.hot.objects.len(), store.cold.objects.len()), (1, 1));
+        assert_eq!(store.demoted, 1);
+
+        // Only the object written at tick 0 is past 10 ticks
+        store.expire(12);
+        assert_eq!(store.cold.objects.len(), 0);
+        assert_eq!(store.hot.objects.len(), 1);
+        assert_eq!(store.deleted_bytes, 50);
+    }
+}
@@ -0,0 +1,421 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use anyhow::Result;
+use rand::Rng;
+use crate::{Colony, GpuFarm, RngAudit, SeedStream, tick_rng, JobQueue, SimClock, Workyard, Worker, WorkClass, WorkerBundle, WorkerSkills, WorkerState, WorkerStateComp, Certifications, CertKind};
+
+const MAX_AUDIT: usize = 256;
+const TICKS_PER_SEC: f32 = 62.5;
+
+/// What a rule watches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleMetric {
+    CpuQueueDepth,
+    GpuQueueDepth,
+    IoQueueDepth,
+    StorageQueueDepth,
+    BandwidthUtil, // 0..1
+    PowerDrawKw,
+    MaxYardHeat,   // hottest yard, degrees
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Above,
+    Below,
+}
+
+/// What a rule does when it fires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScaleAction {
+    AddGpus { count: u32 },
+    RemoveGpus { count: u32 },
+    HireWorkers { class: WorkClass, count: u32 },
+    RetireWorkers { class: WorkClass, count: u32 }, // idle workers only
+}
+
+impl ScaleAction {
+    fn count(&self) -> u32 {
+        match self {
+            ScaleAction::AddGpus { count }
+            | ScaleAction::RemoveGpus { count }
+            | ScaleAction::HireWorkers { count, .. }
+            | ScaleAction::RetireWorkers { count, .. } => *count,
+        }
+    }
+
+    fn grows(&self) -> bool {
+        matches!(self, ScaleAction::AddGpus { .. } | ScaleAction::HireWorkers { .. })
+    }
+}
+
+/// "If `metric` stays `comparison` `threshold` for `sustain_secs`, do `action`"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScaleRule {
+    pub name: String,
+    pub metric: ScaleMetric,
+    pub comparison: Comparison,
+    pub threshold: f32,
+    #[serde(default)]
+    pub sustain_secs: f32,   // sim seconds the condition must hold
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: f32,  // sim seconds before the rule may fire again
+    pub action: ScaleAction,
+    #[serde(default)]
+    pub cost: f64,           // budget spent per unit added; removals are free
+    #[serde(default)]
+    pub limit: Option<u32>,  // most units when adding, fewest when removing
+}
+
+fn default_cooldown_secs() -> f32 { 60.0 }
+
+/// `[autoscale]` table in the game config, also set through `PUT /autoscale`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AutoscaleConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub dry_run: bool,    // evaluate and audit the rules without changing the colony
+    #[serde(default)]
+    pub budget: f64,      // total the rules may spend
+    #[serde(default)]
+    pub rules: Vec<ScaleRule>,
+}
+
+impl AutoscaleConfig {
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for rule in &self.rules {
+            if rule.name.is_empty() {
+                return Err(anyhow::anyhow!("Autoscale rule without a name"));
+            }
+            if !seen.insert(rule.name.as_str()) {
+                return Err(anyhow::anyhow!("Autoscale rule {} is defined more than once", rule.name));
+            }
+            if rule.action.count() == 0 {
+                return Err(anyhow::anyhow!("Autoscale rule {}: count must be at least 1", rule.name));
+            }
+            if rule.sustain_secs < 0.0 || rule.cooldown_secs < 0.0 || rule.cost < 0.0 {
+                return Err(anyhow::anyhow!("Autoscale rule {}: sustain_secs, cooldown_secs and cost can't be negative", rule.name));
+            }
+        }
+        if self.budget < 0.0 {
+            return Err(anyhow::anyhow!("Autoscale budget can't be negative"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleOutcome {
+    Applied,
+    DryRun,      // would have been applied
+    OverBudget,
+    AtLimit,
+    Unavailable, // nothing to scale, e.g. no GPU farm or no idle worker to retire
+}
+
+/// One entry of the scaling audit trail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScalingRecord {
+    pub tick: u64,
+    pub rule: String,
+    pub action: ScaleAction,
+    pub metric_value: f32,
+    pub outcome: ScaleOutcome,
+    pub units: u32,   // added or removed; 0 unless applied or dry run
+    pub before: u32,  // GPUs or workers of the class before the action
+    pub spent: f64,
+}
+
+/// The autoscaling policy engine: rules, the budget they have spent and what they did
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Autoscaler {
+    pub config: AutoscaleConfig,
+    pub spent: f64,
+    pub audit: VecDeque<ScalingRecord>,
+    breach_since: HashMap<String, u64>, // rule -> first tick its condition held
+    last_fired: HashMap<String, u64>,
+}
+
+impl Autoscaler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Swap the rules; spending and the audit trail carry over
+    pub fn configure(&mut self, config: &AutoscaleConfig) -> Result<()> {
+        config.validate()?;
+        self.config = config.clone();
+        self.breach_since.clear();
+        self.last_fired.clear();
+        Ok(())
+    }
+
+    pub fn remaining_budget(&self) -> f64 {
+        (self.config.budget - self.spent).max(0.0)
+    }
+
+    /// Rules whose condition has held long enough and that are out of cooldown, with the metric value
+    pub fn due_rules(&mut self, tick: u64, metric: impl Fn(ScaleMetric) -> f32) -> Vec<(usize, f32)> {
+        if !self.config.enabled {
+            return Vec::new();
+        }
+        let mut due = Vec::new();
+        for (i, rule) in self.config.rules.iter().enumerate() {
+            let value = metric(rule.metric);
+            let holds = match rule.comparison {
+                Comparison::Above => value > rule.threshold,
+                Comparison::Below => value < rule.threshold,
+            };
+            if !holds {
+                self.breach_since.remove(&rule.name);
+                continue;
+            }
+            let since = *self.breach_since.entry(rule.name.clone()).or_insert(tick);
+            let sustained = tick.saturating_sub(since) as f32 >= rule.sustain_secs * TICKS_PER_SEC;
+            let cooled = self.last_fired.get(&rule.name)
+                .is_none_or(|&fired| tick.saturating_sub(fired) as f32 >= rule.cooldown_secs * TICKS_PER_SEC);
+            if sustained && cooled {
+                due.push((i, value));
+            }
+        }
+        due
+    }
+
+    /// Settle a due rule against the budget and its limit, given `current` units (None if there is
+    /// nothing to scale) and how many of them can be removed. Charges the budget and audits the outcome.
+    pub fn decide(&mut self, rule_idx: usize, tick: u64, value: f32, current: Option<u32>, removable: u32) -> ScalingRecord {
+        let rule = self.config.rules[rule_idx].clone();
+        self.last_fired.insert(rule.name.clone(), tick);
+        self.breach_since.remove(&rule.name);
+        let count = rule.action.count();
+        let before = current.unwrap_or(0);
+
+        let mut record = ScalingRecord {
+            tick,
+            rule: rule.name.clone(),
+            action: rule.action.clone(),
+            metric_value: value,
+            outcome: ScaleOutcome::Unavailable,
+            units: 0,
+            before,
+            spent: 0.0,
+        };
+        // Removals never take the last unit
+        let floor = rule.limit.unwrap_or(1).max(1);
+        let units = match current {
+            None => 0,
+            Some(current) if rule.action.grows() => count.min(rule.limit.map_or(count, |max| max.saturating_sub(current))),
+            Some(current) => count.min(removable).min(current.saturating_sub(floor)),
+        };
+        if current.is_some() {
+            record.outcome = if units == 0 {
+                if rule.action.grows() || before <= floor {
+                    ScaleOutcome::AtLimit
+                } else {
+                    ScaleOutcome::Unavailable
+                }
+            } else {
+                let cost = if rule.action.grows() { rule.cost * units as f64 } else { 0.0 };
+                if cost > self.remaining_budget() {
+                    ScaleOutcome::OverBudget
+                } else {
+                    record.units = units;
+                    if self.config.dry_run {
+                        ScaleOutcome::DryRun
+                    } else {
+                        self.spent += cost;
+                        record.spent = cost;
+                        ScaleOutcome::Applied
+                    }
+                }
+            };
+        }
+
+        println!("Autoscale rule {} ({:?} at {:.2}): {:?} x{} -> {:?}", rule.name, rule.metric, value, rule.action, record.units, record.outcome);
+        self.audit.push_back(record.clone());
+        while self.audit.len() > MAX_AUDIT {
+            self.audit.pop_front();
+        }
+        record
+    }
+}
+
+const HIRE_SKILL_JITTER: f32 = 0.05;
+
+/// Class baseline skills, jittered per hire from the worker-gen stream
+fn hire_skills(class: WorkClass, seed: u64, id: u64) -> WorkerSkills {
+    let base = match class {
+        WorkClass::Gpu => WorkerSkills { cpu: 0.4, gpu: 0.9, io: 0.3, discipline: 0.8, focus: 0.9 },
+        _ => WorkerSkills { cpu: 0.8, gpu: 0.3, io: 0.6, discipline: 0.7, focus: 0.8 },
+    };
+    let mut rng = tick_rng(seed, id);
+    let mut jitter = |skill: f32| (skill + rng.gen_range(-HIRE_SKILL_JITTER..=HIRE_SKILL_JITTER)).clamp(0.0, 1.0);
+    WorkerSkills {
+        cpu: jitter(base.cpu),
+        gpu: jitter(base.gpu),
+        io: jitter(base.io),
+        discipline: jitter(base.discipline),
+        focus: jitter(base.focus),
+    }
+}
+
+/// Evaluate the autoscaling rules and carry out the actions they decide on
+pub fn autoscale_system(
+    mut commands: Commands,
+    mut autoscaler: ResMut<Autoscaler>,
+    colony: Res<Colony>,
+    jobq: Res<JobQueue>,
+    clock: Res<SimClock>,
+    yards: Query<&Workyard>,
+    mut farms: Query<&mut GpuFarm>,
+    workers: Query<(Entity, &Worker, &WorkerStateComp)>,
+    mut rng_audit: Option<ResMut<RngAudit>>,
+) {
+    let tick = clock.now.timestamp_millis() as u64 / 16;
+    let max_heat = yards.iter().map(|y| y.heat).fold(0.0, f32::max);
+    let due = autoscaler.due_rules(tick, |metric| match metric {
+        ScaleMetric::CpuQueueDepth => jobq.cpu.len() as f32,
+        ScaleMetric::GpuQueueDepth => jobq.gpu.len() as f32,
+        ScaleMetric::IoQueueDepth => jobq.io.len() as f32,
+        ScaleMetric::StorageQueueDepth => jobq.storage.len() as f32,
+        ScaleMetric::BandwidthUtil => colony.meters.bandwidth_util,
+        ScaleMetric::PowerDrawKw => colony.meters.power_draw_kw,
+        ScaleMetric::MaxYardHeat => max_heat,
+    });
+
+    for (rule_idx, value) in due {
+        let action = autoscaler.config.rules[rule_idx].action.clone();
+        match action {
+            ScaleAction::AddGpus { .. } | ScaleAction::RemoveGpus { .. } => {
+                let mut farm = farms.iter_mut().next();
+                let current = farm.as_ref().map(|f| f.gpus);
+                let record = autoscaler.decide(rule_idx, tick, value, current, current.unwrap_or(0));
+                if let (ScaleOutcome::Applied, Some(farm)) = (record.outcome, farm.as_mut()) {
+                    if action.grows() {
+                        farm.gpus += record.units;
+                    } else {
+                        farm.gpus -= record.units;
+                    }
+                }
+            }
+            ScaleAction::HireWorkers { class, .. } | ScaleAction::RetireWorkers { class, .. } => {
+                let mut of_class: Vec<(Entity, u64, bool)> = workers.iter()
+                    .filter(|(_, w, _)| w.class == class)
+                    .map(|(e, w, s)| (e, w.id, s.0 == WorkerState::Idle))
+                    .collect();
+                let idle = of_class.iter().filter(|(_, _, idle)| *idle).count() as u32;
+                let record = autoscaler.decide(rule_idx, tick, value, Some(of_class.len() as u32), idle);
+                if record.outcome != ScaleOutcome::Applied {
+                    continue;
+                }
+                if action.grows() {
+                    let next_id = workers.iter().map(|(_, w, _)| w.id + 1).max().unwrap_or(0);
+                    let hire_seed = colony.seed_for(SeedStream::WorkerGen);
+                    let certs: &[CertKind] = if class == WorkClass::Gpu { &[CertKind::Vision] } else { &[] };
+                    for id in next_id..next_id + record.units as u64 {
+                        if let Some(audit) = rng_audit.as_mut() {
+                            audit.record(SeedStream::WorkerGen, hire_seed, tick, "hire");
+                        }
+                        commands.spawn((WorkerBundle::new(id, class, hire_skills(class, hire_seed, id)), Certifications::new(certs)));
+                    }
+                } else {
+                    // Newest idle workers go first
+                    of_class.sort_by_key(|(_, id, _)| std::cmp::Reverse(*id));
+                    for (entity, _, _) in of_class.into_iter().filter(|(_, _, idle)| *idle).take(record.units as usize) {
+                        commands.entity(entity).despawn();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gpu_rule() -> ScaleRule {
+        ScaleRule {
+            name: "gpu_backlog".into(),
+            metric: ScaleMetric::GpuQueueDepth,
+            comparison: Comparison::Above,
+            threshold: 50.0,
+            sustain_secs: 300.0,
+            cooldown_secs: 60.0,
+            action: ScaleAction::AddGpus { count: 1 },
+            cost: 100.0,
+            limit: Some(3),
+        }
+    }
+
+    fn scaler(dry_run: bool, budget: f64) -> Autoscaler {
+        let mut scaler = Autoscaler::new();
+        scaler.configure(&AutoscaleConfig { enabled: true, dry_run, budget, rules: vec![gpu_rule()] }).unwrap();
+        scaler
+    }
+
+    #[test]
+    fn test_rule_fires_after_sustain_and_respects_cooldown() {
+        let mut scaler = scaler(false, 250.0);
+        let five_min = (300.0 * TICKS_PER_SEC) as u64;
+        assert!(scaler.due_rules(0, |_| 80.0).is_empty());
+        assert!(scaler.due_rules(five_min - 1, |_| 80.0).is_empty());
+        // A dip resets the clock
+        assert!(scaler.due_rules(five_min - 1, |_| 10.0).is_empty());
+        assert!(scaler.due_rules(five_min, |_| 80.0).is_empty());
+        let due = scaler.due_rules(2 * five_min, |_| 80.0);
+        assert_eq!(due, vec![(0, 80.0)]);
+
+        let record = scaler.decide(0, 2 * five_min, 80.0, Some(1), 1);
+        assert_eq!((record.outcome, record.units, record.spent), (ScaleOutcome::Applied, 1, 100.0));
+        // Cooling down, then sustained again
+        assert!(scaler.due_rules(2 * five_min + 10, |_| 80.0).is_empty());
+        assert!(!scaler.due_rules(3 * five_min + 10, |_| 80.0).is_empty());
+    }
+
+    #[test]
+    fn test_budget_limit_and_dry_run() {
+        let mut scaler = scaler(false, 150.0);
+        assert_eq!(scaler.decide(0, 0, 80.0, Some(1), 1).outcome, ScaleOutcome::Applied);
+        assert_eq!(scaler.decide(0, 0, 80.0, Some(2), 2).outcome, ScaleOutcome::OverBudget);
+        scaler.config.budget = 1000.0;
+        assert_eq!(scaler.decide(0, 0, 80.0, Some(3), 3).outcome, ScaleOutcome::AtLimit);
+        assert_eq!(scaler.decide(0, 0, 80.0, None, 0).outcome, ScaleOutcome::Unavailable);
+        assert_eq!(scaler.spent, 100.0);
+        assert_eq!(scaler.audit.len(), 4);
+
+        let mut dry = self::scaler(true, 1000.0);
+        let record = dry.decide(0, 0, 80.0, Some(1), 1);
+        assert_eq!((record.outcome, record.units), (ScaleOutcome::DryRun, 1));
+        assert_eq!(dry.spent, 0.0);
+    }
+
+    #[test]
+    fn test_retiring_keeps_one_and_only_takes_idle_workers() {
+        let mut scaler = Autoscaler::new();
+        let rule = ScaleRule {
+            name: "quiet".into(),
+            comparison: Comparison::Below,
+            threshold: 5.0,
+            action: ScaleAction::RetireWorkers { class: WorkClass::Cpu, count: 3 },
+            limit: None,
+            ..gpu_rule()
+        };
+        scaler.configure(&AutoscaleConfig { enabled: true, rules: vec![rule.clone()], ..Default::default() }).unwrap();
+        assert_eq!(scaler.decide(0, 0, 1.0, Some(4), 2).units, 2);
+        assert_eq!(scaler.decide(0, 0, 1.0, Some(2), 2).units, 1);
+        assert_eq!(scaler.decide(0, 0, 1.0, Some(1), 1).outcome, ScaleOutcome::AtLimit);
+        assert_eq!(scaler.decide(0, 0, 1.0, Some(4), 0).outcome, ScaleOutcome::Unavailable);
+
+        let twice = AutoscaleConfig { rules: vec![rule.clone(), rule], ..Default::default() };
+        assert!(twice.validate().is_err());
+    }
+}
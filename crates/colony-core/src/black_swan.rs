@@ -1,11 +1,14 @@
 use bevy::prelude::*;
 use serde::{Serialize, Deserialize};
-// HashMap import removed - not used in this file
-use super::{Debts, Debt};
+use anyhow::Result;
+use std::collections::HashMap;
+use super::{Debts, Debt, MetricId};
+
+const MAX_RESOLVED_CHOICES: usize = 64;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TriggerCond {
-    pub metric: String,      // e.g., "bandwidth_util", "gpu_thermal_events", "corruption_field", "vram_frac"
+    pub metric: MetricId,    // e.g., "bandwidth_util", "gpu_thermal_events", "corruption_field", "vram_frac"
     pub op: String,          // ">", ">=", "<", "<="
     pub value: f32,
     pub window_ms: u64,      // rolling window to evaluate condition
@@ -24,17 +27,48 @@ pub enum Effect {
     // System debuffs/debts
     DebtPowerMult { mult: f32, duration_ms: u64 },
     DebtHeatAdd { celsius: f32, duration_ms: u64 },
-    UIIllusion { metric: String, delta: f32, duration_ms: u64 },       // display skew only
+    UIIllusion { metric: MetricId, delta: f32, duration_ms: u64 },     // display skew only
     VramLeak { mb_per_tick: f32, duration_ms: u64 },
     BandwidthTax { mult: f32, duration_ms: u64 },
 
     // Fault weighting tweaks
     FaultBias { kind: String, weight_mult: f32, duration_ms: u64 },       // e.g., "StickyConfig"
 
+    // Take a whole yard out of dispatch
+    YardShutdown { yard: String, duration_ms: u64 },                      // yard: "CpuArray" | "GpuFarm" | "SignalHub" | "StorageArray"
+
+    // Personnel
+    RemoveWorker { yard: String },                                        // the yard's highest-skill worker is poached
+    IdleYard { yard: String, duration_ms: u64 },                          // workers below STRIKE_CROSSING_DISCIPLINE strike
+
+    // Take an export destination offline
+    SinkOutage { sink: String, duration_ms: u64 },                        // sink: "http_export"
+
     // Cure hook request
     RequireRitual { ritual_id: String },                                  // Engine will expose as actionable cure
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum Severity {
+    Minor,
+    #[default]
+    Major,
+    Critical,
+}
+
+/// One answer the player can give to a firing event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventChoice {
+    pub id: String,
+    pub label: String,                 // e.g. "Shut down GpuFarm for 1h"
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+}
+
+fn default_choice_timeout_ms() -> u64 {
+    60_000
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlackSwanDef {
     pub id: String,
@@ -44,6 +78,62 @@ pub struct BlackSwanDef {
     pub cure: Option<String>,          // ritual_id
     pub weight: f32,                   // selection weight if multiple eligible
     pub cooldown_ms: u64,              // after firing
+    #[serde(default)]
+    pub narrative: Option<String>,     // flavour text shown when the event fires
+    #[serde(default)]
+    pub severity: Severity,
+    #[serde(default)]
+    pub choices: Vec<EventChoice>,     // empty: the event resolves without asking
+    #[serde(default)]
+    pub default_choice: Option<String>, // taken on timeout; first choice if unset
+    #[serde(default = "default_choice_timeout_ms")]
+    pub choice_timeout_ms: u64,
+}
+
+/// A fired event waiting on the player; escalation is paused while any are pending
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingChoice {
+    pub swan_id: String,
+    pub name: String,
+    pub narrative: Option<String>,
+    pub severity: Severity,
+    pub choices: Vec<EventChoice>,
+    pub default_choice: String,
+    pub fired_tick: u64,
+    pub expires_tick: u64,
+    pub chosen: Option<String>,
+}
+
+impl PendingChoice {
+    pub fn from_def(def: &BlackSwanDef, current_tick: u64) -> Option<Self> {
+        let first = def.choices.first()?;
+        let default_choice = def.default_choice.clone()
+            .filter(|id| def.choices.iter().any(|c| &c.id == id))
+            .unwrap_or_else(|| first.id.clone());
+        Some(Self {
+            swan_id: def.id.clone(),
+            name: def.name.clone(),
+            narrative: def.narrative.clone(),
+            severity: def.severity,
+            choices: def.choices.clone(),
+            default_choice,
+            fired_tick: current_tick,
+            expires_tick: current_tick + def.choice_timeout_ms / 16,
+            chosen: None,
+        })
+    }
+
+    pub fn ticks_remaining(&self, current_tick: u64) -> u64 {
+        self.expires_tick.saturating_sub(current_tick)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedChoice {
+    pub swan_id: String,
+    pub choice_id: String,
+    pub tick: u64,
+    pub timed_out: bool,
 }
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
@@ -56,6 +146,10 @@ pub struct BlackSwanMeters {
 pub struct BlackSwanIndex {
     pub defs: Vec<BlackSwanDef>,
     pub meters: BlackSwanMeters,
+    #[serde(default)]
+    pub pending: Vec<PendingChoice>,
+    #[serde(default)]
+    pub resolved: Vec<ResolvedChoice>, // most recent last
 }
 
 impl BlackSwanIndex {
@@ -63,9 +157,83 @@ impl BlackSwanIndex {
         Self {
             defs: Vec::new(),
             meters: BlackSwanMeters::default(),
+            pending: Vec::new(),
+            resolved: Vec::new(),
         }
     }
 
+    pub fn has_pending_choice(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn pending_for(&self, swan_id: &str) -> Option<&PendingChoice> {
+        self.pending.iter().find(|p| p.swan_id == swan_id)
+    }
+
+    /// Answer a pending choice; effects are applied on the next scan
+    pub fn choose(&mut self, swan_id: &str, choice_id: &str) -> Result<()> {
+        let pending = self.pending.iter_mut()
+            .find(|p| p.swan_id == swan_id)
+            .ok_or_else(|| anyhow::anyhow!("No pending choice for event '{}'", swan_id))?;
+        if !pending.choices.iter().any(|c| c.id == choice_id) {
+            return Err(anyhow::anyhow!("Event '{}' has no choice '{}'", swan_id, choice_id));
+        }
+        pending.chosen = Some(choice_id.to_string());
+        Ok(())
+    }
+
+    /// Remove answered or timed-out choices, returning the effects of each selected option
+    pub fn take_resolved(&mut self, current_tick: u64) -> Vec<(ResolvedChoice, Vec<Effect>)> {
+        let mut out = Vec::new();
+        let mut still_pending = Vec::new();
+        for pending in self.pending.drain(..) {
+            let (choice_id, timed_out) = match (&pending.chosen, current_tick >= pending.expires_tick) {
+                (Some(chosen), _) => (chosen.clone(), false),
+                (None, true) => (pending.default_choice.clone(), true),
+                (None, false) => {
+                    still_pending.push(pending);
+                    continue;
+                }
+            };
+            let effects = pending.choices.iter()
+                .find(|c| c.id == choice_id)
+                .map(|c| c.effects.clone())
+                .unwrap_or_default();
+            out.push((ResolvedChoice { swan_id: pending.swan_id, choice_id, tick: current_tick, timed_out }, effects));
+        }
+        self.pending = still_pending;
+
+        for (resolved, _) in &out {
+            self.resolved.push(resolved.clone());
+        }
+        if self.resolved.len() > MAX_RESOLVED_CHOICES {
+            let excess = self.resolved.len() - MAX_RESOLVED_CHOICES;
+            self.resolved.drain(..excess);
+        }
+        out
+    }
+
+    /// Fire an event now: it becomes active, starts its cooldown and may ask for a choice.
+    /// Returns the effects the caller should apply.
+    pub fn fire(&mut self, swan_id: &str, current_tick: u64) -> Result<Vec<Effect>> {
+        let swan_def = self.defs.iter()
+            .find(|def| def.id == swan_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown Black Swan: {}", swan_id))?;
+        println!("Black Swan fired: {} - {} ({:?})", swan_def.id, swan_def.name, swan_def.severity);
+        if let Some(ref narrative) = swan_def.narrative {
+            println!("  {}", narrative);
+        }
+        let effects = swan_def.effects.clone();
+        let pending = PendingChoice::from_def(swan_def, current_tick);
+
+        self.mark_fired(swan_id.to_string(), current_tick);
+        self.meters.active.push(swan_id.to_string());
+        if let Some(pending) = pending {
+            self.pending.push(pending);
+        }
+        Ok(effects)
+    }
+
     pub fn add_black_swan(&mut self, def: BlackSwanDef) {
         self.defs.push(def);
     }
@@ -121,6 +289,8 @@ pub struct KpiRingBuffer {
     pub vram_frac: Vec<(f32, u64)>,
     pub power_draw: Vec<(f32, u64)>,
     pub heat_levels: Vec<(f32, u64)>,
+    #[serde(default)]
+    pub custom: HashMap<String, Vec<(f32, u64)>>, // mod-reported, keyed "<mod_id>:<metric>"
 }
 
 impl KpiRingBuffer {
@@ -132,6 +302,7 @@ impl KpiRingBuffer {
             vram_frac: Vec::new(),
             power_draw: Vec::new(),
             heat_levels: Vec::new(),
+            custom: HashMap::new(),
         }
     }
 
@@ -178,42 +349,68 @@ impl KpiRingBuffer {
         }
     }
 
-    pub fn get_metric_in_window(&self, metric: &str, window_ms: u64, current_tick: u64) -> Vec<f32> {
+    pub fn add_custom(&mut self, metric: &str, value: f32, tick: u64) {
+        let series = self.custom.entry(metric.to_string()).or_default();
+        series.push((value, tick));
+        if series.len() > 1000 {
+            series.remove(0);
+        }
+    }
+
+    /// Samples held across every series, custom ones included
+    pub fn entries(&self) -> usize {
+        self.bandwidth_util.len()
+            + self.corruption_field.len()
+            + self.gpu_thermal_events.len()
+            + self.vram_frac.len()
+            + self.power_draw.len()
+            + self.heat_levels.len()
+            + self.custom.values().map(|s| s.len()).sum::<usize>()
+    }
+
+    pub fn get_metric_in_window(&self, metric: &MetricId, window_ms: u64, current_tick: u64) -> Vec<f32> {
         let window_ticks = window_ms / 16;
         let cutoff_tick = current_tick.saturating_sub(window_ticks);
 
         match metric {
-            "bandwidth_util" => self.bandwidth_util
+            MetricId::BandwidthUtil => self.bandwidth_util
                 .iter()
                 .filter(|(_, tick)| *tick >= cutoff_tick)
                 .map(|(value, _)| *value)
                 .collect(),
-            "corruption_field" => self.corruption_field
+            MetricId::CorruptionField => self.corruption_field
                 .iter()
                 .filter(|(_, tick)| *tick >= cutoff_tick)
                 .map(|(value, _)| *value)
                 .collect(),
-            "gpu_thermal_events" => self.gpu_thermal_events
+            MetricId::GpuThermalEvents => self.gpu_thermal_events
                 .iter()
                 .filter(|(_, tick)| *tick >= cutoff_tick)
                 .map(|(count, _)| *count as f32)
                 .collect(),
-            "vram_frac" => self.vram_frac
+            MetricId::VramFrac => self.vram_frac
                 .iter()
                 .filter(|(_, tick)| *tick >= cutoff_tick)
                 .map(|(value, _)| *value)
                 .collect(),
-            "power_draw" => self.power_draw
+            MetricId::PowerDraw => self.power_draw
                 .iter()
                 .filter(|(_, tick)| *tick >= cutoff_tick)
                 .map(|(value, _)| *value)
                 .collect(),
-            "heat_levels" => self.heat_levels
+            MetricId::HeatLevels => self.heat_levels
                 .iter()
                 .filter(|(_, tick)| *tick >= cutoff_tick)
                 .map(|(value, _)| *value)
                 .collect(),
-            _ => Vec::new(),
+            // Clock drift, maintenance waits and mod metrics are kept by name
+            _ => self.custom.get(metric.as_str())
+                .map(|series| series
+                    .iter()
+                    .filter(|(_, tick)| *tick >= cutoff_tick)
+                    .map(|(value, _)| *value)
+                    .collect())
+                .unwrap_or_default(),
         }
     }
 }
@@ -270,9 +467,8 @@ pub fn evaluate_triggers(
 
 pub fn apply_effects(
     effects: &[Effect],
-    mut debts: ResMut<Debts>,
+    debts: &mut Debts,
     current_tick: u64,
-    mut commands: Commands,
 ) {
     for effect in effects {
         match effect {
@@ -303,11 +499,26 @@ pub fn apply_effects(
             Effect::UIIllusion { metric, delta, duration_ms } => {
                 let until_tick = current_tick + (duration_ms / 16);
                 debts.add_debt(Debt::Illusion { 
-                    metric: metric.clone(), 
+                    metric: metric.to_string(), 
                     delta: *delta, 
                     until_tick 
                 });
             }
+            Effect::YardShutdown { yard, duration_ms } => {
+                let until_tick = current_tick + (duration_ms / 16);
+                debts.add_debt(Debt::YardOffline { yard: yard.clone(), until_tick });
+            }
+            Effect::IdleYard { yard, duration_ms } => {
+                let until_tick = current_tick + (duration_ms / 16);
+                debts.add_debt(Debt::YardIdle { yard: yard.clone(), until_tick });
+            }
+            Effect::RemoveWorker { .. } => {
+                // Carried out against the worker roster by PersonnelLedger
+            }
+            Effect::SinkOutage { sink, duration_ms } => {
+                let until_tick = current_tick + (duration_ms / 16);
+                debts.add_debt(Debt::SinkOffline { sink: sink.clone(), until_tick });
+            }
             Effect::InsertOp { pipeline_id, where_, op } => {
                 // TODO: Implement pipeline mutation
                 println!("Black Swan: InsertOp {} in pipeline {} at {}", op, pipeline_id, where_);
@@ -341,27 +552,42 @@ pub fn black_swan_scan_system(
     kpi_buffer: Res<KpiRingBuffer>,
     clock: Res<super::SimClock>,
     mut debts: ResMut<Debts>,
-    commands: Commands,
+    mut personnel: Option<ResMut<super::PersonnelLedger>>,
 ) {
     let current_tick = clock.now.timestamp_millis() as u64 / 16;
     
     // Clear expired cooldowns
     black_swan_index.clear_expired_cooldowns(current_tick);
+
+    // Apply the outcome of any answered or timed-out choices
+    for (resolved, effects) in black_swan_index.take_resolved(current_tick) {
+        println!(
+            "Black Swan choice: {} -> {}{}",
+            resolved.swan_id,
+            resolved.choice_id,
+            if resolved.timed_out { " (timed out, default)" } else { "" }
+        );
+        apply_effects(&effects, &mut debts, current_tick);
+        if let Some(personnel) = personnel.as_mut() {
+            personnel.queue_effects(&effects);
+        }
+    }
+
+    // Nothing escalates while the player is still deciding
+    if black_swan_index.has_pending_choice() {
+        return;
+    }
     
     // Evaluate triggers
     let eligible = evaluate_triggers(&black_swan_index, &kpi_buffer, current_tick);
     
     // Fire eligible Black Swans (for now, fire the first one)
     if let Some(swan_id) = eligible.first() {
-        if let Some(swan_def) = black_swan_index.defs.iter().find(|def| def.id == *swan_id) {
-            println!("Black Swan fired: {} - {}", swan_def.id, swan_def.name);
-            
-            // Apply effects
-            apply_effects(&swan_def.effects, debts, current_tick, commands);
-            
-            // Mark as fired
-            black_swan_index.mark_fired(swan_id.clone(), current_tick);
-            black_swan_index.meters.active.push(swan_id.clone());
+        if let Ok(effects) = black_swan_index.fire(swan_id, current_tick) {
+            apply_effects(&effects, &mut debts, current_tick);
+            if let Some(personnel) = personnel.as_mut() {
+                personnel.queue_effects(&effects);
+            }
         }
     }
 }
@@ -370,12 +596,24 @@ pub fn update_kpi_buffer_system(
     mut kpi_buffer: ResMut<KpiRingBuffer>,
     colony: Res<super::Colony>,
     clock: Res<super::SimClock>,
+    clock_drift: Option<Res<super::ClockDrift>>,
+    maintenance_lane: Option<Res<super::MaintenanceLane>>,
+    dead_letters: Option<Res<super::DeadLetterQueue>>,
 ) {
     let current_tick = clock.now.timestamp_millis() as u64 / 16;
     
     kpi_buffer.add_bandwidth_util(colony.meters.bandwidth_util, current_tick);
     kpi_buffer.add_corruption_field(colony.corruption_field, current_tick);
     kpi_buffer.add_power_draw(colony.meters.power_draw_kw, current_tick);
+    if let Some(drift) = clock_drift {
+        kpi_buffer.add_custom(MetricId::ClockDriftMs.as_str(), drift.max_abs_offset_ms, current_tick);
+    }
+    if let Some(lane) = maintenance_lane {
+        kpi_buffer.add_custom(MetricId::MaintenanceWaitMs.as_str(), lane.avg_wait_ms, current_tick);
+    }
+    if let Some(dead_letters) = dead_letters {
+        kpi_buffer.add_custom(MetricId::QueueDeadLetterDepth.as_str(), dead_letters.depth() as f32, current_tick);
+    }
 }
 
 #[cfg(test)]
@@ -394,7 +632,7 @@ mod tests {
             name: "Test Swan".to_string(),
             triggers: vec![
                 TriggerCond {
-                    metric: "bandwidth_util".to_string(),
+                    metric: MetricId::BandwidthUtil,
                     op: ">".to_string(),
                     value: 0.9,
                     window_ms: 5000,
@@ -405,6 +643,11 @@ mod tests {
             cure: None,
             weight: 1.0,
             cooldown_ms: 10000,
+            narrative: None,
+            severity: Severity::Major,
+            choices: vec![],
+            default_choice: None,
+            choice_timeout_ms: 60_000,
         };
         black_swan_index.add_black_swan(swan_def);
 
@@ -416,6 +659,42 @@ mod tests {
         assert!(eligible.contains(&"test_swan".to_string()));
     }
 
+    #[test]
+    fn test_trigger_on_mod_metric() {
+        let mut black_swan_index = BlackSwanIndex::new();
+        let mut kpi_buffer = KpiRingBuffer::new();
+        let current_tick = 1000;
+
+        black_swan_index.add_black_swan(BlackSwanDef {
+            id: "anomaly_storm".to_string(),
+            name: "Anomaly Storm".to_string(),
+            triggers: vec![
+                TriggerCond {
+                    metric: MetricId::parse("com.example.anomaly:anomaly_score").unwrap(),
+                    op: ">".to_string(),
+                    value: 0.9,
+                    window_ms: 5000,
+                    count_at_least: Some(2),
+                }
+            ],
+            effects: vec![],
+            cure: None,
+            weight: 1.0,
+            cooldown_ms: 10000,
+            narrative: None,
+            severity: Severity::Major,
+            choices: vec![],
+            default_choice: None,
+            choice_timeout_ms: 60_000,
+        });
+
+        kpi_buffer.add_custom("com.example.anomaly:anomaly_score", 0.95, current_tick - 10);
+        assert!(evaluate_triggers(&black_swan_index, &kpi_buffer, current_tick).is_empty());
+
+        kpi_buffer.add_custom("com.example.anomaly:anomaly_score", 0.5, current_tick - 5);
+        assert_eq!(evaluate_triggers(&black_swan_index, &kpi_buffer, current_tick), vec!["anomaly_storm".to_string()]);
+    }
+
     #[test]
     fn test_cooldown_mechanism() {
         let mut black_swan_index = BlackSwanIndex::new();
@@ -429,6 +708,11 @@ mod tests {
             cure: None,
             weight: 1.0,
             cooldown_ms: 10000, // 10 seconds
+            narrative: None,
+            severity: Severity::Major,
+            choices: vec![],
+            default_choice: None,
+            choice_timeout_ms: 60_000,
         };
         black_swan_index.add_black_swan(swan_def);
 
@@ -441,4 +725,91 @@ mod tests {
         // Should be off cooldown after enough time
         assert!(!black_swan_index.is_on_cooldown("test_swan", current_tick + 1000));
     }
+
+    fn swan_with_choices() -> BlackSwanDef {
+        BlackSwanDef {
+            id: "ecc_storm".to_string(),
+            name: "ECC Storm".to_string(),
+            triggers: vec![],
+            effects: vec![],
+            cure: None,
+            weight: 1.0,
+            cooldown_ms: 10000,
+            narrative: Some("The GPU farm is throwing ECC errors.".to_string()),
+            severity: Severity::Critical,
+            choices: vec![
+                EventChoice {
+                    id: "shutdown".to_string(),
+                    label: "Shut down GpuFarm for 1h".to_string(),
+                    effects: vec![Effect::YardShutdown { yard: "GpuFarm".to_string(), duration_ms: 3_600_000 }],
+                },
+                EventChoice {
+                    id: "accept_debt".to_string(),
+                    label: "Accept a corruption debt".to_string(),
+                    effects: vec![Effect::FaultBias { kind: "DataSkew".to_string(), weight_mult: 1.5, duration_ms: 600_000 }],
+                },
+            ],
+            default_choice: Some("accept_debt".to_string()),
+            choice_timeout_ms: 1600, // 100 ticks
+        }
+    }
+
+    #[test]
+    fn test_choice_answered() {
+        let mut index = BlackSwanIndex::new();
+        let pending = PendingChoice::from_def(&swan_with_choices(), 1000).unwrap();
+        assert_eq!(pending.expires_tick, 1100);
+        index.pending.push(pending);
+
+        assert!(index.choose("ecc_storm", "nope").is_err());
+        assert!(index.choose("other", "shutdown").is_err());
+        index.choose("ecc_storm", "shutdown").unwrap();
+
+        let resolved = index.take_resolved(1010);
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].0.choice_id, "shutdown");
+        assert!(!resolved[0].0.timed_out);
+        assert!(!index.has_pending_choice());
+
+        let mut debts = Debts::new();
+        apply_effects(&resolved[0].1, &mut debts, 1010);
+        assert!(debts.is_yard_offline("GpuFarm", 1010));
+        assert!(!debts.is_yard_offline("CpuArray", 1010));
+    }
+
+    #[test]
+    fn test_choice_defaults_after_timeout() {
+        let mut index = BlackSwanIndex::new();
+        index.pending.push(PendingChoice::from_def(&swan_with_choices(), 1000).unwrap());
+
+        assert!(index.take_resolved(1099).is_empty());
+        assert!(index.has_pending_choice());
+
+        let resolved = index.take_resolved(1100);
+        assert_eq!(resolved[0].0.choice_id, "accept_debt");
+        assert!(resolved[0].0.timed_out);
+        assert_eq!(index.resolved.len(), 1);
+    }
+
+    #[test]
+    fn test_events_without_choices_do_not_prompt() {
+        let mut def = swan_with_choices();
+        def.choices.clear();
+        assert!(PendingChoice::from_def(&def, 0).is_none());
+    }
+
+    #[test]
+    fn test_definition_defaults() {
+        let def: BlackSwanDef = toml::from_str(r#"
+            id = "quiet"
+            name = "Quiet"
+            triggers = []
+            effects = []
+            weight = 1.0
+            cooldown_ms = 1000
+        "#).unwrap();
+        assert_eq!(def.severity, Severity::Major);
+        assert!(def.choices.is_empty());
+        assert_eq!(def.choice_timeout_ms, 60_000);
+    }
 }
@@ -0,0 +1,325 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use crate::{Op, Worker, WorkerState, WorkerStateComp, SimClock, ResearchState, TechTree, TechGrant};
+
+const MS_PER_TICK: u64 = 16;
+
+/// Op families a worker can be certified for. Ops outside these families need no certification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertKind {
+    Can,
+    Modbus,
+    Http,
+    Tcp,
+    Vision,
+}
+
+impl CertKind {
+    pub const ALL: [CertKind; 5] = [CertKind::Can, CertKind::Modbus, CertKind::Http, CertKind::Tcp, CertKind::Vision];
+
+    pub fn for_op(op: &Op) -> Option<CertKind> {
+        match op {
+            Op::CanParse => Some(CertKind::Can),
+            Op::ModbusMap => Some(CertKind::Modbus),
+            Op::HttpParse | Op::HttpExport => Some(CertKind::Http),
+            Op::TcpSessionize => Some(CertKind::Tcp),
+            Op::Yolo | Op::GpuPreprocess | Op::GpuExport => Some(CertKind::Vision),
+            _ => None,
+        }
+    }
+
+    /// Display label, e.g. "CAN certified"
+    pub fn label(&self) -> &'static str {
+        match self {
+            CertKind::Can => "CAN",
+            CertKind::Modbus => "Modbus",
+            CertKind::Http => "HTTP",
+            CertKind::Tcp => "TCP",
+            CertKind::Vision => "Vision",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<CertKind> {
+        CertKind::ALL.into_iter().find(|c| c.label().eq_ignore_ascii_case(s))
+    }
+}
+
+/// Certifications a worker holds
+#[derive(Component, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Certifications {
+    pub held: Vec<CertKind>, // sorted, no duplicates
+}
+
+impl Certifications {
+    pub fn new(held: &[CertKind]) -> Self {
+        let mut certs = Self::default();
+        for cert in held {
+            certs.grant(*cert);
+        }
+        certs
+    }
+
+    pub fn has(&self, cert: CertKind) -> bool {
+        self.held.contains(&cert)
+    }
+
+    /// Returns true if the certification is new
+    pub fn grant(&mut self, cert: CertKind) -> bool {
+        if self.has(cert) {
+            return false;
+        }
+        self.held.push(cert);
+        self.held.sort();
+        true
+    }
+
+    /// Certifications the pipeline needs that this worker lacks
+    pub fn missing_for(&self, ops: &[Op]) -> Vec<CertKind> {
+        let mut missing: Vec<CertKind> = ops.iter()
+            .filter_map(CertKind::for_op)
+            .filter(|c| !self.has(*c))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificationTunables {
+    pub uncertified_speed_mult: f32, // work rate of a worker on an op it isn't certified for
+    pub uncertified_fault_mult: f32, // fault probability multiplier, per pipeline
+    pub training_ms: u64,            // sim time to train one certification
+}
+
+impl Default for CertificationTunables {
+    fn default() -> Self {
+        Self {
+            uncertified_speed_mult: 0.6,
+            uncertified_fault_mult: 1.5,
+            training_ms: 30_000,
+        }
+    }
+}
+
+/// Speed and fault penalty for running a pipeline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CertPenalty {
+    pub speed_mult: f32,
+    pub fault_mult: f32,
+}
+
+impl CertPenalty {
+    pub fn none() -> Self {
+        Self { speed_mult: 1.0, fault_mult: 1.0 }
+    }
+
+    pub fn is_penalized(&self) -> bool {
+        self.speed_mult < 1.0 || self.fault_mult > 1.0
+    }
+
+    /// Extra ticks the slower worker spends on work that would take `service_ms` at full speed
+    pub fn extra_ticks(&self, service_ms: u32) -> u64 {
+        if self.speed_mult >= 1.0 || self.speed_mult <= 0.0 {
+            return 0;
+        }
+        let extra_ms = service_ms as f32 * (1.0 / self.speed_mult - 1.0);
+        (extra_ms / MS_PER_TICK as f32).ceil() as u64
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainingRun {
+    pub worker_id: u64,
+    pub cert: CertKind,
+    pub started_tick: u64,
+    pub completes_tick: u64,
+}
+
+/// Certification tuning, training in progress and how often uncertified work ran
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CertificationBoard {
+    pub tunables: CertificationTunables,
+    pub training: Vec<TrainingRun>,
+    pub uncertified_runs: u64,
+}
+
+impl CertificationBoard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_training(&self, worker_id: u64) -> bool {
+        self.training.iter().any(|t| t.worker_id == worker_id)
+    }
+
+    /// Pull a worker off the floor to train; one course at a time per worker
+    pub fn start_training(&mut self, worker_id: u64, cert: CertKind, now_tick: u64) -> Result<&TrainingRun> {
+        if self.is_training(worker_id) {
+            return Err(anyhow::anyhow!("Worker {} is already in training", worker_id));
+        }
+        self.training.push(TrainingRun {
+            worker_id,
+            cert,
+            started_tick: now_tick,
+            completes_tick: now_tick + self.tunables.training_ms / MS_PER_TICK,
+        });
+        Ok(self.training.last().unwrap())
+    }
+
+    /// Penalty for a worker running the given ops; workers without the component count as uncertified
+    pub fn penalty(&self, certs: Option<&Certifications>, ops: &[Op]) -> CertPenalty {
+        let needs_cert = ops.iter().any(|op| CertKind::for_op(op).is_some());
+        let uncertified = match certs {
+            Some(certs) => !certs.missing_for(ops).is_empty(),
+            None => needs_cert,
+        };
+        if uncertified {
+            CertPenalty {
+                speed_mult: self.tunables.uncertified_speed_mult,
+                fault_mult: self.tunables.uncertified_fault_mult,
+            }
+        } else {
+            CertPenalty::none()
+        }
+    }
+}
+
+/// Certifications handed to every worker by researched tech
+pub fn tech_certifications(research: &ResearchState, tech_tree: &TechTree) -> Vec<CertKind> {
+    let mut certs: Vec<CertKind> = tech_tree.get_researched_techs(research).iter()
+        .flat_map(|tech| tech.grants.iter())
+        .filter_map(|grant| match grant {
+            TechGrant::Certify { cert } => CertKind::parse(cert),
+            _ => None,
+        })
+        .collect();
+    certs.sort();
+    certs.dedup();
+    certs
+}
+
+pub fn certification_system(
+    mut board: ResMut<CertificationBoard>,
+    mut workers: Query<(&Worker, &mut WorkerStateComp, &mut Certifications)>,
+    research: Option<Res<ResearchState>>,
+    tech_tree: Option<Res<TechTree>>,
+    clock: Res<SimClock>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+
+    // Trainees leave the dispatch floor as soon as they are idle
+    for (worker, mut state, _) in workers.iter_mut() {
+        if state.0 == WorkerState::Idle && board.is_training(worker.id) {
+            state.0 = WorkerState::Blocked;
+        }
+    }
+
+    let (done, still_training): (Vec<_>, Vec<_>) = board.training.drain(..).partition(|t| now_tick >= t.completes_tick);
+    board.training = still_training;
+    for run in done {
+        for (worker, mut state, mut certs) in workers.iter_mut() {
+            if worker.id != run.worker_id {
+                continue;
+            }
+            certs.grant(run.cert);
+            if state.0 == WorkerState::Blocked {
+                state.0 = WorkerState::Idle;
+            }
+            println!("Worker {} is now {} certified", worker.id, run.cert.label());
+        }
+    }
+
+    if let (Some(research), Some(tech_tree)) = (research, tech_tree) {
+        let granted = tech_certifications(&research, &tech_tree);
+        for (_, _, mut certs) in workers.iter_mut() {
+            // Check before granting so certifications already held don't flag the component as changed
+            for cert in &granted {
+                if !certs.has(*cert) {
+                    certs.grant(*cert);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uncertified_penalty() {
+        let board = CertificationBoard::new();
+        let ops = vec![Op::CanParse, Op::Crc, Op::Kalman];
+        let certified = Certifications::new(&[CertKind::Can]);
+        let other = Certifications::new(&[CertKind::Http]);
+
+        assert_eq!(board.penalty(Some(&certified), &ops), CertPenalty::none());
+        assert_eq!(other.missing_for(&ops), vec![CertKind::Can]);
+        let penalty = board.penalty(Some(&other), &ops);
+        assert!(penalty.is_penalized());
+        assert_eq!(penalty.speed_mult, 0.6);
+
+        // Ops outside any certification family run at full speed for anyone
+        assert_eq!(board.penalty(None, &[Op::Decode, Op::Kalman]), CertPenalty::none());
+        assert!(board.penalty(None, &ops).is_penalized());
+    }
+
+    #[test]
+    fn test_extra_ticks() {
+        assert_eq!(CertPenalty::none().extra_ticks(100), 0);
+        // 32ms at 0.5x speed takes another 32ms, two ticks
+        let half = CertPenalty { speed_mult: 0.5, fault_mult: 1.0 };
+        assert_eq!(half.extra_ticks(32), 2);
+    }
+
+    #[test]
+    fn test_tech_certifies_everyone() {
+        let tech_tree = crate::create_default_tech_tree();
+        let mut research = ResearchState::new();
+        assert!(tech_certifications(&research, &tech_tree).is_empty());
+
+        research.pts = 100;
+        assert!(research.research_tech(tech_tree.get_tech("fieldbus_academy").unwrap()));
+        assert_eq!(tech_certifications(&research, &tech_tree), vec![CertKind::Can, CertKind::Modbus]);
+    }
+
+    #[test]
+    fn test_training_grants_cert() {
+        let mut app = App::new();
+        let clock = SimClock {
+            tick_scale: crate::TickScale::RealTime,
+            now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+        };
+        app.insert_resource(CertificationBoard::new())
+            .insert_resource(clock)
+            .add_systems(Update, certification_system);
+        let worker = app.world_mut().spawn((
+            crate::WorkerBundle::new(7, crate::WorkClass::Cpu, crate::WorkerSkills {
+                cpu: 0.8,
+                gpu: 0.3,
+                io: 0.6,
+                discipline: 0.7,
+                focus: 0.8,
+            }),
+            Certifications::default(),
+        )).id();
+
+        let mut board = app.world_mut().resource_mut::<CertificationBoard>();
+        board.start_training(7, CertKind::Modbus, 0).unwrap();
+        assert!(board.start_training(7, CertKind::Can, 0).is_err());
+
+        app.update();
+        assert_eq!(app.world().get::<WorkerStateComp>(worker).unwrap().0, WorkerState::Blocked);
+        assert!(!app.world().get::<Certifications>(worker).unwrap().has(CertKind::Modbus));
+
+        // Finish the course
+        app.world_mut().resource_mut::<CertificationBoard>().training[0].completes_tick = 0;
+        app.update();
+        assert_eq!(app.world().get::<WorkerStateComp>(worker).unwrap().0, WorkerState::Idle);
+        assert!(app.world().get::<Certifications>(worker).unwrap().has(CertKind::Modbus));
+        assert!(app.world().resource::<CertificationBoard>().training.is_empty());
+    }
+}
@@ -0,0 +1,298 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use bevy::platform::time::Instant;
+use anyhow::Result;
+use crate::{SaveFileV1, SaveProtection, migrate_any_to_latest};
+
+/// Changes to a JSON document, keyed by JSON pointer ("/colony_state/meters/heat")
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JsonDelta {
+    pub set: BTreeMap<String, Value>,
+    pub removed: Vec<String>,
+}
+
+impl JsonDelta {
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.removed.is_empty()
+    }
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Record what differs between two documents. Objects are compared key by key and arrays of the
+/// same length element by element, so one changed entity in a large list costs one entry.
+pub fn diff_json(base: &Value, current: &Value) -> JsonDelta {
+    fn walk(path: &str, base: &Value, current: &Value, delta: &mut JsonDelta) {
+        if base == current {
+            return;
+        }
+        match (base, current) {
+            (Value::Object(old), Value::Object(new)) => {
+                for (key, value) in new {
+                    let child = format!("{}/{}", path, escape_pointer(key));
+                    match old.get(key) {
+                        Some(previous) => walk(&child, previous, value, delta),
+                        None => {
+                            delta.set.insert(child, value.clone());
+                        }
+                    }
+                }
+                for key in old.keys().filter(|k| !new.contains_key(*k)) {
+                    delta.removed.push(format!("{}/{}", path, escape_pointer(key)));
+                }
+            }
+            (Value::Array(old), Value::Array(new)) if old.len() == new.len() && !path.is_empty() => {
+                for (i, (previous, value)) in old.iter().zip(new).enumerate() {
+                    walk(&format!("{}/{}", path, i), previous, value, delta);
+                }
+            }
+            _ => {
+                delta.set.insert(path.to_string(), current.clone());
+            }
+        }
+    }
+    let mut delta = JsonDelta::default();
+    walk("", base, current, &mut delta);
+    delta
+}
+
+/// Replay a delta onto the document it was taken against
+pub fn apply_json_delta(base: &mut Value, delta: &JsonDelta) -> Result<()> {
+    for pointer in &delta.removed {
+        let (parent, key) = pointer.rsplit_once('/').unwrap_or(("", pointer));
+        let key = key.replace("~1", "/").replace("~0", "~");
+        if let Some(Value::Object(map)) = base.pointer_mut(parent) {
+            map.remove(&key);
+        }
+    }
+    for (pointer, value) in &delta.set {
+        if pointer.is_empty() {
+            *base = value.clone();
+            continue;
+        }
+        if let Some(slot) = base.pointer_mut(pointer) {
+            *slot = value.clone();
+            continue;
+        }
+        let (parent, key) = pointer.rsplit_once('/').unwrap_or(("", pointer));
+        match base.pointer_mut(parent) {
+            Some(Value::Object(map)) => {
+                map.insert(key.replace("~1", "/").replace("~0", "~"), value.clone());
+            }
+            _ => return Err(anyhow::anyhow!("Delta path {} doesn't exist in the base snapshot", pointer)),
+        }
+    }
+    Ok(())
+}
+
+fn digest(value: &Value) -> Result<String> {
+    Ok(hex::encode(Sha256::digest(serde_json::to_vec(value)?)))
+}
+
+/// Everything changed since the last full checkpoint; loading needs only the full one plus the latest delta
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeltaSnapshot {
+    pub base_digest: String, // sha256 of the full checkpoint this applies to
+    pub seq: u32,            // deltas taken since that full checkpoint
+    pub delta: JsonDelta,
+}
+
+#[derive(Debug, Clone)]
+pub enum Checkpoint {
+    Full(Value),
+    Delta(DeltaSnapshot),
+}
+
+/// Size and time of the last checkpoint written, for logs and the HUD
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CheckpointStats {
+    pub full: bool,
+    pub bytes: usize,
+    pub changed_paths: usize,
+    pub millis: f64,
+}
+
+/// Decides between full and delta checkpoints. Deltas are taken against the last full
+/// checkpoint; a full one is written again every `consolidate_every` deltas, or sooner once a
+/// delta grows past `max_delta_fraction` of the full size, so deltas never outgrow what they save.
+#[derive(Resource, Debug, Clone)]
+pub struct Checkpointer {
+    pub consolidate_every: u32,
+    pub max_delta_fraction: f32,
+    base: Option<Value>,
+    base_digest: String,
+    base_bytes: usize,
+    deltas_since_full: u32,
+    pub last: Option<CheckpointStats>,
+}
+
+impl Default for Checkpointer {
+    fn default() -> Self {
+        Self {
+            consolidate_every: 10,
+            max_delta_fraction: 0.5,
+            base: None,
+            base_digest: String::new(),
+            base_bytes: 0,
+            deltas_since_full: 0,
+            last: None,
+        }
+    }
+}
+
+impl Checkpointer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Force the next checkpoint to be a full one, e.g. after loading a different save
+    pub fn reset(&mut self) {
+        self.base = None;
+        self.deltas_since_full = 0;
+    }
+
+    pub fn checkpoint(&mut self, save: &SaveFileV1) -> Result<Checkpoint> {
+        let current = serde_json::to_value(save)?;
+        if let Some(base) = &self.base {
+            if self.deltas_since_full < self.consolidate_every {
+                let delta = diff_json(base, &current);
+                let bytes = serde_json::to_vec(&delta)?.len();
+                if (bytes as f32) <= self.base_bytes as f32 * self.max_delta_fraction {
+                    self.deltas_since_full += 1;
+                    return Ok(Checkpoint::Delta(DeltaSnapshot {
+                        base_digest: self.base_digest.clone(),
+                        seq: self.deltas_since_full,
+                        delta,
+                    }));
+                }
+            }
+        }
+        self.base_digest = digest(&current)?;
+        self.base_bytes = serde_json::to_vec(&current)?.len();
+        self.base = Some(current.clone());
+        self.deltas_since_full = 0;
+        Ok(Checkpoint::Full(current))
+    }
+
+    /// Checkpoint into `dir`: a full one replaces `<slot>.json` and drops the old delta,
+    /// a delta goes to `<slot>.delta` next to it
+    pub fn write_to(&mut self, save: &SaveFileV1, dir: &Path, slot: &str, protection: &SaveProtection) -> Result<CheckpointStats> {
+        let started = Instant::now();
+        std::fs::create_dir_all(dir)?;
+        let (full_path, delta_path) = checkpoint_paths(dir, slot);
+        let stats = match self.checkpoint(save)? {
+            Checkpoint::Full(value) => {
+                let written = protection.write(&full_path, &serde_json::to_vec(&value)?)?;
+                if delta_path.exists() {
+                    std::fs::remove_file(&delta_path)?;
+                }
+                CheckpointStats { full: true, bytes: written.stored_bytes, changed_paths: 0, millis: 0.0 }
+            }
+            Checkpoint::Delta(delta) => {
+                let written = protection.write(&delta_path, &serde_json::to_vec(&delta)?)?;
+                let changed_paths = delta.delta.set.len() + delta.delta.removed.len();
+                CheckpointStats { full: false, bytes: written.stored_bytes, changed_paths, millis: 0.0 }
+            }
+        };
+        let stats = CheckpointStats { millis: started.elapsed().as_secs_f64() * 1000.0, ..stats };
+        self.last = Some(stats.clone());
+        Ok(stats)
+    }
+}
+
+fn checkpoint_paths(dir: &Path, slot: &str) -> (PathBuf, PathBuf) {
+    (dir.join(format!("{}.json", slot)), dir.join(format!("{}.delta", slot)))
+}
+
+/// Load a checkpointed slot: the full checkpoint with its latest delta applied. A delta taken
+/// against a different full checkpoint is ignored with a warning rather than misapplied.
+pub fn load_checkpoint_from(dir: &Path, slot: &str, protection: &SaveProtection) -> Result<SaveFileV1> {
+    let (full_path, delta_path) = checkpoint_paths(dir, slot);
+    let mut state: Value = serde_json::from_slice(&protection.read(&full_path)?)?;
+    if delta_path.exists() {
+        let delta: DeltaSnapshot = serde_json::from_slice(&protection.read(&delta_path)?)?;
+        if delta.base_digest == digest(&state)? {
+            apply_json_delta(&mut state, &delta.delta)?;
+        } else {
+            eprintln!("Ignoring {:?}: it belongs to a different full checkpoint", delta_path);
+        }
+    }
+    migrate_any_to_latest(&serde_json::to_vec(&state)?)
+}
+
+pub fn save_checkpoint_to_slot_with(
+    checkpointer: &mut Checkpointer,
+    save_data: &SaveFileV1,
+    slot_name: &str,
+    protection: &SaveProtection,
+) -> Result<CheckpointStats> {
+    checkpointer.write_to(save_data, Path::new("saves"), slot_name, protection)
+}
+
+pub fn load_checkpoint_from_slot_with(slot_name: &str, protection: &SaveProtection) -> Result<SaveFileV1> {
+    load_checkpoint_from(Path::new("saves"), slot_name, protection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlackSwanIndex, Debts, GameSetup, KpiSummary, ReplayLog, ResearchState, SessionCtl, WinLossState};
+
+    fn save(power_cap_kw: f32, history: Vec<f32>) -> SaveFileV1 {
+        let mut colony = crate::colony_for_scenario(None, Some(3)).unwrap();
+        colony.power_cap_kw = power_cap_kw;
+        let kpis = KpiSummary {
+            bandwidth_util_history: history,
+            corruption_field_history: Vec::new(),
+            power_draw_history: Vec::new(),
+            heat_levels_history: Vec::new(),
+            deadline_hit_rates: Vec::new(),
+            black_swan_events: Vec::new(),
+        };
+        SaveFileV1::new(
+            GameSetup::new(crate::load_scenarios().unwrap().remove(0)), &colony, &ResearchState::new(),
+            &BlackSwanIndex::new(), &Debts::new(), &WinLossState::default(), &SessionCtl::new(),
+            &ReplayLog::new(), kpis,
+        )
+    }
+
+    #[test]
+    fn test_delta_records_only_changes() {
+        let base = serde_json::json!({"a": {"b": 1, "c": [1, 2, 3]}, "gone": true, "k/ey": 0});
+        let current = serde_json::json!({"a": {"b": 1, "c": [1, 9, 3], "d": "new"}, "k/ey": 5});
+        let delta = diff_json(&base, &current);
+        assert_eq!(delta.set.len(), 3);
+        assert!(delta.set.contains_key("/a/c/1") && delta.set.contains_key("/k~1ey"));
+        assert_eq!(delta.removed, vec!["/gone".to_string()]);
+
+        let mut rebuilt = base.clone();
+        apply_json_delta(&mut rebuilt, &delta).unwrap();
+        assert_eq!(rebuilt, current);
+    }
+
+    #[test]
+    fn test_checkpoints_consolidate_and_load() {
+        let dir = std::env::temp_dir().join(format!("colony_checkpoint_{}", std::process::id()));
+        let protection = SaveProtection::new(crate::ProtectionMode::Integrity, "checkpoint-test");
+        let mut checkpointer = Checkpointer { consolidate_every: 2, ..Default::default() };
+
+        assert!(checkpointer.write_to(&save(1000.0, vec![0.5; 200]), &dir, "auto", &protection).unwrap().full);
+        let delta = checkpointer.write_to(&save(750.0, vec![0.5; 200]), &dir, "auto", &protection).unwrap();
+        assert!(!delta.full && delta.bytes < 1000);
+        assert_eq!(load_checkpoint_from(&dir, "auto", &protection).unwrap().colony_state.power_cap_kw, 750.0);
+
+        assert!(!checkpointer.write_to(&save(500.0, vec![0.5; 200]), &dir, "auto", &protection).unwrap().full);
+        // Third one consolidates into a new full checkpoint and drops the delta
+        assert!(checkpointer.write_to(&save(250.0, vec![0.5; 200]), &dir, "auto", &protection).unwrap().full);
+        assert!(!dir.join("auto.delta").exists());
+        assert_eq!(load_checkpoint_from(&dir, "auto", &protection).unwrap().colony_state.power_cap_kw, 250.0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
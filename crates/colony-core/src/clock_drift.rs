@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::VecDeque;
+use crate::{Workyard, CorruptionField, ResearchState};
+
+/// Tech that disciplines yard clocks against a reference and slows drift
+pub const NTP_DISCIPLINE_TECH: &str = "ntp_discipline";
+/// Ritual effect that resyncs every yard clock
+pub const SYNC_CLOCKS_EFFECT: &str = "sync:clocks";
+
+const MS_PER_TICK: f32 = 16.0;
+const MAX_PENDING: usize = 4096;
+
+/// A yard's local clock. Deadlines for jobs processed in the yard are judged against it.
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+pub struct YardClock {
+    pub offset_ms: f32,       // local minus true time; positive runs fast
+    pub drift_dir: f32,       // +1.0 or -1.0, which way this oscillator wanders
+    pub last_sync_tick: u64,
+}
+
+impl YardClock {
+    pub fn new(drift_dir: f32) -> Self {
+        Self {
+            offset_ms: 0.0,
+            drift_dir: drift_dir.signum(),
+            last_sync_tick: 0,
+        }
+    }
+
+    /// Pull the clock back towards true time; `strength` 1.0 is a full resync
+    pub fn sync(&mut self, strength: f32, tick: u64) {
+        self.offset_ms *= 1.0 - strength.clamp(0.0, 1.0);
+        self.last_sync_tick = tick;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClockDriftTunables {
+    pub base_ms_per_tick: f32,       // drift of a cool, clean yard
+    pub heat_ms_per_tick: f32,       // extra drift at full heat
+    pub corruption_ms_per_tick: f32, // extra drift at full corruption
+    pub max_offset_ms: f32,
+    pub disciplined_mult: f32,       // drift multiplier once NTP discipline is researched
+}
+
+impl Default for ClockDriftTunables {
+    fn default() -> Self {
+        Self {
+            base_ms_per_tick: 0.0005,
+            heat_ms_per_tick: 0.01,
+            corruption_ms_per_tick: 0.04,
+            max_offset_ms: 500.0,
+            disciplined_mult: 0.1,
+        }
+    }
+}
+
+/// Drift tuning and the deadline judgements skewed by it
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ClockDrift {
+    pub tunables: ClockDriftTunables,
+    pub max_abs_offset_ms: f32,
+    pub mean_abs_offset_ms: f32,
+    pub deadline_checks: u64,
+    pub false_hits: u64,      // late jobs a slow clock called on time
+    pub false_misses: u64,    // on-time jobs a fast clock called late
+    #[serde(skip)]
+    pending: VecDeque<bool>,  // reported hits waiting for the SLA tracker
+}
+
+impl ClockDrift {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn misjudged(&self) -> u64 {
+        self.false_hits + self.false_misses
+    }
+
+    pub fn record_deadline(&mut self, judgement: DeadlineJudgement) {
+        self.deadline_checks += 1;
+        match (judgement.actual_hit, judgement.reported_hit) {
+            (false, true) => self.false_hits += 1,
+            (true, false) => self.false_misses += 1,
+            _ => {}
+        }
+        self.pending.push_back(judgement.reported_hit);
+        if self.pending.len() > MAX_PENDING {
+            self.pending.pop_front();
+        }
+    }
+
+    /// Deadline results as the yards reported them since the last call
+    pub fn take_reported(&mut self) -> Vec<bool> {
+        std::mem::take(&mut self.pending).into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeadlineJudgement {
+    pub actual_hit: bool,
+    pub reported_hit: bool,
+}
+
+/// Judge a job's deadline by true time and by the yard's local clock
+pub fn judge_deadline(clock: Option<&YardClock>, enq_tick: u64, now_tick: u64, deadline_ms: u64) -> DeadlineJudgement {
+    let elapsed_ms = now_tick.saturating_sub(enq_tick) as f32 * MS_PER_TICK;
+    let offset_ms = clock.map(|c| c.offset_ms).unwrap_or(0.0);
+    DeadlineJudgement {
+        actual_hit: elapsed_ms <= deadline_ms as f32,
+        reported_hit: (elapsed_ms + offset_ms).max(0.0) <= deadline_ms as f32,
+    }
+}
+
+/// Per-tick drift: heat and corruption destabilise each yard's oscillator
+pub fn drift_per_tick(tunables: &ClockDriftTunables, heat_frac: f32, corruption: f32, disciplined: bool) -> f32 {
+    let rate = tunables.base_ms_per_tick
+        + tunables.heat_ms_per_tick * heat_frac.clamp(0.0, 1.0)
+        + tunables.corruption_ms_per_tick * corruption.clamp(0.0, 1.0);
+    if disciplined { rate * tunables.disciplined_mult } else { rate }
+}
+
+/// Resync every yard clock if the effects include `sync:clocks`
+pub fn apply_clock_sync<'a>(effects: &[String], strength: f32, clocks: impl Iterator<Item = Mut<'a, YardClock>>, tick: u64) {
+    if !effects.iter().any(|e| e == SYNC_CLOCKS_EFFECT) {
+        return;
+    }
+    for mut clock in clocks {
+        clock.sync(strength, tick);
+    }
+}
+
+pub fn clock_drift_system(
+    mut yards: Query<(&Workyard, &mut YardClock)>,
+    mut drift: ResMut<ClockDrift>,
+    corruption_field: Res<CorruptionField>,
+    research: Option<Res<ResearchState>>,
+) {
+    let disciplined = research.map(|r| r.has_tech(NTP_DISCIPLINE_TECH)).unwrap_or(false);
+    let max_offset = drift.tunables.max_offset_ms;
+
+    let mut total = 0.0;
+    let mut max: f32 = 0.0;
+    let mut count = 0;
+    for (yard, mut clock) in yards.iter_mut() {
+        let heat_frac = if yard.heat_cap > 0.0 { yard.heat / yard.heat_cap } else { 0.0 };
+        let rate = drift_per_tick(&drift.tunables, heat_frac, corruption_field.global, disciplined);
+        clock.offset_ms = (clock.offset_ms + clock.drift_dir * rate).clamp(-max_offset, max_offset);
+
+        total += clock.offset_ms.abs();
+        max = max.max(clock.offset_ms.abs());
+        count += 1;
+    }
+    drift.max_abs_offset_ms = max;
+    drift.mean_abs_offset_ms = if count > 0 { total / count as f32 } else { 0.0 };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drift_skews_deadlines() {
+        // 3 ticks = 48ms against a 50ms deadline
+        let mut clock = YardClock::new(1.0);
+        assert_eq!(judge_deadline(Some(&clock), 0, 3, 50), DeadlineJudgement { actual_hit: true, reported_hit: true });
+
+        // A fast clock calls an on-time job late
+        clock.offset_ms = 10.0;
+        assert_eq!(judge_deadline(Some(&clock), 0, 3, 50), DeadlineJudgement { actual_hit: true, reported_hit: false });
+
+        // A slow clock calls a late job on time
+        clock.offset_ms = -20.0;
+        assert_eq!(judge_deadline(Some(&clock), 0, 4, 50), DeadlineJudgement { actual_hit: false, reported_hit: true });
+
+        clock.sync(1.0, 10);
+        assert_eq!(clock.offset_ms, 0.0);
+        assert_eq!(clock.last_sync_tick, 10);
+    }
+
+    #[test]
+    fn test_heat_corruption_and_discipline() {
+        let tunables = ClockDriftTunables::default();
+        let cool = drift_per_tick(&tunables, 0.2, 0.0, false);
+        let hot = drift_per_tick(&tunables, 0.9, 0.0, false);
+        let corrupt = drift_per_tick(&tunables, 0.2, 0.5, false);
+        assert!(hot > cool);
+        assert!(corrupt > cool);
+        let disciplined = drift_per_tick(&tunables, 0.9, 0.5, true);
+        assert!(disciplined * 5.0 < drift_per_tick(&tunables, 0.9, 0.5, false));
+    }
+
+    #[test]
+    fn test_misjudged_deadlines_are_counted() {
+        let mut drift = ClockDrift::new();
+        drift.record_deadline(DeadlineJudgement { actual_hit: true, reported_hit: true });
+        drift.record_deadline(DeadlineJudgement { actual_hit: false, reported_hit: true });
+        drift.record_deadline(DeadlineJudgement { actual_hit: true, reported_hit: false });
+        assert_eq!(drift.deadline_checks, 3);
+        assert_eq!(drift.misjudged(), 2);
+        assert_eq!(drift.take_reported(), vec![true, true, false]);
+        assert!(drift.take_reported().is_empty());
+    }
+}
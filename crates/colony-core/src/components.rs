@@ -17,21 +17,75 @@ pub enum IoKind {
     Http,
 }
 
-#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+/// Who a worker is. What it can do and how it's holding up live in their own components, so
+/// systems query only what they touch and change detection on each means something.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Worker {
     pub id: u64,
     pub class: WorkClass,
-    pub skill_cpu: f32,
-    pub skill_gpu: f32,
-    pub skill_io: f32,
+}
+
+/// Fixed at hire; read by rituals and the sim, never written per tick
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkerSkills {
+    pub cpu: f32,
+    pub gpu: f32,
+    pub io: f32,
     pub discipline: f32,
     pub focus: f32,
+}
+
+impl WorkerSkills {
+    pub fn best(&self) -> f32 {
+        self.cpu.max(self.gpu).max(self.io)
+    }
+}
+
+/// Wear the corruption and fault systems write
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkerHealth {
     pub corruption: f32,
-    pub state: WorkerState,
-    pub retry: RetryPolicy,
     pub sticky_faults: u32,          // count
 }
 
+/// Where the worker is in its dispatch cycle; the only part dispatch flips every job
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkerStateComp(pub WorkerState);
+
+impl Default for WorkerStateComp {
+    fn default() -> Self {
+        Self(WorkerState::Idle)
+    }
+}
+
+/// Transient-fault retries the worker has left
+#[derive(Component, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct RetryState {
+    pub policy: RetryPolicy,
+}
+
+#[derive(Bundle, Clone)]
+pub struct WorkerBundle {
+    pub worker: Worker,
+    pub skills: WorkerSkills,
+    pub health: WorkerHealth,
+    pub state: WorkerStateComp,
+    pub retry: RetryState,
+}
+
+impl WorkerBundle {
+    /// A fresh, idle, uncorrupted worker
+    pub fn new(id: u64, class: WorkClass, skills: WorkerSkills) -> Self {
+        Self {
+            worker: Worker { id, class },
+            skills,
+            health: WorkerHealth::default(),
+            state: WorkerStateComp::default(),
+            retry: RetryState::default(),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum WorkerState {
     Idle,
@@ -41,7 +95,7 @@ pub enum WorkerState {
     Recovering,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FaultKind {
     Transient,       // retry/backoff helps
     DataSkew,        // output drift; requires re-run
@@ -80,7 +134,7 @@ pub struct Workyard {
     pub isolation_domain: u32,
 }
 
-#[derive(Component, Default)]
+#[derive(Component, Clone, Default)]
 pub struct YardWorkload { 
     pub units_this_tick: f32 
 } // set by dispatcher/segments
@@ -90,6 +144,7 @@ pub enum WorkyardKind {
     CpuArray,
     GpuFarm,
     SignalHub,
+    StorageArray,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -105,9 +160,11 @@ pub struct Job {
 pub struct Pipeline {
     pub ops: Vec<Op>,
     pub mutation_tag: Option<String>,
+    #[serde(default)]
+    pub id: Option<String>, // content pipeline this was built from; None for ad-hoc jobs
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Op {
     UdpDemux,
     Decode,
@@ -115,13 +172,17 @@ pub enum Op {
     Export,
     HttpParse,
     HttpExport,
+    ArchiveWrite,
     Fft,
     Yolo,
     Crc,
     CanParse,
     TcpSessionize,
     ModbusMap,
+    MqttParse,
+    GrpcParse,
     MaintenanceCool,
+    ReplaceDrives,
     GpuPreprocess,
     GpuExport,
     DynamicWasm { op_id: String },
@@ -137,13 +198,17 @@ impl Op {
             Op::Export => 2,
             Op::HttpParse => 3,
             Op::HttpExport => 2,
+            Op::ArchiveWrite => 4,
             Op::Fft => 6,
             Op::Yolo => 18,
             Op::Crc => 1,
             Op::CanParse => 2,
             Op::TcpSessionize => 5,
             Op::ModbusMap => 2,
+            Op::MqttParse => 2,
+            Op::GrpcParse => 3,
             Op::MaintenanceCool => 8,
+            Op::ReplaceDrives => 30,
             Op::GpuPreprocess => 4,
             Op::GpuExport => 3,
             Op::DynamicWasm { .. } => 5, // Default cost for WASM ops
@@ -159,13 +224,17 @@ impl Op {
             Op::Export => 0.3,
             Op::HttpParse => 0.6,
             Op::HttpExport => 0.3,
+            Op::ArchiveWrite => 0.4,
             Op::Fft => 1.5,
             Op::Yolo => 4.5,
             Op::Crc => 0.3,
             Op::CanParse => 0.5,
             Op::TcpSessionize => 1.2,
             Op::ModbusMap => 0.5,
+            Op::MqttParse => 0.4,
+            Op::GrpcParse => 0.7,
             Op::MaintenanceCool => 0.0, // No heat generation
+            Op::ReplaceDrives => 0.0,
             Op::GpuPreprocess => 1.0,
             Op::GpuExport => 0.8,
             Op::DynamicWasm { .. } => 1.0, // Default work units for WASM ops
@@ -200,3 +269,27 @@ pub enum QoS {
     Balanced,
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_flip_only_changes_state() {
+        let mut world = World::new();
+        let worker = world.spawn(WorkerBundle::new(3, WorkClass::Gpu, WorkerSkills {
+            cpu: 0.4,
+            gpu: 0.9,
+            io: 0.3,
+            discipline: 0.8,
+            focus: 0.9,
+        })).id();
+        world.clear_trackers();
+
+        world.get_mut::<WorkerStateComp>(worker).unwrap().0 = WorkerState::Running;
+        let mut changed_state = world.query_filtered::<&Worker, Changed<WorkerStateComp>>();
+        assert_eq!(changed_state.iter(&world).map(|w| w.id).collect::<Vec<_>>(), vec![3]);
+        let mut changed_rest = world.query_filtered::<Entity, Or<(Changed<WorkerSkills>, Changed<WorkerHealth>, Changed<RetryState>)>>();
+        assert_eq!(changed_rest.iter(&world).count(), 0);
+    }
+}
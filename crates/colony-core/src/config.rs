@@ -1,10 +1,29 @@
 use serde::{Deserialize, Serialize};
-use crate::ResourceTunables;
+use std::fmt;
+use crate::{ResourceTunables, CorruptionTunables, SaveProtectionConfig, SaveCompressionConfig, TrafficShapingConfig, JobRetryConfig, TenantConfig, EnergyConfig, ForecastConfig, AutoscaleConfig, tunable_range};
 
 #[derive(Serialize, Deserialize)]
 pub struct GameConfig {
     pub tunables: ResourceTunables,
     pub seed: u64,
+    #[serde(default)]
+    pub corruption: CorruptionTunables,
+    #[serde(default)]
+    pub save_protection: SaveProtectionConfig,
+    #[serde(default)]
+    pub save_compression: SaveCompressionConfig,
+    #[serde(default)]
+    pub traffic_shaping: TrafficShapingConfig,
+    #[serde(default)]
+    pub job_retry: JobRetryConfig,
+    #[serde(default)]
+    pub tenants: TenantConfig,
+    #[serde(default)]
+    pub energy: EnergyConfig,
+    #[serde(default)]
+    pub forecast: ForecastConfig,
+    #[serde(default)]
+    pub autoscale: AutoscaleConfig,
 }
 
 impl Default for GameConfig {
@@ -12,15 +31,230 @@ impl Default for GameConfig {
         Self {
             tunables: ResourceTunables::default(),
             seed: 42,
+            corruption: CorruptionTunables::default(),
+            save_protection: SaveProtectionConfig::default(),
+            save_compression: SaveCompressionConfig::default(),
+            traffic_shaping: TrafficShapingConfig::default(),
+            job_retry: JobRetryConfig::default(),
+            tenants: TenantConfig::default(),
+            energy: EnergyConfig::default(),
+            forecast: ForecastConfig::default(),
+            autoscale: AutoscaleConfig::default(),
         }
     }
 }
 
+/// Tables whose keys depend on a tag and are left to serde to check
+const FREE_FORM_TABLES: &[&str] = &["save_protection.key"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigIssue {
+    Syntax,
+    UnknownKey,
+    WrongType,
+    OutOfRange,
+    Units,
+}
+
+/// One problem found in a config file, with its location when known
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigDiagnostic {
+    pub issue: ConfigIssue,
+    pub key: String,            // dotted path, e.g. "tunables.power_cap_kw"; empty for syntax errors
+    pub line: Option<usize>,    // 1-based
+    pub column: Option<usize>,  // 1-based
+    pub message: String,
+}
+
+impl fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, "{}:{}: ", line, column)?;
+        }
+        if !self.key.is_empty() {
+            write!(f, "{}: ", self.key)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Result of checking a config file; `config` is set when it deserialized
+pub struct ConfigReport {
+    pub file: String,
+    pub diagnostics: Vec<ConfigDiagnostic>,
+    pub config: Option<GameConfig>,
+}
+
+impl ConfigReport {
+    pub fn is_valid(&self) -> bool {
+        self.diagnostics.is_empty() && self.config.is_some()
+    }
+
+    /// All diagnostics as `file:line:col: key: message` lines
+    pub fn render(&self) -> String {
+        self.diagnostics.iter()
+            .map(|d| format!("{}:{}", self.file, d))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn line_col(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map(|l| l.chars().count()).unwrap_or(0) + 1;
+    (line, column)
+}
+
+fn diagnostic(content: &str, issue: ConfigIssue, key: String, span: Option<std::ops::Range<usize>>, message: String) -> ConfigDiagnostic {
+    let (line, column) = match span {
+        Some(span) => {
+            let (line, column) = line_col(content, span.start);
+            (Some(line), Some(column))
+        }
+        None => (None, None),
+    };
+    ConfigDiagnostic { issue, key, line, column, message }
+}
+
+/// `power_cap_kw` -> `power_cap`, so `power_cap_w` can be flagged as the same key in the wrong unit
+fn unit_stem(key: &str) -> &str {
+    key.rsplit_once('_').map(|(stem, _)| stem).unwrap_or(key)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            row.push((prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+fn unknown_key_message(key: &str, known: &toml::Table) -> (ConfigIssue, String) {
+    if let Some(same_stem) = known.keys().find(|k| unit_stem(k) == unit_stem(key) && k.contains('_')) {
+        return (ConfigIssue::Units, format!("unknown key; this value is set as `{}` (note the unit suffix)", same_stem));
+    }
+    match known.keys().min_by_key(|k| edit_distance(k, key)) {
+        Some(closest) if edit_distance(closest, key) <= 2 => {
+            (ConfigIssue::UnknownKey, format!("unknown key; did you mean `{}`?", closest))
+        }
+        _ => (ConfigIssue::UnknownKey, "unknown key".to_string()),
+    }
+}
+
+/// Flag keys that don't exist in the schema, using the default config as the reference shape
+fn check_unknown_keys(content: &str, table: &dyn toml_edit::TableLike, reference: &toml::Table, prefix: &str, out: &mut Vec<ConfigDiagnostic>) {
+    for (key, item) in table.iter() {
+        let path = if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) };
+        match reference.get(key) {
+            None => {
+                let span = table.key(key).and_then(|k| k.span());
+                let (issue, message) = unknown_key_message(key, reference);
+                out.push(diagnostic(content, issue, path, span, message));
+            }
+            Some(toml::Value::Table(sub)) if !FREE_FORM_TABLES.contains(&path.as_str()) => {
+                if let Some(child) = item.as_table_like() {
+                    check_unknown_keys(content, child, sub, &path, out);
+                }
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+fn find_item<'a>(doc: &'a toml_edit::ImDocument<&str>, path: &str) -> Option<&'a toml_edit::Item> {
+    let mut item = doc.as_item();
+    for part in path.split('.') {
+        item = item.as_table_like()?.get(part)?;
+    }
+    Some(item)
+}
+
+/// Strings like "500kW" or "2 Gbps" where a plain number is expected
+fn looks_like_number_with_unit(value: &str) -> bool {
+    let trimmed = value.trim();
+    let digits = trimmed.trim_end_matches(|c: char| c.is_alphabetic() || c == '/' || c == ' ');
+    !digits.is_empty() && digits.len() < trimmed.len() && digits.parse::<f64>().is_ok()
+}
+
+/// Check a game config for syntax errors, unknown keys, wrong types, unit mistakes and out-of-range tunables
+pub fn validate_config_str(content: &str, file: &str) -> ConfigReport {
+    let mut report = ConfigReport { file: file.to_string(), diagnostics: Vec::new(), config: None };
+
+    let doc = match toml_edit::ImDocument::parse(content) {
+        Ok(doc) => doc,
+        Err(e) => {
+            let message = e.message().trim().to_string();
+            report.diagnostics.push(diagnostic(content, ConfigIssue::Syntax, String::new(), e.span(), message));
+            return report;
+        }
+    };
+
+    let reference = match toml::Value::try_from(GameConfig::default()) {
+        Ok(toml::Value::Table(table)) => table,
+        _ => toml::Table::new(),
+    };
+    check_unknown_keys(content, doc.as_table(), &reference, "", &mut report.diagnostics);
+
+    let deserializer = toml::Deserializer::new(content);
+    match serde_path_to_error::deserialize::<_, GameConfig>(deserializer) {
+        Ok(config) => report.config = Some(config),
+        Err(e) => {
+            let key = e.path().to_string();
+            let item = find_item(&doc, &key);
+            let span = item.and_then(|i| i.span()).or_else(|| e.inner().span());
+            let mut message = e.inner().message().to_string();
+            let mut issue = ConfigIssue::WrongType;
+            if let Some(text) = item.and_then(|i| i.as_str()) {
+                if looks_like_number_with_unit(text) {
+                    issue = ConfigIssue::Units;
+                    message = format!("{}; write a plain number, the unit is implied by the key name", message);
+                }
+            }
+            report.diagnostics.push(diagnostic(content, issue, if key == "." { String::new() } else { key }, span, message));
+        }
+    }
+
+    for section in ["tunables", "corruption"] {
+        let Some(table) = doc.as_table().get(section).and_then(|t| t.as_table_like()) else { continue };
+        for (field, item) in table.iter() {
+            let key = format!("{}.{}", section, field);
+            let (Some((min, max)), Some(value)) = (tunable_range(&key), item.as_value()) else { continue };
+            let number = value.as_float().or_else(|| value.as_integer().map(|i| i as f64));
+            if let Some(number) = number {
+                if !(min as f64..=max as f64).contains(&number) {
+                    report.diagnostics.push(diagnostic(
+                        content, ConfigIssue::OutOfRange, key, item.span(),
+                        format!("{} is outside the allowed range {}..={}", number, min, max),
+                    ));
+                }
+            }
+        }
+    }
+
+    report.diagnostics.sort_by_key(|d| (d.line.unwrap_or(0), d.column.unwrap_or(0)));
+    report
+}
+
+pub fn check_config(path: &str) -> anyhow::Result<ConfigReport> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(validate_config_str(&contents, path))
+}
+
 pub fn load_config(path: &str) -> anyhow::Result<GameConfig> {
     if std::path::Path::new(path).exists() {
-        let contents = std::fs::read_to_string(path)?;
-        let config: GameConfig = toml::from_str(&contents)?;
-        Ok(config)
+        let report = check_config(path)?;
+        match report.config {
+            Some(config) if report.diagnostics.is_empty() => Ok(config),
+            _ => Err(anyhow::anyhow!("Invalid game config:\n{}", report.render())),
+        }
     } else {
         // Create default config if it doesn't exist
         let config = GameConfig::default();
@@ -34,3 +268,60 @@ pub fn save_config(config: &GameConfig, path: &str) -> anyhow::Result<()> {
     std::fs::write(path, contents)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_toml() -> String {
+        toml::to_string_pretty(&GameConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        let report = validate_config_str(&default_toml(), "game_config.toml");
+        assert!(report.is_valid(), "{}", report.render());
+    }
+
+    #[test]
+    fn test_unknown_key_and_unit_mistake() {
+        let content = default_toml()
+            .replace("power_cap_kw =", "power_cap_w =")
+            .replace("seed = 42", "seed = 42\nseeed = 1");
+        let report = validate_config_str(&content, "game_config.toml");
+        assert!(!report.is_valid());
+
+        let units = report.diagnostics.iter().find(|d| d.key == "tunables.power_cap_w").unwrap();
+        assert_eq!(units.issue, ConfigIssue::Units);
+        assert!(units.message.contains("power_cap_kw"));
+        assert!(units.line.is_some());
+
+        let typo = report.diagnostics.iter().find(|d| d.key == "seeed").unwrap();
+        assert_eq!(typo.issue, ConfigIssue::UnknownKey);
+        assert!(typo.message.contains("`seed`"));
+        assert_eq!(typo.line, Some(content.lines().position(|l| l.starts_with("seeed")).unwrap() + 1));
+    }
+
+    #[test]
+    fn test_wrong_type_and_range() {
+        let content = "seed = 1\n[tunables]\npower_cap_kw = \"500kW\"\n";
+        let report = validate_config_str(content, "game_config.toml");
+        let d = &report.diagnostics[0];
+        assert_eq!(d.issue, ConfigIssue::Units);
+        assert_eq!(d.key, "tunables.power_cap_kw");
+        assert_eq!((d.line, d.column), (Some(3), Some(16)));
+
+        let content = default_toml().replace("thermal_throttle_knee = 0.85", "thermal_throttle_knee = 4.0");
+        let report = validate_config_str(&content, "game_config.toml");
+        assert!(report.diagnostics.iter().any(|d| d.issue == ConfigIssue::OutOfRange && d.key == "tunables.thermal_throttle_knee"));
+    }
+
+    #[test]
+    fn test_syntax_error_location() {
+        let report = validate_config_str("seed = 1\n[tunables\n", "game_config.toml");
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(report.diagnostics[0].issue, ConfigIssue::Syntax);
+        assert_eq!(report.diagnostics[0].line, Some(2));
+        assert!(report.render().starts_with("game_config.toml:2:"));
+    }
+}
@@ -0,0 +1,256 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::path::Path;
+use std::sync::OnceLock;
+use anyhow::{Context, Result};
+use crate::{BlackSwanDef, BlackSwanIndex, Effect, MetricId, Pipeline, PipelineDef, TechGrant, TechNode, TechTree};
+
+/// Mod that carries the base game's pipelines, tech tree and Black Swans
+pub const VANILLA_MOD_ID: &str = "vanilla";
+
+/// Content files a mod may ship, each holding any of `[[pipeline]]`, `[[black_swan]]` and `[[tech]]`
+pub const CONTENT_FILES: [&str; 3] = ["pipelines.toml", "events.toml", "tech.toml"];
+
+/// The vanilla mod compiled in, so the game runs without a mods/ directory
+const BUNDLED_VANILLA: [(&str, &str); 3] = [
+    ("pipelines.toml", include_str!("../../../mods/vanilla/pipelines.toml")),
+    ("events.toml", include_str!("../../../mods/vanilla/events.toml")),
+    ("tech.toml", include_str!("../../../mods/vanilla/tech.toml")),
+];
+
+/// Game content as loaded from data mods
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContentPack {
+    #[serde(default, rename = "pipeline")]
+    pub pipelines: Vec<PipelineDef>,
+    #[serde(default, rename = "black_swan")]
+    pub black_swans: Vec<BlackSwanDef>,
+    #[serde(default, rename = "tech")]
+    pub tech: Vec<TechNode>,
+    #[serde(skip)]
+    pub sources: Vec<String>, // mods merged into this pack, in load order
+}
+
+/// Per-mod settings read from mod.toml; only what content loading needs
+#[derive(Debug, Default, Deserialize)]
+struct ContentManifest {
+    #[serde(default)]
+    replaces_vanilla: bool,
+}
+
+impl ContentPack {
+    pub fn parse(file_name: &str, src: &str) -> Result<Self> {
+        toml::from_str(src).with_context(|| format!("Failed to parse {}", file_name))
+    }
+
+    /// Parse one mod's content files; `read` returns None for files the mod doesn't ship
+    fn from_files(mod_id: &str, read: impl Fn(&str) -> Result<Option<String>>) -> Result<Self> {
+        let mut pack = Self { sources: vec![mod_id.to_string()], ..Default::default() };
+        for file_name in CONTENT_FILES {
+            if let Some(src) = read(file_name)? {
+                let file = Self::parse(&format!("{}/{}", mod_id, file_name), &src)?;
+                pack.pipelines.extend(file.pipelines);
+                pack.black_swans.extend(file.black_swans);
+                pack.tech.extend(file.tech);
+            }
+        }
+        Ok(pack)
+    }
+
+    pub fn load_dir(dir: &Path) -> Result<Self> {
+        let mod_id = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        Self::from_files(&mod_id, |file_name| {
+            let path = dir.join(file_name);
+            if !path.exists() {
+                return Ok(None);
+            }
+            let src = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+            Ok(Some(src))
+        })
+    }
+
+    /// The bundled vanilla mod
+    pub fn vanilla() -> &'static ContentPack {
+        static VANILLA: OnceLock<ContentPack> = OnceLock::new();
+        VANILLA.get_or_init(|| {
+            Self::from_files(VANILLA_MOD_ID, |file_name| {
+                Ok(BUNDLED_VANILLA.iter().find(|(name, _)| *name == file_name).map(|(_, src)| src.to_string()))
+            })
+            .expect("bundled vanilla content is valid")
+        })
+    }
+
+    /// Vanilla (from mods/vanilla if present, else the bundled copy) overlaid by every other mod in
+    /// name order. A mod with `replaces_vanilla = true` in its mod.toml drops vanilla entirely.
+    pub fn load_mods(mods_dir: &Path) -> Result<Self> {
+        let vanilla_dir = mods_dir.join(VANILLA_MOD_ID);
+        let vanilla = if vanilla_dir.is_dir() {
+            Self::load_dir(&vanilla_dir)?
+        } else {
+            Self::vanilla().clone()
+        };
+
+        let mut mod_dirs: Vec<_> = match std::fs::read_dir(mods_dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_dir() && p.file_name().is_some_and(|n| n != VANILLA_MOD_ID))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        mod_dirs.sort();
+
+        let mut replaces_vanilla = false;
+        let mut mods = Vec::new();
+        for dir in mod_dirs {
+            let manifest_path = dir.join("mod.toml");
+            if manifest_path.exists() {
+                let manifest: ContentManifest = toml::from_str(&std::fs::read_to_string(&manifest_path)?)
+                    .with_context(|| format!("Failed to parse {:?}", manifest_path))?;
+                replaces_vanilla |= manifest.replaces_vanilla;
+            }
+            mods.push(Self::load_dir(&dir)?);
+        }
+
+        let mut pack = if replaces_vanilla { Self::default() } else { vanilla };
+        for content in mods {
+            pack.merge(content);
+        }
+        Ok(pack)
+    }
+
+    /// Overlay another mod's content; entries with an id already present replace it
+    pub fn merge(&mut self, other: ContentPack) {
+        fn overlay<T>(into: &mut Vec<T>, from: Vec<T>, id: impl Fn(&T) -> &str) {
+            for item in from {
+                match into.iter().position(|existing| id(existing) == id(&item)) {
+                    Some(i) => into[i] = item,
+                    None => into.push(item),
+                }
+            }
+        }
+        overlay(&mut self.pipelines, other.pipelines, |p| &p.id);
+        overlay(&mut self.black_swans, other.black_swans, |b| &b.id);
+        overlay(&mut self.tech, other.tech, |t| &t.id);
+        self.sources.extend(other.sources);
+    }
+
+    pub fn pipeline(&self, id: &str) -> Option<Pipeline> {
+        self.pipelines.iter().find(|p| p.id == id).and_then(|p| p.to_pipeline().ok())
+    }
+
+    pub fn tech_tree(&self) -> TechTree {
+        TechTree { nodes: self.tech.clone() }
+    }
+
+    /// Every metric the content reads, with the Black Swan or tech id that reads it. Names are
+    /// checked when the files parse; mod metrics can only be checked against the mods installed.
+    pub fn metric_refs(&self) -> Vec<(&str, &MetricId)> {
+        let mut refs = Vec::new();
+        for def in &self.black_swans {
+            refs.extend(def.triggers.iter().map(|t| (def.id.as_str(), &t.metric)));
+            for effect in &def.effects {
+                if let Effect::UIIllusion { metric, .. } = effect {
+                    refs.push((def.id.as_str(), metric));
+                }
+            }
+        }
+        for node in &self.tech {
+            for grant in &node.grants {
+                if let TechGrant::Sensor { metric } = grant {
+                    refs.push((node.id.as_str(), metric));
+                }
+            }
+        }
+        refs
+    }
+
+    pub fn black_swan_index(&self) -> BlackSwanIndex {
+        let mut index = BlackSwanIndex::new();
+        for def in &self.black_swans {
+            index.add_black_swan(def.clone());
+        }
+        index
+    }
+}
+
+/// Content installed in the app, or vanilla if none was inserted before the plugins were added
+pub fn app_content(app: &App) -> ContentPack {
+    app.world()
+        .get_resource::<ContentPack>()
+        .cloned()
+        .unwrap_or_else(|| ContentPack::vanilla().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_vanilla_carries_base_game() {
+        let vanilla = ContentPack::vanilla();
+        assert_eq!(vanilla.sources, vec![VANILLA_MOD_ID.to_string()]);
+        assert!(vanilla.pipeline("udp_telemetry_ingest").is_some());
+        assert!(vanilla.pipeline("can_telemetry").is_some());
+        assert!(vanilla.tech_tree().get_tech(crate::NTP_DISCIPLINE_TECH).is_some());
+        assert!(vanilla.black_swans.iter().any(|b| b.id == "vram_ecc_propagation"));
+
+        // Every pipeline parses and every prerequisite exists
+        assert!(vanilla.pipelines.iter().all(|p| p.to_pipeline().is_ok()));
+        let tree = vanilla.tech_tree();
+        assert!(vanilla.tech.iter().flat_map(|t| &t.requires).all(|r| tree.get_tech(r).is_some()));
+    }
+
+    #[test]
+    fn test_mods_overlay_or_replace_vanilla() {
+        let dir = std::env::temp_dir().join(format!("colony_content_{}", std::process::id()));
+        let tweak = dir.join("com.example.tweak");
+        std::fs::create_dir_all(&tweak).unwrap();
+        std::fs::write(tweak.join("tech.toml"), r#"
+            [[tech]]
+            id = "truth_beacon"
+            name = "Cheap Truth Beacon"
+            desc = "Half price"
+            cost_pts = 5
+            requires = []
+            grants = []
+        "#).unwrap();
+
+        let pack = ContentPack::load_mods(&dir).unwrap();
+        assert_eq!(pack.sources, vec!["vanilla".to_string(), "com.example.tweak".to_string()]);
+        assert_eq!(pack.tech_tree().get_tech("truth_beacon").unwrap().cost_pts, 5);
+        assert_eq!(pack.tech.len(), ContentPack::vanilla().tech.len());
+        assert!(!pack.black_swans.is_empty());
+
+        // A total conversion starts from nothing
+        std::fs::write(tweak.join("mod.toml"), "replaces_vanilla = true\n").unwrap();
+        let pack = ContentPack::load_mods(&dir).unwrap();
+        assert_eq!(pack.tech.len(), 1);
+        assert!(pack.black_swans.is_empty() && pack.pipelines.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_metric_typos_fail_to_load() {
+        let swan = |metric: &str| format!(r#"
+            [[black_swan]]
+            id = "storm"
+            name = "Storm"
+            triggers = [{{ metric = "{}", op = ">", value = 0.9, window_ms = 5000 }}]
+            effects = []
+            weight = 1.0
+            cooldown_ms = 1000
+        "#, metric);
+        let err = ContentPack::parse("events.toml", &swan("bandwith_util")).unwrap_err();
+        assert!(format!("{:#}", err).contains("bandwith_util"));
+
+        let pack = ContentPack::parse("events.toml", &swan("com.example.anomaly:anomaly_score")).unwrap();
+        let refs = pack.metric_refs();
+        assert_eq!(refs.len(), 1);
+        assert_eq!((refs[0].0, refs[0].1.mod_id()), ("storm", Some("com.example.anomaly")));
+
+        // Vanilla only reads built-ins
+        assert!(ContentPack::vanilla().metric_refs().iter().all(|(_, m)| m.mod_id().is_none()));
+    }
+}
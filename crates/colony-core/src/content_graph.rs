@@ -0,0 +1,226 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use crate::{BlackSwanDef, Effect, TechGrant, TechTree};
+
+/// Fill and outline for anything a player can never reach
+const UNREACHABLE: &str = "color=red, fontcolor=red, style=dashed";
+
+/// DOT string literal; ids come from mod content and may hold anything
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+/// Techs that can never be researched: a prerequisite is missing or is itself unreachable,
+/// which covers every tech on a `requires` cycle
+pub fn unreachable_techs(tree: &TechTree) -> BTreeSet<&str> {
+    let mut reachable: BTreeSet<&str> = BTreeSet::new();
+    loop {
+        let before = reachable.len();
+        for tech in &tree.nodes {
+            if tech.requires.iter().all(|r| reachable.contains(r.as_str())) {
+                reachable.insert(&tech.id);
+            }
+        }
+        if reachable.len() == before {
+            break;
+        }
+    }
+    tree.nodes.iter().map(|t| t.id.as_str()).filter(|id| !reachable.contains(id)).collect()
+}
+
+/// Prerequisite graph of the tech tree, an edge from each requirement to what it unlocks.
+/// Unreachable techs and prerequisites no tech defines are drawn in red.
+pub fn tech_graph_dot(tree: &TechTree) -> String {
+    let unreachable = unreachable_techs(tree);
+    let missing: BTreeSet<&str> = tree.nodes.iter()
+        .flat_map(|t| &t.requires)
+        .map(String::as_str)
+        .filter(|r| tree.get_tech(r).is_none())
+        .collect();
+
+    let mut dot = String::from("digraph tech {\n  rankdir=LR;\n  node [shape=box];\n");
+    for tech in &tree.nodes {
+        let label = quote(&format!("{}\n{} pts", tech.name, tech.cost_pts));
+        let style = if unreachable.contains(tech.id.as_str()) { format!(", {}", UNREACHABLE) } else { String::new() };
+        let _ = writeln!(dot, "  {} [label={}{}];", quote(&tech.id), label, style);
+    }
+    for id in &missing {
+        let _ = writeln!(dot, "  {} [label={}, {}];", quote(id), quote(&format!("{} (missing)", id)), UNREACHABLE);
+    }
+    for tech in &tree.nodes {
+        for req in &tech.requires {
+            let _ = writeln!(dot, "  {} -> {};", quote(req), quote(&tech.id));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn effect_edge(effect: &Effect) -> Option<(&'static str, &str, &'static str)> {
+    // (node kind, target id, edge label)
+    match effect {
+        Effect::InsertOp { pipeline_id, .. } => Some(("pipeline", pipeline_id, "InsertOp")),
+        Effect::ReplaceOp { pipeline_id, .. } => Some(("pipeline", pipeline_id, "ReplaceOp")),
+        Effect::RemoveOp { pipeline_id, .. } => Some(("pipeline", pipeline_id, "RemoveOp")),
+        Effect::BranchDualRun { pipeline_id, .. } => Some(("pipeline", pipeline_id, "BranchDualRun")),
+        Effect::QuarantinePipeline { pipeline_id, .. } => Some(("pipeline", pipeline_id, "QuarantinePipeline")),
+        Effect::UIIllusion { metric, .. } => Some(("metric", metric.as_str(), "illusion")),
+        Effect::RequireRitual { ritual_id } => Some(("ritual", ritual_id, "requires")),
+        _ => None,
+    }
+}
+
+/// Event chains: the metrics that trigger each Black Swan, the pipelines and metrics its effects
+/// (choices included) touch, the rituals that cure it, and the techs that unlock those rituals or
+/// the sensors that see through its illusions. Events with no trigger, rituals no reachable tech
+/// unlocks and illusions no reachable sensor dispels are drawn in red.
+pub fn event_graph_dot(tree: &TechTree, events: &[BlackSwanDef]) -> String {
+    let unreachable = unreachable_techs(tree);
+    let reachable_grants = || tree.nodes.iter()
+        .filter(|t| !unreachable.contains(t.id.as_str()))
+        .flat_map(|t| t.grants.iter().map(move |g| (t, g)));
+    let unlocked_rituals: BTreeSet<&str> = reachable_grants()
+        .filter_map(|(_, g)| match g { TechGrant::UnlockRitual { ritual_id } => Some(ritual_id.as_str()), _ => None })
+        .collect();
+    let sensed: BTreeSet<&str> = reachable_grants()
+        .filter_map(|(_, g)| match g { TechGrant::Sensor { metric } => Some(metric.as_str()), _ => None })
+        .collect();
+
+    // kind -> id -> reachable; sorted so the output is stable across runs
+    let mut nodes: BTreeMap<&str, BTreeMap<String, bool>> = BTreeMap::new();
+    let mut edges: Vec<(String, String, String, bool)> = Vec::new(); // from, to, label, dashed
+
+    for def in events {
+        let event = format!("event:{}", def.id);
+        for trigger in &def.triggers {
+            let metric = trigger.metric.as_str();
+            nodes.entry("metric").or_default().entry(metric.to_string()).or_insert(true);
+            let mut label = format!("{} {}", trigger.op, trigger.value);
+            if let Some(count) = trigger.count_at_least {
+                label.push_str(&format!(" x{}", count));
+            }
+            edges.push((format!("metric:{}", metric), event.clone(), label, false));
+        }
+
+        let choice_effects = def.choices.iter().flat_map(|c| c.effects.iter().map(move |e| (Some(c.id.as_str()), e)));
+        for (choice, effect) in def.effects.iter().map(|e| (None, e)).chain(choice_effects) {
+            let Some((kind, target, label)) = effect_edge(effect) else { continue };
+            let ok = match kind {
+                "metric" => sensed.contains(target),
+                "ritual" => unlocked_rituals.contains(target),
+                _ => true,
+            };
+            let entry = nodes.entry(kind).or_default().entry(target.to_string()).or_insert(ok);
+            *entry &= ok;
+            let label = match choice {
+                Some(choice) => format!("{}: {}", choice, label),
+                None => label.to_string(),
+            };
+            edges.push((event.clone(), format!("{}:{}", kind, target), label, choice.is_some()));
+        }
+        if let Some(cure) = &def.cure {
+            nodes.entry("ritual").or_default().entry(cure.clone()).or_insert(unlocked_rituals.contains(cure.as_str()));
+            edges.push((format!("ritual:{}", cure), event.clone(), "cures".to_string(), false));
+        }
+    }
+
+    // Only techs that feed into the event graph
+    let mut techs: BTreeSet<&str> = BTreeSet::new();
+    for tech in &tree.nodes {
+        for grant in &tech.grants {
+            let (kind, target, label) = match grant {
+                TechGrant::UnlockRitual { ritual_id } => ("ritual", ritual_id.as_str(), "unlocks"),
+                TechGrant::Sensor { metric } => ("metric", metric.as_str(), "sensor"),
+                _ => continue,
+            };
+            if !nodes.get(kind).is_some_and(|ids| ids.contains_key(target)) {
+                continue;
+            }
+            techs.insert(&tech.id);
+            edges.push((format!("tech:{}", tech.id), format!("{}:{}", kind, target), label.to_string(), false));
+        }
+    }
+
+    let mut dot = String::from("digraph events {\n  rankdir=LR;\n");
+    for def in events {
+        let style = if def.triggers.is_empty() { format!(", {}", UNREACHABLE) } else { String::new() };
+        let label = quote(&format!("{}\n{:?}", def.name, def.severity));
+        let _ = writeln!(dot, "  {} [shape=octagon, label={}{}];", quote(&format!("event:{}", def.id)), label, style);
+    }
+    for tech in &techs {
+        let style = if unreachable.contains(tech) { format!(", {}", UNREACHABLE) } else { String::new() };
+        let _ = writeln!(dot, "  {} [shape=box, label={}{}];", quote(&format!("tech:{}", tech)), quote(tech), style);
+    }
+    for (kind, ids) in &nodes {
+        let shape = match *kind {
+            "metric" => "ellipse",
+            "ritual" => "hexagon",
+            _ => "folder",
+        };
+        for (id, ok) in ids {
+            let style = if *ok { String::new() } else { format!(", {}", UNREACHABLE) };
+            let _ = writeln!(dot, "  {} [shape={}, label={}{}];", quote(&format!("{}:{}", kind, id)), shape, quote(id), style);
+        }
+    }
+    for (from, to, label, dashed) in &edges {
+        let style = if *dashed { ", style=dashed" } else { "" };
+        let _ = writeln!(dot, "  {} -> {} [label={}{}];", quote(from), quote(to), quote(label), style);
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContentPack, TechNode};
+
+    fn tech(id: &str, requires: &[&str]) -> TechNode {
+        TechNode {
+            id: id.to_string(),
+            name: format!("The \"{}\"", id),
+            desc: String::new(),
+            cost_pts: 10,
+            requires: requires.iter().map(|r| r.to_string()).collect(),
+            grants: vec![],
+        }
+    }
+
+    #[test]
+    fn test_tech_graph_flags_missing_prereqs_and_cycles() {
+        let tree = TechTree {
+            nodes: vec![
+                tech("root", &[]),
+                tech("leaf", &["root"]),
+                tech("orphan", &["ghost"]),
+                tech("chicken", &["egg"]),
+                tech("egg", &["chicken"]),
+            ],
+        };
+        let unreachable: Vec<&str> = unreachable_techs(&tree).into_iter().collect();
+        assert_eq!(unreachable, vec!["chicken", "egg", "orphan"]);
+
+        let dot = tech_graph_dot(&tree);
+        assert!(dot.starts_with("digraph tech {") && dot.ends_with("}\n"));
+        assert!(dot.contains("\"root\" -> \"leaf\";"));
+        assert!(dot.contains("\"ghost\" [label=\"ghost (missing)\", color=red"));
+        assert!(dot.contains("label=\"The \\\"root\\\"\\n10 pts\"];"));
+        assert!(dot.contains("\"egg\" [label=\"The \\\"egg\\\"\\n10 pts\", color=red"));
+    }
+
+    #[test]
+    fn test_vanilla_event_graph() {
+        let vanilla = ContentPack::vanilla();
+        let dot = event_graph_dot(&vanilla.tech_tree(), &vanilla.black_swans);
+        assert_eq!(dot, event_graph_dot(&vanilla.tech_tree(), &vanilla.black_swans));
+
+        assert!(dot.contains("\"metric:bandwidth_util\" -> \"event:vram_ecc_propagation\" [label=\"> 0.95\"]"));
+        assert!(dot.contains("\"event:vram_ecc_propagation\" -> \"pipeline:can_telemetry\" [label=\"InsertOp\"]"));
+        assert!(dot.contains("\"ritual:ecc_scrub\" -> \"event:vram_ecc_propagation\" [label=\"cures\"]"));
+        assert!(dot.contains("\"ritual:ecc_scrub\" [shape=hexagon, label=\"ecc_scrub\"];"));
+        // Nothing in the vanilla tree unlocks this cure or senses VRAM
+        assert!(dot.contains("\"ritual:numa_rebalance\" [shape=hexagon, label=\"numa_rebalance\", color=red"));
+        assert!(dot.contains("\"metric:vram_frac\" [shape=ellipse, label=\"vram_frac\", color=red"));
+        assert!(dot.contains("\"metric:bandwidth_util\" [shape=ellipse, label=\"bandwidth_util\"];"));
+    }
+}
@@ -63,6 +63,7 @@ pub fn tick_rng(seed: u64, tick: u64) -> Pcg64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::Rng;
 
     #[test]
     fn test_fault_probability_increases_with_stress() {
@@ -84,8 +85,8 @@ mod tests {
 
     #[test]
     fn test_tick_rng_deterministic() {
-        let rng1 = tick_rng(42, 100);
-        let rng2 = tick_rng(42, 100);
+        let mut rng1 = tick_rng(42, 100);
+        let mut rng2 = tick_rng(42, 100);
         
         // Should produce same sequence
         assert_eq!(rng1.gen::<u32>(), rng2.gen::<u32>());
@@ -0,0 +1,277 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, VecDeque};
+use crate::{Colony, CorruptionField, FaultKind, Op, SimClock, WorkClass, Worker, WorkerHealth, WorkerState, WorkerStateComp, Workyard, WorkyardKind};
+
+const MAX_FAULT_SITES: usize = 128;
+/// Rebuilding every tick buys nothing a dashboard can show
+const TOPOLOGY_REFRESH_TICKS: u64 = 8;
+
+/// Where one fault landed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FaultSite {
+    pub tick: u64,
+    pub worker_id: u64,
+    pub yard: String, // node id of the yard the job ran on
+    pub yard_kind: WorkyardKind,
+    pub op: Op,
+    pub kind: FaultKind,
+}
+
+/// Recent fault locations, oldest first; recorded by dispatch
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FaultSites {
+    pub recent: VecDeque<FaultSite>,
+}
+
+impl FaultSites {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, site: FaultSite) {
+        self.recent.push_back(site);
+        if self.recent.len() > MAX_FAULT_SITES {
+            self.recent.pop_front();
+        }
+    }
+}
+
+pub fn yard_node_id(yard: Entity) -> String {
+    format!("yard:{}", yard.index())
+}
+
+pub fn worker_node_id(worker_id: u64) -> String {
+    format!("worker:{}", worker_id)
+}
+
+pub const GLOBAL_NODE: &str = "global";
+pub const BANDWIDTH_NODE: &str = "bandwidth";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    Heat,      // yard heat feeding the global field
+    Bandwidth, // link saturation feeding the global field
+    Field,     // the global field's share of a worker's fault odds
+    Fault,     // faults a worker took while running on a yard
+}
+
+/// How corruption moves between two nodes. Heat and bandwidth weights are the stress terms
+/// corruption_system adds to the field (before its per-tick scale), field weights are the
+/// field's part of the fault probability, and fault weights count recent faults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyEdge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+    pub weight: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YardNode {
+    pub id: String,
+    pub kind: WorkyardKind,
+    pub heat_frac: f32,
+    pub isolation_domain: u32,
+    pub recent_faults: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerNode {
+    pub id: String,
+    pub worker_id: u64,
+    pub class: WorkClass,
+    pub state: WorkerState,
+    pub corruption: f32,
+    pub sticky_faults: u32,
+    pub recent_faults: u32,
+}
+
+/// Per-yard and per-worker corruption, the edges it travels along and where faults landed,
+/// served at `GET /corruption/topology` and drawn by the desktop Corruption tab
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CorruptionTopology {
+    pub tick: u64,
+    pub global: f32,
+    pub bandwidth_util: f32,
+    pub yards: Vec<YardNode>,
+    pub workers: Vec<WorkerNode>,
+    pub edges: Vec<TopologyEdge>,
+    pub recent_faults: Vec<FaultSite>,
+}
+
+impl CorruptionTopology {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn build<'a>(
+        tick: u64,
+        colony: &Colony,
+        global: f32,
+        yards: impl Iterator<Item = (Entity, &'a Workyard)>,
+        workers: impl Iterator<Item = (&'a Worker, &'a WorkerStateComp, &'a WorkerHealth)>,
+        sites: &FaultSites,
+    ) -> Self {
+        let tun = &colony.corruption_tun;
+        let mut by_yard: HashMap<&str, u32> = HashMap::new();
+        let mut by_worker: HashMap<u64, u32> = HashMap::new();
+        let mut fault_edges: HashMap<(u64, &str), u32> = HashMap::new();
+        for site in &sites.recent {
+            *by_yard.entry(site.yard.as_str()).or_default() += 1;
+            *by_worker.entry(site.worker_id).or_default() += 1;
+            *fault_edges.entry((site.worker_id, site.yard.as_str())).or_default() += 1;
+        }
+
+        let mut edges = Vec::new();
+        let bandwidth_util = colony.meters.bandwidth_util;
+        if bandwidth_util > 0.0 {
+            edges.push(TopologyEdge {
+                from: BANDWIDTH_NODE.to_string(),
+                to: GLOBAL_NODE.to_string(),
+                kind: EdgeKind::Bandwidth,
+                weight: tun.bw_weight * bandwidth_util,
+            });
+        }
+
+        let yards: Vec<YardNode> = yards
+            .map(|(entity, yard)| {
+                let id = yard_node_id(entity);
+                let heat_frac = (yard.heat / yard.heat_cap).clamp(0.0, 1.0);
+                if heat_frac > 0.0 {
+                    edges.push(TopologyEdge {
+                        from: id.clone(),
+                        to: GLOBAL_NODE.to_string(),
+                        kind: EdgeKind::Heat,
+                        weight: tun.heat_weight * heat_frac,
+                    });
+                }
+                YardNode {
+                    recent_faults: by_yard.get(id.as_str()).copied().unwrap_or(0),
+                    id,
+                    kind: yard.kind.clone(),
+                    heat_frac,
+                    isolation_domain: yard.isolation_domain,
+                }
+            })
+            .collect();
+
+        let mut workers: Vec<WorkerNode> = workers
+            .map(|(worker, state, health)| {
+                let id = worker_node_id(worker.id);
+                if global > 0.0 {
+                    edges.push(TopologyEdge {
+                        from: GLOBAL_NODE.to_string(),
+                        to: id.clone(),
+                        kind: EdgeKind::Field,
+                        weight: global * 0.5, // as weighted in fault_probability
+                    });
+                }
+                WorkerNode {
+                    id,
+                    worker_id: worker.id,
+                    class: worker.class,
+                    state: state.0,
+                    corruption: health.corruption,
+                    sticky_faults: health.sticky_faults,
+                    recent_faults: by_worker.get(&worker.id).copied().unwrap_or(0),
+                }
+            })
+            .collect();
+        workers.sort_by_key(|w| w.worker_id);
+
+        let mut faults: Vec<_> = fault_edges.into_iter().collect();
+        faults.sort_by(|a, b| a.0.cmp(&b.0));
+        edges.extend(faults.into_iter().map(|((worker_id, yard), n)| TopologyEdge {
+            from: worker_node_id(worker_id),
+            to: yard.to_string(),
+            kind: EdgeKind::Fault,
+            weight: n as f32,
+        }));
+
+        Self {
+            tick,
+            global,
+            bandwidth_util,
+            yards,
+            workers,
+            edges,
+            recent_faults: sites.recent.iter().cloned().collect(),
+        }
+    }
+}
+
+pub fn corruption_topology_system(
+    mut topology: ResMut<CorruptionTopology>,
+    colony: Res<Colony>,
+    field: Res<CorruptionField>,
+    yards: Query<(Entity, &Workyard)>,
+    workers: Query<(&Worker, &WorkerStateComp, &WorkerHealth)>,
+    sites: Res<FaultSites>,
+    clock: Res<SimClock>,
+) {
+    let tick = clock.now.timestamp_millis() as u64 / 16;
+    if !topology.yards.is_empty() && tick.abs_diff(topology.tick) < TOPOLOGY_REFRESH_TICKS {
+        return;
+    }
+    *topology = CorruptionTopology::build(tick, &colony, field.global, yards.iter(), workers.iter(), &sites);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GlobalMeters, ResourceTunables, CorruptionTunables};
+
+    #[test]
+    fn test_topology_links_stress_field_and_fault_sites() {
+        let mut colony = Colony {
+            power_cap_kw: 1000.0,
+            bandwidth_total_gbps: 1.0,
+            corruption_field: 0.0,
+            target_uptime_days: 365,
+            meters: GlobalMeters::new(),
+            tunables: ResourceTunables::default(),
+            corruption_tun: CorruptionTunables::default(),
+            seeds: Default::default(),
+            seed: 1,
+        };
+        colony.meters.bandwidth_util = 0.5;
+        let yard_e = Entity::from_raw(3);
+        let yard = Workyard {
+            kind: WorkyardKind::CpuArray,
+            slots: 4,
+            heat: 50.0,
+            heat_cap: 100.0,
+            power_draw_kw: 100.0,
+            bandwidth_share: 0.5,
+            isolation_domain: 0,
+        };
+        let worker = Worker { id: 7, class: WorkClass::Cpu };
+        let state = WorkerStateComp(WorkerState::Running);
+        let health = WorkerHealth { corruption: 0.2, sticky_faults: 1 };
+        let mut sites = FaultSites::new();
+        for tick in 0..2 {
+            sites.record(FaultSite {
+                tick,
+                worker_id: 7,
+                yard: yard_node_id(yard_e),
+                yard_kind: WorkyardKind::CpuArray,
+                op: Op::Decode,
+                kind: FaultKind::Transient,
+            });
+        }
+
+        let topology = CorruptionTopology::build(10, &colony, 0.4, [(yard_e, &yard)].into_iter(), [(&worker, &state, &health)].into_iter(), &sites);
+        assert_eq!(topology.yards[0].id, "yard:3");
+        assert_eq!(topology.yards[0].recent_faults, 2);
+        assert_eq!(topology.workers[0].corruption, 0.2);
+
+        let edge = |kind| topology.edges.iter().find(|e| e.kind == kind).unwrap();
+        assert_eq!((edge(EdgeKind::Heat).from.as_str(), edge(EdgeKind::Heat).to.as_str()), ("yard:3", GLOBAL_NODE));
+        assert_eq!(edge(EdgeKind::Field).to, "worker:7");
+        assert_eq!(edge(EdgeKind::Fault).weight, 2.0);
+        assert!((edge(EdgeKind::Bandwidth).weight - colony.corruption_tun.bw_weight * 0.5).abs() < 1e-6);
+        assert_eq!(topology.recent_faults.len(), 2);
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use crate::{BlackSwanIndex, Colony, CorruptionField, Debts, Effect, ResearchState, apply_effects};
+
+/// Research points a creative session is topped back up to every tick
+pub const CREATIVE_RESEARCH_PTS: u32 = 1_000_000;
+/// Power and bandwidth caps in creative mode; large but finite so utilization math stays sane
+pub const CREATIVE_POWER_CAP_KW: f32 = 1.0e9;
+pub const CREATIVE_BANDWIDTH_GBPS: f32 = 1.0e6;
+
+/// Gameplay rules for a session. Creative is a sandbox for content authors: no loss conditions,
+/// unlimited research points and power/bandwidth budget, and the debug controls (force events,
+/// set corruption) unlocked. It is recorded in saves, run exports and replay bundles so its
+/// scores are never compared with standard runs.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    #[default]
+    Standard,
+    Creative,
+}
+
+impl GameMode {
+    pub fn is_creative(&self) -> bool {
+        *self == GameMode::Creative
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Standard => "standard",
+            GameMode::Creative => "creative",
+        }
+    }
+
+    /// Gate for controls that only exist in creative mode
+    pub fn require_creative(&self, control: &str) -> Result<()> {
+        if self.is_creative() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("{} is only available in creative mode", control))
+        }
+    }
+}
+
+/// Creative control: fire a Black Swan now, ignoring its triggers and cooldown. Debts are applied
+/// here; the effects are returned for the callers holding the worker roster.
+pub fn force_black_swan(
+    mode: GameMode,
+    index: &mut BlackSwanIndex,
+    debts: &mut Debts,
+    swan_id: &str,
+    current_tick: u64,
+) -> Result<Vec<Effect>> {
+    mode.require_creative("Forcing events")?;
+    let effects = index.fire(swan_id, current_tick)?;
+    apply_effects(&effects, debts, current_tick);
+    Ok(effects)
+}
+
+/// Creative control: set the corruption field directly (clamped to 0..=1), including the
+/// sim's live field when there is one
+pub fn set_corruption_field(
+    mode: GameMode,
+    colony: &mut Colony,
+    field: Option<&mut CorruptionField>,
+    value: f32,
+) -> Result<()> {
+    mode.require_creative("Setting corruption")?;
+    if !value.is_finite() {
+        return Err(anyhow::anyhow!("Corruption must be a finite number"));
+    }
+    colony.corruption_field = value.clamp(0.0, 1.0);
+    if let Some(field) = field {
+        field.global = colony.corruption_field;
+    }
+    Ok(())
+}
+
+/// Keeps a creative session's research points and budgets topped up
+pub fn creative_mode_system(
+    mode: Res<GameMode>,
+    mut colony: ResMut<Colony>,
+    research: Option<ResMut<ResearchState>>,
+) {
+    if !mode.is_creative() {
+        return;
+    }
+    colony.power_cap_kw = colony.power_cap_kw.max(CREATIVE_POWER_CAP_KW);
+    colony.bandwidth_total_gbps = colony.bandwidth_total_gbps.max(CREATIVE_BANDWIDTH_GBPS);
+    if let Some(mut research) = research {
+        research.pts = research.pts.max(CREATIVE_RESEARCH_PTS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColonyPlugin, WinLossState, TechTree};
+
+    #[test]
+    fn test_controls_are_locked_outside_creative() {
+        let mut colony = crate::colony_for_scenario(None, None).unwrap();
+        let mut field = CorruptionField::new();
+        assert!(set_corruption_field(GameMode::Standard, &mut colony, Some(&mut field), 0.9).is_err());
+        set_corruption_field(GameMode::Creative, &mut colony, Some(&mut field), 1.5).unwrap();
+        assert_eq!((colony.corruption_field, field.global), (1.0, 1.0));
+
+        let mut index = crate::ContentPack::vanilla().black_swan_index();
+        let mut debts = Debts::new();
+        assert!(force_black_swan(GameMode::Standard, &mut index, &mut debts, "vram_ecc_propagation", 0).is_err());
+        force_black_swan(GameMode::Creative, &mut index, &mut debts, "vram_ecc_propagation", 0).unwrap();
+        assert!(index.meters.active.contains(&"vram_ecc_propagation".to_string()));
+        assert!(force_black_swan(GameMode::Creative, &mut index, &mut debts, "no_such_swan", 0).is_err());
+    }
+
+    #[test]
+    fn test_creative_session_cannot_lose_and_never_runs_out() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .insert_resource(GameMode::Creative)
+            .add_plugins(ColonyPlugin::default());
+        // A Black Swan chain far longer than any loss rule allows
+        app.world_mut().resource_mut::<BlackSwanIndex>().meters.active = vec!["pcie_link_flap".to_string(); 10];
+        app.update();
+
+        assert!(!app.world().resource::<WinLossState>().doom);
+        assert_eq!(app.world().resource::<ResearchState>().pts, CREATIVE_RESEARCH_PTS);
+        assert!(app.world().resource::<Colony>().power_cap_kw >= CREATIVE_POWER_CAP_KW);
+
+        // Spending points doesn't stick
+        let tree: TechTree = app.world().resource::<TechTree>().clone();
+        let tech = tree.get_tech("truth_beacon").unwrap().clone();
+        assert!(app.world_mut().resource_mut::<ResearchState>().research_tech(&tech));
+        app.update();
+        assert_eq!(app.world().resource::<ResearchState>().pts, CREATIVE_RESEARCH_PTS);
+    }
+}
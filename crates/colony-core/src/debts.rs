@@ -10,6 +10,9 @@ pub enum Debt {
     VramLeak { mb_per_tick: f32, until_tick: u64 },
     FaultBias { kind: String, weight_mult: f32, until_tick: u64 },
     Illusion { metric: String, delta: f32, until_tick: u64 }, // UI only
+    YardOffline { yard: String, until_tick: u64 },            // yard skipped by dispatch
+    YardIdle { yard: String, until_tick: u64 },               // the yard's workers are on strike
+    SinkOffline { sink: String, until_tick: u64 },            // export destination unreachable
 }
 
 impl Debt {
@@ -21,6 +24,9 @@ impl Debt {
             Debt::VramLeak { until_tick, .. } => current_tick >= *until_tick,
             Debt::FaultBias { until_tick, .. } => current_tick >= *until_tick,
             Debt::Illusion { until_tick, .. } => current_tick >= *until_tick,
+            Debt::YardOffline { until_tick, .. } => current_tick >= *until_tick,
+            Debt::YardIdle { until_tick, .. } => current_tick >= *until_tick,
+            Debt::SinkOffline { until_tick, .. } => current_tick >= *until_tick,
         }
     }
 
@@ -32,8 +38,103 @@ impl Debt {
             Debt::VramLeak { until_tick, .. } => *until_tick,
             Debt::FaultBias { until_tick, .. } => *until_tick,
             Debt::Illusion { until_tick, .. } => *until_tick,
+            Debt::YardOffline { until_tick, .. } => *until_tick,
+            Debt::YardIdle { until_tick, .. } => *until_tick,
+            Debt::SinkOffline { until_tick, .. } => *until_tick,
         }
     }
+
+    /// Pull the expiry in by `fraction` of the time remaining
+    pub fn shorten(&mut self, fraction: f32, current_tick: u64) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let until = match self {
+            Debt::PowerMult { until_tick, .. }
+            | Debt::HeatAdd { until_tick, .. }
+            | Debt::BandwidthTax { until_tick, .. }
+            | Debt::VramLeak { until_tick, .. }
+            | Debt::FaultBias { until_tick, .. }
+            | Debt::Illusion { until_tick, .. }
+            | Debt::YardOffline { until_tick, .. }
+            | Debt::YardIdle { until_tick, .. }
+            | Debt::SinkOffline { until_tick, .. } => until_tick,
+        };
+        let remaining = until.saturating_sub(current_tick);
+        *until = current_tick + (remaining as f32 * (1.0 - fraction)).round() as u64;
+    }
+
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Debt::PowerMult { .. } => "PowerMult",
+            Debt::HeatAdd { .. } => "HeatAdd",
+            Debt::BandwidthTax { .. } => "BandwidthTax",
+            Debt::VramLeak { .. } => "VramLeak",
+            Debt::FaultBias { .. } => "FaultBias",
+            Debt::Illusion { .. } => "Illusion",
+            Debt::YardOffline { .. } => "YardOffline",
+            Debt::YardIdle { .. } => "YardIdle",
+            Debt::SinkOffline { .. } => "SinkOffline",
+        }
+    }
+}
+
+/// Projected expiry of a single active debt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebtExpiry {
+    pub kind: String,
+    pub until_tick: u64,
+    pub ticks_remaining: u64,
+}
+
+/// Aggregate view of what the colony can actually deliver once debts are applied
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct EffectiveCapacity {
+    pub power_cap_kw: f32,         // nominal cap / power multiplier
+    pub bandwidth_total_gbps: f32, // nominal bandwidth / bandwidth tax
+    pub fault_rate_mult: f32,      // product of all fault biases
+    pub heat_add_per_tick: f32,
+    pub vram_leak_mb_per_tick: f32,
+    pub expiries: Vec<DebtExpiry>,  // soonest first
+    pub tick: u64,
+}
+
+impl Default for EffectiveCapacity {
+    fn default() -> Self {
+        Self {
+            power_cap_kw: 0.0,
+            bandwidth_total_gbps: 0.0,
+            fault_rate_mult: 1.0,
+            heat_add_per_tick: 0.0,
+            vram_leak_mb_per_tick: 0.0,
+            expiries: Vec::new(),
+            tick: 0,
+        }
+    }
+}
+
+impl EffectiveCapacity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn compute(debts: &Debts, power_cap_kw: f32, bandwidth_total_gbps: f32, current_tick: u64) -> Self {
+        let power_mult = debts.get_power_multiplier(current_tick).max(f32::EPSILON);
+        let bandwidth_tax = debts.get_bandwidth_tax(current_tick).max(f32::EPSILON);
+
+        Self {
+            power_cap_kw: power_cap_kw / power_mult,
+            bandwidth_total_gbps: bandwidth_total_gbps / bandwidth_tax,
+            fault_rate_mult: debts.get_fault_rate_multiplier(current_tick),
+            heat_add_per_tick: debts.get_heat_addition(current_tick),
+            vram_leak_mb_per_tick: debts.get_vram_leak(current_tick),
+            expiries: debts.forecast_expiries(current_tick),
+            tick: current_tick,
+        }
+    }
+
+    /// Ticks until the last active debt expires, or 0 if none are active
+    pub fn ticks_until_clear(&self) -> u64 {
+        self.expiries.iter().map(|e| e.ticks_remaining).max().unwrap_or(0)
+    }
 }
 
 #[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
@@ -136,6 +237,53 @@ impl Debts {
             .fold(1.0, |acc, mult| acc * mult)
     }
 
+    /// Combined multiplier across every fault bias, regardless of fault kind
+    pub fn get_fault_rate_multiplier(&self, current_tick: u64) -> f32 {
+        self.active
+            .iter()
+            .filter(|debt| !debt.is_expired(current_tick))
+            .filter_map(|debt| {
+                if let Debt::FaultBias { weight_mult, .. } = debt {
+                    Some(*weight_mult)
+                } else {
+                    None
+                }
+            })
+            .fold(1.0, |acc, mult| acc * mult)
+    }
+
+    pub fn is_yard_offline(&self, yard: &str, current_tick: u64) -> bool {
+        self.active.iter().any(|debt| {
+            !debt.is_expired(current_tick) && matches!(debt, Debt::YardOffline { yard: y, .. } if y == yard)
+        })
+    }
+
+    pub fn is_yard_idle(&self, yard: &str, current_tick: u64) -> bool {
+        self.active.iter().any(|debt| {
+            !debt.is_expired(current_tick) && matches!(debt, Debt::YardIdle { yard: y, .. } if y == yard)
+        })
+    }
+
+    pub fn is_sink_offline(&self, sink: &str, current_tick: u64) -> bool {
+        self.active.iter().any(|debt| {
+            !debt.is_expired(current_tick) && matches!(debt, Debt::SinkOffline { sink: s, .. } if s == sink)
+        })
+    }
+
+    pub fn forecast_expiries(&self, current_tick: u64) -> Vec<DebtExpiry> {
+        let mut expiries: Vec<DebtExpiry> = self.active
+            .iter()
+            .filter(|debt| !debt.is_expired(current_tick))
+            .map(|debt| DebtExpiry {
+                kind: debt.kind_name().to_string(),
+                until_tick: debt.get_until_tick(),
+                ticks_remaining: debt.get_until_tick() - current_tick,
+            })
+            .collect();
+        expiries.sort_by_key(|e| e.until_tick);
+        expiries
+    }
+
     pub fn get_illusions(&self, current_tick: u64) -> HashMap<String, f32> {
         let mut illusions = HashMap::new();
         for debt in &self.active {
@@ -181,6 +329,16 @@ pub fn apply_debts_system(
     debts.clear_expired(current_tick);
 }
 
+pub fn effective_capacity_system(
+    debts: Res<Debts>,
+    colony: Res<super::Colony>,
+    clock: Res<super::SimClock>,
+    mut capacity: ResMut<EffectiveCapacity>,
+) {
+    let current_tick = clock.now.timestamp_millis() as u64 / 16;
+    *capacity = EffectiveCapacity::compute(&debts, colony.power_cap_kw, colony.bandwidth_total_gbps, current_tick);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,4 +391,35 @@ mod tests {
         assert_eq!(debts.get_fault_bias("StickyConfig", current_tick), 1.5);
         assert_eq!(debts.get_fault_bias("Transient", current_tick), 1.0);
     }
+
+    #[test]
+    fn test_effective_capacity() {
+        let mut debts = Debts::new();
+        let current_tick = 100;
+
+        debts.add_debt(Debt::PowerMult { mult: 1.25, until_tick: 300 });
+        debts.add_debt(Debt::BandwidthTax { mult: 2.0, until_tick: 150 });
+        debts.add_debt(Debt::FaultBias { kind: "Transient".to_string(), weight_mult: 1.5, until_tick: 200 });
+        debts.add_debt(Debt::FaultBias { kind: "StickyConfig".to_string(), weight_mult: 2.0, until_tick: 50 }); // expired
+
+        let cap = EffectiveCapacity::compute(&debts, 1000.0, 32.0, current_tick);
+        assert_eq!(cap.power_cap_kw, 800.0);
+        assert_eq!(cap.bandwidth_total_gbps, 16.0);
+        assert_eq!(cap.fault_rate_mult, 1.5);
+        assert_eq!(cap.ticks_until_clear(), 200);
+    }
+
+    #[test]
+    fn test_forecast_expiries_sorted() {
+        let mut debts = Debts::new();
+        debts.add_debt(Debt::HeatAdd { celsius: 2.0, until_tick: 500 });
+        debts.add_debt(Debt::VramLeak { mb_per_tick: 1.0, until_tick: 200 });
+
+        let expiries = debts.forecast_expiries(100);
+        assert_eq!(expiries.len(), 2);
+        assert_eq!(expiries[0].kind, "VramLeak");
+        assert_eq!(expiries[0].ticks_remaining, 100);
+        assert_eq!(expiries[1].kind, "HeatAdd");
+        assert_eq!(expiries[1].ticks_remaining, 400);
+    }
 }
@@ -0,0 +1,233 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use crate::{Autoscaler, Debts, EventJournal, JournalEvent, ResearchState, SimClock};
+
+const MAX_DIGESTS: usize = 400;
+const DAYS_PER_WEEK: u64 = 7;
+const TICKS_PER_DAY: u64 = 86_400_000 / 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestPeriod {
+    Day,
+    Week,
+}
+
+/// Summary of one sim day or week, written to the journal when the period closes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Digest {
+    pub period: DigestPeriod,
+    pub index: u64,                // day number, or week number for weekly digests
+    pub first_day: u64,
+    pub last_day: u64,
+    pub closed_tick: u64,
+    pub sla_hit_pct: f32,          // mean over the days covered
+    pub notable_events: Vec<String>, // Black Swans, rituals, victory or loss
+    pub debts_gained: Vec<String>, // debt kinds, one entry per debt
+    pub debts_cleared: Vec<String>,
+    pub research_points_gained: i64, // net of points spent
+    pub techs_acquired: Vec<String>,
+    pub budget_delta: f64,         // change in the autoscaling budget left; spending is negative
+}
+
+impl Digest {
+    /// Roll day digests up into one for the week they fall in
+    pub fn week(index: u64, days: &[Digest]) -> Option<Digest> {
+        let (first, last) = (days.first()?, days.last()?);
+        Some(Digest {
+            period: DigestPeriod::Week,
+            index,
+            first_day: first.first_day,
+            last_day: last.last_day,
+            closed_tick: last.closed_tick,
+            sla_hit_pct: days.iter().map(|d| d.sla_hit_pct).sum::<f32>() / days.len() as f32,
+            notable_events: days.iter().flat_map(|d| d.notable_events.iter().cloned()).collect(),
+            debts_gained: days.iter().flat_map(|d| d.debts_gained.iter().cloned()).collect(),
+            debts_cleared: days.iter().flat_map(|d| d.debts_cleared.iter().cloned()).collect(),
+            research_points_gained: days.iter().map(|d| d.research_points_gained).sum(),
+            techs_acquired: days.iter().flat_map(|d| d.techs_acquired.iter().cloned()).collect(),
+            budget_delta: days.iter().map(|d| d.budget_delta).sum(),
+        })
+    }
+
+    /// One-line summary for logs and the day report
+    pub fn headline(&self) -> String {
+        let label = match self.period {
+            DigestPeriod::Day => "Day",
+            DigestPeriod::Week => "Week",
+        };
+        format!(
+            "{} {}: SLA {:.2}%, {} events, debts +{}/-{}, research {:+}, budget {:+.1}",
+            label, self.index, self.sla_hit_pct, self.notable_events.len(),
+            self.debts_gained.len(), self.debts_cleared.len(), self.research_points_gained, self.budget_delta
+        )
+    }
+}
+
+/// The state a day opened with, so its digest can report what changed
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DigestBaseline {
+    pub day: Option<u64>,
+    pub opened_tick: u64,
+    pub debts: BTreeMap<String, usize>, // debt kind -> active count
+    pub research_pts: u32,
+    pub techs: usize,
+    pub budget_left: f64,
+}
+
+fn debt_counts(debts: Option<&Debts>) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for debt in debts.map(|d| d.active.as_slice()).unwrap_or_default() {
+        *counts.entry(debt.kind_name().to_string()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// `to` minus `from`, one entry per extra debt
+fn debt_difference(from: &BTreeMap<String, usize>, to: &BTreeMap<String, usize>) -> Vec<String> {
+    to.iter()
+        .flat_map(|(kind, &n)| std::iter::repeat_n(kind.clone(), n.saturating_sub(from.get(kind).copied().unwrap_or(0))))
+        .collect()
+}
+
+impl DigestBaseline {
+    fn open(&mut self, day: u64, tick: u64, debts: Option<&Debts>, research: Option<&ResearchState>, budget_left: f64) {
+        self.day = Some(day);
+        self.opened_tick = tick;
+        self.debts = debt_counts(debts);
+        self.research_pts = research.map_or(0, |r| r.pts);
+        self.techs = research.map_or(0, |r| r.acquired.len());
+        self.budget_left = budget_left;
+    }
+
+    /// Digest of the day being closed at `tick`
+    pub fn close(&self, journal: &EventJournal, tick: u64, sla_hit_pct: f32, debts: Option<&Debts>, research: Option<&ResearchState>, budget_left: f64) -> Digest {
+        let day = self.day.unwrap_or(0);
+        let now_debts = debt_counts(debts);
+        let notable_events = journal.entries.iter()
+            .filter(|e| e.tick >= self.opened_tick && e.tick <= tick)
+            .filter(|e| !matches!(e.event, JournalEvent::DayClosed { .. }))
+            .map(|e| match e.event.detail() {
+                Some(detail) => format!("{}: {}", e.event.name(), detail),
+                None => e.event.name().to_string(),
+            })
+            .collect();
+        Digest {
+            period: DigestPeriod::Day,
+            index: day,
+            first_day: day,
+            last_day: day,
+            closed_tick: tick,
+            sla_hit_pct,
+            notable_events,
+            debts_gained: debt_difference(&self.debts, &now_debts),
+            debts_cleared: debt_difference(&now_debts, &self.debts),
+            research_points_gained: research.map_or(0, |r| r.pts as i64) - self.research_pts as i64,
+            techs_acquired: research.map(|r| r.acquired.iter().skip(self.techs).cloned().collect()).unwrap_or_default(),
+            budget_delta: budget_left - self.budget_left,
+        }
+    }
+}
+
+impl EventJournal {
+    pub fn push_digest(&mut self, digest: Digest) {
+        self.digests.push_back(digest);
+        while self.digests.len() > MAX_DIGESTS {
+            self.digests.pop_front();
+        }
+    }
+
+    pub fn digests_for(&self, period: DigestPeriod) -> impl Iterator<Item = &Digest> {
+        self.digests.iter().filter(move |d| d.period == period)
+    }
+}
+
+/// Write a digest to the journal whenever it closes a day, and a weekly one every seventh day
+pub fn digest_system(
+    mut journal: ResMut<EventJournal>,
+    mut baseline: ResMut<DigestBaseline>,
+    clock: Res<SimClock>,
+    debts: Option<Res<Debts>>,
+    research: Option<Res<ResearchState>>,
+    autoscaler: Option<Res<Autoscaler>>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    let budget_left = autoscaler.map_or(0.0, |a| a.remaining_budget());
+    let Some(day) = baseline.day else {
+        let day = now_tick / TICKS_PER_DAY;
+        baseline.open(day, now_tick, debts.as_deref(), research.as_deref(), budget_left);
+        return;
+    };
+    let closed = journal.entries.iter().rev()
+        .take_while(|e| e.tick == now_tick)
+        .find_map(|e| match e.event {
+            JournalEvent::DayClosed { sla_hit_pct } => Some(sla_hit_pct),
+            _ => None,
+        });
+    let Some(sla_hit_pct) = closed else { return };
+
+    let digest = baseline.close(&journal, now_tick, sla_hit_pct, debts.as_deref(), research.as_deref(), budget_left);
+    println!("📰 {}", digest.headline());
+    journal.push_digest(digest);
+    if day % DAYS_PER_WEEK == DAYS_PER_WEEK - 1 {
+        let week = day / DAYS_PER_WEEK;
+        let days: Vec<Digest> = journal.digests_for(DigestPeriod::Day)
+            .filter(|d| d.index / DAYS_PER_WEEK == week)
+            .cloned()
+            .collect();
+        if let Some(weekly) = Digest::week(week, &days) {
+            println!("📰 {}", weekly.headline());
+            journal.push_digest(weekly);
+        }
+    }
+    baseline.open(now_tick / TICKS_PER_DAY, now_tick, debts.as_deref(), research.as_deref(), budget_left);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Debt;
+
+    #[test]
+    fn test_day_digest_reports_changes() {
+        let mut debts = Debts::new();
+        debts.add_debt(Debt::PowerMult { mult: 1.2, until_tick: 10 });
+        let mut research = ResearchState::new();
+        research.pts = 10;
+
+        let mut baseline = DigestBaseline::default();
+        baseline.open(3, 3 * TICKS_PER_DAY, Some(&debts), Some(&research), 500.0);
+
+        let mut journal = EventJournal::new();
+        journal.push(3 * TICKS_PER_DAY + 5, JournalEvent::BlackSwanFired { swan_id: "pcie_link_flap".into() });
+        journal.push(2 * TICKS_PER_DAY, JournalEvent::BlackSwanFired { swan_id: "yesterday".into() });
+        debts.active.clear();
+        debts.add_debt(Debt::HeatAdd { celsius: 5.0, until_tick: 99 });
+        debts.add_debt(Debt::HeatAdd { celsius: 5.0, until_tick: 99 });
+        research.pts = 4;
+        research.acquired.push("truth_beacon".into());
+
+        let digest = baseline.close(&journal, 4 * TICKS_PER_DAY, 98.5, Some(&debts), Some(&research), 400.0);
+        assert_eq!(digest.index, 3);
+        assert_eq!(digest.notable_events, vec!["BlackSwanFired: pcie_link_flap".to_string()]);
+        assert_eq!(digest.debts_gained, vec!["HeatAdd".to_string(), "HeatAdd".to_string()]);
+        assert_eq!(digest.debts_cleared, vec!["PowerMult".to_string()]);
+        assert_eq!(digest.research_points_gained, -6);
+        assert_eq!(digest.techs_acquired, vec!["truth_beacon".to_string()]);
+        assert_eq!(digest.budget_delta, -100.0);
+    }
+
+    #[test]
+    fn test_week_rolls_up_days() {
+        let baseline = DigestBaseline::default();
+        let journal = EventJournal::new();
+        let days: Vec<Digest> = (0..7)
+            .map(|d| Digest { index: d, first_day: d, last_day: d, ..baseline.close(&journal, d, if d == 0 { 93.0 } else { 100.0 }, None, None, 0.0) })
+            .collect();
+        let week = Digest::week(0, &days).unwrap();
+        assert_eq!((week.period, week.first_day, week.last_day), (DigestPeriod::Week, 0, 6));
+        assert_eq!(week.sla_hit_pct, 99.0);
+        assert!(Digest::week(1, &[]).is_none());
+    }
+}
@@ -0,0 +1,293 @@
+use bevy::prelude::*;
+use chrono::Timelike;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, VecDeque};
+use anyhow::Result;
+use crate::{Colony, SimClock, Workyard, WorkyardKind, yard_node_id};
+
+/// Carbon periods kept for the report
+const MAX_CARBON_PERIODS: usize = 64;
+/// Carbon is summed per SLA observation window: 7 sim days of 16ms ticks
+pub const DEFAULT_CARBON_PERIOD_TICKS: u64 = 7 * 86_400_000 / 16;
+/// Jobs that didn't come from a content pipeline
+pub const AD_HOC_PIPELINE: &str = "ad_hoc";
+
+/// Grid carbon intensity at one hour of the sim day
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CarbonPoint {
+    pub hour: f32, // 0..24
+    pub g_per_kwh: f32,
+}
+
+/// `[energy]` table in the game config, also set through `PUT /energy/carbon`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyConfig {
+    #[serde(default = "default_carbon_intensity")]
+    pub carbon_intensity: Vec<CarbonPoint>, // interpolated between points, wrapping at midnight
+}
+
+fn default_carbon_intensity() -> Vec<CarbonPoint> {
+    // Cleanest around solar noon, dirtiest over the evening peak
+    [(0.0, 380.0), (6.0, 420.0), (12.0, 220.0), (18.0, 520.0)]
+        .into_iter()
+        .map(|(hour, g_per_kwh)| CarbonPoint { hour, g_per_kwh })
+        .collect()
+}
+
+impl Default for EnergyConfig {
+    fn default() -> Self {
+        Self { carbon_intensity: default_carbon_intensity() }
+    }
+}
+
+impl EnergyConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.carbon_intensity.is_empty() {
+            return Err(anyhow::anyhow!("carbon_intensity needs at least one point"));
+        }
+        for pair in self.carbon_intensity.windows(2) {
+            if pair[1].hour <= pair[0].hour {
+                return Err(anyhow::anyhow!("carbon_intensity hours must increase ({} then {})", pair[0].hour, pair[1].hour));
+            }
+        }
+        for p in &self.carbon_intensity {
+            if !(0.0..24.0).contains(&p.hour) {
+                return Err(anyhow::anyhow!("carbon_intensity hour {} is outside 0..24", p.hour));
+            }
+            if p.g_per_kwh < 0.0 {
+                return Err(anyhow::anyhow!("carbon_intensity at hour {} is negative", p.hour));
+            }
+        }
+        Ok(())
+    }
+
+    /// Grams of CO2 per kWh at `hour` of the day
+    pub fn intensity_at(&self, hour: f32) -> f32 {
+        let points = &self.carbon_intensity;
+        let Some(first) = points.first() else { return 0.0 };
+        let hour = hour.rem_euclid(24.0);
+        let next = points.iter().position(|p| p.hour > hour);
+        let (before, after) = match next {
+            Some(0) | None => (points[points.len() - 1], CarbonPoint { hour: first.hour + 24.0, ..*first }),
+            Some(i) => (points[i - 1], points[i]),
+        };
+        let hour = if hour < before.hour { hour + 24.0 } else { hour };
+        let span = after.hour - before.hour;
+        if span <= 0.0 {
+            return before.g_per_kwh;
+        }
+        before.g_per_kwh + (after.g_per_kwh - before.g_per_kwh) * (hour - before.hour) / span
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YardEnergy {
+    pub kind: WorkyardKind,
+    pub kwh: f64,
+    pub carbon_g: f64,
+}
+
+/// Energy of the work a pipeline's jobs put on their yards
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PipelineEnergy {
+    pub kwh: f64,
+    pub carbon_g: f64,
+    pub jobs_run: u64,
+    pub jobs_completed: u64,
+}
+
+impl PipelineEnergy {
+    /// Joules per completed job, counting the energy of faulted runs too
+    pub fn joules_per_job(&self) -> Option<f64> {
+        (self.jobs_completed > 0).then(|| self.kwh * 3.6e6 / self.jobs_completed as f64)
+    }
+}
+
+/// Energy and carbon over one SLA period
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CarbonPeriod {
+    pub period: u64, // tick / period_ticks
+    pub kwh: f64,
+    pub carbon_g: f64,
+    pub jobs_completed: u64,
+}
+
+/// Cumulative kWh and carbon per yard and per pipeline. `energy_system` meters every yard's
+/// draw each tick; dispatch charges each job its slot's share of the yard for its service time.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyLedger {
+    pub config: EnergyConfig,
+    pub period_ticks: u64,
+    pub intensity_g_per_kwh: f32, // at the last metered tick
+    pub total_kwh: f64,
+    pub total_carbon_g: f64,
+    pub jobs_completed: u64,
+    pub yards: BTreeMap<String, YardEnergy>,
+    pub pipelines: BTreeMap<String, PipelineEnergy>,
+    pub periods: VecDeque<CarbonPeriod>,
+}
+
+impl Default for EnergyLedger {
+    fn default() -> Self {
+        Self {
+            config: EnergyConfig::default(),
+            period_ticks: DEFAULT_CARBON_PERIOD_TICKS,
+            intensity_g_per_kwh: 0.0,
+            total_kwh: 0.0,
+            total_carbon_g: 0.0,
+            jobs_completed: 0,
+            yards: BTreeMap::new(),
+            pipelines: BTreeMap::new(),
+            periods: VecDeque::new(),
+        }
+    }
+}
+
+/// The green-ops summary served at `GET /energy/report`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyReport {
+    pub total_kwh: f64,
+    pub total_carbon_kg: f64,
+    pub joules_per_completed_job: Option<f64>, // whole colony, idle draw included
+    pub carbon_g_per_completed_job: Option<f64>,
+    pub intensity_g_per_kwh: f32,
+    pub yards: BTreeMap<String, YardEnergy>,
+    pub pipelines: BTreeMap<String, PipelineEnergy>,
+    pub carbon_kg_per_period: Vec<(u64, f64)>,
+}
+
+impl EnergyLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn configure(&mut self, config: &EnergyConfig) -> Result<()> {
+        config.validate()?;
+        self.config = config.clone();
+        Ok(())
+    }
+
+    fn period_mut(&mut self, tick: u64) -> &mut CarbonPeriod {
+        let period = tick / self.period_ticks.max(1);
+        if self.periods.back().is_none_or(|p| p.period != period) {
+            self.periods.push_back(CarbonPeriod { period, ..Default::default() });
+            while self.periods.len() > MAX_CARBON_PERIODS {
+                self.periods.pop_front();
+            }
+        }
+        self.periods.back_mut().unwrap()
+    }
+
+    /// Meter one yard drawing `kw` over `hours` of sim time
+    pub fn meter_yard(&mut self, yard_id: &str, kind: WorkyardKind, kw: f32, hours: f64, hour_of_day: f32, tick: u64) {
+        self.intensity_g_per_kwh = self.config.intensity_at(hour_of_day);
+        let kwh = kw as f64 * hours;
+        let carbon_g = kwh * self.intensity_g_per_kwh as f64;
+        let yard = self.yards.entry(yard_id.to_string()).or_insert(YardEnergy { kind, kwh: 0.0, carbon_g: 0.0 });
+        yard.kwh += kwh;
+        yard.carbon_g += carbon_g;
+        self.total_kwh += kwh;
+        self.total_carbon_g += carbon_g;
+        let period = self.period_mut(tick);
+        period.kwh += kwh;
+        period.carbon_g += carbon_g;
+    }
+
+    /// Charge a job's pipeline for `service_ms` on one of the yard's `slots`
+    pub fn charge_job(&mut self, pipeline: Option<&str>, yard_kw: f32, slots: u32, service_ms: u32, completed: bool, tick: u64) {
+        let kwh = (yard_kw / slots.max(1) as f32) as f64 * service_ms as f64 / 3.6e6;
+        let carbon_g = kwh * self.intensity_g_per_kwh as f64;
+        let entry = self.pipelines.entry(pipeline.unwrap_or(AD_HOC_PIPELINE).to_string()).or_default();
+        entry.kwh += kwh;
+        entry.carbon_g += carbon_g;
+        entry.jobs_run += 1;
+        if completed {
+            entry.jobs_completed += 1;
+            self.jobs_completed += 1;
+            self.period_mut(tick).jobs_completed += 1;
+        }
+    }
+
+    /// Carbon of the SLA period `tick` falls in, in kg
+    pub fn period_carbon_kg(&self, tick: u64) -> f64 {
+        let period = tick / self.period_ticks.max(1);
+        self.periods.iter().find(|p| p.period == period).map_or(0.0, |p| p.carbon_g / 1000.0)
+    }
+
+    pub fn report(&self) -> EnergyReport {
+        let per_job = |total: f64| (self.jobs_completed > 0).then(|| total / self.jobs_completed as f64);
+        EnergyReport {
+            total_kwh: self.total_kwh,
+            total_carbon_kg: self.total_carbon_g / 1000.0,
+            joules_per_completed_job: per_job(self.total_kwh * 3.6e6),
+            carbon_g_per_completed_job: per_job(self.total_carbon_g),
+            intensity_g_per_kwh: self.intensity_g_per_kwh,
+            yards: self.yards.clone(),
+            pipelines: self.pipelines.clone(),
+            carbon_kg_per_period: self.periods.iter().map(|p| (p.period, p.carbon_g / 1000.0)).collect(),
+        }
+    }
+}
+
+/// Meter every yard's draw for the tick that just ran
+pub fn energy_system(
+    mut ledger: ResMut<EnergyLedger>,
+    yards: Query<(Entity, &Workyard)>,
+    colony: Res<Colony>,
+    clock: Res<SimClock>,
+) {
+    let tick = clock.now.timestamp_millis() as u64 / 16;
+    let hours = clock.advance().as_secs_f64() / 3600.0;
+    let hour_of_day = clock.now.hour() as f32 + clock.now.minute() as f32 / 60.0;
+    // Debts can raise the colony's draw over the yards' nameplate; spread that over the yards
+    let nameplate: f32 = yards.iter().map(|(_, y)| y.power_draw_kw).sum();
+    let scale = if nameplate > 0.0 { colony.meters.power_draw_kw / nameplate } else { 1.0 };
+    for (yard_e, yard) in &yards {
+        ledger.meter_yard(&yard_node_id(yard_e), yard.kind.clone(), yard.power_draw_kw * scale, hours, hour_of_day, tick);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_carbon_curve_interpolates_and_wraps() {
+        let config = EnergyConfig::default();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.intensity_at(12.0), 220.0);
+        assert_eq!(config.intensity_at(15.0), 370.0);
+        // 18:00 -> 24:00 runs from 520 back to midnight's 380
+        assert_eq!(config.intensity_at(21.0), 450.0);
+        assert_eq!(config.intensity_at(24.0), 380.0);
+
+        let flat = EnergyConfig { carbon_intensity: vec![CarbonPoint { hour: 8.0, g_per_kwh: 100.0 }] };
+        assert_eq!(flat.intensity_at(3.0), 100.0);
+
+        let backwards = EnergyConfig { carbon_intensity: vec![CarbonPoint { hour: 8.0, g_per_kwh: 100.0 }, CarbonPoint { hour: 4.0, g_per_kwh: 100.0 }] };
+        assert!(backwards.validate().is_err());
+    }
+
+    #[test]
+    fn test_yard_and_pipeline_accounting() {
+        let mut ledger = EnergyLedger::new();
+        ledger.configure(&EnergyConfig { carbon_intensity: vec![CarbonPoint { hour: 0.0, g_per_kwh: 500.0 }] }).unwrap();
+        // 120 kW for an hour, then 3 jobs at 2 s each on a 4-slot yard
+        ledger.meter_yard("yard:1", WorkyardKind::CpuArray, 120.0, 1.0, 10.0, 0);
+        for completed in [true, true, false] {
+            ledger.charge_job(Some("http_ingest"), 120.0, 4, 2000, completed, 0);
+        }
+        assert_eq!(ledger.yards["yard:1"].kwh, 120.0);
+
+        let report = ledger.report();
+        assert_eq!(report.total_carbon_kg, 60.0);
+        assert_eq!(report.joules_per_completed_job, Some(120.0 * 3.6e6 / 2.0));
+        let ingest = &report.pipelines["http_ingest"];
+        assert_eq!((ingest.jobs_run, ingest.jobs_completed), (3, 2));
+        // 30 kW per slot for 6 s
+        assert!((ingest.joules_per_job().unwrap() - 30_000.0 * 6.0 / 2.0).abs() < 1e-6);
+        assert_eq!(ledger.period_carbon_kg(0), 60.0);
+        assert_eq!(ledger.period_carbon_kg(DEFAULT_CARBON_PERIOD_TICKS), 0.0);
+    }
+}
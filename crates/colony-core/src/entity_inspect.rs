@@ -0,0 +1,102 @@
+use bevy::ecs::world::EntityRef;
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use anyhow::Result;
+use crate::{Certifications, DriveWear, GpuFarm, PipelineGenome, RetryState, Worker, WorkerHealth, WorkerSkills, WorkerStateComp, Workyard, YardClock, YardPos};
+
+/// Entities returned when a request doesn't set a limit
+pub const DEFAULT_INSPECT_LIMIT: usize = 100;
+
+type Dump = fn(&EntityRef) -> Option<serde_json::Value>;
+
+fn dump<T: Component + Serialize>(entity: &EntityRef) -> Option<serde_json::Value> {
+    entity.get::<T>().and_then(|c| serde_json::to_value(c).ok())
+}
+
+/// The components the inspector can show and filter on, by type name
+const INSPECTABLE: &[(&str, Dump)] = &[
+    ("Worker", dump::<Worker>),
+    ("WorkerSkills", dump::<WorkerSkills>),
+    ("WorkerHealth", dump::<WorkerHealth>),
+    ("WorkerStateComp", dump::<WorkerStateComp>),
+    ("RetryState", dump::<RetryState>),
+    ("Certifications", dump::<Certifications>),
+    ("Workyard", dump::<Workyard>),
+    ("YardClock", dump::<YardClock>),
+    ("YardPos", dump::<YardPos>),
+    ("DriveWear", dump::<DriveWear>),
+    ("GpuFarm", dump::<GpuFarm>),
+    ("PipelineGenome", dump::<PipelineGenome>),
+];
+
+pub fn inspectable_components() -> Vec<&'static str> {
+    INSPECTABLE.iter().map(|(name, _)| *name).collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityDump {
+    pub entity: String, // index and generation, as Bevy prints it
+    pub components: BTreeMap<String, serde_json::Value>, // only the inspectable ones
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityInspection {
+    pub matched: usize,
+    pub entities: Vec<EntityDump>, // the first `limit` matches, by entity index
+}
+
+/// Serialize the live components of every entity that has all the `filter` components
+/// (comma-separated names; empty matches any entity with an inspectable component)
+pub fn inspect_entities(world: &World, filter: &str, limit: usize) -> Result<EntityInspection> {
+    let mut wanted = Vec::new();
+    for name in filter.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let (_, dump) = INSPECTABLE.iter().find(|(known, _)| *known == name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown component '{}', inspectable: {}", name, inspectable_components().join(", ")))?;
+        wanted.push(*dump);
+    }
+
+    let mut matches: Vec<EntityDump> = Vec::new();
+    let mut entities: Vec<EntityRef> = world.iter_entities().collect();
+    entities.sort_by_key(|e| e.id().index());
+    for entity in &entities {
+        if !wanted.iter().all(|dump| dump(entity).is_some()) {
+            continue;
+        }
+        let components: BTreeMap<String, serde_json::Value> = INSPECTABLE.iter()
+            .filter_map(|(name, dump)| dump(entity).map(|value| (name.to_string(), value)))
+            .collect();
+        if !components.is_empty() {
+            matches.push(EntityDump { entity: entity.id().to_string(), components });
+        }
+    }
+    let matched = matches.len();
+    matches.truncate(limit);
+    Ok(EntityInspection { matched, entities: matches })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{WorkClass, WorkerBundle};
+
+    #[test]
+    fn test_filter_and_limit() {
+        let mut world = World::new();
+        let skills = WorkerSkills { cpu: 0.5, gpu: 0.5, io: 0.5, discipline: 0.5, focus: 0.5 };
+        for id in 0..3 {
+            world.spawn(WorkerBundle::new(id, WorkClass::Cpu, skills));
+        }
+        world.spawn(Workyard {
+            kind: crate::WorkyardKind::CpuArray, slots: 4, heat: 0.0, heat_cap: 100.0, power_draw_kw: 0.0, bandwidth_share: 0.1, isolation_domain: 0,
+        });
+
+        let workers = inspect_entities(&world, "Worker", 2).unwrap();
+        assert_eq!((workers.matched, workers.entities.len()), (3, 2));
+        assert_eq!(workers.entities[1].components["Worker"]["id"], 1);
+        assert!(workers.entities[0].components.contains_key("WorkerHealth"));
+        assert_eq!(inspect_entities(&world, "", 10).unwrap().matched, 4);
+        assert_eq!(inspect_entities(&world, "Worker,Workyard", 10).unwrap().matched, 0);
+        assert!(inspect_entities(&world, "Transform", 10).is_err());
+    }
+}
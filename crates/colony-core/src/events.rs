@@ -10,6 +10,7 @@ pub enum WorkerReport {
     },
     Fault {
         worker_id: u64,
+        job_id: u64,
         op: super::Op,
         kind: super::FaultKind,
     },
@@ -0,0 +1,262 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::VecDeque;
+use std::mem::size_of;
+use crate::{Op, Debts, SimClock};
+
+/// Destination id HttpExport delivers to; `SinkOutage` effects name it
+pub const HTTP_EXPORT_SINK: &str = "http_export";
+
+const MS_PER_TICK: u64 = 16;
+
+/// A stretch of ticks the destination refuses connections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutageWindow {
+    pub start_tick: u64,
+    pub end_tick: u64, // exclusive
+}
+
+impl OutageWindow {
+    pub fn contains(&self, tick: u64) -> bool {
+        tick >= self.start_tick && tick < self.end_tick
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportSinkTunables {
+    pub attempt_ms: u32,             // worker time spent on each delivery attempt after the first
+    pub max_retries: u32,
+    pub base_backoff_ms: u32,
+    pub backoff_mult: f32,
+    pub max_backoff_ms: u32,
+    pub buffer_cap_bytes: usize,     // offline buffer budget; exports past it are dropped
+    pub drain_bytes_per_tick: usize, // replay rate once the destination is back
+}
+
+impl Default for ExportSinkTunables {
+    fn default() -> Self {
+        Self {
+            attempt_ms: 5,
+            max_retries: 3,
+            base_backoff_ms: 50,
+            backoff_mult: 2.0,
+            max_backoff_ms: 2_000,
+            buffer_cap_bytes: 64 * 1024 * 1024,
+            drain_bytes_per_tick: 256 * 1024,
+        }
+    }
+}
+
+impl ExportSinkTunables {
+    /// Backoff before retry `n` (0-based)
+    pub fn backoff_ms(&self, n: u32) -> u32 {
+        let backoff = self.base_backoff_ms as f32 * self.backoff_mult.max(1.0).powi(n as i32);
+        (backoff as u32).min(self.max_backoff_ms)
+    }
+}
+
+/// An export held while the destination is down
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferedExport {
+    pub job_id: u64,
+    pub bytes: usize,
+    pub buffered_tick: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportOutcome {
+    Delivered,
+    Buffered,
+    Dropped, // down and the buffer is full
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportAttempt {
+    pub outcome: ExportOutcome,
+    pub retries: u32,
+    pub retry_ms: u32, // worker time spent backing off and retrying
+}
+
+/// The destination behind Op::HttpExport: when it's reachable, the retries it costs, and the backlog held while it isn't
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct ExportSink {
+    pub name: String,
+    pub tunables: ExportSinkTunables,
+    pub schedule: Vec<OutageWindow>,
+    pub up: bool,
+    pub buffer: VecDeque<BufferedExport>,
+    pub buffered_bytes: usize,
+    pub peak_buffered_bytes: usize,
+    pub delivered: u64,
+    pub buffered: u64,  // exports that went to the buffer
+    pub drained: u64,   // buffered exports delivered later
+    pub dropped: u64,
+    pub retries: u64,
+    pub retry_ms_total: u64,
+}
+
+impl Default for ExportSink {
+    fn default() -> Self {
+        Self {
+            name: HTTP_EXPORT_SINK.to_string(),
+            tunables: ExportSinkTunables::default(),
+            schedule: Vec::new(),
+            up: true,
+            buffer: VecDeque::new(),
+            buffered_bytes: 0,
+            peak_buffered_bytes: 0,
+            delivered: 0,
+            buffered: 0,
+            drained: 0,
+            dropped: 0,
+            retries: 0,
+            retry_ms_total: 0,
+        }
+    }
+}
+
+impl ExportSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plan a destination outage starting at `start_tick`
+    pub fn schedule_outage(&mut self, start_tick: u64, duration_ms: u64) {
+        self.schedule.push(OutageWindow { start_tick, end_tick: start_tick + duration_ms / MS_PER_TICK });
+        self.schedule.sort_by_key(|w| w.start_tick);
+    }
+
+    /// Reachable unless a scheduled window or a `SinkOffline` debt covers the tick
+    pub fn is_up(&self, tick: u64, debts: &Debts) -> bool {
+        !self.schedule.iter().any(|w| w.contains(tick)) && !debts.is_sink_offline(&self.name, tick)
+    }
+
+    /// Deliver one export, backing off and retrying while the destination is down
+    pub fn attempt(&mut self, job_id: u64, bytes: usize, tick: u64, debts: &Debts) -> ExportAttempt {
+        let mut retries = 0;
+        let mut retry_ms = 0;
+        let mut delivered = self.is_up(tick, debts);
+        while !delivered && retries < self.tunables.max_retries {
+            retry_ms += self.tunables.backoff_ms(retries) + self.tunables.attempt_ms;
+            retries += 1;
+            delivered = self.is_up(tick + retry_ms as u64 / MS_PER_TICK, debts);
+        }
+        self.retries += retries as u64;
+        self.retry_ms_total += retry_ms as u64;
+
+        let outcome = if delivered {
+            self.delivered += 1;
+            ExportOutcome::Delivered
+        } else if self.buffered_bytes + bytes <= self.tunables.buffer_cap_bytes {
+            self.buffer.push_back(BufferedExport { job_id, bytes, buffered_tick: tick });
+            self.buffered_bytes += bytes;
+            self.peak_buffered_bytes = self.peak_buffered_bytes.max(self.buffered_bytes);
+            self.buffered += 1;
+            ExportOutcome::Buffered
+        } else {
+            self.dropped += 1;
+            ExportOutcome::Dropped
+        };
+        ExportAttempt { outcome, retries, retry_ms }
+    }
+
+    /// Replay buffered exports, oldest first, up to the per-tick drain rate
+    pub fn drain(&mut self) -> usize {
+        let mut budget = self.tunables.drain_bytes_per_tick;
+        let mut count = 0;
+        while let Some(front) = self.buffer.front() {
+            if front.bytes > budget && count > 0 {
+                break;
+            }
+            budget = budget.saturating_sub(front.bytes);
+            self.buffered_bytes -= front.bytes;
+            self.buffer.pop_front();
+            count += 1;
+        }
+        self.drained += count as u64;
+        count
+    }
+
+    /// Heap held by the offline buffer, payloads included
+    pub fn buffer_bytes(&self) -> usize {
+        self.buffer.capacity() * size_of::<BufferedExport>() + self.buffered_bytes
+    }
+}
+
+pub fn has_http_export(ops: &[Op]) -> bool {
+    ops.iter().any(|op| matches!(op, Op::HttpExport))
+}
+
+pub fn export_sink_system(
+    mut sink: ResMut<ExportSink>,
+    debts: Res<Debts>,
+    clock: Res<SimClock>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    let up = sink.is_up(now_tick, &debts);
+    if up != sink.up {
+        if up {
+            println!("Export sink {} is back; draining {} buffered exports", sink.name, sink.buffer.len());
+        } else {
+            println!("Export sink {} went offline", sink.name);
+        }
+        sink.up = up;
+    }
+    if up {
+        sink.drain();
+    }
+    sink.schedule.retain(|w| w.end_tick > now_tick);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Debt;
+
+    #[test]
+    fn test_backoff_grows_to_cap() {
+        let tunables = ExportSinkTunables::default();
+        assert_eq!(tunables.backoff_ms(0), 50);
+        assert_eq!(tunables.backoff_ms(1), 100);
+        assert_eq!(tunables.backoff_ms(10), 2_000);
+    }
+
+    #[test]
+    fn test_retry_rides_out_short_outage() {
+        let mut sink = ExportSink::new();
+        let debts = Debts::new();
+        assert_eq!(sink.attempt(1, 100, 0, &debts), ExportAttempt { outcome: ExportOutcome::Delivered, retries: 0, retry_ms: 0 });
+
+        // Down for 2 ticks; the first retry lands 55ms later, past the window
+        sink.schedule_outage(10, 32);
+        let attempt = sink.attempt(2, 100, 10, &debts);
+        assert_eq!(attempt.outcome, ExportOutcome::Delivered);
+        assert_eq!((attempt.retries, attempt.retry_ms), (1, 55));
+        assert_eq!(sink.delivered, 2);
+        assert_eq!(sink.retry_ms_total, 55);
+    }
+
+    #[test]
+    fn test_offline_buffer_fills_then_drains() {
+        let mut sink = ExportSink::new();
+        sink.tunables.buffer_cap_bytes = 250;
+        sink.tunables.drain_bytes_per_tick = 100;
+        let mut debts = Debts::new();
+        debts.add_debt(Debt::SinkOffline { sink: HTTP_EXPORT_SINK.to_string(), until_tick: 10_000 });
+        assert!(!sink.is_up(0, &debts));
+
+        let attempt = sink.attempt(1, 100, 0, &debts);
+        assert_eq!(attempt.outcome, ExportOutcome::Buffered);
+        assert_eq!(attempt.retries, 3);
+        assert_eq!(sink.attempt(2, 100, 0, &debts).outcome, ExportOutcome::Buffered);
+        assert_eq!(sink.attempt(3, 100, 0, &debts).outcome, ExportOutcome::Dropped);
+        assert_eq!(sink.buffered_bytes, 200);
+        assert!(sink.buffer_bytes() >= 200);
+
+        assert_eq!(sink.drain(), 1);
+        assert_eq!(sink.drain(), 1);
+        assert_eq!(sink.buffered_bytes, 0);
+        assert_eq!(sink.drained, 2);
+        assert_eq!(sink.peak_buffered_bytes, 200);
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use serde::{Serialize, Deserialize};
-use super::{WorkerReport, FaultKind, Worker, Workyard, Op};
+use super::{WorkerReport, FaultKind, Worker, WorkerHealth, WorkerStateComp, RetryState, Workyard, Op, RetryDecision};
 use crate::corruption::{fault_probability, tick_rng, CorruptionTunables};
 use rand::Rng;
 
@@ -33,8 +33,33 @@ impl FaultKpi {
     }
 }
 
+/// The fault a job takes when the IO parser rejected its input
+pub fn fault_for_parse(kind: colony_io::ParseFaultKind) -> FaultKind {
+    use colony_io::ParseFaultKind::*;
+    match kind {
+        Truncated | BadLength | InvalidEncoding | Checksum => FaultKind::DataSkew,
+        BadHeader | BadStatus => FaultKind::Network,
+        Empty => FaultKind::QueueDrop,
+    }
+}
+
 pub fn fault_inject_on_completion(
-    worker: &Worker,
+    health: &WorkerHealth,
+    yard: &Workyard,
+    op: &Op,
+    global_corruption: f32,
+    bandwidth_util: f32,
+    queue_starvation: f32,
+    tunables: &CorruptionTunables,
+    seed: u64,
+    tick: u64,
+) -> Option<FaultKind> {
+    fault_inject_scaled(health, yard, op, global_corruption, bandwidth_util, queue_starvation, tunables, seed, tick, 1.0)
+}
+
+/// Fault injection with the probability scaled, e.g. for a worker without the op's certification
+pub fn fault_inject_scaled(
+    health: &WorkerHealth,
     yard: &Workyard,
     op: &Op,
     global_corruption: f32,
@@ -43,18 +68,19 @@ pub fn fault_inject_on_completion(
     tunables: &CorruptionTunables,
     seed: u64,
     tick: u64,
+    prob_mult: f32,
 ) -> Option<FaultKind> {
     let heat_frac = yard.heat / yard.heat_cap;
     
     let prob = fault_probability(
         tunables.base_fault_rate,
         global_corruption,
-        worker.corruption,
+        health.corruption,
         heat_frac,
         bandwidth_util,
         queue_starvation,
         tunables,
-    );
+    ) * prob_mult;
     
     let mut rng = tick_rng(seed, tick);
     if rng.gen::<f32>() < prob {
@@ -63,7 +89,7 @@ pub fn fault_inject_on_completion(
             (FaultKind::Transient, 0.60),
             (FaultKind::DataSkew, 0.20),
             (FaultKind::QueueDrop, 0.15),
-            (FaultKind::StickyConfig, 0.05 + worker.corruption * 0.1), // More likely with high corruption
+            (FaultKind::StickyConfig, 0.05 + health.corruption * 0.1), // More likely with high corruption
         ];
         
         let total_weight: f32 = fault_weights.iter().map(|(_, w)| w).sum();
@@ -84,32 +110,56 @@ pub fn fault_inject_on_completion(
     }
 }
 
+/// Apply a fault to the worker and report it. `job_retry` is what the job's pipeline retry
+/// policy decided; without one, transient faults spend the worker's own retry budget.
+/// Returns the kind reported, QueueDrop when the job is lost.
 pub fn handle_fault(
     fault: FaultKind,
-    worker: &mut Worker,
+    job_retry: RetryDecision,
+    worker: &Worker,
+    state: &mut WorkerStateComp,
+    health: &mut WorkerHealth,
+    retry: &mut RetryState,
     job_id: u64,
     op: Op,
     tunables: &CorruptionTunables,
     report_writer: &mut EventWriter<WorkerReport>,
-) {
+) -> FaultKind {
+    // A job the pipeline gives up on is lost, whatever the fault did to the worker
+    let mut reported = match job_retry {
+        RetryDecision::Exhausted | RetryDecision::NotRetryable => FaultKind::QueueDrop,
+        _ => fault,
+    };
     match fault {
+        FaultKind::Transient if job_retry != RetryDecision::NoPolicy => {
+            // The pipeline's policy owns the retry; the worker's budget is left alone
+            report_writer.send(WorkerReport::Fault {
+                worker_id: worker.id,
+                job_id,
+                op,
+                kind: reported,
+            });
+        }
         FaultKind::Transient => {
             // Retry with backoff
-            if worker.retry.max_retries > 0 {
-                worker.retry.max_retries -= 1;
+            if retry.policy.max_retries > 0 {
+                retry.policy.max_retries -= 1;
                 // In a real implementation, we'd schedule a retry with exponential backoff
                 // For now, just emit the fault and let the system handle it
                 report_writer.send(WorkerReport::Fault {
                     worker_id: worker.id,
+                    job_id,
                     op,
-                    kind: fault,
+                    kind: reported,
                 });
             } else {
                 // Max retries exceeded, treat as queue drop
+                reported = FaultKind::QueueDrop;
                 report_writer.send(WorkerReport::Fault {
                     worker_id: worker.id,
+                    job_id,
                     op,
-                    kind: FaultKind::QueueDrop,
+                    kind: reported,
                 });
             }
         }
@@ -117,84 +167,93 @@ pub fn handle_fault(
             // Force re-run, no worker quarantine
             report_writer.send(WorkerReport::Fault {
                 worker_id: worker.id,
+                job_id,
                 op,
-                kind: fault,
+                kind: reported,
             });
         }
         FaultKind::StickyConfig => {
             // Quarantine worker
-            worker.state = super::WorkerState::Recovering;
-            worker.sticky_faults += 1;
+            state.0 = super::WorkerState::Recovering;
+            health.sticky_faults += 1;
             report_writer.send(WorkerReport::Fault {
                 worker_id: worker.id,
+                job_id,
                 op,
-                kind: fault,
+                kind: reported,
             });
         }
         FaultKind::QueueDrop => {
             // Job dropped, deadline likely missed
             report_writer.send(WorkerReport::Fault {
                 worker_id: worker.id,
+                job_id,
                 op,
-                kind: fault,
+                kind: reported,
             });
         }
         FaultKind::Thermal => {
             // Thermal fault - worker needs cooling
-            worker.state = super::WorkerState::Recovering;
+            state.0 = super::WorkerState::Recovering;
             report_writer.send(WorkerReport::Fault {
                 worker_id: worker.id,
+                job_id,
                 op,
-                kind: fault,
+                kind: reported,
             });
         }
         FaultKind::Power => {
             // Power fault - worker needs power reset
-            worker.state = super::WorkerState::Recovering;
+            state.0 = super::WorkerState::Recovering;
             report_writer.send(WorkerReport::Fault {
                 worker_id: worker.id,
+                job_id,
                 op,
-                kind: fault,
+                kind: reported,
             });
         }
         FaultKind::Corruption => {
             // Corruption fault - data integrity issue
-            worker.state = super::WorkerState::Recovering;
+            state.0 = super::WorkerState::Recovering;
             report_writer.send(WorkerReport::Fault {
                 worker_id: worker.id,
+                job_id,
                 op,
-                kind: fault,
+                kind: reported,
             });
         }
         FaultKind::Network => {
             // Network fault - connectivity issue
             report_writer.send(WorkerReport::Fault {
                 worker_id: worker.id,
+                job_id,
                 op,
-                kind: fault,
+                kind: reported,
             });
         }
         FaultKind::Hardware => {
             // Hardware fault - physical component failure
-            worker.state = super::WorkerState::Recovering;
+            state.0 = super::WorkerState::Recovering;
             report_writer.send(WorkerReport::Fault {
                 worker_id: worker.id,
+                job_id,
                 op,
-                kind: fault,
+                kind: reported,
             });
         }
     }
+    reported
 }
 
 pub fn update_fault_kpis(
     mut kpis: ResMut<FaultKpi>,
-    workers: Query<&Worker>,
+    workers: Query<&WorkerStateComp>,
     mut report_reader: EventReader<WorkerReport>,
 ) {
     // Count sticky workers
     kpis.sticky_workers = workers
         .iter()
-        .filter(|w| w.state == super::WorkerState::Recovering)
+        .filter(|state| state.0 == super::WorkerState::Recovering)
         .count() as u32;
     
     // Process fault reports
@@ -224,20 +283,11 @@ pub fn update_fault_kpis(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::super::{WorkyardKind, WorkClass, WorkerState};
+    use super::super::WorkyardKind;
 
-    fn create_test_worker() -> Worker {
-        Worker {
-            id: 1,
-            class: WorkClass::Cpu,
-            skill_cpu: 1.0,
-            skill_gpu: 0.0,
-            skill_io: 0.0,
-            discipline: 1.0,
-            focus: 1.0,
+    fn create_test_health() -> WorkerHealth {
+        WorkerHealth {
             corruption: 0.1,
-            state: WorkerState::Idle,
-            retry: RetryPolicy::default(),
             sticky_faults: 0,
         }
     }
@@ -256,13 +306,13 @@ mod tests {
 
     #[test]
     fn test_fault_injection_high_stress() {
-        let worker = create_test_worker();
+        let health = create_test_health();
         let yard = create_test_yard();
         let tunables = CorruptionTunables::default();
         
         // High stress scenario
         let fault = fault_inject_on_completion(
-            &worker,
+            &health,
             &yard,
             &Op::Decode,
             0.5, // high global corruption
@@ -279,13 +329,13 @@ mod tests {
 
     #[test]
     fn test_fault_injection_low_stress() {
-        let worker = create_test_worker();
+        let health = create_test_health();
         let yard = create_test_yard();
         let tunables = CorruptionTunables::default();
         
         // Low stress scenario
         let fault = fault_inject_on_completion(
-            &worker,
+            &health,
             &yard,
             &Op::Decode,
             0.0, // no global corruption
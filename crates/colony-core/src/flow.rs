@@ -0,0 +1,332 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use colony_io::{FlowTag, IoSourceKind};
+
+const MS_PER_TICK: u64 = 16;
+
+/// Latency samples kept per flow for the p95
+const LATENCY_WINDOW: usize = 256;
+
+/// Where a tracked packet ended up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowOutcome {
+    Delivered,
+    Buffered, // held in the export sink's offline buffer
+    Dropped,  // export or archive had nowhere to put it
+    Faulted,
+}
+
+impl FlowOutcome {
+    fn node(&self) -> &'static str {
+        match self {
+            FlowOutcome::Delivered => "delivered",
+            FlowOutcome::Buffered => "buffered",
+            FlowOutcome::Dropped => "dropped",
+            FlowOutcome::Faulted => "faulted",
+        }
+    }
+}
+
+/// A packet between entering the queue and being dispatched
+#[derive(Debug, Clone, Copy)]
+struct InFlight {
+    ingress_ms: u32, // source emit to enqueue, wall clock
+}
+
+/// End-to-end latency split by stage, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct StageLatency {
+    pub ingress_ms: f32, // emitted by the source until the job was queued
+    pub queue_ms: f32,   // waiting for a worker
+    pub service_ms: f32, // running the pipeline, retries and storage waits included
+}
+
+impl StageLatency {
+    pub fn total_ms(&self) -> f32 {
+        self.ingress_ms + self.queue_ms + self.service_ms
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowStats {
+    pub source: IoSourceKind,
+    pub seen: u64,         // packets that reached the sim
+    pub lost: u64,         // sequence gaps: lost before parsing
+    pub parse_faults: u64,
+    pub delivered: u64,
+    pub buffered: u64,
+    pub dropped: u64,
+    pub faulted: u64,
+    pub max_seq: u64,
+    pub stage_sum: StageLatency,
+    pub max_latency_ms: f32,
+    #[serde(skip)]
+    recent: VecDeque<f32>,
+}
+
+impl FlowStats {
+    fn new(source: IoSourceKind) -> Self {
+        Self {
+            source,
+            seen: 0,
+            lost: 0,
+            parse_faults: 0,
+            delivered: 0,
+            buffered: 0,
+            dropped: 0,
+            faulted: 0,
+            max_seq: 0,
+            stage_sum: StageLatency::default(),
+            max_latency_ms: 0.0,
+            recent: VecDeque::new(),
+        }
+    }
+
+    pub fn finished(&self) -> u64 {
+        self.delivered + self.buffered + self.dropped + self.faulted
+    }
+
+    /// Share of emitted packets that never made it out: source loss, drops and faults
+    pub fn loss_rate(&self) -> f32 {
+        let emitted = self.seen + self.lost;
+        if emitted == 0 {
+            0.0
+        } else {
+            (self.lost + self.dropped + self.faulted) as f32 / emitted as f32
+        }
+    }
+
+    pub fn avg_stages(&self) -> StageLatency {
+        let n = self.finished().max(1) as f32;
+        StageLatency {
+            ingress_ms: self.stage_sum.ingress_ms / n,
+            queue_ms: self.stage_sum.queue_ms / n,
+            service_ms: self.stage_sum.service_ms / n,
+        }
+    }
+
+    pub fn p95_latency_ms(&self) -> f32 {
+        if self.recent.is_empty() {
+            return 0.0;
+        }
+        let mut sorted: Vec<f32> = self.recent.iter().copied().collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let idx = ((sorted.len() as f32 * 0.95) as usize).min(sorted.len() - 1);
+        sorted[idx]
+    }
+}
+
+/// One row of `/metrics/flows`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowReport {
+    pub flow: u32,
+    pub source: IoSourceKind,
+    pub seen: u64,
+    pub lost: u64,
+    pub parse_faults: u64,
+    pub delivered: u64,
+    pub buffered: u64,
+    pub dropped: u64,
+    pub faulted: u64,
+    pub in_flight: u64,
+    pub loss_rate: f32,
+    pub avg_latency: StageLatency,
+    pub avg_latency_ms: f32,
+    pub p95_latency_ms: f32,
+    pub max_latency_ms: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SankeyLink {
+    pub source: usize, // index into `nodes`
+    pub target: usize,
+    pub value: u64,
+}
+
+/// Packet counts between pipeline stages, laid out for a Sankey chart
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlowSankey {
+    pub nodes: Vec<String>,
+    pub links: Vec<SankeyLink>,
+}
+
+/// Follows flow-tagged packets from the IO source to their final outcome
+#[derive(Resource, Debug, Clone, Default)]
+pub struct FlowTracker {
+    pub flows: BTreeMap<u32, FlowStats>,
+    pub links: BTreeMap<(String, String), u64>,
+    in_flight: HashMap<(u32, u64), InFlight>,
+}
+
+impl FlowTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn link(&mut self, from: &str, to: &str, n: u64) {
+        if n > 0 {
+            *self.links.entry((from.to_string(), to.to_string())).or_default() += n;
+        }
+    }
+
+    /// A tagged packet was parsed (or rejected) and its job queued
+    pub fn ingress(&mut self, tag: FlowTag, parse_faulted: bool, now_ns: u64) {
+        let source = match tag.source {
+            IoSourceKind::Udp => "udp",
+            IoSourceKind::Http => "http",
+        };
+        let stats = self.flows.entry(tag.flow).or_insert_with(|| FlowStats::new(tag.source));
+        // Sequence numbers the source burned without sending
+        let gap = if stats.seen == 0 {
+            tag.seq
+        } else {
+            tag.seq.saturating_sub(stats.max_seq + 1)
+        };
+        stats.lost += gap;
+        stats.max_seq = stats.max_seq.max(tag.seq);
+        stats.seen += 1;
+        if parse_faulted {
+            stats.parse_faults += 1;
+        }
+
+        self.link(source, "lost", gap);
+        self.link(source, if parse_faulted { "parse_fault" } else { "parsed" }, 1);
+        self.link(if parse_faulted { "parse_fault" } else { "parsed" }, "queued", 1);
+
+        let ingress_ms = (now_ns.saturating_sub(tag.emitted_ns) / 1_000_000) as u32;
+        self.in_flight.insert((tag.flow, tag.seq), InFlight { ingress_ms });
+    }
+
+    /// A tagged job left the queue; `service_ms` covers everything the worker spent on it
+    pub fn finish(&mut self, tag: FlowTag, enq_tick: u64, dispatch_tick: u64, service_ms: u32, outcome: FlowOutcome) {
+        let ingress_ms = self.in_flight.remove(&(tag.flow, tag.seq)).map(|f| f.ingress_ms).unwrap_or(0);
+        let stages = StageLatency {
+            ingress_ms: ingress_ms as f32,
+            queue_ms: (dispatch_tick.saturating_sub(enq_tick) * MS_PER_TICK) as f32,
+            service_ms: service_ms as f32,
+        };
+        let stats = self.flows.entry(tag.flow).or_insert_with(|| FlowStats::new(tag.source));
+        match outcome {
+            FlowOutcome::Delivered => stats.delivered += 1,
+            FlowOutcome::Buffered => stats.buffered += 1,
+            FlowOutcome::Dropped => stats.dropped += 1,
+            FlowOutcome::Faulted => stats.faulted += 1,
+        }
+        stats.stage_sum.ingress_ms += stages.ingress_ms;
+        stats.stage_sum.queue_ms += stages.queue_ms;
+        stats.stage_sum.service_ms += stages.service_ms;
+        stats.max_latency_ms = stats.max_latency_ms.max(stages.total_ms());
+        stats.recent.push_back(stages.total_ms());
+        if stats.recent.len() > LATENCY_WINDOW {
+            stats.recent.pop_front();
+        }
+
+        self.link("queued", "dispatched", 1);
+        self.link("dispatched", outcome.node(), 1);
+    }
+
+    pub fn in_flight(&self, flow: u32) -> u64 {
+        self.in_flight.keys().filter(|(f, _)| *f == flow).count() as u64
+    }
+
+    pub fn reports(&self) -> Vec<FlowReport> {
+        self.flows
+            .iter()
+            .map(|(&flow, s)| {
+                let avg = s.avg_stages();
+                FlowReport {
+                    flow,
+                    source: s.source,
+                    seen: s.seen,
+                    lost: s.lost,
+                    parse_faults: s.parse_faults,
+                    delivered: s.delivered,
+                    buffered: s.buffered,
+                    dropped: s.dropped,
+                    faulted: s.faulted,
+                    in_flight: self.in_flight(flow),
+                    loss_rate: s.loss_rate(),
+                    avg_latency: avg,
+                    avg_latency_ms: avg.total_ms(),
+                    p95_latency_ms: s.p95_latency_ms(),
+                    max_latency_ms: s.max_latency_ms,
+                }
+            })
+            .collect()
+    }
+
+    pub fn sankey(&self) -> FlowSankey {
+        let mut sankey = FlowSankey::default();
+        let index = |nodes: &mut Vec<String>, name: &str| match nodes.iter().position(|n| n == name) {
+            Some(i) => i,
+            None => {
+                nodes.push(name.to_string());
+                nodes.len() - 1
+            }
+        };
+        for ((from, to), &value) in &self.links {
+            let source = index(&mut sankey.nodes, from);
+            let target = index(&mut sankey.nodes, to);
+            sankey.links.push(SankeyLink { source, target, value });
+        }
+        sankey
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(seq: u64) -> FlowTag {
+        FlowTag { flow: 7, source: IoSourceKind::Udp, seq, emitted_ns: 0 }
+    }
+
+    #[test]
+    fn test_latency_adds_up_every_stage() {
+        let mut tracker = FlowTracker::new();
+        tracker.ingress(tag(0), false, 4_000_000);
+        assert_eq!(tracker.in_flight(7), 1);
+
+        tracker.finish(tag(0), 10, 12, 20, FlowOutcome::Delivered);
+        let report = &tracker.reports()[0];
+        assert_eq!(report.avg_latency, StageLatency { ingress_ms: 4.0, queue_ms: 32.0, service_ms: 20.0 });
+        assert_eq!(report.p95_latency_ms, 56.0);
+        assert_eq!(report.in_flight, 0);
+    }
+
+    #[test]
+    fn test_loss_counts_gaps_drops_and_faults() {
+        let mut tracker = FlowTracker::new();
+        tracker.ingress(tag(0), false, 0);
+        tracker.ingress(tag(3), true, 0); // 1 and 2 never arrived
+        tracker.ingress(tag(4), false, 0);
+        tracker.finish(tag(0), 0, 0, 1, FlowOutcome::Delivered);
+        tracker.finish(tag(3), 0, 0, 1, FlowOutcome::Faulted);
+        tracker.finish(tag(4), 0, 0, 1, FlowOutcome::Dropped);
+
+        let stats = &tracker.flows[&7];
+        assert_eq!((stats.seen, stats.lost, stats.parse_faults), (3, 2, 1));
+        assert_eq!(stats.loss_rate(), 0.8);
+    }
+
+    #[test]
+    fn test_sankey_links_stages() {
+        let mut tracker = FlowTracker::new();
+        tracker.ingress(tag(1), false, 0);
+        tracker.ingress(tag(2), true, 0);
+        tracker.finish(tag(1), 0, 0, 1, FlowOutcome::Delivered);
+
+        let sankey = tracker.sankey();
+        let value = |from: &str, to: &str| {
+            let s = sankey.nodes.iter().position(|n| n == from).unwrap();
+            let t = sankey.nodes.iter().position(|n| n == to).unwrap();
+            sankey.links.iter().find(|l| l.source == s && l.target == t).map(|l| l.value)
+        };
+        assert_eq!(value("udp", "lost"), Some(1));
+        assert_eq!(value("udp", "parsed"), Some(1));
+        assert_eq!(value("parse_fault", "queued"), Some(1));
+        assert_eq!(value("dispatched", "delivered"), Some(1));
+    }
+}
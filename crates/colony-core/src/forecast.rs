@@ -0,0 +1,421 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use anyhow::Result;
+use crate::{ArrivalLog, Colony, JobQueue, SimClock, Workyard, WorkyardKind, RunCommand, plan_maintenance, yard_node_id};
+
+/// Sim seconds per tick
+const TICK_SECS: f32 = 0.016;
+/// A forecast packet rate this much over the current one counts as a surge
+const SURGE_FACTOR: f32 = 1.2;
+/// Bandwidth utilisation above which a surge is worth more bandwidth
+const BANDWIDTH_PRESSURE: f32 = 0.7;
+/// Queue depth below which growth isn't worth mentioning
+const QUEUE_DEPTH_FLOOR: f32 = 50.0;
+
+/// `[forecast]` table in the game config, also set through `PUT /forecast/config`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ForecastConfig {
+    pub window_ticks: u64,       // KPIs are averaged over windows of this many ticks, 1 sim minute by default
+    pub horizon_windows: usize,  // how far ahead to forecast, 1 sim hour by default
+    pub alpha: f32,              // level smoothing, 0..=1
+    pub beta: f32,               // trend smoothing; 0 leaves a plain EWMA
+    pub gamma: f32,              // seasonal smoothing
+    pub season_windows: usize,   // season length, 1 sim day by default; 0 turns seasonality off
+}
+
+impl Default for ForecastConfig {
+    fn default() -> Self {
+        Self {
+            window_ticks: 3750,
+            horizon_windows: 60,
+            alpha: 0.3,
+            beta: 0.05,
+            gamma: 0.1,
+            season_windows: 1440,
+        }
+    }
+}
+
+impl ForecastConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.window_ticks == 0 {
+            return Err(anyhow::anyhow!("window_ticks must be at least 1"));
+        }
+        if self.horizon_windows == 0 {
+            return Err(anyhow::anyhow!("horizon_windows must be at least 1"));
+        }
+        for (name, value) in [("alpha", self.alpha), ("beta", self.beta), ("gamma", self.gamma)] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(anyhow::anyhow!("{} {} is outside 0..=1", name, value));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn horizon_minutes(&self) -> f32 {
+        self.window_minutes() * self.horizon_windows as f32
+    }
+
+    fn window_minutes(&self) -> f32 {
+        self.window_ticks as f32 * TICK_SECS / 60.0
+    }
+}
+
+/// Additive Holt-Winters smoothing over one KPI. Until a full season has been seen it forecasts
+/// with level and trend alone (Holt); with `beta` at 0 that is an EWMA.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HoltWinters {
+    pub level: Option<f32>,
+    pub trend: f32,
+    pub season: Vec<f32>,
+    pub observed: u64,
+}
+
+impl HoltWinters {
+    fn seasonal(&self, config: &ForecastConfig, offset: u64) -> f32 {
+        if config.season_windows == 0 || self.observed < config.season_windows as u64 {
+            return 0.0;
+        }
+        self.season.get(((self.observed + offset) % config.season_windows as u64) as usize).copied().unwrap_or(0.0)
+    }
+
+    pub fn observe(&mut self, config: &ForecastConfig, x: f32) {
+        if self.season.len() != config.season_windows {
+            self.season = vec![0.0; config.season_windows];
+        }
+        let s = self.seasonal(config, 0);
+        let level = match self.level {
+            None => x,
+            Some(prev) => {
+                let level = config.alpha * (x - s) + (1.0 - config.alpha) * (prev + self.trend);
+                self.trend = config.beta * (level - prev) + (1.0 - config.beta) * self.trend;
+                level
+            }
+        };
+        self.level = Some(level);
+        if config.season_windows > 0 {
+            let i = (self.observed % config.season_windows as u64) as usize;
+            self.season[i] = config.gamma * (x - level) + (1.0 - config.gamma) * self.season[i];
+        }
+        self.observed += 1;
+    }
+
+    /// Value expected `steps` windows ahead, never below zero
+    pub fn predict(&self, config: &ForecastConfig, steps: usize) -> Option<f32> {
+        let level = self.level?;
+        Some((level + steps as f32 * self.trend + self.seasonal(config, steps.saturating_sub(1) as u64)).max(0.0))
+    }
+}
+
+/// One KPI: its model and the window being filled
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KpiSeries {
+    pub model: HoltWinters,
+    pub last: f32,        // mean of the last closed window
+    window_sum: f64,
+    window_samples: u32,
+}
+
+impl KpiSeries {
+    fn sample(&mut self, value: f32) {
+        self.window_sum += value as f64;
+        self.window_samples += 1;
+    }
+
+    fn close(&mut self, config: &ForecastConfig) {
+        if self.window_samples == 0 {
+            return;
+        }
+        self.last = (self.window_sum / self.window_samples as f64) as f32;
+        self.model.observe(config, self.last);
+        self.window_sum = 0.0;
+        self.window_samples = 0;
+    }
+
+    fn forecast(&self, config: &ForecastConfig) -> SeriesForecast {
+        let points: Vec<f32> = (1..=config.horizon_windows)
+            .map(|step| self.model.predict(config, step).unwrap_or(self.last))
+            .collect();
+        let (peak_at, peak) = points.iter().copied().enumerate()
+            .fold((0, self.last), |best, (i, v)| if v > best.1 { (i + 1, v) } else { best });
+        SeriesForecast {
+            current: self.last,
+            points,
+            peak,
+            peak_in_minutes: peak_at as f32 * config.window_minutes(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YardHeatSeries {
+    pub kind: WorkyardKind,
+    pub heat_cap: f32,
+    pub heat: KpiSeries,
+}
+
+/// Forecast of one KPI over the horizon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesForecast {
+    pub current: f32,
+    pub points: Vec<f32>,      // one per window, starting one window ahead
+    pub peak: f32,             // highest of `current` and `points`
+    pub peak_in_minutes: f32,  // 0 when nothing ahead beats `current`
+}
+
+impl SeriesForecast {
+    /// Sim minutes until the forecast first reaches `threshold`
+    pub fn minutes_until(&self, threshold: f32, window_minutes: f32) -> Option<f32> {
+        if self.current >= threshold {
+            return Some(0.0);
+        }
+        self.points.iter().position(|&v| v >= threshold).map(|i| (i + 1) as f32 * window_minutes)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YardHeatForecast {
+    pub kind: WorkyardKind,
+    pub heat_cap: f32,
+    pub heat: SeriesForecast,
+}
+
+/// The forecast served at `GET /forecast`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForecastReport {
+    pub window_minutes: f32,
+    pub horizon_minutes: f32,
+    pub windows_observed: u64,
+    pub packet_rate: SeriesForecast, // job arrivals per sim second
+    pub queue_depth: SeriesForecast,
+    pub heat: SeriesForecast,        // hottest yard
+    pub yards: BTreeMap<String, YardHeatForecast>,
+}
+
+/// A recommended action and the forecast behind it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Advice {
+    pub metric: String,
+    pub reason: String,
+    pub eta_minutes: Option<f32>,
+    pub command: Option<RunCommand>, // run it through a run spec or timed command; None is a heads-up only
+}
+
+/// Short-term demand forecasts for packet rates, queue depth and heat, refreshed once per window
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DemandForecast {
+    pub config: ForecastConfig,
+    pub packet_rate: KpiSeries,
+    pub queue_depth: KpiSeries,
+    pub heat: KpiSeries,
+    pub yards: BTreeMap<String, YardHeatSeries>,
+    pub windows_observed: u64,
+    window_ticks_seen: u64,
+    window_arrivals: u64,
+}
+
+impl DemandForecast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Swap the settings; the models start over since their windows no longer line up
+    pub fn configure(&mut self, config: &ForecastConfig) -> Result<()> {
+        config.validate()?;
+        *self = Self { config: config.clone(), ..Self::default() };
+        Ok(())
+    }
+
+    /// Feed one tick; closes the window once it has `window_ticks` of them
+    pub fn record_tick(&mut self, arrivals: usize, queue_depth: usize, yard_heat: &[(String, WorkyardKind, f32, f32)]) {
+        self.window_arrivals += arrivals as u64;
+        self.queue_depth.sample(queue_depth as f32);
+        let hottest = yard_heat.iter().map(|(_, _, heat, _)| *heat).fold(0.0, f32::max);
+        self.heat.sample(hottest);
+        for (id, kind, heat, heat_cap) in yard_heat {
+            let yard = self.yards.entry(id.clone()).or_insert_with(|| YardHeatSeries {
+                kind: kind.clone(),
+                heat_cap: *heat_cap,
+                heat: KpiSeries::default(),
+            });
+            yard.heat_cap = *heat_cap;
+            yard.heat.sample(*heat);
+        }
+        self.window_ticks_seen += 1;
+        if self.window_ticks_seen >= self.config.window_ticks {
+            self.close_window();
+        }
+    }
+
+    fn close_window(&mut self) {
+        let secs = self.window_ticks_seen as f32 * TICK_SECS;
+        self.packet_rate.sample(self.window_arrivals as f32 / secs);
+        for series in [&mut self.packet_rate, &mut self.queue_depth, &mut self.heat] {
+            series.close(&self.config);
+        }
+        for yard in self.yards.values_mut() {
+            yard.heat.close(&self.config);
+        }
+        self.windows_observed += 1;
+        self.window_ticks_seen = 0;
+        self.window_arrivals = 0;
+    }
+
+    pub fn report(&self) -> ForecastReport {
+        ForecastReport {
+            window_minutes: self.config.window_minutes(),
+            horizon_minutes: self.config.horizon_minutes(),
+            windows_observed: self.windows_observed,
+            packet_rate: self.packet_rate.forecast(&self.config),
+            queue_depth: self.queue_depth.forecast(&self.config),
+            heat: self.heat.forecast(&self.config),
+            yards: self.yards.iter()
+                .map(|(id, y)| (id.clone(), YardHeatForecast {
+                    kind: y.kind.clone(),
+                    heat_cap: y.heat_cap,
+                    heat: y.heat.forecast(&self.config),
+                }))
+                .collect(),
+        }
+    }
+}
+
+/// Recommendations backed by the forecast: maintenance for yards heading into thermal throttle,
+/// more bandwidth ahead of a packet surge on a busy bus, and a warning when the queue is building
+pub fn advise(report: &ForecastReport, colony: &Colony) -> Vec<Advice> {
+    let mut advice: Vec<Advice> = plan_maintenance(report, colony.tunables.thermal_throttle_knee)
+        .into_iter()
+        .map(|plan| Advice {
+            metric: "heat".to_string(),
+            reason: plan.reason,
+            eta_minutes: Some(plan.eta_minutes),
+            command: Some(RunCommand::EnqueueMaintenance { yard: plan.kind }),
+        })
+        .collect();
+
+    let rate = &report.packet_rate;
+    if rate.current > 0.0 && rate.peak > rate.current * SURGE_FACTOR && colony.meters.bandwidth_util > BANDWIDTH_PRESSURE {
+        let gbps = colony.bandwidth_total_gbps * rate.peak / rate.current;
+        advice.push(Advice {
+            metric: "packet_rate".to_string(),
+            reason: format!(
+                "Packet rate forecast to rise from {:.1}/s to {:.1}/s in {:.0} min with the bus already {:.0}% busy",
+                rate.current, rate.peak, rate.peak_in_minutes, colony.meters.bandwidth_util * 100.0
+            ),
+            eta_minutes: Some(rate.peak_in_minutes),
+            command: Some(RunCommand::SetBandwidth { gbps }),
+        });
+    }
+
+    let depth = &report.queue_depth;
+    if depth.peak >= QUEUE_DEPTH_FLOOR && depth.peak > depth.current * 2.0 {
+        advice.push(Advice {
+            metric: "queue_depth".to_string(),
+            reason: format!(
+                "Queue depth forecast to grow from {:.0} to {:.0} jobs in {:.0} min; deadlines will start slipping",
+                depth.current, depth.peak, depth.peak_in_minutes
+            ),
+            eta_minutes: Some(depth.peak_in_minutes),
+            command: None,
+        });
+    }
+    advice
+}
+
+/// Sample arrivals, queue depth and yard heat once per tick
+pub fn forecast_system(
+    mut forecast: ResMut<DemandForecast>,
+    jobq: Res<JobQueue>,
+    arrivals: Res<ArrivalLog>,
+    yards: Query<(Entity, &Workyard)>,
+    clock: Res<SimClock>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    let arrived = arrivals.arrivals.iter().rev().take_while(|a| a.tick >= now_tick).count();
+    let yard_heat: Vec<(String, WorkyardKind, f32, f32)> = yards.iter()
+        .map(|(e, y)| (yard_node_id(e), y.kind.clone(), y.heat, y.heat_cap))
+        .collect();
+    forecast.record_tick(arrived, jobq.len(), &yard_heat);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(season_windows: usize) -> ForecastConfig {
+        ForecastConfig { window_ticks: 10, horizon_windows: 6, season_windows, ..ForecastConfig::default() }
+    }
+
+    #[test]
+    fn test_holt_follows_a_trend_and_ewma_stays_flat() {
+        let holt = ForecastConfig { alpha: 0.5, beta: 0.5, ..config(0) };
+        let mut model = HoltWinters::default();
+        for x in 0..40 {
+            model.observe(&holt, 10.0 + x as f32);
+        }
+        // Next value is 50; an hour of windows ahead keeps climbing
+        assert!((model.predict(&holt, 1).unwrap() - 50.0).abs() < 0.5);
+        assert!((model.predict(&holt, 6).unwrap() - 55.0).abs() < 0.5);
+
+        let ewma = ForecastConfig { beta: 0.0, ..config(0) };
+        let mut model = HoltWinters::default();
+        for x in [5.0, 5.0, 5.0] {
+            model.observe(&ewma, x);
+        }
+        assert_eq!(model.predict(&ewma, 6), Some(5.0));
+        assert_eq!(HoltWinters::default().predict(&ewma, 1), None);
+    }
+
+    #[test]
+    fn test_season_is_learned() {
+        let seasonal = ForecastConfig { alpha: 0.2, beta: 0.0, gamma: 0.5, ..config(4) };
+        let mut model = HoltWinters::default();
+        let pattern = [10.0, 10.0, 30.0, 10.0];
+        for i in 0..200 {
+            model.observe(&seasonal, pattern[i % 4]);
+        }
+        // 200 windows in, the next ones are positions 0, 1, 2 of the pattern
+        let ahead: Vec<f32> = (1..=3).map(|s| model.predict(&seasonal, s).unwrap()).collect();
+        assert!(ahead[2] > ahead[0] + 15.0, "{:?}", ahead);
+    }
+
+    #[test]
+    fn test_windows_close_and_maintenance_is_advised() {
+        let mut forecast = DemandForecast::new();
+        forecast.configure(&ForecastConfig { alpha: 0.5, beta: 0.5, ..config(0) }).unwrap();
+        // A GPU yard warming by 2 degrees a window, 10 arrivals per window
+        for window in 0..20 {
+            for tick in 0..10 {
+                let heat = 40.0 + window as f32 * 2.0;
+                forecast.record_tick(if tick == 0 { 10 } else { 0 }, 5, &[("yard:7".into(), WorkyardKind::GpuFarm, heat, 100.0)]);
+            }
+        }
+        assert_eq!(forecast.windows_observed, 20);
+        let report = forecast.report();
+        assert!((report.packet_rate.current - 10.0 / 0.16).abs() < 1e-3);
+        assert_eq!(report.queue_depth.current, 5.0);
+        let yard = &report.yards["yard:7"];
+        assert_eq!(yard.heat.current, 78.0);
+
+        let colony = Colony {
+            power_cap_kw: 1000.0,
+            bandwidth_total_gbps: 32.0,
+            corruption_field: 0.0,
+            target_uptime_days: 365,
+            meters: crate::GlobalMeters::new(),
+            tunables: crate::ResourceTunables::default(),
+            corruption_tun: crate::CorruptionTunables::default(),
+            seeds: Default::default(),
+            seed: 42,
+        };
+        // Heading for the 85 degree knee in about 4 windows
+        let advice = advise(&report, &colony);
+        assert_eq!(advice.len(), 1);
+        assert!(matches!(advice[0].command, Some(RunCommand::EnqueueMaintenance { yard: WorkyardKind::GpuFarm })));
+        assert!(advice[0].eta_minutes.unwrap() > 0.0);
+
+        assert!(forecast.configure(&ForecastConfig { alpha: 1.5, ..config(0) }).is_err());
+    }
+}
@@ -33,6 +33,8 @@ pub struct VictoryRules {
     pub min_deadline_hit_pct: f32,       // e.g., 99.5
     pub max_corruption_field: f32,       // e.g., 0.35
     pub observation_window_days: u32,    // rolling window for SLA verification
+    #[serde(default)]
+    pub max_carbon_kg_per_period: Option<f32>, // green objective: carbon per SLA period, see EnergyLedger
 }
 
 impl Default for VictoryRules {
@@ -42,6 +44,7 @@ impl Default for VictoryRules {
             min_deadline_hit_pct: 99.5,
             max_corruption_field: 0.35,
             observation_window_days: 7,
+            max_carbon_kg_per_period: None,
         }
     }
 }
@@ -79,6 +82,30 @@ pub struct Scenario {
     pub start_tunables: Option<serde_json::Value>, // override knobs (power, heat, gpu, corruption, etc.)
     pub enabled_pipelines: Option<Vec<String>>,    // subset for small starts
     pub enabled_events: Option<Vec<String>>,       // restrict Black Swans
+    #[serde(default)]
+    pub seeds: super::SeedOverrides,               // [seeds] table: pin faults/blackswan/io/worker_gen
+    #[serde(default = "default_gpu")]
+    pub gpu: bool,                                 // false starts the colony without a GPU farm
+    #[serde(default)]
+    pub io_channels: Vec<colony_io::IoChannelSpec>, // [[io_channels]]: registered source -> parser -> pipeline
+    #[serde(default)]
+    pub class_reservations: Vec<super::ClassReservation>, // [[class_reservations]]: yard slots held for other job classes
+    #[serde(default)]
+    pub op_limits: Vec<super::OpLimit>,            // [[op_limits]]: ops only so many jobs may run at once
+}
+
+fn default_gpu() -> bool { true }
+
+impl Scenario {
+    /// Sub-plugins this scenario runs; a colony without a GPU farm leaves GPU dispatch out
+    pub fn plugin_config(&self) -> super::ColonyPluginConfig {
+        let config = super::ColonyPluginConfig::default();
+        if self.gpu {
+            config
+        } else {
+            config.without(super::ColonySet::Gpu)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +113,8 @@ pub struct GameSetup {
     pub scenario: Scenario,
     pub mods: Vec<String>,               // loaded mod IDs
     pub tick_scale: String,              // "RealTime" | "Seconds:1" | "Days:1" | "Years:1..10"
+    #[serde(default)]
+    pub mode: super::GameMode,           // creative saves are marked so their scores aren't compared
 }
 
 impl GameSetup {
@@ -94,10 +123,28 @@ impl GameSetup {
             scenario,
             mods: vec!["vanilla".to_string()],
             tick_scale: "RealTime".to_string(),
+            mode: super::GameMode::Standard,
+        }
+    }
+
+    /// The clock scale `tick_scale` names, e.g. "Seconds:1"; a bare unit counts as 1
+    pub fn parse_tick_scale(&self) -> anyhow::Result<super::TickScale> {
+        let (unit, count) = self.tick_scale.split_once(':').unwrap_or((self.tick_scale.as_str(), "1"));
+        let count: u64 = count.parse().map_err(|_| anyhow::anyhow!("Bad tick scale count in '{}'", self.tick_scale))?;
+        match unit {
+            "RealTime" => Ok(super::TickScale::RealTime),
+            "Seconds" if count > 0 => Ok(super::TickScale::Seconds(count)),
+            "Days" if (1..=u16::MAX as u64).contains(&count) => Ok(super::TickScale::Days(count as u16)),
+            "Years" if (1..=10).contains(&count) => Ok(super::TickScale::Years(count as u8)),
+            "Seconds" | "Days" | "Years" => Err(anyhow::anyhow!("Tick scale '{}' is out of range", self.tick_scale)),
+            other => Err(anyhow::anyhow!("Unknown tick scale: {}", other)),
         }
     }
 }
 
+/// Built-in stress profile used by the performance envelope bench
+pub const STRESS_SCENARIO_ID: &str = "packet_storm_stress";
+
 pub fn load_scenarios() -> anyhow::Result<Vec<Scenario>> {
     // For now, return hardcoded scenarios
     // In a real implementation, this would read from colony-content/scenarios.toml + mods/*/scenarios.toml
@@ -121,6 +168,7 @@ pub fn load_scenarios() -> anyhow::Result<Vec<Scenario>> {
                 min_deadline_hit_pct: 95.0,
                 max_corruption_field: 0.5,
                 observation_window_days: 3,
+                max_carbon_kg_per_period: None,
             },
             loss: LossRules {
                 hard_power_deficit_ticks: 2000,
@@ -137,6 +185,11 @@ pub fn load_scenarios() -> anyhow::Result<Vec<Scenario>> {
             enabled_events: Some(vec![
                 "pcie_link_flap".to_string(),
             ]),
+            seeds: Default::default(),
+            gpu: false, // CPU yard only
+            io_channels: Vec::new(),
+            class_reservations: Vec::new(),
+            op_limits: Vec::new(),
         },
         Scenario {
             id: "factory_horizon_nominal".to_string(),
@@ -157,6 +210,7 @@ pub fn load_scenarios() -> anyhow::Result<Vec<Scenario>> {
                 min_deadline_hit_pct: 99.5,
                 max_corruption_field: 0.35,
                 observation_window_days: 7,
+                max_carbon_kg_per_period: None,
             },
             loss: LossRules {
                 hard_power_deficit_ticks: 1000,
@@ -168,6 +222,11 @@ pub fn load_scenarios() -> anyhow::Result<Vec<Scenario>> {
             start_tunables: None,
             enabled_pipelines: None, // All pipelines enabled
             enabled_events: None,    // All events enabled
+            seeds: Default::default(),
+            gpu: true,
+            io_channels: Vec::new(),
+            class_reservations: Vec::new(),
+            op_limits: Vec::new(),
         },
         Scenario {
             id: "signal_tempest_abyssal".to_string(),
@@ -188,6 +247,7 @@ pub fn load_scenarios() -> anyhow::Result<Vec<Scenario>> {
                 min_deadline_hit_pct: 99.8,
                 max_corruption_field: 0.25,
                 observation_window_days: 14,
+                max_carbon_kg_per_period: None,
             },
             loss: LossRules {
                 hard_power_deficit_ticks: 500,
@@ -199,6 +259,51 @@ pub fn load_scenarios() -> anyhow::Result<Vec<Scenario>> {
             start_tunables: None,
             enabled_pipelines: None, // All pipelines enabled
             enabled_events: None,    // All events enabled
+            seeds: Default::default(),
+            gpu: true,
+            io_channels: Vec::new(),
+            class_reservations: Vec::new(),
+            op_limits: Vec::new(),
+        },
+        Scenario {
+            id: STRESS_SCENARIO_ID.to_string(),
+            name: "Packet Storm (Stress)".to_string(),
+            description: "Performance envelope profile. 1M packets/minute across UDP and CAN with thousands of queued jobs; not balanced for play.".to_string(),
+            seed: 1_000_000,
+            difficulty: Difficulty {
+                name: "Stress".to_string(),
+                power_cap_mult: 4.0,
+                heat_cap_mult: 2.0,
+                bw_total_mult: 4.0,
+                fault_rate_mult: 0.0,
+                black_swan_weight_mult: 0.0,
+                research_rate_mult: 1.0,
+            },
+            victory: VictoryRules {
+                target_uptime_days: 365,
+                min_deadline_hit_pct: 0.0,
+                max_corruption_field: 1.0,
+                observation_window_days: 1,
+                max_carbon_kg_per_period: None,
+            },
+            loss: LossRules {
+                hard_power_deficit_ticks: u32::MAX,
+                sustained_deadline_miss_pct: 100.0,
+                max_sticky_workers: u32::MAX,
+                black_swan_chain_len: u32::MAX,
+                time_limit_days: None,
+            },
+            start_tunables: None,
+            enabled_pipelines: Some(vec![
+                "udp_telemetry_ingest".to_string(),
+                "can_telemetry".to_string(),
+            ]),
+            enabled_events: Some(Vec::new()),
+            seeds: Default::default(),
+            gpu: true,
+            io_channels: Vec::new(),
+            class_reservations: Vec::new(),
+            op_limits: Vec::new(),
         },
     ])
 }
@@ -264,4 +369,16 @@ mod tests {
         assert_eq!(setup.mods, vec!["vanilla"]);
         assert_eq!(setup.tick_scale, "RealTime");
     }
+
+    #[test]
+    fn test_game_setup_tick_scale() {
+        let mut setup = GameSetup::new(load_scenarios().unwrap()[0].clone());
+        assert!(matches!(setup.parse_tick_scale().unwrap(), crate::TickScale::RealTime));
+        setup.tick_scale = "Days:2".to_string();
+        assert!(matches!(setup.parse_tick_scale().unwrap(), crate::TickScale::Days(2)));
+        setup.tick_scale = "Years:11".to_string();
+        assert!(setup.parse_tick_scale().is_err());
+        setup.tick_scale = "Fortnights:1".to_string();
+        assert!(setup.parse_tick_scale().is_err());
+    }
 }
@@ -69,6 +69,27 @@ impl GpuFarm {
     }
 }
 
+/// Run condition: GPU systems only run in a colony that has a GPU farm
+pub fn gpu_present(farms: Query<(), With<GpuFarm>>) -> bool {
+    !farms.is_empty()
+}
+
+/// Refuse a pipeline with GPU ops in a colony that has no GPU farm to run it
+pub fn check_gpu_pipeline(pipeline_id: &str, pipeline: &super::Pipeline, has_gpu: bool) -> anyhow::Result<()> {
+    if has_gpu || !super::requires_gpu(&pipeline.ops) {
+        return Ok(());
+    }
+    let gpu_ops: Vec<String> = pipeline.ops.iter()
+        .filter(|op| super::requires_gpu(std::slice::from_ref(*op)))
+        .map(|op| format!("{:?}", op))
+        .collect();
+    Err(anyhow::anyhow!(
+        "Pipeline {} needs a GPU farm for {}, but this colony has none",
+        pipeline_id,
+        gpu_ops.join(", ")
+    ))
+}
+
 // Batching buffer for GPU operations
 #[derive(Default, Clone, Debug)]
 pub struct GpuBatchBuffer {
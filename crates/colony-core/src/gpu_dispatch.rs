@@ -1,10 +1,10 @@
 use bevy::prelude::*;
-use super::{GpuFarm, GpuBatchBuffer, GpuBatchItem, calculate_batch_timing, Worker, WorkerState, Workyard, YardWorkload, Op, thermal_throttle, bandwidth_latency_multiplier, IoRolling, CorruptionField, WorkerReport};
+use super::{GpuFarm, GpuBatchBuffer, GpuBatchItem, calculate_batch_timing, Worker, WorkerState, WorkerStateComp, WorkerHealth, RetryState, Workyard, YardWorkload, Op, thermal_throttle, bandwidth_latency_multiplier, IoRolling, CorruptionField, WorkerReport, FaultSite, FaultSites, RetryDecision, TenantLedger, EnergyLedger, yard_node_id, SeedStream, BlackSwanIndex, GpuFaults, GpuBatchFault, roll_batch_fault, pcie_flapping, OpLicenses, InFlightJobs, RngAudit};
 use super::faults::{fault_inject_on_completion, handle_fault};
 use super::queue::starvation;
 // Duration import removed - not used in this file
 
-#[derive(Resource, Default)]
+#[derive(Resource, Default, Clone)]
 pub struct GpuBatchQueues {
     pub buffers: std::collections::HashMap<String, GpuBatchBuffer>,
 }
@@ -21,9 +21,18 @@ impl GpuBatchQueues {
     }
 }
 
+/// How a flushed batch went
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BatchOutcome {
+    Clean,
+    Faulted,               // an op fault hit the batch; its jobs leave the queue
+    Aborted(GpuBatchFault), // dropped mid-flight; its items go back to the queue
+    NoWorker,
+}
+
 pub fn gpu_dispatch_system(
     mut yards: Query<(Entity, &mut Workyard, &mut YardWorkload, &mut GpuFarm)>,
-    mut workers: Query<(Entity, &mut Worker)>,
+    mut workers: Query<(Entity, &Worker, &mut WorkerStateComp, &mut WorkerHealth, &mut RetryState)>,
     mut jobq: ResMut<super::queue::JobQueue>,
     mut batch_queues: ResMut<GpuBatchQueues>,
     colony: Res<super::Colony>,
@@ -31,8 +40,16 @@ pub fn gpu_dispatch_system(
     mut io_rolling: ResMut<IoRolling>,
     corruption_field: Res<CorruptionField>,
     clock: Res<super::SimClock>,
+    mut fault_sites: ResMut<FaultSites>,
+    mut tenants: ResMut<TenantLedger>,
+    mut energy: ResMut<EnergyLedger>,
+    mut gpu_faults: ResMut<GpuFaults>,
+    swans: Option<Res<BlackSwanIndex>>,
+    (mut licenses, mut in_flight, mut rng_audit): (ResMut<OpLicenses>, ResMut<InFlightJobs>, Option<ResMut<RngAudit>>),
     mut report_writer: EventWriter<WorkerReport>,
 ) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    let link_flapping = pcie_flapping(swans.as_deref(), &gpu_faults.config, now_tick);
     for (yard_e, mut yard, mut workload, mut gpu_farm) in yards.iter_mut() {
         if yard.kind != super::WorkyardKind::GpuFarm {
             continue;
@@ -40,8 +57,8 @@ pub fn gpu_dispatch_system(
 
         let available_workers: Vec<Entity> = workers
             .iter()
-            .filter(|(_, worker)| worker.state == WorkerState::Idle && worker.class == super::WorkClass::Gpu)
-            .map(|(entity, _)| entity)
+            .filter(|(_, worker, state, ..)| state.0 == WorkerState::Idle && worker.class == super::WorkClass::Gpu)
+            .map(|(entity, ..)| entity)
             .collect();
 
         if available_workers.is_empty() {
@@ -66,7 +83,7 @@ pub fn gpu_dispatch_system(
                 matches!(op, Op::GpuPreprocess | Op::Yolo | Op::GpuExport)
             });
 
-            if !has_gpu_ops {
+            if !has_gpu_ops || gpu_faults.backing_off(job.id, now_tick) {
                 continue;
             }
 
@@ -87,8 +104,21 @@ pub fn gpu_dispatch_system(
                     continue;
                 }
 
+                // Jobs needing a licensed op wait for a free slot
+                if !licenses.admit(job.id, &job.pipeline.ops, now_tick) {
+                    continue;
+                }
+
+                // Tenants past their share of this tick's batch slots wait
+                let slots = gpu_farm.gpus as usize * gpu_farm.per_gpu.batch_max;
+                if !tenants.charge_gpu_item(enqueued_job.tenant.as_deref(), slots) {
+                    continue;
+                }
+
+                // The slot is held from batching until the job's ops are done
+                licenses.acquire(job.id, &job.pipeline.ops, now_tick, job.pipeline.ops.iter().map(|op| op.cost_ms()).sum());
+
                 // Add to batch buffer
-                let now_tick = clock.now.timestamp_millis() as u64 / 16;
                 buffer.add_item(GpuBatchItem {
                     job_id: job.id,
                     op: gpu_op.clone(),
@@ -98,7 +128,8 @@ pub fn gpu_dispatch_system(
 
                 // Check if we should flush the batch
                 if buffer.should_flush(&gpu_farm.per_gpu, now_tick) {
-                    process_gpu_batch(
+                    let item_ids: Vec<u64> = buffer.items.iter().map(|item| item.job_id).collect();
+                    let outcome = process_gpu_batch(
                         &mut gpu_farm,
                         &mut workers,
                         &mut workload,
@@ -108,8 +139,30 @@ pub fn gpu_dispatch_system(
                         &mut io_rolling,
                         &corruption_field,
                         &clock,
-                        &mut report_writer,
+                        (yard_e, &mut fault_sites),
+                        (&mut gpu_faults, link_flapping, rng_audit.as_deref_mut()),
+                        (&mut report_writer, &mut in_flight),
                     );
+                    let ran_clean = outcome == BatchOutcome::Clean;
+                    let service_ms: u32 = job.pipeline.ops.iter().map(|op| op.cost_ms()).sum();
+                    energy.charge_job(job.pipeline.id.as_deref(), yard.power_draw_kw, yard.slots, service_ms, ran_clean, now_tick);
+
+                    if let BatchOutcome::Aborted(_) = outcome {
+                        // Requeued items stay in the queue behind their penalty; the rest are lost
+                        for item_id in item_ids {
+                            if !gpu_faults.requeue(item_id, now_tick) {
+                                if item_id == job.id {
+                                    tenants.record_done(enqueued_job.tenant.as_deref(), Some(false));
+                                }
+                                completed_job_ids.push(item_id);
+                            }
+                        }
+                        continue;
+                    }
+                    gpu_faults.finish(job.id);
+
+                    let deadline_hit = (now_tick.saturating_sub(enqueued_job.enq_tick) * 16) <= job.deadline_ms;
+                    tenants.record_done(enqueued_job.tenant.as_deref(), ran_clean.then_some(deadline_hit));
 
                     // Mark job for removal
                     completed_job_ids.push(job.id);
@@ -124,9 +177,10 @@ pub fn gpu_dispatch_system(
     }
 }
 
+/// Run a flushed batch on an idle GPU worker
 fn process_gpu_batch(
     gpu_farm: &mut GpuFarm,
-    workers: &mut Query<(Entity, &mut Worker)>,
+    workers: &mut Query<(Entity, &Worker, &mut WorkerStateComp, &mut WorkerHealth, &mut RetryState)>,
     workload: &mut YardWorkload,
     batch: &mut GpuBatchBuffer,
     colony: &super::Colony,
@@ -134,21 +188,28 @@ fn process_gpu_batch(
     io_rolling: &mut IoRolling,
     corruption_field: &CorruptionField,
     clock: &super::SimClock,
-    report_writer: &mut EventWriter<WorkerReport>,
-) {
+    (yard_e, fault_sites): (Entity, &mut FaultSites),
+    (gpu_faults, link_flapping, rng_audit): (&mut GpuFaults, bool, Option<&mut RngAudit>),
+    (report_writer, in_flight): (&mut EventWriter<WorkerReport>, &mut InFlightJobs),
+) -> BatchOutcome {
     if batch.items.is_empty() {
-        return;
+        return BatchOutcome::NoWorker;
     }
+    let mut outcome = BatchOutcome::NoWorker;
 
     // Find an available GPU worker
     let worker_entity = workers
         .iter()
-        .find(|(_, worker)| worker.state == WorkerState::Idle && worker.class == super::WorkClass::Gpu)
-        .map(|(entity, _)| entity);
+        .find(|(_, worker, state, ..)| state.0 == WorkerState::Idle && worker.class == super::WorkClass::Gpu)
+        .map(|(entity, ..)| entity);
 
     if let Some(worker_entity) = worker_entity {
-        let (_, mut worker) = workers.get_mut(worker_entity).unwrap();
-        worker.state = WorkerState::Running;
+        let (_, worker, mut state, mut health, mut retry) = workers.get_mut(worker_entity).unwrap();
+        state.0 = WorkerState::Running;
+        // The worker holds the whole batch until each of its jobs is reported
+        for item in &batch.items {
+            in_flight.assign(item.job_id, worker.id);
+        }
 
         // Calculate batch timing
         let is_first_batch = gpu_farm.meters.batches_inflight == 0;
@@ -193,9 +254,25 @@ fn process_gpu_batch(
         let enq_tick = batch.first_enqueue_tick.unwrap_or(now_tick);
         let queue_starvation = starvation(now_tick, enq_tick, 1000);
 
+        // The whole batch can be lost before any op fault is rolled
+        if let Some(audit) = rng_audit {
+            audit.record(SeedStream::Faults, colony.seed_for(SeedStream::Faults), now_tick, "gpu_batch_fault");
+            audit.record(SeedStream::Faults, colony.seed_for(SeedStream::Faults), now_tick, "gpu_fault");
+        }
+        let batch_fault = roll_batch_fault(
+            &gpu_faults.config,
+            batch.total_vram_mb(),
+            final_exec_ms,
+            corruption_field.global,
+            link_flapping,
+            colony.seed_for(SeedStream::Faults),
+            batch.items[0].job_id,
+            now_tick,
+        );
+
         // Check for fault injection (batch-level)
         let fault = fault_inject_on_completion(
-            &*worker,
+            &*health,
             &super::Workyard {
                 kind: super::WorkyardKind::GpuFarm,
                 slots: 2,
@@ -210,15 +287,42 @@ fn process_gpu_batch(
             colony.meters.bandwidth_util,
             queue_starvation,
             &colony.corruption_tun,
-            colony.seed,
+            colony.seed_for(SeedStream::Faults),
             now_tick,
         );
 
-        if let Some(fault_kind) = fault {
-            // Handle batch-level fault
+        if let Some(batch_fault) = batch_fault {
+            gpu_faults.record_abort(batch_fault);
+            if batch_fault == GpuBatchFault::EccError {
+                health.corruption = (health.corruption + gpu_faults.config.ecc_worker_corruption).min(1.0);
+            }
+            outcome = BatchOutcome::Aborted(batch_fault);
+            // Nothing ran, so nothing is reported; the worker is free again at once
+            for item in &batch.items {
+                in_flight.finish(item.job_id);
+            }
+            state.0 = WorkerState::Idle;
+        } else if let Some(fault_kind) = fault {
+            outcome = BatchOutcome::Faulted;
+            fault_sites.record(FaultSite {
+                tick: now_tick,
+                worker_id: worker.id,
+                yard: yard_node_id(yard_e),
+                yard_kind: super::WorkyardKind::GpuFarm,
+                op: batch.items[0].op.clone(),
+                kind: fault_kind,
+            });
+            // The fault is reported against the lead job, which frees the worker for the whole batch
+            for item in &batch.items[1..] {
+                in_flight.finish(item.job_id);
+            }
             handle_fault(
                 fault_kind,
-                &mut worker,
+                RetryDecision::NoPolicy, // batches aren't retried job by job
+                worker,
+                &mut state,
+                &mut health,
+                &mut retry,
                 batch.items[0].job_id,
                 batch.items[0].op.clone(),
                 &colony.corruption_tun,
@@ -226,6 +330,7 @@ fn process_gpu_batch(
             );
         } else {
             // Normal batch completion
+            outcome = BatchOutcome::Clean;
             for item in &batch.items {
                 report_writer.send(WorkerReport::Completed { job_id: item.job_id });
             }
@@ -240,11 +345,12 @@ fn process_gpu_batch(
         let alpha = 0.1; // EWMA smoothing factor
         gpu_farm.meters.batch_latency_ms = alpha * final_exec_ms + (1.0 - alpha) * gpu_farm.meters.batch_latency_ms;
 
-        worker.state = WorkerState::Idle;
+        state.0 = WorkerState::Idle;
     }
 
     // Clear the batch buffer
     batch.clear();
+    outcome
 }
 
 #[cfg(test)]
@@ -0,0 +1,213 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use anyhow::{bail, Result};
+use rand::Rng;
+use crate::{BlackSwanIndex, tick_rng};
+
+/// Black Swan whose bus trouble makes in-flight batches drop
+pub const PCIE_FLAP_SWAN: &str = "pcie_link_flap";
+/// Keeps batch rolls apart from the per-op fault roll made on the same tick
+const GPU_FAULT_SALT: u64 = 0x6770_755f_6661_756c;
+
+/// Why a GPU batch was aborted mid-flight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GpuBatchFault {
+    EccError,      // uncorrectable VRAM error, more likely with a corrupted field
+    KernelTimeout, // batch ran past the watchdog
+    PcieFlap,      // host link dropped while pcie_link_flap is in effect
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuFaultConfig {
+    pub ecc_rate_per_gb: f32,       // ECC chance per GB of batch VRAM
+    pub ecc_corruption_mult: f32,   // ECC rate scales by 1 + mult * corruption field
+    pub ecc_worker_corruption: f32, // added to the worker's corruption on an ECC error
+    pub kernel_timeout_ms: f32,     // watchdog for one batch
+    pub pcie_flap_rate: f32,        // chance per batch while the link is flapping
+    pub pcie_flap_window_ms: u64,   // how long after pcie_link_flap fires the link flaps
+    pub requeue_penalty_ms: u64,    // aborted items wait this long before they can batch again
+    pub max_requeues: u32,          // items aborted more often than this are dropped
+}
+
+impl Default for GpuFaultConfig {
+    fn default() -> Self {
+        Self {
+            ecc_rate_per_gb: 0.002,
+            ecc_corruption_mult: 4.0,
+            ecc_worker_corruption: 0.01,
+            kernel_timeout_ms: 2000.0,
+            pcie_flap_rate: 0.25,
+            pcie_flap_window_ms: 1_800_000, // the event's bandwidth tax lasts as long
+            requeue_penalty_ms: 250,
+            max_requeues: 3,
+        }
+    }
+}
+
+impl GpuFaultConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (name, rate) in [("ecc_rate_per_gb", self.ecc_rate_per_gb), ("pcie_flap_rate", self.pcie_flap_rate)] {
+            if !(0.0..=1.0).contains(&rate) {
+                bail!("{} must be between 0 and 1, got {}", name, rate);
+            }
+        }
+        if self.kernel_timeout_ms.is_nan() || self.kernel_timeout_ms <= 0.0 {
+            bail!("kernel_timeout_ms must be positive");
+        }
+        if self.ecc_corruption_mult < 0.0 || self.ecc_worker_corruption < 0.0 {
+            bail!("ECC multipliers can't be negative");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GpuFaultKpi {
+    pub ecc_errors: u32,
+    pub kernel_timeouts: u32,
+    pub pcie_flaps: u32,
+    pub batches_aborted: u32,
+    pub items_requeued: u32,
+    pub items_dropped: u32, // out of requeues
+}
+
+/// An aborted item waiting out its penalty
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GpuRequeue {
+    pub until_tick: u64,
+    pub count: u32,
+}
+
+/// Batch-level GPU faults: config, KPIs and the items serving a requeue penalty
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GpuFaults {
+    pub config: GpuFaultConfig,
+    pub kpi: GpuFaultKpi,
+    pub requeued: HashMap<u64, GpuRequeue>, // job id -> penalty
+}
+
+impl GpuFaults {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn configure(&mut self, config: GpuFaultConfig) -> Result<()> {
+        config.validate()?;
+        self.config = config;
+        Ok(())
+    }
+
+    /// True while an aborted job still waits out its penalty
+    pub fn backing_off(&self, job_id: u64, now_tick: u64) -> bool {
+        self.requeued.get(&job_id).is_some_and(|r| now_tick < r.until_tick)
+    }
+
+    pub fn record_abort(&mut self, fault: GpuBatchFault) {
+        self.kpi.batches_aborted += 1;
+        match fault {
+            GpuBatchFault::EccError => self.kpi.ecc_errors += 1,
+            GpuBatchFault::KernelTimeout => self.kpi.kernel_timeouts += 1,
+            GpuBatchFault::PcieFlap => self.kpi.pcie_flaps += 1,
+        }
+    }
+
+    /// Put an aborted item back behind its penalty; false when it is out of requeues and dropped
+    pub fn requeue(&mut self, job_id: u64, now_tick: u64) -> bool {
+        let count = self.requeued.get(&job_id).map_or(0, |r| r.count) + 1;
+        if count > self.config.max_requeues {
+            self.requeued.remove(&job_id);
+            self.kpi.items_dropped += 1;
+            return false;
+        }
+        let until_tick = now_tick + self.config.requeue_penalty_ms / 16;
+        self.requeued.insert(job_id, GpuRequeue { until_tick, count });
+        self.kpi.items_requeued += 1;
+        true
+    }
+
+    /// Forget a job that left the queue
+    pub fn finish(&mut self, job_id: u64) {
+        self.requeued.remove(&job_id);
+    }
+}
+
+/// Whether pcie_link_flap fired recently enough that the host link is still flapping
+pub fn pcie_flapping(swans: Option<&BlackSwanIndex>, config: &GpuFaultConfig, now_tick: u64) -> bool {
+    let window_ticks = config.pcie_flap_window_ms / 16;
+    swans.is_some_and(|s| {
+        s.meters.recently_fired.iter()
+            .any(|(id, tick)| id == PCIE_FLAP_SWAN && now_tick.saturating_sub(*tick) < window_ticks)
+    })
+}
+
+/// Roll for a batch-level fault. Timeouts follow from the batch's run time, flaps from the
+/// link state, and ECC errors from VRAM in use and the corruption field.
+pub fn roll_batch_fault(
+    config: &GpuFaultConfig,
+    batch_vram_mb: f32,
+    exec_ms: f32,
+    corruption: f32,
+    link_flapping: bool,
+    seed: u64,
+    first_job_id: u64,
+    tick: u64,
+) -> Option<GpuBatchFault> {
+    if exec_ms > config.kernel_timeout_ms {
+        return Some(GpuBatchFault::KernelTimeout);
+    }
+    let mut rng = tick_rng(seed ^ GPU_FAULT_SALT ^ first_job_id, tick);
+    if link_flapping && rng.gen::<f32>() < config.pcie_flap_rate {
+        return Some(GpuBatchFault::PcieFlap);
+    }
+    let ecc_prob = config.ecc_rate_per_gb * (batch_vram_mb / 1024.0) * (1.0 + config.ecc_corruption_mult * corruption);
+    if rng.gen::<f32>() < ecc_prob.min(1.0) {
+        return Some(GpuBatchFault::EccError);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeouts_and_flaps() {
+        let config = GpuFaultConfig { ecc_rate_per_gb: 0.0, pcie_flap_rate: 1.0, ..Default::default() };
+        assert_eq!(roll_batch_fault(&config, 512.0, 2500.0, 0.0, false, 7, 1, 10), Some(GpuBatchFault::KernelTimeout));
+        assert_eq!(roll_batch_fault(&config, 512.0, 20.0, 0.0, false, 7, 1, 10), None);
+        assert_eq!(roll_batch_fault(&config, 512.0, 20.0, 0.0, true, 7, 1, 10), Some(GpuBatchFault::PcieFlap));
+
+        let mut swans = BlackSwanIndex::new();
+        swans.meters.recently_fired.push((PCIE_FLAP_SWAN.to_string(), 1000));
+        assert!(pcie_flapping(Some(&swans), &config, 1000 + 10));
+        assert!(!pcie_flapping(Some(&swans), &config, 1000 + config.pcie_flap_window_ms / 16));
+        assert!(!pcie_flapping(None, &config, 0));
+    }
+
+    #[test]
+    fn test_ecc_rises_with_corruption() {
+        let config = GpuFaultConfig { ecc_rate_per_gb: 0.05, ..Default::default() };
+        let count = |corruption: f32| (0..2000u64)
+            .filter(|&t| roll_batch_fault(&config, 2048.0, 20.0, corruption, false, 42, 3, t).is_some())
+            .count();
+        let (clean, corrupted) = (count(0.0), count(1.0));
+        assert!(clean > 0);
+        assert!(corrupted > clean * 3, "{} vs {}", corrupted, clean);
+    }
+
+    #[test]
+    fn test_requeue_penalty_then_drop() {
+        let mut faults = GpuFaults::new();
+        faults.config.max_requeues = 2;
+        faults.record_abort(GpuBatchFault::EccError);
+        assert!(faults.requeue(9, 100));
+        assert!(faults.backing_off(9, 100 + 1));
+        assert!(!faults.backing_off(9, 100 + faults.config.requeue_penalty_ms / 16));
+        assert!(faults.requeue(9, 200));
+        assert!(!faults.requeue(9, 300));
+        assert!(!faults.backing_off(9, 301));
+        assert_eq!((faults.kpi.ecc_errors, faults.kpi.items_requeued, faults.kpi.items_dropped), (1, 2, 1));
+        assert!(faults.configure(GpuFaultConfig { pcie_flap_rate: 2.0, ..Default::default() }).is_err());
+    }
+}
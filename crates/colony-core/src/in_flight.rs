@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use crate::{Worker, WorkerReport, WorkerState, WorkerStateComp};
+
+/// Which worker each dispatched job is running on, so a report frees the worker that ran the
+/// job rather than whichever happens to be Running. A GPU worker holds every job of its batch.
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InFlightJobs {
+    pub assigned: BTreeMap<u64, u64>, // job id -> worker id
+}
+
+impl InFlightJobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn assign(&mut self, job_id: u64, worker_id: u64) {
+        self.assigned.insert(job_id, worker_id);
+    }
+
+    /// Drop a job's assignment, returning the worker it was on
+    pub fn finish(&mut self, job_id: u64) -> Option<u64> {
+        self.assigned.remove(&job_id)
+    }
+
+    pub fn worker_for(&self, job_id: u64) -> Option<u64> {
+        self.assigned.get(&job_id).copied()
+    }
+
+    pub fn is_busy(&self, worker_id: u64) -> bool {
+        self.assigned.values().any(|&w| w == worker_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.assigned.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.assigned.is_empty()
+    }
+}
+
+/// Free workers whose jobs completed or faulted. A worker goes back to Idle once it has no
+/// jobs left in flight, unless the fault quarantined it; reports for jobs never dispatched to
+/// a worker are ignored.
+pub fn report_ingest_system(
+    mut report_reader: EventReader<WorkerReport>,
+    mut in_flight: ResMut<InFlightJobs>,
+    mut workers: Query<(&Worker, &mut WorkerStateComp)>,
+) {
+    for report in report_reader.read() {
+        let job_id = match report {
+            WorkerReport::Completed { job_id } | WorkerReport::Fault { job_id, .. } => *job_id,
+            WorkerReport::Progress { .. } => continue,
+        };
+        let Some(worker_id) = in_flight.finish(job_id) else {
+            continue;
+        };
+        if in_flight.is_busy(worker_id) {
+            continue;
+        }
+        if let Some((_, mut state)) = workers.iter_mut().find(|(worker, _)| worker.id == worker_id) {
+            if state.0 == WorkerState::Running {
+                state.0 = WorkerState::Idle;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FaultKind, Op, WorkClass, WorkerBundle, WorkerSkills};
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.insert_resource(InFlightJobs::new())
+            .add_event::<WorkerReport>()
+            .add_systems(Update, report_ingest_system);
+        for id in 1..=3 {
+            let mut worker = WorkerBundle::new(id, WorkClass::Cpu, WorkerSkills { cpu: 0.5, gpu: 0.5, io: 0.5, discipline: 0.5, focus: 0.5 });
+            worker.state.0 = WorkerState::Running;
+            app.world_mut().spawn(worker);
+        }
+        app
+    }
+
+    fn states(app: &mut App) -> Vec<(u64, WorkerState)> {
+        let mut workers = app.world_mut().query::<(&Worker, &WorkerStateComp)>();
+        let mut states: Vec<(u64, WorkerState)> = workers.iter(app.world()).map(|(w, s)| (w.id, s.0.clone())).collect();
+        states.sort_by_key(|(id, _)| *id);
+        states
+    }
+
+    #[test]
+    fn test_completions_free_the_worker_that_ran_the_job() {
+        let mut app = app();
+        {
+            let mut in_flight = app.world_mut().resource_mut::<InFlightJobs>();
+            in_flight.assign(10, 3);
+            in_flight.assign(11, 2);
+            in_flight.assign(12, 2); // a batch of two on worker 2
+            in_flight.assign(13, 1);
+        }
+        app.world_mut().send_event(WorkerReport::Completed { job_id: 10 });
+        app.world_mut().send_event(WorkerReport::Completed { job_id: 11 });
+        app.world_mut().send_event(WorkerReport::Completed { job_id: 99 }); // never dispatched
+        app.update();
+        assert_eq!(states(&mut app), vec![(1, WorkerState::Running), (2, WorkerState::Running), (3, WorkerState::Idle)]);
+
+        app.world_mut().send_event(WorkerReport::Completed { job_id: 12 });
+        app.update();
+        assert_eq!(states(&mut app)[1], (2, WorkerState::Idle));
+        assert_eq!(app.world().resource::<InFlightJobs>().worker_for(13), Some(1));
+    }
+
+    #[test]
+    fn test_faults_free_their_worker_unless_quarantined() {
+        let mut app = app();
+        {
+            let mut in_flight = app.world_mut().resource_mut::<InFlightJobs>();
+            in_flight.assign(20, 1);
+            in_flight.assign(21, 2);
+        }
+        let mut workers = app.world_mut().query::<(&Worker, &mut WorkerStateComp)>();
+        for (worker, mut state) in workers.iter_mut(app.world_mut()) {
+            if worker.id == 2 {
+                state.0 = WorkerState::Recovering;
+            }
+        }
+        app.world_mut().send_event(WorkerReport::Fault { worker_id: 1, job_id: 20, op: Op::Decode, kind: FaultKind::Transient });
+        app.world_mut().send_event(WorkerReport::Fault { worker_id: 2, job_id: 21, op: Op::Decode, kind: FaultKind::StickyConfig });
+        app.update();
+        assert_eq!(states(&mut app), vec![(1, WorkerState::Idle), (2, WorkerState::Recovering), (3, WorkerState::Running)]);
+        assert!(app.world().resource::<InFlightJobs>().is_empty());
+    }
+}
@@ -1,13 +1,65 @@
 use bevy::prelude::*;
-use colony_io::{IoSimulatorConfig, UdpSimulator, HttpSimulator, HttpParser, IoPacket, ParsedOp, IoSource, IoParser};
+use colony_io::{IoSimulatorConfig, UdpSimulator, HttpSimulator, HttpParser, UdpFramer, IoPacket, ParsedOp, IoSource, IoParser, ParseCounters, ParseFaultKind, FlowTag, IoChannelSpec, IoRegistry, CanSimConfig, ModbusSimConfig, CanPacket, run_can_sim, run_modbus_sim};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
-use super::{Job, QoS};
+use super::{Job, QoS, JobQueue, JobIdAllocator, EnqueuedJob, FlowTracker, SimClock, payload_digest, get_pipeline_by_id};
+
+/// Jobs the IO tasks may have in flight to the sim; a full channel stalls the simulators
+pub const IO_JOB_CHANNEL_CAPACITY: usize = 1000;
+
+/// A job created from IO traffic; `parse_fault` is set when the packet behind it was malformed.
+/// The IO tasks run off the sim thread, so the job gets its id when it reaches the queue.
+#[derive(Debug, Clone)]
+pub struct IoJob {
+    pub job: Job,
+    pub parse_fault: Option<ParseFaultKind>,
+    pub flow: Option<FlowTag>, // set when the source stamps flow ids
+    pub payload_digest: Option<u64>, // set when the packet parsed, so the op cache can key on it
+    pub source: Option<IngestSource>, // built-in simulator it came from; IoIngest routes these
+}
+
+impl IoJob {
+    pub fn enqueue(mut self, jobq: &mut JobQueue, job_ids: &mut JobIdAllocator, flows: &mut FlowTracker, tick: u64) {
+        self.job.id = job_ids.allocate();
+        if let Some(tag) = self.flow {
+            let now_ns = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64;
+            flows.ingress(tag, self.parse_fault.is_some(), now_ns);
+        }
+        let mut enqueued = EnqueuedJob::new(self.job, tick);
+        enqueued.parse_fault = self.parse_fault;
+        enqueued.flow = self.flow;
+        enqueued.payload_digest = self.payload_digest;
+        jobq.push_enqueued(enqueued);
+    }
+
+    /// Point the job at `pipeline_id` with that pipeline's QoS and deadline; false if there's no such pipeline
+    fn retarget(&mut self, pipeline_id: &str) -> bool {
+        match pipeline_job(pipeline_id, self.job.payload_sz) {
+            Some(job) => {
+                self.job = job;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Fold a later arrival into this one; the merged input has no single digest
+    fn absorb(&mut self, other: IoJob) {
+        self.job.payload_sz += other.job.payload_sz;
+        self.parse_fault = self.parse_fault.or(other.parse_fault);
+        self.payload_digest = None;
+    }
+}
 
 #[derive(Resource, Clone)]
 pub struct IoRuntime {
     pub udp_tx: Option<mpsc::Sender<IoPacket>>,
     pub http_tx: Option<mpsc::Sender<IoPacket>>,
-    pub job_tx: Option<mpsc::Sender<Job>>,
+    pub job_tx: Option<mpsc::Sender<IoJob>>,
+    pub job_rx: Option<Arc<Mutex<mpsc::Receiver<IoJob>>>>, // drained each tick by io_ingest_system
+    pub parse_counters: ParseCounters, // per-source parsed/faulted packet counts
 }
 
 impl Default for IoRuntime {
@@ -16,21 +68,36 @@ impl Default for IoRuntime {
             udp_tx: None,
             http_tx: None,
             job_tx: None,
+            job_rx: None,
+            parse_counters: ParseCounters::new(),
         }
     }
 }
 
+impl IoRuntime {
+    /// Open the job channel; hand the sender to the IO tasks and the sim drains the other end
+    pub fn connect(&mut self) -> mpsc::Sender<IoJob> {
+        let (tx, rx) = mpsc::channel(IO_JOB_CHANNEL_CAPACITY);
+        self.job_tx = Some(tx.clone());
+        self.job_rx = Some(Arc::new(Mutex::new(rx)));
+        tx
+    }
+}
+
+/// `seed` should be the colony's IO stream, `colony.seed_for(SeedStream::Io)`, so packet
+/// timing can be pinned without touching fault or Black Swan rolls
 pub async fn start_io_runtime(
     seed: u64, 
     udp_cfg: IoSimulatorConfig, 
     http_cfg: IoSimulatorConfig,
-    job_tx: mpsc::Sender<Job>
+    job_tx: mpsc::Sender<IoJob>,
+    parse_counters: ParseCounters,
 ) {
     // Create channels
     let (udp_packet_tx, udp_packet_rx) = mpsc::channel(1000);
     let (http_packet_tx, http_packet_rx) = mpsc::channel(1000);
-    let (udp_ops_tx, mut udp_ops_rx) = mpsc::channel(1000);
-    let (http_ops_tx, mut http_ops_rx) = mpsc::channel(1000);
+    let (udp_ops_tx, udp_ops_rx) = mpsc::channel(1000);
+    let (http_ops_tx, http_ops_rx) = mpsc::channel(1000);
     
     // Start UDP simulator
     let udp_sim = UdpSimulator::new(udp_cfg);
@@ -44,63 +111,405 @@ pub async fn start_io_runtime(
         Box::new(http_sim).run(http_packet_tx, seed + 1).await;
     });
     
-    // Start UDP framer (simple passthrough for now)
+    // Start UDP framer
+    let udp_framer = UdpFramer::with_counters(parse_counters.clone());
     tokio::spawn(async move {
-        let mut rx = udp_packet_rx;
-        while let Some(packet) = rx.recv().await {
-            if let IoPacket::Udp { data, .. } = packet {
-                let parsed = ParsedOp::UdpFrame { payload: data };
-                if udp_ops_tx.send(parsed).await.is_err() {
-                    break;
-                }
-            }
-        }
+        Box::new(udp_framer).start(udp_packet_rx, udp_ops_tx).await;
     });
     
     // Start HTTP parser
-    let http_parser = HttpParser::new();
+    let http_parser = HttpParser::with_counters(parse_counters);
     tokio::spawn(async move {
         Box::new(http_parser).start(http_packet_rx, http_ops_tx).await;
     });
     
-    // Job enqueuer for UDP
-    let job_tx_udp = job_tx.clone();
+    // Job enqueuers
+    tokio::spawn(forward_parsed_ops(IngestSource::Udp.default_pipeline().to_string(), Some(IngestSource::Udp), udp_ops_rx, job_tx.clone()));
+    tokio::spawn(forward_parsed_ops(IngestSource::Http.default_pipeline().to_string(), Some(IngestSource::Http), http_ops_rx, job_tx));
+}
+
+/// Salt that keeps the fieldbus simulators apart from the UDP/HTTP ones and scenario channels
+const FIELDBUS_SALT: u64 = 0x6669_656c_6462_7573;
+
+/// Start the CAN and Modbus simulators; each frame becomes a job sized by its data bytes.
+/// CAN error frames and PDUs that don't encode become parse-faulted jobs.
+pub fn start_fieldbus_sims(seed: u64, can_cfg: CanSimConfig, modbus_cfg: ModbusSimConfig, job_tx: mpsc::Sender<IoJob>) {
+    let (can_tx, mut can_rx) = mpsc::channel(1000);
+    let (modbus_tx, mut modbus_rx) = mpsc::channel(1000);
+    tokio::spawn(run_can_sim(can_tx, can_cfg, seed ^ FIELDBUS_SALT));
+    tokio::spawn(run_modbus_sim(modbus_tx, modbus_cfg, (seed ^ FIELDBUS_SALT).wrapping_add(1)));
+
+    let can_jobs = job_tx.clone();
     tokio::spawn(async move {
-        while let Some(parsed_op) = udp_ops_rx.recv().await {
-            if let ParsedOp::UdpFrame { payload } = parsed_op {
-                enqueue_job_for_pipeline("udp_telemetry_ingest", payload.len(), &job_tx_udp).await;
+        let pipeline_id = IngestSource::Can.default_pipeline();
+        while let Some(packet) = can_rx.recv().await {
+            let io_job = match packet {
+                CanPacket::Data { dlc, bytes, .. } => {
+                    let data = &bytes[..dlc.min(8) as usize];
+                    io_job_for_pipeline(pipeline_id, data.len(), Some(payload_digest(data)), None, None)
+                }
+                CanPacket::Error => io_job_for_pipeline(pipeline_id, colony_io::CAN_FRAME_LEN, None, Some(ParseFaultKind::Checksum), None),
+            };
+            if let Some(io_job) = io_job {
+                if can_jobs.send(IoJob { source: Some(IngestSource::Can), ..io_job }).await.is_err() {
+                    break;
+                }
             }
         }
     });
-    
-    // Job enqueuer for HTTP
     tokio::spawn(async move {
-        while let Some(parsed_op) = http_ops_rx.recv().await {
-            if let ParsedOp::HttpMessage { bytes, .. } = parsed_op {
-                enqueue_job_for_pipeline("http_ingest", bytes.len(), &job_tx).await;
+        let pipeline_id = IngestSource::Modbus.default_pipeline();
+        while let Some(pdu) = modbus_rx.recv().await {
+            let io_job = match pdu.encode() {
+                Ok(bytes) => io_job_for_pipeline(pipeline_id, bytes.len(), Some(payload_digest(&bytes)), None, None),
+                Err(e) => io_job_for_pipeline(pipeline_id, 0, None, Some(ParseFaultKind::from(&e)), None),
+            };
+            if let Some(io_job) = io_job {
+                if job_tx.send(IoJob { source: Some(IngestSource::Modbus), ..io_job }).await.is_err() {
+                    break;
+                }
             }
         }
     });
 }
 
-async fn enqueue_job_for_pipeline(pipeline_id: &str, payload_sz: usize, job_tx: &mpsc::Sender<Job>) {
-    if let Some(pipeline) = super::pipelines::get_pipeline_by_id(pipeline_id) {
-        let job = Job {
-            id: chrono::Utc::now().timestamp_millis() as u64,
-            pipeline,
-            qos: match pipeline_id {
-                "udp_telemetry_ingest" => QoS::Balanced,
-                "http_ingest" => QoS::Latency,
-                _ => QoS::Balanced,
-            },
-            deadline_ms: match pipeline_id {
-                "udp_telemetry_ingest" => 50,
-                "http_ingest" => 100,
-                _ => 100,
-            },
-            payload_sz,
+/// Start a scenario's `[[io_channels]]` alongside the built-in simulators. Every channel is
+/// checked against the registry and the pipeline list before anything is spawned, so one bad
+/// name starts nothing. Channel `i` runs on `seed + 2 + i`, after the UDP and HTTP simulators.
+pub fn start_io_channels(
+    registry: &IoRegistry,
+    channels: &[IoChannelSpec],
+    seed: u64,
+    job_tx: mpsc::Sender<IoJob>,
+    parse_counters: ParseCounters,
+) -> anyhow::Result<()> {
+    validate_io_channels(registry, channels)?;
+    for (i, channel) in channels.iter().enumerate() {
+        let source = registry.source(&channel.source, &channel.config)?;
+        let parser = registry.parser(&channel.parser, parse_counters.clone())?;
+        let (packet_tx, packet_rx) = mpsc::channel(1000);
+        let (ops_tx, ops_rx) = mpsc::channel(1000);
+        let channel_seed = seed.wrapping_add(2 + i as u64);
+        tokio::spawn(async move {
+            source.run(packet_tx, channel_seed).await;
+        });
+        tokio::spawn(async move {
+            parser.start(packet_rx, ops_tx).await;
+        });
+        tokio::spawn(forward_parsed_ops(channel.pipeline.clone(), None, ops_rx, job_tx.clone()));
+        println!("IO channel '{}' started: {} -> {} -> {}", channel.name, channel.source, channel.parser, channel.pipeline);
+    }
+    Ok(())
+}
+
+/// Every channel names a registered source and parser and a known pipeline, and no two share a name
+pub fn validate_io_channels(registry: &IoRegistry, channels: &[IoChannelSpec]) -> anyhow::Result<()> {
+    let mut names = std::collections::HashSet::new();
+    for channel in channels {
+        if !names.insert(channel.name.as_str()) {
+            anyhow::bail!("IO channel '{}' is defined twice", channel.name);
+        }
+        registry.check(channel).map_err(|e| anyhow::anyhow!("IO channel '{}': {}", channel.name, e))?;
+        if super::pipelines::get_pipeline_by_id(&channel.pipeline).is_none() {
+            anyhow::bail!("IO channel '{}': unknown pipeline '{}'", channel.name, channel.pipeline);
+        }
+    }
+    Ok(())
+}
+
+/// Turn a parser's output into jobs on `pipeline_id`; rejected packets become parse-faulted jobs
+async fn forward_parsed_ops(pipeline_id: String, source: Option<IngestSource>, mut ops_rx: mpsc::Receiver<ParsedOp>, job_tx: mpsc::Sender<IoJob>) {
+    while let Some(parsed_op) = ops_rx.recv().await {
+        let io_job = match parsed_op {
+            ParsedOp::UdpFrame { payload: bytes, flow } | ParsedOp::HttpMessage { bytes, flow, .. } => {
+                io_job_for_pipeline(&pipeline_id, bytes.len(), Some(payload_digest(&bytes)), None, flow)
+            }
+            ParsedOp::ParseFault(fault) => {
+                io_job_for_pipeline(&pipeline_id, fault.len, None, Some(fault.kind), fault.flow)
+            }
         };
-        
-        let _ = job_tx.send(job).await;
+        if let Some(io_job) = io_job {
+            let _ = job_tx.send(IoJob { source, ..io_job }).await;
+        }
+    }
+}
+
+fn io_job_for_pipeline(pipeline_id: &str, payload_sz: usize, payload_digest: Option<u64>, parse_fault: Option<ParseFaultKind>, flow: Option<FlowTag>) -> Option<IoJob> {
+    let job = pipeline_job(pipeline_id, payload_sz)?;
+    Some(IoJob { job, parse_fault, flow, payload_digest, source: None })
+}
+
+/// A job on the built-in pipeline `pipeline_id`, id assigned by IoJob::enqueue
+fn pipeline_job(pipeline_id: &str, payload_sz: usize) -> Option<Job> {
+    let pipeline = get_pipeline_by_id(pipeline_id)?;
+    Some(Job {
+        id: 0, // assigned by IoJob::enqueue
+        pipeline,
+        qos: match pipeline_id {
+            "udp_telemetry_ingest" => QoS::Balanced,
+            "http_ingest" => QoS::Latency,
+            "modbus_poll" => QoS::Throughput,
+            _ => QoS::Balanced,
+        },
+        deadline_ms: match pipeline_id {
+            "udp_telemetry_ingest" => 50,
+            "http_ingest" => 100,
+            "can_telemetry" => 40,
+            _ => 100,
+        },
+        payload_sz,
+    })
+}
+
+/// A built-in simulator whose traffic the bridge turns into jobs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IngestSource {
+    Udp,
+    Http,
+    Can,
+    Modbus,
+}
+
+impl IngestSource {
+    pub const ALL: [IngestSource; 4] = [IngestSource::Udp, IngestSource::Http, IngestSource::Can, IngestSource::Modbus];
+
+    /// The vanilla pipeline built for this traffic
+    pub fn default_pipeline(self) -> &'static str {
+        match self {
+            IngestSource::Udp => "udp_telemetry_ingest",
+            IngestSource::Http => "http_ingest",
+            IngestSource::Can => "can_telemetry",
+            IngestSource::Modbus => "modbus_poll",
+        }
+    }
+}
+
+/// What happens to IO jobs that arrive while the queue is at its depth limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backpressure {
+    /// Discard the arrival
+    #[default]
+    Drop,
+    /// Fold arrivals into one held job per pipeline, queued once the depth falls
+    Coalesce,
+    /// Evict the oldest job waiting in the arrival's class queue to make room for it
+    Shed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IoIngestConfig {
+    pub routes: BTreeMap<IngestSource, String>, // pipeline per source; unrouted sources are discarded
+    pub max_queue_depth: usize, // backpressure applies from this many queued jobs
+    pub backpressure: Backpressure,
+    pub max_coalesced_sz: usize, // payload bytes one coalesced job may grow to; arrivals past it drop
+}
+
+impl Default for IoIngestConfig {
+    fn default() -> Self {
+        Self {
+            routes: IngestSource::ALL.iter().map(|source| (*source, source.default_pipeline().to_string())).collect(),
+            max_queue_depth: 10_000,
+            backpressure: Backpressure::Drop,
+            max_coalesced_sz: 1 << 20,
+        }
+    }
+}
+
+impl IoIngestConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (source, pipeline) in &self.routes {
+            if get_pipeline_by_id(pipeline).is_none() {
+                anyhow::bail!("Route for {:?} names unknown pipeline '{}'", source, pipeline);
+            }
+        }
+        if self.max_queue_depth == 0 {
+            anyhow::bail!("max_queue_depth must be at least 1");
+        }
+        if self.max_coalesced_sz == 0 {
+            anyhow::bail!("max_coalesced_sz must be at least 1");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IoIngestStats {
+    pub enqueued: u64,  // including coalesced jobs once released
+    pub dropped: u64,   // refused at the depth limit
+    pub coalesced: u64, // arrivals folded into a held job
+    pub shed: u64,      // queued jobs evicted for newer arrivals
+    pub unrouted: u64,  // from sources with no route
+}
+
+/// Routes simulator traffic into the JobQueue and applies backpressure at the depth limit.
+/// Scenario io_channels name their own pipeline and skip routing, but not backpressure.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IoIngest {
+    pub config: IoIngestConfig,
+    pub stats: IoIngestStats,
+    #[serde(skip)]
+    pub held: BTreeMap<String, IoJob>, // coalesced jobs waiting for room, by pipeline id
+}
+
+impl IoIngest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a new config; counters start over, held jobs are released under the new limit
+    pub fn configure(&mut self, config: IoIngestConfig) -> anyhow::Result<()> {
+        config.validate()?;
+        self.config = config;
+        self.stats = IoIngestStats::default();
+        Ok(())
+    }
+
+    /// Queue held coalesced jobs while there's room
+    pub fn release_held(&mut self, jobq: &mut JobQueue, job_ids: &mut JobIdAllocator, flows: &mut FlowTracker, tick: u64) {
+        while jobq.len() < self.config.max_queue_depth {
+            let Some((_, io_job)) = self.held.pop_first() else { break };
+            io_job.enqueue(jobq, job_ids, flows, tick);
+            self.stats.enqueued += 1;
+        }
+    }
+
+    /// Route one arrival and queue it, or apply backpressure if the queue is full
+    pub fn admit(&mut self, mut io_job: IoJob, jobq: &mut JobQueue, job_ids: &mut JobIdAllocator, flows: &mut FlowTracker, tick: u64) {
+        if let Some(source) = io_job.source {
+            let routed = self.config.routes.get(&source).is_some_and(|pipeline| io_job.retarget(pipeline));
+            if !routed {
+                self.stats.unrouted += 1;
+                return;
+            }
+        }
+        if jobq.len() < self.config.max_queue_depth {
+            io_job.enqueue(jobq, job_ids, flows, tick);
+            self.stats.enqueued += 1;
+            return;
+        }
+        match self.config.backpressure {
+            Backpressure::Drop => self.stats.dropped += 1,
+            Backpressure::Coalesce => {
+                let key = io_job.job.pipeline.id.clone().unwrap_or_default();
+                match self.held.get_mut(&key) {
+                    Some(held) if held.job.payload_sz + io_job.job.payload_sz > self.config.max_coalesced_sz => {
+                        self.stats.dropped += 1;
+                    }
+                    Some(held) => {
+                        held.absorb(io_job);
+                        self.stats.coalesced += 1;
+                    }
+                    None => {
+                        self.held.insert(key, io_job);
+                        self.stats.coalesced += 1;
+                    }
+                }
+            }
+            Backpressure::Shed => {
+                let queue = jobq.class_mut(JobQueue::class_of(&io_job.job));
+                match queue.iter().enumerate().min_by_key(|(_, queued)| queued.enq_tick).map(|(i, _)| i) {
+                    Some(oldest) => {
+                        queue.remove(oldest);
+                        self.stats.shed += 1;
+                        io_job.enqueue(jobq, job_ids, flows, tick);
+                        self.stats.enqueued += 1;
+                    }
+                    None => self.stats.dropped += 1,
+                }
+            }
+        }
+    }
+}
+
+/// Drain the IO job channel into the JobQueue, releasing coalesced jobs first
+pub fn io_ingest_system(
+    mut ingest: ResMut<IoIngest>,
+    runtime: Res<IoRuntime>,
+    mut jobq: ResMut<JobQueue>,
+    mut job_ids: ResMut<JobIdAllocator>,
+    mut flows: ResMut<FlowTracker>,
+    clock: Res<SimClock>,
+) {
+    let Some(job_rx) = &runtime.job_rx else { return };
+    let tick = clock.now.timestamp_millis() as u64 / 16;
+    ingest.release_held(&mut jobq, &mut job_ids, &mut flows, tick);
+    let mut job_rx = job_rx.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    while let Ok(io_job) = job_rx.try_recv() {
+        ingest.admit(io_job, &mut jobq, &mut job_ids, &mut flows, tick);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scenario_channels_validated() {
+        let channels: Vec<IoChannelSpec> = toml::from_str::<toml::Value>(
+            "[[io_channels]]\nname = \"plant_floor\"\nsource = \"udp_sim\"\nparser = \"udp_framer\"\npipeline = \"udp_telemetry_ingest\"",
+        ).unwrap()["io_channels"].clone().try_into().unwrap();
+        let registry = IoRegistry::with_builtins();
+        assert!(validate_io_channels(&registry, &channels).is_ok());
+
+        let twice = vec![channels[0].clone(), channels[0].clone()];
+        assert!(validate_io_channels(&registry, &twice).is_err());
+        let bad_pipeline = IoChannelSpec { pipeline: "nope".into(), ..channels[0].clone() };
+        assert!(validate_io_channels(&registry, &[bad_pipeline]).is_err());
+        let bad_parser = IoChannelSpec { parser: "lorawan".into(), ..channels[0].clone() };
+        assert!(validate_io_channels(&IoRegistry::new(), &[bad_parser]).is_err());
+    }
+
+    fn arrival(source: IngestSource, payload_sz: usize) -> IoJob {
+        let job = io_job_for_pipeline(source.default_pipeline(), payload_sz, None, None, None).unwrap();
+        IoJob { source: Some(source), ..job }
+    }
+
+    fn ingest(backpressure: Backpressure) -> IoIngest {
+        let mut ingest = IoIngest::new();
+        ingest.configure(IoIngestConfig { max_queue_depth: 2, backpressure, ..Default::default() }).unwrap();
+        ingest
+    }
+
+    #[test]
+    fn test_ingest_routes_and_backpressure() {
+        let (mut job_ids, mut flows) = (JobIdAllocator::new(), FlowTracker::new());
+
+        let mut routed = IoIngest::new();
+        routed.config.routes.insert(IngestSource::Can, "modbus_poll".into());
+        routed.config.routes.remove(&IngestSource::Http);
+        let mut jobq = JobQueue::new();
+        routed.admit(arrival(IngestSource::Can, 8), &mut jobq, &mut job_ids, &mut flows, 0);
+        routed.admit(arrival(IngestSource::Http, 64), &mut jobq, &mut job_ids, &mut flows, 0);
+        let modbus = JobQueue::class_of(&arrival(IngestSource::Modbus, 8).job);
+        assert_eq!(jobq.peek_class(modbus)[0].job.pipeline.id.as_deref(), Some("modbus_poll"));
+        assert_eq!((jobq.len(), routed.stats.unrouted), (1, 1));
+
+        let mut drop = ingest(Backpressure::Drop);
+        let mut jobq = JobQueue::new();
+        for tick in 0..4 {
+            drop.admit(arrival(IngestSource::Udp, 100), &mut jobq, &mut job_ids, &mut flows, tick);
+        }
+        assert_eq!((jobq.len(), drop.stats.enqueued, drop.stats.dropped), (2, 2, 2));
+
+        let mut coalesce = ingest(Backpressure::Coalesce);
+        let mut jobq = JobQueue::new();
+        for tick in 0..5 {
+            coalesce.admit(arrival(IngestSource::Udp, 100), &mut jobq, &mut job_ids, &mut flows, tick);
+        }
+        assert_eq!((jobq.len(), coalesce.stats.coalesced), (2, 3));
+        jobq.clear();
+        coalesce.release_held(&mut jobq, &mut job_ids, &mut flows, 5);
+        assert_eq!(jobq.io[0].job.payload_sz, 300);
+        assert!(coalesce.held.is_empty());
+
+        let mut shed = ingest(Backpressure::Shed);
+        let mut jobq = JobQueue::new();
+        for tick in 0..3 {
+            shed.admit(arrival(IngestSource::Udp, 100), &mut jobq, &mut job_ids, &mut flows, tick);
+        }
+        assert_eq!(jobq.io.iter().map(|queued| queued.enq_tick).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!((shed.stats.enqueued, shed.stats.shed), (3, 1));
     }
 }
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use serde::Serialize;
+use crate::{bytes_to_gbits, requires_gpu, Colony, GpuFarm, ModOps, Op, Pipeline, TenantLedger};
+
+/// What a job would cost by the numbers dispatch charges
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct JobProjection {
+    pub cost_ms: u32,      // sum of the op costs, mod hints included
+    pub work_units: f32,
+    pub vram_mb: f32,      // the hungriest op's; ops run one at a time
+    pub gbits: f32,        // payload moved per dispatch
+    pub needs_gpu: bool,
+}
+
+/// Dry run of a job against a running colony: errors mean it would be refused or never run,
+/// warnings that it would run but likely miss or wait
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JobCheck {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub projected: JobProjection,
+}
+
+impl JobCheck {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Check a job before it is queued; nothing in the world is changed or counted
+pub fn check_job(world: &mut World, pipeline: &Pipeline, deadline_ms: u64, payload_sz: usize, tenant: Option<&str>) -> JobCheck {
+    let mod_ops = world.get_resource::<ModOps>().cloned().unwrap_or_default();
+    let mut check = JobCheck::default();
+
+    for op in &pipeline.ops {
+        if let Op::DynamicWasm { op_id } = op {
+            if mod_ops.get(op_id).is_none() {
+                check.errors.push(format!("WASM op {} isn't provided by any enabled mod", op_id));
+            }
+        }
+        check.projected.cost_ms += mod_ops.cost_ms(op);
+        check.projected.work_units += mod_ops.work_units(op);
+        check.projected.vram_mb = check.projected.vram_mb.max(op.vram_needed_mb(payload_sz));
+    }
+    check.projected.gbits = bytes_to_gbits(payload_sz);
+    check.projected.needs_gpu = requires_gpu(&pipeline.ops);
+
+    if check.projected.cost_ms as u64 > deadline_ms {
+        check.warnings.push(format!("Projected {} ms of work exceeds the {} ms deadline", check.projected.cost_ms, deadline_ms));
+    }
+
+    let vram_gb = world.query::<&GpuFarm>().iter(world).map(|farm| farm.per_gpu.vram_gb * farm.gpus as f32).reduce(f32::max);
+    match vram_gb {
+        None if check.projected.needs_gpu => check.warnings.push("GPU op but no GpuFarm present".to_string()),
+        Some(vram_gb) if check.projected.vram_mb > vram_gb * 1024.0 => check.warnings.push(format!(
+            "Needs {:.0} MB of VRAM; the GPU farm has {:.0} MB", check.projected.vram_mb, vram_gb * 1024.0
+        )),
+        _ => {}
+    }
+
+    let total_gbps = world.get_resource::<Colony>().map_or(0.0, |colony| colony.bandwidth_total_gbps);
+    if let Some(tenants) = world.get_resource::<TenantLedger>() {
+        if let Err(e) = tenants.check_admit(tenant) {
+            check.errors.push(e.to_string());
+        }
+        if let Some(budget) = tenant.and_then(|t| tenants.bandwidth_budget(t, total_gbps)) {
+            if check.projected.gbits > budget {
+                check.warnings.push(format!(
+                    "Moves {:.3} Gbit, over the tenant's {:.3} Gbit share; it waits for a tick to itself", check.projected.gbits, budget
+                ));
+            }
+        }
+    }
+    if check.projected.gbits > total_gbps {
+        check.warnings.push(format!("Moves {:.3} Gbit, over the colony's {:.1} Gbit per tick", check.projected.gbits, total_gbps));
+    }
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{colony_for_scenario, TenantConfig, TenantQuota};
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.insert_resource(colony_for_scenario(None, Some(1)).unwrap());
+        world.insert_resource(ModOps::new());
+        world.insert_resource(TenantLedger::new());
+        world
+    }
+
+    fn pipeline(ops: Vec<Op>) -> Pipeline {
+        Pipeline { ops, mutation_tag: None, id: None }
+    }
+
+    #[test]
+    fn test_gpu_and_mod_ops_are_checked() {
+        let mut world = world();
+        let check = check_job(&mut world, &pipeline(vec![Op::Decode, Op::Yolo]), 1000, 4096, None);
+        assert!(check.is_valid());
+        assert_eq!(check.warnings, vec!["GPU op but no GpuFarm present".to_string()]);
+        assert_eq!(check.projected.cost_ms, Op::Decode.cost_ms() + Op::Yolo.cost_ms());
+
+        world.spawn(GpuFarm::new());
+        let wasm = Op::DynamicWasm { op_id: "com.example.fft/Op_Fft".into() };
+        let check = check_job(&mut world, &pipeline(vec![Op::Yolo, wasm]), 10, 4096, None);
+        assert_eq!(check.errors.len(), 1);
+        assert_eq!(check.warnings.len(), 1); // over the deadline only
+    }
+
+    #[test]
+    fn test_tenant_quota_refuses_without_counting() {
+        let mut world = world();
+        let quota = TenantQuota { tenant: "etl".into(), max_queued_jobs: Some(0), bandwidth_share: None, gpu_batch_share: None };
+        let ledger = TenantLedger::from_config(&TenantConfig { enabled: true, quotas: vec![quota] }).unwrap();
+        world.insert_resource(ledger);
+        let check = check_job(&mut world, &pipeline(vec![Op::Decode]), 1000, 1024, Some("etl"));
+        assert!(!check.is_valid());
+        assert!(world.resource::<TenantLedger>().usage.get("etl").is_none());
+    }
+}
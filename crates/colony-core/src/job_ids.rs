@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+
+pub const FIRST_JOB_ID: u64 = 1;
+
+/// Hands out job ids for a session: a monotonic counter, saved with the game, so the same run
+/// always numbers its jobs the same way and ids never repeat. Every producer takes its ids from
+/// here: scripted commands, maintenance, the IO bridge, the HTTP API and mods.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobIdAllocator {
+    next: u64,
+}
+
+impl Default for JobIdAllocator {
+    fn default() -> Self {
+        Self { next: FIRST_JOB_ID }
+    }
+}
+
+impl JobIdAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn starting_at(next: u64) -> Self {
+        Self { next: next.max(FIRST_JOB_ID) }
+    }
+
+    pub fn allocate(&mut self) -> u64 {
+        let id = self.next;
+        self.next += 1;
+        id
+    }
+
+    /// The id the next `allocate` returns
+    pub fn peek(&self) -> u64 {
+        self.next
+    }
+
+    /// Skip past an id that was handed out elsewhere, e.g. a job restored from an older save
+    pub fn observe(&mut self, id: u64) {
+        self.next = self.next.max(id.saturating_add(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_are_monotonic_and_survive_a_save() {
+        let mut ids = JobIdAllocator::new();
+        assert_eq!((ids.allocate(), ids.allocate()), (1, 2));
+        ids.observe(10);
+        ids.observe(4);
+        assert_eq!(ids.allocate(), 11);
+
+        let saved = serde_json::to_string(&ids).unwrap();
+        let mut loaded: JobIdAllocator = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.allocate(), 12);
+    }
+}
@@ -0,0 +1,315 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashSet};
+use anyhow::Result;
+use rand::Rng;
+use crate::corruption::tick_rng;
+use crate::queue::{EnqueuedJob, JobQueue};
+use crate::{FaultKind, SimClock};
+
+/// How long a faulted job waits, in sim ticks, before it is queued again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Backoff {
+    Fixed { ticks: u64 },
+    Exponential { base_ticks: u64, max_ticks: u64 }, // base, 2x base, 4x base... up to max
+    Jittered { base_ticks: u64, max_ticks: u64 },    // anywhere up to the exponential delay, seeded per job
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff::Exponential { base_ticks: 4, max_ticks: 256 }
+    }
+}
+
+impl Backoff {
+    /// Delay before retry number `attempt` (1-based) of job `job_id`
+    pub fn delay_ticks(&self, attempt: u32, seed: u64, job_id: u64) -> u64 {
+        let exponential = |base: u64, max: u64| {
+            base.saturating_mul(1u64 << attempt.saturating_sub(1).min(32)).min(max)
+        };
+        match *self {
+            Backoff::Fixed { ticks } => ticks,
+            Backoff::Exponential { base_ticks, max_ticks } => exponential(base_ticks, max_ticks),
+            Backoff::Jittered { base_ticks, max_ticks } => {
+                let ceiling = exponential(base_ticks, max_ticks);
+                // Same job, same attempt, same delay: replays stay deterministic
+                tick_rng(seed ^ job_id, attempt as u64).gen_range(0..=ceiling)
+            }
+        }
+    }
+}
+
+fn default_retry_on() -> Vec<FaultKind> {
+    vec![FaultKind::Transient]
+}
+
+/// Retry rules for one content pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetrySpec {
+    pub pipeline: String,  // content pipeline id, e.g. "http_ingest"
+    pub max_attempts: u32, // retries after the first run; 0 drops on the first fault
+    #[serde(default)]
+    pub backoff: Backoff,
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<FaultKind>, // other fault kinds are dropped without a retry
+}
+
+/// `[job_retry]` table in the game config, also set through `PUT /jobs/retry`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobRetryConfig {
+    #[serde(default)]
+    pub policies: Vec<RetrySpec>,
+}
+
+impl JobRetryConfig {
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for spec in &self.policies {
+            if !seen.insert(spec.pipeline.as_str()) {
+                return Err(anyhow::anyhow!("Pipeline {} has more than one retry policy", spec.pipeline));
+            }
+            if spec.max_attempts > 0 && spec.retry_on.is_empty() {
+                return Err(anyhow::anyhow!("Pipeline {}: retry_on names no fault kinds", spec.pipeline));
+            }
+            match spec.backoff {
+                Backoff::Exponential { base_ticks, max_ticks } | Backoff::Jittered { base_ticks, max_ticks } if max_ticks < base_ticks => {
+                    return Err(anyhow::anyhow!(
+                        "Pipeline {}: backoff max_ticks {} is below base_ticks {}",
+                        spec.pipeline, max_ticks, base_ticks
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What the pipeline's policy made of a fault; the fault handler reports accordingly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryDecision {
+    NoPolicy,                          // the worker's own retry budget applies
+    Scheduled { attempt: u32, due_tick: u64 },
+    NotRetryable,                      // the policy doesn't retry this fault kind
+    Exhausted,                         // out of attempts; the job is dropped
+}
+
+impl RetryDecision {
+    pub fn is_scheduled(&self) -> bool {
+        matches!(self, RetryDecision::Scheduled { .. })
+    }
+}
+
+/// Retry outcomes for one pipeline
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetryStats {
+    pub faults: u64,
+    pub retries: u64,       // re-queued after a backoff
+    pub recovered: u64,     // completed on a retry
+    pub exhausted: u64,     // dropped after the last attempt
+    pub not_retryable: u64, // dropped on a fault kind the policy skips
+}
+
+impl RetryStats {
+    /// Share of jobs that went into retry and came out completed
+    pub fn success_rate(&self) -> f32 {
+        let settled = self.recovered + self.exhausted;
+        if settled == 0 { 1.0 } else { self.recovered as f32 / settled as f32 }
+    }
+}
+
+/// A faulted job sitting out its backoff
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingRetry {
+    pub job: EnqueuedJob,
+    pub due_tick: u64,
+}
+
+/// Per-pipeline job retries. Dispatch reports faults through `on_fault`; jobs whose policy
+/// allows another attempt wait here and `job_retry_system` queues them again once due.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobRetries {
+    pub policies: Vec<RetrySpec>,
+    pub pending: Vec<PendingRetry>,
+    pub stats: BTreeMap<String, RetryStats>,
+}
+
+impl JobRetries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_config(config: &JobRetryConfig) -> Result<Self> {
+        let mut retries = Self::new();
+        retries.configure(config)?;
+        Ok(retries)
+    }
+
+    /// Replace the policies; stats of pipelines that keep a policy carry over, and jobs
+    /// already waiting still go back on the queue
+    pub fn configure(&mut self, config: &JobRetryConfig) -> Result<()> {
+        config.validate()?;
+        self.policies = config.policies.clone();
+        self.stats.retain(|id, _| config.policies.iter().any(|p| &p.pipeline == id));
+        Ok(())
+    }
+
+    pub fn config(&self) -> JobRetryConfig {
+        JobRetryConfig { policies: self.policies.clone() }
+    }
+
+    fn policy(&self, job: &EnqueuedJob) -> Option<&RetrySpec> {
+        let id = job.job.pipeline.id.as_ref()?;
+        self.policies.iter().find(|p| &p.pipeline == id)
+    }
+
+    /// Decide what happens to a job that just faulted, holding it for a retry if its policy allows
+    pub fn on_fault(&mut self, job: &EnqueuedJob, fault: FaultKind, now_tick: u64, seed: u64) -> RetryDecision {
+        let Some(spec) = self.policy(job).cloned() else {
+            return RetryDecision::NoPolicy;
+        };
+        let stats = self.stats.entry(spec.pipeline.clone()).or_default();
+        stats.faults += 1;
+        if !spec.retry_on.contains(&fault) {
+            stats.not_retryable += 1;
+            return RetryDecision::NotRetryable;
+        }
+        if job.attempt >= spec.max_attempts {
+            stats.exhausted += 1;
+            return RetryDecision::Exhausted;
+        }
+        stats.retries += 1;
+        let attempt = job.attempt + 1;
+        let due_tick = now_tick + spec.backoff.delay_ticks(attempt, seed, job.job.id);
+        let mut retried = job.clone();
+        retried.attempt = attempt;
+        self.pending.push(PendingRetry { job: retried, due_tick });
+        RetryDecision::Scheduled { attempt, due_tick }
+    }
+
+    /// Count a completed job; one that needed retries counts as recovered
+    pub fn on_success(&mut self, job: &EnqueuedJob) {
+        if job.attempt == 0 {
+            return;
+        }
+        if let Some(id) = job.job.pipeline.id.as_ref() {
+            self.stats.entry(id.clone()).or_default().recovered += 1;
+        }
+    }
+
+    /// Take the jobs whose backoff has run out by `now_tick`
+    pub fn release_due(&mut self, now_tick: u64) -> Vec<EnqueuedJob> {
+        let (due, waiting) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|p| p.due_tick <= now_tick);
+        self.pending = waiting;
+        due.into_iter().map(|p: PendingRetry| p.job).collect()
+    }
+
+    pub fn stats(&self, pipeline: &str) -> Option<&RetryStats> {
+        self.stats.get(pipeline)
+    }
+}
+
+/// Put jobs whose backoff has run out back on the queue ahead of dispatch
+pub fn job_retry_system(clock: Res<SimClock>, mut retries: ResMut<JobRetries>, mut jobq: ResMut<JobQueue>) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    for job in retries.release_due(now_tick) {
+        jobq.push_enqueued(job);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Job, Pipeline, Op, QoS};
+
+    fn job(id: u64, pipeline: &str) -> EnqueuedJob {
+        EnqueuedJob::new(Job {
+            id,
+            pipeline: Pipeline { ops: vec![Op::HttpParse], mutation_tag: None, id: Some(pipeline.to_string()) },
+            qos: QoS::Balanced,
+            deadline_ms: 50,
+            payload_sz: 64,
+        }, 0)
+    }
+
+    fn retries(max_attempts: u32, backoff: Backoff) -> JobRetries {
+        JobRetries::from_config(&JobRetryConfig {
+            policies: vec![RetrySpec { pipeline: "http_ingest".into(), max_attempts, backoff, retry_on: default_retry_on() }],
+        }).unwrap()
+    }
+
+    #[test]
+    fn test_backoff_strategies() {
+        let exp = Backoff::Exponential { base_ticks: 4, max_ticks: 20 };
+        let delays: Vec<u64> = (1..=4).map(|a| exp.delay_ticks(a, 42, 7)).collect();
+        assert_eq!(delays, vec![4, 8, 16, 20]);
+        assert_eq!(Backoff::Fixed { ticks: 3 }.delay_ticks(5, 42, 7), 3);
+
+        let jitter = Backoff::Jittered { base_ticks: 4, max_ticks: 64 };
+        for attempt in 1..=5 {
+            let delay = jitter.delay_ticks(attempt, 42, 7);
+            assert!(delay <= (4u64 << (attempt - 1)).min(64));
+            assert_eq!(delay, jitter.delay_ticks(attempt, 42, 7));
+        }
+    }
+
+    #[test]
+    fn test_faulted_job_is_retried_until_exhausted() {
+        let mut retries = retries(2, Backoff::Fixed { ticks: 5 });
+        let first = job(1, "http_ingest");
+        assert_eq!(retries.on_fault(&first, FaultKind::Transient, 100, 42), RetryDecision::Scheduled { attempt: 1, due_tick: 105 });
+        assert!(retries.release_due(104).is_empty());
+        let second = retries.release_due(105).pop().unwrap();
+        assert_eq!(second.attempt, 1);
+
+        assert!(retries.on_fault(&second, FaultKind::Transient, 110, 42).is_scheduled());
+        let third = retries.release_due(200).pop().unwrap();
+        assert_eq!(retries.on_fault(&third, FaultKind::Transient, 200, 42), RetryDecision::Exhausted);
+
+        let stats = retries.stats("http_ingest").unwrap();
+        assert_eq!((stats.faults, stats.retries, stats.exhausted), (3, 2, 1));
+        assert_eq!(stats.success_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_retry_on_and_recovery_kpis() {
+        let mut retries = retries(3, Backoff::default());
+        let first = job(1, "http_ingest");
+        assert_eq!(retries.on_fault(&first, FaultKind::DataSkew, 0, 42), RetryDecision::NotRetryable);
+        assert_eq!(retries.on_fault(&job(2, "udp_ingest"), FaultKind::Transient, 0, 42), RetryDecision::NoPolicy);
+
+        retries.on_fault(&first, FaultKind::Transient, 0, 42);
+        let retried = retries.release_due(u64::MAX).pop().unwrap();
+        retries.on_success(&retried);
+        retries.on_success(&job(3, "http_ingest")); // first-try successes aren't recoveries
+        let stats = retries.stats("http_ingest").unwrap();
+        assert_eq!((stats.recovered, stats.not_retryable), (1, 1));
+        assert_eq!(stats.success_rate(), 1.0);
+        assert!(retries.stats("udp_ingest").is_none());
+    }
+
+    #[test]
+    fn test_config_is_checked() {
+        let config: JobRetryConfig = toml::from_str(
+            "[[policies]]\npipeline = \"http_ingest\"\nmax_attempts = 3\nretry_on = [\"Transient\", \"Network\"]\n\
+             [policies.backoff]\nstrategy = \"jittered\"\nbase_ticks = 2\nmax_ticks = 64\n",
+        ).unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.policies[0].backoff, Backoff::Jittered { base_ticks: 2, max_ticks: 64 });
+
+        let mut inverted = config.clone();
+        inverted.policies[0].backoff = Backoff::Exponential { base_ticks: 10, max_ticks: 5 };
+        assert!(inverted.validate().is_err());
+        let mut twice = config.clone();
+        twice.policies.push(config.policies[0].clone());
+        assert!(twice.validate().is_err());
+        let mut nothing = config.clone();
+        nothing.policies[0].retry_on.clear();
+        assert!(nothing.validate().is_err());
+    }
+}
@@ -5,14 +5,17 @@ pub mod scheduler;
 pub mod time;
 pub mod systems;
 pub mod maintenance;
+pub mod reservation;
 pub mod config;
 pub mod pipelines;
+#[cfg(feature = "native")]
 pub mod io_bridge;
 pub mod corruption;
 pub mod faults;
 pub mod queue;
 pub mod gpu;
 pub mod gpu_dispatch;
+pub mod gpu_faults;
 pub mod debts;
 pub mod black_swan;
 pub mod mutation;
@@ -22,7 +25,69 @@ pub mod victory;
 pub mod session;
 pub mod save;
 pub mod mod_loader;
+pub mod shadow;
+pub mod preview;
+pub mod run_spec;
+pub mod run_compare;
+pub mod stress;
+pub mod memory;
+pub mod throttle;
+pub mod qos_queue;
+pub mod sandbox;
+pub mod ritual;
+pub mod achievements;
+pub mod replay_bundle;
+pub mod save_protection;
+pub mod save_compression;
+pub mod tunable_watch;
+pub mod clock_drift;
+pub mod certification;
+pub mod export_sink;
+pub mod archive;
+pub mod storage_yard;
+pub mod flow;
+pub mod profiler;
+pub mod progress;
+pub mod profiles;
+pub mod pause_policy;
+pub mod content;
+pub mod creative;
+pub mod job_ids;
+pub mod session_schedule;
+pub mod migration;
+pub mod checkpoint;
+pub mod plugin_config;
+pub mod shaping;
+pub mod job_retry;
+pub mod tenants;
+pub mod energy;
+pub mod forecast;
+pub mod autoscale;
+pub mod scenario_gen;
+pub mod digest;
+pub mod repl;
+pub mod seeds;
+pub mod op_cache;
+pub mod job_check;
+pub mod notifications;
+pub mod op_limits;
+pub mod in_flight;
+pub mod postmortem;
+pub mod metrics;
+pub mod worker_bulk;
+pub mod loadgen;
+pub mod content_graph;
+pub mod personnel;
+pub mod rng_audit;
+pub mod entity_inspect;
+pub mod placement;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod corruption_map;
+#[cfg(feature = "tick_trace")]
+pub mod tick_trace;
 // pub mod hotreload; // TODO: Implement hotreload functionality
+#[cfg(feature = "native")]
 pub mod script;
 
 #[cfg(test)]
@@ -35,14 +100,17 @@ pub use scheduler::*;
 pub use time::*;
 pub use systems::*;
 pub use maintenance::*;
+pub use reservation::*;
 pub use config::*;
 pub use pipelines::*;
+#[cfg(feature = "native")]
 pub use io_bridge::*;
 pub use corruption::*;
 pub use faults::*;
 pub use queue::*;
 pub use gpu::*;
 pub use gpu_dispatch::*;
+pub use gpu_faults::*;
 pub use debts::*;
 pub use black_swan::*;
 pub use mutation::*;
@@ -51,17 +119,136 @@ pub use game_config::*;
 pub use victory::*;
 pub use session::*;
 pub use save::*;
-// pub use mod_loader::*; // TODO: Implement mod_loader functionality
+pub use shadow::*;
+pub use preview::*;
+pub use run_spec::*;
+pub use run_compare::*;
+pub use stress::*;
+pub use memory::*;
+pub use throttle::*;
+pub use qos_queue::*;
+pub use sandbox::*;
+pub use ritual::*;
+pub use achievements::*;
+pub use replay_bundle::*;
+pub use save_protection::*;
+pub use save_compression::*;
+pub use tunable_watch::*;
+pub use clock_drift::*;
+pub use certification::*;
+pub use export_sink::*;
+pub use archive::*;
+pub use storage_yard::*;
+pub use flow::*;
+pub use profiler::*;
+pub use progress::*;
+pub use profiles::*;
+pub use pause_policy::*;
+pub use content::*;
+pub use creative::*;
+pub use job_ids::*;
+pub use session_schedule::*;
+pub use migration::*;
+pub use checkpoint::*;
+pub use plugin_config::*;
+pub use shaping::*;
+pub use job_retry::*;
+pub use tenants::*;
+pub use energy::*;
+pub use forecast::*;
+pub use autoscale::*;
+pub use scenario_gen::*;
+pub use digest::*;
+pub use repl::*;
+pub use seeds::*;
+pub use op_cache::*;
+pub use job_check::*;
+pub use notifications::*;
+pub use op_limits::*;
+pub use in_flight::*;
+pub use postmortem::*;
+pub use metrics::*;
+pub use worker_bulk::*;
+pub use loadgen::*;
+pub use content_graph::*;
+pub use personnel::*;
+pub use rng_audit::*;
+pub use entity_inspect::*;
+pub use placement::*;
+#[cfg(feature = "wasm")]
+pub use wasm::*;
+pub use corruption_map::*;
+#[cfg(feature = "tick_trace")]
+pub use tick_trace::*;
+pub use mod_loader::*;
 // pub use hotreload::*; // TODO: Implement hotreload functionality
+#[cfg(feature = "native")]
 pub use script::*;
 
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 
-pub struct ColonyPlugin;
+/// Convenience bundle of every colony sub-plugin, filtered by ColonyPluginConfig
+#[derive(Default)]
+pub struct ColonyPlugin {
+    pub config: ColonyPluginConfig,
+}
+
+impl ColonyPlugin {
+    pub fn with_config(config: ColonyPluginConfig) -> Self {
+        Self { config }
+    }
+}
 
 impl Plugin for ColonyPlugin {
     fn build(&self, app: &mut App) {
-        app        .insert_resource(Colony {
+        // Apps that loaded mods insert their ContentPack first; everyone else plays vanilla
+        if !app.world().contains_resource::<ContentPack>() {
+            app.insert_resource(ContentPack::vanilla().clone());
+        }
+        app.add_plugins(ColonyCorePlugin { config: self.config.clone() });
+        #[cfg(feature = "native")]
+        app.add_plugins(ColonyIoPlugin);
+
+        if self.config.is_enabled(ColonySet::Gpu) {
+            app.add_plugins(ColonyGpuPlugin);
+        }
+        if self.config.is_enabled(ColonySet::BlackSwans) {
+            app.add_plugins(ColonyEventsPlugin);
+        }
+        if self.config.is_enabled(ColonySet::Research) {
+            app.add_plugins(ColonyResearchPlugin);
+        }
+        if self.config.is_enabled(ColonySet::Victory) {
+            app.add_plugins(ColonyVictoryPlugin);
+        }
+        if self.config.is_enabled(ColonySet::Session) {
+            app.add_plugins(ColonySessionPlugin);
+        }
+        #[cfg(feature = "native")]
+        if self.config.is_enabled(ColonySet::Scripting) {
+            app.add_plugins(ColonyScriptingPlugin);
+        }
+    }
+}
+
+/// Clock, colony resources, CPU/IO dispatch, faults and debts. Every other sub-plugin builds on this one.
+#[derive(Default)]
+pub struct ColonyCorePlugin {
+    pub config: ColonyPluginConfig,
+}
+
+impl Plugin for ColonyCorePlugin {
+    fn build(&self, app: &mut App) {
+        // Creative sessions insert their GameMode before adding the plugins
+        if !app.world().contains_resource::<GameMode>() {
+            app.insert_resource(GameMode::default());
+        }
+        // Downstream crates may register their schedulers before adding the plugins
+        if !app.world().contains_resource::<SchedulerRegistry>() {
+            app.insert_resource(SchedulerRegistry::new());
+        }
+        app.insert_resource(Colony {
             power_cap_kw: 1000.0,
             bandwidth_total_gbps: 32.0,
             corruption_field: 0.0,
@@ -69,157 +256,347 @@ impl Plugin for ColonyPlugin {
             meters: GlobalMeters::new(),
             tunables: ResourceTunables::default(),
             corruption_tun: CorruptionTunables::default(),
+            seeds: Default::default(),
             seed: 42,
         })
         .insert_resource(DispatchScale(1.0))
+        .insert_resource(AutoThrottle::new())
         .insert_resource(IoRolling::default())
-        .insert_resource(IoRuntime::default())
+        .insert_resource(TrafficShaper::new())
+        .insert_resource(JobRetries::new())
+        .insert_resource(DeadLetterQueue::new())
+        .insert_resource(TenantLedger::new())
+        .insert_resource(EnergyLedger::new())
+        .insert_resource(DemandForecast::new())
+        .insert_resource(Autoscaler::new())
         .insert_resource(CorruptionField::new())
+        .insert_resource(FaultSites::new())
+        .insert_resource(CorruptionTopology::new())
         .insert_resource(FaultKpi::new())
         .insert_resource(ActiveScheduler::default())
         .insert_resource(JobQueue::new())
-        .insert_resource(GpuBatchQueues::new())
+        .insert_resource(JobIdAllocator::new())
+        .insert_resource(QosQueueing::new())
         .insert_resource(Debts::new())
-        .insert_resource(BlackSwanIndex::new())
-        .insert_resource(KpiRingBuffer::new())
-        .insert_resource(ResearchState::new())
-        .insert_resource(create_default_tech_tree())
-        .insert_resource(SessionCtl::new())
-        .insert_resource(ReplayLog::new())
-        .insert_resource(WinLossState::new())
-        .insert_resource(SlaTracker::new(7, 86400000 / 16))
-        .insert_resource(WasmHost::new())
-        // .insert_resource(LuaHost::new()) // TODO: Fix thread safety issues
-        // .insert_resource(ModLoader::new(std::path::PathBuf::from("mods"))) // TODO: Implement
-        // .insert_resource(HotReloadManager::new()) // TODO: Implement
+        .insert_resource(EffectiveCapacity::new())
+        .insert_resource(MemoryAccounting::new())
+        .insert_resource(OpCache::new())
+        .insert_resource(LoadGenerator::new())
+        .insert_resource(ModOps::new())
+        .insert_resource(ArrivalLog::new())
+        .insert_resource(ClockDrift::new())
+        .insert_resource(YardGrid::new())
+        .insert_resource(MaintenanceLane::new())
+        .insert_resource(ClassReservations::new())
+        .insert_resource(OpLicenses::new())
+        .insert_resource(InFlightJobs::new())
+        .insert_resource(CertificationBoard::new())
+        .insert_resource(PersonnelLedger::new())
+        .insert_resource(RngAudit::new())
+        .insert_resource(ExportSink::new())
+        .insert_resource(ArchiveStore::new())
+        .insert_resource(FlowTracker::new())
+        .insert_resource(SimProfiler::new())
+        .insert_resource(NotificationFeed::new())
         .insert_resource(SimClock {
             tick_scale: TickScale::RealTime,
             now: chrono::Utc::now(),
         })
+        .insert_resource(self.config.clone())
         .add_event::<WorkerReport>()
-        .add_systems(Startup, setup)
-        .add_systems(Update, (
-            time_system,
-            power_bandwidth_system,
-            heat_system,
-            corruption_system,
-            dispatch_system,
-            gpu_dispatch_system,
-            report_ingest_system,
-            maintenance_system,
-            update_fault_kpis,
-            apply_debts_system,
-            update_kpi_buffer_system,
-            black_swan_scan_system,
-            mutation_commit_system,
-            research_progress_system,
-            update_sla_window,
-            win_loss_system,
-            session_control_system,
-            update_wasm_host_system,
-            // TODO: Re-enable when Lua host thread safety is resolved
-            // update_lua_host_system,
-            // execute_lua_events_system,
-            // initialize_mod_loader_system,
-            // process_hot_reload_system,
-            // update_shadow_world_system,
-        ));
+        .add_systems(Startup, setup);
+
+        self.config.configure_sets(app);
+
+        // Bracket every colony set so the profiler sees the whole sim step
+        let order = self.config.resolved_order();
+        if let (Some(first), Some(last)) = (order.first(), order.last()) {
+            app.add_systems(Update, sim_profile_begin.before(*first))
+                .add_systems(Update, sim_profile_end.after(*last))
+                .add_systems(Update, notification_system.after(*last));
+        }
+
+        app.add_systems(Update, time_system.in_set(ColonySet::Time))
+            .add_systems(Update, (
+                power_bandwidth_system,
+                auto_throttle_system,
+                heat_system,
+                adjacency_heat_system,
+                corruption_system,
+                clock_drift_system,
+            ).chain().in_set(ColonySet::Resources))
+            .add_systems(Update, (
+                certification_system,
+                personnel_system,
+                op_cache_system,
+                op_license_system,
+                storage_wear_system,
+                tenant_tick_system,
+                job_retry_system,
+                dead_letter_system,
+                maintenance_complete_system,
+                dispatch_system,
+                export_sink_system,
+                archive_system,
+                report_ingest_system,
+                maintenance_system,
+                update_fault_kpis,
+            ).chain().in_set(ColonySet::Dispatch))
+            .add_systems(Update, (loadgen_system, arrival_log_system).chain().in_set(DispatchStage::Intake))
+            .add_systems(Update, creative_mode_system.before(ColonySet::Resources))
+            .add_systems(Update, (
+                memory_accounting_system,
+                energy_system,
+                forecast_system,
+                autoscale_system,
+                corruption_topology_system,
+            ).chain().in_set(DispatchStage::Accounting));
+
+        if self.config.is_enabled(ColonySet::Debts) {
+            app.add_systems(Update, (
+                apply_debts_system,
+                effective_capacity_system,
+            ).chain().in_set(ColonySet::Debts));
+        }
     }
 }
 
-fn setup(mut commands: Commands, mut jobq: ResMut<queue::JobQueue>) {
-    // Create a basic CPU workyard
-    commands.spawn((
-        Workyard {
-            kind: WorkyardKind::CpuArray,
-            slots: 4,
-            heat: 20.0,
-            heat_cap: 100.0,
-            power_draw_kw: 200.0,
-            bandwidth_share: 0.3,
-            isolation_domain: 0,
-        },
-        YardWorkload::default(),
-    ));
-
-    // Create a GPU farm
-    commands.spawn((
-        Workyard {
-            kind: WorkyardKind::GpuFarm,
-            slots: 2,
-            heat: 25.0,
-            heat_cap: 85.0,
-            power_draw_kw: 300.0,
-            bandwidth_share: 0.4,
-            isolation_domain: 1,
-        },
-        YardWorkload::default(),
-        GpuFarm::new(),
-    ));
+/// Channels into the async IO simulators
+#[cfg(feature = "native")]
+pub struct ColonyIoPlugin;
+
+#[cfg(feature = "native")]
+impl Plugin for ColonyIoPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(IoRuntime::default())
+            .insert_resource(IoIngest::new())
+            .add_systems(Update, io_ingest_system.before(loadgen_system).in_set(DispatchStage::Intake));
+    }
+}
+
+/// GPU batch queues and dispatch
+pub struct ColonyGpuPlugin;
+
+impl Plugin for ColonyGpuPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GpuBatchQueues::new())
+            .insert_resource(GpuFaults::new())
+            .add_systems(Update, gpu_dispatch_system.run_if(gpu_present).in_set(ColonySet::Gpu));
+    }
+}
+
+/// KPI history, Black Swan triggers and pipeline mutations
+pub struct ColonyEventsPlugin;
+
+impl Plugin for ColonyEventsPlugin {
+    fn build(&self, app: &mut App) {
+        let black_swans = app_content(app).black_swan_index();
+        if !app.world().contains_resource::<MetricRegistry>() {
+            app.insert_resource(MetricRegistry::new());
+        }
+        app.insert_resource(black_swans)
+            .insert_resource(KpiRingBuffer::new())
+            .add_systems(Update, (
+                update_kpi_buffer_system,
+                black_swan_scan_system,
+                mutation_commit_system,
+            ).chain().in_set(ColonySet::BlackSwans));
+    }
+}
+
+/// Tech tree, research points and rituals
+pub struct ColonyResearchPlugin;
+
+impl Plugin for ColonyResearchPlugin {
+    fn build(&self, app: &mut App) {
+        let tech_tree = app_content(app).tech_tree();
+        app.insert_resource(ResearchState::new())
+            .insert_resource(tech_tree)
+            .insert_resource(RitualRuns::new())
+            .add_systems(Update, (
+                research_progress_system,
+                ritual_progress_system,
+            ).in_set(ColonySet::Research));
+    }
+}
+
+/// SLA tracking, win/loss evaluation and achievements
+pub struct ColonyVictoryPlugin;
+
+impl Plugin for ColonyVictoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WinLossState::new())
+            .insert_resource(SlaTracker::new(7, 86400000 / 16))
+            .insert_resource(EventJournal::new())
+            .insert_resource(DigestBaseline::default())
+            .insert_resource(AchievementRegistry::with_defaults())
+            .insert_resource(Achievements::new())
+            .insert_resource(BlackBox::new())
+            .add_systems(Update, (
+                update_sla_window,
+                win_loss_system,
+                event_journal_system,
+                digest_system,
+                achievement_system,
+                black_box_system,
+            ).chain().in_set(ColonySet::Victory));
+    }
+}
+
+/// Session control, autosave, replay log and live tunable reloads
+pub struct ColonySessionPlugin;
+
+impl Plugin for ColonySessionPlugin {
+    fn build(&self, app: &mut App) {
+        // Apps that read a game config insert SaveProtection::from_game_config first
+        if !app.world().contains_resource::<SaveProtection>() {
+            app.insert_resource(SaveProtection::off());
+        }
+        app.insert_resource(SessionCtl::new())
+            .insert_resource(ReplayLog::new())
+            .insert_resource(Checkpointer::new())
+            .insert_resource(PausePolicies::default())
+            .insert_resource(PauseMonitor::new())
+            .add_systems(Update, (session_control_system, pause_policy_system).chain().in_set(ColonySet::Session))
+            .add_systems(Update, config_watch_system.before(ColonySet::Time).before(creative_mode_system));
+    }
+}
+
+/// WASM and Lua mod hosts
+#[cfg(feature = "native")]
+pub struct ColonyScriptingPlugin;
+
+#[cfg(feature = "native")]
+impl Plugin for ColonyScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        // Apps and tests point the loader elsewhere by inserting their own first
+        if !app.world().contains_resource::<ModLoader>() {
+            app.insert_resource(ModLoader::new(std::path::PathBuf::from("mods")));
+        }
+        app.insert_resource(WasmHost::new())
+            .insert_resource(LuaHost::new())
+            // .insert_resource(HotReloadManager::new()) // TODO: Implement
+            .add_systems(Startup, initialize_mod_loader_system)
+            .add_systems(Update, (
+                update_wasm_host_system,
+                (update_lua_host_system, execute_lua_events_system).chain(),
+                // process_hot_reload_system,
+                // update_shadow_world_system,
+            ).in_set(ColonySet::Scripting));
+    }
+}
+
+fn setup(mut commands: Commands, mut jobq: ResMut<queue::JobQueue>, mut job_ids: ResMut<JobIdAllocator>, config: Res<ColonyPluginConfig>, layout: Option<Res<YardLayout>>, grid: Option<Res<YardGrid>>, clock: Res<SimClock>, yards: Query<(), With<Workyard>>) {
+    // A forked shadow world arrives with the live world's yards, workers and queue
+    if !yards.is_empty() {
+        return;
+    }
+
+    // Colonies without GPU dispatch start with no farm, GPU workers or GPU jobs
+    let gpu = config.is_enabled(ColonySet::Gpu);
+
+    // CPU array, GPU farm and a storage array for Export and ArchiveWrite traffic, unless the
+    // scenario brought its own layout
+    let layout = layout.map(|l| l.clone()).unwrap_or_else(|| YardLayout::stock(gpu));
+    let grid = grid.map(|g| g.clone()).unwrap_or_default();
+    for (index, spec) in layout.0.into_iter().filter(|spec| spec.kind != WorkyardKind::GpuFarm || gpu).enumerate() {
+        let heat = if spec.kind == WorkyardKind::GpuFarm { 25.0 } else { 20.0 };
+        let mut yard = commands.spawn((
+            Workyard {
+                kind: spec.kind.clone(),
+                slots: spec.slots,
+                heat,
+                heat_cap: spec.heat_cap,
+                power_draw_kw: spec.power_draw_kw,
+                bandwidth_share: spec.bandwidth_share,
+                isolation_domain: spec.isolation_domain,
+            },
+            YardWorkload::default(),
+            grid.auto_place(index),
+        ));
+        match spec.kind {
+            WorkyardKind::GpuFarm => {
+                yard.insert((YardClock::new(-1.0), GpuFarm::new()));
+            }
+            WorkyardKind::StorageArray => {
+                yard.insert((YardClock::new(1.0), DriveWear::new(64)));
+            }
+            _ => {
+                yard.insert(YardClock::new(1.0));
+            }
+        }
+    }
 
-    // Create some CPU workers
+    // Create some CPU workers, each certified for a different mix of protocols
+    let cpu_certs: [&[CertKind]; 4] = [
+        &[CertKind::Http, CertKind::Tcp],
+        &[CertKind::Can, CertKind::Modbus],
+        &[CertKind::Http],
+        &[],
+    ];
     for i in 0..4 {
-        commands.spawn(Worker {
-            id: i,
-            class: WorkClass::Cpu,
-            skill_cpu: 0.8 + (i as f32 * 0.05),
-            skill_gpu: 0.3,
-            skill_io: 0.6,
-            discipline: 0.7,
-            focus: 0.8,
-            corruption: 0.0,
-            state: WorkerState::Idle,
-            retry: RetryPolicy::default(),
-            sticky_faults: 0,
-        });
+        commands.spawn((
+            WorkerBundle::new(i, WorkClass::Cpu, WorkerSkills {
+                cpu: 0.8 + (i as f32 * 0.05),
+                gpu: 0.3,
+                io: 0.6,
+                discipline: 0.7,
+                focus: 0.8,
+            }),
+            Certifications::new(cpu_certs[i as usize]),
+        ));
     }
 
     // Create some GPU workers
-    for i in 4..6 {
-        commands.spawn(Worker {
-            id: i,
-            class: WorkClass::Gpu,
-            skill_cpu: 0.4,
-            skill_gpu: 0.9 + ((i - 4) as f32 * 0.05),
-            skill_io: 0.3,
-            discipline: 0.8,
-            focus: 0.9,
-            corruption: 0.0,
-            state: WorkerState::Idle,
-            retry: RetryPolicy::default(),
-            sticky_faults: 0,
-        });
+    if gpu {
+        for i in 4..6 {
+            commands.spawn((
+                WorkerBundle::new(i, WorkClass::Gpu, WorkerSkills {
+                    cpu: 0.4,
+                    gpu: 0.9 + ((i - 4) as f32 * 0.05),
+                    io: 0.3,
+                    discipline: 0.8,
+                    focus: 0.9,
+                }),
+                Certifications::new(&[CertKind::Vision]),
+            ));
+        }
     }
 
-    // Add some sample jobs to the queue
-    let now_tick = chrono::Utc::now().timestamp_millis() as u64 / 16;
+    // Add some sample jobs to the queue, stamped with sim time so scripted runs stay reproducible
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
     jobq.push(Job {
-        id: 1,
+        id: job_ids.allocate(),
         pipeline: Pipeline {
             ops: vec![Op::UdpDemux, Op::Decode, Op::Kalman],
             mutation_tag: None,
+            id: None,
         },
         qos: QoS::Balanced,
         deadline_ms: 50,
         payload_sz: 4096,
     }, now_tick);
 
-    jobq.push(Job {
-        id: 2,
-        pipeline: Pipeline {
-            ops: vec![Op::HttpParse, Op::Decode, Op::Fft],
-            mutation_tag: None,
-        },
-        qos: QoS::Latency,
-        deadline_ms: 100,
-        payload_sz: 8192,
-    }, now_tick);
+    if gpu {
+        jobq.push(Job {
+            id: job_ids.allocate(),
+            pipeline: Pipeline {
+                ops: vec![Op::HttpParse, Op::Decode, Op::Fft],
+                mutation_tag: None,
+                id: None,
+            },
+            qos: QoS::Latency,
+            deadline_ms: 100,
+            payload_sz: 8192,
+        }, now_tick);
+    }
 
     jobq.push(Job {
-        id: 3,
+        id: job_ids.allocate(),
         pipeline: Pipeline {
             ops: vec![Op::CanParse, Op::Crc, Op::Kalman],
             mutation_tag: None,
+            id: None,
         },
         qos: QoS::Throughput,
         deadline_ms: 10,
@@ -227,27 +604,31 @@ fn setup(mut commands: Commands, mut jobq: ResMut<queue::JobQueue>) {
     }, now_tick);
 
     // Add GPU jobs
-    jobq.push(Job {
-        id: 4,
-        pipeline: Pipeline {
-            ops: vec![Op::Decode, Op::Kalman, Op::GpuPreprocess, Op::Yolo, Op::GpuExport],
-            mutation_tag: None,
-        },
-        qos: QoS::Balanced,
-        deadline_ms: 40,
-        payload_sz: 256,
-    }, now_tick);
+    if gpu {
+        jobq.push(Job {
+            id: job_ids.allocate(),
+            pipeline: Pipeline {
+                ops: vec![Op::Decode, Op::Kalman, Op::GpuPreprocess, Op::Yolo, Op::GpuExport],
+                mutation_tag: None,
+                id: None,
+            },
+            qos: QoS::Balanced,
+            deadline_ms: 40,
+            payload_sz: 256,
+        }, now_tick);
 
-    jobq.push(Job {
-        id: 5,
-        pipeline: Pipeline {
-            ops: vec![Op::GpuPreprocess, Op::Yolo, Op::GpuExport],
-            mutation_tag: None,
-        },
-        qos: QoS::Latency,
-        deadline_ms: 20,
-        payload_sz: 512,
-    }, now_tick);
+        jobq.push(Job {
+            id: job_ids.allocate(),
+            pipeline: Pipeline {
+                ops: vec![Op::GpuPreprocess, Op::Yolo, Op::GpuExport],
+                mutation_tag: None,
+                id: None,
+            },
+            qos: QoS::Latency,
+            deadline_ms: 20,
+            payload_sz: 512,
+        }, now_tick);
+    }
 }
 
 fn time_system(
@@ -260,33 +641,109 @@ fn time_system(
     }
 }
 
+/// What dispatch takes work from and orders it with
+#[derive(SystemParam)]
+struct DispatchQueues<'w> {
+    jobq: ResMut<'w, queue::JobQueue>,
+    policy: Res<'w, ActiveScheduler>,
+    schedulers: Option<Res<'w, SchedulerRegistry>>,
+    qos_queueing: ResMut<'w, QosQueueing>,
+    maintenance_lane: ResMut<'w, MaintenanceLane>,
+    class_reservations: ResMut<'w, ClassReservations>,
+}
+
+/// Colony state dispatch reads without changing
+#[derive(SystemParam)]
+struct DispatchEnv<'w, 's> {
+    colony: Res<'w, Colony>,
+    dispatch_scale: Res<'w, DispatchScale>,
+    corruption_field: Res<'w, CorruptionField>,
+    clock: Res<'w, SimClock>,
+    debts: Res<'w, Debts>,
+    mod_ops: Res<'w, ModOps>,
+    yard_grid: Res<'w, YardGrid>,
+    certs: Query<'w, 's, &'static Certifications>,
+}
+
+/// Limits a picked job has to fit under before a worker takes it
+#[derive(SystemParam)]
+struct DispatchAdmission<'w> {
+    licenses: ResMut<'w, OpLicenses>,
+    tenants: ResMut<'w, TenantLedger>,
+    shaper: ResMut<'w, TrafficShaper>,
+}
+
+/// Where a dispatched job's traffic, cost and outcome are recorded
+#[derive(SystemParam)]
+struct DispatchRecords<'w> {
+    io_rolling: ResMut<'w, IoRolling>,
+    export_sink: ResMut<'w, ExportSink>,
+    archive: ResMut<'w, ArchiveStore>,
+    flows: ResMut<'w, FlowTracker>,
+    clock_drift: ResMut<'w, ClockDrift>,
+    fault_sites: ResMut<'w, FaultSites>,
+    retries: ResMut<'w, JobRetries>,
+    energy: ResMut<'w, EnergyLedger>,
+    op_cache: ResMut<'w, OpCache>,
+    in_flight: ResMut<'w, InFlightJobs>,
+    cert_board: ResMut<'w, CertificationBoard>,
+    rng_audit: Option<ResMut<'w, RngAudit>>,
+    dead_letters: ResMut<'w, DeadLetterQueue>,
+    replay_log: Option<ResMut<'w, ReplayLog>>,
+    report_writer: EventWriter<'w, WorkerReport>,
+}
+
+/// Every yard dispatch can place work on, with the per-yard state it charges
+type YardQuery<'w, 's> = Query<'w, 's, (Entity, &'static mut Workyard, &'static mut YardWorkload, Option<&'static YardClock>, Option<&'static mut DriveWear>, Option<&'static YardPos>)>;
+
 fn dispatch_system(
-    mut yards: Query<(Entity, &mut Workyard, &mut YardWorkload)>,
-    mut workers: Query<(Entity, &mut Worker)>,
-    mut jobq: ResMut<queue::JobQueue>,
-    policy: Res<ActiveScheduler>,
-    colony: Res<Colony>,
-    dispatch_scale: Res<DispatchScale>,
-    mut io_rolling: ResMut<IoRolling>,
-    corruption_field: Res<CorruptionField>,
-    clock: Res<SimClock>,
-    mut report_writer: EventWriter<WorkerReport>,
+    mut yards: YardQuery,
+    mut workers: Query<(Entity, &Worker, &mut WorkerStateComp, &mut WorkerHealth, &mut RetryState)>,
+    queues: DispatchQueues,
+    env: DispatchEnv,
+    admission: DispatchAdmission,
+    records: DispatchRecords,
 ) {
-    for (yard_e, mut yard, mut workload) in yards.iter_mut() {
+    let DispatchQueues { mut jobq, policy, schedulers, mut qos_queueing, mut maintenance_lane, mut class_reservations } = queues;
+    let DispatchEnv { colony, dispatch_scale, corruption_field, clock, debts, mod_ops, yard_grid, certs } = env;
+    let DispatchAdmission { mut licenses, mut tenants, mut shaper } = admission;
+    let DispatchRecords {
+        mut io_rolling, mut export_sink, mut archive, mut flows, mut clock_drift, mut fault_sites, mut retries, mut energy,
+        mut op_cache, mut in_flight, mut cert_board, mut rng_audit, mut dead_letters, mut replay_log, mut report_writer,
+    } = records;
+
+    let current_tick = clock.now.timestamp_millis() as u64 / 16;
+    for (yard_e, yard, mut workload, yard_clock, mut drives, yard_pos) in yards.iter_mut() {
+        // Yards shut down by an event choice take no work until the debt expires
+        if debts.is_yard_offline(&format!("{:?}", yard.kind), current_tick) {
+            continue;
+        }
+
+        // Maintenance goes first through the yard's reserved lane so regular traffic can't starve it
+        let idle = workers.iter().filter(|(_, _, state, ..)| state.0 == WorkerState::Idle).count();
+        for ej in maintenance_lane.take_for_yard(&mut jobq, yard_e, yard.slots, idle, current_tick) {
+            if let Some((_, worker, mut state, ..)) = workers.iter_mut().find(|(_, _, state, ..)| state.0 == WorkerState::Idle) {
+                state.0 = WorkerState::Running;
+                in_flight.assign(ej.job.id, worker.id);
+                // The worker stays busy until maintenance_complete_system reports the job done
+                let service_ms = ej.job.pipeline.ops.iter().map(|op| mod_ops.cost_ms(op)).sum();
+                maintenance_lane.start(&ej.job, yard_e, current_tick, service_ms);
+            }
+        }
+
         let available_workers: Vec<Entity> = workers
             .iter()
-            .filter(|(_, worker)| worker.state == WorkerState::Idle)
-            .map(|(entity, _)| entity)
+            .filter(|(_, _, state, ..)| state.0 == WorkerState::Idle)
+            .map(|(entity, ..)| entity)
             .collect();
 
         // Get jobs from the appropriate queue based on yard type
-        let jobs = match yard.kind {
-            WorkyardKind::CpuArray => jobq.peek_cpu(),
-            WorkyardKind::GpuFarm => jobq.peek_gpu(),
-            WorkyardKind::SignalHub => jobq.peek_io(),
-        };
-        
-        if available_workers.is_empty() || jobs.is_empty() {
+        let home = JobClass::of_yard(&yard.kind);
+        let home_jobs = jobq.peek_class(home);
+        // Slots the yard holds for other classes go to their oldest waiting jobs first
+        let reserved = class_reservations.candidates(&jobq, &yard.kind, yard.slots, available_workers.len());
+
+        if available_workers.is_empty() || (home_jobs.is_empty() && reserved.is_empty()) {
             continue;
         }
 
@@ -294,19 +751,63 @@ fn dispatch_system(
         let mut completed_job_ids = Vec::new();
         
         // Use the active scheduler to pick jobs
-        let scheduler = policy.get_scheduler();
+        let scheduler = policy.resolve(schedulers.as_deref());
         let worker_refs: Vec<(Entity, &Worker)> = workers
             .iter()
-            .filter(|(_, worker)| worker.state == WorkerState::Idle)
-            .map(|(entity, worker)| (entity, worker))
+            .filter(|(_, _, state, ..)| state.0 == WorkerState::Idle)
+            .map(|(entity, worker, ..)| (entity, worker))
             .collect();
         
-        let job_values: Vec<Job> = jobs.iter().map(|ej| ej.job.clone()).collect();
+        // Queueing discipline narrows and orders the candidates before the scheduler sees them
+        let selected = qos_queueing.select(&yard.kind, home_jobs, worker_refs.len() - reserved.len());
+        let (jobs, selected): (std::borrow::Cow<[queue::EnqueuedJob]>, Vec<usize>) = if reserved.is_empty() {
+            (home_jobs.into(), selected)
+        } else {
+            let own = worker_refs.len() - reserved.len();
+            let mut jobs: Vec<queue::EnqueuedJob> = reserved.iter().map(|(_, ej)| ej.clone()).collect();
+            jobs.extend(selected.iter().take(own).map(|&i| home_jobs[i].clone()));
+            let selected = (0..jobs.len()).collect();
+            (jobs.into(), selected)
+        };
+        let job_values: Vec<Job> = selected.iter().map(|&i| jobs[i].job.clone()).collect();
         let picks = scheduler.pick(&*yard, &job_values, &worker_refs);
+        // Fallbacks change what runs, so a replay needs them alongside the picks
+        for event in scheduler.take_replay_events() {
+            if let Some(log) = replay_log.as_mut() {
+                log.record_event(event);
+            }
+        }
         
         for (worker_e, job) in picks {
-            if let Ok((_, mut worker)) = workers.get_mut(worker_e) {
-                worker.state = WorkerState::Running;
+            // Jobs needing an op whose slots are all held wait for one to free up
+            if !licenses.admit(job.id, &job.pipeline.ops, current_tick) {
+                continue;
+            }
+            // Jobs whose pipeline is out of bandwidth this tick stay queued for the next
+            let net_bytes = job.payload_sz * network_ops(&job.pipeline.ops);
+            // Tenants over their bandwidth share wait too
+            let tenant = jobs.iter().find(|ej| ej.job.id == job.id).and_then(|ej| ej.tenant.clone());
+            if !tenants.check_bandwidth(tenant.as_deref(), bytes_to_gbits(net_bytes), colony.bandwidth_total_gbps) {
+                continue;
+            }
+            // Storage-bound jobs wait for the next tick once the array's IOPS are spent
+            if let Some(drives) = drives.as_mut() {
+                if !drives.has_iops(storage_ops(&job.pipeline.ops)) {
+                    continue;
+                }
+            }
+            // Last, as admitting takes the pipeline's tokens and shared bandwidth
+            if !shaper.admit(&job.pipeline, bytes_to_gbits(net_bytes)) {
+                continue;
+            }
+            if let Ok((_, worker, mut state, mut health, mut retry)) = workers.get_mut(worker_e) {
+                // Only charged now that nothing can hold the job back any more
+                tenants.commit_bandwidth(tenant.as_deref(), bytes_to_gbits(net_bytes));
+                if let Some(drives) = drives.as_mut() {
+                    drives.consume(storage_ops(&job.pipeline.ops));
+                }
+                state.0 = WorkerState::Running;
+                in_flight.assign(job.id, worker.id);
                 
                 // Calculate throttling factors
                 let throttle = thermal_throttle(
@@ -315,56 +816,151 @@ fn dispatch_system(
                     colony.tunables.thermal_throttle_knee, 
                     colony.tunables.thermal_min_throttle
                 );
-                let power_scale = dispatch_scale.0;
+                // A power-capped colony runs each job slower and cooler
+                let power_scale = dispatch_scale.0.max(f32::EPSILON);
                 let bw_mult = bandwidth_latency_multiplier(
                     colony.meters.bandwidth_util, 
                     colony.tunables.bandwidth_tail_exp
                 );
+                // Network traffic crosses the yard's cable run from the uplink, slower on busy links
+                let cable_ms = if net_bytes > 0 { yard_grid.cable_ms(yard_pos, bw_mult) as u32 } else { 0 };
 
                 // Calculate work units for heat generation
                 let mut total_work_units = 0.0;
                 for op in &job.pipeline.ops {
-                    total_work_units += op.work_units();
-                    // Track I/O bandwidth for UdpDemux and HttpParse
-                    match op {
-                        Op::UdpDemux | Op::HttpParse => {
-                            io_rolling.add_bytes(job.payload_sz);
-                        }
-                        _ => {}
-                    }
+                    total_work_units += mod_ops.work_units(op);
+                }
+                // Ingress parsing and HTTP export move the payload over the colony's links
+                io_rolling.add_bytes(net_bytes);
+                // Uncertified workers run the pipeline slower and fault more often
+                let penalty = cert_board.penalty(certs.get(worker_e).ok(), &job.pipeline.ops);
+                if penalty.is_penalized() {
+                    cert_board.uncertified_runs += 1;
                 }
-                workload.units_this_tick += total_work_units;
-                
                 // Calculate queue starvation for fault injection
                 let now_tick = clock.now.timestamp_millis() as u64 / 16;
+
+                // Exports back off and retry against the destination on the worker's time
+                let export = if export_sink::has_http_export(&job.pipeline.ops) {
+                    Some(export_sink.attempt(job.id, job.payload_sz, now_tick, &debts))
+                } else {
+                    None
+                };
+                let retry_ms = export.map(|e| e.retry_ms).unwrap_or(0);
+                let archived = if archive::has_archive_write(&job.pipeline.ops) {
+                    Some(archive.write(job.id, job.payload_sz as u64, now_tick))
+                } else {
+                    None
+                };
+                let storage_wait_ticks = archived.map(|a| a.wait_ticks).unwrap_or(0);
+                total_work_units += Op::HttpExport.work_units() * export.map(|e| e.retries).unwrap_or(0) as f32;
+                // Repeated payloads skip the deterministic ops already cached for them
+                let parse_fault = jobs.iter().find(|ej| ej.job.id == job.id).and_then(|ej| ej.parse_fault);
+                let cached = match jobs.iter().find(|ej| ej.job.id == job.id).and_then(|ej| ej.payload_digest) {
+                    Some(digest) if parse_fault.is_none() => op_cache.run(&job.pipeline.ops, digest, job.payload_sz),
+                    _ => CacheRun::default(),
+                };
+                total_work_units -= cached.saved_units;
+                workload.units_this_tick += total_work_units * power_scale / penalty.speed_mult;
                 let enq_tick = jobs.iter().find(|ej| ej.job.id == job.id).map(|ej| ej.enq_tick).unwrap_or(now_tick);
+                let flow = jobs.iter().find(|ej| ej.job.id == job.id).and_then(|ej| ej.flow);
                 let queue_starvation = queue::starvation(now_tick, enq_tick, 1000);
+                qos_queueing.record_dispatch(&job.qos, enq_tick, now_tick);
                 
-                // Check for fault injection
-                let fault = faults::fault_inject_on_completion(
-                    &*worker,
-                    &*yard,
-                    &job.pipeline.ops[0], // Use first op for fault check
-                    corruption_field.global,
-                    colony.meters.bandwidth_util,
-                    queue_starvation,
-                    &colony.corruption_tun,
-                    colony.seed,
-                    now_tick,
-                );
+                // Malformed input faults the job outright; otherwise roll for a fault
+                let fault = match parse_fault {
+                    Some(kind) => Some(faults::fault_for_parse(kind)),
+                    // Down destination and a full offline buffer: the export is lost
+                    None if export.map(|e| e.outcome) == Some(ExportOutcome::Dropped) => Some(FaultKind::QueueDrop),
+                    None => {
+                        if let Some(audit) = rng_audit.as_mut() {
+                            audit.record(SeedStream::Faults, colony.seed_for(SeedStream::Faults), now_tick, "dispatch_fault");
+                        }
+                        faults::fault_inject_scaled(
+                            &*health,
+                            &*yard,
+                            &job.pipeline.ops[0], // Use first op for fault check
+                            corruption_field.global,
+                            colony.meters.bandwidth_util,
+                            queue_starvation,
+                            &colony.corruption_tun,
+                            colony.seed_for(SeedStream::Faults),
+                            now_tick,
+                            penalty.fault_mult,
+                        )
+                    }
+                };
                 
+                let full_speed_ms: u32 = job.pipeline.ops.iter().map(|op| mod_ops.cost_ms(op)).sum::<u32>().saturating_sub(cached.saved_ms);
+                let service_ms = (full_speed_ms as f32 / power_scale).ceil() as u32;
+                let throttled_ticks = service_ms.saturating_sub(full_speed_ms) as u64 / 16;
+                licenses.acquire(job.id, &job.pipeline.ops, now_tick, service_ms);
+                // The job holds one of the yard's slots for its service time, faulted or not, at the scaled draw
+                energy.charge_job(job.pipeline.id.as_deref(), yard.power_draw_kw * power_scale, yard.slots, service_ms, fault.is_none(), now_tick);
+                if let Some(tag) = flow {
+                    let outcome = if fault.is_some() {
+                        FlowOutcome::Faulted
+                    } else if archived.map(|a| a.outcome) == Some(ArchiveOutcome::Rejected) {
+                        FlowOutcome::Dropped
+                    } else if export.map(|e| e.outcome) == Some(ExportOutcome::Buffered) {
+                        FlowOutcome::Buffered
+                    } else {
+                        FlowOutcome::Delivered
+                    };
+                    let worked_ms = penalty.extra_ticks(service_ms) as u32 * 16 + service_ms + retry_ms + cable_ms + storage_wait_ticks as u32 * 16;
+                    flows.finish(tag, enq_tick, now_tick, worked_ms, outcome);
+                }
+
                 if let Some(fault_kind) = fault {
+                    fault_sites.record(FaultSite {
+                        tick: now_tick,
+                        worker_id: worker.id,
+                        yard: yard_node_id(yard_e),
+                        yard_kind: yard.kind.clone(),
+                        op: job.pipeline.ops[0].clone(),
+                        kind: fault_kind,
+                    });
+                    // The pipeline's retry policy may put the job back on the queue after a backoff
+                    let decision = match jobs.iter().find(|ej| ej.job.id == job.id) {
+                        Some(ej) => retries.on_fault(ej, fault_kind, now_tick, colony.seed_for(SeedStream::Faults)),
+                        None => RetryDecision::NoPolicy,
+                    };
+                    if !decision.is_scheduled() {
+                        tenants.record_done(tenant.as_deref(), None);
+                    }
                     // Handle fault
-                    faults::handle_fault(
+                    let reported = faults::handle_fault(
                         fault_kind,
-                        &mut worker,
+                        decision,
+                        worker,
+                        &mut state,
+                        &mut health,
+                        &mut retry,
                         job.id,
                         job.pipeline.ops[0].clone(),
                         &colony.corruption_tun,
                         &mut report_writer,
                     );
+                    // A lost job goes to the dead-letter queue instead of vanishing
+                    if reported == FaultKind::QueueDrop && !decision.is_scheduled() {
+                        if let Some(ej) = jobs.iter().find(|ej| ej.job.id == job.id) {
+                            dead_letters.record(ej.clone(), DeadLetterReason::QueueDrop, now_tick);
+                        }
+                    }
                 } else {
-                    // Normal completion
+                    // Normal completion, with the deadline judged by the yard's own clock
+                    let done_tick = now_tick + throttled_ticks + penalty.extra_ticks(service_ms) + (retry_ms + cable_ms) as u64 / 16 + storage_wait_ticks;
+                    let judgement = if archived.map(|a| a.outcome) == Some(ArchiveOutcome::Rejected) {
+                        // Nowhere to put the data: the job finishes but breaks its SLA
+                        DeadlineJudgement { actual_hit: false, reported_hit: false }
+                    } else {
+                        judge_deadline(yard_clock, enq_tick, done_tick, job.deadline_ms)
+                    };
+                    tenants.record_done(tenant.as_deref(), Some(judgement.actual_hit));
+                    clock_drift.record_deadline(judgement);
+                    if let Some(ej) = jobs.iter().find(|ej| ej.job.id == job.id) {
+                        retries.on_success(ej);
+                    }
                     report_writer.send(WorkerReport::Completed { job_id: job.id });
                 }
                 
@@ -373,33 +969,16 @@ fn dispatch_system(
             }
         }
         
-        // Remove completed jobs from the appropriate queue
+        // Remove completed jobs from the queue each came from
         for job_id in completed_job_ids {
-            match yard.kind {
-                WorkyardKind::CpuArray => { jobq.cpu.retain(|ej| ej.job.id != job_id); }
-                WorkyardKind::GpuFarm => { jobq.gpu.retain(|ej| ej.job.id != job_id); }
-                WorkyardKind::SignalHub => { jobq.io.retain(|ej| ej.job.id != job_id); }
-            }
-        }
-    }
-}
-
-fn report_ingest_system(
-    mut report_reader: EventReader<WorkerReport>,
-    mut workers: Query<&mut Worker>,
-) {
-    for report in report_reader.read() {
-        match report {
-            WorkerReport::Completed { job_id } => {
-                // Find and reset worker to idle
-                for mut worker in workers.iter_mut() {
-                    if worker.state == WorkerState::Running {
-                        worker.state = WorkerState::Idle;
-                        break;
-                    }
+            let class = match reserved.iter().find(|(_, ej)| ej.job.id == job_id) {
+                Some((class, _)) => {
+                    class_reservations.dispatched += 1;
+                    *class
                 }
-            }
-            _ => {}
+                None => home,
+            };
+            jobq.class_mut(class).retain(|ej| ej.job.id != job_id);
         }
     }
 }
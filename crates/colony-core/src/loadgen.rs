@@ -0,0 +1,253 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use crate::{Colony, Job, JobIdAllocator, JobQueue, QoS, RngAudit, SeedStream, SimClock, get_pipeline_by_id, tick_rng};
+
+/// Highest mean arrivals per tick any model may ask for; sampling is linear in the rate
+pub const MAX_LOADGEN_RATE: f64 = 10_000.0;
+
+/// How synthetic jobs arrive; rates are mean jobs per tick
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum ArrivalModel {
+    Poisson { rate: f64 },
+    /// Markov-modulated Poisson: Poisson at the current phase's rate, leaving for the next
+    /// phase (wrapping round) with `switch_prob` each tick
+    Mmpp { rates: Vec<f64>, switch_prob: f64 },
+    /// Exact arrivals per tick, from the start again after the last when `repeat` is set
+    Trace {
+        counts: Vec<u32>,
+        #[serde(default)]
+        repeat: bool,
+    },
+}
+
+impl Default for ArrivalModel {
+    fn default() -> Self {
+        Self::Poisson { rate: 1.0 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadGenConfig {
+    pub enabled: bool,
+    pub arrivals: ArrivalModel,
+    pub pipeline: String, // built-in pipeline id, e.g. "udp_telemetry_ingest"
+    pub qos: QoS,
+    pub deadline_ms: u64,
+    pub payload_sz: usize,
+    pub max_queue_depth: usize, // arrivals beyond this are dropped, not queued
+}
+
+impl Default for LoadGenConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            arrivals: ArrivalModel::default(),
+            pipeline: "udp_telemetry_ingest".to_string(),
+            qos: QoS::Throughput,
+            deadline_ms: 50,
+            payload_sz: 1024,
+            max_queue_depth: 10_000,
+        }
+    }
+}
+
+impl LoadGenConfig {
+    pub fn validate(&self) -> Result<()> {
+        if get_pipeline_by_id(&self.pipeline).is_none() {
+            anyhow::bail!("Unknown pipeline '{}'", self.pipeline);
+        }
+        let check_rate = |rate: f64| {
+            if !rate.is_finite() || !(0.0..=MAX_LOADGEN_RATE).contains(&rate) {
+                anyhow::bail!("Arrival rate {} is outside 0..={}", rate, MAX_LOADGEN_RATE);
+            }
+            Ok(())
+        };
+        match &self.arrivals {
+            ArrivalModel::Poisson { rate } => check_rate(*rate)?,
+            ArrivalModel::Mmpp { rates, switch_prob } => {
+                if rates.is_empty() {
+                    anyhow::bail!("An MMPP needs at least one phase rate");
+                }
+                for rate in rates {
+                    check_rate(*rate)?;
+                }
+                if !(0.0..=1.0).contains(switch_prob) {
+                    anyhow::bail!("switch_prob is {}, must be between 0 and 1", switch_prob);
+                }
+            }
+            ArrivalModel::Trace { counts, .. } => {
+                if counts.is_empty() {
+                    anyhow::bail!("A trace needs at least one tick of counts");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadGenStats {
+    pub generated: u64,
+    pub dropped: u64, // at max_queue_depth
+    pub last_tick_arrivals: u32,
+    pub phase: usize,  // current MMPP phase
+    pub cursor: usize, // next trace entry
+}
+
+/// Synthesizes job arrivals straight into the JobQueue, bypassing the packet simulators, so
+/// schedulers can be stress-tested in isolation. Off until configured.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadGenerator {
+    pub config: LoadGenConfig,
+    pub stats: LoadGenStats,
+}
+
+/// Salt that keeps load generator rolls apart from the packet simulators on the IO stream
+const LOADGEN_SALT: u64 = 0x6c6f_6164_6765_6e00;
+
+/// Poisson-distributed count: exponential inter-arrival times that fit in one tick
+fn poisson(rng: &mut impl Rng, rate: f64) -> u32 {
+    if rate <= 0.0 {
+        return 0;
+    }
+    let mut count = 0;
+    let mut t = -(1.0 - rng.gen::<f64>()).ln() / rate;
+    while t < 1.0 {
+        count += 1;
+        t += -(1.0 - rng.gen::<f64>()).ln() / rate;
+    }
+    count
+}
+
+impl LoadGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a new config; the MMPP phase, trace position and counters start over
+    pub fn configure(&mut self, config: LoadGenConfig) -> Result<()> {
+        config.validate()?;
+        self.config = config;
+        self.stats = LoadGenStats::default();
+        Ok(())
+    }
+
+    /// Arrivals for `tick`, advancing the MMPP phase or trace cursor
+    pub fn sample(&mut self, seed: u64, tick: u64) -> u32 {
+        let mut rng = tick_rng(seed ^ LOADGEN_SALT, tick);
+        match &self.config.arrivals {
+            ArrivalModel::Poisson { rate } => poisson(&mut rng, *rate),
+            ArrivalModel::Mmpp { rates, switch_prob } => {
+                if rng.gen::<f64>() < *switch_prob {
+                    self.stats.phase = (self.stats.phase + 1) % rates.len();
+                }
+                poisson(&mut rng, rates[self.stats.phase % rates.len()])
+            }
+            ArrivalModel::Trace { counts, repeat } => {
+                if self.stats.cursor >= counts.len() {
+                    if !*repeat {
+                        return 0;
+                    }
+                    self.stats.cursor = 0;
+                }
+                self.stats.cursor += 1;
+                counts[self.stats.cursor - 1]
+            }
+        }
+    }
+
+    /// Queue this tick's arrivals; returns how many were dropped at the depth limit
+    pub fn generate(&mut self, seed: u64, tick: u64, jobq: &mut JobQueue, job_ids: &mut JobIdAllocator) -> u32 {
+        let arrivals = self.sample(seed, tick);
+        self.stats.last_tick_arrivals = arrivals;
+        let Some(pipeline) = get_pipeline_by_id(&self.config.pipeline) else { return 0 };
+        let mut dropped = 0;
+        for _ in 0..arrivals {
+            if jobq.len() >= self.config.max_queue_depth {
+                dropped += 1;
+                continue;
+            }
+            jobq.push(Job {
+                id: job_ids.allocate(),
+                pipeline: pipeline.clone(),
+                qos: self.config.qos.clone(),
+                deadline_ms: self.config.deadline_ms,
+                payload_sz: self.config.payload_sz,
+            }, tick);
+        }
+        self.stats.generated += (arrivals - dropped) as u64;
+        self.stats.dropped += dropped as u64;
+        dropped
+    }
+}
+
+pub fn loadgen_system(
+    mut loadgen: ResMut<LoadGenerator>,
+    mut jobq: ResMut<JobQueue>,
+    mut job_ids: ResMut<JobIdAllocator>,
+    colony: Res<Colony>,
+    clock: Res<SimClock>,
+    rng_audit: Option<ResMut<RngAudit>>,
+) {
+    if !loadgen.config.enabled {
+        return;
+    }
+    let tick = clock.now.timestamp_millis() as u64 / 16;
+    let seed = colony.seed_for(SeedStream::Io);
+    if let Some(mut audit) = rng_audit {
+        audit.record(SeedStream::Io, seed, tick, "loadgen");
+    }
+    loadgen.generate(seed, tick, &mut jobq, &mut job_ids);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generator(arrivals: ArrivalModel) -> LoadGenerator {
+        let mut loadgen = LoadGenerator::new();
+        loadgen.configure(LoadGenConfig { enabled: true, arrivals, ..Default::default() }).unwrap();
+        loadgen
+    }
+
+    #[test]
+    fn test_poisson_mean_and_determinism() {
+        let mut a = generator(ArrivalModel::Poisson { rate: 3.0 });
+        let mut b = a.clone();
+        let counts: Vec<u32> = (0..2_000).map(|tick| a.sample(7, tick)).collect();
+        assert_eq!(counts, (0..2_000).map(|tick| b.sample(7, tick)).collect::<Vec<_>>());
+        let mean = counts.iter().sum::<u32>() as f64 / counts.len() as f64;
+        assert!((mean - 3.0).abs() < 0.2, "mean {}", mean);
+    }
+
+    #[test]
+    fn test_trace_and_mmpp() {
+        let mut trace = generator(ArrivalModel::Trace { counts: vec![2, 0, 5], repeat: true });
+        assert_eq!((0..5).map(|tick| trace.sample(1, tick)).collect::<Vec<_>>(), vec![2, 0, 5, 2, 0]);
+        let mut once = generator(ArrivalModel::Trace { counts: vec![4], repeat: false });
+        assert_eq!((once.sample(1, 0), once.sample(1, 1)), (4, 0));
+
+        // A silent phase and a busy one, switching every tick
+        let mut mmpp = generator(ArrivalModel::Mmpp { rates: vec![0.0, 50.0], switch_prob: 1.0 });
+        let counts: Vec<u32> = (0..4).map(|tick| mmpp.sample(1, tick)).collect();
+        assert!(counts[0] > 0 && counts[1] == 0 && counts[2] > 0 && counts[3] == 0, "{:?}", counts);
+    }
+
+    #[test]
+    fn test_generate_respects_depth_and_validates() {
+        let mut loadgen = generator(ArrivalModel::Trace { counts: vec![8], repeat: true });
+        loadgen.config.max_queue_depth = 5;
+        let (mut jobq, mut job_ids) = (JobQueue::new(), JobIdAllocator::new());
+        assert_eq!(loadgen.generate(1, 0, &mut jobq, &mut job_ids), 3);
+        assert_eq!((jobq.len(), loadgen.stats.generated, loadgen.stats.dropped), (5, 5, 3));
+
+        let bad = |arrivals| LoadGenConfig { arrivals, ..Default::default() }.validate().is_err();
+        assert!(bad(ArrivalModel::Poisson { rate: -1.0 }));
+        assert!(bad(ArrivalModel::Mmpp { rates: vec![], switch_prob: 0.5 }));
+        assert!(bad(ArrivalModel::Trace { counts: vec![], repeat: false }));
+        assert!(LoadGenConfig { pipeline: "nope".to_string(), ..Default::default() }.validate().is_err());
+    }
+}
@@ -1,17 +1,257 @@
 use bevy::prelude::*;
-use crate::{Job, Pipeline, Op, QoS, JobQueue};
+use serde::{Serialize, Deserialize};
+use crate::{Job, Pipeline, Op, QoS, JobQueue, JobIdAllocator, WorkyardKind, ForecastReport, DriveWear, SimClock, WorkerReport};
+use crate::queue::EnqueuedJob;
 
-pub fn enqueue_maintenance(yard_entity: Entity, jobq: &mut JobQueue) {
+const WAIT_EWMA_ALPHA: f32 = 0.2;
+
+/// A maintenance job waiting in its yard's reserved lane
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MaintenanceEntry {
+    pub yard: Entity,
+    pub job: EnqueuedJob,
+}
+
+/// A maintenance job a worker is busy with until `done_tick`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunningMaintenance {
+    pub job_id: u64,
+    pub yard: Entity,
+    pub done_tick: u64,
+    pub replaces_drives: bool,
+}
+
+pub fn enqueue_maintenance(yard_entity: Entity, jobq: &mut JobQueue, job_ids: &mut JobIdAllocator, now_tick: u64) {
     let maintenance_job = Job {
-        id: chrono::Utc::now().timestamp_millis() as u64,
+        id: job_ids.allocate(),
         pipeline: Pipeline {
             ops: vec![Op::MaintenanceCool],
             mutation_tag: Some("maintenance".to_string()),
+            id: None,
         },
         qos: QoS::Balanced,
         deadline_ms: 5000, // 5 second deadline for maintenance
         payload_sz: 0, // No payload for maintenance
     };
-    
-    jobq.push(maintenance_job, 0); // TODO: Pass actual current tick
+
+    // Maintenance bypasses the class queues and waits in its yard's reserved lane
+    jobq.maintenance.push(MaintenanceEntry {
+        yard: yard_entity,
+        job: EnqueuedJob::new(maintenance_job, now_tick),
+    });
+}
+
+/// Reserved dispatch capacity for maintenance and how long maintenance waits for it
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct MaintenanceLane {
+    pub reservation_frac: f32,   // share of a yard's slots held for maintenance when any is queued
+    pub dispatched: u64,
+    pub last_wait_ms: f32,
+    pub avg_wait_ms: f32,        // EWMA over dispatched maintenance jobs
+    pub max_wait_ms: f32,
+    pub queued: usize,           // maintenance jobs still waiting, all yards
+    #[serde(default)]
+    pub running: Vec<RunningMaintenance>,
+}
+
+impl Default for MaintenanceLane {
+    fn default() -> Self {
+        Self {
+            reservation_frac: 0.25,
+            dispatched: 0,
+            last_wait_ms: 0.0,
+            avg_wait_ms: 0.0,
+            max_wait_ms: 0.0,
+            queued: 0,
+            running: Vec::new(),
+        }
+    }
+}
+
+impl MaintenanceLane {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_reservation(&mut self, frac: f32) {
+        self.reservation_frac = frac.clamp(0.0, 1.0);
+    }
+
+    /// Slots a yard holds for maintenance; any non-zero reservation keeps at least one
+    pub fn reserved_slots(&self, yard_slots: u32) -> usize {
+        if self.reservation_frac <= 0.0 || yard_slots == 0 {
+            return 0;
+        }
+        ((yard_slots as f32 * self.reservation_frac).ceil() as usize).max(1)
+    }
+
+    pub fn record_dispatch(&mut self, enq_tick: u64, now_tick: u64) {
+        let wait_ms = now_tick.saturating_sub(enq_tick) as f32 * 16.0;
+        self.avg_wait_ms = if self.dispatched == 0 {
+            wait_ms
+        } else {
+            self.avg_wait_ms + WAIT_EWMA_ALPHA * (wait_ms - self.avg_wait_ms)
+        };
+        self.dispatched += 1;
+        self.last_wait_ms = wait_ms;
+        self.max_wait_ms = self.max_wait_ms.max(wait_ms);
+    }
+
+    /// Take up to `idle_workers` of the yard's reserved maintenance jobs, oldest first
+    pub fn take_for_yard(&mut self, jobq: &mut JobQueue, yard: Entity, yard_slots: u32, idle_workers: usize, now_tick: u64) -> Vec<EnqueuedJob> {
+        let budget = self.reserved_slots(yard_slots).min(idle_workers);
+        let mut mine: Vec<usize> = jobq.maintenance.iter()
+            .enumerate()
+            .filter(|(_, e)| e.yard == yard)
+            .map(|(i, _)| i)
+            .collect();
+        mine.sort_by_key(|&i| jobq.maintenance[i].job.enq_tick);
+        mine.truncate(budget);
+        mine.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut taken: Vec<EnqueuedJob> = mine.into_iter()
+            .map(|i| jobq.maintenance.remove(i).job)
+            .collect();
+        taken.sort_by_key(|j| j.enq_tick);
+        for job in &taken {
+            self.record_dispatch(job.enq_tick, now_tick);
+        }
+        self.queued = jobq.maintenance.len();
+        taken
+    }
+
+    /// Hold a dispatched maintenance job until its service time has elapsed
+    pub fn start(&mut self, job: &Job, yard: Entity, now_tick: u64, service_ms: u32) {
+        self.running.push(RunningMaintenance {
+            job_id: job.id,
+            yard,
+            done_tick: now_tick + (service_ms as u64).div_ceil(16).max(1),
+            replaces_drives: job.pipeline.ops.iter().any(|op| matches!(op, Op::ReplaceDrives)),
+        });
+    }
+
+    /// Maintenance jobs finished by `now_tick`, in dispatch order
+    pub fn finish_due(&mut self, now_tick: u64) -> Vec<RunningMaintenance> {
+        let (done, running) = std::mem::take(&mut self.running)
+            .into_iter()
+            .partition(|m| m.done_tick <= now_tick);
+        self.running = running;
+        done
+    }
+}
+
+/// Report maintenance jobs whose workers have finished them; drive swaps take effect here
+pub fn maintenance_complete_system(
+    clock: Res<SimClock>,
+    mut lane: ResMut<MaintenanceLane>,
+    mut drives: Query<&mut DriveWear>,
+    mut report_writer: EventWriter<WorkerReport>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    for done in lane.finish_due(now_tick) {
+        if done.replaces_drives {
+            if let Ok(mut wear) = drives.get_mut(done.yard) {
+                wear.replace();
+            }
+        }
+        report_writer.send(WorkerReport::Completed { job_id: done.job_id });
+    }
+}
+
+/// A yard the heat forecast says should be cooled before it reaches the throttle knee
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MaintenancePlan {
+    pub yard_id: String,
+    pub kind: WorkyardKind,
+    pub eta_minutes: f32,     // sim minutes until the knee; 0 if it is there already
+    pub forecast_heat: f32,   // peak over the horizon
+    pub knee_heat: f32,
+    pub reason: String,
+}
+
+/// Yards forecast to reach `knee` of their heat cap within the horizon, soonest first
+pub fn plan_maintenance(forecast: &ForecastReport, knee: f32) -> Vec<MaintenancePlan> {
+    let mut plans: Vec<MaintenancePlan> = forecast.yards.iter()
+        .filter_map(|(yard_id, yard)| {
+            let knee_heat = yard.heat_cap * knee;
+            let eta_minutes = yard.heat.minutes_until(knee_heat, forecast.window_minutes)?;
+            let reason = if eta_minutes == 0.0 {
+                format!("{:?} yard {} is at {:.0}C, past its {:.0}C throttle knee", yard.kind, yard_id, yard.heat.current, knee_heat)
+            } else {
+                format!(
+                    "{:?} yard {} forecast to heat from {:.0}C to {:.0}C, past its {:.0}C throttle knee in {:.0} min",
+                    yard.kind, yard_id, yard.heat.current, yard.heat.peak, knee_heat, eta_minutes
+                )
+            };
+            Some(MaintenancePlan {
+                yard_id: yard_id.clone(),
+                kind: yard.kind.clone(),
+                eta_minutes,
+                forecast_heat: yard.heat.peak,
+                knee_heat,
+                reason,
+            })
+        })
+        .collect();
+    plans.sort_by(|a, b| a.eta_minutes.total_cmp(&b.eta_minutes));
+    plans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserved_slots() {
+        let mut lane = MaintenanceLane::new();
+        assert_eq!(lane.reserved_slots(4), 1);
+        lane.set_reservation(0.5);
+        assert_eq!(lane.reserved_slots(4), 2);
+        assert_eq!(lane.reserved_slots(1), 1);
+        lane.set_reservation(0.0);
+        assert_eq!(lane.reserved_slots(4), 0);
+    }
+
+    #[test]
+    fn test_lane_is_per_yard_and_tracks_wait() {
+        let mut world = World::new();
+        let (cpu, gpu) = (world.spawn_empty().id(), world.spawn_empty().id());
+        let mut jobq = JobQueue::new();
+        let mut job_ids = JobIdAllocator::new();
+        enqueue_maintenance(cpu, &mut jobq, &mut job_ids, 10);
+        enqueue_maintenance(gpu, &mut jobq, &mut job_ids, 12);
+        enqueue_maintenance(cpu, &mut jobq, &mut job_ids, 14);
+        assert_eq!(jobq.len(), 3);
+        assert!(jobq.peek_cpu().is_empty());
+
+        let mut lane = MaintenanceLane::new();
+        let taken = lane.take_for_yard(&mut jobq, cpu, 4, 3, 20);
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].enq_tick, 10);
+        assert_eq!(lane.last_wait_ms, 160.0);
+        assert_eq!(lane.queued, 2);
+
+        // No idle workers, nothing taken
+        assert!(lane.take_for_yard(&mut jobq, gpu, 2, 0, 20).is_empty());
+        assert_eq!(lane.take_for_yard(&mut jobq, gpu, 2, 1, 20).len(), 1);
+        assert_eq!(lane.dispatched, 2);
+    }
+
+    #[test]
+    fn test_running_maintenance_finishes_after_service_time() {
+        let mut world = World::new();
+        let yard = world.spawn_empty().id();
+        let mut jobq = JobQueue::new();
+        let mut job_ids = JobIdAllocator::new();
+        enqueue_maintenance(yard, &mut jobq, &mut job_ids, 0);
+
+        let mut lane = MaintenanceLane::new();
+        let job = lane.take_for_yard(&mut jobq, yard, 4, 1, 10).remove(0).job;
+        lane.start(&job, yard, 10, 40);
+        assert!(lane.finish_due(12).is_empty());
+        let done = lane.finish_due(13);
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].job_id, job.id);
+        assert!(lane.running.is_empty());
+    }
 }
@@ -0,0 +1,326 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::mem::size_of;
+use crate::{
+    SimClock, JobQueue, EnqueuedJob, Op, KpiRingBuffer, ReplayLog, ReplayEvent, GpuBatchQueues, GpuBatchItem,
+    GpuFarm, SlaTracker, SlaWindow, Debts, ExportSink, OpCache,
+};
+
+const MB: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MemoryCategory {
+    JobQueues,
+    KpiBuffers,
+    ReplayLog,
+    GpuState,
+    SlaHistory,
+    Debts,
+    ExportBuffer,
+    OpCache,
+}
+
+impl MemoryCategory {
+    pub fn all() -> [MemoryCategory; 8] {
+        [
+            MemoryCategory::JobQueues,
+            MemoryCategory::KpiBuffers,
+            MemoryCategory::ReplayLog,
+            MemoryCategory::GpuState,
+            MemoryCategory::SlaHistory,
+            MemoryCategory::Debts,
+            MemoryCategory::ExportBuffer,
+            MemoryCategory::OpCache,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MemoryCategory::JobQueues => "job_queues",
+            MemoryCategory::KpiBuffers => "kpi_buffers",
+            MemoryCategory::ReplayLog => "replay_log",
+            MemoryCategory::GpuState => "gpu_state",
+            MemoryCategory::SlaHistory => "sla_history",
+            MemoryCategory::Debts => "debts",
+            MemoryCategory::ExportBuffer => "export_buffer",
+            MemoryCategory::OpCache => "op_cache",
+        }
+    }
+}
+
+/// Per-category byte caps; crossing `warn_fraction` of a cap logs a warning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryCaps {
+    pub job_queues: usize,
+    pub kpi_buffers: usize,
+    pub replay_log: usize,
+    pub gpu_state: usize,
+    pub sla_history: usize,
+    pub debts: usize,
+    pub export_buffer: usize,
+    #[serde(default = "default_op_cache_cap")]
+    pub op_cache: usize,
+    pub warn_fraction: f32,
+}
+
+fn default_op_cache_cap() -> usize { 64 * MB }
+
+impl Default for MemoryCaps {
+    fn default() -> Self {
+        Self {
+            job_queues: 256 * MB,
+            kpi_buffers: 16 * MB,
+            replay_log: 64 * MB,
+            gpu_state: 32 * MB,
+            sla_history: 4 * MB,
+            debts: MB,
+            export_buffer: 64 * MB,
+            op_cache: default_op_cache_cap(),
+            warn_fraction: 0.8,
+        }
+    }
+}
+
+impl MemoryCaps {
+    pub fn cap_for(&self, category: MemoryCategory) -> usize {
+        match category {
+            MemoryCategory::JobQueues => self.job_queues,
+            MemoryCategory::KpiBuffers => self.kpi_buffers,
+            MemoryCategory::ReplayLog => self.replay_log,
+            MemoryCategory::GpuState => self.gpu_state,
+            MemoryCategory::SlaHistory => self.sla_history,
+            MemoryCategory::Debts => self.debts,
+            MemoryCategory::ExportBuffer => self.export_buffer,
+            MemoryCategory::OpCache => self.op_cache,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    pub category: MemoryCategory,
+    pub bytes: usize,
+    pub cap_bytes: usize,
+    pub near_cap: bool,
+}
+
+/// Periodically sampled estimate of heap held by the major sim resources
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct MemoryAccounting {
+    pub caps: MemoryCaps,
+    pub sample_every_ticks: u64,
+    pub last_sample_tick: Option<u64>,
+    pub usage: Vec<MemoryUsage>,
+    pub total_bytes: usize,
+    pub peak_total_bytes: usize,
+    pub warnings: Vec<String>, // categories currently above the warn threshold
+}
+
+impl Default for MemoryAccounting {
+    fn default() -> Self {
+        Self {
+            caps: MemoryCaps::default(),
+            sample_every_ticks: 60,
+            last_sample_tick: None,
+            usage: Vec::new(),
+            total_bytes: 0,
+            peak_total_bytes: 0,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl MemoryAccounting {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn due(&self, now_tick: u64) -> bool {
+        match self.last_sample_tick {
+            None => true,
+            Some(last) => now_tick.saturating_sub(last) >= self.sample_every_ticks,
+        }
+    }
+
+    /// Record a sample; returns warnings for categories that just crossed their threshold
+    pub fn record(&mut self, samples: &[(MemoryCategory, usize)], now_tick: u64) -> Vec<String> {
+        let mut new_warnings = Vec::new();
+        let mut still_warning = Vec::new();
+
+        self.usage = samples
+            .iter()
+            .map(|&(category, bytes)| {
+                let cap_bytes = self.caps.cap_for(category);
+                let near_cap = bytes as f64 >= cap_bytes as f64 * self.caps.warn_fraction as f64;
+                if near_cap {
+                    let name = category.name().to_string();
+                    if !self.warnings.contains(&name) {
+                        new_warnings.push(format!(
+                            "{} at {:.1} MB of {:.1} MB cap",
+                            name,
+                            bytes as f64 / MB as f64,
+                            cap_bytes as f64 / MB as f64
+                        ));
+                    }
+                    still_warning.push(name);
+                }
+                MemoryUsage { category, bytes, cap_bytes, near_cap }
+            })
+            .collect();
+
+        self.warnings = still_warning;
+        self.total_bytes = self.usage.iter().map(|u| u.bytes).sum();
+        self.peak_total_bytes = self.peak_total_bytes.max(self.total_bytes);
+        self.last_sample_tick = Some(now_tick);
+        new_warnings
+    }
+
+    pub fn bytes_for(&self, category: MemoryCategory) -> usize {
+        self.usage.iter().find(|u| u.category == category).map(|u| u.bytes).unwrap_or(0)
+    }
+}
+
+fn vec_bytes<T>(v: &Vec<T>) -> usize {
+    v.capacity() * size_of::<T>()
+}
+
+fn enqueued_jobs_bytes(jobs: &Vec<EnqueuedJob>) -> usize {
+    vec_bytes(jobs)
+        + jobs.iter()
+            .map(|ej| {
+                ej.job.pipeline.ops.capacity() * size_of::<Op>()
+                    + ej.job.pipeline.mutation_tag.as_ref().map_or(0, |t| t.capacity())
+            })
+            .sum::<usize>()
+}
+
+pub fn job_queue_bytes(jobq: &JobQueue) -> usize {
+    enqueued_jobs_bytes(&jobq.cpu) + enqueued_jobs_bytes(&jobq.gpu) + enqueued_jobs_bytes(&jobq.io) + enqueued_jobs_bytes(&jobq.storage)
+}
+
+pub fn kpi_buffer_bytes(kpi: &KpiRingBuffer) -> usize {
+    vec_bytes(&kpi.bandwidth_util)
+        + vec_bytes(&kpi.corruption_field)
+        + vec_bytes(&kpi.gpu_thermal_events)
+        + vec_bytes(&kpi.vram_frac)
+        + vec_bytes(&kpi.power_draw)
+        + vec_bytes(&kpi.heat_levels)
+}
+
+pub fn replay_log_bytes(log: &ReplayLog) -> usize {
+    vec_bytes(&log.events)
+        + log.events.iter()
+            .map(|e| match e {
+                ReplayEvent::EnqueueJob { pipeline_id, .. } => pipeline_id.capacity(),
+                ReplayEvent::PolicyChange { policy } => policy.capacity(),
+                ReplayEvent::TunableChange { key, .. } => key.capacity(),
+                ReplayEvent::SimStart { scenario_id, .. } => scenario_id.capacity(),
+                ReplayEvent::RitualStarted { id } => id.capacity(),
+                ReplayEvent::EventFired { swan_id } => swan_id.capacity(),
+                ReplayEvent::MutationApplied { pipeline_id, kind } => pipeline_id.capacity() + kind.capacity(),
+                ReplayEvent::SchedulerFallback { scheduler, reason } => scheduler.capacity() + reason.capacity(),
+                ReplayEvent::SchedulerQuarantined { scheduler } => scheduler.capacity(),
+                ReplayEvent::Tick { .. } => 0,
+            })
+            .sum::<usize>()
+}
+
+pub fn gpu_state_bytes(queues: Option<&GpuBatchQueues>, farms: usize) -> usize {
+    let batch_bytes = queues.map_or(0, |q| {
+        q.buffers.iter()
+            .map(|(key, buf)| key.capacity() + buf.items.capacity() * size_of::<GpuBatchItem>())
+            .sum::<usize>()
+    });
+    batch_bytes + farms * size_of::<GpuFarm>()
+}
+
+pub fn sla_history_bytes(sla: &SlaTracker) -> usize {
+    sla.windows.capacity() * size_of::<SlaWindow>()
+}
+
+pub fn debts_bytes(debts: &Debts) -> usize {
+    vec_bytes(&debts.active)
+}
+
+pub fn memory_accounting_system(
+    mut accounting: ResMut<MemoryAccounting>,
+    clock: Res<SimClock>,
+    jobq: Res<JobQueue>,
+    debts: Res<Debts>,
+    kpi_buffer: Option<Res<KpiRingBuffer>>,
+    replay_log: Option<Res<ReplayLog>>,
+    gpu_queues: Option<Res<GpuBatchQueues>>,
+    sla: Option<Res<SlaTracker>>,
+    export_sink: Option<Res<ExportSink>>,
+    op_cache: Option<Res<OpCache>>,
+    farms: Query<&GpuFarm>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    if !accounting.due(now_tick) {
+        return;
+    }
+
+    let samples = [
+        (MemoryCategory::JobQueues, job_queue_bytes(&jobq)),
+        (MemoryCategory::KpiBuffers, kpi_buffer.map_or(0, |k| kpi_buffer_bytes(&k))),
+        (MemoryCategory::ReplayLog, replay_log.map_or(0, |r| replay_log_bytes(&r))),
+        (MemoryCategory::GpuState, gpu_state_bytes(gpu_queues.as_deref(), farms.iter().count())),
+        (MemoryCategory::SlaHistory, sla.map_or(0, |s| sla_history_bytes(&s))),
+        (MemoryCategory::Debts, debts_bytes(&debts)),
+        (MemoryCategory::ExportBuffer, export_sink.map_or(0, |s| s.buffer_bytes())),
+        (MemoryCategory::OpCache, op_cache.map_or(0, |c| c.used_bytes())),
+    ];
+
+    for warning in accounting.record(&samples, now_tick) {
+        println!("Memory warning: {}", warning);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warns_once_when_crossing_threshold() {
+        let mut accounting = MemoryAccounting::new();
+        accounting.caps.debts = 1000;
+
+        let warnings = accounting.record(&[(MemoryCategory::Debts, 900)], 0);
+        assert_eq!(warnings.len(), 1);
+        assert!(accounting.usage[0].near_cap);
+
+        // Still above threshold: no repeat warning
+        assert!(accounting.record(&[(MemoryCategory::Debts, 950)], 60).is_empty());
+
+        // Drops below, then crosses again
+        assert!(accounting.record(&[(MemoryCategory::Debts, 100)], 120).is_empty());
+        assert!(accounting.warnings.is_empty());
+        assert_eq!(accounting.record(&[(MemoryCategory::Debts, 990)], 180).len(), 1);
+        assert_eq!(accounting.peak_total_bytes, 990);
+    }
+
+    #[test]
+    fn test_sample_interval() {
+        let mut accounting = MemoryAccounting::new();
+        assert!(accounting.due(0));
+        accounting.record(&[], 100);
+        assert!(!accounting.due(120));
+        assert!(accounting.due(160));
+    }
+
+    #[test]
+    fn test_job_queue_bytes_grow_with_jobs() {
+        let mut jobq = JobQueue::new();
+        let empty = job_queue_bytes(&jobq);
+        for id in 0..100 {
+            jobq.push(crate::Job {
+                id,
+                pipeline: crate::get_pipeline_by_id("udp_telemetry_ingest").unwrap(),
+                qos: crate::QoS::Balanced,
+                deadline_ms: 50,
+                payload_sz: 1024,
+            }, 0);
+        }
+        assert!(job_queue_bytes(&jobq) >= empty + 100 * size_of::<EnqueuedJob>());
+    }
+}
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use colony_modsdk::abi::MAX_METRIC_NAME_LEN;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// A KPI series that Black Swan triggers, illusions, sensors and mods refer to. Built-ins are
+/// variants; mod-reported metrics are `Custom("<mod_id>:<metric>")`. Data files spell metrics as
+/// strings, and parsing rejects names that are neither a built-in nor namespaced to a mod.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum MetricId {
+    BandwidthUtil,
+    CorruptionField,
+    GpuThermalEvents,
+    VramFrac,
+    PowerDraw,
+    HeatLevels,
+    ClockDriftMs,
+    MaintenanceWaitMs,
+    QueueDeadLetterDepth,
+    Custom(Arc<str>), // "<mod_id>:<metric>", known once a mod reports it
+}
+
+impl MetricId {
+    pub const BUILTIN: [MetricId; 9] = [
+        MetricId::BandwidthUtil,
+        MetricId::CorruptionField,
+        MetricId::GpuThermalEvents,
+        MetricId::VramFrac,
+        MetricId::PowerDraw,
+        MetricId::HeatLevels,
+        MetricId::ClockDriftMs,
+        MetricId::MaintenanceWaitMs,
+        MetricId::QueueDeadLetterDepth,
+    ];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            MetricId::BandwidthUtil => "bandwidth_util",
+            MetricId::CorruptionField => "corruption_field",
+            MetricId::GpuThermalEvents => "gpu_thermal_events",
+            MetricId::VramFrac => "vram_frac",
+            MetricId::PowerDraw => "power_draw",
+            MetricId::HeatLevels => "heat_levels",
+            MetricId::ClockDriftMs => "clock_drift_ms",
+            MetricId::MaintenanceWaitMs => "maintenance_wait_ms",
+            MetricId::QueueDeadLetterDepth => "queue_dead_letter_depth",
+            MetricId::Custom(name) => name,
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<MetricId> {
+        Self::BUILTIN.into_iter().find(|m| m.as_str() == name)
+    }
+
+    /// A built-in by name, or a well-formed `<mod_id>:<metric>`
+    pub fn parse(name: &str) -> Result<MetricId> {
+        if let Some(metric) = Self::builtin(name) {
+            return Ok(metric);
+        }
+        let Some((mod_id, metric)) = name.split_once(':') else {
+            anyhow::bail!("Unknown metric {:?}; expected one of {} or <mod_id>:<metric>", name, Self::builtin_names().join(", "));
+        };
+        if mod_id.is_empty() || !is_metric_name(metric) {
+            anyhow::bail!("Malformed mod metric {:?}", name);
+        }
+        Ok(MetricId::Custom(name.into()))
+    }
+
+    /// The mod a custom metric belongs to
+    pub fn mod_id(&self) -> Option<&str> {
+        match self {
+            MetricId::Custom(name) => name.split_once(':').map(|(mod_id, _)| mod_id),
+            _ => None,
+        }
+    }
+
+    pub fn builtin_names() -> Vec<String> {
+        Self::BUILTIN.iter().map(|m| m.to_string()).collect()
+    }
+}
+
+/// Same rule the WASM host applies to `report_metric` names
+fn is_metric_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_METRIC_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+impl fmt::Display for MetricId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for MetricId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for MetricId {
+    type Error = anyhow::Error;
+
+    fn try_from(name: String) -> Result<Self> {
+        Self::parse(&name)
+    }
+}
+
+impl From<MetricId> for String {
+    fn from(metric: MetricId) -> Self {
+        metric.as_str().to_string()
+    }
+}
+
+/// Mod metrics seen this session. Names are interned, so every series and trigger that refers to
+/// the same metric shares one allocation.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct MetricRegistry {
+    custom: BTreeSet<Arc<str>>,
+}
+
+impl MetricRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register (or look up) a metric a mod reports at runtime
+    pub fn register(&mut self, mod_id: &str, metric: &str) -> Result<MetricId> {
+        let name = format!("{}:{}", mod_id, metric);
+        if let Some(existing) = self.custom.get(name.as_str()) {
+            return Ok(MetricId::Custom(existing.clone()));
+        }
+        let MetricId::Custom(interned) = MetricId::parse(&name)? else {
+            anyhow::bail!("{} shadows a built-in metric", name);
+        };
+        self.custom.insert(interned.clone());
+        Ok(MetricId::Custom(interned))
+    }
+
+    /// Drop a mod's metrics when it's unloaded
+    pub fn unregister_mod(&mut self, mod_id: &str) {
+        self.custom.retain(|name| name.split_once(':').map(|(m, _)| m) != Some(mod_id));
+    }
+
+    pub fn contains(&self, metric: &MetricId) -> bool {
+        match metric {
+            MetricId::Custom(name) => self.custom.contains(name),
+            _ => true,
+        }
+    }
+
+    /// Built-ins then registered mod metrics
+    pub fn names(&self) -> Vec<String> {
+        let mut names = MetricId::builtin_names();
+        names.extend(self.custom.iter().map(|name| name.to_string()));
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_builtins_and_mod_metrics() {
+        for metric in MetricId::BUILTIN {
+            assert_eq!(MetricId::parse(metric.as_str()).unwrap(), metric);
+        }
+        let custom = MetricId::parse("com.example.anomaly:anomaly_score").unwrap();
+        assert_eq!(custom.mod_id(), Some("com.example.anomaly"));
+        assert!(MetricId::parse("bandwith_util").is_err());
+        assert!(MetricId::parse(":anomaly_score").is_err());
+        assert!(MetricId::parse("com.example.anomaly:bad name").is_err());
+    }
+
+    #[test]
+    fn test_serde_round_trips_as_string() {
+        let json = serde_json::to_string(&MetricId::VramFrac).unwrap();
+        assert_eq!(json, "\"vram_frac\"");
+        assert_eq!(serde_json::from_str::<MetricId>(&json).unwrap(), MetricId::VramFrac);
+        assert!(serde_json::from_str::<MetricId>("\"vram_fraction\"").is_err());
+    }
+
+    #[test]
+    fn test_registry_interns_mod_metrics() {
+        let mut registry = MetricRegistry::new();
+        let a = registry.register("com.example.anomaly", "anomaly_score").unwrap();
+        let b = registry.register("com.example.anomaly", "anomaly_score").unwrap();
+        match (&a, &b) {
+            (MetricId::Custom(x), MetricId::Custom(y)) => assert!(Arc::ptr_eq(x, y)),
+            _ => panic!("expected custom metrics"),
+        }
+        assert!(registry.contains(&a) && registry.contains(&MetricId::PowerDraw));
+        assert!(registry.register("com.example.anomaly", "").is_err());
+
+        registry.unregister_mod("com.example.anomaly");
+        assert!(!registry.contains(&a));
+        assert_eq!(registry.names().len(), MetricId::BUILTIN.len());
+    }
+}
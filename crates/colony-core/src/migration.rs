@@ -0,0 +1,187 @@
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use anyhow::Result;
+use crate::{Colony, ColonyState, GameMode, SaveFileV1, SimClock, TimedCommand, tick_rng};
+
+/// Bumped when the bundle layout changes; importers refuse versions they don't know
+pub const MIGRATION_FORMAT_VERSION: u32 = 1;
+
+/// Where the sim's randomness stands. Every roll comes from `tick_rng(seed, tick)`, so the seed
+/// and the current tick are the whole RNG state; there is no stream position to carry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RngPosition {
+    pub seed: u64,
+    pub tick: u64,
+}
+
+impl RngPosition {
+    pub fn of(colony: &Colony, clock: &SimClock) -> Self {
+        Self { seed: colony.seed, tick: clock.now.timestamp_millis() as u64 / 16 }
+    }
+
+    /// First value the next tick's rng draws; lets both ends confirm they agree before resuming
+    pub fn probe(&self) -> u64 {
+        use rand::RngCore;
+        tick_rng(self.seed, self.tick + 1).next_u64()
+    }
+}
+
+/// A complete live session, exported by one headless instance and imported by another so the
+/// session resumes where it stopped: the save snapshot, the sim clock, the RNG position and
+/// commands that were accepted for a later tick but not applied yet.
+#[derive(Serialize, Deserialize)]
+pub struct LiveSession {
+    pub format_version: u32,
+    pub exported_at: chrono::DateTime<chrono::Utc>,
+    pub mode: GameMode,
+    pub clock: SimClock,
+    pub rng: RngPosition,
+    pub rng_probe: u64,
+    pub snapshot: SaveFileV1,
+    pub pending_commands: Vec<TimedCommand>, // in tick order
+    #[serde(default)]
+    pub checksum: String, // sha256 of the bundle with this field empty
+}
+
+impl LiveSession {
+    pub fn new(snapshot: SaveFileV1, clock: SimClock, mut pending_commands: Vec<TimedCommand>) -> Self {
+        let rng = RngPosition {
+            seed: snapshot.colony_state.seed,
+            tick: clock.now.timestamp_millis() as u64 / 16,
+        };
+        pending_commands.sort_by_key(|c| c.at_tick);
+        let mut session = Self {
+            format_version: MIGRATION_FORMAT_VERSION,
+            exported_at: chrono::Utc::now(),
+            mode: snapshot.game_setup.mode,
+            clock,
+            rng,
+            rng_probe: rng.probe(),
+            snapshot,
+            pending_commands,
+            checksum: String::new(),
+        };
+        session.checksum = session.compute_checksum().unwrap_or_default();
+        session
+    }
+
+    fn compute_checksum(&self) -> Result<String> {
+        let mut value = serde_json::to_value(self)?;
+        value["checksum"] = serde_json::Value::String(String::new());
+        Ok(hex::encode(Sha256::digest(serde_json::to_vec(&value)?)))
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    /// Parse and check a bundle from another instance
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let session: LiveSession = serde_json::from_slice(bytes)
+            .map_err(|e| anyhow::anyhow!("Not a live session bundle: {}", e))?;
+        session.validate()?;
+        Ok(session)
+    }
+
+    /// Refuse bundles that are damaged or wouldn't resume deterministically here
+    pub fn validate(&self) -> Result<()> {
+        if self.format_version != MIGRATION_FORMAT_VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported live session format {} (this build reads {})",
+                self.format_version, MIGRATION_FORMAT_VERSION
+            ));
+        }
+        if self.checksum != self.compute_checksum()? {
+            return Err(anyhow::anyhow!("Live session checksum mismatch; the bundle was altered or truncated"));
+        }
+        if self.rng.seed != self.snapshot.colony_state.seed {
+            return Err(anyhow::anyhow!("RNG seed {} doesn't match the snapshot's seed {}", self.rng.seed, self.snapshot.colony_state.seed));
+        }
+        if self.rng.tick != self.clock.now.timestamp_millis() as u64 / 16 {
+            return Err(anyhow::anyhow!("RNG position doesn't match the sim clock"));
+        }
+        if self.rng.probe() != self.rng_probe {
+            return Err(anyhow::anyhow!("RNG probe differs; this build's random streams don't match the exporter's"));
+        }
+        if let Some(stale) = self.pending_commands.iter().find(|c| c.at_tick < self.rng.tick) {
+            return Err(anyhow::anyhow!("Pending command for tick {} is already in the past (now {})", stale.at_tick, self.rng.tick));
+        }
+        let creative_only = self.pending_commands.iter().any(|c| matches!(
+            c.command,
+            crate::RunCommand::ForceEvent { .. } | crate::RunCommand::SetCorruption { .. }
+        ));
+        if creative_only && !self.mode.is_creative() {
+            return Err(anyhow::anyhow!("Pending commands include creative-only controls but the session isn't creative"));
+        }
+        Ok(())
+    }
+}
+
+impl ColonyState {
+    /// Rebuild the live colony resource from a snapshot
+    pub fn to_colony(&self) -> Colony {
+        Colony {
+            power_cap_kw: self.power_cap_kw,
+            bandwidth_total_gbps: self.bandwidth_total_gbps,
+            corruption_field: self.corruption_field,
+            target_uptime_days: self.target_uptime_days,
+            meters: self.meters.clone(),
+            tunables: self.tunables.clone(),
+            corruption_tun: self.corruption_tun.clone(),
+            seeds: self.seeds.clone(),
+            seed: self.seed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlackSwanIndex, Debts, GameSetup, JobIdAllocator, ReplayLog, ResearchState, RunCommand, SessionCtl, TickScale, WinLossState, KpiSummary};
+
+    fn bundle(pending: Vec<TimedCommand>) -> LiveSession {
+        let colony = crate::colony_for_scenario(None, Some(7)).unwrap();
+        let setup = GameSetup::new(crate::load_scenarios().unwrap().remove(0));
+        let kpis = KpiSummary {
+            bandwidth_util_history: Vec::new(),
+            corruption_field_history: Vec::new(),
+            power_draw_history: Vec::new(),
+            heat_levels_history: Vec::new(),
+            deadline_hit_rates: Vec::new(),
+            black_swan_events: Vec::new(),
+        };
+        let snapshot = SaveFileV1::new(
+            setup, &colony, &ResearchState::new(), &BlackSwanIndex::new(), &Debts::new(),
+            &WinLossState::default(), &SessionCtl::new(), &ReplayLog::new(), kpis,
+        ).with_job_ids(&JobIdAllocator::starting_at(40));
+        let clock = SimClock {
+            tick_scale: TickScale::Seconds(1),
+            now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH + chrono::Duration::milliseconds(16 * 1000),
+        };
+        LiveSession::new(snapshot, clock, pending)
+    }
+
+    #[test]
+    fn test_live_session_round_trips() {
+        let pending = vec![TimedCommand { at_tick: 1200, repeat_every: None, command: RunCommand::SetPowerCap { kw: 900.0 } }];
+        let bytes = bundle(pending).to_bytes().unwrap();
+        let imported = LiveSession::from_bytes(&bytes).unwrap();
+
+        assert_eq!(imported.rng, RngPosition { seed: 7, tick: 1000 });
+        assert_eq!(imported.snapshot.job_ids.peek(), 40);
+        assert_eq!(imported.snapshot.colony_state.to_colony().seed, 7);
+        assert_eq!(imported.pending_commands.len(), 1);
+    }
+
+    #[test]
+    fn test_damaged_or_stale_bundles_are_refused() {
+        let mut value: serde_json::Value = serde_json::from_slice(&bundle(Vec::new()).to_bytes().unwrap()).unwrap();
+        value["rng"]["tick"] = serde_json::json!(5);
+        assert!(LiveSession::from_bytes(&serde_json::to_vec(&value).unwrap()).is_err());
+
+        let stale = bundle(vec![TimedCommand { at_tick: 10, repeat_every: None, command: RunCommand::ReplaceDrives }]);
+        assert!(LiveSession::from_bytes(&stale.to_bytes().unwrap()).is_err());
+        let creative = bundle(vec![TimedCommand { at_tick: 2000, repeat_every: None, command: RunCommand::SetCorruption { value: 0.5 } }]);
+        assert!(LiveSession::from_bytes(&creative.to_bytes().unwrap()).is_err());
+    }
+}
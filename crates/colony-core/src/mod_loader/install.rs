@@ -0,0 +1,295 @@
+use bevy::prelude::*;
+use colony_modsdk::abi::WasmAbi;
+use colony_modsdk::{ModManifest, WasmOpSpec};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use anyhow::{Context, Result};
+use crate::{ContentPack, LuaHost, Op, SchedulerRegistry, WasmHost, WasmScheduler, mount_mod_vfs};
+use super::{ModLoader, ModOps, mod_op_id};
+
+/// Cost hints for an op: `ops/<op>.toml` if the mod ships one, else the generic WASM op costs
+fn op_spec(dir: &Path, op_name: &str, bytes: &[u8]) -> Result<WasmOpSpec> {
+    let path = dir.join("ops").join(format!("{}.toml", op_name));
+    if path.is_file() {
+        let spec: WasmOpSpec = toml::from_str(&std::fs::read_to_string(&path)?)
+            .with_context(|| format!("Failed to parse {:?}", path))?;
+        if spec.name != op_name {
+            anyhow::bail!("{:?} describes op {}, expected {}", path, spec.name, op_name);
+        }
+        return Ok(spec);
+    }
+    let generic = Op::DynamicWasm { op_id: op_name.to_string() };
+    Ok(WasmOpSpec {
+        name: op_name.to_string(),
+        version: "0.0.0".to_string(),
+        cost_hint_ms: generic.cost_ms(),
+        work_units_hint: generic.work_units(),
+        vram_hint_mb: 0.0,
+        bandwidth_hint_mb: 0.0,
+        description: None,
+        abi: WasmAbi::detect(bytes).unwrap_or_default(),
+    })
+}
+
+/// Load a mod into the running game: its WASM ops (checked against the hashes in mod.toml),
+/// schedulers, Lua event scripts, bundled files and content. A mod that fails part way is taken back out.
+pub fn install_mod(
+    manifest: &ModManifest,
+    dir: &Path,
+    wasm_host: &mut WasmHost,
+    lua_host: &mut LuaHost,
+    mod_ops: &mut ModOps,
+    schedulers: &mut SchedulerRegistry,
+    content: &mut ContentPack,
+) -> Result<()> {
+    // Apps that loaded content up front (ContentPack::load_mods) already have this mod's
+    let source = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let pack = match content.sources.contains(&source) {
+        true => None,
+        false => Some(ContentPack::load_dir(dir)?),
+    };
+
+    let outcome = (|| -> Result<()> {
+        let mut specs = Vec::new();
+        for op_name in &manifest.entrypoints.wasm_ops {
+            let path = dir.join("ops").join(format!("{}.wasm", op_name));
+            let bytes = std::fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+            if let Some(expected) = manifest.op_hashes.get(op_name) {
+                if hex::encode(Sha256::digest(&bytes)) != *expected {
+                    anyhow::bail!("{:?} doesn't match the hash in mod.toml", path);
+                }
+            }
+            let spec = op_spec(dir, op_name, &bytes)?;
+            wasm_host.load_module(&mod_op_id(&manifest.id, op_name), &bytes)
+                .with_context(|| format!("Failed to load WASM op {}", op_name))?;
+            specs.push(spec);
+        }
+        if !manifest.entrypoints.schedulers.is_empty() && !manifest.capabilities.scheduler {
+            anyhow::bail!("{} ships schedulers but lacks the scheduler capability", manifest.id);
+        }
+        let mut mod_schedulers = Vec::new();
+        for name in &manifest.entrypoints.schedulers {
+            let path = dir.join("schedulers").join(format!("{}.wasm", name));
+            let bytes = std::fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+            let scheduler = WasmScheduler::new(&mod_op_id(&manifest.id, name), &wasm_host.engine, &bytes)
+                .with_context(|| format!("Failed to load scheduler {}", name))?;
+            mod_schedulers.push(scheduler);
+        }
+        lua_host.load_mod_events(&manifest.id, &dir.join("scripts"), &manifest.entrypoints.lua_events)?;
+        mount_mod_vfs(manifest, dir, wasm_host, lua_host)?;
+        for spec in specs {
+            mod_ops.register(&manifest.id, spec);
+        }
+        for scheduler in mod_schedulers {
+            schedulers.register(std::sync::Arc::new(scheduler))?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = outcome {
+        uninstall_mod(manifest, wasm_host, lua_host, mod_ops, schedulers);
+        return Err(e);
+    }
+    if let Some(pack) = pack {
+        content.merge(pack);
+    }
+    Ok(())
+}
+
+/// Take a mod's ops, schedulers and scripts out of the hosts
+pub fn uninstall_mod(manifest: &ModManifest, wasm_host: &mut WasmHost, lua_host: &mut LuaHost, mod_ops: &mut ModOps, schedulers: &mut SchedulerRegistry) {
+    for op_name in &manifest.entrypoints.wasm_ops {
+        wasm_host.unload_module(&mod_op_id(&manifest.id, op_name));
+    }
+    wasm_host.store.data_mut().vfs.remove(&manifest.id);
+    lua_host.unload_mod(&manifest.id);
+    mod_ops.unregister_mod(&manifest.id);
+    schedulers.unregister_mod(&manifest.id);
+}
+
+/// Startup: discover the mods directory, install every valid mod in load order, and drop
+/// content pipelines that name WASM ops no installed mod provides
+pub fn initialize_mod_loader_system(
+    mut mod_loader: ResMut<ModLoader>,
+    mut wasm_host: ResMut<WasmHost>,
+    mut lua_host: ResMut<LuaHost>,
+    mut mod_ops: ResMut<ModOps>,
+    mut content: ResMut<ContentPack>,
+    mut schedulers: ResMut<SchedulerRegistry>,
+) {
+    if let Err(e) = mod_loader.discover_mods() {
+        eprintln!("Failed to scan {:?} for mods: {:#}", mod_loader.mods_dir, e);
+        return;
+    }
+    let discovered = mod_loader.registry.load_order.clone();
+    for mod_id in discovered {
+        let (manifest, dir) = (mod_loader.registry.mods[&mod_id].clone(), mod_loader.registry.dirs[&mod_id].clone());
+        match install_mod(&manifest, &dir, &mut wasm_host, &mut lua_host, &mut mod_ops, &mut schedulers, &mut content) {
+            Ok(()) => {
+                let _ = mod_loader.enable_mod(&mod_id);
+                println!("Loaded mod {} {} ({} WASM ops, {} schedulers, {} Lua events)",
+                    mod_id, manifest.version, manifest.entrypoints.wasm_ops.len(), manifest.entrypoints.schedulers.len(), manifest.entrypoints.lua_events.len());
+            }
+            Err(e) => {
+                eprintln!("Failed to load mod {}: {:#}", mod_id, e);
+                mod_loader.rejected.push((dir, format!("{:#}", e)));
+            }
+        }
+    }
+
+    for (owner, metric) in content.metric_refs() {
+        if let Some(mod_id) = metric.mod_id() {
+            if !mod_loader.enabled_mods.iter().any(|id| id == mod_id) {
+                eprintln!("{} reads {}, but mod {} isn't loaded", owner, metric, mod_id);
+            }
+        }
+    }
+
+    content.pipelines.retain(|def| match mod_ops.check_pipeline(def) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("Dropping {}", e);
+            false
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PipelineDef;
+    use std::path::PathBuf;
+
+    const EMPTY_MODULE: &[u8] = b"\0asm\x01\0\0\0";
+
+    fn mods_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("colony_mods_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_mod(mods: &Path, dir_name: &str, id: &str, extra: &str) -> PathBuf {
+        let dir = mods.join(dir_name);
+        std::fs::create_dir_all(dir.join("ops")).unwrap();
+        std::fs::create_dir_all(dir.join("scripts")).unwrap();
+        std::fs::write(dir.join("ops/Op_Fft.wasm"), EMPTY_MODULE).unwrap();
+        std::fs::write(dir.join("scripts/on_tick.lua"), "return function(arg) end").unwrap();
+        std::fs::write(dir.join("mod.toml"), format!(r#"
+id = "{}"
+name = "FFT"
+version = "1.0.0"
+authors = ["tester"]
+{}
+[entrypoints]
+wasm_ops = ["Op_Fft"]
+lua_events = ["on_tick.lua"]
+
+[capabilities]
+sim_time = true
+rng = false
+metrics_read = false
+enqueue_job = false
+log_debug = false
+modify_tunables = false
+trigger_events = false
+"#, id, extra)).unwrap();
+        dir
+    }
+
+    fn startup(mods: &Path, content: ContentPack) -> App {
+        let mut app = App::new();
+        app.insert_resource(ModLoader::new(mods.to_path_buf()))
+            .insert_resource(WasmHost::new())
+            .insert_resource(LuaHost::new())
+            .insert_resource(ModOps::new())
+            .insert_resource(SchedulerRegistry::new())
+            .insert_resource(content)
+            .add_systems(Startup, initialize_mod_loader_system);
+        app.update();
+        app
+    }
+
+    #[test]
+    fn test_startup_installs_valid_mods() {
+        let mods = mods_dir("install");
+        let dir = write_mod(&mods, "fft", "com.example.fft", "");
+        std::fs::write(dir.join("pipelines.toml"), r#"
+[[pipeline]]
+id = "fft_ingest"
+ops = ["UdpDemux", "wasm:com.example.fft/Op_Fft", "Export"]
+qos = "Balanced"
+deadline_ms = 1000
+payload_sz = 1024
+"#).unwrap();
+        write_mod(&mods, "broken", "", "");
+
+        let app = startup(&mods, ContentPack::default());
+        let loader = app.world().resource::<ModLoader>();
+        assert_eq!(loader.enabled_mods, vec!["com.example.fft"]);
+        assert_eq!(loader.rejected.len(), 1);
+        assert!(app.world().resource::<WasmHost>().abi_of("com.example.fft/Op_Fft").is_some());
+        assert_eq!(app.world().resource::<LuaHost>().mods_with_hook("on_tick"), vec!["com.example.fft"]);
+
+        let mod_ops = app.world().resource::<ModOps>();
+        let pipeline = app.world().resource::<ContentPack>().pipeline("fft_ingest").unwrap();
+        assert!(matches!(&pipeline.ops[1], Op::DynamicWasm { op_id } if op_id == "com.example.fft/Op_Fft"));
+        assert_eq!(mod_ops.work_units(&pipeline.ops[1]), 1.0);
+        std::fs::remove_dir_all(&mods).ok();
+    }
+
+    #[test]
+    fn test_hash_mismatch_rolls_back() {
+        let mods = mods_dir("hash");
+        write_mod(&mods, "fft", "com.example.fft", "[op_hashes]\nOp_Fft = \"00\"\n");
+        let app = startup(&mods, ContentPack::default());
+        assert!(app.world().resource::<ModLoader>().enabled_mods.is_empty());
+        assert!(app.world().resource::<ModOps>().specs.is_empty());
+        assert!(app.world().resource::<LuaHost>().scripts.is_empty());
+        assert!(app.world().resource::<WasmHost>().abi_of("com.example.fft/Op_Fft").is_none());
+        std::fs::remove_dir_all(&mods).ok();
+    }
+
+    #[test]
+    fn test_schedulers_need_the_capability() {
+        // `priority` returns the deadline, making this an EDF
+        const BY_DEADLINE: &[u8] = &[
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+            0x01, 0x09, 0x01, 0x60, 0x04, 0x7e, 0x7f, 0x7f, 0x7f, 0x01, 0x7e,
+            0x03, 0x02, 0x01, 0x00,
+            0x07, 0x0c, 0x01, 0x08, b'p', b'r', b'i', b'o', b'r', b'i', b't', b'y', 0x00, 0x00,
+            0x0a, 0x06, 0x01, 0x04, 0x00, 0x20, 0x00, 0x0b,
+        ];
+        let mods = mods_dir("schedulers");
+        for (dir_name, id) in [("granted", "com.example.sched"), ("denied", "com.example.nosched")] {
+            let dir = write_mod(&mods, dir_name, id, "");
+            std::fs::create_dir_all(dir.join("schedulers")).unwrap();
+            std::fs::write(dir.join("schedulers/Deadline.wasm"), BY_DEADLINE).unwrap();
+            let manifest = std::fs::read_to_string(dir.join("mod.toml")).unwrap()
+                .replace("lua_events", "schedulers = [\"Deadline\"]\nlua_events");
+            let manifest = if dir_name == "granted" { manifest.replace("trigger_events = false", "trigger_events = false\nscheduler = true") } else { manifest };
+            std::fs::write(dir.join("mod.toml"), manifest).unwrap();
+        }
+        let app = startup(&mods, ContentPack::default());
+        assert_eq!(app.world().resource::<ModLoader>().enabled_mods, vec!["com.example.sched"]);
+        let names = app.world().resource::<SchedulerRegistry>().names();
+        assert!(names.contains(&"com.example.sched/Deadline".to_string()));
+        assert!(!names.iter().any(|n| n.starts_with("com.example.nosched")));
+        std::fs::remove_dir_all(&mods).ok();
+    }
+
+    #[test]
+    fn test_pipelines_with_missing_ops_dropped() {
+        let mods = mods_dir("orphans");
+        let mut content = ContentPack::default();
+        content.pipelines.push(PipelineDef {
+            id: "orphan".into(),
+            ops: vec!["wasm:com.example.gone/Op_Fft".into()],
+            qos: "Balanced".into(),
+            deadline_ms: 1000,
+            payload_sz: 1024,
+        });
+        let app = startup(&mods, content);
+        assert!(app.world().resource::<ContentPack>().pipelines.is_empty());
+        std::fs::remove_dir_all(&mods).ok();
+    }
+}
@@ -1,7 +1,13 @@
 pub mod registry;
 pub mod discovery;
 pub mod validation;
+pub mod mod_ops;
+#[cfg(feature = "native")]
+pub mod install;
 
 pub use registry::*;
 pub use discovery::*;
 pub use validation::*;
+pub use mod_ops::*;
+#[cfg(feature = "native")]
+pub use install::*;
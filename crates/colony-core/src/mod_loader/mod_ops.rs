@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use colony_modsdk::WasmOpSpec;
+use std::collections::BTreeMap;
+use crate::{Op, PipelineDef};
+
+/// Prefix a pipeline uses to name a mod's WASM op, e.g. `"wasm:com.example.fft/Op_AdaptiveFft"`
+pub const WASM_OP_PREFIX: &str = "wasm:";
+/// Prefix a pipeline uses to name a Lua op, e.g. `"lua:smooth"`
+pub const LUA_OP_PREFIX: &str = "lua:";
+
+/// Id a mod's op goes by in pipelines and the WASM host: `<mod id>/<op name>`
+pub fn mod_op_id(mod_id: &str, op_name: &str) -> String {
+    format!("{}/{}", mod_id, op_name)
+}
+
+/// The mod an op id belongs to; ids without a `/` name the mod itself
+pub fn op_mod_id(op_id: &str) -> &str {
+    op_id.split('/').next().unwrap_or(op_id)
+}
+
+/// WASM ops installed from mods, with the cost hints dispatch charges for them
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ModOps {
+    pub specs: BTreeMap<String, WasmOpSpec>, // op id -> spec
+}
+
+impl ModOps {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, mod_id: &str, spec: WasmOpSpec) {
+        self.specs.insert(mod_op_id(mod_id, &spec.name), spec);
+    }
+
+    /// Drop every op a mod registered
+    pub fn unregister_mod(&mut self, mod_id: &str) {
+        let prefix = format!("{}/", mod_id);
+        self.specs.retain(|id, _| !id.starts_with(&prefix));
+    }
+
+    pub fn get(&self, op_id: &str) -> Option<&WasmOpSpec> {
+        self.specs.get(op_id)
+    }
+
+    /// Work units for an op, from the mod's hint for installed WASM ops
+    pub fn work_units(&self, op: &Op) -> f32 {
+        match op {
+            Op::DynamicWasm { op_id } => self.get(op_id).map_or_else(|| op.work_units(), |spec| spec.work_units_hint),
+            _ => op.work_units(),
+        }
+    }
+
+    pub fn cost_ms(&self, op: &Op) -> u32 {
+        match op {
+            Op::DynamicWasm { op_id } => self.get(op_id).map_or_else(|| op.cost_ms(), |spec| spec.cost_hint_ms),
+            _ => op.cost_ms(),
+        }
+    }
+
+    /// Every `wasm:` op the pipeline names is installed
+    pub fn check_pipeline(&self, def: &PipelineDef) -> Result<(), String> {
+        match def.ops.iter().filter_map(|op| op.strip_prefix(WASM_OP_PREFIX)).find(|id| !self.specs.contains_key(*id)) {
+            Some(missing) => Err(format!("pipeline {} uses WASM op {} which no loaded mod provides", def.id, missing)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, cost_hint_ms: u32, work_units_hint: f32) -> WasmOpSpec {
+        WasmOpSpec {
+            name: name.into(),
+            version: "1.0.0".into(),
+            cost_hint_ms,
+            work_units_hint,
+            vram_hint_mb: 0.0,
+            bandwidth_hint_mb: 0.0,
+            description: None,
+            abi: Default::default(),
+        }
+    }
+
+    fn def(ops: &[&str]) -> PipelineDef {
+        PipelineDef {
+            id: "fft_ingest".into(),
+            ops: ops.iter().map(|s| s.to_string()).collect(),
+            qos: "Balanced".into(),
+            deadline_ms: 1000,
+            payload_sz: 1024,
+        }
+    }
+
+    #[test]
+    fn test_pipelines_name_mod_ops() {
+        let pipeline = def(&["Decode", "wasm:com.example.fft/Op_Fft", "lua:smooth"]).to_pipeline().unwrap();
+        assert!(matches!(&pipeline.ops[1], Op::DynamicWasm { op_id } if op_id == "com.example.fft/Op_Fft"));
+        assert!(matches!(&pipeline.ops[2], Op::DynamicLua { func } if func == "smooth"));
+        assert!(def(&["wasm:"]).to_pipeline().is_err());
+        assert_eq!(op_mod_id("com.example.fft/Op_Fft"), "com.example.fft");
+    }
+
+    #[test]
+    fn test_costs_from_installed_specs() {
+        let mut ops = ModOps::new();
+        let fft = Op::DynamicWasm { op_id: "com.example.fft/Op_Fft".into() };
+        assert!(ops.check_pipeline(&def(&["wasm:com.example.fft/Op_Fft"])).is_err());
+        assert_eq!(ops.cost_ms(&fft), fft.cost_ms());
+
+        ops.register("com.example.fft", spec("Op_Fft", 40, 6.0));
+        assert!(ops.check_pipeline(&def(&["Decode", "wasm:com.example.fft/Op_Fft"])).is_ok());
+        assert_eq!((ops.cost_ms(&fft), ops.work_units(&fft)), (40, 6.0));
+        assert_eq!(ops.work_units(&Op::Decode), Op::Decode.work_units());
+
+        ops.unregister_mod("com.example.fft");
+        assert!(ops.specs.is_empty());
+    }
+}
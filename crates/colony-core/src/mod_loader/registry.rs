@@ -1,19 +1,22 @@
 use bevy::prelude::*;
 use colony_modsdk::ModManifest;
 use std::collections::HashMap;
-use std::path::PathBuf;
-use anyhow::Result;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use super::validate_mod_manifest;
 
 #[derive(Resource)]
 pub struct ModLoader {
     pub mods_dir: PathBuf,
     pub registry: ModRegistry,
     pub enabled_mods: Vec<String>,
+    pub rejected: Vec<(PathBuf, String)>, // mod directories that failed validation, with the reason
 }
 
 #[derive(Clone)]
 pub struct ModRegistry {
     pub mods: HashMap<String, ModManifest>,
+    pub dirs: HashMap<String, PathBuf>, // mod id -> directory it was read from
     pub load_order: Vec<String>,
 }
 
@@ -23,32 +26,88 @@ impl Default for ModLoader {
     }
 }
 
+/// Read and validate `<dir>/mod.toml`
+pub fn read_mod_manifest(dir: &Path) -> Result<ModManifest> {
+    let path = dir.join("mod.toml");
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    let manifest: ModManifest = toml::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))?;
+    validate_mod_manifest(&manifest)?;
+    let result = manifest.validate();
+    if !result.valid {
+        anyhow::bail!("{}", result.errors.join("; "));
+    }
+    Ok(manifest)
+}
+
 impl ModLoader {
     pub fn new(mods_dir: PathBuf) -> Self {
         Self {
             mods_dir,
             registry: ModRegistry {
                 mods: HashMap::new(),
+                dirs: HashMap::new(),
                 load_order: Vec::new(),
             },
             enabled_mods: Vec::new(),
+            rejected: Vec::new(),
         }
     }
 
+    /// Scan the mods directory in name order. Every subdirectory with a valid mod.toml is
+    /// registered; invalid ones and duplicate ids are recorded in `rejected` and skipped.
+    /// Data-only mods without a mod.toml (like vanilla) are left to ContentPack.
     pub fn discover_mods(&mut self) -> Result<()> {
-        // Scan the mods directory for mod manifests
-        // This is a simplified implementation
+        let mut dirs: Vec<PathBuf> = match std::fs::read_dir(&self.mods_dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.join("mod.toml").is_file())
+                .collect(),
+            Err(_) => return Ok(()), // no mods directory, nothing to load
+        };
+        dirs.sort();
+
+        self.rejected.clear();
+        for dir in dirs {
+            let outcome = read_mod_manifest(&dir).and_then(|manifest| {
+                if self.registry.dirs.get(&manifest.id).is_some_and(|known| known != &dir) {
+                    anyhow::bail!("mod id {} is already used by {:?}", manifest.id, self.registry.dirs[&manifest.id]);
+                }
+                Ok(manifest)
+            });
+            match outcome {
+                Ok(manifest) => self.register(manifest, dir),
+                Err(e) => {
+                    eprintln!("Skipping mod in {:?}: {:#}", dir, e);
+                    self.rejected.push((dir, format!("{:#}", e)));
+                }
+            }
+        }
         Ok(())
     }
 
+    fn register(&mut self, manifest: ModManifest, dir: PathBuf) {
+        if !self.registry.load_order.contains(&manifest.id) {
+            self.registry.load_order.push(manifest.id.clone());
+        }
+        self.registry.dirs.insert(manifest.id.clone(), dir);
+        self.registry.mods.insert(manifest.id.clone(), manifest);
+    }
+
+    /// Re-read a discovered mod's manifest and enable it
     pub fn load_mod(&mut self, mod_id: &str) -> Result<()> {
-        // Load a specific mod by ID
-        // This would involve loading the manifest, validating it, and registering it
-        Ok(())
+        let dir = self.registry.dirs.get(mod_id).cloned()
+            .with_context(|| format!("Unknown mod {}", mod_id))?;
+        let manifest = read_mod_manifest(&dir)?;
+        if manifest.id != mod_id {
+            anyhow::bail!("{:?} now declares mod id {}, expected {}", dir, manifest.id, mod_id);
+        }
+        self.register(manifest, dir);
+        self.enable_mod(mod_id)
     }
 
     pub fn unload_mod(&mut self, mod_id: &str) -> Result<()> {
-        // Unload a specific mod
+        // Unload a specific mod; its directory is kept so it can be loaded again
         self.registry.mods.remove(mod_id);
         self.registry.load_order.retain(|id| id != mod_id);
         self.enabled_mods.retain(|id| id != mod_id);
@@ -74,11 +133,11 @@ impl ModLoader {
         self.load_mod(mod_id)?;
         Ok(())
     }
-}
 
-pub fn initialize_mod_loader_system(
-    mut mod_loader: ResMut<ModLoader>,
-) {
-    // Initialize the mod loader
-    // This would scan for mods and load them
+    /// Enabled mods with their manifest and directory, in load order
+    pub fn enabled(&self) -> impl Iterator<Item = (&ModManifest, &Path)> {
+        self.registry.load_order.iter()
+            .filter(|id| self.enabled_mods.contains(id))
+            .filter_map(|id| Some((self.registry.mods.get(id)?, self.registry.dirs.get(id)?.as_path())))
+    }
 }
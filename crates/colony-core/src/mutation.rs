@@ -55,6 +55,7 @@ impl PipelineGenome {
         Pipeline {
             ops: self.ops.clone(),
             mutation_tag: Some(self.gene.tags.join(",")),
+            id: Some(self.id.clone()),
         }
     }
 }
@@ -158,11 +159,15 @@ fn parse_op_from_string(op_str: &str) -> Option<Op> {
         "UdpDemux" => Some(Op::UdpDemux),
         "TcpSessionize" => Some(Op::TcpSessionize),
         "ModbusMap" => Some(Op::ModbusMap),
+        "MqttParse" => Some(Op::MqttParse),
+        "GrpcParse" => Some(Op::GrpcParse),
         "HttpParse" => Some(Op::HttpParse),
         "Export" => Some(Op::Export),
+        "ArchiveWrite" => Some(Op::ArchiveWrite),
         "GpuPreprocess" => Some(Op::GpuPreprocess),
         "GpuExport" => Some(Op::GpuExport),
         "MaintenanceCool" => Some(Op::MaintenanceCool),
+        "ReplaceDrives" => Some(Op::ReplaceDrives),
         _ => None,
     }
 }
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use colony_modsdk::Capabilities;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use crate::{EventJournal, FaultKpi, JournalEvent, ResearchState, SimClock};
+
+/// Notifications kept for a reader that hasn't drained the feed; older ones are dropped
+const MAX_PENDING: usize = 1024;
+
+/// What a notification is about; subscribers pick topics
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    BlackSwans, // events firing
+    Faults,     // new worker faults
+    Research,   // techs acquired, rituals completed
+    Journal,    // day closes, victory, loss
+}
+
+impl Topic {
+    pub fn all() -> [Topic; 4] {
+        [Topic::BlackSwans, Topic::Faults, Topic::Research, Topic::Journal]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topic::BlackSwans => "black_swans",
+            Topic::Faults => "faults",
+            Topic::Research => "research",
+            Topic::Journal => "journal",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Topic> {
+        Topic::all().into_iter().find(|topic| topic.name() == name)
+    }
+
+    /// The mod capability that reads the same state, and so gates a subscription
+    pub fn capability(&self) -> &'static str {
+        match self {
+            Topic::BlackSwans | Topic::Faults | Topic::Research => "metrics_read",
+            Topic::Journal => "sim_time",
+        }
+    }
+
+    pub fn allowed(&self, caps: &Capabilities) -> bool {
+        match self.capability() {
+            "metrics_read" => caps.metrics_read,
+            _ => caps.sim_time,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Notification {
+    pub seq: u64, // gaps mean a reader fell behind
+    pub tick: u64,
+    pub topic: Topic,
+    pub event: String,
+    pub detail: serde_json::Value,
+}
+
+/// Notifications raised since the feed was last drained, from journal entries and KPI changes
+#[derive(Resource, Debug, Default)]
+pub struct NotificationFeed {
+    next_seq: u64,
+    pending: VecDeque<Notification>,
+    journal_mark: Option<(u64, usize)>, // tick of the newest journal entry seen, and how many at that tick
+    faults_seen: u32,
+    techs_seen: usize,
+}
+
+impl NotificationFeed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, tick: u64, topic: Topic, event: &str, detail: serde_json::Value) {
+        self.next_seq += 1;
+        self.pending.push_back(Notification { seq: self.next_seq, tick, topic, event: event.to_string(), detail });
+        if self.pending.len() > MAX_PENDING {
+            self.pending.pop_front();
+        }
+    }
+
+    pub fn drain(&mut self) -> Vec<Notification> {
+        self.pending.drain(..).collect()
+    }
+
+    fn fresh_journal_entries<'a>(&mut self, journal: &'a EventJournal) -> Vec<&'a crate::JournalEntry> {
+        let mut at_mark = 0;
+        let fresh: Vec<_> = journal.entries.iter()
+            .filter(|entry| match self.journal_mark {
+                Some((tick, _)) if entry.tick < tick => false,
+                Some((tick, seen)) if entry.tick == tick => {
+                    at_mark += 1;
+                    at_mark > seen
+                }
+                _ => true,
+            })
+            .collect();
+        if let Some(last) = journal.entries.back() {
+            let at_last = journal.entries.iter().rev().take_while(|e| e.tick == last.tick).count();
+            self.journal_mark = Some((last.tick, at_last));
+        }
+        fresh
+    }
+}
+
+fn journal_topic(event: &JournalEvent) -> Topic {
+    match event {
+        JournalEvent::BlackSwanFired { .. } => Topic::BlackSwans,
+        JournalEvent::RitualCompleted { .. } => Topic::Research,
+        JournalEvent::DayClosed { .. } | JournalEvent::Victory | JournalEvent::Loss { .. } => Topic::Journal,
+    }
+}
+
+/// Runs after the sim step, so a tick's notifications are complete when the feed is drained
+pub fn notification_system(
+    mut feed: ResMut<NotificationFeed>,
+    clock: Res<SimClock>,
+    faults: Res<FaultKpi>,
+    journal: Option<Res<EventJournal>>,
+    research: Option<Res<ResearchState>>,
+) {
+    let tick = clock.now.timestamp_millis() as u64 / 16;
+    if let Some(journal) = journal {
+        for entry in feed.fresh_journal_entries(&journal) {
+            let detail = serde_json::to_value(&entry.event).unwrap_or_default();
+            feed.push(entry.tick, journal_topic(&entry.event), entry.event.name(), detail);
+        }
+    }
+    if faults.total_faults > feed.faults_seen {
+        let new = faults.total_faults - feed.faults_seen;
+        feed.faults_seen = faults.total_faults;
+        feed.push(tick, Topic::Faults, "WorkerFaults", serde_json::json!({
+            "new": new,
+            "total": faults.total_faults,
+            "sticky_workers": faults.sticky_workers,
+        }));
+    }
+    if let Some(research) = research {
+        let acquired = research.acquired.get(feed.techs_seen..).unwrap_or_default().to_vec();
+        feed.techs_seen = research.acquired.len();
+        for tech_id in acquired {
+            feed.push(tick, Topic::Research, "TechAcquired", serde_json::json!({ "tech_id": tech_id, "pts": research.pts }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.insert_resource(NotificationFeed::new())
+            .insert_resource(SimClock { tick_scale: crate::TickScale::Seconds(1), now: chrono::Utc::now() })
+            .insert_resource(FaultKpi::new())
+            .insert_resource(EventJournal::new())
+            .insert_resource(ResearchState::new())
+            .add_systems(Update, notification_system);
+        app
+    }
+
+    #[test]
+    fn test_each_change_is_notified_once() {
+        let mut app = app();
+        app.world_mut().resource_mut::<EventJournal>().push(10, JournalEvent::BlackSwanFired { swan_id: "pcie_link_flap".into() });
+        app.world_mut().resource_mut::<FaultKpi>().total_faults = 3;
+        app.update();
+        let first = app.world_mut().resource_mut::<NotificationFeed>().drain();
+        assert_eq!(first.iter().map(|n| n.topic).collect::<Vec<_>>(), vec![Topic::BlackSwans, Topic::Faults]);
+        assert_eq!(first[1].detail["new"], 3);
+
+        // A second entry at the same tick is new; the first isn't repeated
+        app.world_mut().resource_mut::<EventJournal>().push(10, JournalEvent::Victory);
+        app.world_mut().resource_mut::<ResearchState>().acquired.push("truth_beacon".into());
+        app.update();
+        let second = app.world_mut().resource_mut::<NotificationFeed>().drain();
+        assert_eq!(second.iter().map(|n| n.event.as_str()).collect::<Vec<_>>(), vec!["Victory", "TechAcquired"]);
+        assert!(second[0].seq > first[1].seq);
+        app.update();
+        assert!(app.world_mut().resource_mut::<NotificationFeed>().drain().is_empty());
+    }
+
+    #[test]
+    fn test_topics_follow_mod_capabilities() {
+        let caps = Capabilities { metrics_read: true, ..Default::default() };
+        assert!(Topic::Faults.allowed(&caps));
+        assert!(!Topic::Journal.allowed(&caps));
+        assert_eq!(Topic::parse("black_swans"), Some(Topic::BlackSwans));
+        assert_eq!(Topic::parse("weather"), None);
+    }
+}
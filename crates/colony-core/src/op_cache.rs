@@ -0,0 +1,269 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+use crate::{Op, ResearchState, TechGrant, TechTree};
+
+/// Tech tunable that scales the cache, e.g. `{ Tunable = { key = "op_cache_mb", mult = 4.0 } }`
+pub const OP_CACHE_TUNABLE: &str = "op_cache_mb";
+const MB: usize = 1024 * 1024;
+
+/// FNV-1a over a payload, so the same bytes address the same cache entry in every run
+pub fn payload_digest(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, b| (h ^ *b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Address of `op`'s result for an input with the given digest; it is also the digest of that result
+fn cache_key(op: &Op, input: u64) -> u64 {
+    let name = format!("{:?}", op);
+    payload_digest(name.as_bytes()) ^ input.rotate_left(17).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
+
+/// Ops whose result depends only on their input. Exports, archive writes, maintenance,
+/// session tracking and Lua scripts have effects or state, so they always run.
+pub fn is_memoizable(op: &Op) -> bool {
+    !matches!(
+        op,
+        Op::Export | Op::HttpExport | Op::ArchiveWrite | Op::GpuExport | Op::TcpSessionize
+            | Op::MaintenanceCool | Op::ReplaceDrives | Op::DynamicLua { .. }
+    )
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpCacheConfig {
+    pub enabled: bool,
+    pub base_capacity_mb: f32, // before tech multipliers
+}
+
+impl OpCacheConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !self.base_capacity_mb.is_finite() || self.base_capacity_mb < 0.0 {
+            anyhow::bail!("base_capacity_mb must be zero or more, got {}", self.base_capacity_mb);
+        }
+        Ok(())
+    }
+}
+
+impl Default for OpCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            base_capacity_mb: 8.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub saved_ms: u64, // op time skipped on hits
+}
+
+impl OpCacheStats {
+    pub fn hit_rate(&self) -> f32 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 { 0.0 } else { self.hits as f32 / lookups as f32 }
+    }
+}
+
+/// What the cache saved one job
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheRun {
+    pub hits: u32,
+    pub saved_ms: u32,
+    pub saved_units: f32,
+}
+
+/// Content-addressed results of deterministic ops, evicted least recently used first.
+/// Entries are sized like the payload they stand for and count against the memory budget.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpCache {
+    pub config: OpCacheConfig,
+    pub capacity_bytes: usize, // base capacity times researched tech
+    pub stats: OpCacheStats,
+    #[serde(skip)]
+    entries: HashMap<u64, (u64, usize)>, // key -> (recency, bytes)
+    #[serde(skip)]
+    lru: BTreeMap<u64, u64>, // recency -> key, oldest first
+    #[serde(skip)]
+    next_use: u64,
+    #[serde(skip)]
+    used_bytes: usize,
+}
+
+impl OpCache {
+    pub fn new() -> Self {
+        let config = OpCacheConfig::default();
+        let capacity_bytes = (config.base_capacity_mb * MB as f32) as usize;
+        Self { config, capacity_bytes, ..Default::default() }
+    }
+
+    /// Apply a new config; the size takes effect on the next tick, turning it off empties the cache
+    pub fn configure(&mut self, config: OpCacheConfig) -> anyhow::Result<()> {
+        config.validate()?;
+        if !config.enabled {
+            self.clear();
+        }
+        self.config = config;
+        Ok(())
+    }
+
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Resize, evicting the oldest entries if the cache no longer fits
+    pub fn set_capacity(&mut self, bytes: usize) {
+        self.capacity_bytes = bytes;
+        self.evict_to(bytes);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.lru.clear();
+        self.used_bytes = 0;
+    }
+
+    fn evict_to(&mut self, bytes: usize) {
+        while self.used_bytes > bytes {
+            let Some((_, key)) = self.lru.pop_first() else { break };
+            if let Some((_, size)) = self.entries.remove(&key) {
+                self.used_bytes -= size;
+                self.stats.evictions += 1;
+            }
+        }
+    }
+
+    /// Mark `key` used; true on a hit
+    fn touch(&mut self, key: u64) -> bool {
+        let Some(entry) = self.entries.get_mut(&key) else { return false };
+        self.lru.remove(&entry.0);
+        entry.0 = self.next_use;
+        self.lru.insert(self.next_use, key);
+        self.next_use += 1;
+        true
+    }
+
+    fn insert(&mut self, key: u64, bytes: usize) {
+        if bytes > self.capacity_bytes {
+            return;
+        }
+        self.evict_to(self.capacity_bytes - bytes);
+        self.entries.insert(key, (self.next_use, bytes));
+        self.lru.insert(self.next_use, key);
+        self.next_use += 1;
+        self.used_bytes += bytes;
+    }
+
+    /// Run a pipeline's leading deterministic ops through the cache. Hits skip compute;
+    /// misses store their result. The first op with effects ends the cacheable prefix.
+    pub fn run(&mut self, ops: &[Op], digest: u64, payload_sz: usize) -> CacheRun {
+        let mut run = CacheRun::default();
+        if !self.config.enabled {
+            return run;
+        }
+        let mut input = digest;
+        for op in ops.iter().take_while(|op| is_memoizable(op)) {
+            let key = cache_key(op, input);
+            if self.touch(key) {
+                run.hits += 1;
+                run.saved_ms += op.cost_ms();
+                run.saved_units += op.work_units();
+                self.stats.hits += 1;
+                self.stats.saved_ms += op.cost_ms() as u64;
+            } else {
+                self.stats.misses += 1;
+                self.insert(key, payload_sz.max(1));
+            }
+            input = key;
+        }
+        run
+    }
+}
+
+/// Multiplier on the cache size from researched tech
+pub fn tech_cache_mult(research: &ResearchState, tech_tree: &TechTree) -> f32 {
+    tech_tree.get_researched_techs(research).iter()
+        .flat_map(|tech| tech.grants.iter())
+        .filter_map(|grant| match grant {
+            TechGrant::Tunable { key, mult } if key == OP_CACHE_TUNABLE => Some(*mult),
+            _ => None,
+        })
+        .product()
+}
+
+/// Keep the cache sized to its config and the tech researched so far
+pub fn op_cache_system(
+    mut cache: ResMut<OpCache>,
+    research: Option<Res<ResearchState>>,
+    tech_tree: Option<Res<TechTree>>,
+) {
+    let mult = match (research, tech_tree) {
+        (Some(research), Some(tech_tree)) => tech_cache_mult(&research, &tech_tree),
+        _ => 1.0,
+    };
+    let capacity = (cache.config.base_capacity_mb * mult * MB as f32) as usize;
+    if capacity != cache.capacity_bytes {
+        println!("Op cache resized to {:.1} MB", capacity as f64 / MB as f64);
+        cache.set_capacity(capacity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeated_payload_hits() {
+        let mut cache = OpCache::new();
+        let ops = vec![Op::UdpDemux, Op::Decode, Op::Kalman, Op::Export];
+        let digest = payload_digest(b"sensor 7: 21.5C");
+
+        let first = cache.run(&ops, digest, 1024);
+        assert_eq!(first.hits, 0);
+        let second = cache.run(&ops, digest, 1024);
+        assert_eq!(second.hits, 3); // Export always runs
+        assert_eq!(second.saved_ms, Op::UdpDemux.cost_ms() + Op::Decode.cost_ms() + Op::Kalman.cost_ms());
+
+        let other = cache.run(&ops, payload_digest(b"sensor 7: 21.6C"), 1024);
+        assert_eq!(other.hits, 0);
+        assert_eq!((cache.stats.hits, cache.stats.misses), (3, 6));
+        assert!((cache.stats.hit_rate() - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = OpCache::new();
+        cache.set_capacity(2048);
+        let ops = vec![Op::Crc];
+        cache.run(&ops, 1, 1024);
+        cache.run(&ops, 2, 1024);
+        cache.run(&ops, 1, 1024); // 1 is now the most recent
+        cache.run(&ops, 3, 1024);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats.evictions, 1);
+        assert_eq!(cache.run(&ops, 1, 1024).hits, 1);
+        assert_eq!(cache.run(&ops, 2, 1024).hits, 0);
+
+        cache.set_capacity(1024);
+        assert!(cache.used_bytes() <= 1024);
+    }
+
+    #[test]
+    fn test_tech_enlarges_cache() {
+        let tech_tree = crate::create_default_tech_tree();
+        let mut research = ResearchState::new();
+        assert_eq!(tech_cache_mult(&research, &tech_tree), 1.0);
+        research.acquired.push("result_memoization".to_string());
+        assert!(tech_cache_mult(&research, &tech_tree) > 1.0);
+    }
+}
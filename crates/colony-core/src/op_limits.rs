@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use anyhow::Result;
+use crate::{op_from_name, op_name, Autoscaler, Op, ResearchState, TechGrant, TechTree};
+
+/// `[[op_limits]]` in a scenario: how many jobs may run an op at once, e.g. two Yolo licenses
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OpLimit {
+    pub op: String,     // as named in pipelines, e.g. "Yolo" or "wasm:com.example.fft/Op_Fft"
+    pub slots: u32,     // before purchases and research; 0 means none until some are bought
+    #[serde(default)]
+    pub slot_cost: f64, // budget per extra slot bought; 0 means slots can't be bought
+}
+
+/// Check a limit set: every op parses and is limited once
+pub fn validate_op_limits(limits: &[OpLimit]) -> Result<()> {
+    for (i, limit) in limits.iter().enumerate() {
+        op_from_name(&limit.op).map_err(|e| anyhow::anyhow!(e))?;
+        if !limit.slot_cost.is_finite() || limit.slot_cost < 0.0 {
+            anyhow::bail!("Slot cost for {} can't be negative", limit.op);
+        }
+        if limits[..i].iter().any(|other| other.op == limit.op) {
+            anyhow::bail!("{} is limited more than once", limit.op);
+        }
+    }
+    Ok(())
+}
+
+/// How jobs fared waiting for one op's slots
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OpContention {
+    pub refused: u64,        // dispatch attempts turned away for want of a slot, once per job per tick
+    pub waited_jobs: u64,    // jobs that got a slot after being refused one
+    pub wait_ticks: u64,     // total ticks those jobs waited
+    pub max_wait_ticks: u64,
+}
+
+impl OpContention {
+    pub fn avg_wait_ms(&self) -> f32 {
+        if self.waited_jobs == 0 { 0.0 } else { self.wait_ticks as f32 * 16.0 / self.waited_jobs as f32 }
+    }
+}
+
+/// Concurrency caps on ops, enforced by both dispatchers. A job holds one slot of each limited
+/// op in its pipeline for its service time; jobs that find an op full stay queued.
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OpLicenses {
+    pub limits: Vec<OpLimit>,
+    pub purchased: BTreeMap<String, u32>,  // op -> slots bought from the budget
+    pub researched: BTreeMap<String, u32>, // op -> slots granted by tech, kept current by op_license_system
+    pub contention: BTreeMap<String, OpContention>,
+    #[serde(default)]
+    held: BTreeMap<String, Vec<u64>>,      // op -> release tick of each slot in use
+    #[serde(default)]
+    refused: BTreeMap<u64, (u64, u64)>,    // job id -> ticks it was first and last refused a slot
+}
+
+impl OpLicenses {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_limits(limits: &[OpLimit]) -> Result<Self> {
+        validate_op_limits(limits)?;
+        Ok(Self { limits: limits.to_vec(), ..Self::default() })
+    }
+
+    /// Swap the limits; purchases, research and contention stats carry over
+    pub fn set_limits(&mut self, limits: &[OpLimit]) -> Result<()> {
+        validate_op_limits(limits)?;
+        self.limits = limits.to_vec();
+        Ok(())
+    }
+
+    fn limit(&self, op: &str) -> Option<&OpLimit> {
+        self.limits.iter().find(|limit| limit.op == op)
+    }
+
+    /// Slots an op has in all, or None when it isn't limited
+    pub fn capacity(&self, op: &str) -> Option<u32> {
+        self.limit(op).map(|limit| {
+            limit.slots + self.purchased.get(op).copied().unwrap_or(0) + self.researched.get(op).copied().unwrap_or(0)
+        })
+    }
+
+    pub fn in_use(&self, op: &str) -> usize {
+        self.held.get(op).map_or(0, |held| held.len())
+    }
+
+    /// The limited ops of a pipeline, each once however often it appears
+    fn limited_ops(&self, ops: &[Op]) -> Vec<String> {
+        let mut names: Vec<String> = ops.iter().map(op_name).filter(|name| self.limit(name).is_some()).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Free the slots whose holders are done by `now_tick`
+    fn release(&mut self, now_tick: u64) {
+        for held in self.held.values_mut() {
+            held.retain(|&until| until > now_tick);
+        }
+    }
+
+    /// Whether a job can get a slot of every limited op it runs; a refusal is counted against each full op
+    pub fn admit(&mut self, job_id: u64, ops: &[Op], now_tick: u64) -> bool {
+        self.release(now_tick);
+        let full: Vec<String> = self.limited_ops(ops).into_iter()
+            .filter(|op| self.in_use(op) >= self.capacity(op).unwrap_or(0) as usize)
+            .collect();
+        if full.is_empty() {
+            return true;
+        }
+        // Every yard may try the same job in a tick; it is counted once
+        let last = self.refused.get(&job_id).map(|&(_, last)| last);
+        if last != Some(now_tick) {
+            for op in full {
+                self.contention.entry(op).or_default().refused += 1;
+            }
+        }
+        self.refused.entry(job_id).or_insert((now_tick, now_tick)).1 = now_tick;
+        false
+    }
+
+    /// Hold slots for an admitted job until its service time is up, and record how long it waited for them
+    pub fn acquire(&mut self, job_id: u64, ops: &[Op], now_tick: u64, service_ms: u32) {
+        let until = now_tick + (service_ms as u64).div_ceil(16).max(1);
+        let waited = self.refused.remove(&job_id).map(|(since, _)| now_tick.saturating_sub(since));
+        for op in self.limited_ops(ops) {
+            self.held.entry(op.clone()).or_default().push(until);
+            if let Some(ticks) = waited {
+                let contention = self.contention.entry(op).or_default();
+                contention.waited_jobs += 1;
+                contention.wait_ticks += ticks;
+                contention.max_wait_ticks = contention.max_wait_ticks.max(ticks);
+            }
+        }
+    }
+
+    /// Buy extra slots for a limited op from the autoscaling budget; returns what they cost
+    pub fn purchase(&mut self, op: &str, slots: u32, budget: &mut Autoscaler) -> Result<f64> {
+        let limit = self.limit(op).ok_or_else(|| anyhow::anyhow!("{} isn't limited, there are no slots to buy", op))?;
+        if limit.slot_cost <= 0.0 {
+            anyhow::bail!("Slots for {} aren't for sale", op);
+        }
+        let cost = limit.slot_cost * slots as f64;
+        if cost > budget.remaining_budget() {
+            anyhow::bail!("{} slots of {} cost {:.1}, the budget has {:.1} left", slots, op, cost, budget.remaining_budget());
+        }
+        budget.spent += cost;
+        *self.purchased.entry(op.to_string()).or_default() += slots;
+        Ok(cost)
+    }
+}
+
+/// Slots per op granted by researched tech
+pub fn tech_op_slots(research: &ResearchState, tech_tree: &TechTree) -> BTreeMap<String, u32> {
+    let mut slots = BTreeMap::new();
+    for grant in tech_tree.get_researched_techs(research).iter().flat_map(|tech| tech.grants.iter()) {
+        if let TechGrant::OpSlots { op, slots: granted } = grant {
+            *slots.entry(op.clone()).or_default() += granted;
+        }
+    }
+    slots
+}
+
+/// Keep researched slots in step with the tech tree
+pub fn op_license_system(
+    mut licenses: ResMut<OpLicenses>,
+    research: Option<Res<ResearchState>>,
+    tech_tree: Option<Res<TechTree>>,
+) {
+    let researched = match (research, tech_tree) {
+        (Some(research), Some(tech_tree)) => tech_op_slots(&research, &tech_tree),
+        _ => BTreeMap::new(),
+    };
+    if researched != licenses.researched {
+        println!("Researched op slots: {:?}", researched);
+        licenses.researched = researched;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AutoscaleConfig, Job, Pipeline, QoS, SimClock, TickScale, WorkyardKind};
+    use crate::queue::{EnqueuedJob, JobQueue};
+
+    fn yolo_limit(slots: u32) -> OpLicenses {
+        OpLicenses::from_limits(&[OpLimit { op: "Yolo".into(), slots, slot_cost: 50.0 }]).unwrap()
+    }
+
+    #[test]
+    fn test_slots_are_held_for_service_time() {
+        let mut licenses = yolo_limit(2);
+        let ops = vec![Op::GpuPreprocess, Op::Yolo, Op::Yolo];
+        for job_id in 0..2 {
+            assert!(licenses.admit(job_id, &ops, 100));
+            licenses.acquire(job_id, &ops, 100, 40); // 3 ticks
+        }
+        assert_eq!(licenses.in_use("Yolo"), 2);
+        assert!(!licenses.admit(2, &ops, 100));
+        assert!(!licenses.admit(2, &ops, 101));
+        assert!(licenses.admit(3, &[Op::Decode], 101)); // unlimited ops pass
+
+        assert!(licenses.admit(2, &ops, 103));
+        licenses.acquire(2, &ops, 103, 40);
+        let yolo = &licenses.contention["Yolo"];
+        assert_eq!((yolo.refused, yolo.waited_jobs, yolo.max_wait_ticks), (2, 1, 3));
+        assert_eq!(yolo.avg_wait_ms(), 48.0);
+    }
+
+    #[test]
+    fn test_purchases_and_research_add_slots() {
+        let mut licenses = yolo_limit(1);
+        let mut budget = Autoscaler::new();
+        budget.configure(&AutoscaleConfig { budget: 120.0, ..Default::default() }).unwrap();
+        assert_eq!(licenses.purchase("Yolo", 2, &mut budget).unwrap(), 100.0);
+        assert!(licenses.purchase("Yolo", 1, &mut budget).is_err());
+        assert!(licenses.purchase("Decode", 1, &mut budget).is_err());
+        assert_eq!(licenses.capacity("Yolo"), Some(3));
+
+        let tech_tree = crate::create_default_tech_tree();
+        let mut research = ResearchState::new();
+        research.acquired.push("model_licensing".to_string());
+        licenses.researched = tech_op_slots(&research, &tech_tree);
+        assert!(licenses.capacity("Yolo").unwrap() > 3);
+        assert!(licenses.set_limits(&[OpLimit { op: "Fourier".into(), slots: 1, slot_cost: 0.0 }]).is_err());
+    }
+
+    #[test]
+    fn test_dispatch_waits_for_a_slot() {
+        let colony = crate::colony_for_scenario(None, Some(7)).unwrap();
+        let clock = SimClock { tick_scale: TickScale::RealTime, now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH };
+        let mut app = crate::build_shadow_app(&colony, &clock, &crate::ActiveScheduler::default());
+        app.insert_resource(OpLicenses::from_limits(&[OpLimit { op: "ModbusMap".into(), slots: 0, slot_cost: 0.0 }]).unwrap());
+        app.update();
+        for id in 0..3 {
+            app.world_mut().resource_mut::<JobQueue>().push_enqueued(EnqueuedJob::new(Job {
+                id: 1000 + id,
+                pipeline: Pipeline { ops: vec![Op::Decode, Op::ModbusMap], mutation_tag: None, id: None },
+                qos: QoS::Balanced,
+                deadline_ms: 1000,
+                payload_sz: 1024,
+            }, 0));
+        }
+        app.update();
+        let queued = |app: &App| app.world().resource::<JobQueue>().peek_class(crate::JobClass::of_yard(&WorkyardKind::CpuArray)).len();
+        assert_eq!(queued(&app), 3);
+        assert_eq!(app.world().resource::<OpLicenses>().contention["ModbusMap"].refused, 3);
+
+        app.world_mut().resource_mut::<OpLicenses>().set_limits(&[OpLimit { op: "ModbusMap".into(), slots: 3, slot_cost: 0.0 }]).unwrap();
+        app.update();
+        assert_eq!(queued(&app), 0);
+    }
+}
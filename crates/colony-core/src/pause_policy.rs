@@ -0,0 +1,207 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use crate::{BlackSwanIndex, FaultKpi, SessionCtl, SimClock, SlaTracker};
+
+/// Pause events kept for the status screens
+const MAX_PAUSE_EVENTS: usize = 50;
+
+/// Conditions that pause the session so an unattended run stops at a decision point
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PausePolicies {
+    pub on_black_swan: bool,
+    pub sla_below_pct: Option<f32>, // deadline hit rate, 0..=100
+    pub on_worker_quarantine: bool, // a worker went sticky and needs reimage/maintenance
+}
+
+impl PausePolicies {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(pct) = self.sla_below_pct {
+            if !(0.0..=100.0).contains(&pct) {
+                return Err(anyhow::anyhow!("sla_below_pct must be between 0 and 100, got {}", pct));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn any_enabled(&self) -> bool {
+        self.on_black_swan || self.sla_below_pct.is_some() || self.on_worker_quarantine
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PauseReason {
+    BlackSwan { swan_id: String },
+    SlaBelow { hit_pct: f32, threshold_pct: f32 },
+    WorkerQuarantined { sticky_workers: u32 },
+}
+
+impl std::fmt::Display for PauseReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PauseReason::BlackSwan { swan_id } => write!(f, "Black Swan '{}' fired", swan_id),
+            PauseReason::SlaBelow { hit_pct, threshold_pct } => {
+                write!(f, "SLA at {:.1}%, below {:.1}%", hit_pct, threshold_pct)
+            }
+            PauseReason::WorkerQuarantined { sticky_workers } => {
+                write!(f, "worker quarantined ({} sticky)", sticky_workers)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PauseEvent {
+    pub tick: u64,
+    pub reason: PauseReason,
+}
+
+/// What the pause policies have seen so far; each condition fires once per crossing
+#[derive(Resource, Debug, Clone)]
+pub struct PauseMonitor {
+    pub events: Vec<PauseEvent>,
+    pub unacknowledged: Option<PauseEvent>, // cleared by whoever reacts to the pause
+    next_swan_tick: u64,
+    sla_armed: bool,
+    sticky_seen: u32,
+}
+
+impl Default for PauseMonitor {
+    fn default() -> Self {
+        Self {
+            events: Vec::new(),
+            unacknowledged: None,
+            next_swan_tick: 0,
+            sla_armed: true,
+            sticky_seen: 0,
+        }
+    }
+}
+
+impl PauseMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn last(&self) -> Option<&PauseEvent> {
+        self.events.last()
+    }
+
+    pub fn take_unacknowledged(&mut self) -> Option<PauseEvent> {
+        self.unacknowledged.take()
+    }
+
+    /// Check the policies against this tick's state; returns the reason to pause, if any
+    pub fn check(
+        &mut self,
+        policies: &PausePolicies,
+        tick: u64,
+        fired_swans: &[(String, u64)],
+        sla_hit_pct: Option<f32>,
+        sticky_workers: u32,
+    ) -> Option<PauseReason> {
+        let mut reason = None;
+
+        // Swans fired since the last check; seen even with the policy off so enabling it later doesn't replay them
+        let swan = fired_swans.iter().find(|(_, fired)| *fired >= self.next_swan_tick);
+        self.next_swan_tick = tick + 1;
+        if let (true, Some((swan_id, _))) = (policies.on_black_swan, swan) {
+            reason = Some(PauseReason::BlackSwan { swan_id: swan_id.clone() });
+        }
+
+        if let (Some(threshold_pct), Some(hit_pct)) = (policies.sla_below_pct, sla_hit_pct) {
+            if hit_pct >= threshold_pct {
+                self.sla_armed = true;
+            } else if self.sla_armed {
+                self.sla_armed = false;
+                reason = reason.or(Some(PauseReason::SlaBelow { hit_pct, threshold_pct }));
+            }
+        }
+
+        if sticky_workers > self.sticky_seen && policies.on_worker_quarantine {
+            reason = reason.or(Some(PauseReason::WorkerQuarantined { sticky_workers }));
+        }
+        self.sticky_seen = sticky_workers;
+
+        if let Some(ref reason) = reason {
+            let event = PauseEvent { tick, reason: reason.clone() };
+            self.events.push(event.clone());
+            if self.events.len() > MAX_PAUSE_EVENTS {
+                self.events.remove(0);
+            }
+            self.unacknowledged = Some(event);
+        }
+        reason
+    }
+}
+
+/// Pauses the session when an enabled policy trips
+pub fn pause_policy_system(
+    policies: Res<PausePolicies>,
+    mut monitor: ResMut<PauseMonitor>,
+    mut session: ResMut<SessionCtl>,
+    clock: Res<SimClock>,
+    black_swans: Option<Res<BlackSwanIndex>>,
+    sla: Option<Res<SlaTracker>>,
+    faults: Option<Res<FaultKpi>>,
+) {
+    let tick = clock.now.timestamp_millis() as u64 / 16;
+    let fired = black_swans.map(|b| b.meters.recently_fired.clone()).unwrap_or_default();
+    // No verdict on the SLA until some deadlines have been scored
+    let sla_hit_pct = sla
+        .filter(|s| s.current_window.total > 0 || !s.windows.is_empty())
+        .map(|s| s.get_recent_hit_rate());
+    let sticky = faults.map(|f| f.sticky_workers).unwrap_or(0);
+
+    if let Some(reason) = monitor.check(&policies, tick, &fired, sla_hit_pct, sticky) {
+        if session.running {
+            println!("Session paused: {}", reason);
+        }
+        session.pause();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_policy_pauses_once_per_crossing() {
+        let policies = PausePolicies { on_black_swan: true, sla_below_pct: Some(95.0), on_worker_quarantine: true };
+        let mut monitor = PauseMonitor::new();
+
+        let swans = vec![("grid_brownout".to_string(), 10)];
+        assert_eq!(
+            monitor.check(&policies, 10, &swans, Some(99.0), 0),
+            Some(PauseReason::BlackSwan { swan_id: "grid_brownout".to_string() })
+        );
+        assert_eq!(monitor.check(&policies, 11, &swans, Some(99.0), 0), None); // already seen
+
+        assert!(matches!(monitor.check(&policies, 12, &swans, Some(90.0), 0), Some(PauseReason::SlaBelow { .. })));
+        assert_eq!(monitor.check(&policies, 13, &swans, Some(89.0), 0), None); // still below
+        monitor.check(&policies, 14, &swans, Some(97.0), 0);
+        assert!(monitor.check(&policies, 15, &swans, Some(94.0), 0).is_some()); // dipped again
+
+        assert_eq!(
+            monitor.check(&policies, 16, &swans, Some(97.0), 1),
+            Some(PauseReason::WorkerQuarantined { sticky_workers: 1 })
+        );
+        assert_eq!(monitor.check(&policies, 17, &swans, Some(97.0), 1), None);
+        assert_eq!(monitor.events.len(), 4);
+        assert_eq!(monitor.take_unacknowledged().map(|e| e.tick), Some(16));
+    }
+
+    #[test]
+    fn test_disabled_policies_never_pause() {
+        let mut monitor = PauseMonitor::new();
+        let swans = vec![("solar_flare".to_string(), 3)];
+        assert_eq!(monitor.check(&PausePolicies::default(), 3, &swans, Some(10.0), 4), None);
+
+        // Turning the swan policy on afterwards doesn't replay the earlier swan
+        let policies = PausePolicies { on_black_swan: true, ..Default::default() };
+        assert_eq!(monitor.check(&policies, 4, &swans, None, 4), None);
+        assert!(PausePolicies { sla_below_pct: Some(120.0), ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use crate::{Debts, Effect, SimClock, WorkClass, Worker, WorkerSkills, WorkerState, WorkerStateComp};
+
+/// Workers this disciplined cross the picket line and keep working through a strike
+pub const STRIKE_CROSSING_DISCIPLINE: f32 = 0.7;
+
+/// Yard a worker of this class is hired for, named as events name yards
+pub fn home_yard(class: WorkClass) -> &'static str {
+    match class {
+        WorkClass::Cpu => "CpuArray",
+        WorkClass::Gpu => "GpuFarm",
+        WorkClass::Io(_) => "SignalHub",
+    }
+}
+
+fn class_skill(class: WorkClass, skills: &WorkerSkills) -> f32 {
+    match class {
+        WorkClass::Cpu => skills.cpu,
+        WorkClass::Gpu => skills.gpu,
+        WorkClass::Io(_) => skills.io,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoachedWorker {
+    pub worker_id: u64,
+    pub yard: String,
+    pub skill: f32, // in the yard's own skill
+    pub tick: u64,
+}
+
+/// Personnel fallout of Black Swans: poaching offers waiting on their target and who is out on strike
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PersonnelLedger {
+    pub pending_poaches: Vec<String>, // yard of each accepted offer, served in order
+    pub poached: Vec<PoachedWorker>,
+    pub striking: Vec<u64>, // worker ids this ledger blocked, so only they are released
+}
+
+impl PersonnelLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take on the effects only the worker roster can carry out; debts go through `apply_effects`
+    pub fn queue_effects(&mut self, effects: &[Effect]) {
+        for effect in effects {
+            if let Effect::RemoveWorker { yard } = effect {
+                self.pending_poaches.push(yard.clone());
+            }
+        }
+    }
+}
+
+/// Serves poaching offers and walks workers out of, and back into, striking yards. A poached
+/// worker works out their notice: the highest-skill worker of the yard leaves once idle, with
+/// ties going to the lowest id. Strikers are held Blocked until the yard's strike debt expires.
+pub fn personnel_system(
+    mut commands: Commands,
+    mut ledger: ResMut<PersonnelLedger>,
+    debts: Res<Debts>,
+    clock: Res<SimClock>,
+    mut workers: Query<(Entity, &Worker, &WorkerSkills, &mut WorkerStateComp)>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+
+    let mut taken = Vec::new();
+    let mut still_pending = Vec::new();
+    for yard in std::mem::take(&mut ledger.pending_poaches) {
+        let target = workers.iter()
+            .filter(|(entity, worker, ..)| home_yard(worker.class) == yard && !taken.contains(entity))
+            .max_by(|a, b| class_skill(a.1.class, a.2).total_cmp(&class_skill(b.1.class, b.2)).then(b.1.id.cmp(&a.1.id)));
+        match target {
+            // Nobody left to poach; the offer lapses
+            None => {}
+            Some((entity, worker, skills, state)) if state.0 == WorkerState::Idle => {
+                println!("Worker {} was poached from {}", worker.id, yard);
+                ledger.poached.push(PoachedWorker { worker_id: worker.id, yard: yard.clone(), skill: class_skill(worker.class, skills), tick: now_tick });
+                taken.push(entity);
+            }
+            Some(_) => still_pending.push(yard),
+        }
+    }
+    ledger.pending_poaches = still_pending;
+    for entity in taken {
+        commands.entity(entity).despawn();
+    }
+
+    for (_, worker, skills, mut state) in workers.iter_mut() {
+        let on_strike = debts.is_yard_idle(home_yard(worker.class), now_tick);
+        let striker = ledger.striking.contains(&worker.id);
+        if on_strike && !striker && state.0 == WorkerState::Idle && skills.discipline < STRIKE_CROSSING_DISCIPLINE {
+            state.0 = WorkerState::Blocked;
+            ledger.striking.push(worker.id);
+        } else if !on_strike && striker {
+            if state.0 == WorkerState::Blocked {
+                state.0 = WorkerState::Idle;
+            }
+            ledger.striking.retain(|id| *id != worker.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Debt, WorkerBundle};
+
+    fn skills(gpu: f32, discipline: f32) -> WorkerSkills {
+        WorkerSkills { cpu: 0.5, gpu, io: 0.5, discipline, focus: 0.5 }
+    }
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.insert_resource(PersonnelLedger::new())
+            .insert_resource(Debts::new())
+            .insert_resource(SimClock {
+                tick_scale: crate::TickScale::RealTime,
+                now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+            })
+            .add_systems(Update, personnel_system);
+        app
+    }
+
+    #[test]
+    fn test_poaching_takes_the_best_idle_worker_of_the_yard() {
+        let mut app = app();
+        app.world_mut().spawn(WorkerBundle::new(1, WorkClass::Gpu, skills(0.6, 0.5)));
+        let star = app.world_mut().spawn(WorkerBundle::new(2, WorkClass::Gpu, skills(0.95, 0.5))).id();
+        app.world_mut().spawn(WorkerBundle::new(3, WorkClass::Cpu, skills(0.99, 0.5)));
+        app.world_mut().get_mut::<WorkerStateComp>(star).unwrap().0 = WorkerState::Running;
+
+        app.world_mut().resource_mut::<PersonnelLedger>()
+            .queue_effects(&[Effect::RemoveWorker { yard: "GpuFarm".to_string() }]);
+        app.update();
+        // Still working out their notice
+        assert!(app.world().get_entity(star).is_ok());
+        assert_eq!(app.world().resource::<PersonnelLedger>().pending_poaches, vec!["GpuFarm".to_string()]);
+
+        app.world_mut().get_mut::<WorkerStateComp>(star).unwrap().0 = WorkerState::Idle;
+        app.update();
+        assert!(app.world().get_entity(star).is_err());
+        let ledger = app.world().resource::<PersonnelLedger>();
+        assert!(ledger.pending_poaches.is_empty());
+        assert_eq!((ledger.poached[0].worker_id, ledger.poached[0].skill), (2, 0.95));
+        assert_eq!(app.world_mut().query::<&Worker>().iter(app.world()).count(), 2);
+    }
+
+    #[test]
+    fn test_strike_blocks_undisciplined_workers_until_it_ends() {
+        let mut app = app();
+        let striker = app.world_mut().spawn(WorkerBundle::new(1, WorkClass::Gpu, skills(0.5, 0.3))).id();
+        let loyal = app.world_mut().spawn(WorkerBundle::new(2, WorkClass::Gpu, skills(0.5, 0.9))).id();
+        let other_yard = app.world_mut().spawn(WorkerBundle::new(3, WorkClass::Cpu, skills(0.5, 0.3))).id();
+        app.world_mut().resource_mut::<Debts>().add_debt(Debt::YardIdle { yard: "GpuFarm".to_string(), until_tick: 1_000 });
+
+        app.update();
+        let state = |app: &App, e: Entity| app.world().get::<WorkerStateComp>(e).unwrap().0;
+        assert_eq!(state(&app, striker), WorkerState::Blocked);
+        assert_eq!(state(&app, loyal), WorkerState::Idle);
+        assert_eq!(state(&app, other_yard), WorkerState::Idle);
+        assert_eq!(app.world().resource::<PersonnelLedger>().striking, vec![1]);
+
+        app.world_mut().resource_mut::<Debts>().active.clear();
+        app.update();
+        assert_eq!(state(&app, striker), WorkerState::Idle);
+        assert!(app.world().resource::<PersonnelLedger>().striking.is_empty());
+    }
+}
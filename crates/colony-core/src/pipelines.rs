@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use super::{Op, Pipeline};
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PipelineDef {
     pub id: String,
     pub ops: Vec<String>,
@@ -12,66 +12,65 @@ pub struct PipelineDef {
 
 impl PipelineDef {
     pub fn to_pipeline(&self) -> Result<Pipeline, String> {
-        let ops: Result<Vec<Op>, _> = self.ops
-            .iter()
-            .map(|op_str| match op_str.as_str() {
-                "UdpDemux" => Ok(Op::UdpDemux),
-                "Decode" => Ok(Op::Decode),
-                "Kalman" => Ok(Op::Kalman),
-                "Export" => Ok(Op::Export),
-                "HttpParse" => Ok(Op::HttpParse),
-                "HttpExport" => Ok(Op::HttpExport),
-                "Fft" => Ok(Op::Fft),
-                "Yolo" => Ok(Op::Yolo),
-                "Crc" => Ok(Op::Crc),
-                "CanParse" => Ok(Op::CanParse),
-                "TcpSessionize" => Ok(Op::TcpSessionize),
-                "ModbusMap" => Ok(Op::ModbusMap),
-                "MaintenanceCool" => Ok(Op::MaintenanceCool),
-                _ => Err(format!("Unknown operation: {}", op_str)),
-            })
-            .collect();
+        let ops: Result<Vec<Op>, _> = self.ops.iter().map(|op| op_from_name(op)).collect();
 
         let ops = ops?;
 
         Ok(Pipeline {
             ops,
             mutation_tag: None,
+            id: Some(self.id.clone()),
         })
     }
 }
 
-pub fn builtin_pipelines() -> Vec<Pipeline> {
-    vec![
-        Pipeline { 
-            ops: vec![Op::UdpDemux, Op::Decode, Op::Kalman, Op::Export], 
-            mutation_tag: None 
-        },
-        Pipeline { 
-            ops: vec![Op::HttpParse, Op::HttpExport], 
-            mutation_tag: None 
+/// The op a pipeline names: a built-in op, `wasm:<mod>/<op>` or `lua:<func>`
+pub fn op_from_name(name: &str) -> Result<Op, String> {
+    match name {
+        "UdpDemux" => Ok(Op::UdpDemux),
+        "Decode" => Ok(Op::Decode),
+        "Kalman" => Ok(Op::Kalman),
+        "Export" => Ok(Op::Export),
+        "HttpParse" => Ok(Op::HttpParse),
+        "HttpExport" => Ok(Op::HttpExport),
+        "ArchiveWrite" => Ok(Op::ArchiveWrite),
+        "Fft" => Ok(Op::Fft),
+        "Yolo" => Ok(Op::Yolo),
+        "Crc" => Ok(Op::Crc),
+        "CanParse" => Ok(Op::CanParse),
+        "TcpSessionize" => Ok(Op::TcpSessionize),
+        "ModbusMap" => Ok(Op::ModbusMap),
+        "MqttParse" => Ok(Op::MqttParse),
+        "GrpcParse" => Ok(Op::GrpcParse),
+        "MaintenanceCool" => Ok(Op::MaintenanceCool),
+        "ReplaceDrives" => Ok(Op::ReplaceDrives),
+        "GpuPreprocess" => Ok(Op::GpuPreprocess),
+        "GpuExport" => Ok(Op::GpuExport),
+        other => match (other.strip_prefix(crate::WASM_OP_PREFIX), other.strip_prefix(crate::LUA_OP_PREFIX)) {
+            (Some(op_id), _) if !op_id.is_empty() => Ok(Op::DynamicWasm { op_id: op_id.to_string() }),
+            (_, Some(func)) if !func.is_empty() => Ok(Op::DynamicLua { func: func.to_string() }),
+            _ => Err(format!("Unknown operation: {}", name)),
         },
-    ]
+    }
 }
 
-pub fn get_pipeline_by_id(id: &str) -> Option<Pipeline> {
-    match id {
-        "udp_telemetry_ingest" => Some(Pipeline {
-            ops: vec![Op::UdpDemux, Op::Decode, Op::Kalman, Op::Export],
-            mutation_tag: None,
-        }),
-        "http_ingest" => Some(Pipeline {
-            ops: vec![Op::HttpParse, Op::HttpExport],
-            mutation_tag: None,
-        }),
-        "can_telemetry" => Some(Pipeline {
-            ops: vec![Op::Decode, Op::Kalman, Op::GpuPreprocess, Op::Yolo, Op::GpuExport],
-            mutation_tag: None,
-        }),
-        "modbus_poll" => Some(Pipeline {
-            ops: vec![Op::Decode, Op::Kalman, Op::Export],
-            mutation_tag: None,
-        }),
-        _ => None,
+/// The name `op_from_name` reads back as the same op
+pub fn op_name(op: &Op) -> String {
+    match op {
+        Op::DynamicWasm { op_id } => format!("{}{}", crate::WASM_OP_PREFIX, op_id),
+        Op::DynamicLua { func } => format!("{}{}", crate::LUA_OP_PREFIX, func),
+        builtin => format!("{:?}", builtin),
     }
 }
+
+pub fn builtin_pipelines() -> Vec<Pipeline> {
+    ["udp_telemetry_ingest", "http_ingest"]
+        .iter()
+        .filter_map(|id| get_pipeline_by_id(id))
+        .collect()
+}
+
+/// Pipeline template from the bundled vanilla content
+pub fn get_pipeline_by_id(id: &str) -> Option<Pipeline> {
+    crate::ContentPack::vanilla().pipeline(id)
+}
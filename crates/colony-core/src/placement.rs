@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use crate::{Workyard, WorkyardKind, yard_node_id};
+
+const MIN_HEAT: f32 = 20.0;
+
+/// A yard's cell on the colony floor
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct YardPos {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl YardPos {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    /// Cells of cable between two positions; cables run along the grid
+    pub fn cable_len(self, other: YardPos) -> u32 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    /// Sharing a wall, which is what lets heat cross
+    pub fn is_adjacent(self, other: YardPos) -> bool {
+        self.cable_len(other) == 1
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlacementTunables {
+    pub width: i32,
+    pub height: i32,
+    pub uplink: YardPos,        // where the colony's network comes in; every yard is cabled back to it
+    pub heat_exchange: f32,     // share of the heat difference that crosses a shared wall per tick
+    pub cable_ms_per_cell: f32, // latency per cell of cable on an idle network
+}
+
+impl Default for PlacementTunables {
+    fn default() -> Self {
+        Self {
+            width: 8,
+            height: 8,
+            uplink: YardPos::new(0, 0),
+            heat_exchange: 0.02,
+            cable_ms_per_cell: 1.0,
+        }
+    }
+}
+
+impl PlacementTunables {
+    pub fn validate(&self) -> Result<()> {
+        if self.width < 1 || self.height < 1 {
+            anyhow::bail!("Grid must be at least 1x1, got {}x{}", self.width, self.height);
+        }
+        if !self.contains(self.uplink) {
+            anyhow::bail!("Uplink ({}, {}) is off the {}x{} grid", self.uplink.x, self.uplink.y, self.width, self.height);
+        }
+        // Past half, a wall would move more heat than the difference it evens out
+        if !(0.0..=0.5).contains(&self.heat_exchange) {
+            anyhow::bail!("heat_exchange must be within 0.0..=0.5, got {}", self.heat_exchange);
+        }
+        if self.cable_ms_per_cell.is_nan() || self.cable_ms_per_cell < 0.0 {
+            anyhow::bail!("cable_ms_per_cell can't be negative, got {}", self.cable_ms_per_cell);
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, pos: YardPos) -> bool {
+        (0..self.width).contains(&pos.x) && (0..self.height).contains(&pos.y)
+    }
+}
+
+/// The floor yards are placed on: neighbours trade heat, and a yard's cable run to the
+/// uplink adds latency to the traffic it handles
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct YardGrid {
+    pub tunables: PlacementTunables,
+}
+
+impl YardGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn configure(&mut self, tunables: PlacementTunables) -> Result<()> {
+        tunables.validate()?;
+        self.tunables = tunables;
+        Ok(())
+    }
+
+    /// Where the `index`th yard spawns: every other cell, so no two start out adjacent
+    pub fn auto_place(&self, index: usize) -> YardPos {
+        let cell = index as i32 * 2;
+        let width = self.tunables.width.max(1);
+        YardPos::new(cell % width, (cell / width * 2) % self.tunables.height.max(1))
+    }
+
+    /// Latency a job picks up crossing the yard's cable; `bw_mult` stretches it as links saturate
+    pub fn cable_ms(&self, pos: Option<&YardPos>, bw_mult: f32) -> f32 {
+        pos.map_or(0.0, |pos| pos.cable_len(self.tunables.uplink) as f32 * self.tunables.cable_ms_per_cell * bw_mult)
+    }
+
+    /// Refuse a move off the grid or onto another yard's cell
+    pub fn check_move(&self, yard: Entity, to: YardPos, placed: &[(Entity, YardPos)]) -> Result<()> {
+        if !self.tunables.contains(to) {
+            anyhow::bail!("({}, {}) is off the {}x{} grid", to.x, to.y, self.tunables.width, self.tunables.height);
+        }
+        if let Some((other, _)) = placed.iter().find(|(other, pos)| *other != yard && *pos == to) {
+            anyhow::bail!("({}, {}) is taken by {}", to.x, to.y, yard_node_id(*other));
+        }
+        Ok(())
+    }
+}
+
+/// One yard's place on the floor and what it costs there
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YardPlacement {
+    pub yard: String,
+    pub kind: WorkyardKind,
+    pub pos: YardPos,
+    pub neighbours: Vec<String>, // yards it trades heat with
+    pub cable_len: u32,
+    pub cable_ms: f32, // on an idle network
+}
+
+/// Every placed yard, by entity index
+pub fn yard_layout(world: &mut World) -> Vec<YardPlacement> {
+    let grid = world.get_resource::<YardGrid>().cloned().unwrap_or_default();
+    let mut placed: Vec<(Entity, WorkyardKind, YardPos)> = world.query::<(Entity, &Workyard, &YardPos)>()
+        .iter(world)
+        .map(|(entity, yard, pos)| (entity, yard.kind.clone(), *pos))
+        .collect();
+    placed.sort_by_key(|(entity, ..)| entity.index());
+    placed.iter().map(|(entity, kind, pos)| YardPlacement {
+        yard: yard_node_id(*entity),
+        kind: kind.clone(),
+        pos: *pos,
+        neighbours: placed.iter().filter(|(_, _, other)| pos.is_adjacent(*other)).map(|(e, ..)| yard_node_id(*e)).collect(),
+        cable_len: pos.cable_len(grid.tunables.uplink),
+        cable_ms: grid.cable_ms(Some(pos), 1.0),
+    }).collect()
+}
+
+/// Move the yard with entity index `yard_index` to `to`
+pub fn move_yard(world: &mut World, yard_index: u32, to: YardPos) -> Result<()> {
+    let grid = world.get_resource::<YardGrid>().cloned().unwrap_or_default();
+    let yards: Vec<(Entity, Option<YardPos>)> = world.query_filtered::<(Entity, Option<&YardPos>), With<Workyard>>()
+        .iter(world)
+        .map(|(entity, pos)| (entity, pos.copied()))
+        .collect();
+    let yard = yards.iter().map(|(entity, _)| *entity).find(|entity| entity.index() == yard_index)
+        .ok_or_else(|| anyhow::anyhow!("No yard:{}", yard_index))?;
+    let placed: Vec<(Entity, YardPos)> = yards.iter().filter_map(|(entity, pos)| pos.map(|pos| (*entity, pos))).collect();
+    grid.check_move(yard, to, &placed)?;
+    world.entity_mut(yard).insert(to);
+    Ok(())
+}
+
+/// Heat flow between two yards sharing a wall, from the hotter into the cooler
+pub fn exchanged_heat(from: f32, to: f32, heat_exchange: f32) -> f32 {
+    (from - to) * heat_exchange
+}
+
+/// Adjacent yards even out their heat: a hot neighbour warms its walls, a cool one takes heat off
+pub fn adjacency_heat_system(grid: Res<YardGrid>, mut yards: Query<(Entity, &mut Workyard, &YardPos)>) {
+    let heat_exchange = grid.tunables.heat_exchange;
+    if heat_exchange <= 0.0 {
+        return;
+    }
+    // Fixed order, so the sums come out the same however the query iterates
+    let mut placed: Vec<(Entity, YardPos, f32)> = yards.iter().map(|(entity, yard, pos)| (entity, *pos, yard.heat)).collect();
+    placed.sort_by_key(|(entity, ..)| entity.index());
+    let mut delta = vec![0.0; placed.len()];
+    for i in 0..placed.len() {
+        for j in i + 1..placed.len() {
+            if placed[i].1.is_adjacent(placed[j].1) {
+                let flow = exchanged_heat(placed[i].2, placed[j].2, heat_exchange);
+                delta[i] -= flow;
+                delta[j] += flow;
+            }
+        }
+    }
+    for ((entity, ..), delta) in placed.iter().zip(delta) {
+        if let Ok((_, mut yard, _)) = yards.get_mut(*entity) {
+            yard.heat = (yard.heat + delta).max(MIN_HEAT);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn yard(heat: f32) -> Workyard {
+        Workyard { kind: WorkyardKind::CpuArray, slots: 4, heat, heat_cap: 100.0, power_draw_kw: 0.0, bandwidth_share: 0.1, isolation_domain: 0 }
+    }
+
+    #[test]
+    fn test_adjacent_yards_trade_heat() {
+        let mut world = World::new();
+        world.insert_resource(YardGrid::new());
+        let hot = world.spawn((yard(80.0), YardPos::new(0, 0))).id();
+        let neighbour = world.spawn((yard(40.0), YardPos::new(1, 0))).id();
+        let distant = world.spawn((yard(40.0), YardPos::new(3, 3))).id();
+        world.run_system_once(adjacency_heat_system).unwrap();
+
+        let heat = |world: &World, e: Entity| world.get::<Workyard>(e).unwrap().heat;
+        assert!((heat(&world, hot) - 79.2).abs() < 1e-4);
+        assert!((heat(&world, neighbour) - 40.8).abs() < 1e-4);
+        assert_eq!(heat(&world, distant), 40.0);
+
+        assert!(move_yard(&mut world, distant.index(), YardPos::new(1, 0)).is_err());
+        move_yard(&mut world, distant.index(), YardPos::new(0, 1)).unwrap();
+        let layout = yard_layout(&mut world);
+        assert_eq!(layout[0].neighbours, vec![yard_node_id(neighbour), yard_node_id(distant)]);
+        assert_eq!(layout[2].cable_len, 1);
+    }
+
+    #[test]
+    fn test_cable_latency_and_moves() {
+        let grid = YardGrid::new();
+        assert_eq!(grid.cable_ms(None, 1.0), 0.0);
+        assert_eq!(grid.cable_ms(Some(&YardPos::new(3, 2)), 1.0), 5.0);
+        assert_eq!(grid.cable_ms(Some(&YardPos::new(3, 2)), 2.0), 10.0);
+
+        let (a, b) = (Entity::from_raw(1), Entity::from_raw(2));
+        let placed = [(a, grid.auto_place(0)), (b, grid.auto_place(1))];
+        assert!(!placed[0].1.is_adjacent(placed[1].1));
+        assert!(grid.check_move(a, YardPos::new(1, 0), &placed).is_ok());
+        assert!(grid.check_move(a, placed[1].1, &placed).is_err());
+        assert!(grid.check_move(a, YardPos::new(8, 0), &placed).is_err());
+        assert!(YardGrid::new().configure(PlacementTunables { heat_exchange: 0.6, ..Default::default() }).is_err());
+    }
+}
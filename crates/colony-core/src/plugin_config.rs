@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+
+/// System groups scheduled by ColonyPlugin in the Update schedule
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColonySet {
+    Time,       // sim clock
+    Resources,  // power, bandwidth, heat, corruption
+    Dispatch,   // CPU/IO dispatch, worker reports, maintenance, faults
+    Gpu,        // GPU batch dispatch
+    Debts,      // debt expiry and effective capacity
+    BlackSwans, // KPI buffer, black swan scan, mutations
+    Research,   // research points and rituals
+    Victory,    // SLA windows and win/loss
+    Session,    // session control, autosave
+    Scripting,  // WASM / Lua hosts
+}
+
+impl ColonySet {
+    pub fn all() -> Vec<ColonySet> {
+        vec![
+            ColonySet::Time,
+            ColonySet::Resources,
+            ColonySet::Dispatch,
+            ColonySet::Gpu,
+            ColonySet::Debts,
+            ColonySet::BlackSwans,
+            ColonySet::Research,
+            ColonySet::Victory,
+            ColonySet::Session,
+            ColonySet::Scripting,
+        ]
+    }
+
+    /// Groups the rest of the simulation can't run without
+    pub fn is_required(&self) -> bool {
+        matches!(self, ColonySet::Time | ColonySet::Resources | ColonySet::Dispatch)
+    }
+}
+
+/// Stages for the systems around Dispatch that belong to no ColonySet: job arrivals queue in
+/// Intake, per-tick accounting reads what dispatch did in Accounting. Each is pinned between
+/// Dispatch and its neighbour in the resolved order, so a parallel executor can't interleave
+/// them with the neighbouring sets differently from run to run.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DispatchStage {
+    Intake,
+    Accounting,
+}
+
+/// Size the compute pool Bevy runs systems on when built with its `multi_threaded` feature.
+/// Only the first call before any app update counts; returns the pool's actual thread count.
+pub fn init_compute_threads(threads: usize) -> usize {
+    use bevy::tasks::{ComputeTaskPool, TaskPoolBuilder};
+    ComputeTaskPool::get_or_init(|| TaskPoolBuilder::new().num_threads(threads.max(1)).thread_name("colony-compute".into()).build())
+        .thread_num()
+}
+
+/// Controls which system groups ColonyPlugin registers and the order they run in
+#[derive(Resource, Debug, Clone)]
+pub struct ColonyPluginConfig {
+    pub order: Vec<ColonySet>,
+    pub disabled: Vec<ColonySet>,
+}
+
+impl Default for ColonyPluginConfig {
+    fn default() -> Self {
+        Self {
+            order: ColonySet::all(),
+            disabled: Vec::new(),
+        }
+    }
+}
+
+impl ColonyPluginConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn without(mut self, set: ColonySet) -> Self {
+        if !set.is_required() && !self.disabled.contains(&set) {
+            self.disabled.push(set);
+        }
+        self
+    }
+
+    pub fn with_order(mut self, order: Vec<ColonySet>) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn is_enabled(&self, set: ColonySet) -> bool {
+        !self.disabled.contains(&set)
+    }
+
+    /// Order actually applied: configured order first, then any groups it left out
+    pub fn resolved_order(&self) -> Vec<ColonySet> {
+        let mut order: Vec<ColonySet> = Vec::new();
+        for set in self.order.iter().chain(ColonySet::all().iter()) {
+            if self.is_enabled(*set) && !order.contains(set) {
+                order.push(*set);
+            }
+        }
+        order
+    }
+
+    pub fn configure_sets(&self, app: &mut App) {
+        let order = self.resolved_order();
+        for pair in order.windows(2) {
+            app.configure_sets(Update, pair[1].after(pair[0]));
+        }
+
+        // Dispatch is required, so it's always in the order
+        let dispatch = order.iter().position(|set| *set == ColonySet::Dispatch).unwrap_or(0);
+        app.configure_sets(Update, (
+            DispatchStage::Intake.before(ColonySet::Dispatch),
+            DispatchStage::Accounting.after(ColonySet::Dispatch),
+        ));
+        if let Some(previous) = dispatch.checked_sub(1).map(|i| order[i]) {
+            app.configure_sets(Update, DispatchStage::Intake.after(previous));
+        }
+        if let Some(next) = order.get(dispatch + 1) {
+            app.configure_sets(Update, DispatchStage::Accounting.before(*next));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_schedule_has_no_ambiguous_systems() {
+        use bevy::ecs::schedule::{LogLevel, ScheduleBuildSettings};
+        // Two systems touching the same data with no order between them could run either way
+        // round on a multi-threaded executor, and seeded runs would stop reproducing
+        let mut app = App::new();
+        app.init_resource::<Time>().add_plugins(crate::ColonyPlugin::default());
+        app.edit_schedule(Update, |schedule| {
+            schedule.set_build_settings(ScheduleBuildSettings { ambiguity_detection: LogLevel::Error, ..Default::default() });
+        });
+        app.update();
+    }
+
+    #[test]
+    fn test_default_config_enables_everything() {
+        let config = ColonyPluginConfig::default();
+        assert_eq!(config.resolved_order(), ColonySet::all());
+        assert!(config.is_enabled(ColonySet::Gpu));
+    }
+
+    #[test]
+    fn test_required_sets_cannot_be_disabled() {
+        let config = ColonyPluginConfig::new()
+            .without(ColonySet::Dispatch)
+            .without(ColonySet::BlackSwans);
+        assert!(config.is_enabled(ColonySet::Dispatch));
+        assert!(!config.is_enabled(ColonySet::BlackSwans));
+        assert!(!config.resolved_order().contains(&ColonySet::BlackSwans));
+    }
+
+    #[test]
+    fn test_partial_order_appends_missing_sets() {
+        let config = ColonyPluginConfig::new()
+            .with_order(vec![ColonySet::Time, ColonySet::Debts, ColonySet::Resources]);
+        let order = config.resolved_order();
+        assert_eq!(&order[..3], &[ColonySet::Time, ColonySet::Debts, ColonySet::Resources]);
+        assert_eq!(order.len(), ColonySet::all().len());
+    }
+
+    #[test]
+    fn test_sub_plugins_assemble_without_optional_groups() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_plugins(crate::ColonyCorePlugin::default())
+            .add_plugins(crate::ColonyVictoryPlugin);
+        for _ in 0..3 {
+            app.update();
+        }
+        assert!(app.world().get_resource::<crate::BlackSwanIndex>().is_none());
+        assert!(app.world().get_resource::<crate::WinLossState>().is_some());
+    }
+}
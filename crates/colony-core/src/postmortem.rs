@@ -0,0 +1,314 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use crate::{
+    BlackSwanIndex, Colony, EventJournal, FaultKpi, JobQueue, JournalEntry, JournalEvent, KpiSnapshot, ReplayEvent, ReplayLog,
+    ResearchState, SaveProtection, SimClock, WinLossState,
+};
+
+/// Where hosts archive post-mortem bundles
+pub const DEFAULT_POSTMORTEM_DIR: &str = "saves/postmortems";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlackBoxConfig {
+    pub window_minutes: f32,     // how much history a bundle keeps
+    pub sample_every_ticks: u64, // ~1s at 62
+}
+
+impl Default for BlackBoxConfig {
+    fn default() -> Self {
+        Self { window_minutes: 10.0, sample_every_ticks: 62 }
+    }
+}
+
+impl BlackBoxConfig {
+    pub fn window_ticks(&self) -> u64 {
+        (self.window_minutes.max(0.0) * 3750.0) as u64 // 3750 ticks a minute
+    }
+}
+
+/// The colony's vitals at one sampled tick
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlackBoxFrame {
+    pub tick: u64,
+    pub kpis: KpiSnapshot,
+    pub queue_depth: usize,
+    pub total_faults: u32,
+    pub sticky_workers: u32,
+    pub active_swans: Vec<String>,
+}
+
+impl BlackBoxFrame {
+    fn capture(world: &mut World, tick: u64) -> Self {
+        let (total_faults, sticky_workers) = world.get_resource::<FaultKpi>()
+            .map_or((0, 0), |kpi| (kpi.total_faults, kpi.sticky_workers));
+        Self {
+            tick,
+            kpis: KpiSnapshot::capture(world),
+            queue_depth: world.get_resource::<JobQueue>().map_or(0, |queue| queue.len()),
+            total_faults,
+            sticky_workers,
+            active_swans: world.get_resource::<BlackSwanIndex>().map(|index| index.meters.active.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+/// State of the colony the moment it was lost
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PostMortemSnapshot {
+    pub colony: Option<Colony>,
+    pub win_loss: WinLossState,
+    pub faults: FaultKpi,
+    pub research: Vec<String>, // acquired tech ids
+    pub last_frame: BlackBoxFrame,
+}
+
+/// One journal event on the way to the loss, with the vitals sampled just before it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PostMortemStep {
+    pub tick: u64,
+    pub secs_before_loss: f32,
+    pub event: String,   // journal event name
+    pub summary: String,
+    pub frame: Option<BlackBoxFrame>,
+}
+
+/// Everything the black box held when doom triggered: the sampled vitals, journal and replay
+/// of the last window, and a snapshot of the final state
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PostMortem {
+    pub reason: String,
+    pub doom_tick: u64,
+    pub window_start_tick: u64,
+    pub frames: Vec<BlackBoxFrame>,
+    pub journal: Vec<JournalEntry>,
+    pub replay: Vec<ReplayEvent>, // empty unless the session was recording
+    pub snapshot: PostMortemSnapshot,
+}
+
+impl PostMortem {
+    /// The journal walked in order, each event paired with the last frame sampled at or before it
+    pub fn timeline(&self) -> Vec<PostMortemStep> {
+        self.journal.iter()
+            .map(|entry| PostMortemStep {
+                tick: entry.tick,
+                secs_before_loss: self.doom_tick.saturating_sub(entry.tick) as f32 * 16.0 / 1000.0,
+                event: entry.event.name().to_string(),
+                summary: describe(&entry.event),
+                frame: self.frames.iter().rev().find(|frame| frame.tick <= entry.tick).cloned(),
+            })
+            .collect()
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("postmortem-{}.json", self.doom_tick)
+    }
+
+    /// Write the bundle into `dir`, returning the file it went to
+    pub fn save_to(&self, dir: &Path, protection: &SaveProtection) -> anyhow::Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(self.file_name());
+        let stats = protection.write(&path, &serde_json::to_vec(self)?)?;
+        println!("Saved post-mortem {:?}: {}", path, stats.line());
+        Ok(path)
+    }
+
+    pub fn load_from(path: &Path, protection: &SaveProtection) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(&protection.read(path)?)?)
+    }
+}
+
+fn describe(event: &JournalEvent) -> String {
+    match event {
+        JournalEvent::BlackSwanFired { swan_id } => format!("Black swan '{}' fired", swan_id),
+        JournalEvent::RitualCompleted { ritual_id, outcome } => format!("Ritual '{}' ended in {}", ritual_id, outcome),
+        JournalEvent::DayClosed { sla_hit_pct } => format!("Day closed at {:.1}% SLA", sla_hit_pct),
+        JournalEvent::Victory => "Victory".to_string(),
+        JournalEvent::Loss { reason } => format!("Lost: {}", reason),
+    }
+}
+
+/// Replay events recorded from `since_tick` on, found by the log's tick markers
+pub fn replay_since(log: &ReplayLog, since_tick: u64) -> Vec<ReplayEvent> {
+    let start = log.events.iter().position(|event| matches!(event, ReplayEvent::Tick { n } if *n >= since_tick));
+    start.map(|start| log.events[start..].to_vec()).unwrap_or_default()
+}
+
+/// Flight recorder: keeps a rolling window of vitals and seals it into a post-mortem when the
+/// colony is lost. Hosts archive the bundle through `take_unarchived`.
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BlackBox {
+    pub config: BlackBoxConfig,
+    pub frames: VecDeque<BlackBoxFrame>,
+    pub last: Option<PostMortem>,
+    #[serde(default)]
+    archived: bool,
+}
+
+impl BlackBox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: BlackBoxConfig) -> Self {
+        Self { config, ..Self::default() }
+    }
+
+    fn record(&mut self, frame: BlackBoxFrame) {
+        let window_start = frame.tick.saturating_sub(self.config.window_ticks());
+        self.frames.push_back(frame);
+        while self.frames.front().is_some_and(|f| f.tick < window_start) {
+            self.frames.pop_front();
+        }
+    }
+
+    /// The latest post-mortem, once, if no host has archived it yet
+    pub fn take_unarchived(&mut self) -> Option<PostMortem> {
+        if self.archived {
+            return None;
+        }
+        self.archived = true;
+        self.last.clone()
+    }
+}
+
+/// Sample the colony's vitals while it runs and seal a post-mortem on the tick doom is declared
+pub fn black_box_system(world: &mut World) {
+    let Some(now_tick) = world.get_resource::<SimClock>().map(|clock| clock.now.timestamp_millis() as u64 / 16) else {
+        return;
+    };
+    let Some(win_loss) = world.get_resource::<WinLossState>().cloned() else {
+        return;
+    };
+    let Some(black_box) = world.get_resource::<BlackBox>() else {
+        return;
+    };
+
+    if !win_loss.doom {
+        let due = black_box.frames.back().is_none_or(|f| now_tick >= f.tick + black_box.config.sample_every_ticks.max(1));
+        if due {
+            let frame = BlackBoxFrame::capture(world, now_tick);
+            world.resource_mut::<BlackBox>().record(frame);
+        }
+        return;
+    }
+
+    let doom_tick = win_loss.doom_time.unwrap_or(now_tick);
+    if black_box.last.as_ref().is_some_and(|p| p.doom_tick == doom_tick) {
+        return;
+    }
+    let window_start_tick = doom_tick.saturating_sub(black_box.config.window_ticks());
+    let last_frame = BlackBoxFrame::capture(world, now_tick);
+    let journal = world.get_resource::<EventJournal>()
+        .map(|journal| journal.entries.iter().filter(|e| e.tick >= window_start_tick).cloned().collect())
+        .unwrap_or_default();
+    let replay = world.get_resource::<ReplayLog>().map(|log| replay_since(log, window_start_tick)).unwrap_or_default();
+    let snapshot = PostMortemSnapshot {
+        colony: world.get_resource::<Colony>().cloned(),
+        win_loss: win_loss.clone(),
+        faults: world.get_resource::<FaultKpi>().cloned().unwrap_or_default(),
+        research: world.get_resource::<ResearchState>().map(|r| r.acquired.clone()).unwrap_or_default(),
+        last_frame,
+    };
+
+    let mut black_box = world.resource_mut::<BlackBox>();
+    let mut frames: Vec<BlackBoxFrame> = black_box.frames.iter().cloned().collect();
+    frames.push(snapshot.last_frame.clone());
+    let postmortem = PostMortem {
+        reason: win_loss.doom_reason.clone().unwrap_or_else(|| "unknown".to_string()),
+        doom_tick,
+        window_start_tick,
+        frames,
+        journal,
+        replay,
+        snapshot,
+    };
+    println!(
+        "Black box sealed: {} frames, {} journal events before '{}'",
+        postmortem.frames.len(), postmortem.journal.len(), postmortem.reason
+    );
+    black_box.last = Some(postmortem);
+    black_box.archived = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ReplayMode, TickScale};
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.insert_resource(SimClock { tick_scale: TickScale::RealTime, now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH })
+            .insert_resource(WinLossState::new())
+            .insert_resource(EventJournal::new())
+            .insert_resource(FaultKpi::default())
+            .insert_resource(BlackBox::with_config(BlackBoxConfig { window_minutes: 1.0, sample_every_ticks: 100 }))
+            .add_systems(Update, black_box_system);
+        app
+    }
+
+    fn set_tick(app: &mut App, tick: u64) {
+        app.world_mut().resource_mut::<SimClock>().now = chrono::DateTime::<chrono::Utc>::UNIX_EPOCH + chrono::Duration::milliseconds(tick as i64 * 16);
+    }
+
+    #[test]
+    fn test_frames_cover_only_the_window() {
+        let mut app = app();
+        for tick in (0..10_000).step_by(50) {
+            set_tick(&mut app, tick);
+            app.update();
+        }
+        let frames = &app.world().resource::<BlackBox>().frames;
+        assert_eq!(frames.back().unwrap().tick, 9_900);
+        assert!(frames.front().unwrap().tick >= 9_900 - 3750);
+        assert!(frames.iter().zip(frames.iter().skip(1)).all(|(a, b)| b.tick - a.tick >= 100));
+    }
+
+    #[test]
+    fn test_doom_seals_one_bundle_with_its_journal() {
+        let mut app = app();
+        {
+            let mut journal = app.world_mut().resource_mut::<EventJournal>();
+            journal.push(100, JournalEvent::BlackSwanFired { swan_id: "too_early".into() });
+            journal.push(5_000, JournalEvent::BlackSwanFired { swan_id: "vram_leak".into() });
+        }
+        for tick in [4_900, 5_050, 5_100] {
+            set_tick(&mut app, tick);
+            app.update();
+        }
+        {
+            let mut win_loss = app.world_mut().resource_mut::<WinLossState>();
+            win_loss.doom = true;
+            win_loss.doom_time = Some(5_100);
+            win_loss.doom_reason = Some("Power deficit".into());
+        }
+        app.update();
+        set_tick(&mut app, 5_200);
+        app.update();
+
+        let mut black_box = app.world_mut().resource_mut::<BlackBox>();
+        let postmortem = black_box.take_unarchived().unwrap();
+        assert!(black_box.take_unarchived().is_none());
+        assert_eq!((postmortem.reason.as_str(), postmortem.doom_tick), ("Power deficit", 5_100));
+        let timeline = postmortem.timeline();
+        assert_eq!(timeline.len(), 1);
+        assert_eq!(timeline[0].summary, "Black swan 'vram_leak' fired");
+        assert_eq!(timeline[0].frame.as_ref().map(|f| f.tick), Some(4_900));
+        assert_eq!(timeline[0].secs_before_loss, 1.6);
+    }
+
+    #[test]
+    fn test_replay_is_cut_at_the_window() {
+        let mut log = ReplayLog::new();
+        log.mode = ReplayMode::Record;
+        for n in [10, 20, 30] {
+            log.record_event(ReplayEvent::Tick { n });
+            log.record_event(ReplayEvent::PolicyChange { policy: format!("p{}", n) });
+        }
+        let tail = replay_since(&log, 15);
+        assert_eq!(tail.len(), 4);
+        assert!(matches!(tail[0], ReplayEvent::Tick { n: 20 }));
+        assert!(replay_since(&log, 31).is_empty());
+    }
+}
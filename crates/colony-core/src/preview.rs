@@ -0,0 +1,117 @@
+use colony_modsdk::KpiDeltas;
+use serde::{Serialize, Deserialize};
+use crate::{Colony, ActiveScheduler, SchedPolicy, ResourceTunables, CorruptionTunables};
+use crate::shadow::{KpiSnapshot, ShadowSeed, ShadowWorld};
+
+pub const DEFAULT_PREVIEW_TICKS: u32 = 120;
+pub const MAX_PREVIEW_TICKS: u32 = 3600;
+
+/// Proposed change set to evaluate before committing it to the live sim
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreviewRequest {
+    pub ticks: Option<u32>,
+    pub power_cap_kw: Option<f32>,
+    pub bandwidth_total_gbps: Option<f32>,
+    pub tunables: Option<ResourceTunables>,
+    pub corruption_tunables: Option<CorruptionTunables>,
+    pub scheduler: Option<SchedPolicy>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewResult {
+    pub ticks_simulated: u32,
+    pub baseline: KpiSnapshot,
+    pub proposed: KpiSnapshot,
+    pub deltas: KpiDeltas,
+}
+
+impl PreviewRequest {
+    pub fn is_empty(&self) -> bool {
+        self.power_cap_kw.is_none()
+            && self.bandwidth_total_gbps.is_none()
+            && self.tunables.is_none()
+            && self.corruption_tunables.is_none()
+            && self.scheduler.is_none()
+    }
+
+    pub fn apply(&self, colony: &mut Colony, scheduler: &mut ActiveScheduler) {
+        if let Some(cap) = self.power_cap_kw {
+            colony.power_cap_kw = cap;
+        }
+        if let Some(bw) = self.bandwidth_total_gbps {
+            colony.bandwidth_total_gbps = bw;
+        }
+        if let Some(ref tunables) = self.tunables {
+            colony.tunables = tunables.clone();
+        }
+        if let Some(ref corruption_tun) = self.corruption_tunables {
+            colony.corruption_tun = corruption_tun.clone();
+        }
+        if let Some(policy) = self.scheduler {
+            scheduler.policy = policy;
+            scheduler.custom = None;
+        }
+    }
+}
+
+/// Run the forked state forward twice in shadow worlds, once unchanged and once
+/// with the proposed changes, and report how the KPIs diverge.
+pub fn run_preview(seed: &ShadowSeed, request: &PreviewRequest) -> PreviewResult {
+    let ticks = request.ticks.unwrap_or(DEFAULT_PREVIEW_TICKS).min(MAX_PREVIEW_TICKS);
+
+    let mut baseline_world = ShadowWorld::from_seed(seed);
+    let baseline = baseline_world.advance(ticks);
+
+    let mut proposed_seed = seed.clone();
+    request.apply(&mut proposed_seed.colony, &mut proposed_seed.scheduler);
+
+    let mut proposed_world = ShadowWorld::from_seed(&proposed_seed);
+    let proposed = proposed_world.advance(ticks);
+
+    let deltas = baseline_world.diff_kpis(&mut proposed_world);
+
+    PreviewResult {
+        ticks_simulated: ticks,
+        baseline,
+        proposed,
+        deltas,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_request_apply() {
+        let mut colony = Colony {
+            power_cap_kw: 1000.0,
+            bandwidth_total_gbps: 32.0,
+            corruption_field: 0.0,
+            target_uptime_days: 365,
+            meters: crate::GlobalMeters::new(),
+            tunables: ResourceTunables::default(),
+            corruption_tun: CorruptionTunables::default(),
+            seeds: Default::default(),
+            seed: 42,
+        };
+        let mut scheduler = ActiveScheduler::default();
+
+        let request = PreviewRequest {
+            power_cap_kw: Some(1200.0),
+            scheduler: Some(SchedPolicy::Edf),
+            ..Default::default()
+        };
+        assert!(!request.is_empty());
+
+        request.apply(&mut colony, &mut scheduler);
+        assert_eq!(colony.power_cap_kw, 1200.0);
+        assert_eq!(colony.bandwidth_total_gbps, 32.0);
+        assert_eq!(scheduler.policy, SchedPolicy::Edf);
+    }
+
+    #[test]
+    fn test_empty_preview_request() {
+        assert!(PreviewRequest::default().is_empty());
+    }
+}
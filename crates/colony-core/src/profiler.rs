@@ -0,0 +1,173 @@
+use bevy::platform::time::Instant; // std's Instant::now panics on wasm32
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::VecDeque;
+use crate::{JobQueue, Worker, Workyard};
+
+/// One sim step per frame at the RealTime rate
+pub const TARGET_TICKS_PER_SEC: f32 = 1000.0 / 16.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FrameSample {
+    pub frame_ms: f32, // wall time between sim steps
+    pub sim_ms: f32,   // spent inside the colony systems
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntityCounts {
+    pub total: usize,
+    pub workers: usize,
+    pub yards: usize,
+    pub queued_jobs: usize,
+}
+
+/// What's holding the tick rate below target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Bottleneck {
+    OnTarget,
+    Sim,    // colony systems take most of the frame
+    Render, // the frame is slow but the sim isn't what's eating it
+}
+
+/// Rolling frame-vs-sim timing for the speed HUD
+#[derive(Resource, Debug, Clone)]
+pub struct SimProfiler {
+    pub target_tps: f32,
+    pub window: usize,
+    pub samples: VecDeque<FrameSample>,
+    pub steps: u64,
+    pub entities: EntityCounts,
+    last_frame: Option<Instant>,
+    sim_started: Option<Instant>,
+    frame_ms: f32,
+}
+
+impl Default for SimProfiler {
+    fn default() -> Self {
+        Self {
+            target_tps: TARGET_TICKS_PER_SEC,
+            window: 120,
+            samples: VecDeque::new(),
+            steps: 0,
+            entities: EntityCounts::default(),
+            last_frame: None,
+            sim_started: None,
+            frame_ms: 0.0,
+        }
+    }
+}
+
+impl SimProfiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, sample: FrameSample) {
+        self.samples.push_back(sample);
+        while self.samples.len() > self.window {
+            self.samples.pop_front();
+        }
+        self.steps += 1;
+    }
+
+    pub fn avg_frame_ms(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        self.samples.iter().map(|s| s.frame_ms).sum::<f32>() / self.samples.len() as f32
+    }
+
+    pub fn avg_sim_ms(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        self.samples.iter().map(|s| s.sim_ms).sum::<f32>() / self.samples.len() as f32
+    }
+
+    pub fn achieved_tps(&self) -> f32 {
+        let frame_ms = self.avg_frame_ms();
+        if frame_ms <= 0.0 { 0.0 } else { 1000.0 / frame_ms }
+    }
+
+    /// Share of each frame spent in the sim, 0..1
+    pub fn sim_share(&self) -> f32 {
+        let frame_ms = self.avg_frame_ms();
+        if frame_ms <= 0.0 { 0.0 } else { (self.avg_sim_ms() / frame_ms).min(1.0) }
+    }
+
+    pub fn bottleneck(&self) -> Bottleneck {
+        if self.samples.is_empty() || self.achieved_tps() >= self.target_tps * 0.95 {
+            Bottleneck::OnTarget
+        } else if self.sim_share() >= 0.5 {
+            Bottleneck::Sim
+        } else {
+            Bottleneck::Render
+        }
+    }
+}
+
+/// Runs before the first colony set
+pub fn sim_profile_begin(mut profiler: ResMut<SimProfiler>) {
+    let now = Instant::now();
+    profiler.frame_ms = profiler.last_frame.map(|t| (now - t).as_secs_f32() * 1000.0).unwrap_or(0.0);
+    profiler.last_frame = Some(now);
+    profiler.sim_started = Some(now);
+}
+
+/// Runs after the last colony set
+pub fn sim_profile_end(
+    mut profiler: ResMut<SimProfiler>,
+    entities: Query<Entity>,
+    workers: Query<(), With<Worker>>,
+    yards: Query<(), With<Workyard>>,
+    jobq: Res<JobQueue>,
+) {
+    let Some(started) = profiler.sim_started.take() else {
+        return;
+    };
+    let sim_ms = started.elapsed().as_secs_f32() * 1000.0;
+    // The first step has no previous frame to measure against
+    if profiler.frame_ms > 0.0 {
+        let frame_ms = profiler.frame_ms;
+        profiler.record(FrameSample { frame_ms, sim_ms });
+    }
+    profiler.entities = EntityCounts {
+        total: entities.iter().count(),
+        workers: workers.iter().count(),
+        yards: yards.iter().count(),
+        queued_jobs: jobq.len(),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rates_and_share_from_samples() {
+        let mut profiler = SimProfiler::new();
+        assert_eq!(profiler.bottleneck(), Bottleneck::OnTarget);
+        for _ in 0..4 {
+            profiler.record(FrameSample { frame_ms: 16.0, sim_ms: 4.0 });
+        }
+        assert_eq!(profiler.achieved_tps(), 62.5);
+        assert_eq!(profiler.sim_share(), 0.25);
+        assert_eq!(profiler.bottleneck(), Bottleneck::OnTarget);
+    }
+
+    #[test]
+    fn test_bottleneck_blames_the_slow_side() {
+        let mut profiler = SimProfiler::new();
+        profiler.window = 2;
+        profiler.record(FrameSample { frame_ms: 40.0, sim_ms: 30.0 });
+        profiler.record(FrameSample { frame_ms: 40.0, sim_ms: 30.0 });
+        assert_eq!(profiler.bottleneck(), Bottleneck::Sim);
+
+        // Old samples age out of the window
+        profiler.record(FrameSample { frame_ms: 40.0, sim_ms: 2.0 });
+        profiler.record(FrameSample { frame_ms: 40.0, sim_ms: 2.0 });
+        assert_eq!(profiler.samples.len(), 2);
+        assert_eq!(profiler.bottleneck(), Bottleneck::Render);
+        assert_eq!(profiler.steps, 4);
+    }
+}
@@ -0,0 +1,394 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use colony_io::IoSimulatorConfig;
+use crate::{Achievements, PausePolicies, DEFAULT_PROFILE_PATH};
+
+pub const DEFAULT_PROFILE_NAME: &str = "default";
+
+/// Overrides the platform config directory, e.g. for portable installs or tests
+pub const CONFIG_DIR_ENV: &str = "COLONY_CONFIG_DIR";
+
+const LAST_PROFILE_FILE: &str = "last_profile";
+
+/// Smallest window the desktop UI restores to
+pub const MIN_WINDOW_SIZE: (f32, f32) = (640.0, 480.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self { width: 1200.0, height: 800.0 }
+    }
+}
+
+impl WindowSettings {
+    /// Size to open the window at, never smaller than `MIN_WINDOW_SIZE`
+    pub fn clamped(&self) -> (f32, f32) {
+        (self.width.max(MIN_WINDOW_SIZE.0), self.height.max(MIN_WINDOW_SIZE.1))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChartPrefs {
+    pub history_secs: u32, // how far back KPI charts reach
+    pub smoothing: bool,   // moving average instead of raw samples
+    pub log_scale: bool,
+}
+
+impl Default for ChartPrefs {
+    fn default() -> Self {
+        Self {
+            history_secs: 300,
+            smoothing: false,
+            log_scale: false,
+        }
+    }
+}
+
+/// Per-player preferences; `settings.toml` in the profile directory, saved whenever they change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerSettings {
+    pub ui_scale: f32,
+    pub autosave_every_min: u32,
+    pub show_speed_hud: bool,
+    pub show_profiler: bool,
+    pub open_screen: Option<String>,   // desktop screen left open: achievements | workers | pause_settings
+    pub last_scenario: Option<String>, // scenario id the desktop starts with when none is given
+    pub window: WindowSettings,
+    pub pause_policies: PausePolicies,
+    pub udp_sim: IoSimulatorConfig,
+    pub http_sim: IoSimulatorConfig,
+    pub charts: ChartPrefs,
+}
+
+impl Default for PlayerSettings {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            autosave_every_min: 5,
+            show_speed_hud: false,
+            show_profiler: false,
+            open_screen: None,
+            last_scenario: None,
+            window: WindowSettings::default(),
+            pause_policies: PausePolicies::default(),
+            udp_sim: IoSimulatorConfig::default(),
+            http_sim: IoSimulatorConfig::default(),
+            charts: ChartPrefs::default(),
+        }
+    }
+}
+
+/// Action name -> key name, as written in `keybindings.toml`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keybindings {
+    pub bindings: BTreeMap<String, String>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        let bindings = [
+            ("start_game", "Space"),
+            ("pause", "P"),
+            ("resume", "R"),
+            ("toggle_auto_throttle", "T"),
+            ("toggle_speed_hud", "F2"),
+            ("toggle_profiler", "F3"),
+            ("toggle_achievements", "F4"),
+            ("toggle_workers", "F5"),
+            ("toggle_pause_settings", "F6"),
+            ("toggle_postmortem", "F7"),
+            ("toggle_scheduler", "F8"),
+            ("switch_profile", "Tab"),
+            ("reset_settings", "F10"),
+        ];
+        Self {
+            bindings: bindings.iter().map(|(a, k)| (a.to_string(), k.to_string())).collect(),
+        }
+    }
+}
+
+impl Keybindings {
+    /// Key bound to an action, falling back to the default binding
+    pub fn key_for(&self, action: &str) -> Option<String> {
+        self.bindings
+            .get(action)
+            .cloned()
+            .or_else(|| Keybindings::default().bindings.get(action).cloned())
+    }
+
+    pub fn rebind(&mut self, action: &str, key: &str) {
+        self.bindings.insert(action.to_string(), key.to_string());
+    }
+}
+
+/// Progress that carries across runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Prestige {
+    pub points: u64,
+    pub runs: u32,
+    pub victories: u32,
+    pub best_score: i64,
+}
+
+impl Prestige {
+    pub fn record_run(&mut self, victory: bool, score: i64) {
+        self.runs += 1;
+        self.best_score = self.best_score.max(score);
+        if victory {
+            self.victories += 1;
+            self.points += 1 + score.max(0) as u64 / 10_000;
+        }
+    }
+}
+
+/// Where one profile keeps its files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfilePaths {
+    pub dir: PathBuf,
+    pub settings: PathBuf,
+    pub keybindings: PathBuf,
+    pub achievements: PathBuf,
+    pub prestige: PathBuf,
+}
+
+impl ProfilePaths {
+    fn new(dir: PathBuf) -> Self {
+        Self {
+            settings: dir.join("settings.toml"),
+            keybindings: dir.join("keybindings.toml"),
+            achievements: dir.join("achievements.json"),
+            prestige: dir.join("prestige.toml"),
+            dir,
+        }
+    }
+}
+
+/// The player profile in use: settings, keybindings and prestige, plus where they live
+#[derive(Resource, Debug, Clone)]
+pub struct LocalProfile {
+    pub name: String,
+    pub paths: ProfilePaths,
+    pub settings: PlayerSettings,
+    pub keybindings: Keybindings,
+    pub prestige: Prestige,
+}
+
+impl LocalProfile {
+    pub fn achievements(&self) -> Result<Achievements> {
+        Achievements::with_profile(&self.paths.achievements)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_settings()?;
+        std::fs::write(&self.paths.keybindings, toml::to_string_pretty(&self.keybindings)?)?;
+        std::fs::write(&self.paths.prestige, toml::to_string_pretty(&self.prestige)?)?;
+        Ok(())
+    }
+
+    /// Write just `settings.toml`; cheap enough to call on every change
+    pub fn save_settings(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.paths.dir)?;
+        std::fs::write(&self.paths.settings, toml::to_string_pretty(&self.settings)?)?;
+        Ok(())
+    }
+
+    /// Put every setting back to its default and save
+    pub fn reset_settings(&mut self) -> Result<()> {
+        self.settings = PlayerSettings::default();
+        self.save_settings()
+    }
+}
+
+fn load_toml_or_default<T: Default + for<'de> Deserialize<'de>>(path: &Path) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let content = std::fs::read_to_string(path)?;
+    toml::from_str(&content).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+}
+
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > 32 {
+        return Err(anyhow::anyhow!("profile name must be 1 to 32 characters"));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(anyhow::anyhow!("profile name '{}' may only use letters, digits, '-' and '_'", name));
+    }
+    Ok(())
+}
+
+/// Local profiles under the platform config directory, one subdirectory each
+#[derive(Debug, Clone)]
+pub struct ProfileStore {
+    pub root: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// `$COLONY_CONFIG_DIR`, else the platform config directory (e.g. ~/.config/colony), else `saves/`
+    pub fn open_default() -> Self {
+        let root = std::env::var_os(CONFIG_DIR_ENV)
+            .map(PathBuf::from)
+            .or_else(|| directories::ProjectDirs::from("io", "sempervent", "colony").map(|d| d.config_dir().to_path_buf()))
+            .unwrap_or_else(|| PathBuf::from("saves"));
+        Self::new(root)
+    }
+
+    fn profiles_dir(&self) -> PathBuf {
+        self.root.join("profiles")
+    }
+
+    pub fn paths(&self, name: &str) -> ProfilePaths {
+        ProfilePaths::new(self.profiles_dir().join(name))
+    }
+
+    pub fn list(&self) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(self.profiles_dir())
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.path().is_dir())
+                    .filter_map(|e| e.file_name().into_string().ok())
+                    .filter(|n| validate_profile_name(n).is_ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    pub fn last_used(&self) -> Option<String> {
+        std::fs::read_to_string(self.root.join(LAST_PROFILE_FILE))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|n| validate_profile_name(n).is_ok())
+    }
+
+    /// Load a profile, creating it on first use, and remember it as the last one used
+    pub fn load(&self, name: &str) -> Result<LocalProfile> {
+        validate_profile_name(name)?;
+        let paths = self.paths(name);
+        let is_new = !paths.dir.exists();
+        std::fs::create_dir_all(&paths.dir)?;
+        let profile = LocalProfile {
+            name: name.to_string(),
+            settings: load_toml_or_default(&paths.settings)?,
+            keybindings: load_toml_or_default(&paths.keybindings)?,
+            prestige: load_toml_or_default(&paths.prestige)?,
+            paths,
+        };
+        if is_new {
+            profile.save()?;
+        }
+        std::fs::write(self.root.join(LAST_PROFILE_FILE), name)?;
+        Ok(profile)
+    }
+
+    /// `requested`, else the last profile used, else the default one.
+    /// Unlocks from the old CWD-relative profile move into the default profile the first time.
+    pub fn select(&self, requested: Option<&str>) -> Result<LocalProfile> {
+        let name = requested
+            .map(str::to_string)
+            .or_else(|| self.last_used())
+            .unwrap_or_else(|| DEFAULT_PROFILE_NAME.to_string());
+        let profile = self.load(&name)?;
+        let legacy = Path::new(DEFAULT_PROFILE_PATH);
+        if name == DEFAULT_PROFILE_NAME && legacy.exists() && !profile.paths.achievements.exists() {
+            std::fs::copy(legacy, &profile.paths.achievements)?;
+            println!("Moved achievements from {} into profile '{}'", legacy.display(), name);
+        }
+        Ok(profile)
+    }
+
+    /// The profile after `current` in name order, wrapping around
+    pub fn next_after(&self, current: &str) -> Option<String> {
+        let names = self.list();
+        let i = names.iter().position(|n| n == current)?;
+        names.get((i + 1) % names.len()).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(tag: &str) -> ProfileStore {
+        let root = std::env::temp_dir().join(format!("colony_profiles_{}_{}", tag, std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        ProfileStore::new(root)
+    }
+
+    #[test]
+    fn test_profiles_keep_separate_files() {
+        let store = temp_store("separate");
+        let mut alice = store.load("alice").unwrap();
+        alice.settings.ui_scale = 1.5;
+        alice.keybindings.rebind("pause", "Escape");
+        alice.prestige.record_run(true, 25_000);
+        alice.save().unwrap();
+        store.load("bob").unwrap();
+
+        assert_eq!(store.list(), vec!["alice", "bob"]);
+        assert_eq!(store.last_used().as_deref(), Some("bob"));
+        assert_eq!(store.next_after("bob").as_deref(), Some("alice"));
+
+        let alice = store.select(Some("alice")).unwrap();
+        assert_eq!(alice.settings.ui_scale, 1.5);
+        assert_eq!(alice.keybindings.key_for("pause").as_deref(), Some("Escape"));
+        assert_eq!(alice.prestige.points, 3);
+        let again = store.select(None).unwrap();
+        assert_eq!(again.name, "alice"); // last used
+        assert_ne!(alice.paths.achievements, store.paths("bob").achievements);
+        std::fs::remove_dir_all(&store.root).ok();
+    }
+
+    #[test]
+    fn test_settings_round_trip_and_reset() {
+        let store = temp_store("settings");
+        let mut profile = store.load("carol").unwrap();
+        profile.settings.window = WindowSettings { width: 1600.0, height: 300.0 };
+        profile.settings.last_scenario = Some("first_light_chill".to_string());
+        profile.settings.open_screen = Some("workers".to_string());
+        profile.settings.udp_sim.rate_hz = 250.0;
+        profile.settings.charts.log_scale = true;
+        profile.save_settings().unwrap();
+
+        let mut reloaded = store.load("carol").unwrap();
+        assert_eq!(reloaded.settings, profile.settings);
+        assert_eq!(reloaded.settings.window.clamped(), (1600.0, 480.0));
+
+        reloaded.reset_settings().unwrap();
+        assert_eq!(store.load("carol").unwrap().settings, PlayerSettings::default());
+        std::fs::remove_dir_all(&store.root).ok();
+    }
+
+    #[test]
+    fn test_rejects_path_like_names() {
+        let store = temp_store("names");
+        assert!(store.load("../escape").is_err());
+        assert!(store.load("").is_err());
+        assert!(validate_profile_name("speed_runner-2").is_ok());
+    }
+
+    #[test]
+    fn test_missing_bindings_fall_back_to_defaults() {
+        let bindings: Keybindings = toml::from_str("[bindings]\npause = \"Escape\"\n").unwrap();
+        assert_eq!(bindings.key_for("pause").as_deref(), Some("Escape"));
+        assert_eq!(bindings.key_for("toggle_profiler").as_deref(), Some("F3"));
+    }
+}
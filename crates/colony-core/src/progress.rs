@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::sync::{Arc, Mutex};
+use bevy::platform::time::Instant;
+use crate::{KpiRingBuffer, MemoryAccounting};
+
+/// How far a fast-forward run has got toward its target tick
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunProgress {
+    pub ticks_run: u64,
+    pub target_ticks: u64,
+    pub elapsed_secs: f64,
+    pub ticks_per_sec: f64,
+    pub eta_secs: Option<f64>, // None until a rate is known, or once done
+    pub running: bool,
+    pub done: bool,
+    #[serde(default)]
+    pub footprint: Option<RunFootprint>, // refreshed every few hundred ticks
+}
+
+/// What a running sim is holding on to; `xtask soak` watches these for unbounded growth
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunFootprint {
+    pub tick: u64,
+    pub entities: u32,
+    pub kpi_buffer_entries: usize,
+    pub tracked_bytes: usize, // MemoryAccounting's last total
+}
+
+impl RunFootprint {
+    pub fn capture(world: &World, tick: u64) -> Self {
+        Self {
+            tick,
+            entities: world.entities().len(),
+            kpi_buffer_entries: world.get_resource::<KpiRingBuffer>().map_or(0, |k| k.entries()),
+            tracked_bytes: world.get_resource::<MemoryAccounting>().map_or(0, |m| m.total_bytes),
+        }
+    }
+}
+
+impl RunProgress {
+    pub fn fraction(&self) -> f64 {
+        if self.target_ticks == 0 {
+            0.0
+        } else {
+            (self.ticks_run as f64 / self.target_ticks as f64).min(1.0)
+        }
+    }
+
+    pub fn bar(&self, width: usize) -> String {
+        let filled = (self.fraction() * width as f64).round() as usize;
+        format!("[{}{}]", "#".repeat(filled), ".".repeat(width - filled.min(width)))
+    }
+
+    /// One terminal line: bar, percent, ticks, rate and ETA
+    pub fn line(&self) -> String {
+        let eta = match self.eta_secs {
+            Some(secs) => format_secs(secs),
+            None if self.done => "done".to_string(),
+            None => "--".to_string(),
+        };
+        format!(
+            "{} {:5.1}% tick {}/{} | {:.0} ticks/s | elapsed {} | ETA {}",
+            self.bar(30),
+            self.fraction() * 100.0,
+            self.ticks_run,
+            self.target_ticks,
+            self.ticks_per_sec,
+            format_secs(self.elapsed_secs),
+            eta
+        )
+    }
+}
+
+fn format_secs(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60)
+}
+
+/// Progress shared between the thread running the sim and whoever reports it
+#[derive(Debug, Clone, Default)]
+pub struct ProgressHandle {
+    inner: Arc<Mutex<RunProgress>>,
+}
+
+impl ProgressHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start(&self, target_ticks: u64) {
+        if let Ok(mut p) = self.inner.lock() {
+            *p = RunProgress { target_ticks, running: true, ..Default::default() };
+        }
+    }
+
+    pub fn update(&self, ticks_run: u64, started: Instant) {
+        let elapsed_secs = started.elapsed().as_secs_f64();
+        if let Ok(mut p) = self.inner.lock() {
+            p.ticks_run = ticks_run;
+            p.elapsed_secs = elapsed_secs;
+            p.ticks_per_sec = if elapsed_secs > 0.0 { ticks_run as f64 / elapsed_secs } else { 0.0 };
+            p.eta_secs = (p.ticks_per_sec > 0.0)
+                .then(|| p.target_ticks.saturating_sub(ticks_run) as f64 / p.ticks_per_sec);
+        }
+    }
+
+    pub fn set_footprint(&self, footprint: RunFootprint) {
+        if let Ok(mut p) = self.inner.lock() {
+            p.footprint = Some(footprint);
+        }
+    }
+
+    pub fn finish(&self) {
+        if let Ok(mut p) = self.inner.lock() {
+            p.running = false;
+            p.done = true;
+            p.eta_secs = None;
+        }
+    }
+
+    pub fn snapshot(&self) -> RunProgress {
+        self.inner.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_line_reports_rate_and_eta() {
+        let progress = RunProgress {
+            ticks_run: 250,
+            target_ticks: 1000,
+            elapsed_secs: 5.0,
+            ticks_per_sec: 50.0,
+            eta_secs: Some(15.0),
+            running: true,
+            done: false,
+            footprint: None,
+        };
+        assert_eq!(progress.fraction(), 0.25);
+        assert_eq!(progress.bar(4), "[#...]");
+        assert!(progress.line().contains("tick 250/1000 | 50 ticks/s | elapsed 00:00:05 | ETA 00:00:15"));
+    }
+
+    #[test]
+    fn test_handle_tracks_a_run() {
+        let handle = ProgressHandle::new();
+        handle.start(100);
+        handle.update(40, Instant::now() - std::time::Duration::from_secs(2));
+        let p = handle.snapshot();
+        assert!(p.running);
+        assert!(p.ticks_per_sec > 0.0 && p.ticks_per_sec <= 20.0);
+        assert!(p.eta_secs.unwrap() >= 2.9);
+
+        handle.finish();
+        let p = handle.snapshot();
+        assert!(p.done && !p.running);
+        assert!(p.line().ends_with("ETA done"));
+    }
+}
@@ -0,0 +1,295 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use crate::{QoS, WorkyardKind};
+use crate::queue::EnqueuedJob;
+
+/// Queueing discipline applied across QoS classes before the scheduler picks jobs
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum QueueDiscipline {
+    #[default]
+    Shared,            // one ordering for all classes (scheduler sees the whole queue)
+    StrictPriority,    // Latency, then Balanced, then Throughput
+    DeficitRoundRobin, // per-class deficit counters, quantum scaled by weight
+    WeightedFair,      // virtual finish times, cost divided by weight
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QosWeights {
+    pub latency: f32,
+    pub balanced: f32,
+    pub throughput: f32,
+}
+
+impl Default for QosWeights {
+    fn default() -> Self {
+        Self {
+            latency: 4.0,
+            balanced: 2.0,
+            throughput: 1.0,
+        }
+    }
+}
+
+impl QosWeights {
+    fn get(&self, class: usize) -> f32 {
+        match class {
+            0 => self.latency,
+            1 => self.balanced,
+            _ => self.throughput,
+        }
+        .max(0.01)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QosWaitStats {
+    pub dispatched: u64,
+    pub total_wait_ticks: u64,
+    pub max_wait_ticks: u64,
+}
+
+impl QosWaitStats {
+    pub fn mean_wait_ticks(&self) -> f32 {
+        if self.dispatched == 0 {
+            0.0
+        } else {
+            self.total_wait_ticks as f32 / self.dispatched as f32
+        }
+    }
+}
+
+/// Per-yard discipline state that must survive between ticks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DisciplineState {
+    deficits: [f32; 3],
+    virtual_time: f64,
+    last_finish: [f64; 3],
+    finish_tags: HashMap<u64, f64>, // job id -> WFQ finish tag
+}
+
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QosQueueing {
+    pub discipline: QueueDiscipline,
+    pub weights: QosWeights,
+    pub drr_quantum_ms: f32,
+    pub wait: [QosWaitStats; 3], // indexed Latency, Balanced, Throughput
+    state: [DisciplineState; 4], // indexed by yard kind
+}
+
+pub fn qos_index(qos: &QoS) -> usize {
+    match qos {
+        QoS::Latency => 0,
+        QoS::Balanced => 1,
+        QoS::Throughput => 2,
+    }
+}
+
+pub fn qos_name(class: usize) -> &'static str {
+    match class {
+        0 => "Latency",
+        1 => "Balanced",
+        _ => "Throughput",
+    }
+}
+
+fn yard_index(kind: &WorkyardKind) -> usize {
+    match kind {
+        WorkyardKind::CpuArray => 0,
+        WorkyardKind::GpuFarm => 1,
+        WorkyardKind::SignalHub => 2,
+        WorkyardKind::StorageArray => 3,
+    }
+}
+
+fn job_cost(ej: &EnqueuedJob) -> f32 {
+    ej.job.pipeline.ops.iter().map(|op| op.cost_ms() as f32).sum::<f32>().max(1.0)
+}
+
+impl QosQueueing {
+    pub fn new() -> Self {
+        Self {
+            drr_quantum_ms: 10.0,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_discipline(&mut self, discipline: QueueDiscipline) {
+        if self.discipline != discipline {
+            self.discipline = discipline;
+            self.state = Default::default();
+        }
+    }
+
+    pub fn reset_wait_stats(&mut self) {
+        self.wait = Default::default();
+    }
+
+    /// Indices into `jobs` the scheduler should see this tick, in service order
+    pub fn select(&mut self, yard: &WorkyardKind, jobs: &[EnqueuedJob], slots: usize) -> Vec<usize> {
+        if self.discipline == QueueDiscipline::Shared {
+            return (0..jobs.len()).collect();
+        }
+
+        // Per-class FIFO queues, preserving enqueue order within each class
+        let mut classes: [Vec<usize>; 3] = Default::default();
+        for (i, ej) in jobs.iter().enumerate() {
+            classes[qos_index(&ej.job.qos)].push(i);
+        }
+        for class in classes.iter_mut() {
+            class.sort_by_key(|&i| jobs[i].enq_tick);
+        }
+
+        let weights = self.weights.clone();
+        let quantum = self.drr_quantum_ms.max(1.0);
+        let state = &mut self.state[yard_index(yard)];
+
+        match self.discipline {
+            QueueDiscipline::Shared => unreachable!(),
+            QueueDiscipline::StrictPriority => {
+                classes.iter().flatten().copied().take(slots).collect()
+            }
+            QueueDiscipline::DeficitRoundRobin => {
+                let mut heads = [0usize; 3];
+                let mut picked = Vec::new();
+                while picked.len() < slots && (0..3).any(|c| heads[c] < classes[c].len()) {
+                    for c in 0..3 {
+                        if heads[c] >= classes[c].len() {
+                            state.deficits[c] = 0.0; // idle classes don't bank credit
+                            continue;
+                        }
+                        state.deficits[c] += quantum * weights.get(c);
+                        while heads[c] < classes[c].len() && picked.len() < slots {
+                            let idx = classes[c][heads[c]];
+                            let cost = job_cost(&jobs[idx]);
+                            if cost > state.deficits[c] {
+                                break;
+                            }
+                            state.deficits[c] -= cost;
+                            picked.push(idx);
+                            heads[c] += 1;
+                        }
+                    }
+                }
+                picked
+            }
+            QueueDiscipline::WeightedFair => {
+                let present: std::collections::HashSet<u64> = jobs.iter().map(|ej| ej.job.id).collect();
+                state.finish_tags.retain(|id, _| present.contains(id));
+
+                for (c, class) in classes.iter().enumerate() {
+                    for &idx in class {
+                        let id = jobs[idx].job.id;
+                        if !state.finish_tags.contains_key(&id) {
+                            let start = state.virtual_time.max(state.last_finish[c]);
+                            let finish = start + job_cost(&jobs[idx]) as f64 / weights.get(c) as f64;
+                            state.last_finish[c] = finish;
+                            state.finish_tags.insert(id, finish);
+                        }
+                    }
+                }
+
+                let mut order: Vec<usize> = (0..jobs.len()).collect();
+                order.sort_by(|&a, &b| {
+                    let fa = state.finish_tags[&jobs[a].job.id];
+                    let fb = state.finish_tags[&jobs[b].job.id];
+                    fa.partial_cmp(&fb).unwrap_or(std::cmp::Ordering::Equal)
+                });
+                order.truncate(slots);
+                if let Some(&last) = order.last() {
+                    state.virtual_time = state.virtual_time.max(state.finish_tags[&jobs[last].job.id]);
+                }
+                order
+            }
+        }
+    }
+
+    pub fn record_dispatch(&mut self, qos: &QoS, enq_tick: u64, now_tick: u64) {
+        let wait = now_tick.saturating_sub(enq_tick);
+        let stats = &mut self.wait[qos_index(qos)];
+        stats.dispatched += 1;
+        stats.total_wait_ticks += wait;
+        stats.max_wait_ticks = stats.max_wait_ticks.max(wait);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Job, Pipeline, Op};
+
+    fn job(id: u64, qos: QoS, enq_tick: u64) -> EnqueuedJob {
+        EnqueuedJob::new(Job {
+            id,
+            pipeline: Pipeline { ops: vec![Op::Decode], mutation_tag: None, id: None },
+            qos,
+            deadline_ms: 50,
+            payload_sz: 64,
+        }, enq_tick)
+    }
+
+    fn mixed_queue() -> Vec<EnqueuedJob> {
+        let mut jobs = Vec::new();
+        for i in 0..6 {
+            jobs.push(job(i, QoS::Throughput, i));
+        }
+        for i in 6..12 {
+            jobs.push(job(i, QoS::Latency, i));
+        }
+        jobs
+    }
+
+    #[test]
+    fn test_shared_passes_everything_through() {
+        let mut q = QosQueueing::new();
+        let jobs = mixed_queue();
+        assert_eq!(q.select(&WorkyardKind::CpuArray, &jobs, 2).len(), jobs.len());
+    }
+
+    #[test]
+    fn test_strict_priority_serves_latency_first() {
+        let mut q = QosQueueing::new();
+        q.set_discipline(QueueDiscipline::StrictPriority);
+        let jobs = mixed_queue();
+        let picked = q.select(&WorkyardKind::CpuArray, &jobs, 4);
+        assert!(picked.iter().all(|&i| matches!(jobs[i].job.qos, QoS::Latency)));
+        assert_eq!(jobs[picked[0]].job.id, 6);
+    }
+
+    #[test]
+    fn test_drr_shares_by_weight() {
+        let mut q = QosQueueing::new();
+        q.set_discipline(QueueDiscipline::DeficitRoundRobin);
+        q.drr_quantum_ms = 4.0; // one Decode job per unit of weight per round
+        let jobs = mixed_queue();
+        let picked = q.select(&WorkyardKind::CpuArray, &jobs, 5);
+        let latency = picked.iter().filter(|&&i| matches!(jobs[i].job.qos, QoS::Latency)).count();
+        assert_eq!(latency, 4);
+        assert_eq!(picked.len() - latency, 1);
+    }
+
+    #[test]
+    fn test_wfq_interleaves_by_weight() {
+        let mut q = QosQueueing::new();
+        q.set_discipline(QueueDiscipline::WeightedFair);
+        let jobs = mixed_queue();
+        let picked = q.select(&WorkyardKind::CpuArray, &jobs, 5);
+        let latency = picked.iter().filter(|&&i| matches!(jobs[i].job.qos, QoS::Latency)).count();
+        assert_eq!(latency, 4);
+
+        // Tags persist, so a job already tagged keeps its place next tick
+        let again = q.select(&WorkyardKind::CpuArray, &jobs, 5);
+        assert_eq!(picked, again);
+    }
+
+    #[test]
+    fn test_wait_stats() {
+        let mut q = QosQueueing::new();
+        q.record_dispatch(&QoS::Latency, 10, 14);
+        q.record_dispatch(&QoS::Latency, 10, 20);
+        assert_eq!(q.wait[0].dispatched, 2);
+        assert_eq!(q.wait[0].mean_wait_ticks(), 7.0);
+        assert_eq!(q.wait[0].max_wait_ticks, 10);
+        assert_eq!(q.wait[2].dispatched, 0);
+    }
+}
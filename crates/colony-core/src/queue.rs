@@ -1,24 +1,66 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
-use super::Job;
+use std::collections::VecDeque;
+use anyhow::Result;
+use super::{FlowOutcome, FlowTracker, Job, QoS, SimClock, TenantLedger};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EnqueuedJob {
     pub job: Job,
     pub enq_tick: u64,
+    #[serde(default)]
+    pub parse_fault: Option<colony_io::ParseFaultKind>, // its input was rejected by the IO parser
+    #[serde(default)]
+    pub flow: Option<colony_io::FlowTag>, // packet behind the job, when the source tags flows
+    #[serde(default)]
+    pub attempt: u32, // retries so far under the pipeline's retry policy
+    #[serde(default)]
+    pub tenant: Option<String>, // team the job is billed to, see TenantLedger
+    #[serde(default)]
+    pub payload_digest: Option<u64>, // content hash of the input when the source had its bytes, see OpCache
 }
 
 impl EnqueuedJob {
     pub fn new(job: Job, enq_tick: u64) -> Self {
-        Self { job, enq_tick }
+        Self { job, enq_tick, parse_fault: None, flow: None, attempt: 0, tenant: None, payload_digest: None }
     }
 }
 
-#[derive(Resource, Default, Debug, Serialize, Deserialize)]
+/// Whether a pipeline with these ops is queued for, and can only run on, a GPU farm
+pub fn requires_gpu(ops: &[super::Op]) -> bool {
+    ops.iter().any(|op| matches!(op, super::Op::Yolo | super::Op::Fft))
+}
+
+/// The class queue a job waits in; each yard kind serves its own class
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobClass {
+    Cpu,
+    Gpu,
+    Io,
+    Storage,
+}
+
+impl JobClass {
+    pub fn of_yard(kind: &super::WorkyardKind) -> Self {
+        match kind {
+            super::WorkyardKind::CpuArray => JobClass::Cpu,
+            super::WorkyardKind::GpuFarm => JobClass::Gpu,
+            super::WorkyardKind::SignalHub => JobClass::Io,
+            super::WorkyardKind::StorageArray => JobClass::Storage,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Default, Debug, Serialize, Deserialize)]
 pub struct JobQueue {
     pub cpu: Vec<EnqueuedJob>,
     pub gpu: Vec<EnqueuedJob>,
     pub io: Vec<EnqueuedJob>,
+    #[serde(default)]
+    pub storage: Vec<EnqueuedJob>, // Export/ArchiveWrite work for the StorageArray
+    #[serde(default)]
+    pub maintenance: Vec<super::MaintenanceEntry>, // per-yard reserved lane, see MaintenanceLane
 }
 
 impl JobQueue {
@@ -27,27 +69,45 @@ impl JobQueue {
             cpu: Vec::new(),
             gpu: Vec::new(),
             io: Vec::new(),
+            storage: Vec::new(),
+            maintenance: Vec::new(),
         }
     }
 
     pub fn push(&mut self, job: Job, tick: u64) {
-        let enqueued = EnqueuedJob::new(job, tick);
-        
-        // Simple classification based on operations
-        let has_gpu_ops = enqueued.job.pipeline.ops.iter().any(|op| {
-            matches!(op, super::Op::Yolo | super::Op::Fft)
-        });
-        
-        let has_io_ops = enqueued.job.pipeline.ops.iter().any(|op| {
-            matches!(op, super::Op::UdpDemux | super::Op::HttpParse | super::Op::CanParse | super::Op::TcpSessionize)
+        self.push_enqueued(EnqueuedJob::new(job, tick));
+    }
+
+    /// Enqueue a job whose input failed to parse; it faults when dispatched instead of completing
+    pub fn push_parse_faulted(&mut self, job: Job, kind: colony_io::ParseFaultKind, tick: u64) {
+        let mut enqueued = EnqueuedJob::new(job, tick);
+        enqueued.parse_fault = Some(kind);
+        self.push_enqueued(enqueued);
+    }
+
+    pub fn push_enqueued(&mut self, enqueued: EnqueuedJob) {
+        let class = Self::class_of(&enqueued.job);
+        self.class_mut(class).push(enqueued);
+    }
+
+    /// The class queue a job goes into, judged by its ops
+    pub fn class_of(job: &Job) -> JobClass {
+        let has_gpu_ops = requires_gpu(&job.pipeline.ops);
+
+        let has_io_ops = job.pipeline.ops.iter().any(|op| {
+            matches!(op, super::Op::UdpDemux | super::Op::HttpParse | super::Op::CanParse | super::Op::MqttParse | super::Op::GrpcParse | super::Op::TcpSessionize)
         });
         
+        let has_storage_ops = super::storage_ops(&job.pipeline.ops) > 0;
+
         if has_gpu_ops {
-            self.gpu.push(enqueued);
+            JobClass::Gpu
         } else if has_io_ops {
-            self.io.push(enqueued);
+            JobClass::Io
+        } else if has_storage_ops {
+            JobClass::Storage
         } else {
-            self.cpu.push(enqueued);
+            JobClass::Cpu
         }
     }
 
@@ -75,18 +135,218 @@ impl JobQueue {
         &self.io
     }
 
+    pub fn peek_storage(&self) -> &[EnqueuedJob] {
+        &self.storage
+    }
+
+    pub fn peek_class(&self, class: JobClass) -> &[EnqueuedJob] {
+        match class {
+            JobClass::Cpu => &self.cpu,
+            JobClass::Gpu => &self.gpu,
+            JobClass::Io => &self.io,
+            JobClass::Storage => &self.storage,
+        }
+    }
+
+    pub fn class_mut(&mut self, class: JobClass) -> &mut Vec<EnqueuedJob> {
+        match class {
+            JobClass::Cpu => &mut self.cpu,
+            JobClass::Gpu => &mut self.gpu,
+            JobClass::Io => &mut self.io,
+            JobClass::Storage => &mut self.storage,
+        }
+    }
+
     pub fn len(&self) -> usize {
-        self.cpu.len() + self.gpu.len() + self.io.len()
+        self.cpu.len() + self.gpu.len() + self.io.len() + self.storage.len() + self.maintenance.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.cpu.is_empty() && self.gpu.is_empty() && self.io.is_empty()
+        self.cpu.is_empty() && self.gpu.is_empty() && self.io.is_empty() && self.storage.is_empty() && self.maintenance.is_empty()
     }
 
     pub fn clear(&mut self) {
         self.cpu.clear();
         self.gpu.clear();
         self.io.clear();
+        self.storage.clear();
+        self.maintenance.clear();
+    }
+}
+
+/// Why a job left the queue without finishing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadLetterReason {
+    QueueDrop,       // faulted and reported lost, see faults::handle_fault
+    DeadlineExpired, // waited past DeadLetterConfig::expire_after deadlines
+}
+
+/// A job that was dropped, kept so it can be inspected and requeued
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub job: EnqueuedJob,
+    pub reason: DeadLetterReason,
+    pub dropped_tick: u64,
+    pub age_ticks: u64, // from enqueue to drop
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetterConfig {
+    pub capacity: usize, // letters kept; the oldest are evicted first
+    #[serde(default)]
+    pub expire_after: Option<f32>, // deadlines a job may wait before it's dead-lettered; None never expires
+    #[serde(default)]
+    pub escalate_after: Option<f32>, // deadlines a Throughput/Balanced job may wait before it's raised to Latency
+}
+
+impl Default for DeadLetterConfig {
+    fn default() -> Self {
+        Self { capacity: 1000, expire_after: None, escalate_after: None }
+    }
+}
+
+impl DeadLetterConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.capacity == 0 {
+            anyhow::bail!("capacity must be at least 1");
+        }
+        for (name, after) in [("expire_after", self.expire_after), ("escalate_after", self.escalate_after)] {
+            if after.is_some_and(|deadlines| !deadlines.is_finite() || deadlines <= 0.0) {
+                anyhow::bail!("{} must be a positive number of deadlines", name);
+            }
+        }
+        if let (Some(expire), Some(escalate)) = (self.expire_after, self.escalate_after) {
+            if escalate >= expire {
+                anyhow::bail!("escalate_after ({}) must come before expire_after ({})", escalate, expire);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DeadLetterStats {
+    pub dropped: u64,   // QueueDrop faults recorded
+    pub expired: u64,   // jobs that waited past expire_after
+    pub escalated: u64, // starving jobs raised to Latency QoS
+    pub requeued: u64,
+    pub evicted: u64,   // letters pushed out at capacity
+}
+
+/// Jobs dropped by QueueDrop faults or deadline expiry, with why and how long they waited.
+/// Also escalates starving jobs before they expire.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeadLetterQueue {
+    pub config: DeadLetterConfig,
+    pub letters: VecDeque<DeadLetter>, // oldest first
+    pub stats: DeadLetterStats,
+}
+
+impl DeadLetterQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a new config; letters past the new capacity are evicted
+    pub fn configure(&mut self, config: DeadLetterConfig) -> Result<()> {
+        config.validate()?;
+        self.config = config;
+        self.evict();
+        Ok(())
+    }
+
+    pub fn depth(&self) -> usize {
+        self.letters.len()
+    }
+
+    pub fn record(&mut self, job: EnqueuedJob, reason: DeadLetterReason, tick: u64) {
+        match reason {
+            DeadLetterReason::QueueDrop => self.stats.dropped += 1,
+            DeadLetterReason::DeadlineExpired => self.stats.expired += 1,
+        }
+        let age_ticks = tick.saturating_sub(job.enq_tick);
+        self.letters.push_back(DeadLetter { job, reason, dropped_tick: tick, age_ticks });
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.letters.len() > self.config.capacity {
+            self.letters.pop_front();
+            self.stats.evicted += 1;
+        }
+    }
+
+    /// Put a dead-lettered job back on the queue as a fresh attempt, if its tenant has room
+    pub fn requeue(&mut self, job_id: u64, jobq: &mut JobQueue, tenants: &mut TenantLedger, tick: u64) -> Result<()> {
+        let index = self.letters.iter().position(|letter| letter.job.job.id == job_id)
+            .ok_or_else(|| anyhow::anyhow!("Job {} is not in the dead-letter queue", job_id))?;
+        let mut job = self.letters[index].job.clone();
+        tenants.admit(job.tenant.as_deref())?;
+        self.letters.remove(index);
+        job.enq_tick = tick;
+        job.attempt = 0;
+        job.flow = None; // its flow already counted the drop
+        jobq.push_enqueued(job);
+        self.stats.requeued += 1;
+        Ok(())
+    }
+
+    /// Requeue every letter whose tenant has room; returns how many went back
+    pub fn requeue_all(&mut self, jobq: &mut JobQueue, tenants: &mut TenantLedger, tick: u64) -> usize {
+        let ids: Vec<u64> = self.letters.iter().map(|letter| letter.job.job.id).collect();
+        ids.into_iter().filter(|id| self.requeue(*id, jobq, tenants, tick).is_ok()).count()
+    }
+
+    /// Escalate starving jobs and take out the expired ones, which the caller still has to settle
+    pub fn sweep(&mut self, jobq: &mut JobQueue, tick: u64) -> Vec<EnqueuedJob> {
+        let (expire_after, escalate_after) = (self.config.expire_after, self.config.escalate_after);
+        if expire_after.is_none() && escalate_after.is_none() {
+            return Vec::new();
+        }
+        let mut expired = Vec::new();
+        for class in [JobClass::Cpu, JobClass::Gpu, JobClass::Io, JobClass::Storage] {
+            let queue = jobq.class_mut(class);
+            let mut i = 0;
+            while i < queue.len() {
+                let waited = deadlines_waited(&queue[i], tick);
+                if expire_after.is_some_and(|after| waited >= after) {
+                    expired.push(queue.remove(i));
+                    continue;
+                }
+                if escalate_after.is_some_and(|after| waited >= after) && !matches!(queue[i].job.qos, QoS::Latency) {
+                    queue[i].job.qos = QoS::Latency;
+                    self.stats.escalated += 1;
+                }
+                i += 1;
+            }
+        }
+        for job in &expired {
+            self.record(job.clone(), DeadLetterReason::DeadlineExpired, tick);
+        }
+        expired
+    }
+}
+
+/// How many of its own deadlines a job has spent in the queue
+fn deadlines_waited(job: &EnqueuedJob, tick: u64) -> f32 {
+    (tick.saturating_sub(job.enq_tick) * 16) as f32 / job.job.deadline_ms.max(1) as f32
+}
+
+/// Escalate starving jobs and dead-letter the expired ones before dispatch
+pub fn dead_letter_system(
+    clock: Res<SimClock>,
+    mut dead_letters: ResMut<DeadLetterQueue>,
+    mut jobq: ResMut<JobQueue>,
+    mut tenants: ResMut<TenantLedger>,
+    mut flows: ResMut<FlowTracker>,
+) {
+    let tick = clock.now.timestamp_millis() as u64 / 16;
+    for job in dead_letters.sweep(&mut jobq, tick) {
+        tenants.record_done(job.tenant.as_deref(), None);
+        if let Some(tag) = job.flow {
+            flows.finish(tag, job.enq_tick, tick, 0, FlowOutcome::Dropped);
+        }
     }
 }
 
@@ -127,6 +387,7 @@ mod tests {
             pipeline: Pipeline {
                 ops: vec![Op::Decode],
                 mutation_tag: None,
+                id: None,
             },
             qos: QoS::Balanced,
             deadline_ms: 100,
@@ -178,4 +439,55 @@ mod tests {
         // Average: (0.4 + 0.3 + 0.2) / 3 = 0.3
         assert!((avg - 0.3).abs() < 0.01);
     }
+
+    #[test]
+    fn test_dead_letters_requeue_and_evict() {
+        let mut dead_letters = DeadLetterQueue::new();
+        let mut queue = JobQueue::new();
+        let mut tenants = TenantLedger::new();
+        dead_letters.configure(DeadLetterConfig { capacity: 2, ..Default::default() }).unwrap();
+        for id in 1..=3 {
+            let mut job = EnqueuedJob::new(create_test_job(id), 100);
+            job.attempt = 2;
+            dead_letters.record(job, DeadLetterReason::QueueDrop, 150);
+        }
+        assert_eq!(dead_letters.depth(), 2);
+        assert_eq!(dead_letters.letters[0].job.job.id, 2);
+        assert_eq!(dead_letters.letters[0].age_ticks, 50);
+        assert_eq!((dead_letters.stats.dropped, dead_letters.stats.evicted), (3, 1));
+
+        assert!(dead_letters.requeue(1, &mut queue, &mut tenants, 200).is_err());
+        dead_letters.requeue(2, &mut queue, &mut tenants, 200).unwrap();
+        let requeued = queue.pop_cpu().unwrap();
+        assert_eq!((requeued.job.id, requeued.enq_tick, requeued.attempt), (2, 200, 0));
+        assert_eq!(dead_letters.requeue_all(&mut queue, &mut tenants, 200), 1);
+        assert_eq!((dead_letters.depth(), queue.len(), dead_letters.stats.requeued), (0, 1, 2));
+    }
+
+    #[test]
+    fn test_sweep_escalates_then_expires() {
+        let mut dead_letters = DeadLetterQueue::new();
+        let mut queue = JobQueue::new();
+        // 100ms deadline: 6.25 ticks per deadline
+        queue.push(create_test_job(1), 0);
+        assert!(dead_letters.sweep(&mut queue, 1000).is_empty());
+        assert!(matches!(queue.cpu[0].job.qos, QoS::Balanced));
+
+        let config = DeadLetterConfig { expire_after: Some(4.0), escalate_after: Some(2.0), ..Default::default() };
+        dead_letters.configure(config).unwrap();
+        let mut queue = JobQueue::new();
+        queue.push(create_test_job(1), 0);
+        queue.push(create_test_job(2), 20);
+        assert!(dead_letters.sweep(&mut queue, 13).is_empty());
+        assert!(matches!(queue.cpu[0].job.qos, QoS::Latency));
+        assert!(matches!(queue.cpu[1].job.qos, QoS::Balanced));
+
+        let expired = dead_letters.sweep(&mut queue, 25);
+        assert_eq!(expired.iter().map(|ej| ej.job.id).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(dead_letters.letters[0].reason, DeadLetterReason::DeadlineExpired);
+        assert_eq!((queue.len(), dead_letters.stats.escalated, dead_letters.stats.expired), (1, 1, 1));
+
+        assert!(dead_letters.configure(DeadLetterConfig { expire_after: Some(2.0), escalate_after: Some(3.0), ..Default::default() }).is_err());
+        assert!(dead_letters.configure(DeadLetterConfig { capacity: 0, ..Default::default() }).is_err());
+    }
 }
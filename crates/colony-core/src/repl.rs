@@ -0,0 +1,274 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use anyhow::{anyhow, bail, Result};
+use crate::{
+    ActiveScheduler, GameMode, JobQueue, KpiSnapshot, RunCommand, SchedPolicy, SimClock, TickScale, WinLossState,
+    build_shadow_app_with_config, colony_for_scenario, get_pipeline_by_id, plugin_config_for_scenario,
+};
+use crate::run_spec::apply_command;
+
+const DEFAULT_PAYLOAD_SZ: usize = 4096;
+const DEFAULT_DEADLINE_MS: u64 = 250;
+/// Upper bound for one `ffwd`, so a typo can't hang the session
+const MAX_FFWD_TICKS: u64 = 10_000_000;
+
+pub const REPL_HELP: &str = "\
+status                                      tick, pause state and KPIs
+pause | resume                              stop or restart the background clock
+ffwd <ticks>                                run that many ticks now, paused or not
+enqueue <pipeline> [payload] [count] [deadline_ms]
+fire <black_swan>                           force an event (creative only)
+sched fcfs|sjf|edf|fair                     switch the scheduler
+power <kw> | bandwidth <gbps>               set colony caps
+set <tunable> <value>                       e.g. set corruption.base_fault_rate 0.02
+help | quit";
+
+/// One line typed at `colony-headless --interactive`
+#[derive(Debug, Clone)]
+pub enum ReplCommand {
+    Status,
+    Pause,
+    Resume,
+    FastForward { ticks: u64 },
+    Apply(RunCommand),
+    Help,
+    Quit,
+}
+
+fn arg<T: std::str::FromStr>(words: &[&str], i: usize, name: &str) -> Result<Option<T>> {
+    words.get(i)
+        .map(|w| w.parse::<T>().map_err(|_| anyhow!("{} must be a number, got '{}'", name, w)))
+        .transpose()
+}
+
+fn required<T: std::str::FromStr>(words: &[&str], i: usize, name: &str) -> Result<T> {
+    arg(words, i, name)?.ok_or_else(|| anyhow!("missing {}", name))
+}
+
+impl ReplCommand {
+    /// Parse a line; blank lines and `#` comments give None
+    pub fn parse(line: &str) -> Result<Option<Self>> {
+        let line = line.split('#').next().unwrap_or_default();
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some(&verb) = words.first() else {
+            return Ok(None);
+        };
+        let max_args = match verb {
+            "status" | "pause" | "resume" | "help" | "quit" | "exit" => 0,
+            "ffwd" | "fire" | "sched" | "power" | "bandwidth" => 1,
+            "set" => 2,
+            "enqueue" => 4,
+            other => bail!("unknown command '{}', try 'help'", other),
+        };
+        if words.len() - 1 > max_args {
+            bail!("too many arguments for '{}'", verb);
+        }
+        let command = match verb {
+            "status" => ReplCommand::Status,
+            "pause" => ReplCommand::Pause,
+            "resume" => ReplCommand::Resume,
+            "help" => ReplCommand::Help,
+            "quit" | "exit" => ReplCommand::Quit,
+            "ffwd" => {
+                let ticks: u64 = required(&words, 1, "ticks")?;
+                if ticks > MAX_FFWD_TICKS {
+                    bail!("ffwd is limited to {} ticks at a time", MAX_FFWD_TICKS);
+                }
+                ReplCommand::FastForward { ticks }
+            }
+            "enqueue" => {
+                let pipeline = words.get(1).ok_or_else(|| anyhow!("missing pipeline"))?.to_string();
+                if get_pipeline_by_id(&pipeline).is_none() {
+                    bail!("unknown pipeline '{}'", pipeline);
+                }
+                ReplCommand::Apply(RunCommand::EnqueuePipeline {
+                    pipeline,
+                    payload_sz: arg(&words, 2, "payload")?.unwrap_or(DEFAULT_PAYLOAD_SZ),
+                    count: arg(&words, 3, "count")?.unwrap_or(1),
+                    deadline_ms: arg(&words, 4, "deadline_ms")?.unwrap_or(DEFAULT_DEADLINE_MS),
+                    tenant: None,
+                })
+            }
+            "fire" => ReplCommand::Apply(RunCommand::ForceEvent {
+                event: words.get(1).ok_or_else(|| anyhow!("missing event id"))?.to_string(),
+            }),
+            "sched" => {
+                let policy = match words.get(1).copied() {
+                    Some("fcfs") => SchedPolicy::Fcfs,
+                    Some("sjf") => SchedPolicy::Sjf,
+                    Some("edf") => SchedPolicy::Edf,
+                    Some("fair") => SchedPolicy::FairShare,
+                    Some(other) => bail!("unknown policy '{}', expected fcfs, sjf, edf or fair", other),
+                    None => bail!("missing policy"),
+                };
+                ReplCommand::Apply(RunCommand::SetScheduler { policy })
+            }
+            "power" => ReplCommand::Apply(RunCommand::SetPowerCap { kw: required(&words, 1, "kw")? }),
+            "bandwidth" => ReplCommand::Apply(RunCommand::SetBandwidth { gbps: required(&words, 1, "gbps")? }),
+            _ => ReplCommand::Apply(RunCommand::SetTunable {
+                key: words.get(1).ok_or_else(|| anyhow!("missing tunable"))?.to_string(),
+                value: required(&words, 2, "value")?,
+            }),
+        };
+        Ok(Some(command))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplStatus {
+    pub tick: u64,
+    pub paused: bool,
+    pub queued_jobs: usize,
+    pub victory: bool,
+    pub doom: bool,
+    pub kpis: KpiSnapshot,
+}
+
+impl std::fmt::Display for ReplStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "tick={} paused={} queued={} deadline_hit_rate={:.3} power_kw={:.1} bandwidth_util={:.3} corruption={:.3} victory={} doom={}",
+            self.tick, self.paused, self.queued_jobs, self.kpis.deadline_hit_rate, self.kpis.power_draw_kw,
+            self.kpis.bandwidth_util, self.kpis.corruption_field, self.victory, self.doom
+        )
+    }
+}
+
+/// What the caller should do after a command
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplReply {
+    Text(String),
+    Quit,
+}
+
+/// A sim session driven line by line; the clock ticks in the background unless paused
+pub struct ReplSession {
+    app: App,
+    mode: GameMode,
+    pub tick: u64,
+    pub paused: bool,
+}
+
+impl ReplSession {
+    pub fn new(scenario: Option<&str>, seed: Option<u64>, mode: GameMode) -> Result<Self> {
+        let colony = colony_for_scenario(scenario, seed)?;
+        let clock = SimClock {
+            tick_scale: TickScale::Seconds(1),
+            now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+        };
+        let config = plugin_config_for_scenario(scenario)?;
+        let mut app = build_shadow_app_with_config(&colony, &clock, &ActiveScheduler::default(), config);
+        app.insert_resource(mode);
+        Ok(Self { app, mode, tick: 0, paused: false })
+    }
+
+    /// Advance one tick of the background clock; does nothing while paused
+    pub fn tick(&mut self) {
+        if !self.paused {
+            self.step(1);
+        }
+    }
+
+    /// Run `ticks` ticks now, paused or not
+    pub fn step(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.app.update();
+            self.tick += 1;
+        }
+    }
+
+    pub fn status(&mut self) -> ReplStatus {
+        let world = self.app.world_mut();
+        let queued_jobs = world.get_resource::<JobQueue>()
+            .map_or(0, |q| q.cpu.len() + q.gpu.len() + q.io.len() + q.storage.len());
+        let (victory, doom) = world.get_resource::<WinLossState>().map_or((false, false), |s| (s.victory, s.doom));
+        ReplStatus {
+            tick: self.tick,
+            paused: self.paused,
+            queued_jobs,
+            victory,
+            doom,
+            kpis: KpiSnapshot::capture(world),
+        }
+    }
+
+    pub fn execute(&mut self, command: ReplCommand) -> Result<ReplReply> {
+        let text = match command {
+            ReplCommand::Status => self.status().to_string(),
+            ReplCommand::Pause => {
+                self.paused = true;
+                format!("paused at tick {}", self.tick)
+            }
+            ReplCommand::Resume => {
+                self.paused = false;
+                format!("resumed at tick {}", self.tick)
+            }
+            ReplCommand::FastForward { ticks } => {
+                self.step(ticks);
+                format!("now at tick {}", self.tick)
+            }
+            ReplCommand::Apply(command) => {
+                if matches!(command, RunCommand::ForceEvent { .. }) {
+                    self.mode.require_creative("Forcing events")?;
+                }
+                apply_command(&mut self.app, &command);
+                "ok".to_string()
+            }
+            ReplCommand::Help => REPL_HELP.to_string(),
+            ReplCommand::Quit => return Ok(ReplReply::Quit),
+        };
+        Ok(ReplReply::Text(text))
+    }
+
+    /// Parse and run one line; None for blank lines
+    pub fn execute_line(&mut self, line: &str) -> Result<Option<ReplReply>> {
+        match ReplCommand::parse(line)? {
+            Some(command) => self.execute(command).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert!(matches!(ReplCommand::parse("ffwd 1000").unwrap(), Some(ReplCommand::FastForward { ticks: 1000 })));
+        assert!(matches!(
+            ReplCommand::parse("enqueue udp_telemetry_ingest 4096").unwrap(),
+            Some(ReplCommand::Apply(RunCommand::EnqueuePipeline { payload_sz: 4096, count: 1, .. }))
+        ));
+        assert!(ReplCommand::parse("  # just a comment").unwrap().is_none());
+        assert!(ReplCommand::parse("enqueue no_such_pipeline").is_err());
+        assert!(ReplCommand::parse("ffwd lots").is_err());
+        assert!(ReplCommand::parse("pause now").is_err());
+        assert!(ReplCommand::parse("launch").is_err());
+    }
+
+    #[test]
+    fn test_session_pauses_and_fast_forwards() {
+        let mut session = ReplSession::new(Some("first_light_chill"), Some(7), GameMode::Standard).unwrap();
+        session.tick();
+        assert_eq!(session.tick, 1);
+        session.execute_line("pause").unwrap();
+        session.tick();
+        assert_eq!(session.tick, 1);
+        session.execute_line("ffwd 5").unwrap();
+        let status = session.status();
+        assert_eq!((status.tick, status.paused), (6, true));
+        assert_eq!(session.execute_line("quit").unwrap(), Some(ReplReply::Quit));
+    }
+
+    #[test]
+    fn test_fire_needs_creative() {
+        let mut session = ReplSession::new(Some("first_light_chill"), Some(7), GameMode::Standard).unwrap();
+        assert!(session.execute_line("fire vram_ecc_propagation").is_err());
+        let mut creative = ReplSession::new(Some("first_light_chill"), Some(7), GameMode::Creative).unwrap();
+        assert!(creative.execute_line("fire vram_ecc_propagation").is_ok());
+        assert!(creative.execute_line("enqueue udp_telemetry_ingest 4096 3").is_ok());
+        assert_eq!(creative.status().queued_jobs, 3);
+    }
+}
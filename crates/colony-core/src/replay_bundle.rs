@@ -0,0 +1,344 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use anyhow::Result;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+use crate::{GameMode, Colony, Worker, WorkerHealth, WorkerSkills, WorkerStateComp, WinLossState, JobQueue, KpiSnapshot, RunSpec, RunOutcome, RunVerdict, RunExports, RngAudit, execute_run_spec};
+
+pub const REPLAY_BUNDLE_VERSION: u32 = 1;
+pub const DEFAULT_CLIENT_KEY_PATH: &str = "saves/client.key";
+
+/// Exit code for `colony-headless verify-replay` when the bundle doesn't check out
+pub const EXIT_REPLAY_REJECTED: i32 = 4;
+
+/// What the submitter says their run achieved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClaimedResult {
+    pub verdict: RunVerdict,
+    pub ticks_run: u64,
+    pub seed: u64,
+    pub score: i64,
+    #[serde(default)]
+    pub mode: GameMode, // a creative claim is never ranked against standard runs
+}
+
+impl ClaimedResult {
+    pub fn from_outcome(outcome: &RunOutcome) -> Self {
+        Self {
+            verdict: outcome.verdict,
+            ticks_run: outcome.ticks_run,
+            seed: outcome.seed,
+            score: outcome.score,
+            mode: outcome.mode,
+        }
+    }
+}
+
+/// The signed part of a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleBody {
+    pub format_version: u32,
+    pub spec: RunSpec,                       // scenario, seed, tick scale, stop condition and full command log
+    pub mod_hashes: BTreeMap<String, String>, // mod directory -> sha256 of its files
+    pub claimed: ClaimedResult,
+    pub final_state_hash: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub rng_audit: RngAudit, // empty in bundles made before draws were audited
+}
+
+/// A finished run packaged for someone else to re-simulate and check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayBundle {
+    pub body: BundleBody,
+    pub public_key: String, // hex ed25519 verifying key
+    pub signature: String,  // hex signature over the JSON-encoded body
+}
+
+/// Per-install signing key, created on first use
+pub struct ClientKey {
+    signing: SigningKey,
+}
+
+impl ClientKey {
+    pub fn generate() -> Self {
+        Self { signing: SigningKey::from_bytes(&rand::random::<[u8; 32]>()) }
+    }
+
+    pub fn load_or_create(path: &Path) -> Result<Self> {
+        if path.exists() {
+            let bytes = hex::decode(std::fs::read_to_string(path)?.trim())?;
+            let secret: [u8; 32] = bytes.try_into()
+                .map_err(|_| anyhow::anyhow!("Client key {} is not 32 bytes", path.display()))?;
+            return Ok(Self { signing: SigningKey::from_bytes(&secret) });
+        }
+
+        let key = Self::generate();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, hex::encode(key.signing.to_bytes()))?;
+        println!("Created client key at {}", path.display());
+        Ok(key)
+    }
+
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.signing.verifying_key().to_bytes())
+    }
+}
+
+/// Hash of the end-of-run world: colony, workers, queue depth, win/loss and KPIs
+pub fn final_state_hash(world: &mut World) -> String {
+    let kpis = KpiSnapshot::capture(world);
+    let mut workers: Vec<(Worker, WorkerSkills, WorkerHealth, WorkerStateComp)> = world
+        .query::<(&Worker, &WorkerSkills, &WorkerHealth, &WorkerStateComp)>()
+        .iter(world)
+        .map(|(w, skills, health, state)| (*w, *skills, *health, *state))
+        .collect();
+    workers.sort_by_key(|(w, ..)| w.id);
+    let queue_depth = world.get_resource::<JobQueue>()
+        .map(|q| [q.cpu.len(), q.gpu.len(), q.io.len(), q.storage.len()])
+        .unwrap_or_default();
+
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(&world.get_resource::<Colony>()).unwrap_or_default());
+    hasher.update(serde_json::to_vec(&workers).unwrap_or_default());
+    hasher.update(serde_json::to_vec(&queue_depth).unwrap_or_default());
+    hasher.update(serde_json::to_vec(&world.get_resource::<WinLossState>()).unwrap_or_default());
+    hasher.update(serde_json::to_vec(&kpis).unwrap_or_default());
+    hex::encode(hasher.finalize())
+}
+
+/// Hash every mod under `mods_dir`, one entry per mod directory
+pub fn hash_mods(mods_dir: &Path) -> Result<BTreeMap<String, String>> {
+    let mut hashes = BTreeMap::new();
+    if !mods_dir.exists() {
+        return Ok(hashes);
+    }
+    let mut mod_dirs: Vec<_> = std::fs::read_dir(mods_dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    mod_dirs.sort();
+
+    for dir in mod_dirs {
+        let mut files: Vec<_> = walkdir::WalkDir::new(&dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .collect();
+        files.sort();
+
+        let mut hasher = Sha256::new();
+        for file in files {
+            let rel = file.strip_prefix(&dir).unwrap_or(&file);
+            hasher.update(rel.to_string_lossy().as_bytes());
+            hasher.update(std::fs::read(&file)?);
+        }
+        let name = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        hashes.insert(name, hex::encode(hasher.finalize()));
+    }
+    Ok(hashes)
+}
+
+impl ReplayBundle {
+    /// Package and sign a finished run; export paths are dropped from the spec
+    pub fn create(spec: &RunSpec, outcome: &RunOutcome, mod_hashes: BTreeMap<String, String>, key: &ClientKey) -> Result<Self> {
+        let mut spec = spec.clone();
+        spec.export = RunExports { sample_every: spec.export.sample_every, ..Default::default() };
+        let body = BundleBody {
+            format_version: REPLAY_BUNDLE_VERSION,
+            spec,
+            mod_hashes,
+            claimed: ClaimedResult::from_outcome(outcome),
+            final_state_hash: outcome.state_hash.clone(),
+            created_at: chrono::Utc::now(),
+            rng_audit: outcome.rng_audit.clone(),
+        };
+        let signature = key.signing.sign(&serde_json::to_vec(&body)?);
+        Ok(Self {
+            body,
+            public_key: key.public_key_hex(),
+            signature: hex::encode(signature.to_bytes()),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn verify_signature(&self) -> Result<()> {
+        let key_bytes: [u8; 32] = hex::decode(&self.public_key)?.try_into()
+            .map_err(|_| anyhow::anyhow!("Public key is not 32 bytes"))?;
+        let sig_bytes: [u8; 64] = hex::decode(&self.signature)?.try_into()
+            .map_err(|_| anyhow::anyhow!("Signature is not 64 bytes"))?;
+        let key = VerifyingKey::from_bytes(&key_bytes)?;
+        key.verify(&serde_json::to_vec(&self.body)?, &Signature::from_bytes(&sig_bytes))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayVerification {
+    pub signature_valid: bool,
+    pub mismatched_mods: Vec<String>, // missing locally, extra locally, or different content
+    pub claimed: ClaimedResult,
+    pub replayed: ClaimedResult,
+    pub claimed_state_hash: String,
+    pub replayed_state_hash: String,
+    pub replayed_rng: RngAudit,
+    pub rng_divergence: Option<String>, // first stream whose draws differ from the claim
+}
+
+impl ReplayVerification {
+    pub fn result_matches(&self) -> bool {
+        self.claimed == self.replayed && self.claimed_state_hash == self.replayed_state_hash && self.rng_divergence.is_none()
+    }
+
+    pub fn is_verified(&self) -> bool {
+        self.signature_valid && self.mismatched_mods.is_empty() && self.result_matches()
+    }
+}
+
+/// Check the signature and mod set, then re-simulate the command log and compare the outcome
+pub fn verify_replay(bundle: &ReplayBundle, local_mod_hashes: &BTreeMap<String, String>) -> Result<ReplayVerification> {
+    if bundle.body.format_version != REPLAY_BUNDLE_VERSION {
+        return Err(anyhow::anyhow!("Unsupported replay bundle version {}", bundle.body.format_version));
+    }
+    let signature_valid = bundle.verify_signature().is_ok();
+
+    let mut mismatched_mods: Vec<String> = bundle.body.mod_hashes.iter()
+        .filter(|(id, hash)| local_mod_hashes.get(*id) != Some(*hash))
+        .map(|(id, _)| id.clone())
+        .collect();
+    mismatched_mods.extend(local_mod_hashes.keys().filter(|id| !bundle.body.mod_hashes.contains_key(*id)).cloned());
+
+    bundle.body.spec.validate()?;
+    let outcome = execute_run_spec(&bundle.body.spec)?;
+    // Bundles from before the audit carry no trail to hold the replay to
+    let rng_divergence = match bundle.body.rng_audit.streams.is_empty() {
+        true => None,
+        false => bundle.body.rng_audit.divergence(&outcome.rng_audit),
+    };
+
+    Ok(ReplayVerification {
+        signature_valid,
+        mismatched_mods,
+        claimed: bundle.body.claimed.clone(),
+        replayed: ClaimedResult::from_outcome(&outcome),
+        claimed_state_hash: bundle.body.final_state_hash.clone(),
+        replayed_state_hash: outcome.state_hash,
+        replayed_rng: outcome.rng_audit,
+        rng_divergence,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r#"
+seed = 11
+tick_scale = "seconds"
+
+[stop]
+max_ticks = 15
+
+[[command]]
+at_tick = 0
+action = "enqueue_pipeline"
+pipeline = "udp_telemetry_ingest"
+count = 4
+payload_sz = 512
+deadline_ms = 80
+
+[[command]]
+at_tick = 6
+action = "set_scheduler"
+policy = "Sjf"
+"#;
+
+    fn signed_bundle() -> ReplayBundle {
+        let spec = RunSpec::from_toml(SPEC).unwrap();
+        let outcome = execute_run_spec(&spec).unwrap();
+        let mut mods = BTreeMap::new();
+        mods.insert("vanilla".to_string(), "abc123".to_string());
+        ReplayBundle::create(&spec, &outcome, mods, &ClientKey::generate()).unwrap()
+    }
+
+    #[test]
+    fn test_honest_bundle_verifies() {
+        let bundle = signed_bundle();
+        bundle.verify_signature().unwrap();
+
+        let report = verify_replay(&bundle, &bundle.body.mod_hashes.clone()).unwrap();
+        assert!(report.signature_valid);
+        assert!(report.result_matches(), "{:?}", report);
+        assert!(report.is_verified());
+    }
+
+    #[test]
+    fn test_inflated_score_is_rejected() {
+        let mut bundle = signed_bundle();
+        bundle.body.claimed.score += 1000;
+        assert!(bundle.verify_signature().is_err());
+
+        // Re-signing with another key fixes the signature but not the replay
+        let key = ClientKey::generate();
+        bundle.signature = hex::encode(key.signing.sign(&serde_json::to_vec(&bundle.body).unwrap()).to_bytes());
+        bundle.public_key = key.public_key_hex();
+        let report = verify_replay(&bundle, &bundle.body.mod_hashes.clone()).unwrap();
+        assert!(report.signature_valid);
+        assert!(!report.result_matches());
+        assert!(!report.is_verified());
+    }
+
+    #[test]
+    fn test_hidden_reroll_is_rejected() {
+        let mut bundle = signed_bundle();
+        assert!(bundle.body.rng_audit.total_draws() > 0);
+        // A trail one fault roll longer than the run that produced it
+        let audit = &mut bundle.body.rng_audit;
+        let faults = audit.streams["faults"].clone();
+        audit.record(crate::SeedStream::Faults, faults.seed, faults.tick, "dispatch_fault");
+        let report = verify_replay(&bundle, &bundle.body.mod_hashes.clone()).unwrap();
+        assert_eq!(report.rng_divergence, Some("faults".to_string()));
+        assert_eq!(report.claimed_state_hash, report.replayed_state_hash);
+        assert!(!report.result_matches());
+    }
+
+    #[test]
+    fn test_mod_mismatch_is_reported() {
+        let bundle = signed_bundle();
+        let mut local = BTreeMap::new();
+        local.insert("vanilla".to_string(), "different".to_string());
+        local.insert("extra_mod".to_string(), "x".to_string());
+        let report = verify_replay(&bundle, &local).unwrap();
+        assert_eq!(report.mismatched_mods, vec!["vanilla".to_string(), "extra_mod".to_string()]);
+        assert!(!report.is_verified());
+    }
+
+    #[test]
+    fn test_client_key_round_trip() {
+        let path = std::env::temp_dir().join(format!("colony_client_key_{}", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        let created = ClientKey::load_or_create(&path).unwrap();
+        let loaded = ClientKey::load_or_create(&path).unwrap();
+        assert_eq!(created.public_key_hex(), loaded.public_key_hex());
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use serde::{Serialize, Deserialize};
+use crate::MetricId;
 // HashMap import removed - not used in this file
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,7 +19,9 @@ pub enum TechGrant {
     UnlockOp { op: String },                           // e.g., "Adjudicator"
     UnlockRitual { ritual_id: String },
     SchedulerBias { policy: String, weight: f32 },     // EDF tweak
-    Sensor { metric: String },                         // makes metric visible; required for dispelling illusions
+    Sensor { metric: MetricId },                       // makes metric visible; required for dispelling illusions
+    Certify { cert: String },                          // every worker earns the certification, e.g. "CAN"
+    OpSlots { op: String, slots: u32 },                // more of a limited op may run at once, e.g. "Yolo", 2
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +33,28 @@ pub struct RitualDef {
     pub effects: Vec<String>,  // e.g., "clear:DebtPowerMult", "reduce:corruption=0.1", "reimage:domain=1"
 }
 
+impl RitualDef {
+    /// Placeholder definition until rituals are loaded from content
+    pub fn default_for(ritual_id: &str) -> Self {
+        if ritual_id == "ntp_sync" {
+            return Self {
+                id: ritual_id.to_string(),
+                name: "NTP Sync".to_string(),
+                time_ms: 15000,
+                parts: 0,
+                effects: vec![crate::SYNC_CLOCKS_EFFECT.to_string()],
+            };
+        }
+        Self {
+            id: ritual_id.to_string(),
+            name: format!("Ritual: {}", ritual_id),
+            time_ms: 30000, // 30 seconds default
+            parts: 1,
+            effects: vec!["clear:DebtPowerMult".to_string()],
+        }
+    }
+}
+
 #[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
 pub struct ResearchState {
     pub pts: u32,
@@ -85,14 +110,7 @@ impl ResearchState {
         for grant in &tech.grants {
             if let TechGrant::UnlockRitual { ritual_id } = grant {
                 // TODO: Load ritual definition from content
-                let ritual = RitualDef {
-                    id: ritual_id.clone(),
-                    name: format!("Ritual: {}", ritual_id),
-                    time_ms: 30000, // 30 seconds default
-                    parts: 1,
-                    effects: vec!["clear:DebtPowerMult".to_string()],
-                };
-                self.rituals.push(ritual);
+                self.rituals.push(RitualDef::default_for(ritual_id));
             }
         }
 
@@ -139,87 +157,9 @@ impl TechTree {
     }
 }
 
-// Default tech tree
+/// Tech tree of the bundled vanilla mod (mods/vanilla/tech.toml)
 pub fn create_default_tech_tree() -> TechTree {
-    let mut tech_tree = TechTree::new();
-
-    // Truth Beacon - reveals real metrics
-    tech_tree.add_tech(TechNode {
-        id: "truth_beacon".to_string(),
-        name: "Truth Beacon".to_string(),
-        desc: "Reveals real metrics, dispelling UI illusions".to_string(),
-        cost_pts: 10,
-        requires: vec![],
-        grants: vec![
-            TechGrant::Sensor { metric: "bandwidth_util".to_string() },
-            TechGrant::Sensor { metric: "corruption_field".to_string() },
-            TechGrant::Sensor { metric: "power_draw".to_string() },
-        ],
-    });
-
-    // Dual-Run Adjudicator - enables dual-run mutations
-    tech_tree.add_tech(TechNode {
-        id: "dual_run_adjudicator".to_string(),
-        name: "Dual-Run Adjudicator".to_string(),
-        desc: "Enables dual-run pipeline mutations for fault tolerance".to_string(),
-        cost_pts: 15,
-        requires: vec!["truth_beacon".to_string()],
-        grants: vec![
-            TechGrant::UnlockOp { op: "Adjudicator".to_string() },
-        ],
-    });
-
-    // NUMA Isolation - improves thermal management
-    tech_tree.add_tech(TechNode {
-        id: "numa_isolation".to_string(),
-        name: "NUMA Isolation".to_string(),
-        desc: "Improves thermal management and reduces bandwidth tax".to_string(),
-        cost_pts: 20,
-        requires: vec![],
-        grants: vec![
-            TechGrant::Tunable { key: "thermal_min_throttle".to_string(), mult: 1.05 },
-            TechGrant::Tunable { key: "bandwidth_tail_exp".to_string(), mult: 0.95 },
-        ],
-    });
-
-    // ECC Scrub - enables memory error correction
-    tech_tree.add_tech(TechNode {
-        id: "ecc_scrub".to_string(),
-        name: "ECC Scrub".to_string(),
-        desc: "Enables memory error correction and VRAM maintenance".to_string(),
-        cost_pts: 25,
-        requires: vec!["truth_beacon".to_string()],
-        grants: vec![
-            TechGrant::UnlockRitual { ritual_id: "ecc_scrub".to_string() },
-        ],
-    });
-
-    // PCIe Lanes - improves bandwidth
-    tech_tree.add_tech(TechNode {
-        id: "pcie_lanes".to_string(),
-        name: "PCIe Lanes".to_string(),
-        desc: "Increases PCIe bandwidth and reduces latency".to_string(),
-        cost_pts: 18,
-        requires: vec!["numa_isolation".to_string()],
-        grants: vec![
-            TechGrant::Tunable { key: "bandwidth_total_gbps".to_string(), mult: 1.2 },
-        ],
-    });
-
-    // VRAM Pager - reduces VRAM pressure
-    tech_tree.add_tech(TechNode {
-        id: "vram_pager".to_string(),
-        name: "VRAM Pager".to_string(),
-        desc: "Reduces VRAM pressure and StickyConfig fault bias".to_string(),
-        cost_pts: 22,
-        requires: vec!["ecc_scrub".to_string()],
-        grants: vec![
-            TechGrant::Tunable { key: "vram_gb".to_string(), mult: 1.15 },
-            TechGrant::Tunable { key: "sticky_fault_bias".to_string(), mult: 0.8 },
-        ],
-    });
-
-    tech_tree
+    crate::ContentPack::vanilla().tech_tree()
 }
 
 pub fn research_progress_system(
@@ -259,6 +199,14 @@ pub fn apply_tech_grants(
                         // TODO: Enable sensor
                         println!("Enabled sensor: {}", metric);
                     }
+                    TechGrant::Certify { cert } => {
+                        // Handed out to workers by certification_system
+                        println!("Certified all workers: {}", cert);
+                    }
+                    TechGrant::OpSlots { op, slots } => {
+                        // Counted by op_license_system
+                        println!("Op slots granted: {} +{}", op, slots);
+                    }
                 }
             }
         }
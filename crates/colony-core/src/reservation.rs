@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use crate::{JobClass, JobQueue, WorkyardKind};
+use crate::queue::EnqueuedJob;
+
+/// `[[class_reservations]]` in a scenario: a share of a yard kind's slots held for jobs from
+/// another class queue, e.g. CPU arrays keeping 20% for IO jobs whose early ops are CPU-bound
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClassReservation {
+    pub yard: WorkyardKind,
+    pub class: JobClass,
+    pub fraction: f32, // of the yard's slots, held while jobs of the class are waiting
+}
+
+/// Per-yard reservations for foreign job classes so a burst in a yard's own queue can't starve them
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ClassReservations {
+    pub reservations: Vec<ClassReservation>,
+    pub dispatched: u64, // jobs run on a yard outside their own class, all yards
+}
+
+/// Check a reservation set: fractions in 0..=1 and at most 1 per yard, one entry per yard and
+/// class, no yard reserving its own class, and GPU jobs only ever on GPU farms
+pub fn validate_class_reservations(reservations: &[ClassReservation]) -> Result<()> {
+    for (i, r) in reservations.iter().enumerate() {
+        if !(0.0..=1.0).contains(&r.fraction) {
+            anyhow::bail!("Reservation of {:?} slots for {:?} jobs is {}, must be between 0 and 1", r.yard, r.class, r.fraction);
+        }
+        if r.class == JobClass::of_yard(&r.yard) {
+            anyhow::bail!("{:?} already serves {:?} jobs, it can't reserve slots for them", r.yard, r.class);
+        }
+        if r.class == JobClass::Gpu {
+            anyhow::bail!("GPU jobs only run on a GPU farm, {:?} can't reserve slots for them", r.yard);
+        }
+        if reservations[..i].iter().any(|other| other.yard == r.yard && other.class == r.class) {
+            anyhow::bail!("{:?} reserves slots for {:?} jobs more than once", r.yard, r.class);
+        }
+    }
+    for r in reservations {
+        let total: f32 = reservations.iter().filter(|o| o.yard == r.yard).map(|o| o.fraction).sum();
+        if total > 1.0 {
+            anyhow::bail!("{:?} reserves {:.0}% of its slots, more than it has", r.yard, total * 100.0);
+        }
+    }
+    Ok(())
+}
+
+/// Slots held for a reservation; any non-zero share keeps at least one
+fn reserved_slots(fraction: f32, yard_slots: u32) -> usize {
+    if fraction <= 0.0 || yard_slots == 0 {
+        return 0;
+    }
+    ((yard_slots as f32 * fraction).ceil() as usize).max(1)
+}
+
+impl ClassReservations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_reservations(reservations: &[ClassReservation]) -> Result<Self> {
+        validate_class_reservations(reservations)?;
+        Ok(Self { reservations: reservations.to_vec(), ..Self::default() })
+    }
+
+    /// Add, change or (with 0) remove one yard's reservation for a class
+    pub fn set(&mut self, yard: WorkyardKind, class: JobClass, fraction: f32) -> Result<()> {
+        let mut next: Vec<ClassReservation> = self.reservations.iter()
+            .filter(|r| !(r.yard == yard && r.class == class))
+            .cloned()
+            .collect();
+        if fraction > 0.0 {
+            next.push(ClassReservation { yard, class, fraction });
+        }
+        validate_class_reservations(&next)?;
+        self.reservations = next;
+        Ok(())
+    }
+
+    /// Foreign-class jobs a yard should run this tick, oldest first per class, with the class
+    /// queue each came from. Every reservation gets its slots while its class has jobs waiting;
+    /// idle workers the yard's own queue can't use go to reserved classes as well.
+    pub fn candidates(&self, jobq: &JobQueue, yard: &WorkyardKind, yard_slots: u32, idle_workers: usize) -> Vec<(JobClass, EnqueuedJob)> {
+        let mine: Vec<&ClassReservation> = self.reservations.iter().filter(|r| &r.yard == yard).collect();
+        if mine.is_empty() || idle_workers == 0 {
+            return Vec::new();
+        }
+        let mut waiting: Vec<(JobClass, Vec<&EnqueuedJob>)> = mine.iter()
+            .map(|r| {
+                let mut jobs: Vec<&EnqueuedJob> = jobq.peek_class(r.class).iter().collect();
+                jobs.sort_by_key(|ej| ej.enq_tick);
+                (r.class, jobs)
+            })
+            .collect();
+
+        let mut taken = Vec::new();
+        let mut budget = idle_workers;
+        for (r, (class, jobs)) in mine.iter().zip(waiting.iter_mut()) {
+            let n = reserved_slots(r.fraction, yard_slots).min(jobs.len()).min(budget);
+            taken.extend(jobs.drain(..n).map(|ej| (*class, ej.clone())));
+            budget -= n;
+        }
+        let mut spare = budget.saturating_sub(jobq.peek_class(JobClass::of_yard(yard)).len());
+        for (class, jobs) in waiting.iter_mut() {
+            let n = spare.min(jobs.len());
+            taken.extend(jobs.drain(..n).map(|ej| (*class, ej.clone())));
+            spare -= n;
+        }
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Job, Pipeline, Op, QoS};
+
+    fn job(id: u64, ops: Vec<Op>, enq_tick: u64) -> EnqueuedJob {
+        EnqueuedJob::new(Job {
+            id,
+            pipeline: Pipeline { ops, mutation_tag: None, id: None },
+            qos: QoS::Balanced,
+            deadline_ms: 1000,
+            payload_sz: 1024,
+        }, enq_tick)
+    }
+
+    fn queue(cpu_jobs: u64, io_jobs: u64) -> JobQueue {
+        let mut jobq = JobQueue::new();
+        for id in 0..cpu_jobs {
+            jobq.push_enqueued(job(id, vec![Op::Decode, Op::Kalman], id));
+        }
+        for id in 0..io_jobs {
+            jobq.push_enqueued(job(100 + id, vec![Op::UdpDemux, Op::Decode], 50 - id));
+        }
+        jobq
+    }
+
+    #[test]
+    fn test_reserved_slots_go_to_oldest_waiting_jobs() {
+        let reservations = ClassReservations::from_reservations(&[ClassReservation {
+            yard: WorkyardKind::CpuArray, class: JobClass::Io, fraction: 0.2,
+        }]).unwrap();
+
+        // 10 slots at 20% hold 2 for IO jobs even while CPU jobs fill the queue
+        let taken = reservations.candidates(&queue(30, 5), &WorkyardKind::CpuArray, 10, 10);
+        let ids: Vec<u64> = taken.iter().map(|(_, ej)| ej.job.id).collect();
+        assert_eq!(ids, vec![104, 103]);
+        assert!(taken.iter().all(|(class, _)| *class == JobClass::Io));
+
+        // Nothing held when no IO jobs wait, and other yard kinds are unaffected
+        assert!(reservations.candidates(&queue(30, 0), &WorkyardKind::CpuArray, 10, 10).is_empty());
+        assert!(reservations.candidates(&queue(30, 5), &WorkyardKind::SignalHub, 10, 10).is_empty());
+    }
+
+    #[test]
+    fn test_idle_slots_are_lent_to_reserved_classes() {
+        let reservations = ClassReservations::from_reservations(&[ClassReservation {
+            yard: WorkyardKind::CpuArray, class: JobClass::Io, fraction: 0.2,
+        }]).unwrap();
+        // 2 reserved plus the 5 slots 3 CPU jobs leave free, out of 10 idle workers
+        assert_eq!(reservations.candidates(&queue(3, 20), &WorkyardKind::CpuArray, 10, 10).len(), 7);
+        // Never more than there are idle workers
+        assert_eq!(reservations.candidates(&queue(0, 20), &WorkyardKind::CpuArray, 10, 4).len(), 4);
+    }
+
+    #[test]
+    fn test_cpu_array_drains_io_jobs_it_reserves_for() {
+        // First Light has no SignalHub, so IO jobs only ever run through a reservation
+        let colony = crate::colony_for_scenario(Some("first_light_chill"), Some(7)).unwrap();
+        let clock = crate::SimClock { tick_scale: crate::TickScale::RealTime, now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH };
+        let config = crate::find_scenario("first_light_chill").unwrap().plugin_config();
+        let run = |reservations: ClassReservations| {
+            let mut app = crate::build_shadow_app_with_config(&colony, &clock, &crate::ActiveScheduler::default(), config.clone());
+            app.insert_resource(reservations);
+            app.update();
+            for id in 0..4 {
+                app.world_mut().resource_mut::<JobQueue>().push_enqueued(job(1000 + id, vec![Op::UdpDemux, Op::Decode], 0));
+            }
+            for _ in 0..5 {
+                app.update();
+            }
+            (app.world().resource::<JobQueue>().io.len(), app.world().resource::<ClassReservations>().dispatched)
+        };
+
+        let (stuck, dispatched) = run(ClassReservations::new());
+        assert!(stuck >= 4);
+        assert_eq!(dispatched, 0);
+        let mut reservations = ClassReservations::new();
+        reservations.set(WorkyardKind::CpuArray, JobClass::Io, 0.2).unwrap();
+        let (left, dispatched) = run(reservations);
+        assert_eq!(left, 0);
+        assert_eq!(dispatched, stuck as u64);
+    }
+
+    #[test]
+    fn test_invalid_reservations_rejected() {
+        let mut reservations = ClassReservations::new();
+        assert!(reservations.set(WorkyardKind::CpuArray, JobClass::Cpu, 0.2).is_err());
+        assert!(reservations.set(WorkyardKind::CpuArray, JobClass::Gpu, 0.2).is_err());
+        assert!(reservations.set(WorkyardKind::CpuArray, JobClass::Io, 1.5).is_err());
+        reservations.set(WorkyardKind::CpuArray, JobClass::Io, 0.6).unwrap();
+        assert!(reservations.set(WorkyardKind::CpuArray, JobClass::Storage, 0.6).is_err());
+        reservations.set(WorkyardKind::CpuArray, JobClass::Io, 0.3).unwrap();
+        assert_eq!(reservations.reservations.len(), 1);
+        reservations.set(WorkyardKind::CpuArray, JobClass::Io, 0.0).unwrap();
+        assert!(reservations.reservations.is_empty());
+    }
+}
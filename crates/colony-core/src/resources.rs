@@ -52,6 +52,8 @@ pub struct Colony {
     pub tunables: ResourceTunables,
     pub corruption_tun: super::corruption::CorruptionTunables,
     pub seed: u64,
+    #[serde(default)]
+    pub seeds: super::SeedOverrides, // per-subsystem pins, see seed_for
 }
 
 // JobQueue is defined in queue.rs to avoid conflicts
@@ -0,0 +1,308 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use rand::Rng;
+use anyhow::Result;
+use crate::{RitualDef, ResearchState, Worker, WorkerHealth, WorkerSkills, WorkerState, WorkerStateComp, Colony, Debts, Debt, SimClock, YardClock, tick_rng, apply_clock_sync, SeedStream, RngAudit};
+
+const MAX_RITUAL_RESULTS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RitualOutcome {
+    Success,
+    PartialSuccess { strength: f32 }, // effects applied at this fraction of full strength
+    CriticalFailure,                  // no effect, and the botched ritual leaves a debt behind
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveRitual {
+    pub def: RitualDef,
+    pub started_tick: u64,
+    pub completes_tick: u64,
+    pub workers: Vec<u64>, // worker ids held out of dispatch until the ritual completes
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RitualResult {
+    pub ritual_id: String,
+    pub tick: u64,
+    pub workers: Vec<u64>,
+    pub skill: f32,
+    pub roll: f32,
+    pub outcome: RitualOutcome,
+}
+
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct RitualRuns {
+    pub active: Vec<ActiveRitual>,
+    pub results: Vec<RitualResult>, // most recent last
+}
+
+impl RitualRuns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_assigned(&self, worker_id: u64) -> bool {
+        self.active.iter().any(|r| r.workers.contains(&worker_id))
+    }
+
+    /// Start a ritual with the given crew; a worker can only serve one ritual at a time
+    pub fn start(&mut self, def: &RitualDef, workers: Vec<u64>, now_tick: u64) -> Result<&ActiveRitual> {
+        if self.active.iter().any(|r| r.def.id == def.id) {
+            return Err(anyhow::anyhow!("Ritual '{}' is already in progress", def.id));
+        }
+        if let Some(busy) = workers.iter().find(|&&id| self.is_assigned(id)) {
+            return Err(anyhow::anyhow!("Worker {} is already assigned to a ritual", busy));
+        }
+
+        self.active.push(ActiveRitual {
+            def: def.clone(),
+            started_tick: now_tick,
+            completes_tick: now_tick + def.time_ms / 16,
+            workers,
+        });
+        Ok(self.active.last().unwrap())
+    }
+
+    /// Look the ritual up among those unlocked by research and start it
+    pub fn start_unlocked(&mut self, research: &ResearchState, ritual_id: &str, workers: Vec<u64>, now_tick: u64) -> Result<&ActiveRitual> {
+        let def = research.rituals.iter()
+            .find(|r| r.id == ritual_id)
+            .ok_or_else(|| anyhow::anyhow!("Ritual '{}' is not unlocked", ritual_id))?
+            .clone();
+        self.start(&def, workers, now_tick)
+    }
+
+    fn record(&mut self, result: RitualResult) {
+        self.results.push(result);
+        if self.results.len() > MAX_RITUAL_RESULTS {
+            self.results.remove(0);
+        }
+    }
+}
+
+/// How well a crew can perform a ritual, 0..1. An empty crew still gets a skeleton-crew chance.
+pub fn ritual_skill(workers: &[(&WorkerSkills, &WorkerHealth)]) -> f32 {
+    if workers.is_empty() {
+        return 0.3;
+    }
+    let mean = workers.iter()
+        .map(|(skills, health)| {
+            skills.best() * (0.5 + 0.5 * skills.discipline) * (0.5 + 0.5 * skills.focus) * (1.0 - health.corruption)
+        })
+        .sum::<f32>() / workers.len() as f32;
+    // Extra hands help a little, with diminishing returns
+    (mean + 0.05 * (workers.len() as f32 - 1.0).min(3.0)).clamp(0.0, 1.0)
+}
+
+/// Map a uniform roll to an outcome; higher skill shrinks both failure bands
+pub fn roll_outcome(skill: f32, roll: f32) -> RitualOutcome {
+    let skill = skill.clamp(0.0, 1.0);
+    let critical = 0.15 * (1.0 - skill);
+    let partial = 0.6 * (1.0 - skill);
+    if roll < critical {
+        RitualOutcome::CriticalFailure
+    } else if roll < critical + partial {
+        // Rolls just past the critical band barely work, rolls near success almost fully work
+        let position = (roll - critical) / partial;
+        RitualOutcome::PartialSuccess { strength: 0.25 + 0.5 * position }
+    } else {
+        RitualOutcome::Success
+    }
+}
+
+fn ritual_seed(seed: u64, ritual_id: &str) -> u64 {
+    // FNV-1a over the id so the roll stream is stable across builds
+    ritual_id.bytes().fold(seed ^ 0xcbf29ce484222325, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Apply ritual effect strings (`clear:<Debt>`, `reduce:corruption=<x>`) at the given strength
+pub fn apply_ritual_effects(effects: &[String], strength: f32, colony: &mut Colony, debts: &mut Debts, current_tick: u64) {
+    for effect in effects {
+        if let Some(kind) = effect.strip_prefix("clear:") {
+            // Effects are written against Black Swan effect names; debts drop the "Debt" prefix
+            let kind = kind.strip_prefix("Debt").unwrap_or(kind);
+            if strength >= 1.0 {
+                debts.active.retain(|d| d.kind_name() != kind);
+            } else {
+                for debt in debts.active.iter_mut().filter(|d| d.kind_name() == kind) {
+                    debt.shorten(strength, current_tick);
+                }
+            }
+        } else if let Some(amount) = effect.strip_prefix("reduce:corruption=") {
+            let amount: f32 = amount.parse().unwrap_or(0.0);
+            colony.corruption_field = (colony.corruption_field - amount * strength).max(0.0);
+        } else if effect.starts_with("sync:") {
+            // Yard clocks are components; see apply_clock_sync
+        } else {
+            // TODO: reimage and other structural effects
+            println!("Ritual effect not yet supported: {}", effect);
+        }
+    }
+}
+
+pub fn ritual_progress_system(
+    mut runs: ResMut<RitualRuns>,
+    mut workers: Query<(&Worker, &WorkerSkills, &WorkerHealth, &mut WorkerStateComp)>,
+    mut colony: ResMut<Colony>,
+    mut debts: ResMut<Debts>,
+    mut yard_clocks: Query<&mut YardClock>,
+    clock: Res<SimClock>,
+    mut rng_audit: Option<ResMut<RngAudit>>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+
+    // Assigned workers step off the dispatch floor as soon as they are idle
+    for (worker, _, _, mut state) in workers.iter_mut() {
+        if state.0 == WorkerState::Idle && runs.is_assigned(worker.id) {
+            state.0 = WorkerState::Blocked;
+        }
+    }
+
+    let (done, still_active): (Vec<_>, Vec<_>) = runs.active.drain(..).partition(|r| now_tick >= r.completes_tick);
+    runs.active = still_active;
+
+    for ritual in done {
+        let crew: Vec<(&WorkerSkills, &WorkerHealth)> = workers.iter()
+            .filter(|(w, ..)| ritual.workers.contains(&w.id))
+            .map(|(_, skills, health, _)| (skills, health))
+            .collect();
+        let skill = ritual_skill(&crew);
+        if let Some(audit) = rng_audit.as_mut() {
+            audit.record(SeedStream::BlackSwan, colony.seed_for(SeedStream::BlackSwan), now_tick, &ritual.def.id);
+        }
+        let roll = tick_rng(ritual_seed(colony.seed_for(SeedStream::BlackSwan), &ritual.def.id), now_tick).gen::<f32>();
+        let outcome = roll_outcome(skill, roll);
+
+        match outcome {
+            RitualOutcome::Success => {
+                apply_ritual_effects(&ritual.def.effects, 1.0, &mut colony, &mut debts, now_tick);
+                apply_clock_sync(&ritual.def.effects, 1.0, yard_clocks.iter_mut(), now_tick);
+            }
+            RitualOutcome::PartialSuccess { strength } => {
+                apply_ritual_effects(&ritual.def.effects, strength, &mut colony, &mut debts, now_tick);
+                apply_clock_sync(&ritual.def.effects, strength, yard_clocks.iter_mut(), now_tick);
+            }
+            RitualOutcome::CriticalFailure => {
+                // A botched ritual leaves the systems it touched misconfigured
+                let until_tick = now_tick + 4 * (ritual.def.time_ms / 16).max(1);
+                debts.add_debt(Debt::FaultBias { kind: "StickyConfig".to_string(), weight_mult: 1.3, until_tick });
+            }
+        }
+        println!("Ritual {} finished: {:?} (skill {:.2}, roll {:.2})", ritual.def.id, outcome, skill, roll);
+
+        for (worker, _, _, mut state) in workers.iter_mut() {
+            if ritual.workers.contains(&worker.id) && state.0 == WorkerState::Blocked {
+                state.0 = WorkerState::Idle;
+            }
+        }
+
+        runs.record(RitualResult {
+            ritual_id: ritual.def.id.clone(),
+            tick: now_tick,
+            workers: ritual.workers,
+            skill,
+            roll,
+            outcome,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{WorkClass, WorkerBundle};
+
+    fn worker(id: u64, skill: f32, corruption: f32) -> WorkerBundle {
+        let mut bundle = WorkerBundle::new(id, WorkClass::Cpu, WorkerSkills {
+            cpu: skill,
+            gpu: 0.2,
+            io: 0.2,
+            discipline: 0.8,
+            focus: 0.8,
+        });
+        bundle.health.corruption = corruption;
+        bundle
+    }
+
+    #[test]
+    fn test_skill_improves_outcomes() {
+        let novice = worker(1, 0.3, 0.2);
+        let expert = worker(2, 1.0, 0.0);
+        let novice_skill = ritual_skill(&[(&novice.skills, &novice.health)]);
+        let expert_skill = ritual_skill(&[(&expert.skills, &expert.health)]);
+        assert!(expert_skill > novice_skill);
+
+        // Same roll, different crews
+        assert_eq!(roll_outcome(novice_skill, 0.05), RitualOutcome::CriticalFailure);
+        assert!(matches!(roll_outcome(expert_skill, 0.05), RitualOutcome::Success | RitualOutcome::PartialSuccess { .. }));
+        assert_eq!(roll_outcome(1.0, 0.0), RitualOutcome::Success);
+    }
+
+    #[test]
+    fn test_partial_strength_scales_with_roll() {
+        let low = roll_outcome(0.0, 0.16);
+        let high = roll_outcome(0.0, 0.74);
+        match (low, high) {
+            (RitualOutcome::PartialSuccess { strength: a }, RitualOutcome::PartialSuccess { strength: b }) => assert!(a < b),
+            other => panic!("expected two partial successes, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_workers_cannot_double_book() {
+        let def = RitualDef::default_for("ecc_scrub");
+        let mut runs = RitualRuns::new();
+        runs.start(&def, vec![1, 2], 0).unwrap();
+        assert!(runs.is_assigned(2));
+        assert!(runs.start(&RitualDef::default_for("numa_rebalance"), vec![2], 0).is_err());
+        assert!(runs.start(&def, vec![3], 0).is_err());
+        assert!(runs.start_unlocked(&ResearchState::new(), "numa_rebalance", vec![3], 0).is_err());
+    }
+
+    #[test]
+    fn test_partial_clear_shortens_debt() {
+        let mut colony = crate::colony_for_scenario(None, Some(1)).unwrap();
+        colony.corruption_field = 0.5;
+        let mut debts = Debts::new();
+        debts.add_debt(Debt::PowerMult { mult: 1.1, until_tick: 1100 });
+
+        let effects = vec!["clear:DebtPowerMult".to_string(), "reduce:corruption=0.2".to_string()];
+        apply_ritual_effects(&effects, 0.5, &mut colony, &mut debts, 100);
+        assert_eq!(debts.active[0].get_until_tick(), 600);
+        assert!((colony.corruption_field - 0.4).abs() < 1e-6);
+
+        apply_ritual_effects(&effects, 1.0, &mut colony, &mut debts, 100);
+        assert!(debts.active.is_empty());
+    }
+
+    #[test]
+    fn test_ritual_completes_in_world() {
+        let mut app = App::new();
+        let clock = SimClock {
+            tick_scale: crate::TickScale::RealTime,
+            now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+        };
+        let mut runs = RitualRuns::new();
+        runs.start(&RitualDef::default_for("ecc_scrub"), vec![7], 0).unwrap();
+        app.insert_resource(runs)
+            .insert_resource(crate::colony_for_scenario(None, Some(42)).unwrap())
+            .insert_resource(Debts::new())
+            .insert_resource(clock)
+            .add_systems(Update, ritual_progress_system);
+        app.world_mut().spawn(worker(7, 0.9, 0.0));
+
+        app.update();
+        let mut q = app.world_mut().query::<&WorkerStateComp>();
+        assert_eq!(q.single(app.world()).unwrap().0, WorkerState::Blocked);
+
+        app.world_mut().resource_mut::<SimClock>().now += chrono::Duration::seconds(31);
+        app.update();
+        let runs = app.world().resource::<RitualRuns>();
+        assert!(runs.active.is_empty());
+        assert_eq!(runs.results.len(), 1);
+        assert_eq!(runs.results[0].workers, vec![7]);
+        let mut q = app.world_mut().query::<&WorkerStateComp>();
+        assert_eq!(q.single(app.world()).unwrap().0, WorkerState::Idle);
+    }
+}
@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use crate::SeedStream;
+
+/// Where one stream stands and how it got there
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamAudit {
+    pub seed: u64,       // sub-seed of the latest draw
+    pub draws: u64,      // over the whole run
+    pub tick: u64,       // tick of the latest draw
+    pub tick_draws: u32, // draws so far at that tick; with `tick`, the stream position
+    pub chain: String,   // hex sha256 chained over the seed, tick and site of every draw
+}
+
+/// Every roll the seeded streams gave out. Rolls come from `tick_rng(seed, tick)`, so a draw is one
+/// roll site reached: a fault check, a GPU batch fault, a ritual outcome, a hire or a load
+/// generator tick. A hidden reroll adds a draw the honest replay of the same run doesn't
+/// have, so the counts and chain stop matching.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RngAudit {
+    pub streams: BTreeMap<String, StreamAudit>, // by SeedStream::name
+}
+
+impl RngAudit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, stream: SeedStream, seed: u64, tick: u64, site: &str) {
+        let audit = self.streams.entry(stream.name().to_string()).or_default();
+        let mut hasher = Sha256::new();
+        hasher.update(audit.chain.as_bytes());
+        hasher.update(seed.to_le_bytes());
+        hasher.update(tick.to_le_bytes());
+        hasher.update(site.as_bytes());
+        audit.chain = hex::encode(hasher.finalize());
+
+        audit.tick_draws = if audit.draws > 0 && audit.tick == tick { audit.tick_draws + 1 } else { 1 };
+        audit.seed = seed;
+        audit.tick = tick;
+        audit.draws += 1;
+    }
+
+    pub fn stream(&self, stream: SeedStream) -> Option<&StreamAudit> {
+        self.streams.get(stream.name())
+    }
+
+    pub fn total_draws(&self) -> u64 {
+        self.streams.values().map(|s| s.draws).sum()
+    }
+
+    /// First stream, by name, whose draws differ between the two trails
+    pub fn divergence(&self, other: &RngAudit) -> Option<String> {
+        self.streams.keys()
+            .chain(other.streams.keys())
+            .filter(|name| self.streams.get(*name) != other.streams.get(*name))
+            .min()
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions_and_reroll_detection() {
+        let mut honest = RngAudit::new();
+        honest.record(SeedStream::Faults, 7, 10, "dispatch_fault");
+        honest.record(SeedStream::Faults, 7, 10, "dispatch_fault");
+        honest.record(SeedStream::Faults, 7, 12, "gpu_fault");
+        honest.record(SeedStream::BlackSwan, 3, 12, "ritual");
+
+        let faults = honest.stream(SeedStream::Faults).unwrap();
+        assert_eq!((faults.draws, faults.tick, faults.tick_draws), (3, 12, 1));
+        assert_eq!(honest.total_draws(), 4);
+        assert!(honest.stream(SeedStream::Io).is_none());
+
+        // Same rolls in the same order give the same trail
+        let mut replay = RngAudit::new();
+        replay.record(SeedStream::Faults, 7, 10, "dispatch_fault");
+        replay.record(SeedStream::Faults, 7, 10, "dispatch_fault");
+        replay.record(SeedStream::Faults, 7, 12, "gpu_fault");
+        replay.record(SeedStream::BlackSwan, 3, 12, "ritual");
+        assert_eq!(honest, replay);
+        assert_eq!(honest.divergence(&replay), None);
+
+        // Rolling the ritual again shows up even though the final tick is the same
+        replay.record(SeedStream::BlackSwan, 3, 12, "ritual");
+        assert_eq!(replay.stream(SeedStream::BlackSwan).unwrap().tick_draws, 2);
+        assert_eq!(honest.divergence(&replay), Some("blackswan".to_string()));
+        assert_eq!(RngAudit::new().divergence(&honest), Some("blackswan".to_string()));
+    }
+}
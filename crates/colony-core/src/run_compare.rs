@@ -0,0 +1,286 @@
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use crate::{KpiSnapshot, RunEvent, RunOutcome, RunVerdict};
+
+/// Where the headless server looks for run summaries named in `GET /compare`
+pub const DEFAULT_RUNS_DIR: &str = "runs";
+
+/// Summary JSON for a named run under `dir`; `name` may leave off `.json` but can't leave `dir`
+pub fn run_summary_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        anyhow::bail!("Invalid run name: {:?}", name);
+    }
+    let file = if name.ends_with(".json") { name.to_string() } else { format!("{}.json", name) };
+    Ok(dir.join(file))
+}
+
+/// Read a summary written by `export.summary_json`
+pub fn load_run_summary(path: &Path) -> Result<RunOutcome> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Can't read run summary {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("{} is not a run summary", path.display()))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSide {
+    pub label: String,
+    pub scenario: Option<String>,
+    pub seed: u64,
+    pub verdict: RunVerdict,
+    pub ticks_run: u64,
+    pub score: i64,
+    pub state_hash: String,
+}
+
+/// One KPI curve from both runs over the ticks both sampled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KpiCurveDiff {
+    pub kpi: String,
+    pub mean_a: f32,
+    pub mean_b: f32,
+    pub max_abs_delta: f32,
+    pub max_delta_tick: Option<u64>, // where the curves are furthest apart
+    pub final_a: Option<f32>,
+    pub final_b: Option<f32>,
+}
+
+/// An event occurrence the runs disagree on: missing from one, or at a different tick
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventDiff {
+    pub event: String,
+    pub detail: Option<String>,
+    pub tick_a: Option<u64>,
+    pub tick_b: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunComparison {
+    pub a: RunSide,
+    pub b: RunSide,
+    pub aligned_ticks: usize, // sample ticks present in both runs
+    pub kpis: Vec<KpiCurveDiff>,
+    pub events: Vec<EventDiff>,
+    pub matched_events: usize, // events both runs had at the same tick
+    pub score_delta: i64,      // b - a
+    pub identical: bool,
+}
+
+fn kpi_values(kpis: &KpiSnapshot) -> Vec<(&'static str, Option<f32>)> {
+    vec![
+        ("deadline_hit_rate", Some(kpis.deadline_hit_rate)),
+        ("power_draw_kw", Some(kpis.power_draw_kw)),
+        ("bandwidth_util", Some(kpis.bandwidth_util)),
+        ("corruption_field", Some(kpis.corruption_field)),
+        ("gpu_util", kpis.gpu_util),
+        ("vram_used_mb", kpis.vram_used_mb),
+        ("clock_drift_ms", Some(kpis.clock_drift_ms)),
+        ("maintenance_wait_ms", Some(kpis.maintenance_wait_ms)),
+    ]
+}
+
+fn side(label: &str, run: &RunOutcome) -> RunSide {
+    RunSide {
+        label: label.to_string(),
+        scenario: run.scenario.clone(),
+        seed: run.seed,
+        verdict: run.verdict,
+        ticks_run: run.ticks_run,
+        score: run.score,
+        state_hash: run.state_hash.clone(),
+    }
+}
+
+fn compare_curves(a: &RunOutcome, b: &RunOutcome) -> (usize, Vec<KpiCurveDiff>) {
+    let b_by_tick: BTreeMap<u64, &KpiSnapshot> = b.samples.iter().map(|s| (s.tick, &s.kpis)).collect();
+    let aligned: Vec<(u64, &KpiSnapshot, &KpiSnapshot)> = a.samples.iter()
+        .filter_map(|s| Some((s.tick, &s.kpis, *b_by_tick.get(&s.tick)?)))
+        .collect();
+
+    let (final_a, final_b) = (kpi_values(&a.final_kpis), kpi_values(&b.final_kpis));
+    let mut curves = Vec::new();
+    for (i, (kpi, _)) in final_a.iter().enumerate() {
+        let points: Vec<(u64, f32, f32)> = aligned.iter()
+            .filter_map(|(tick, ka, kb)| Some((*tick, kpi_values(ka)[i].1?, kpi_values(kb)[i].1?)))
+            .collect();
+        if points.is_empty() && final_a[i].1.is_none() && final_b[i].1.is_none() {
+            continue; // e.g. GPU KPIs when neither colony has a GPU farm
+        }
+        let n = points.len().max(1) as f32;
+        let widest = points.iter().max_by(|x, y| (x.2 - x.1).abs().total_cmp(&(y.2 - y.1).abs()));
+        curves.push(KpiCurveDiff {
+            kpi: kpi.to_string(),
+            mean_a: points.iter().map(|p| p.1).sum::<f32>() / n,
+            mean_b: points.iter().map(|p| p.2).sum::<f32>() / n,
+            max_abs_delta: widest.map(|p| (p.2 - p.1).abs()).unwrap_or(0.0),
+            max_delta_tick: widest.filter(|p| p.2 != p.1).map(|p| p.0),
+            final_a: final_a[i].1,
+            final_b: final_b[i].1,
+        });
+    }
+    (aligned.len(), curves)
+}
+
+/// Pair each event with the same kind and detail in the other run, in order of occurrence
+fn compare_events(a: &[RunEvent], b: &[RunEvent]) -> (usize, Vec<EventDiff>) {
+    let key = |e: &RunEvent| (e.event.name().to_string(), e.event.detail().map(str::to_string));
+    let mut b_ticks: BTreeMap<(String, Option<String>), VecDeque<u64>> = BTreeMap::new();
+    for e in b {
+        b_ticks.entry(key(e)).or_default().push_back(e.tick);
+    }
+
+    let (mut matched, mut diffs) = (0, Vec::new());
+    for e in a {
+        let (event, detail) = key(e);
+        let tick_b = b_ticks.get_mut(&(event.clone(), detail.clone())).and_then(|ticks| ticks.pop_front());
+        if tick_b == Some(e.tick) {
+            matched += 1;
+        } else {
+            diffs.push(EventDiff { event, detail, tick_a: Some(e.tick), tick_b });
+        }
+    }
+    for ((event, detail), ticks) in b_ticks {
+        for tick in ticks {
+            diffs.push(EventDiff { event: event.clone(), detail: detail.clone(), tick_a: None, tick_b: Some(tick) });
+        }
+    }
+    diffs.sort_by_key(|d| d.tick_a.or(d.tick_b));
+    (matched, diffs)
+}
+
+/// Line two runs up by tick and diff their KPI curves, event timelines and final scores
+pub fn compare_runs(label_a: &str, a: &RunOutcome, label_b: &str, b: &RunOutcome) -> RunComparison {
+    let (aligned_ticks, kpis) = compare_curves(a, b);
+    let (matched_events, events) = compare_events(&a.events, &b.events);
+    let identical = a.state_hash == b.state_hash
+        && a.verdict == b.verdict
+        && a.ticks_run == b.ticks_run
+        && a.score == b.score
+        && events.is_empty()
+        && kpis.iter().all(|k| k.max_abs_delta == 0.0 && k.final_a == k.final_b);
+    RunComparison {
+        a: side(label_a, a),
+        b: side(label_b, b),
+        aligned_ticks,
+        kpis,
+        events,
+        matched_events,
+        score_delta: b.score - a.score,
+        identical,
+    }
+}
+
+fn fmt_opt(value: Option<f32>) -> String {
+    value.map(|v| format!("{:.4}", v)).unwrap_or_else(|| "-".to_string())
+}
+
+impl RunComparison {
+    /// Report for pasting into a PR description
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("## Run comparison: {} vs {}\n\n", self.a.label, self.b.label);
+        if self.identical {
+            out.push_str("Runs are identical.\n\n");
+        }
+        out.push_str(&format!("| | {} | {} |\n|---|---|---|\n", self.a.label, self.b.label));
+        let scenario = |s: &RunSide| s.scenario.clone().unwrap_or_else(|| "-".to_string());
+        out.push_str(&format!("| Scenario | {} | {} |\n", scenario(&self.a), scenario(&self.b)));
+        out.push_str(&format!("| Seed | {} | {} |\n", self.a.seed, self.b.seed));
+        out.push_str(&format!("| Verdict | {:?} | {:?} |\n", self.a.verdict, self.b.verdict));
+        out.push_str(&format!("| Ticks | {} | {} |\n", self.a.ticks_run, self.b.ticks_run));
+        out.push_str(&format!("| Score | {} | {} ({:+}) |\n\n", self.a.score, self.b.score, self.score_delta));
+
+        out.push_str(&format!("### KPI curves ({} aligned samples)\n\n", self.aligned_ticks));
+        out.push_str("| KPI | mean A | mean B | max Δ | at tick | final A | final B |\n|---|---|---|---|---|---|---|\n");
+        for k in &self.kpis {
+            out.push_str(&format!(
+                "| {} | {:.4} | {:.4} | {:.4} | {} | {} | {} |\n",
+                k.kpi, k.mean_a, k.mean_b, k.max_abs_delta,
+                k.max_delta_tick.map(|t| t.to_string()).unwrap_or_else(|| "-".to_string()),
+                fmt_opt(k.final_a), fmt_opt(k.final_b),
+            ));
+        }
+
+        out.push_str(&format!("\n### Events ({} matched, {} differ)\n\n", self.matched_events, self.events.len()));
+        let tick = |t: Option<u64>| t.map(|t| t.to_string()).unwrap_or_else(|| "missing".to_string());
+        for e in &self.events {
+            let name = match &e.detail {
+                Some(detail) => format!("{} ({})", e.event, detail),
+                None => e.event.clone(),
+            };
+            out.push_str(&format!("- {}: A {}, B {}\n", name, tick(e.tick_a), tick(e.tick_b)));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JournalEvent, KpiSample};
+
+    fn run(score: i64, hit_rates: &[f32], events: Vec<RunEvent>) -> RunOutcome {
+        let samples: Vec<KpiSample> = hit_rates.iter().enumerate()
+            .map(|(i, &rate)| KpiSample { tick: (i as u64 + 1) * 10, kpis: KpiSnapshot { deadline_hit_rate: rate, ..Default::default() } })
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "verdict": "Incomplete",
+            "ticks_run": samples.len() as u64 * 10,
+            "seed": 7,
+            "scenario": "first_light_chill",
+            "doom_reason": null,
+            "score": score,
+            "final_kpis": samples.last().map(|s| s.kpis.clone()).unwrap_or_default(),
+            "qos_wait": <[crate::QosWaitStats; 3]>::default(),
+            "state_hash": format!("{}", score),
+            "samples": samples,
+            "events": events,
+        })).unwrap()
+    }
+
+    fn swan(tick: u64, id: &str) -> RunEvent {
+        RunEvent { tick, event: JournalEvent::BlackSwanFired { swan_id: id.to_string() } }
+    }
+
+    #[test]
+    fn test_aligns_curves_by_tick() {
+        let a = run(100, &[0.9, 0.9, 0.8], vec![]);
+        let mut b = run(120, &[0.9, 0.5], vec![]);
+        b.samples.insert(0, KpiSample { tick: 5, kpis: KpiSnapshot::default() }); // only b sampled tick 5
+
+        let report = compare_runs("a", &a, "b", &b);
+        assert_eq!(report.aligned_ticks, 2);
+        assert_eq!(report.score_delta, 20);
+        assert!(!report.identical);
+        let hits = report.kpis.iter().find(|k| k.kpi == "deadline_hit_rate").unwrap();
+        assert_eq!(hits.max_delta_tick, Some(20));
+        assert!((hits.max_abs_delta - 0.4).abs() < 1e-6);
+        assert!(report.kpis.iter().all(|k| k.kpi != "gpu_util"));
+        assert!(compare_runs("a", &a, "a", &a).identical);
+    }
+
+    #[test]
+    fn test_event_timelines_paired_in_order() {
+        let a = run(1, &[1.0], vec![swan(10, "pcie_link_flap"), swan(40, "pcie_link_flap"), swan(50, "heatwave")]);
+        let b = run(1, &[1.0], vec![swan(10, "pcie_link_flap"), swan(45, "pcie_link_flap"), swan(60, "brownout")]);
+        let report = compare_runs("a", &a, "b", &b);
+        assert_eq!(report.matched_events, 1);
+        let diffs: Vec<(Option<&str>, Option<u64>, Option<u64>)> = report.events.iter()
+            .map(|e| (e.detail.as_deref(), e.tick_a, e.tick_b))
+            .collect();
+        assert_eq!(diffs, vec![
+            (Some("pcie_link_flap"), Some(40), Some(45)),
+            (Some("heatwave"), Some(50), None),
+            (Some("brownout"), None, Some(60)),
+        ]);
+        assert!(report.to_markdown().contains("- BlackSwanFired (brownout): A missing, B 60"));
+    }
+
+    #[test]
+    fn test_run_names_stay_in_runs_dir() {
+        let dir = Path::new("runs");
+        assert_eq!(run_summary_path(dir, "nightly").unwrap(), dir.join("nightly.json"));
+        assert_eq!(run_summary_path(dir, "nightly.json").unwrap(), dir.join("nightly.json"));
+        assert!(run_summary_path(dir, "../secrets").is_err());
+        assert!(run_summary_path(dir, "").is_err());
+    }
+}
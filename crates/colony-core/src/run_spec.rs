@@ -0,0 +1,807 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::Path;
+use anyhow::Result;
+use crate::{
+    Colony, GlobalMeters, ResourceTunables, CorruptionTunables, SimClock, TickScale, ActiveScheduler,
+    SchedPolicy, JobQueue, Job, QoS, AutoThrottle, QosQueueing, QueueDiscipline, QosWaitStats, WinLossState, KpiSnapshot, build_shadow_app_with_config,
+    get_pipeline_by_id, load_scenarios, apply_difficulty_scaling, final_state_hash, ConfigWatcher,
+    tunable_range, set_tunable, MaintenanceLane, ClassReservations, OpLicenses, validate_op_limits, JobClass, Workyard, WorkyardKind, enqueue_maintenance, CertKind, CertificationBoard,
+    ArchiveStore, DeletionPolicy, StorageMetrics, enqueue_drive_replacement, ProgressHandle, RunFootprint, SessionMeta,
+    PausePolicies, PauseEvent, PauseMonitor, GameMode, BlackSwanIndex, CorruptionField, Debts, JobIdAllocator, force_black_swan, set_corruption_field, PersonnelLedger, RngAudit,
+    Scenario, SeedOverrides, EventJournal, JournalEvent, ColonyPluginConfig, GpuFarm, check_gpu_pipeline, EnqueuedJob, TenantLedger, generated_scenario,
+    WorkerFilter, BulkAction, BulkWorkerOp, apply_bulk,
+};
+
+/// Exit codes used by `colony-headless --run-spec`
+pub const EXIT_VICTORY: i32 = 0;
+pub const EXIT_LOSS: i32 = 1;
+pub const EXIT_INCOMPLETE: i32 = 2;
+pub const EXIT_SPEC_ERROR: i32 = 3;
+
+/// How often a run with a progress handle refreshes its footprint
+const FOOTPRINT_EVERY_TICKS: u64 = 600;
+
+/// Fully scripted run: scenario, seed, timed commands, stop condition and exports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSpec {
+    pub scenario: Option<String>,  // scenario id, see load_scenarios()
+    pub seed: Option<u64>,         // overrides the scenario seed
+    #[serde(default = "default_tick_scale")]
+    pub tick_scale: String,        // realtime | seconds | days | years
+    #[serde(default = "default_tick_scale_value")]
+    pub tick_scale_value: u64,
+    #[serde(default)]
+    pub stop: StopCondition,
+    #[serde(default, rename = "command")]
+    pub commands: Vec<TimedCommand>,
+    #[serde(default)]
+    pub export: RunExports,
+    #[serde(default)]
+    pub meta: SessionMeta, // labels copied into the summary export and replay bundle
+    #[serde(default)]
+    pub pause: PausePolicies, // stop early, as Incomplete, when one trips
+    #[serde(default)]
+    pub mode: GameMode, // creative unlocks the force_event and set_corruption commands
+    #[serde(default)]
+    pub seeds: SeedOverrides, // pins sub-seeds on top of the scenario's [seeds] table
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StopCondition {
+    pub max_ticks: u64,
+    #[serde(default = "default_true")]
+    pub on_victory: bool,
+    #[serde(default = "default_true")]
+    pub on_loss: bool,
+}
+
+impl Default for StopCondition {
+    fn default() -> Self {
+        Self {
+            max_ticks: 10_000,
+            on_victory: true,
+            on_loss: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunExports {
+    pub summary_json: Option<String>,
+    pub kpi_csv: Option<String>,
+    #[serde(default = "default_sample_every")]
+    pub sample_every: u64, // ticks between KPI samples
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tick_trace: Option<String>, // per-system state hashes as JSON lines; needs the tick_trace feature
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimedCommand {
+    pub at_tick: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_every: Option<u64>, // apply again every N ticks after at_tick, for sustained load
+    #[serde(flatten)]
+    pub command: RunCommand,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RunCommand {
+    SetScheduler { policy: SchedPolicy },
+    SetPowerCap { kw: f32 },
+    SetBandwidth { gbps: f32 },
+    SetAutoThrottle { enabled: bool },
+    SetQueueDiscipline { discipline: QueueDiscipline },
+    EnqueuePipeline {
+        pipeline: String,
+        count: u32,
+        payload_sz: usize,
+        deadline_ms: u64,
+        #[serde(default)]
+        tenant: Option<String>, // checked against the tenant's max_queued_jobs quota
+    },
+    SetTunable { key: String, value: f32 }, // e.g. "corruption.base_fault_rate", see tunable_watch
+    SetMaintenanceReservation { fraction: f32 },
+    SetClassReservation { yard: WorkyardKind, class: JobClass, fraction: f32 }, // 0 removes it
+    EnqueueMaintenance { yard: WorkyardKind },
+    TrainWorker { worker_id: u64, cert: CertKind },
+    SetArchivePolicy { policy: DeletionPolicy },
+    ReplaceDrives,
+    BulkWorkers {
+        #[serde(default)]
+        filter: WorkerFilter,
+        bulk: BulkAction,
+    },
+    ForceEvent { event: String },   // creative only
+    SetCorruption { value: f32 },   // creative only
+}
+
+fn default_tick_scale() -> String { "seconds".to_string() }
+fn default_tick_scale_value() -> u64 { 1 }
+fn default_true() -> bool { true }
+fn default_sample_every() -> u64 { 100 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RunVerdict {
+    Victory,
+    Loss,
+    Incomplete,
+}
+
+impl RunVerdict {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunVerdict::Victory => EXIT_VICTORY,
+            RunVerdict::Loss => EXIT_LOSS,
+            RunVerdict::Incomplete => EXIT_INCOMPLETE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KpiSample {
+    pub tick: u64,
+    pub kpis: KpiSnapshot,
+}
+
+/// A journal event and the run tick it happened on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunEvent {
+    pub tick: u64,
+    pub event: JournalEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunOutcome {
+    pub verdict: RunVerdict,
+    pub ticks_run: u64,
+    pub seed: u64,
+    pub scenario: Option<String>,
+    pub doom_reason: Option<String>,
+    pub score: i64,
+    pub final_kpis: KpiSnapshot,
+    pub qos_wait: [QosWaitStats; 3], // Latency, Balanced, Throughput
+    pub state_hash: String,          // see final_state_hash()
+    pub samples: Vec<KpiSample>,
+    #[serde(default)]
+    pub live_commands: Vec<TimedCommand>, // tunable changes picked up from watched config files
+    #[serde(default)]
+    pub storage: StorageMetrics,
+    #[serde(default)]
+    pub meta: SessionMeta,
+    #[serde(default)]
+    pub paused: Option<PauseEvent>, // the pause policy that ended the run early
+    #[serde(default)]
+    pub mode: GameMode, // creative runs can't lose; their scores aren't comparable
+    #[serde(default)]
+    pub events: Vec<RunEvent>, // event timeline: swans, rituals, day closes, the ending
+    #[serde(default)]
+    pub rng_audit: RngAudit,
+}
+
+impl RunSpec {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_toml(&content)
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let spec: RunSpec = toml::from_str(content)?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.parse_tick_scale()?;
+        if self.export.tick_trace.is_some() && !cfg!(feature = "tick_trace") {
+            return Err(anyhow::anyhow!("export.tick_trace needs a build with the tick_trace feature"));
+        }
+        if self.stop.max_ticks == 0 {
+            return Err(anyhow::anyhow!("stop.max_ticks must be greater than 0"));
+        }
+        self.meta.validate()?;
+        self.pause.validate()?;
+        let (has_gpu, mut reservations) = match &self.scenario {
+            Some(id) => {
+                let scenario = find_scenario(id)?;
+                validate_op_limits(&scenario.op_limits)?;
+                (scenario.gpu, ClassReservations::from_reservations(&scenario.class_reservations)?)
+            }
+            None => (true, ClassReservations::new()),
+        };
+        for cmd in &self.commands {
+            if cmd.repeat_every == Some(0) {
+                return Err(anyhow::anyhow!("repeat_every must be greater than 0"));
+            }
+            match &cmd.command {
+                RunCommand::EnqueuePipeline { pipeline, .. } => {
+                    let template = get_pipeline_by_id(pipeline)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown pipeline in command: {}", pipeline))?;
+                    check_gpu_pipeline(pipeline, &template, has_gpu)?;
+                }
+                RunCommand::SetTunable { key, value } => {
+                    let (min, max) = tunable_range(key)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown tunable in command: {}", key))?;
+                    if !(min..=max).contains(value) {
+                        return Err(anyhow::anyhow!("{} = {} is outside {}..={}", key, value, min, max));
+                    }
+                }
+                RunCommand::SetMaintenanceReservation { fraction } if !(0.0..=1.0).contains(fraction) => {
+                    return Err(anyhow::anyhow!("Maintenance reservation {} must be between 0 and 1", fraction));
+                }
+                RunCommand::SetClassReservation { yard, class, fraction } => {
+                    reservations.set(yard.clone(), *class, *fraction)?;
+                }
+                RunCommand::ForceEvent { .. } => self.mode.require_creative("The force_event command")?,
+                RunCommand::SetCorruption { .. } => self.mode.require_creative("The set_corruption command")?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The spec plus any live tunable changes from a watched run, so the run can be replayed exactly
+    pub fn with_live_commands(&self, outcome: &RunOutcome) -> RunSpec {
+        let mut spec = self.clone();
+        spec.commands.extend(outcome.live_commands.iter().cloned());
+        spec
+    }
+
+    fn parse_tick_scale(&self) -> Result<TickScale> {
+        match self.tick_scale.as_str() {
+            "realtime" => Ok(TickScale::RealTime),
+            "seconds" => Ok(TickScale::Seconds(self.tick_scale_value)),
+            "days" => Ok(TickScale::Days(self.tick_scale_value as u16)),
+            "years" => Ok(TickScale::Years(self.tick_scale_value as u8)),
+            other => Err(anyhow::anyhow!("Unknown tick scale: {}", other)),
+        }
+    }
+}
+
+/// Starting colony for a run, with scenario difficulty scaling and an optional seed override
+pub fn colony_for_scenario(scenario: Option<&str>, seed: Option<u64>) -> Result<Colony> {
+    let scenario = scenario.map(find_scenario).transpose()?;
+    Ok(colony_from_scenario(scenario.as_ref(), seed))
+}
+
+/// `colony_for_scenario` for a scenario that's already loaded, e.g. one sent with a game setup
+pub fn colony_from_scenario(scenario: Option<&Scenario>, seed: Option<u64>) -> Colony {
+    let mut colony = Colony {
+        power_cap_kw: 1000.0,
+        bandwidth_total_gbps: 32.0,
+        corruption_field: 0.0,
+        target_uptime_days: 365,
+        meters: GlobalMeters::new(),
+        tunables: ResourceTunables::default(),
+        corruption_tun: CorruptionTunables::default(),
+        seeds: Default::default(),
+        seed: 42,
+    };
+
+    if let Some(scenario) = scenario {
+        let mut corruption_tun = colony.corruption_tun.clone();
+        apply_difficulty_scaling(&scenario.difficulty, &mut colony, &mut corruption_tun);
+        colony.corruption_tun = corruption_tun;
+        colony.target_uptime_days = scenario.victory.target_uptime_days;
+        colony.seed = scenario.seed;
+        colony.seeds = scenario.seeds.clone();
+    }
+    if let Some(seed) = seed {
+        colony.seed = seed;
+    }
+    colony
+}
+
+pub fn find_scenario(id: &str) -> Result<Scenario> {
+    if let Some(generated) = generated_scenario(id) {
+        return Ok(generated?.scenario);
+    }
+    load_scenarios()?
+        .into_iter()
+        .find(|s| s.id == id)
+        .ok_or_else(|| anyhow::anyhow!("Unknown scenario: {}", id))
+}
+
+/// Sub-plugins a run of the scenario uses; no scenario means the full stack
+pub fn plugin_config_for_scenario(scenario: Option<&str>) -> Result<ColonyPluginConfig> {
+    match scenario {
+        Some(id) => Ok(find_scenario(id)?.plugin_config()),
+        None => Ok(ColonyPluginConfig::default()),
+    }
+}
+
+/// Apply one spec command to a running app; commands that can't apply are logged and skipped
+pub fn apply_command(app: &mut App, command: &RunCommand) {
+    let world = app.world_mut();
+    match command {
+        RunCommand::SetScheduler { policy } => {
+            world.resource_mut::<ActiveScheduler>().policy = *policy;
+        }
+        RunCommand::SetPowerCap { kw } => {
+            world.resource_mut::<Colony>().power_cap_kw = *kw;
+        }
+        RunCommand::SetBandwidth { gbps } => {
+            world.resource_mut::<Colony>().bandwidth_total_gbps = *gbps;
+        }
+        RunCommand::SetAutoThrottle { enabled } => {
+            world.resource_mut::<AutoThrottle>().set_enabled(*enabled);
+        }
+        RunCommand::SetQueueDiscipline { discipline } => {
+            world.resource_mut::<QosQueueing>().set_discipline(*discipline);
+        }
+        RunCommand::SetMaintenanceReservation { fraction } => {
+            world.resource_mut::<MaintenanceLane>().set_reservation(*fraction);
+        }
+        RunCommand::SetClassReservation { yard, class, fraction } => {
+            if let Err(e) = world.resource_mut::<ClassReservations>().set(yard.clone(), *class, *fraction) {
+                eprintln!("Ignoring class reservation: {}", e);
+            }
+        }
+        RunCommand::EnqueueMaintenance { yard } => {
+            let now_tick = world.resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+            let mut yards = world.query::<(Entity, &Workyard)>();
+            let target = yards.iter(world).find(|(_, y)| y.kind == *yard).map(|(e, _)| e);
+            if let Some(target) = target {
+                world.resource_scope(|world, mut job_ids: Mut<JobIdAllocator>| {
+                    enqueue_maintenance(target, &mut world.resource_mut::<JobQueue>(), &mut job_ids, now_tick);
+                });
+            }
+        }
+        RunCommand::TrainWorker { worker_id, cert } => {
+            let now_tick = world.resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+            if let Err(e) = world.resource_mut::<CertificationBoard>().start_training(*worker_id, *cert, now_tick) {
+                println!("Skipping training command: {}", e);
+            }
+        }
+        RunCommand::ReplaceDrives => {
+            let now_tick = world.resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+            let mut yards = world.query::<(Entity, &Workyard)>();
+            let target = yards.iter(world).find(|(_, y)| y.kind == WorkyardKind::StorageArray).map(|(e, _)| e);
+            if let Some(target) = target {
+                world.resource_scope(|world, mut job_ids: Mut<JobIdAllocator>| {
+                    enqueue_drive_replacement(target, &mut world.resource_mut::<JobQueue>(), &mut job_ids, now_tick);
+                });
+            }
+        }
+        RunCommand::BulkWorkers { filter, bulk } => {
+            let op = BulkWorkerOp { filter: filter.clone(), action: bulk.clone() };
+            match apply_bulk(world, &op) {
+                Ok(outcome) => println!("Bulk {:?} changed {} of {} matching workers", op.action, outcome.changed.len(), outcome.matched.len()),
+                Err(e) => println!("Skipping bulk_workers command: {}", e),
+            }
+        }
+        RunCommand::SetArchivePolicy { policy } => {
+            world.resource_mut::<ArchiveStore>().policy = *policy;
+        }
+        RunCommand::ForceEvent { event } => {
+            let now_tick = world.resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+            let mode = *world.resource::<GameMode>();
+            if !world.contains_resource::<BlackSwanIndex>() {
+                return;
+            }
+            world.resource_scope(|world, mut index: Mut<BlackSwanIndex>| {
+                match force_black_swan(mode, &mut index, &mut world.resource_mut::<Debts>(), event, now_tick) {
+                    Ok(effects) => {
+                        if let Some(mut personnel) = world.get_resource_mut::<PersonnelLedger>() {
+                            personnel.queue_effects(&effects);
+                        }
+                    }
+                    Err(e) => println!("Skipping force_event command: {}", e),
+                }
+            });
+        }
+        RunCommand::SetCorruption { value } => {
+            let mode = *world.resource::<GameMode>();
+            world.resource_scope(|world, mut colony: Mut<Colony>| {
+                let field = world.get_resource_mut::<CorruptionField>();
+                if let Err(e) = set_corruption_field(mode, &mut colony, field.map(|f| f.into_inner()), *value) {
+                    println!("Skipping set_corruption command: {}", e);
+                }
+            });
+        }
+        RunCommand::SetTunable { key, value } => {
+            if let Err(e) = set_tunable(&mut world.resource_mut::<Colony>(), key, *value) {
+                println!("Skipping tunable command: {}", e);
+            }
+        }
+        RunCommand::EnqueuePipeline { pipeline, count, payload_sz, deadline_ms, tenant } => {
+            let Some(template) = get_pipeline_by_id(pipeline) else { return };
+            let has_gpu = world.query_filtered::<(), With<GpuFarm>>().iter(world).next().is_some();
+            if let Err(e) = check_gpu_pipeline(pipeline, &template, has_gpu) {
+                println!("Skipping enqueue_pipeline command: {}", e);
+                return;
+            }
+            let now_tick = world.resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+            world.resource_scope(|world, mut job_ids: Mut<JobIdAllocator>| {
+                world.resource_scope(|world, mut tenants: Mut<TenantLedger>| {
+                    let mut jobq = world.resource_mut::<JobQueue>();
+                    for _ in 0..*count {
+                        if let Err(e) = tenants.admit(tenant.as_deref()) {
+                            println!("Skipping the rest of enqueue_pipeline command: {}", e);
+                            break;
+                        }
+                        let mut enqueued = EnqueuedJob::new(Job {
+                            id: job_ids.allocate(),
+                            pipeline: template.clone(),
+                            qos: QoS::Balanced,
+                            deadline_ms: *deadline_ms,
+                            payload_sz: *payload_sz,
+                        }, now_tick);
+                        enqueued.tenant = tenant.clone();
+                        jobq.push_enqueued(enqueued);
+                    }
+                });
+            });
+        }
+    }
+}
+
+/// Spec commands ordered by the tick they're next due; repeating commands go back in after they run
+struct CommandSchedule<'a> {
+    commands: &'a [TimedCommand],
+    pending: BinaryHeap<Reverse<(u64, usize)>>, // (due tick, spec index), so same-tick commands keep spec order
+}
+
+impl<'a> CommandSchedule<'a> {
+    fn new(commands: &'a [TimedCommand]) -> Self {
+        let pending = commands.iter().enumerate().map(|(i, c)| Reverse((c.at_tick, i))).collect();
+        Self { commands, pending }
+    }
+
+    fn due(&mut self, tick: u64) -> Vec<&'a TimedCommand> {
+        let mut due = Vec::new();
+        while let Some(&Reverse((at_tick, i))) = self.pending.peek() {
+            if at_tick > tick {
+                break;
+            }
+            self.pending.pop();
+            let cmd = &self.commands[i];
+            if let Some(every) = cmd.repeat_every {
+                self.pending.push(Reverse((at_tick + every, i)));
+            }
+            due.push(cmd);
+        }
+        due
+    }
+}
+
+/// Execute a run spec to completion in an isolated app
+pub fn execute_run_spec(spec: &RunSpec) -> Result<RunOutcome> {
+    execute_run_spec_watched(spec, None)
+}
+
+/// Execute a run spec, reloading watched config files at each tick boundary.
+/// Applied changes are returned as `live_commands`, after any spec commands for the same tick.
+pub fn execute_run_spec_watched(spec: &RunSpec, watcher: Option<&mut ConfigWatcher>) -> Result<RunOutcome> {
+    execute_run_spec_with_progress(spec, watcher, None)
+}
+
+/// Same as `execute_run_spec_watched`, publishing ticks/sec and ETA to `progress` as the run goes
+pub fn execute_run_spec_with_progress(
+    spec: &RunSpec,
+    mut watcher: Option<&mut ConfigWatcher>,
+    progress: Option<&ProgressHandle>,
+) -> Result<RunOutcome> {
+    let mut colony = colony_for_scenario(spec.scenario.as_deref(), spec.seed)?;
+    colony.seeds = colony.seeds.merged(&spec.seeds);
+    let seed = colony.seed;
+    // Start every scripted run from the same epoch so sim ticks line up across runs
+    let clock = SimClock {
+        tick_scale: spec.parse_tick_scale()?,
+        now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+    };
+
+    let config = plugin_config_for_scenario(spec.scenario.as_deref())?;
+    let mut app = build_shadow_app_with_config(&colony, &clock, &ActiveScheduler::default(), config);
+    app.insert_resource(spec.pause.clone())
+        .insert_resource(spec.mode);
+    if let Some(id) = spec.scenario.as_deref() {
+        let scenario = find_scenario(id)?;
+        app.insert_resource(ClassReservations::from_reservations(&scenario.class_reservations)?)
+            .insert_resource(OpLicenses::from_limits(&scenario.op_limits)?);
+    }
+    #[cfg(feature = "tick_trace")]
+    if spec.export.tick_trace.is_some() {
+        app.add_plugins(crate::TickTracePlugin);
+    }
+    // A generated scenario brings its yard layout and traffic mix; the spec's commands run after its traffic
+    let mut commands = Vec::new();
+    if let Some(generated) = spec.scenario.as_deref().and_then(generated_scenario) {
+        let generated = generated?;
+        commands.extend(generated.commands());
+        app.insert_resource(generated.yards);
+    }
+    commands.extend(spec.commands.iter().cloned());
+    let mut schedule = CommandSchedule::new(&commands);
+
+    let mut samples = Vec::new();
+    let mut verdict = RunVerdict::Incomplete;
+    let mut ticks_run = 0;
+    let sample_every = spec.export.sample_every.max(1);
+    let mut live_commands = Vec::new();
+    let mut events = Vec::new();
+    let mut journal_seen = 0;
+    let mut paused = None;
+    let started = bevy::platform::time::Instant::now();
+    if let Some(progress) = progress {
+        progress.start(spec.stop.max_ticks);
+    }
+
+    while ticks_run < spec.stop.max_ticks {
+        for cmd in schedule.due(ticks_run) {
+            apply_command(&mut app, &cmd.command);
+        }
+        if let Some(watcher) = watcher.as_deref_mut() {
+            let mut colony = app.world_mut().resource_mut::<Colony>();
+            for change in watcher.poll(&mut colony, ticks_run) {
+                live_commands.push(TimedCommand {
+                    at_tick: ticks_run,
+                    repeat_every: None,
+                    command: RunCommand::SetTunable { key: change.key, value: change.new },
+                });
+            }
+        }
+
+        app.update();
+        ticks_run += 1;
+        if let Some(progress) = progress {
+            progress.update(ticks_run, started);
+            if ticks_run % FOOTPRINT_EVERY_TICKS == 0 {
+                progress.set_footprint(RunFootprint::capture(app.world(), ticks_run));
+            }
+        }
+
+        if let Some(journal) = app.world().get_resource::<EventJournal>() {
+            // Timeline stops growing if a very long run fills the journal
+            events.extend(journal.entries.iter().skip(journal_seen)
+                .map(|entry| RunEvent { tick: ticks_run, event: entry.event.clone() }));
+            journal_seen = journal.entries.len();
+        }
+
+        if ticks_run % sample_every == 0 {
+            samples.push(KpiSample { tick: ticks_run, kpis: KpiSnapshot::capture(app.world_mut()) });
+        }
+
+        if let Some(state) = app.world().get_resource::<WinLossState>() {
+            if state.victory && spec.stop.on_victory {
+                verdict = RunVerdict::Victory;
+                break;
+            }
+            if state.doom && spec.stop.on_loss {
+                verdict = RunVerdict::Loss;
+                break;
+            }
+        }
+        if let Some(event) = app.world_mut().resource_mut::<PauseMonitor>().take_unacknowledged() {
+            paused = Some(event);
+            break;
+        }
+    }
+
+    if let Some(progress) = progress {
+        progress.finish();
+    }
+    // The trace lives in the app, so it's written here rather than in write_run_exports
+    #[cfg(feature = "tick_trace")]
+    if let Some(ref path) = spec.export.tick_trace {
+        create_parent_dir(path)?;
+        crate::write_tick_trace(Path::new(path), &app.world().resource::<crate::TickTrace>().records)?;
+    }
+
+    let (doom_reason, score) = app.world()
+        .get_resource::<WinLossState>()
+        .map(|s| (s.doom_reason.clone(), s.score))
+        .unwrap_or((None, 0));
+
+    Ok(RunOutcome {
+        verdict,
+        ticks_run,
+        seed,
+        scenario: spec.scenario.clone(),
+        doom_reason,
+        score,
+        final_kpis: KpiSnapshot::capture(app.world_mut()),
+        qos_wait: app.world().resource::<QosQueueing>().wait.clone(),
+        state_hash: final_state_hash(app.world_mut()),
+        samples,
+        live_commands,
+        storage: app.world().get_resource::<ArchiveStore>().map(|s| s.metrics()).unwrap_or_default(),
+        meta: spec.meta.clone(),
+        paused,
+        mode: spec.mode,
+        events,
+        rng_audit: app.world().get_resource::<RngAudit>().cloned().unwrap_or_default(),
+    })
+}
+
+pub fn write_run_exports(spec: &RunSpec, outcome: &RunOutcome) -> Result<()> {
+    if let Some(ref path) = spec.export.summary_json {
+        create_parent_dir(path)?;
+        std::fs::write(path, serde_json::to_string_pretty(outcome)?)?;
+    }
+    if let Some(ref path) = spec.export.kpi_csv {
+        create_parent_dir(path)?;
+        // GPU columns only for colonies that have a GPU farm
+        let has_gpu = outcome.final_kpis.gpu_util.is_some();
+        let mut csv = String::from("tick,deadline_hit_rate,power_draw_kw,bandwidth_util,corruption_field");
+        csv.push_str(if has_gpu { ",gpu_util,vram_used_mb\n" } else { "\n" });
+        for s in &outcome.samples {
+            csv.push_str(&format!(
+                "{},{},{},{},{}",
+                s.tick, s.kpis.deadline_hit_rate, s.kpis.power_draw_kw, s.kpis.bandwidth_util, s.kpis.corruption_field
+            ));
+            if has_gpu {
+                csv.push_str(&format!(
+                    ",{},{}",
+                    s.kpis.gpu_util.unwrap_or_default(),
+                    s.kpis.vram_used_mb.unwrap_or_default()
+                ));
+            }
+            csv.push('\n');
+        }
+        std::fs::write(path, csv)?;
+    }
+    Ok(())
+}
+
+fn create_parent_dir(path: &str) -> Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GenKnobs;
+
+    const SPEC: &str = r#"
+scenario = "first_light_chill"
+seed = 7
+tick_scale = "seconds"
+tick_scale_value = 1
+
+[stop]
+max_ticks = 20
+
+[[command]]
+at_tick = 5
+action = "set_scheduler"
+policy = "Edf"
+
+[[command]]
+at_tick = 0
+action = "enqueue_pipeline"
+pipeline = "udp_telemetry_ingest"
+count = 3
+payload_sz = 1024
+deadline_ms = 50
+
+[export]
+sample_every = 5
+"#;
+
+    #[test]
+    fn test_parse_run_spec() {
+        let spec = RunSpec::from_toml(SPEC).unwrap();
+        assert_eq!(spec.scenario.as_deref(), Some("first_light_chill"));
+        assert_eq!(spec.stop.max_ticks, 20);
+        assert!(spec.stop.on_victory);
+        assert_eq!(spec.commands.len(), 2);
+        assert!(matches!(spec.commands[0].command, RunCommand::SetScheduler { policy: SchedPolicy::Edf }));
+    }
+
+    #[test]
+    fn test_rejects_unknown_pipeline() {
+        let spec = SPEC.replace("udp_telemetry_ingest", "no_such_pipeline");
+        assert!(RunSpec::from_toml(&spec).is_err());
+    }
+
+    #[test]
+    fn test_execute_run_spec() {
+        let spec = RunSpec::from_toml(SPEC).unwrap();
+        let outcome = execute_run_spec(&spec).unwrap();
+        assert_eq!(outcome.seed, 7);
+        assert!(outcome.ticks_run <= 20);
+        assert_eq!(outcome.samples.len() as u64, outcome.ticks_run / 5);
+    }
+
+    #[test]
+    fn test_cpu_only_scenario_runs_without_gpu() {
+        // First Light has no GPU farm: GPU pipelines are refused up front
+        let gpu_spec = SPEC.replace("udp_telemetry_ingest", "can_telemetry");
+        let err = RunSpec::from_toml(&gpu_spec).unwrap_err();
+        assert!(err.to_string().contains("needs a GPU farm for Yolo"));
+        assert!(RunSpec::from_toml(&gpu_spec.replace("first_light_chill", "factory_horizon_nominal")).is_ok());
+
+        let outcome = execute_run_spec(&RunSpec::from_toml(SPEC).unwrap()).unwrap();
+        assert_eq!(outcome.final_kpis.gpu_util, None);
+        assert_eq!(outcome.final_kpis.heat_levels.len(), 2); // CPU and storage yards only
+        let summary = serde_json::to_value(&outcome).unwrap();
+        assert!(summary["final_kpis"].get("gpu_util").is_none());
+    }
+
+    #[test]
+    fn test_generated_scenario_runs_with_its_layout() {
+        let id = GenKnobs { size: 1.0, gpu: Some(false), ..GenKnobs::default() }.scenario_id(5);
+        let spec = RunSpec::from_toml(&SPEC.replace("first_light_chill", &id)).unwrap();
+        let generated = generated_scenario(&id).unwrap().unwrap();
+        let outcome = execute_run_spec(&spec).unwrap();
+        assert_eq!(outcome.final_kpis.heat_levels.len(), generated.yards.0.len());
+        assert_eq!(outcome.final_kpis.gpu_util, None);
+        assert!(find_scenario("gen-5-l50").is_err());
+    }
+
+    #[test]
+    fn test_repeated_commands_keep_load_on() {
+        let spec = SPEC.replace("at_tick = 0\n", "at_tick = 0\nrepeat_every = 4\n");
+        let spec = RunSpec::from_toml(&spec).unwrap();
+        let mut schedule = CommandSchedule::new(&spec.commands);
+        let due: Vec<(u64, bool)> = (0..10)
+            .flat_map(|tick| schedule.due(tick).into_iter().map(move |c| (tick, c.repeat_every.is_some())))
+            .collect();
+        assert_eq!(due, vec![(0, true), (4, true), (5, false), (8, true)]);
+
+        let zero = SPEC.replace("at_tick = 0\n", "at_tick = 0\nrepeat_every = 0\n");
+        assert!(RunSpec::from_toml(&zero).is_err());
+    }
+
+    #[test]
+    fn test_creative_commands_need_creative_mode() {
+        let commands = format!("{}\n[[command]]\nat_tick = 2\naction = \"set_corruption\"\nvalue = 0.6\n", SPEC);
+        assert!(RunSpec::from_toml(&commands).is_err());
+
+        let spec = RunSpec::from_toml(&format!("mode = \"creative\"\n{}", commands)).unwrap();
+        let outcome = execute_run_spec(&spec).unwrap();
+        assert_eq!(outcome.mode, GameMode::Creative);
+        assert_eq!(outcome.verdict, RunVerdict::Incomplete);
+        assert!(outcome.final_kpis.corruption_field > 0.5);
+    }
+
+    #[test]
+    fn test_meta_carries_into_outcome() {
+        let spec = format!("{}\n[meta]\nname = \"chill baseline\"\ntags = [\"baseline\"]\n", SPEC);
+        let spec = RunSpec::from_toml(&spec).unwrap();
+        let outcome = execute_run_spec(&spec).unwrap();
+        assert_eq!(outcome.meta.label().as_deref(), Some("chill baseline [baseline]"));
+
+        let bad = format!("{}\n[meta]\ntags = [\"has space\"]\n", SPEC);
+        assert!(RunSpec::from_toml(&bad).is_err());
+    }
+
+    #[test]
+    fn test_pause_policy_ends_run_early() {
+        let spec = SPEC.replace("max_ticks = 20", "max_ticks = 400").replace("deadline_ms = 50", "deadline_ms = 1");
+        // The chill layout has no SignalHub, so the CPU array has to take the UDP ingest
+        let reserve = "[[command]]\nat_tick = 0\naction = \"set_class_reservation\"\nyard = \"CpuArray\"\nclass = \"io\"\nfraction = 0.5\n";
+        let spec = RunSpec::from_toml(&format!("{}\n{}\n[pause]\nsla_below_pct = 100.0\n", spec, reserve)).unwrap();
+        let outcome = execute_run_spec(&spec).unwrap();
+        let paused = outcome.paused.expect("missed deadlines should trip the SLA policy");
+        assert!(matches!(paused.reason, crate::PauseReason::SlaBelow { .. }));
+        assert_eq!(outcome.verdict, RunVerdict::Incomplete);
+        assert!(outcome.ticks_run < 400);
+    }
+
+    #[test]
+    fn test_watched_changes_replay() {
+        let path = std::env::temp_dir().join(format!("colony_run_tunables_{}.toml", std::process::id()));
+        std::fs::write(&path, "[corruption]\nbase_fault_rate = 0.2\n").unwrap();
+        let spec = RunSpec::from_toml(SPEC).unwrap();
+
+        let mut watcher = ConfigWatcher::unwatched(vec![path.clone()]);
+        let live = execute_run_spec_watched(&spec, Some(&mut watcher)).unwrap();
+        assert_eq!(live.live_commands.len(), 1);
+        assert!(matches!(live.live_commands[0].command, RunCommand::SetTunable { ref key, value } if key == "corruption.base_fault_rate" && value == 0.2));
+
+        // Replaying the recorded commands reproduces the watched run
+        let replayed = execute_run_spec(&spec.with_live_commands(&live)).unwrap();
+        assert_eq!(replayed.state_hash, live.state_hash);
+        std::fs::remove_file(&path).ok();
+    }
+}
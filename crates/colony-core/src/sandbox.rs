@@ -0,0 +1,334 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::{SimClock, ActiveScheduler, SchedPolicy, Job, JobQueue, KpiSnapshot, ShadowSeed, ShadowWorld};
+
+pub const DEFAULT_SANDBOX_TICKS: u64 = 600;
+pub const MAX_SANDBOX_UPDATES: u64 = 3600;
+
+/// A job as it arrived in the live queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Arrival {
+    pub tick: u64,
+    pub job: Job,
+}
+
+/// Bounded record of recent job arrivals, kept so they can be replayed in a sandbox
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct ArrivalLog {
+    pub arrivals: VecDeque<Arrival>,
+    pub retention_ticks: u64,
+    pub max_arrivals: usize,
+    seen: HashSet<u64>, // job ids currently in `arrivals`
+}
+
+impl Default for ArrivalLog {
+    fn default() -> Self {
+        Self {
+            arrivals: VecDeque::new(),
+            retention_ticks: 5 * 3750, // 5 minutes of 16ms ticks
+            max_arrivals: 50_000,
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl ArrivalLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an arrival; jobs already in the log (e.g. requeued retries) are ignored
+    pub fn record(&mut self, job: &Job, tick: u64) {
+        if !self.seen.insert(job.id) {
+            return;
+        }
+        self.arrivals.push_back(Arrival { tick, job: job.clone() });
+        self.prune(tick);
+    }
+
+    fn prune(&mut self, now_tick: u64) {
+        let cutoff = now_tick.saturating_sub(self.retention_ticks);
+        while let Some(front) = self.arrivals.front() {
+            if front.tick >= cutoff && self.arrivals.len() <= self.max_arrivals {
+                break;
+            }
+            if let Some(old) = self.arrivals.pop_front() {
+                self.seen.remove(&old.job.id);
+            }
+        }
+    }
+
+    /// Arrivals in the last `ticks` ticks before `now_tick`, oldest first
+    pub fn window(&self, now_tick: u64, ticks: u64) -> Vec<Arrival> {
+        let start = now_tick.saturating_sub(ticks);
+        self.arrivals.iter().filter(|a| a.tick >= start && a.tick <= now_tick).cloned().collect()
+    }
+}
+
+/// Records every job the first time it shows up in the queue, whoever pushed it
+pub fn arrival_log_system(
+    mut log: ResMut<ArrivalLog>,
+    jobq: Res<JobQueue>,
+    clock: Res<SimClock>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    for ej in jobq.cpu.iter().chain(jobq.gpu.iter()).chain(jobq.io.iter()).chain(jobq.storage.iter()) {
+        if !log.seen.contains(&ej.job.id) {
+            log.record(&ej.job, now_tick);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SandboxRequest {
+    pub ticks: Option<u64>,
+    pub policies: Option<Vec<SchedPolicy>>, // defaults to every policy
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyOutcome {
+    pub policy: SchedPolicy,
+    pub is_live_policy: bool,
+    pub jobs_arrived: u64,
+    pub jobs_dispatched: u64,
+    pub jobs_pending: u64,   // still queued at the end with deadline not yet passed
+    pub deadline_hits: u64,
+    pub deadline_hit_rate: f32, // hits over dispatched plus expired-in-queue
+    pub mean_latency_ticks: f32,
+    pub p95_latency_ticks: u64,
+    pub kpis: KpiSnapshot,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxResult {
+    pub ticks_replayed: u64,
+    pub arrivals_replayed: usize,
+    pub live_policy: SchedPolicy,
+    pub best_policy: Option<SchedPolicy>,
+    pub outcomes: Vec<PolicyOutcome>,
+}
+
+fn deadline_ticks(job: &Job) -> u64 {
+    job.deadline_ms / 16
+}
+
+/// Replay the last `ticks` ticks of recorded arrivals once per policy, each in its own
+/// shadow world forked from the live colony's seed with its queue emptied, and compare queue
+/// latency and deadline hits.
+pub fn run_scheduler_sandbox(
+    seed: &ShadowSeed,
+    arrivals: &ArrivalLog,
+    request: &SandboxRequest,
+) -> SandboxResult {
+    let ticks = request.ticks.unwrap_or(DEFAULT_SANDBOX_TICKS).min(arrivals.retention_ticks);
+    let now_tick = seed.clock.now.timestamp_millis() as u64 / 16;
+    let window = arrivals.window(now_tick, ticks);
+    let policies = request.policies.clone()
+        .unwrap_or_else(|| SchedPolicy::ALL.to_vec());
+
+    let outcomes: Vec<PolicyOutcome> = policies.iter()
+        .map(|&policy| {
+            let mut outcome = replay_policy(seed, policy, &window, now_tick.saturating_sub(ticks), ticks);
+            outcome.is_live_policy = policy == seed.scheduler.policy;
+            outcome
+        })
+        .collect();
+
+    let best_policy = outcomes.iter()
+        .filter(|o| o.jobs_arrived > 0)
+        .max_by(|a, b| {
+            a.deadline_hit_rate.partial_cmp(&b.deadline_hit_rate)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.mean_latency_ticks.partial_cmp(&a.mean_latency_ticks).unwrap_or(std::cmp::Ordering::Equal))
+        })
+        .map(|o| o.policy);
+
+    SandboxResult {
+        ticks_replayed: ticks,
+        arrivals_replayed: window.len(),
+        live_policy: seed.scheduler.policy,
+        best_policy,
+        outcomes,
+    }
+}
+
+fn replay_policy(
+    seed: &ShadowSeed,
+    policy: SchedPolicy,
+    window: &[Arrival],
+    start_tick: u64,
+    ticks: u64,
+) -> PolicyOutcome {
+    // The live yards, workers, config and registry, rewound to the start of the window
+    let mut seed = seed.clone();
+    seed.clock.now -= chrono::Duration::milliseconds((ticks * 16) as i64);
+    seed.scheduler = ActiveScheduler { policy, custom: None };
+    let shadow_clock = seed.clock.clone();
+    let mut shadow = ShadowWorld::from_seed(&seed);
+    // Only recorded arrivals are scheduled, not what the live queue held at the fork
+    shadow.world_mut().resource_mut::<JobQueue>().clear();
+
+    // Arrivals are replayed on a virtual tick line so a paused live clock still replays in order
+    let ticks_per_update = if shadow_clock.is_paused() {
+        1
+    } else {
+        (shadow_clock.advance().as_millis() as u64 / 16).max(1)
+    };
+    let updates = ticks.div_ceil(ticks_per_update).clamp(1, MAX_SANDBOX_UPDATES);
+
+    let mut next = 0;
+    let mut queued: HashMap<u64, (u64, u64)> = HashMap::new(); // job id -> (arrival tick, deadline ticks)
+    let mut latencies: Vec<u64> = Vec::new();
+    let mut deadline_hits = 0u64;
+
+    for update in 0..updates {
+        let virtual_tick = update * ticks_per_update;
+        {
+            let shadow_now = shadow.world().resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+            let mut jobq = shadow.world_mut().resource_mut::<JobQueue>();
+            while next < window.len() && window[next].tick.saturating_sub(start_tick) <= virtual_tick {
+                let arrival = &window[next];
+                queued.insert(arrival.job.id, (virtual_tick, deadline_ticks(&arrival.job)));
+                jobq.push(arrival.job.clone(), shadow_now);
+                next += 1;
+            }
+        }
+
+        shadow.advance(1);
+
+        let dispatch_tick = virtual_tick + ticks_per_update;
+        let jobq = shadow.world().resource::<JobQueue>();
+        let still_queued: HashSet<u64> = jobq.cpu.iter()
+            .chain(jobq.gpu.iter())
+            .chain(jobq.io.iter())
+            .chain(jobq.storage.iter())
+            .map(|ej| ej.job.id)
+            .collect();
+        queued.retain(|id, &mut (arrived, deadline)| {
+            if still_queued.contains(id) {
+                return true;
+            }
+            let latency = dispatch_tick - arrived;
+            if latency <= deadline {
+                deadline_hits += 1;
+            }
+            latencies.push(latency);
+            false
+        });
+    }
+
+    // Anything still queued past its deadline is a miss; the rest could still make it
+    let end_tick = updates * ticks_per_update;
+    let expired = queued.values().filter(|&&(arrived, deadline)| end_tick - arrived > deadline).count() as u64;
+    let resolved = latencies.len() as u64 + expired;
+
+    latencies.sort_unstable();
+    let mean_latency_ticks = if latencies.is_empty() {
+        0.0
+    } else {
+        latencies.iter().sum::<u64>() as f32 / latencies.len() as f32
+    };
+    let p95_latency_ticks = if latencies.is_empty() {
+        0
+    } else {
+        latencies[((latencies.len() as f32 * 0.95) as usize).min(latencies.len() - 1)]
+    };
+
+    PolicyOutcome {
+        policy,
+        is_live_policy: false,
+        jobs_arrived: next as u64,
+        jobs_dispatched: latencies.len() as u64,
+        jobs_pending: queued.len() as u64 - expired,
+        deadline_hits,
+        deadline_hit_rate: if resolved > 0 { deadline_hits as f32 / resolved as f32 } else { 1.0 },
+        mean_latency_ticks,
+        p95_latency_ticks,
+        kpis: shadow.kpis(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pipeline, Op, QoS, TickScale, ColonyPluginConfig, Worker, colony_for_scenario};
+
+    fn job(id: u64, deadline_ms: u64) -> Job {
+        Job {
+            id,
+            pipeline: Pipeline { ops: vec![Op::Decode, Op::Kalman], mutation_tag: None, id: None },
+            qos: QoS::Balanced,
+            deadline_ms,
+            payload_sz: 256,
+        }
+    }
+
+    #[test]
+    fn test_arrival_log_dedups_and_prunes() {
+        let mut log = ArrivalLog::new();
+        log.retention_ticks = 100;
+        log.record(&job(1, 50), 10);
+        log.record(&job(1, 50), 20);
+        assert_eq!(log.arrivals.len(), 1);
+
+        log.record(&job(2, 50), 200);
+        assert_eq!(log.arrivals.len(), 1);
+        assert_eq!(log.arrivals[0].job.id, 2);
+
+        // Pruned ids can be recorded again
+        log.record(&job(1, 50), 210);
+        assert_eq!(log.window(210, 50).len(), 2);
+        assert_eq!(log.window(210, 5).len(), 1);
+    }
+
+    /// A live world one tick past Startup, with 20 arrivals over the last 50 ticks
+    fn live_world() -> (ShadowWorld, ArrivalLog) {
+        let colony = colony_for_scenario(None, Some(42)).unwrap();
+        let clock = SimClock {
+            tick_scale: TickScale::RealTime,
+            now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH + chrono::Duration::seconds(60),
+        };
+        let mut live = ShadowWorld::new(&colony, &clock, &ActiveScheduler::default(), ColonyPluginConfig::default());
+        live.advance(1);
+        let now_tick = live.world().resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+
+        let mut log = ArrivalLog::new();
+        for i in 0..20 {
+            log.record(&job(100 + i, 16 * (5 + i)), now_tick - 50 + i);
+        }
+        (live, log)
+    }
+
+    #[test]
+    fn test_sandbox_replays_each_policy() {
+        let (live, log) = live_world();
+        let request = SandboxRequest { ticks: Some(60), policies: None };
+        let result = run_scheduler_sandbox(&ShadowSeed::capture(live.world()).unwrap(), &log, &request);
+        assert_eq!(result.arrivals_replayed, 20);
+        assert_eq!(result.outcomes.len(), SchedPolicy::ALL.len());
+        assert!(result.outcomes[0].is_live_policy);
+        for outcome in &result.outcomes {
+            assert_eq!(outcome.jobs_arrived, 20);
+            assert!(outcome.jobs_dispatched + outcome.jobs_pending <= 20);
+            assert!((0.0..=1.0).contains(&outcome.deadline_hit_rate));
+        }
+        assert!(result.best_policy.is_some());
+    }
+
+    #[test]
+    fn test_sandbox_runs_on_the_live_workers() {
+        let (mut live, log) = live_world();
+        let mut workers = live.world_mut().query_filtered::<Entity, With<Worker>>();
+        let workers: Vec<Entity> = workers.iter(live.world()).collect();
+        for worker in workers {
+            live.world_mut().despawn(worker);
+        }
+
+        let request = SandboxRequest { ticks: Some(60), policies: Some(vec![SchedPolicy::Fcfs]) };
+        let result = run_scheduler_sandbox(&ShadowSeed::capture(live.world()).unwrap(), &log, &request);
+        // With nobody to run them, no replayed job leaves the queue
+        assert_eq!(result.outcomes[0].jobs_arrived, 20);
+        assert_eq!(result.outcomes[0].jobs_dispatched, 0);
+    }
+}
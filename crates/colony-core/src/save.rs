@@ -1,5 +1,6 @@
 use serde::{Serialize, Deserialize};
 use serde_json;
+use super::SaveProtection;
 // HashMap import removed - not used in this file
 
 #[derive(Serialize, Deserialize)]
@@ -15,6 +16,13 @@ pub struct SaveFileV1 {
     pub replay_log: super::session::ReplayLog,
     pub kpis: KpiSummary,
     pub timestamp: u64,
+    #[cfg(feature = "native")]
+    #[serde(default)]
+    pub lua_coroutines: Vec<super::CoroutineSnapshot>, // restored with LuaHost::restore_coroutines
+    #[serde(default)]
+    pub job_ids: super::JobIdAllocator, // older saves start counting from 1 again
+    #[serde(default)]
+    pub rng_audit: super::RngAudit, // draws so far; a loaded save keeps counting from here
 }
 
 #[derive(Serialize, Deserialize)]
@@ -27,6 +35,8 @@ pub struct ColonyState {
     pub tunables: super::ResourceTunables,
     pub corruption_tun: super::CorruptionTunables,
     pub seed: u64,
+    #[serde(default)]
+    pub seeds: super::SeedOverrides,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -62,6 +72,7 @@ impl SaveFileV1 {
                 meters: colony.meters.clone(),
                 tunables: colony.tunables.clone(),
                 corruption_tun: colony.corruption_tun.clone(),
+                seeds: colony.seeds.clone(),
                 seed: colony.seed,
             },
             research_state: research_state.clone(),
@@ -72,8 +83,28 @@ impl SaveFileV1 {
             replay_log: replay_log.clone(),
             kpis: kpi_summary,
             timestamp: chrono::Utc::now().timestamp() as u64,
+            #[cfg(feature = "native")]
+            lua_coroutines: Vec::new(),
+            job_ids: super::JobIdAllocator::new(),
+            rng_audit: super::RngAudit::new(),
         }
     }
+
+    pub fn with_job_ids(mut self, job_ids: &super::JobIdAllocator) -> Self {
+        self.job_ids = job_ids.clone();
+        self
+    }
+
+    pub fn with_rng_audit(mut self, rng_audit: &super::RngAudit) -> Self {
+        self.rng_audit = rng_audit.clone();
+        self
+    }
+
+    #[cfg(feature = "native")]
+    pub fn with_lua_coroutines(mut self, coroutines: Vec<super::CoroutineSnapshot>) -> Self {
+        self.lua_coroutines = coroutines;
+        self
+    }
 }
 
 pub fn migrate_any_to_latest(bytes: &[u8]) -> anyhow::Result<SaveFileV1> {
@@ -103,19 +134,24 @@ pub fn migrate_any_to_latest(bytes: &[u8]) -> anyhow::Result<SaveFileV1> {
     Err(anyhow::anyhow!("Invalid save file format"))
 }
 
-pub fn save_to_file(
+/// Write a save, sealed according to `protection`
+pub fn save_to_file_with(
     save_data: &SaveFileV1,
     file_path: &str,
+    protection: &SaveProtection,
 ) -> anyhow::Result<()> {
     let json = serde_json::to_string_pretty(save_data)?;
-    std::fs::write(file_path, json)?;
+    let stats = protection.write(std::path::Path::new(file_path), json.as_bytes())?;
+    println!("Saved {}: {}", file_path, stats.line());
     Ok(())
 }
 
-pub fn load_from_file(
+/// Read a save, rejecting it if it fails `protection`'s integrity check
+pub fn load_from_file_with(
     file_path: &str,
+    protection: &SaveProtection,
 ) -> anyhow::Result<SaveFileV1> {
-    let bytes = std::fs::read(file_path)?;
+    let bytes = protection.read(std::path::Path::new(file_path))?;
     migrate_any_to_latest(&bytes)
 }
 
@@ -139,9 +175,10 @@ pub fn get_save_slots() -> anyhow::Result<Vec<String>> {
     Ok(slots)
 }
 
-pub fn save_to_slot(
+pub fn save_to_slot_with(
     save_data: &SaveFileV1,
     slot_name: &str,
+    protection: &SaveProtection,
 ) -> anyhow::Result<()> {
     let save_dir = "saves";
     if !std::path::Path::new(save_dir).exists() {
@@ -149,14 +186,15 @@ pub fn save_to_slot(
     }
 
     let file_path = format!("{}/{}.json", save_dir, slot_name);
-    save_to_file(save_data, &file_path)
+    save_to_file_with(save_data, &file_path, protection)
 }
 
-pub fn load_from_slot(
+pub fn load_from_slot_with(
     slot_name: &str,
+    protection: &SaveProtection,
 ) -> anyhow::Result<SaveFileV1> {
     let file_path = format!("saves/{}.json", slot_name);
-    load_from_file(&file_path)
+    load_from_file_with(&file_path, protection)
 }
 
 pub fn delete_slot(
@@ -169,10 +207,11 @@ pub fn delete_slot(
     Ok(())
 }
 
-pub fn get_slot_info(
+pub fn get_slot_info_with(
     slot_name: &str,
+    protection: &SaveProtection,
 ) -> anyhow::Result<SlotInfo> {
-    let save_data = load_from_slot(slot_name)?;
+    let save_data = load_from_slot_with(slot_name, protection)?;
     Ok(SlotInfo {
         name: slot_name.to_string(),
         scenario: save_data.game_setup.scenario.name,
@@ -182,6 +221,7 @@ pub fn get_slot_info(
         doom: save_data.winloss.doom,
         score: save_data.winloss.score,
         achieved_days: save_data.winloss.achieved_days,
+        meta: save_data.session_ctl.meta,
     })
 }
 
@@ -195,6 +235,8 @@ pub struct SlotInfo {
     pub doom: bool,
     pub score: i64,
     pub achieved_days: u32,
+    #[serde(default)]
+    pub meta: super::session::SessionMeta, // name, description and tags from the saved session
 }
 
 impl SlotInfo {
@@ -233,6 +275,11 @@ mod tests {
                 start_tunables: None,
                 enabled_pipelines: None,
                 enabled_events: None,
+                seeds: Default::default(),
+                gpu: true,
+                io_channels: Vec::new(),
+                class_reservations: Vec::new(),
+                op_limits: Vec::new(),
             }
         );
 
@@ -244,6 +291,7 @@ mod tests {
             meters: super::super::GlobalMeters::new(),
             tunables: super::super::ResourceTunables::default(),
             corruption_tun: super::super::CorruptionTunables::default(),
+            seeds: Default::default(),
             seed: 42,
         };
 
@@ -293,6 +341,11 @@ mod tests {
                 start_tunables: None,
                 enabled_pipelines: None,
                 enabled_events: None,
+                seeds: Default::default(),
+                gpu: true,
+                io_channels: Vec::new(),
+                class_reservations: Vec::new(),
+                op_limits: Vec::new(),
             }
         );
 
@@ -304,6 +357,7 @@ mod tests {
             meters: super::super::GlobalMeters::new(),
             tunables: super::super::ResourceTunables::default(),
             corruption_tun: super::super::CorruptionTunables::default(),
+            seeds: Default::default(),
             seed: 42,
         };
 
@@ -336,16 +390,81 @@ mod tests {
 
         // Test save/load cycle
         let slot_name = "test_slot";
-        save_to_slot(&save_data, slot_name).unwrap();
-        let loaded_data = load_from_slot(slot_name).unwrap();
+        let protection = SaveProtection::off();
+        save_to_slot_with(&save_data, slot_name, &protection).unwrap();
+        let loaded_data = load_from_slot_with(slot_name, &protection).unwrap();
         assert_eq!(loaded_data.version, save_data.version);
 
         // Test slot info
-        let slot_info = get_slot_info(slot_name).unwrap();
+        let slot_info = get_slot_info_with(slot_name, &protection).unwrap();
         assert_eq!(slot_info.name, slot_name);
         assert_eq!(slot_info.scenario, "Test Scenario");
 
         // Clean up
         delete_slot(slot_name).unwrap();
     }
+
+    #[test]
+    fn test_protected_save_round_trip() {
+        let game_setup = super::super::game_config::GameSetup::new(
+            super::super::game_config::Scenario {
+                id: "test".to_string(),
+                name: "Test Scenario".to_string(),
+                description: "Test".to_string(),
+                seed: 42,
+                difficulty: super::super::game_config::Difficulty::default(),
+                victory: super::super::game_config::VictoryRules::default(),
+                loss: super::super::game_config::LossRules::default(),
+                start_tunables: None,
+                enabled_pipelines: None,
+                enabled_events: None,
+                seeds: Default::default(),
+                gpu: true,
+                io_channels: Vec::new(),
+                class_reservations: Vec::new(),
+                op_limits: Vec::new(),
+            }
+        );
+        let colony = super::super::Colony {
+            power_cap_kw: 1000.0,
+            bandwidth_total_gbps: 32.0,
+            corruption_field: 0.1,
+            target_uptime_days: 365,
+            meters: super::super::GlobalMeters::new(),
+            tunables: super::super::ResourceTunables::default(),
+            corruption_tun: super::super::CorruptionTunables::default(),
+            seeds: Default::default(),
+            seed: 42,
+        };
+        let kpi_summary = KpiSummary {
+            bandwidth_util_history: vec![],
+            corruption_field_history: vec![],
+            power_draw_history: vec![],
+            heat_levels_history: vec![],
+            deadline_hit_rates: vec![],
+            black_swan_events: vec![],
+        };
+        let save_data = SaveFileV1::new(
+            game_setup,
+            &colony,
+            &super::super::ResearchState::new(),
+            &super::super::BlackSwanIndex::new(),
+            &super::super::Debts::new(),
+            &super::super::victory::WinLossState::new(),
+            &super::super::session::SessionCtl::new(),
+            &super::super::session::ReplayLog::new(),
+            kpi_summary,
+        ).with_job_ids(&super::super::JobIdAllocator::starting_at(500));
+
+        let protection = SaveProtection::new(super::super::ProtectionMode::Encrypted, "test-key");
+        let path = std::env::temp_dir().join(format!("colony_protected_save_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        save_to_file_with(&save_data, path, &protection).unwrap();
+
+        let loaded = load_from_file_with(path, &protection).unwrap();
+        assert_eq!(loaded.colony_state.seed, 42);
+        assert_eq!(loaded.job_ids.peek(), 500);
+        assert!(load_from_file_with(path, &SaveProtection::off()).is_err());
+        std::fs::remove_file(path).ok();
+    }
 }
@@ -0,0 +1,130 @@
+use serde::{Serialize, Deserialize};
+use std::io::{BufRead, BufReader, Read};
+use anyhow::Result;
+
+/// Frame magic zstd writes first; files without it are read as they are
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+pub const MIN_ZSTD_LEVEL: i32 = 1;
+pub const MAX_ZSTD_LEVEL: i32 = 22;
+
+/// `[save_compression]` table in the game config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveCompressionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_level")]
+    pub level: i32, // zstd level 1..=22; 3 is fast, 19+ is slow but small
+}
+
+fn default_level() -> i32 {
+    3
+}
+
+impl Default for SaveCompressionConfig {
+    fn default() -> Self {
+        Self { enabled: false, level: default_level() }
+    }
+}
+
+/// zstd compression for saves and replay logs, applied before protection seals the file.
+/// Loading detects compressed files by their magic, so old uncompressed saves still load.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SaveCompression {
+    pub level: Option<i32>, // None writes uncompressed
+}
+
+impl SaveCompression {
+    pub fn off() -> Self {
+        Self::default()
+    }
+
+    pub fn zstd(level: i32) -> Result<Self> {
+        if !(MIN_ZSTD_LEVEL..=MAX_ZSTD_LEVEL).contains(&level) {
+            return Err(anyhow::anyhow!("zstd level must be {}..={}, got {}", MIN_ZSTD_LEVEL, MAX_ZSTD_LEVEL, level));
+        }
+        Ok(Self { level: Some(level) })
+    }
+
+    pub fn from_config(config: &SaveCompressionConfig) -> Result<Self> {
+        if config.enabled {
+            Self::zstd(config.level)
+        } else {
+            Ok(Self::off())
+        }
+    }
+
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self.level {
+            #[cfg(feature = "native")]
+            Some(level) => Ok(zstd::stream::encode_all(data, level)?),
+            #[cfg(not(feature = "native"))]
+            Some(_) => Err(anyhow::anyhow!("save compression needs the native feature")),
+            None => Ok(data.to_vec()),
+        }
+    }
+}
+
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ZSTD_MAGIC)
+}
+
+/// Read everything from `reader`, decompressing as it streams if it holds a zstd frame
+pub fn read_maybe_compressed(reader: impl Read) -> Result<Vec<u8>> {
+    let mut reader = BufReader::new(reader);
+    let compressed = is_compressed(reader.fill_buf()?);
+    let mut out = Vec::new();
+    if compressed {
+        #[cfg(feature = "native")]
+        zstd::stream::read::Decoder::with_buffer(reader)?.read_to_end(&mut out)?;
+        #[cfg(not(feature = "native"))]
+        return Err(anyhow::anyhow!("compressed saves need the native feature"));
+    } else {
+        reader.read_to_end(&mut out)?;
+    }
+    Ok(out)
+}
+
+/// What a save or replay write cost, reported after each one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SaveIoStats {
+    pub raw_bytes: usize,
+    pub stored_bytes: usize,
+    pub compressed: bool,
+    pub millis: f64,
+}
+
+impl SaveIoStats {
+    pub fn ratio(&self) -> f64 {
+        if self.raw_bytes == 0 {
+            1.0
+        } else {
+            self.stored_bytes as f64 / self.raw_bytes as f64
+        }
+    }
+
+    pub fn line(&self) -> String {
+        if self.compressed {
+            format!("{} -> {} bytes ({:.0}%) in {:.1} ms", self.raw_bytes, self.stored_bytes, self.ratio() * 100.0, self.millis)
+        } else {
+            format!("{} bytes in {:.1} ms", self.stored_bytes, self.millis)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_round_trips_and_reads_plain_data() {
+        let data = br#"{"bandwidth_util_history":[0.5,0.5,0.5,0.5,0.5,0.5,0.5,0.5,0.5,0.5]}"#.repeat(50);
+        let compression = SaveCompression::zstd(19).unwrap();
+        let packed = compression.compress(&data).unwrap();
+        assert!(is_compressed(&packed) && packed.len() < data.len() / 10);
+        assert_eq!(read_maybe_compressed(&packed[..]).unwrap(), data);
+        assert_eq!(read_maybe_compressed(&data[..]).unwrap(), data);
+
+        assert!(SaveCompression::zstd(0).is_err());
+        assert_eq!(SaveCompression::from_config(&SaveCompressionConfig::default()).unwrap(), SaveCompression::off());
+    }
+}
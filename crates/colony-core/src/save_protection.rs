@@ -0,0 +1,356 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use aes_gcm::aead::Aead;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use crate::{GameConfig, SaveCompression, SaveIoStats, read_maybe_compressed};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const MAGIC: &[u8; 8] = b"CLNYSAVE";
+const MAC_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+/// HKDF salt; fixed, since keys are per server rather than per file
+const HKDF_SALT: &[u8] = b"colony-save-hkdf-v1";
+
+pub const DEFAULT_SAVE_KEY_ENV: &str = "COLONY_SAVE_KEY";
+
+/// How save slots and replay logs are written to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtectionMode {
+    #[default]
+    Off,        // plain JSON, as before
+    Integrity,  // plain JSON with an HMAC-SHA256 trailer
+    Encrypted,  // AES-256-GCM, then HMAC-SHA256 over the envelope
+}
+
+impl ProtectionMode {
+    fn tag(&self) -> u8 {
+        match self {
+            ProtectionMode::Off => 0,
+            ProtectionMode::Integrity => 1,
+            ProtectionMode::Encrypted => 2,
+        }
+    }
+}
+
+/// Where the protection secret comes from. Use a random key rather than a passphrase; HKDF
+/// expands it to two 256-bit keys but does not slow down guessing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum SaveKeySource {
+    Env { var: String },
+    File { path: String },
+    Inline { secret: String }, // for tests and throwaway servers; prefer env or keychain
+    Keychain { service: String, account: String }, // needs the `keychain` feature
+}
+
+impl Default for SaveKeySource {
+    fn default() -> Self {
+        Self::Env { var: DEFAULT_SAVE_KEY_ENV.to_string() }
+    }
+}
+
+impl SaveKeySource {
+    pub fn resolve(&self) -> Result<String> {
+        let secret = match self {
+            SaveKeySource::Env { var } => std::env::var(var)
+                .map_err(|_| anyhow::anyhow!("Save key environment variable {} is not set", var))?,
+            SaveKeySource::File { path } => std::fs::read_to_string(path)?.trim().to_string(),
+            SaveKeySource::Inline { secret } => secret.clone(),
+            SaveKeySource::Keychain { service, account } => read_keychain(service, account)?,
+        };
+        if secret.is_empty() {
+            return Err(anyhow::anyhow!("Save key is empty"));
+        }
+        Ok(secret)
+    }
+}
+
+#[cfg(feature = "keychain")]
+fn read_keychain(service: &str, account: &str) -> Result<String> {
+    Ok(keyring::Entry::new(service, account)?.get_password()?)
+}
+
+#[cfg(not(feature = "keychain"))]
+fn read_keychain(_service: &str, _account: &str) -> Result<String> {
+    Err(anyhow::anyhow!("Keychain save keys need colony-core built with the `keychain` feature"))
+}
+
+/// `[save_protection]` table in the game config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SaveProtectionConfig {
+    #[serde(default)]
+    pub mode: ProtectionMode,
+    #[serde(default)]
+    pub key: SaveKeySource,
+}
+
+#[derive(Clone)]
+struct SaveKeys {
+    enc: [u8; 32],
+    mac: [u8; 32],
+}
+
+impl SaveKeys {
+    fn derive(secret: &str) -> Self {
+        Self {
+            enc: hkdf_sha256(HKDF_SALT, secret.as_bytes(), b"colony-save-enc"),
+            mac: hkdf_sha256(HKDF_SALT, secret.as_bytes(), b"colony-save-mac"),
+        }
+    }
+}
+
+/// HKDF-SHA256 (RFC 5869) extract-then-expand, for a single 32-byte output block
+fn hkdf_sha256(salt: &[u8], ikm: &[u8], info: &[u8]) -> [u8; 32] {
+    let mut extract = <HmacSha256 as Mac>::new_from_slice(salt).expect("HMAC accepts keys of any length");
+    extract.update(ikm);
+    let prk = extract.finalize().into_bytes();
+    let mut expand = <HmacSha256 as Mac>::new_from_slice(&prk).expect("HMAC accepts keys of any length");
+    expand.update(info);
+    expand.update(&[1]);
+    expand.finalize().into_bytes().into()
+}
+
+/// Seals and opens save files. With protection on, unprotected or tampered files are rejected.
+/// Compression, when set, is applied before sealing.
+#[derive(Resource, Clone, Default)]
+pub struct SaveProtection {
+    pub mode: ProtectionMode,
+    keys: Option<SaveKeys>,
+    pub compression: SaveCompression,
+}
+
+impl SaveProtection {
+    pub fn off() -> Self {
+        Self::default()
+    }
+
+    pub fn new(mode: ProtectionMode, secret: &str) -> Self {
+        Self {
+            mode,
+            keys: Some(SaveKeys::derive(secret)),
+            compression: SaveCompression::off(),
+        }
+    }
+
+    pub fn with_compression(mut self, compression: SaveCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn from_config(config: &SaveProtectionConfig) -> Result<Self> {
+        if config.mode == ProtectionMode::Off {
+            return Ok(Self::off());
+        }
+        Ok(Self::new(config.mode, &config.key.resolve()?))
+    }
+
+    /// Protection and compression as a game config's `[save_protection]` and `[save_compression]` set them
+    pub fn from_game_config(config: &GameConfig) -> Result<Self> {
+        Ok(Self::from_config(&config.save_protection)?
+            .with_compression(SaveCompression::from_config(&config.save_compression)?))
+    }
+
+    /// `from_game_config` for the config file at `path`, as apps taking `--game-config` load it
+    pub fn from_game_config_file(path: &str) -> Result<Self> {
+        Self::from_game_config(&crate::load_config(path)?)
+    }
+
+    fn mac(&self, keys: &SaveKeys, data: &[u8]) -> Result<HmacSha256> {
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(&keys.mac)
+            .map_err(|e| anyhow::anyhow!("Invalid MAC key: {}", e))?;
+        mac.update(data);
+        Ok(mac)
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let keys = match (&self.mode, &self.keys) {
+            (ProtectionMode::Off, _) => return Ok(plaintext.to_vec()),
+            (_, Some(keys)) => keys,
+            (_, None) => return Err(anyhow::anyhow!("Save protection is on but no key is loaded")),
+        };
+
+        let mut out = MAGIC.to_vec();
+        out.push(self.mode.tag());
+        if self.mode == ProtectionMode::Encrypted {
+            let nonce_bytes = rand::random::<[u8; NONCE_LEN]>();
+            let cipher = Aes256Gcm::new_from_slice(&keys.enc)
+                .map_err(|e| anyhow::anyhow!("Invalid encryption key: {}", e))?;
+            let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce_bytes), plaintext)
+                .map_err(|_| anyhow::anyhow!("Save encryption failed"))?;
+            out.extend_from_slice(&nonce_bytes);
+            out.extend_from_slice(&ciphertext);
+        } else {
+            out.extend_from_slice(plaintext);
+        }
+        let tag = self.mac(keys, &out)?.finalize().into_bytes();
+        out.extend_from_slice(&tag);
+        Ok(out)
+    }
+
+    pub fn open(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        if !is_sealed(bytes) {
+            if self.mode != ProtectionMode::Off {
+                return Err(anyhow::anyhow!("Save file is not protected; refusing to load it"));
+            }
+            return Ok(bytes.to_vec());
+        }
+
+        let keys = self.keys.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Save file is protected but no save key is configured"))?;
+        if bytes.len() < MAGIC.len() + 1 + MAC_LEN {
+            return Err(anyhow::anyhow!("Protected save file is truncated"));
+        }
+        let (body, tag) = bytes.split_at(bytes.len() - MAC_LEN);
+        self.mac(keys, body)?.verify_slice(tag)
+            .map_err(|_| anyhow::anyhow!("Save file integrity check failed (tampered or wrong key)"))?;
+
+        let payload = &body[MAGIC.len() + 1..];
+        match body[MAGIC.len()] {
+            1 => Ok(payload.to_vec()),
+            2 => {
+                if payload.len() < NONCE_LEN {
+                    return Err(anyhow::anyhow!("Protected save file is truncated"));
+                }
+                let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+                let cipher = Aes256Gcm::new_from_slice(&keys.enc)
+                    .map_err(|e| anyhow::anyhow!("Invalid encryption key: {}", e))?;
+                cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+                    .map_err(|_| anyhow::anyhow!("Save file could not be decrypted"))
+            }
+            other => Err(anyhow::anyhow!("Unknown save protection mode {}", other)),
+        }
+    }
+
+    /// Compress, seal and write; returns the sizes and time taken
+    pub fn write(&self, path: &std::path::Path, plaintext: &[u8]) -> Result<SaveIoStats> {
+        let started = bevy::platform::time::Instant::now();
+        let sealed = self.seal(&self.compression.compress(plaintext)?)?;
+        std::fs::write(path, &sealed)?;
+        Ok(SaveIoStats {
+            raw_bytes: plaintext.len(),
+            stored_bytes: sealed.len(),
+            compressed: self.compression.level.is_some(),
+            millis: started.elapsed().as_secs_f64() * 1000.0,
+        })
+    }
+
+    /// Read, open and decompress; unsealed files are decompressed as they stream off disk
+    pub fn read(&self, path: &std::path::Path) -> Result<Vec<u8>> {
+        let mut file = std::io::BufReader::new(std::fs::File::open(path)?);
+        if is_sealed(std::io::BufRead::fill_buf(&mut file)?) || self.mode != ProtectionMode::Off {
+            let mut bytes = Vec::new();
+            std::io::Read::read_to_end(&mut file, &mut bytes)?;
+            return read_maybe_compressed(&self.open(&bytes)?[..]);
+        }
+        read_maybe_compressed(file)
+    }
+}
+
+pub fn is_sealed(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAVE: &[u8] = br#"{"version":1,"score":42}"#;
+
+    #[test]
+    fn test_integrity_round_trip_and_tamper() {
+        let protection = SaveProtection::new(ProtectionMode::Integrity, "hunter2");
+        let mut sealed = protection.seal(SAVE).unwrap();
+        assert_eq!(protection.open(&sealed).unwrap(), SAVE);
+
+        // Bump the score in place
+        let pos = sealed.windows(2).position(|w| w == b"42").unwrap();
+        sealed[pos] = b'9';
+        assert!(protection.open(&sealed).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_hides_contents() {
+        let protection = SaveProtection::new(ProtectionMode::Encrypted, "hunter2");
+        let sealed = protection.seal(SAVE).unwrap();
+        assert!(!sealed.windows(7).any(|w| w == b"version"));
+        assert_eq!(protection.open(&sealed).unwrap(), SAVE);
+
+        let wrong_key = SaveProtection::new(ProtectionMode::Encrypted, "hunter3");
+        assert!(wrong_key.open(&sealed).is_err());
+    }
+
+    #[test]
+    fn test_unprotected_saves() {
+        assert_eq!(SaveProtection::off().open(SAVE).unwrap(), SAVE);
+        assert!(SaveProtection::new(ProtectionMode::Integrity, "k").open(SAVE).is_err());
+
+        // A protected save can't be loaded without the key
+        let sealed = SaveProtection::new(ProtectionMode::Integrity, "k").seal(SAVE).unwrap();
+        assert!(SaveProtection::off().open(&sealed).is_err());
+    }
+
+    #[test]
+    fn test_config_parsing() {
+        let config: SaveProtectionConfig = toml::from_str(r#"
+            mode = "encrypted"
+            key = { source = "inline", secret = "s3cret" }
+        "#).unwrap();
+        let protection = SaveProtection::from_config(&config).unwrap();
+        assert_eq!(protection.mode, ProtectionMode::Encrypted);
+
+        let missing: SaveProtectionConfig = toml::from_str(r#"
+            mode = "integrity"
+            key = { source = "env", var = "COLONY_TEST_KEY_THAT_IS_NOT_SET" }
+        "#).unwrap();
+        assert!(SaveProtection::from_config(&missing).is_err());
+    }
+
+    #[test]
+    fn test_hkdf_matches_rfc5869() {
+        // Test case 1; the first 32 bytes of OKM are the first expand block
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+        let okm = hkdf_sha256(&salt, &[0x0b; 22], &info);
+        assert_eq!(hex::encode(okm), "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf");
+
+        let keys = SaveKeys::derive("k");
+        assert_ne!(keys.enc, keys.mac);
+    }
+
+    #[test]
+    fn test_game_config_sets_protection_and_compression() {
+        let mut config = GameConfig::default();
+        config.save_protection = toml::from_str(r#"
+            mode = "integrity"
+            key = { source = "inline", secret = "s3cret" }
+        "#).unwrap();
+        config.save_compression.enabled = true;
+        config.save_compression.level = 5;
+        let protection = SaveProtection::from_game_config(&config).unwrap();
+        assert_eq!(protection.mode, ProtectionMode::Integrity);
+        assert_eq!(protection.compression, SaveCompression::zstd(5).unwrap());
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_compressed_files_round_trip() {
+        let dir = std::env::temp_dir();
+        let data = SAVE.repeat(200);
+        for (name, protection) in [
+            ("plain", SaveProtection::off()),
+            ("sealed", SaveProtection::new(ProtectionMode::Encrypted, "k")),
+        ] {
+            let path = dir.join(format!("colony_compressed_{}_{}.json", name, std::process::id()));
+            let stats = protection.clone().with_compression(SaveCompression::zstd(3).unwrap()).write(&path, &data).unwrap();
+            assert!(stats.compressed && stats.stored_bytes < stats.raw_bytes / 5);
+            // Loading doesn't need to know the file was compressed
+            assert_eq!(protection.read(&path).unwrap(), data);
+            std::fs::remove_file(&path).ok();
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use rand::Rng;
+use rand::seq::SliceRandom;
+use anyhow::Result;
+use crate::{
+    ContentPack, Difficulty, LossRules, RunCommand, Scenario, TimedCommand, VictoryRules, WorkyardKind,
+    requires_gpu, tick_rng,
+};
+
+/// Ids of generated scenarios start with this, followed by the seed and knobs, see `GenKnobs::scenario_id`
+pub const GENERATED_PREFIX: &str = "gen";
+
+/// One yard of a colony's starting layout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct YardSpec {
+    pub kind: WorkyardKind,
+    pub slots: u32,
+    pub heat_cap: f32,
+    pub power_draw_kw: f32,
+    pub bandwidth_share: f32,
+    pub isolation_domain: u32,
+}
+
+/// Yards spawned at startup; when absent the colony gets the stock layout
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct YardLayout(pub Vec<YardSpec>);
+
+impl YardLayout {
+    /// CPU array, GPU farm (when `gpu`) and storage array
+    pub fn stock(gpu: bool) -> Self {
+        let mut yards = vec![YardSpec {
+            kind: WorkyardKind::CpuArray,
+            slots: 4,
+            heat_cap: 100.0,
+            power_draw_kw: 200.0,
+            bandwidth_share: 0.3,
+            isolation_domain: 0,
+        }];
+        if gpu {
+            yards.push(YardSpec {
+                kind: WorkyardKind::GpuFarm,
+                slots: 2,
+                heat_cap: 85.0,
+                power_draw_kw: 300.0,
+                bandwidth_share: 0.4,
+                isolation_domain: 1,
+            });
+        }
+        yards.push(YardSpec {
+            kind: WorkyardKind::StorageArray,
+            slots: 2,
+            heat_cap: 70.0,
+            power_draw_kw: 80.0,
+            bandwidth_share: 0.2,
+            isolation_domain: 2,
+        });
+        Self(yards)
+    }
+}
+
+/// Difficulty knobs for the generator, each 0 (gentle) ..= 1 (brutal)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenKnobs {
+    pub load: f32,       // traffic streams, arrival rates and burst sizes
+    pub hazard: f32,     // fault rates and the Black Swan pool
+    pub strictness: f32, // victory and loss thresholds
+    pub size: f32,       // yard count and slots
+    #[serde(default)]
+    pub gpu: Option<bool>, // None leaves it to the seed
+}
+
+impl Default for GenKnobs {
+    fn default() -> Self {
+        Self { load: 0.5, hazard: 0.5, strictness: 0.5, size: 0.5, gpu: None }
+    }
+}
+
+impl GenKnobs {
+    pub fn validate(&self) -> Result<()> {
+        for (name, value) in [("load", self.load), ("hazard", self.hazard), ("strictness", self.strictness), ("size", self.size)] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(anyhow::anyhow!("{} {} is outside 0..=1", name, value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Knobs rounded to whole percent, which is what a scenario id can carry
+    fn quantized(&self) -> Self {
+        let q = |v: f32| (v * 100.0).round() / 100.0;
+        Self { load: q(self.load), hazard: q(self.hazard), strictness: q(self.strictness), size: q(self.size), gpu: self.gpu }
+    }
+
+    /// e.g. `gen-7-l50-h30-s80-z50` or, with the GPU fixed, `gen-7-l50-h30-s80-z50-cpu`
+    pub fn scenario_id(&self, seed: u64) -> String {
+        let pct = |v: f32| (v * 100.0).round() as u32;
+        let gpu = match self.gpu {
+            Some(true) => "-gpu",
+            Some(false) => "-cpu",
+            None => "",
+        };
+        format!("{}-{}-l{}-h{}-s{}-z{}{}", GENERATED_PREFIX, seed, pct(self.load), pct(self.hazard), pct(self.strictness), pct(self.size), gpu)
+    }
+
+    /// Seed and knobs back from a generated scenario id; None for any other id
+    pub fn parse_id(id: &str) -> Option<(u64, GenKnobs)> {
+        let mut parts = id.strip_prefix(GENERATED_PREFIX)?.strip_prefix('-')?.split('-');
+        let seed = parts.next()?.parse().ok()?;
+        let mut knob = |tag: char| -> Option<f32> {
+            let pct: u32 = parts.next()?.strip_prefix(tag)?.parse().ok()?;
+            (pct <= 100).then_some(pct as f32 / 100.0)
+        };
+        let (load, hazard, strictness, size) = (knob('l')?, knob('h')?, knob('s')?, knob('z')?);
+        let gpu = match parts.next() {
+            None => None,
+            Some("gpu") => Some(true),
+            Some("cpu") => Some(false),
+            Some(_) => return None,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some((seed, GenKnobs { load, hazard, strictness, size, gpu }))
+    }
+}
+
+/// A recurring burst of jobs from one pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrafficStream {
+    pub pipeline: String,
+    pub start_tick: u64,
+    pub every_ticks: u64,
+    pub count: u32,
+    pub payload_sz: usize,
+    pub deadline_ms: u64,
+}
+
+impl TrafficStream {
+    pub fn command(&self) -> TimedCommand {
+        TimedCommand {
+            at_tick: self.start_tick,
+            repeat_every: Some(self.every_ticks),
+            command: RunCommand::EnqueuePipeline {
+                pipeline: self.pipeline.clone(),
+                count: self.count,
+                payload_sz: self.payload_sz,
+                deadline_ms: self.deadline_ms,
+                tenant: None,
+            },
+        }
+    }
+}
+
+/// A procedurally generated scenario with its yard layout and traffic mix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedScenario {
+    pub knobs: GenKnobs,
+    pub scenario: Scenario,
+    pub yards: YardLayout,
+    pub traffic: Vec<TrafficStream>,
+}
+
+impl GeneratedScenario {
+    /// The traffic mix as run spec commands
+    pub fn commands(&self) -> Vec<TimedCommand> {
+        self.traffic.iter().map(TrafficStream::command).collect()
+    }
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+/// Scale `value` by up to +-`spread` at random
+fn jitter(rng: &mut impl Rng, value: f32, spread: f32) -> f32 {
+    value * (1.0 + rng.gen_range(-spread..=spread))
+}
+
+/// Compose a scenario from `seed` and difficulty knobs. The same seed and knobs always give the
+/// same scenario, and its id (see `GenKnobs::scenario_id`) regenerates it wherever a scenario id is taken.
+pub fn generate_scenario(seed: u64, knobs: &GenKnobs) -> Result<GeneratedScenario> {
+    knobs.validate()?;
+    let knobs = knobs.quantized();
+    let mut rng = tick_rng(seed, 0);
+    let content = ContentPack::vanilla();
+    let gpu = knobs.gpu.unwrap_or_else(|| rng.gen_bool(0.75));
+    let mean = (knobs.load + knobs.hazard + knobs.strictness) / 3.0;
+
+    let difficulty = Difficulty {
+        name: match mean {
+            m if m < 0.33 => "Chill",
+            m if m < 0.66 => "Nominal",
+            _ => "Abyssal",
+        }.to_string(),
+        power_cap_mult: jitter(&mut rng, lerp(1.3, 0.8, knobs.load), 0.05),
+        heat_cap_mult: jitter(&mut rng, lerp(1.1, 0.9, knobs.hazard), 0.05),
+        bw_total_mult: jitter(&mut rng, lerp(1.2, 0.8, knobs.load), 0.05),
+        fault_rate_mult: jitter(&mut rng, lerp(0.5, 2.0, knobs.hazard), 0.1),
+        black_swan_weight_mult: jitter(&mut rng, lerp(0.3, 2.5, knobs.hazard), 0.1),
+        research_rate_mult: lerp(1.5, 0.7, knobs.strictness),
+    };
+
+    let target_uptime_days = lerp(30.0, 365.0, knobs.strictness).round() as u32;
+    let victory = VictoryRules {
+        target_uptime_days,
+        min_deadline_hit_pct: lerp(95.0, 99.8, knobs.strictness),
+        max_corruption_field: lerp(0.5, 0.25, knobs.strictness),
+        observation_window_days: lerp(3.0, 14.0, knobs.strictness).round() as u32,
+        // Some strict colonies also have to stay green
+        max_carbon_kg_per_period: rng.gen_bool((knobs.strictness * 0.3) as f64)
+            .then(|| jitter(&mut rng, lerp(48_000.0, 32_000.0, knobs.strictness), 0.1).round()),
+    };
+    let loss = LossRules {
+        hard_power_deficit_ticks: lerp(2000.0, 500.0, knobs.strictness) as u32,
+        sustained_deadline_miss_pct: lerp(10.0, 2.0, knobs.strictness),
+        max_sticky_workers: lerp(5.0, 2.0, knobs.strictness).round() as u32,
+        black_swan_chain_len: lerp(5.0, 2.0, knobs.hazard).round() as u32,
+        time_limit_days: rng.gen_bool((knobs.strictness * 0.5) as f64).then_some(target_uptime_days + 20),
+    };
+
+    // Yards: the stock layout grown by `size`, plus a signal hub in bigger colonies
+    let mut yards = YardLayout::stock(gpu).0;
+    for yard in &mut yards {
+        yard.slots = jitter(&mut rng, yard.slots as f32 * lerp(1.0, 3.0, knobs.size), 0.2).round().max(1.0) as u32;
+        yard.heat_cap *= difficulty.heat_cap_mult;
+        yard.power_draw_kw = jitter(&mut rng, yard.power_draw_kw * lerp(1.0, 2.0, knobs.size), 0.1).round();
+    }
+    let extra_cpu = (knobs.size * 2.0 + rng.gen_range(-0.5..0.5)).round().max(0.0) as usize;
+    for _ in 0..extra_cpu {
+        let mut cpu = yards[0].clone();
+        cpu.slots = rng.gen_range(2..=6);
+        yards.push(cpu);
+    }
+    if rng.gen_bool(knobs.size as f64) {
+        yards.push(YardSpec {
+            kind: WorkyardKind::SignalHub,
+            slots: rng.gen_range(2..=4),
+            heat_cap: 90.0 * difficulty.heat_cap_mult,
+            power_draw_kw: 120.0,
+            bandwidth_share: 0.2,
+            isolation_domain: 0,
+        });
+    }
+    for (i, yard) in yards.iter_mut().enumerate() {
+        yard.isolation_domain = i as u32;
+    }
+
+    // Traffic mix from the vanilla pipelines this colony can run
+    let mut pipelines: Vec<String> = content.pipelines.iter()
+        .filter(|p| gpu || p.to_pipeline().is_ok_and(|pipe| !requires_gpu(&pipe.ops)))
+        .map(|p| p.id.clone())
+        .collect();
+    pipelines.shuffle(&mut rng);
+    let streams = ((2.0 + knobs.load * 4.0).round() as usize).min(pipelines.len());
+    let traffic: Vec<TrafficStream> = pipelines.into_iter().take(streams)
+        .map(|pipeline| TrafficStream {
+            pipeline,
+            start_tick: rng.gen_range(0..120),
+            every_ticks: jitter(&mut rng, lerp(600.0, 60.0, knobs.load), 0.3).round().max(1.0) as u64,
+            count: rng.gen_range(1..=1 + (knobs.load * 8.0).round() as u32),
+            payload_sz: *[64, 512, 4096, 65536].choose(&mut rng).unwrap(),
+            deadline_ms: *[10, 50, 100, 250, 500].choose(&mut rng).unwrap(),
+        })
+        .collect();
+
+    // Black Swan pool: more of them as the hazard rises
+    let mut swans: Vec<String> = content.black_swans.iter().map(|b| b.id.clone()).collect();
+    swans.shuffle(&mut rng);
+    swans.truncate((knobs.hazard * swans.len() as f32).round() as usize);
+    swans.sort();
+
+    let id = knobs.scenario_id(seed);
+    Ok(GeneratedScenario {
+        knobs,
+        scenario: Scenario {
+            name: format!("Generated {} #{}", difficulty.name, seed),
+            description: format!(
+                "Procedural colony: {} yards, {} traffic streams, {} possible Black Swans.",
+                yards.len(), traffic.len(), swans.len()
+            ),
+            id,
+            seed,
+            difficulty,
+            victory,
+            loss,
+            start_tunables: None,
+            enabled_pipelines: Some(traffic.iter().map(|t| t.pipeline.clone()).collect()),
+            enabled_events: Some(swans),
+            seeds: Default::default(),
+            gpu,
+            io_channels: Vec::new(),
+            class_reservations: Vec::new(),
+            op_limits: Vec::new(),
+        },
+        yards: YardLayout(yards),
+        traffic,
+    })
+}
+
+/// Regenerate the scenario behind a generated id
+pub fn generated_scenario(id: &str) -> Option<Result<GeneratedScenario>> {
+    GenKnobs::parse_id(id).map(|(seed, knobs)| generate_scenario(seed, &knobs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_scenario() {
+        let knobs = GenKnobs { load: 0.7, ..GenKnobs::default() };
+        let a = generate_scenario(11, &knobs).unwrap();
+        let b = generate_scenario(11, &knobs).unwrap();
+        assert_eq!(serde_json::to_string(&a).unwrap(), serde_json::to_string(&b).unwrap());
+        let c = generate_scenario(12, &knobs).unwrap();
+        assert_ne!(serde_json::to_string(&a).unwrap(), serde_json::to_string(&c).unwrap());
+
+        let again = generated_scenario(&a.scenario.id).unwrap().unwrap();
+        assert_eq!(again.traffic, a.traffic);
+        assert_eq!(again.yards, a.yards);
+    }
+
+    #[test]
+    fn test_knobs_shape_the_scenario() {
+        let gentle = generate_scenario(3, &GenKnobs { load: 0.0, hazard: 0.0, strictness: 0.0, size: 0.0, gpu: Some(false) }).unwrap();
+        let brutal = generate_scenario(3, &GenKnobs { load: 1.0, hazard: 1.0, strictness: 1.0, size: 1.0, gpu: Some(true) }).unwrap();
+        assert_eq!(gentle.scenario.difficulty.name, "Chill");
+        assert_eq!(brutal.scenario.difficulty.name, "Abyssal");
+        assert!(gentle.scenario.enabled_events.as_ref().unwrap().is_empty());
+        assert!(brutal.scenario.enabled_events.as_ref().unwrap().len() > 3);
+        assert!(brutal.scenario.victory.min_deadline_hit_pct > gentle.scenario.victory.min_deadline_hit_pct);
+        assert!(brutal.traffic.len() > gentle.traffic.len());
+        // A CPU-only colony gets no farm and no GPU pipelines
+        assert!(gentle.yards.0.iter().all(|y| y.kind != WorkyardKind::GpuFarm));
+        assert!(gentle.traffic.iter().all(|t| !requires_gpu(&crate::get_pipeline_by_id(&t.pipeline).unwrap().ops)));
+        assert!(brutal.yards.0.iter().any(|y| y.kind == WorkyardKind::GpuFarm));
+    }
+
+    #[test]
+    fn test_scenario_ids_round_trip() {
+        let knobs = GenKnobs { load: 0.25, hazard: 1.0, strictness: 0.0, size: 0.5, gpu: Some(false) };
+        let id = knobs.scenario_id(99);
+        assert_eq!(id, "gen-99-l25-h100-s0-z50-cpu");
+        assert_eq!(GenKnobs::parse_id(&id), Some((99, knobs)));
+        assert!(GenKnobs::parse_id("gen-99-l25-h100-s0").is_none());
+        assert!(GenKnobs::parse_id("gen-99-l25-h100-s0-z500").is_none());
+        assert!(GenKnobs::parse_id("first_light_chill").is_none());
+        assert!(generate_scenario(1, &GenKnobs { load: 2.0, ..GenKnobs::default() }).is_err());
+    }
+}
@@ -1,12 +1,16 @@
 use bevy::prelude::*;
 use serde::{Serialize, Deserialize};
-use super::{Job, Workyard, Worker};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use anyhow::Result;
+use super::{Job, QoS, Workyard, WorkyardKind, Worker, QosWeights, ReplayEvent};
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum SchedPolicy { 
     Fcfs, 
     Sjf, 
-    Edf 
+    Edf,
+    FairShare,
 }
 
 impl std::fmt::Display for SchedPolicy {
@@ -15,13 +19,18 @@ impl std::fmt::Display for SchedPolicy {
             SchedPolicy::Fcfs => write!(f, "FCFS"),
             SchedPolicy::Sjf => write!(f, "SJF"),
             SchedPolicy::Edf => write!(f, "EDF"),
+            SchedPolicy::FairShare => write!(f, "FAIR"),
         }
     }
 }
 
 pub trait Scheduler: Send + Sync {
     fn pick(&self, yard: &Workyard, queue: &[Job], workers: &[(Entity, &Worker)]) -> Vec<(Entity, Job)>;
-    fn name(&self) -> &'static str;
+    fn name(&self) -> &str;
+    /// Decisions taken since the last call that a replay has to see, like falling back to queue order
+    fn take_replay_events(&self) -> Vec<ReplayEvent> {
+        Vec::new()
+    }
 }
 
 pub struct Fcfs;
@@ -37,7 +46,7 @@ impl Scheduler for Fcfs {
         out
     }
     
-    fn name(&self) -> &'static str { "FCFS" }
+    fn name(&self) -> &str { "FCFS" }
 }
 
 impl Scheduler for Sjf {
@@ -51,7 +60,7 @@ impl Scheduler for Sjf {
         out
     }
     
-    fn name(&self) -> &'static str { "SJF" }
+    fn name(&self) -> &str { "SJF" }
 }
 
 impl Scheduler for Edf {
@@ -65,17 +74,251 @@ impl Scheduler for Edf {
         out
     }
     
-    fn name(&self) -> &'static str { "EDF" }
+    fn name(&self) -> &str { "EDF" }
+}
+
+/// Weights and aging for the weighted fair scheduler
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FairTunables {
+    pub weights: QosWeights, // share of the yard each QoS class's pipelines get
+    pub aging: f32,          // how fast a passed-over job's finish tag shrinks, per dispatch round
+}
+
+impl Default for FairTunables {
+    fn default() -> Self {
+        Self { weights: QosWeights::default(), aging: 0.25 }
+    }
+}
+
+impl FairTunables {
+    pub fn validate(&self) -> Result<()> {
+        let w = &self.weights;
+        for (name, weight) in [("latency", w.latency), ("balanced", w.balanced), ("throughput", w.throughput)] {
+            if !weight.is_finite() || weight <= 0.0 {
+                anyhow::bail!("The {} weight is {}, must be above 0", name, weight);
+            }
+        }
+        if !self.aging.is_finite() || self.aging < 0.0 {
+            anyhow::bail!("aging is {}, must be 0 or more", self.aging);
+        }
+        Ok(())
+    }
+
+    fn weight(&self, qos: &QoS) -> f64 {
+        let weight = match qos {
+            QoS::Latency => self.weights.latency,
+            QoS::Balanced => self.weights.balanced,
+            QoS::Throughput => self.weights.throughput,
+        };
+        weight as f64
+    }
+}
+
+/// A queued job's place in the fair order
+#[derive(Debug, Clone, Copy)]
+struct FairTag {
+    finish: f64,
+    passed_over: u32, // dispatch rounds it was a candidate and didn't get a worker
+}
+
+/// Per-yard virtual clock, last finish tag per pipeline and tags of the jobs still waiting
+#[derive(Debug, Clone, Default)]
+struct FairYard {
+    virtual_time: f64,
+    last_finish: BTreeMap<String, f64>,
+    tags: BTreeMap<u64, FairTag>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct FairState {
+    tunables: FairTunables,
+    yards: BTreeMap<String, FairYard>,
+}
+
+/// Weighted fair queuing across pipelines: each pipeline's jobs get finish tags spaced by cost
+/// over their QoS weight, and every round a job is passed over divides its tag down, so bursts
+/// of Latency work can't keep Throughput pipelines waiting forever
+#[derive(Debug, Default)]
+pub struct FairShareScheduler {
+    state: Mutex<FairState>,
+}
+
+fn flow_key(job: &Job) -> String {
+    job.pipeline.id.clone().unwrap_or_else(|| format!("{:?}", job.pipeline.ops))
+}
+
+fn yard_key(kind: &WorkyardKind) -> String {
+    format!("{:?}", kind)
+}
+
+impl Clone for FairShareScheduler {
+    fn clone(&self) -> Self {
+        let state = self.state.lock().map(|s| s.clone()).unwrap_or_default();
+        Self { state: Mutex::new(state) }
+    }
+}
+
+impl FairShareScheduler {
+    pub fn new(tunables: FairTunables) -> Self {
+        Self { state: Mutex::new(FairState { tunables, yards: BTreeMap::new() }) }
+    }
+
+    pub fn tunables(&self) -> FairTunables {
+        self.state.lock().map(|s| s.tunables.clone()).unwrap_or_default()
+    }
+
+    /// Takes effect for jobs tagged from now on; jobs already waiting keep their tags
+    pub fn set_tunables(&self, tunables: FairTunables) -> Result<()> {
+        tunables.validate()?;
+        let mut state = self.state.lock().map_err(|_| anyhow::anyhow!("Fair-share scheduler state is poisoned"))?;
+        state.tunables = tunables;
+        Ok(())
+    }
+}
+
+impl Scheduler for FairShareScheduler {
+    fn pick(&self, y: &Workyard, q: &[Job], w: &[(Entity, &Worker)]) -> Vec<(Entity, Job)> {
+        let Ok(mut state) = self.state.lock() else { return Vec::new() };
+        let FairState { tunables, yards } = &mut *state;
+        let yard = yards.entry(yard_key(&y.kind)).or_default();
+
+        // Jobs no longer offered were dispatched or dropped
+        yard.tags.retain(|id, _| q.iter().any(|j| j.id == *id));
+        for job in q {
+            if yard.tags.contains_key(&job.id) {
+                continue;
+            }
+            let cost = job.pipeline.ops.iter().map(|op| op.cost_ms() as f64).sum::<f64>().max(1.0);
+            let last = yard.last_finish.entry(flow_key(job)).or_insert(0.0);
+            let finish = last.max(yard.virtual_time) + cost / tunables.weight(&job.qos);
+            *last = finish;
+            yard.tags.insert(job.id, FairTag { finish, passed_over: 0 });
+        }
+
+        let aging = tunables.aging as f64;
+        let effective = |tag: &FairTag| tag.finish / (1.0 + aging * tag.passed_over as f64);
+        let mut order: Vec<&Job> = q.iter().collect();
+        order.sort_by(|a, b| {
+            effective(&yard.tags[&a.id]).total_cmp(&effective(&yard.tags[&b.id])).then(a.id.cmp(&b.id))
+        });
+
+        let picks: Vec<(Entity, Job)> = w.iter().zip(order.iter()).map(|((we, _), j)| (*we, (*j).clone())).collect();
+        for job in order.iter().skip(picks.len()) {
+            if let Some(tag) = yard.tags.get_mut(&job.id) {
+                tag.passed_over += 1;
+            }
+        }
+        // Self-clocked: virtual time follows the finish tags of the work being served
+        if let Some(latest) = picks.iter().map(|(_, j)| yard.tags[&j.id].finish).reduce(f64::max) {
+            yard.virtual_time = yard.virtual_time.max(latest);
+        }
+        picks
+    }
+
+    fn name(&self) -> &str { "FAIR" }
+}
+
+impl SchedPolicy {
+    pub const ALL: [SchedPolicy; 4] = [SchedPolicy::Fcfs, SchedPolicy::Sjf, SchedPolicy::Edf, SchedPolicy::FairShare];
+
+    /// A fresh scheduler for this policy; FAIR keeps its fair-share state in the instance
+    pub fn scheduler(&self) -> Arc<dyn Scheduler> {
+        match self {
+            SchedPolicy::Fcfs => Arc::new(Fcfs),
+            SchedPolicy::Sjf => Arc::new(Sjf),
+            SchedPolicy::Edf => Arc::new(Edf),
+            SchedPolicy::FairShare => Arc::new(FairShareScheduler::default()),
+        }
+    }
+
+    /// The built-in policy a name refers to, in any case
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|policy| policy.to_string().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Named scheduling policies dispatch can switch to: the built-ins, plus any a downstream crate
+/// or a mod with the `scheduler` capability registers at runtime
+#[derive(Resource)]
+pub struct SchedulerRegistry {
+    schedulers: BTreeMap<String, Arc<dyn Scheduler>>,
+    fair: Arc<FairShareScheduler>, // the FAIR entry, kept typed so its tunables can be changed
+}
+
+impl Default for SchedulerRegistry {
+    fn default() -> Self {
+        let fair = Arc::new(FairShareScheduler::default());
+        let mut schedulers: BTreeMap<String, Arc<dyn Scheduler>> = SchedPolicy::ALL.iter()
+            .map(|policy| (policy.to_string(), policy.scheduler()))
+            .collect();
+        schedulers.insert(SchedPolicy::FairShare.to_string(), fair.clone());
+        Self { schedulers, fair }
+    }
+}
+
+/// A clone gets its own copy of the fair-share state, so a shadow or preview world dispatching
+/// from it can't move the finish tags of the world it was cloned from
+impl Clone for SchedulerRegistry {
+    fn clone(&self) -> Self {
+        let fair = Arc::new((*self.fair).clone());
+        let mut schedulers = self.schedulers.clone();
+        schedulers.insert(SchedPolicy::FairShare.to_string(), fair.clone());
+        Self { schedulers, fair }
+    }
+}
+
+impl SchedulerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a scheduler under its name, replacing an earlier one of that name; built-ins can't be replaced
+    pub fn register(&mut self, scheduler: Arc<dyn Scheduler>) -> Result<()> {
+        let name = scheduler.name().to_string();
+        if name.is_empty() {
+            anyhow::bail!("A scheduler needs a name");
+        }
+        if SchedPolicy::from_name(&name).is_some() {
+            anyhow::bail!("{} is a built-in scheduler", name);
+        }
+        self.schedulers.insert(name, scheduler);
+        Ok(())
+    }
+
+    pub fn unregister(&mut self, name: &str) -> bool {
+        SchedPolicy::from_name(name).is_none() && self.schedulers.remove(name).is_some()
+    }
+
+    /// Drop every scheduler a mod registered, named `<mod id>/<name>`
+    pub fn unregister_mod(&mut self, mod_id: &str) {
+        let prefix = format!("{}/", mod_id);
+        self.schedulers.retain(|name, _| !name.starts_with(&prefix));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Scheduler>> {
+        self.schedulers.get(name).cloned()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.schedulers.keys().cloned().collect()
+    }
+
+    /// The fair-share scheduler dispatch uses, whose state lives as long as this copy of the registry
+    pub fn fair(&self) -> &Arc<FairShareScheduler> {
+        &self.fair
+    }
 }
 
 #[derive(Resource, Debug, Clone, Serialize, Deserialize)]
 pub struct ActiveScheduler { 
-    pub policy: SchedPolicy 
+    pub policy: SchedPolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom: Option<String>, // a registered scheduler that runs instead of `policy`
 }
 
 impl Default for ActiveScheduler {
     fn default() -> Self {
-        Self { policy: SchedPolicy::Fcfs }
+        Self { policy: SchedPolicy::Fcfs, custom: None }
     }
 }
 
@@ -85,19 +328,48 @@ impl ActiveScheduler {
             SchedPolicy::Fcfs => Box::new(Fcfs),
             SchedPolicy::Sjf => Box::new(Sjf),
             SchedPolicy::Edf => Box::new(Edf),
+            SchedPolicy::FairShare => Box::new(FairShareScheduler::default()),
         }
     }
     
+    /// The registered scheduler to dispatch with; a custom one that is gone falls back to `policy`
+    pub fn resolve(&self, registry: Option<&SchedulerRegistry>) -> Arc<dyn Scheduler> {
+        self.custom.as_deref()
+            .and_then(|name| registry.and_then(|registry| registry.get(name)))
+            // The registry's built-ins carry state between ticks that a fresh one wouldn't
+            .or_else(|| registry.and_then(|registry| registry.get(&self.policy.to_string())))
+            .unwrap_or_else(|| self.policy.scheduler())
+    }
+
+    /// Switch to a built-in policy or a registered scheduler by name
+    pub fn select(&mut self, name: &str, registry: &SchedulerRegistry) -> Result<()> {
+        if let Some(policy) = SchedPolicy::from_name(name) {
+            self.policy = policy;
+            self.custom = None;
+            return Ok(());
+        }
+        if registry.get(name).is_none() {
+            anyhow::bail!("Unknown scheduler '{}'; expected one of {}", name, registry.names().join(", "));
+        }
+        self.custom = Some(name.to_string());
+        Ok(())
+    }
+
     pub fn new_fcfs() -> Self {
-        Self { policy: SchedPolicy::Fcfs }
+        Self { policy: SchedPolicy::Fcfs, custom: None }
     }
     
     pub fn new_sjf() -> Self {
-        Self { policy: SchedPolicy::Sjf }
+        Self { policy: SchedPolicy::Sjf, custom: None }
     }
     
     pub fn new_edf() -> Self {
-        Self { policy: SchedPolicy::Edf }
+        Self { policy: SchedPolicy::Edf, custom: None }
+    }
+
+    /// The custom scheduler's name when one is selected, else the policy's
+    pub fn name(&self) -> String {
+        self.custom.clone().unwrap_or_else(|| self.policy.to_string())
     }
     
     pub fn get_name(&self) -> &'static str {
@@ -105,6 +377,7 @@ impl ActiveScheduler {
             SchedPolicy::Fcfs => "FCFS",
             SchedPolicy::Sjf => "SJF",
             SchedPolicy::Edf => "EDF",
+            SchedPolicy::FairShare => "FAIR",
         }
     }
 }
@@ -112,14 +385,15 @@ impl ActiveScheduler {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::super::{Pipeline, QoS};
+    use crate::{Op, Pipeline, QoS, WorkClass};
 
     fn create_test_job(id: u64, cost_ms: u32, deadline_ms: u64) -> Job {
         Job {
             id,
             pipeline: Pipeline {
-                ops: vec![Op::Decode], // Simple op for testing
+                ops: vec![Op::Export; cost_ms as usize], // SJF ranks by summed op cost
                 mutation_tag: None,
+                id: None,
             },
             qos: QoS::Balanced,
             deadline_ms,
@@ -131,15 +405,6 @@ mod tests {
         Worker {
             id,
             class: WorkClass::Cpu,
-            skill_cpu: 1.0,
-            skill_gpu: 0.0,
-            skill_io: 0.0,
-            discipline: 1.0,
-            focus: 1.0,
-            corruption: 0.0,
-            state: WorkerState::Idle,
-            retry: RetryPolicy::default(),
-            sticky_faults: 0,
         }
     }
 
@@ -162,9 +427,10 @@ mod tests {
             create_test_job(3, 3, 200),
         ];
         
+        let (w1, w2) = (create_test_worker(1), create_test_worker(2));
         let workers = vec![
-            (Entity::from_raw(1), &create_test_worker(1)),
-            (Entity::from_raw(2), &create_test_worker(2)),
+            (Entity::from_raw(1), &w1),
+            (Entity::from_raw(2), &w2),
         ];
         
         let picks = fcfs.pick(&yard, &jobs, &workers);
@@ -193,9 +459,10 @@ mod tests {
             create_test_job(3, 3, 200),
         ];
         
+        let (w1, w2) = (create_test_worker(1), create_test_worker(2));
         let workers = vec![
-            (Entity::from_raw(1), &create_test_worker(1)),
-            (Entity::from_raw(2), &create_test_worker(2)),
+            (Entity::from_raw(1), &w1),
+            (Entity::from_raw(2), &w2),
         ];
         
         let picks = sjf.pick(&yard, &jobs, &workers);
@@ -224,9 +491,10 @@ mod tests {
             create_test_job(3, 3, 200),
         ];
         
+        let (w1, w2) = (create_test_worker(1), create_test_worker(2));
         let workers = vec![
-            (Entity::from_raw(1), &create_test_worker(1)),
-            (Entity::from_raw(2), &create_test_worker(2)),
+            (Entity::from_raw(1), &w1),
+            (Entity::from_raw(2), &w2),
         ];
         
         let picks = edf.pick(&yard, &jobs, &workers);
@@ -235,4 +503,92 @@ mod tests {
         assert_eq!(picks[0].1.id, 2); // deadline 50
         assert_eq!(picks[1].1.id, 1); // deadline 100
     }
-}
\ No newline at end of file
+
+    struct Newest;
+
+    impl Scheduler for Newest {
+        fn pick(&self, _y: &Workyard, q: &[Job], w: &[(Entity, &Worker)]) -> Vec<(Entity, Job)> {
+            w.iter().zip(q.iter().rev()).map(|((we, _), j)| (*we, j.clone())).collect()
+        }
+
+        fn name(&self) -> &str { "com.example/Newest" }
+    }
+
+    #[test]
+    fn test_registered_schedulers_are_selectable() {
+        let mut registry = SchedulerRegistry::new();
+        registry.register(Arc::new(Newest)).unwrap();
+        assert!(registry.register(Arc::new(Fcfs)).is_err());
+        assert_eq!(registry.names(), vec!["EDF", "FAIR", "FCFS", "SJF", "com.example/Newest"]);
+
+        let mut active = ActiveScheduler::default();
+        active.select("com.example/Newest", &registry).unwrap();
+        assert_eq!(active.resolve(Some(&registry)).name(), "com.example/Newest");
+        assert!(active.select("LIFO", &registry).is_err());
+
+        // A custom scheduler whose mod went away falls back to the policy
+        registry.unregister_mod("com.example");
+        assert_eq!(active.resolve(Some(&registry)).name(), "FCFS");
+        active.select("edf", &registry).unwrap();
+        assert_eq!((active.policy, active.custom.as_deref()), (SchedPolicy::Edf, None));
+    }
+
+    #[test]
+    fn test_fair_share_serves_throughput_during_latency_burst() {
+        let fair = FairShareScheduler::default();
+        let yard = Workyard {
+            kind: WorkyardKind::CpuArray,
+            slots: 1,
+            heat: 50.0,
+            heat_cap: 100.0,
+            power_draw_kw: 200.0,
+            bandwidth_share: 0.1,
+            isolation_domain: 0,
+        };
+        let worker = create_test_worker(1);
+        let workers = vec![(Entity::from_raw(1), &worker)];
+
+        let mut bulk = create_test_job(1, 1, 1000);
+        bulk.qos = QoS::Throughput;
+        bulk.pipeline.id = Some("bulk".to_string());
+        let burst = |id: u64| {
+            let mut job = create_test_job(id, 1, 10);
+            job.qos = QoS::Latency;
+            job.pipeline.id = Some("burst".to_string());
+            job
+        };
+        let mut queue: Vec<Job> = (2..20).map(burst).collect();
+        queue.insert(0, bulk);
+
+        // One Latency job arrives for every one served, so the burst never drains
+        let mut served_at = None;
+        for round in 0..12u64 {
+            let picked = fair.pick(&yard, &queue, &workers)[0].1.id;
+            if picked == 1 {
+                served_at = Some(round);
+                break;
+            }
+            queue.retain(|j| j.id != picked);
+            queue.push(burst(100 + round));
+        }
+        assert!(served_at.is_some_and(|round| (1..=5).contains(&round)), "served at {:?}", served_at);
+
+        let bad = FairTunables { weights: QosWeights { latency: 0.0, ..Default::default() }, aging: 0.25 };
+        assert!(fair.set_tunables(bad).is_err());
+        assert!(fair.set_tunables(FairTunables { aging: -1.0, ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn test_registry_clone_has_its_own_fair_state() {
+        let live = SchedulerRegistry::new();
+        let shadow = live.clone();
+        let tunables = FairTunables { aging: 1.0, ..Default::default() };
+        shadow.fair().set_tunables(tunables).unwrap();
+
+        assert_eq!(live.fair().tunables().aging, FairTunables::default().aging);
+        assert_eq!(shadow.fair().tunables().aging, 1.0);
+        // Dispatch resolves the policy by name, so the map has to hold the clone's copy too
+        let fair: Arc<dyn Scheduler> = shadow.fair().clone();
+        assert!(Arc::ptr_eq(&shadow.get("FAIR").unwrap(), &fair));
+    }
+}
@@ -0,0 +1,405 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::ffi::{c_char, CStr};
+use std::path::Path;
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use anyhow::Result;
+use crate::{Job, QoS, ReplayEvent, Scheduler, Worker, Workyard, WorkyardKind};
+
+/// Entry point a scheduler library exports: `const ColonySchedulerV1 *colony_scheduler_v1(void)`
+pub const COLONY_SCHEDULER_V1_SYMBOL: &[u8] = b"colony_scheduler_v1\0";
+pub const COLONY_SCHEDULER_ABI_V1: u32 = 1;
+/// Registered names are `ffi/<name>`, so a library can't shadow a built-in or a mod's scheduler
+pub const FFI_SCHEDULER_PREFIX: &str = "ffi";
+const MAX_NAME_LEN: usize = 64;
+
+/// A queued job as the library sees it; see `include/colony_scheduler_v1.h`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColonyJobV1 {
+    pub id: u64,
+    pub deadline_ms: u64,
+    pub cost_ms: u32,
+    pub payload_sz: u32,
+    pub qos: u32, // 0 Throughput, 1 Latency, 2 Balanced, as for WASM schedulers
+    pub op_count: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColonyYardV1 {
+    pub kind: u32, // 0 CpuArray, 1 GpuFarm, 2 SignalHub, 3 StorageArray
+    pub slots: u32,
+    pub heat: f32,
+    pub heat_cap: f32,
+}
+
+/// Write the indices of the jobs to run, best first, into `out` and return how many were written.
+/// At most `out_cap` (the idle worker count, capped at the job count); negative means failure.
+pub type ColonyPickV1 = unsafe extern "C" fn(
+    yard: *const ColonyYardV1,
+    jobs: *const ColonyJobV1,
+    job_count: usize,
+    out: *mut u32,
+    out_cap: usize,
+) -> i64;
+
+#[repr(C)]
+pub struct ColonySchedulerV1 {
+    pub abi_version: u32,
+    pub name: *const c_char, // NUL-terminated UTF-8, static for the life of the library
+    pub pick: Option<ColonyPickV1>,
+}
+
+type EntryV1 = unsafe extern "C" fn() -> *const ColonySchedulerV1;
+
+/// How much a library gets away with before dispatch stops calling it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FfiSchedulerLimits {
+    pub timeout_ms: u64,    // per pick; an overrun counts as a failure
+    pub max_failures: u32,  // consecutive failed, malformed or overrun picks before quarantine
+}
+
+impl Default for FfiSchedulerLimits {
+    fn default() -> Self {
+        Self { timeout_ms: 5, max_failures: 3 }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FfiSchedulerStats {
+    pub calls: u64,
+    pub failures: u64, // errors and malformed picks, timeouts included
+    pub timeouts: u64,
+    pub quarantined: bool,
+}
+
+struct PickCall {
+    yard: ColonyYardV1,
+    jobs: Vec<ColonyJobV1>,
+    out_cap: usize,
+    reply: mpsc::SyncSender<Result<Vec<usize>, String>>,
+}
+
+/// `pick` and the library it lives in, moved onto the thread that calls it
+struct Plugin {
+    pick: ColonyPickV1,
+    library: Option<libloading::Library>,
+}
+
+struct FfiState {
+    calls: Option<mpsc::Sender<PickCall>>, // None once quarantined
+    consecutive_failures: u32,
+    stats: FfiSchedulerStats,
+    replay_events: Vec<ReplayEvent>, // fallbacks and the quarantine, until dispatch records them
+}
+
+/// A scheduler written in C, C++ or anything else that can export `colony_scheduler_v1`, adapted
+/// to the Scheduler trait. The library runs in-process, so the guards contain a buggy scheduler,
+/// not a hostile one: every pick runs on the library's own thread against copies of the queue, a
+/// pick that overruns its timeout or returns out-of-range or repeated indices keeps queue order,
+/// and a library that keeps failing is quarantined and never called again. A hung library fails
+/// every pick queued behind it, so it reaches quarantine after `max_failures` picks.
+pub struct FfiScheduler {
+    name: String,
+    limits: FfiSchedulerLimits,
+    state: Mutex<FfiState>,
+}
+
+impl FfiScheduler {
+    /// Load a scheduler library and check its entry point. The library is unloaded when the
+    /// scheduler is dropped, unless a pick is hung inside it.
+    pub fn load(path: &Path, limits: FfiSchedulerLimits) -> Result<Self> {
+        // SAFETY: loading runs the library's initializers; that is the trust the caller extends
+        // by naming the library at all
+        let library = unsafe { libloading::Library::new(path) }
+            .map_err(|e| anyhow::anyhow!("Failed to load scheduler library {}: {}", path.display(), e))?;
+        let descriptor = unsafe {
+            let entry = library.get::<EntryV1>(COLONY_SCHEDULER_V1_SYMBOL)
+                .map_err(|e| anyhow::anyhow!("{} doesn't export colony_scheduler_v1: {}", path.display(), e))?;
+            entry()
+        };
+        // SAFETY: the descriptor and its name are static data of the library, which the plugin
+        // thread keeps loaded for as long as `pick` can be called
+        unsafe { Self::from_descriptor(descriptor, Some(library), limits) }
+    }
+
+    /// Adapt a descriptor that isn't behind a library, e.g. one linked into the binary
+    ///
+    /// # Safety
+    /// `descriptor` must be null or point to a valid `ColonySchedulerV1` whose name and `pick`
+    /// stay valid while `library` (or the program, if none) is loaded
+    pub unsafe fn from_descriptor(descriptor: *const ColonySchedulerV1, library: Option<libloading::Library>, limits: FfiSchedulerLimits) -> Result<Self> {
+        let descriptor = descriptor.as_ref().ok_or_else(|| anyhow::anyhow!("colony_scheduler_v1 returned null"))?;
+        if descriptor.abi_version != COLONY_SCHEDULER_ABI_V1 {
+            anyhow::bail!("Scheduler ABI version {} is not supported, expected {}", descriptor.abi_version, COLONY_SCHEDULER_ABI_V1);
+        }
+        let pick = descriptor.pick.ok_or_else(|| anyhow::anyhow!("Scheduler has no pick function"))?;
+        if descriptor.name.is_null() {
+            anyhow::bail!("Scheduler has no name");
+        }
+        let name = CStr::from_ptr(descriptor.name).to_str()
+            .map_err(|_| anyhow::anyhow!("Scheduler name is not UTF-8"))?;
+        if name.is_empty() || name.len() > MAX_NAME_LEN || !name.chars().all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c)) {
+            anyhow::bail!("Scheduler name '{}' must be 1 to {} letters, digits, '_', '-' or '.'", name, MAX_NAME_LEN);
+        }
+        if limits.timeout_ms == 0 || limits.max_failures == 0 {
+            anyhow::bail!("timeout_ms and max_failures must be above 0");
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let plugin = Plugin { pick, library };
+        std::thread::Builder::new()
+            .name(format!("ffi-scheduler-{}", name))
+            .spawn(move || serve(plugin, rx))?;
+        Ok(Self {
+            name: format!("{}/{}", FFI_SCHEDULER_PREFIX, name),
+            limits,
+            state: Mutex::new(FfiState { calls: Some(tx), consecutive_failures: 0, stats: FfiSchedulerStats::default(), replay_events: Vec::new() }),
+        })
+    }
+
+    pub fn stats(&self) -> FfiSchedulerStats {
+        self.state.lock().map(|s| s.stats.clone()).unwrap_or_default()
+    }
+
+    /// Job indices in the library's order; Err when the queue order should be kept instead
+    fn call(&self, yard: &Workyard, queue: &[Job], workers: usize) -> Result<Vec<usize>, String> {
+        let calls = {
+            let mut state = self.state.lock().map_err(|_| "state is poisoned".to_string())?;
+            let Some(calls) = state.calls.clone() else { return Err(String::new()) };
+            state.stats.calls += 1;
+            calls
+        };
+
+        // The lock is released while the library runs, so a slow pick doesn't hold up stats()
+        let (reply, answer) = mpsc::sync_channel(1);
+        let call = PickCall { yard: yard_v1(yard), jobs: queue.iter().map(job_v1).collect(), out_cap: workers.min(queue.len()), reply };
+        let mut timed_out = false;
+        let result = match calls.send(call) {
+            Err(_) => Err("the scheduler thread is gone".to_string()),
+            Ok(()) => match answer.recv_timeout(Duration::from_millis(self.limits.timeout_ms)) {
+                Ok(result) => result,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    // The thread is still inside the library and can't be interrupted; its late
+                    // answer goes nowhere and the picks queued behind it overrun in turn
+                    timed_out = true;
+                    Err(format!("pick ran past {}ms", self.limits.timeout_ms))
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => Err("the scheduler thread is gone".to_string()),
+            },
+        };
+
+        let mut state = self.state.lock().map_err(|_| "state is poisoned".to_string())?;
+        match result {
+            Ok(order) => {
+                state.consecutive_failures = 0;
+                Ok(order)
+            }
+            Err(e) => {
+                state.stats.failures += 1;
+                if timed_out {
+                    state.stats.timeouts += 1;
+                }
+                state.consecutive_failures += 1;
+                state.replay_events.push(ReplayEvent::SchedulerFallback { scheduler: self.name.clone(), reason: e.clone() });
+                // Another pick may have quarantined the library while this one waited
+                if state.consecutive_failures >= self.limits.max_failures && state.calls.take().is_some() {
+                    state.stats.quarantined = true;
+                    state.replay_events.push(ReplayEvent::SchedulerQuarantined { scheduler: self.name.clone() });
+                    return Err(format!("{}; quarantined, dispatch keeps queue order from now on", e));
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Runs picks one at a time until the scheduler is dropped
+fn serve(plugin: Plugin, calls: mpsc::Receiver<PickCall>) {
+    let Plugin { pick, library } = plugin;
+    for call in calls {
+        let mut out = vec![u32::MAX; call.out_cap];
+        // SAFETY: the buffers are owned by this thread and sized as promised; `library` is
+        // still loaded, so `pick` is too
+        let written = unsafe { pick(&call.yard, call.jobs.as_ptr(), call.jobs.len(), out.as_mut_ptr(), out.len()) };
+        let _ = call.reply.send(check_picks(written, &out, call.jobs.len()));
+    }
+    drop(library);
+}
+
+/// A pick must name each job at most once, in range, and no more jobs than there are workers
+fn check_picks(written: i64, out: &[u32], job_count: usize) -> Result<Vec<usize>, String> {
+    if written < 0 {
+        return Err(format!("pick returned error {}", written));
+    }
+    if written as u64 > out.len() as u64 {
+        return Err(format!("pick claims {} jobs but only {} fit", written, out.len()));
+    }
+    let mut seen = vec![false; job_count];
+    let mut order = Vec::with_capacity(written as usize);
+    for &index in &out[..written as usize] {
+        let index = index as usize;
+        match seen.get_mut(index) {
+            None => return Err(format!("pick named job index {} of {}", index, job_count)),
+            Some(true) => return Err(format!("pick named job index {} twice", index)),
+            Some(taken) => *taken = true,
+        }
+        order.push(index);
+    }
+    Ok(order)
+}
+
+fn job_v1(job: &Job) -> ColonyJobV1 {
+    ColonyJobV1 {
+        id: job.id,
+        deadline_ms: job.deadline_ms,
+        cost_ms: job.pipeline.ops.iter().map(|op| op.cost_ms()).sum(),
+        payload_sz: job.payload_sz.min(u32::MAX as usize) as u32,
+        qos: match job.qos {
+            QoS::Throughput => 0,
+            QoS::Latency => 1,
+            QoS::Balanced => 2,
+        },
+        op_count: job.pipeline.ops.len() as u32,
+    }
+}
+
+fn yard_v1(yard: &Workyard) -> ColonyYardV1 {
+    ColonyYardV1 {
+        kind: match yard.kind {
+            WorkyardKind::CpuArray => 0,
+            WorkyardKind::GpuFarm => 1,
+            WorkyardKind::SignalHub => 2,
+            WorkyardKind::StorageArray => 3,
+        },
+        slots: yard.slots,
+        heat: yard.heat,
+        heat_cap: yard.heat_cap,
+    }
+}
+
+impl Scheduler for FfiScheduler {
+    fn pick(&self, y: &Workyard, q: &[Job], w: &[(Entity, &Worker)]) -> Vec<(Entity, Job)> {
+        if q.is_empty() || w.is_empty() {
+            return Vec::new();
+        }
+        let order = match self.call(y, q, w.len()) {
+            Ok(order) => order,
+            Err(e) => {
+                if !e.is_empty() {
+                    println!("Scheduler {} failed, keeping queue order: {}", self.name, e);
+                }
+                (0..q.len()).collect()
+            }
+        };
+        w.iter().zip(order).map(|((we, _), i)| (*we, q[i].clone())).collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn take_replay_events(&self) -> Vec<ReplayEvent> {
+        self.state.lock().map(|mut s| std::mem::take(&mut s.replay_events)).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pipeline, Op, WorkClass};
+
+    unsafe fn jobs<'a>(jobs: *const ColonyJobV1, count: usize) -> &'a [ColonyJobV1] {
+        std::slice::from_raw_parts(jobs, count)
+    }
+
+    /// Smallest payload first, as a C scheduler would write it
+    unsafe extern "C" fn by_payload(_yard: *const ColonyYardV1, jobs_ptr: *const ColonyJobV1, count: usize, out: *mut u32, cap: usize) -> i64 {
+        let mut order: Vec<u32> = (0..count as u32).collect();
+        order.sort_by_key(|&i| jobs(jobs_ptr, count)[i as usize].payload_sz);
+        for (slot, index) in order.iter().take(cap).enumerate() {
+            *out.add(slot) = *index;
+        }
+        order.len().min(cap) as i64
+    }
+
+    unsafe extern "C" fn repeats(_yard: *const ColonyYardV1, _jobs: *const ColonyJobV1, _count: usize, out: *mut u32, cap: usize) -> i64 {
+        for slot in 0..cap {
+            *out.add(slot) = 0;
+        }
+        cap as i64
+    }
+
+    unsafe extern "C" fn hangs(_yard: *const ColonyYardV1, _jobs: *const ColonyJobV1, _count: usize, _out: *mut u32, _cap: usize) -> i64 {
+        std::thread::sleep(Duration::from_millis(200));
+        0
+    }
+
+    fn descriptor(name: &'static CStr, pick: ColonyPickV1) -> &'static ColonySchedulerV1 {
+        Box::leak(Box::new(ColonySchedulerV1 { abi_version: COLONY_SCHEDULER_ABI_V1, name: name.as_ptr(), pick: Some(pick) }))
+    }
+
+    fn job(id: u64, payload_sz: usize) -> Job {
+        Job {
+            id,
+            pipeline: Pipeline { ops: vec![Op::Decode], mutation_tag: None, id: None },
+            qos: QoS::Balanced,
+            deadline_ms: 1000,
+            payload_sz,
+        }
+    }
+
+    fn pick_ids(scheduler: &FfiScheduler, workers: usize) -> Vec<u64> {
+        let yard = Workyard { kind: WorkyardKind::CpuArray, slots: 4, heat: 0.0, heat_cap: 100.0, power_draw_kw: 0.0, bandwidth_share: 0.1, isolation_domain: 0 };
+        let worker = Worker { id: 1, class: WorkClass::Cpu };
+        let ws: Vec<(Entity, &Worker)> = (0..workers as u32).map(|i| (Entity::from_raw(i + 1), &worker)).collect();
+        scheduler.pick(&yard, &[job(1, 900), job(2, 100), job(3, 500)], &ws).into_iter().map(|(_, j)| j.id).collect()
+    }
+
+    #[test]
+    fn test_library_order_is_used() {
+        let scheduler = unsafe { FfiScheduler::from_descriptor(descriptor(c"by_payload", by_payload), None, FfiSchedulerLimits::default()) }.unwrap();
+        assert_eq!(scheduler.name(), "ffi/by_payload");
+        assert_eq!(pick_ids(&scheduler, 2), vec![2, 3]);
+        assert_eq!(pick_ids(&scheduler, 5), vec![2, 3, 1]);
+        assert_eq!(scheduler.stats().calls, 2);
+
+        let bad_abi = Box::leak(Box::new(ColonySchedulerV1 { abi_version: 2, name: c"x".as_ptr(), pick: Some(by_payload) }));
+        assert!(unsafe { FfiScheduler::from_descriptor(bad_abi, None, FfiSchedulerLimits::default()) }.is_err());
+        assert!(unsafe { FfiScheduler::from_descriptor(descriptor(c"no spaces", by_payload), None, FfiSchedulerLimits::default()) }.is_err());
+    }
+
+    #[test]
+    fn test_bad_picks_fall_back_then_quarantine() {
+        let limits = FfiSchedulerLimits { max_failures: 2, ..Default::default() };
+        let scheduler = unsafe { FfiScheduler::from_descriptor(descriptor(c"repeats", repeats), None, limits) }.unwrap();
+        assert_eq!(pick_ids(&scheduler, 2), vec![1, 2]);
+        assert!(!scheduler.stats().quarantined);
+        pick_ids(&scheduler, 2);
+        pick_ids(&scheduler, 2);
+        let stats = scheduler.stats();
+        assert_eq!((stats.calls, stats.failures, stats.quarantined), (2, 2, true));
+        let events = scheduler.take_replay_events();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], ReplayEvent::SchedulerFallback { scheduler, .. } if scheduler == "ffi/repeats"));
+        assert!(matches!(&events[2], ReplayEvent::SchedulerQuarantined { scheduler } if scheduler == "ffi/repeats"));
+        assert!(scheduler.take_replay_events().is_empty());
+    }
+
+    #[test]
+    fn test_overruns_count_toward_quarantine() {
+        let scheduler = unsafe { FfiScheduler::from_descriptor(descriptor(c"hangs", hangs), None, FfiSchedulerLimits::default()) }.unwrap();
+        assert_eq!(pick_ids(&scheduler, 3), vec![1, 2, 3]);
+        let stats = scheduler.stats();
+        assert_eq!((stats.timeouts, stats.quarantined), (1, false));
+
+        // The picks queued behind the hung one overrun too
+        pick_ids(&scheduler, 3);
+        pick_ids(&scheduler, 3);
+        let stats = scheduler.stats();
+        assert_eq!((stats.calls, stats.timeouts, stats.quarantined), (3, 3, true));
+        assert!(matches!(scheduler.take_replay_events().last(), Some(ReplayEvent::SchedulerQuarantined { .. })));
+    }
+}
@@ -0,0 +1,305 @@
+use mlua::{Function, HookTriggers, Lua, RegistryKey, Table, Thread, ThreadStatus, Value};
+use serde::{Serialize, Deserialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use anyhow::Result;
+use super::{bind_lua_vfs, catch_mod_panic, LuaHost, ModFaultKind};
+
+/// VM instructions between budget checks while a coroutine runs
+const BUDGET_CHECK_EVERY: u32 = 1_000;
+
+/// A long-running script: a Lua coroutine that yields back to the host and resumes on a later tick.
+///
+/// The script evaluates to `function(state) ... end`. `coroutine.yield(n)` sleeps for `n` ticks
+/// (1 if omitted). `state` is a plain table that is saved with the game; after a load the function
+/// starts again from the top with the saved `state`, so it should keep its progress there.
+pub struct LuaCoroutine {
+    pub id: u64,
+    pub mod_id: String,
+    pub event_name: String,
+    pub wake_tick: u64,
+    pub resumes: u64,
+    thread: RegistryKey,
+    state: RegistryKey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CoroutineStep {
+    Sleeping { wake_tick: u64 },
+    Finished,
+    Failed(String), // script error or instruction budget exhausted; the coroutine is dropped
+}
+
+/// What a save keeps of a coroutine: where it came from, when it wakes and its `state` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoroutineSnapshot {
+    pub id: u64,
+    pub mod_id: String,
+    pub event_name: String,
+    pub wake_tick: u64,
+    pub state: serde_json::Value,
+}
+
+impl LuaHost {
+    /// Start a loaded script as a coroutine; its first resume is on `current_tick`
+    pub fn spawn_coroutine(&mut self, mod_id: &str, event_name: &str, current_tick: u64) -> Result<u64> {
+        let lua = self.lua.lock();
+        let state = lua.create_registry_value(lua.create_table()?)?;
+        drop(lua); // start_coroutine takes the lock again
+        let id = self.next_coroutine_id;
+        self.start_coroutine(id, mod_id, event_name, state, current_tick)?;
+        self.next_coroutine_id += 1;
+        Ok(id)
+    }
+
+    fn start_coroutine(&mut self, id: u64, mod_id: &str, event_name: &str, state: RegistryKey, wake_tick: u64) -> Result<()> {
+        let key = format!("{}:{}", mod_id, event_name);
+        let script = self.scripts.get(&key)
+            .ok_or_else(|| anyhow::anyhow!("Script not found: {}", key))?;
+        let lua = self.lua.lock();
+        let function: Function = lua.load(&script.script_content).eval()?;
+        let thread = lua.create_thread(function)?;
+        let coroutine = LuaCoroutine {
+            id,
+            mod_id: mod_id.to_string(),
+            event_name: event_name.to_string(),
+            wake_tick,
+            resumes: 0,
+            thread: lua.create_registry_value(thread)?,
+            state,
+        };
+        drop(lua);
+        self.coroutines.push(coroutine);
+        Ok(())
+    }
+
+    /// Resume every coroutine that is due, each within the instruction budget.
+    /// Coroutines of suspended mods stay asleep until the mod is resumed or reloaded.
+    pub fn resume_coroutines(&mut self, current_tick: u64) -> Vec<(u64, CoroutineStep)> {
+        let budget = self.execution_env.instruction_budget;
+        self.faults.tick = current_tick;
+        let mut steps = Vec::new();
+        let lua = self.lua.lock();
+        for coroutine in self.coroutines.iter_mut().filter(|c| c.wake_tick <= current_tick) {
+            if self.faults.is_suspended(&coroutine.mod_id) {
+                continue;
+            }
+            let vfs = self.vfs.get(&coroutine.mod_id);
+            let resumed = catch_mod_panic(|| {
+                bind_lua_vfs(&lua, &coroutine.mod_id, vfs)?;
+                resume_one(&lua, coroutine, current_tick, budget)
+            });
+            let (step, kind) = match resumed {
+                Ok(step) => (step, ModFaultKind::Error),
+                Err(e) => (CoroutineStep::Failed(e.to_string()), ModFaultKind::of(&e)),
+            };
+            match &step {
+                CoroutineStep::Sleeping { wake_tick } => {
+                    coroutine.wake_tick = *wake_tick;
+                    self.faults.record_success(&coroutine.mod_id);
+                }
+                CoroutineStep::Finished => self.faults.record_success(&coroutine.mod_id),
+                CoroutineStep::Failed(message) => {
+                    self.faults.record_failure(&coroutine.mod_id, kind, message);
+                }
+            }
+            steps.push((coroutine.id, step));
+        }
+        let done: Vec<u64> = steps.iter()
+            .filter(|(_, step)| !matches!(step, CoroutineStep::Sleeping { .. }))
+            .map(|(id, _)| *id)
+            .collect();
+        lua.expire_registry_values();
+        drop(lua);
+        self.coroutines.retain(|c| !done.contains(&c.id));
+        steps
+    }
+
+    pub fn cancel_coroutine(&mut self, id: u64) -> bool {
+        let before = self.coroutines.len();
+        self.coroutines.retain(|c| c.id != id);
+        before != self.coroutines.len()
+    }
+
+    /// Coroutine state for a save; fails if a `state` table holds something that can't be saved
+    pub fn snapshot_coroutines(&self) -> Result<Vec<CoroutineSnapshot>> {
+        let lua = self.lua.lock();
+        self.coroutines.iter()
+            .map(|c| {
+                let state: Value = lua.registry_value(&c.state)?;
+                Ok(CoroutineSnapshot {
+                    id: c.id,
+                    mod_id: c.mod_id.clone(),
+                    event_name: c.event_name.clone(),
+                    wake_tick: c.wake_tick,
+                    state: lua_to_json(&state)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Restart saved coroutines from the top of their scripts with their saved `state`
+    pub fn restore_coroutines(&mut self, snapshots: &[CoroutineSnapshot]) -> Result<()> {
+        self.coroutines.clear();
+        for snapshot in snapshots {
+            let lua = self.lua.lock();
+            let state = lua.create_registry_value(json_to_lua(&lua, &snapshot.state)?)?;
+            drop(lua);
+            self.start_coroutine(snapshot.id, &snapshot.mod_id, &snapshot.event_name, state, snapshot.wake_tick)?;
+            self.next_coroutine_id = self.next_coroutine_id.max(snapshot.id + 1);
+        }
+        Ok(())
+    }
+}
+
+fn resume_one(lua: &Lua, coroutine: &mut LuaCoroutine, current_tick: u64, budget: u64) -> Result<CoroutineStep> {
+    let thread: Thread = lua.registry_value(&coroutine.thread)?;
+    let state: Value = lua.registry_value(&coroutine.state)?;
+
+    thread.set_hook(budget_triggers(), budget_hook(budget));
+
+    // The first resume passes `state` to the script's function; later ones return it from `yield`
+    let sleep: Option<u64> = thread.resume(state)?;
+    coroutine.resumes += 1;
+    Ok(match thread.status() {
+        ThreadStatus::Resumable => CoroutineStep::Sleeping { wake_tick: current_tick + sleep.unwrap_or(1).max(1) },
+        ThreadStatus::Unresumable => CoroutineStep::Finished,
+        ThreadStatus::Error => CoroutineStep::Failed("coroutine errored".to_string()),
+    })
+}
+
+pub fn budget_triggers() -> HookTriggers {
+    HookTriggers::new().every_nth_instruction(BUDGET_CHECK_EVERY)
+}
+
+/// Hook that fails the running call once it has spent more than `budget` VM instructions
+pub fn budget_hook(budget: u64) -> impl Fn(&Lua, mlua::Debug) -> mlua::Result<()> + Send + 'static {
+    let counter = Arc::new(AtomicU64::new(0));
+    move |_, _| {
+        let spent = counter.fetch_add(BUDGET_CHECK_EVERY as u64, Ordering::Relaxed) + BUDGET_CHECK_EVERY as u64;
+        if spent > budget {
+            return Err(mlua::Error::runtime(format!("instruction budget of {} exceeded", budget)));
+        }
+        Ok(())
+    }
+}
+
+pub fn lua_to_json(value: &Value) -> Result<serde_json::Value> {
+    Ok(match value {
+        Value::Nil => serde_json::Value::Null,
+        Value::Boolean(b) => serde_json::Value::Bool(*b),
+        Value::Integer(i) => serde_json::Value::from(*i),
+        Value::Number(n) => serde_json::Number::from_f64(*n)
+            .map(serde_json::Value::Number)
+            .ok_or_else(|| anyhow::anyhow!("can't save non-finite number {}", n))?,
+        Value::String(s) => serde_json::Value::String(s.to_str()?.to_string()),
+        Value::Table(table) => table_to_json(table)?,
+        other => return Err(anyhow::anyhow!("can't save a {} in coroutine state", other.type_name())),
+    })
+}
+
+fn table_to_json(table: &Table) -> Result<serde_json::Value> {
+    let len = table.raw_len();
+    let pairs: Vec<(Value, Value)> = table.clone().pairs::<Value, Value>().collect::<mlua::Result<_>>()?;
+    // A table with keys exactly 1..n is a list
+    if len > 0 && pairs.len() == len {
+        let items = (1..=len)
+            .map(|i| lua_to_json(&table.raw_get::<_, Value>(i)?))
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(serde_json::Value::Array(items));
+    }
+    let mut map = serde_json::Map::new();
+    for (k, v) in pairs {
+        let key = match k {
+            Value::String(s) => s.to_str()?.to_string(),
+            Value::Integer(i) => i.to_string(),
+            other => return Err(anyhow::anyhow!("can't save a {} table key in coroutine state", other.type_name())),
+        };
+        map.insert(key, lua_to_json(&v)?);
+    }
+    Ok(serde_json::Value::Object(map))
+}
+
+pub fn json_to_lua<'lua>(lua: &'lua Lua, value: &serde_json::Value) -> Result<Value<'lua>> {
+    Ok(match value {
+        serde_json::Value::Null => Value::Nil,
+        serde_json::Value::Bool(b) => Value::Boolean(*b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Number(n.as_f64().unwrap_or(0.0)),
+        },
+        serde_json::Value::String(s) => Value::String(lua.create_string(s)?),
+        serde_json::Value::Array(items) => {
+            let table = lua.create_table()?;
+            for (i, item) in items.iter().enumerate() {
+                table.raw_set(i + 1, json_to_lua(lua, item)?)?;
+            }
+            Value::Table(table)
+        }
+        serde_json::Value::Object(map) => {
+            let table = lua.create_table()?;
+            for (k, v) in map {
+                table.raw_set(k.as_str(), json_to_lua(lua, v)?)?;
+            }
+            Value::Table(table)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COUNTDOWN: &str = r#"
+        return function(state)
+            state.left = state.left or 3
+            while state.left > 0 do
+                state.left = state.left - 1
+                coroutine.yield(2)
+            end
+        end
+    "#;
+
+    #[test]
+    fn test_coroutine_sleeps_between_ticks_and_finishes() {
+        let mut host = LuaHost::new();
+        host.load_script("timer_mod", "countdown", COUNTDOWN.to_string()).unwrap();
+        let id = host.spawn_coroutine("timer_mod", "countdown", 10).unwrap();
+
+        assert_eq!(host.resume_coroutines(10), vec![(id, CoroutineStep::Sleeping { wake_tick: 12 })]);
+        assert!(host.resume_coroutines(11).is_empty()); // still asleep
+        host.resume_coroutines(12);
+        host.resume_coroutines(14);
+        assert_eq!(host.resume_coroutines(16), vec![(id, CoroutineStep::Finished)]);
+        assert!(host.coroutines.is_empty());
+    }
+
+    #[test]
+    fn test_state_survives_a_save() {
+        let mut host = LuaHost::new();
+        host.load_script("timer_mod", "countdown", COUNTDOWN.to_string()).unwrap();
+        host.spawn_coroutine("timer_mod", "countdown", 0).unwrap();
+        host.resume_coroutines(0);
+        let saved = host.snapshot_coroutines().unwrap();
+        assert_eq!(saved[0].state, serde_json::json!({ "left": 2 }));
+
+        // A fresh host picks up where the saved one stopped: two more yields, then done
+        let mut loaded = LuaHost::new();
+        loaded.load_script("timer_mod", "countdown", COUNTDOWN.to_string()).unwrap();
+        loaded.restore_coroutines(&saved).unwrap();
+        assert!(matches!(loaded.resume_coroutines(2)[0].1, CoroutineStep::Sleeping { .. }));
+        assert!(matches!(loaded.resume_coroutines(4)[0].1, CoroutineStep::Sleeping { .. }));
+        assert_eq!(loaded.resume_coroutines(6)[0].1, CoroutineStep::Finished);
+    }
+
+    #[test]
+    fn test_runaway_coroutine_hits_the_budget() {
+        let mut host = LuaHost::new();
+        host.execution_env.instruction_budget = 10_000;
+        host.load_script("bad_mod", "spin", "return function(state) while true do end end".to_string()).unwrap();
+        host.spawn_coroutine("bad_mod", "spin", 0).unwrap();
+        let steps = host.resume_coroutines(0);
+        assert!(matches!(&steps[0].1, CoroutineStep::Failed(msg) if msg.contains("budget")));
+        assert!(host.coroutines.is_empty());
+    }
+}
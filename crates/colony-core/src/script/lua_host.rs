@@ -1,12 +1,51 @@
-use mlua::{Lua, Function};
+use bevy::prelude::*;
+use mlua::{Function, Lua, LuaOptions, StdLib};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
 use anyhow::Result;
+use crate::{SimClock, WorkerReport};
+use super::{bind_lua_vfs, budget_hook, budget_triggers, catch_mod_panic, CoroutineStep, LuaCoroutine, ModFaults, ModVfs};
 
-// Note: Cannot derive Resource due to thread safety issues with mlua
+/// Hook a mod's `on_tick.lua` defines; called once per tick with `{ tick = n }`
+pub const ON_TICK_EVENT: &str = "on_tick";
+/// Hook a mod's `on_fault.lua` defines; called per worker fault with `{ tick, worker_id, op, kind }`
+pub const ON_FAULT_EVENT: &str = "on_fault";
+
+/// The Lua state behind a mutex. mlua's `send` feature makes `Lua` Send but not Sync,
+/// which a Bevy resource must be; every call locks it for its duration.
+pub struct LuaVm(Mutex<Lua>);
+
+impl LuaVm {
+    /// The safe standard libraries minus `io`; mods read their own files through `colony.read_file`
+    pub fn new() -> Self {
+        let lua = Lua::new_with(StdLib::ALL_SAFE ^ StdLib::IO, LuaOptions::default())
+            .expect("safe Lua standard libraries load");
+        Self(Mutex::new(lua))
+    }
+
+    /// Lock the state. A panic inside a script is caught before the guard drops,
+    /// so a poisoned lock only means a host bug, and the state is still usable.
+    pub fn lock(&self) -> MutexGuard<'_, Lua> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for LuaVm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Resource)]
 pub struct LuaHost {
-    pub lua: Lua,
+    pub lua: LuaVm,
     pub scripts: HashMap<String, LuaScript>,
     pub execution_env: LuaExecutionEnv,
+    pub coroutines: Vec<LuaCoroutine>, // long-running scripts, see lua_coroutines
+    pub next_coroutine_id: u64,
+    pub faults: ModFaults, // failing mods are suspended rather than taking the tick down
+    pub vfs: HashMap<String, ModVfs>, // mod id -> its read-only files, for mods granted fs_read
 }
 
 #[derive(Clone)]
@@ -31,23 +70,24 @@ impl Default for LuaHost {
 
 impl LuaHost {
     pub fn new() -> Self {
-        let lua = Lua::new();
-        
         Self {
-            lua,
+            lua: LuaVm::new(),
             scripts: HashMap::new(),
             execution_env: LuaExecutionEnv {
                 sandbox_mode: true,
                 instruction_budget: 200_000,
                 memory_limit_mib: 32,
             },
+            coroutines: Vec::new(),
+            next_coroutine_id: 1,
+            faults: ModFaults::default(),
+            vfs: HashMap::new(),
         }
     }
 
     pub fn load_script(&mut self, mod_id: &str, event_name: &str, script_content: String) -> Result<()> {
         // Validate the script by trying to compile it
-        let lua = &self.lua;
-        let _: Function = lua.load(&script_content).eval()?;
+        let _: Function = self.lua.lock().load(&script_content).eval()?;
         
         let script = LuaScript {
             mod_id: mod_id.to_string(),
@@ -57,41 +97,168 @@ impl LuaHost {
         
         let key = format!("{}:{}", mod_id, event_name);
         self.scripts.insert(key, script);
+        self.faults.clear(mod_id);
         Ok(())
     }
 
+    /// Load the `lua_events` files a mod manifest lists (e.g. `on_tick.lua`) from its directory;
+    /// each file's stem is the event it handles. Returns how many scripts were loaded.
+    pub fn load_mod_events(&mut self, mod_id: &str, mod_dir: &Path, lua_events: &[String]) -> Result<usize> {
+        for file in lua_events {
+            let event_name = Path::new(file).file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| anyhow::anyhow!("Bad Lua event file name: {}", file))?;
+            let content = std::fs::read_to_string(mod_dir.join(file))
+                .map_err(|e| anyhow::anyhow!("Can't read {} for mod {}: {}", file, mod_id, e))?;
+            self.load_script(mod_id, event_name, content)?;
+        }
+        Ok(lua_events.len())
+    }
+
     pub fn call_event_hook(&mut self, mod_id: &str, event_name: &str) -> Result<()> {
+        self.call_event_hook_with(mod_id, event_name, &serde_json::Value::Null)
+    }
+
+    /// Call a hook with one argument, converted to a Lua value, within the instruction budget
+    pub fn call_event_hook_with(&mut self, mod_id: &str, event_name: &str, arg: &serde_json::Value) -> Result<()> {
         let key = format!("{}:{}", mod_id, event_name);
         let script = self.scripts.get(&key)
             .ok_or_else(|| anyhow::anyhow!("Script not found: {}", key))?;
-        
+        self.faults.check(mod_id)?;
+
         // Execute the script by compiling and running it
-        let lua = &self.lua;
-        let function: Function = lua.load(&script.script_content).eval()?;
-        function.call::<_, ()>(())?;
-        
-        Ok(())
+        let lua = self.lua.lock();
+        lua.set_hook(budget_triggers(), budget_hook(self.execution_env.instruction_budget));
+        let outcome = catch_mod_panic(|| {
+            bind_lua_vfs(&lua, mod_id, self.vfs.get(mod_id))?;
+            let function: Function = lua.load(&script.script_content).eval()?;
+            function.call::<_, ()>(super::json_to_lua(&lua, arg)?)?;
+            Ok(())
+        });
+        lua.remove_hook();
+        drop(lua);
+        self.faults.record(mod_id, outcome)
+    }
+
+    /// Mods with a loaded script for `event_name`, in a stable order
+    pub fn mods_with_hook(&self, event_name: &str) -> Vec<String> {
+        let mut mods: Vec<String> = self.scripts.values()
+            .filter(|s| s.event_name == event_name)
+            .map(|s| s.mod_id.clone())
+            .collect();
+        mods.sort();
+        mods
     }
 
     pub fn unload_script(&mut self, mod_id: &str, event_name: &str) {
         let key = format!("{}:{}", mod_id, event_name);
         self.scripts.remove(&key);
     }
+
+    /// Drop every script, coroutine and mounted file a mod has in this host
+    pub fn unload_mod(&mut self, mod_id: &str) {
+        self.scripts.retain(|_, s| s.mod_id != mod_id);
+        self.coroutines.retain(|c| c.mod_id != mod_id);
+        self.vfs.remove(mod_id);
+    }
+}
+
+/// Resume the coroutines that are due this tick
+pub fn update_lua_host_system(
+    mut lua_host: ResMut<LuaHost>,
+    clock: Res<SimClock>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    for (id, step) in lua_host.resume_coroutines(now_tick) {
+        if let CoroutineStep::Failed(message) = step {
+            eprintln!("Lua coroutine {} failed: {}", id, message);
+        }
+    }
 }
 
-// TODO: Implement Lua host systems when thread safety is resolved
-// pub fn update_lua_host_system(
-//     mut lua_host: ResMut<LuaHost>,
-//     time: Res<Time>,
-// ) {
-//     // Update Lua host state
-//     // This would handle instruction counting, memory management, etc.
-// }
-
-// pub fn execute_lua_events_system(
-//     mut lua_host: ResMut<LuaHost>,
-//     time: Res<Time>,
-// ) {
-//     // Execute Lua event hooks
-//     // This would iterate through registered event hooks and call them
-// }
\ No newline at end of file
+/// Call every mod's `on_tick` hook, then its `on_fault` hook for each worker fault reported
+/// this tick. Suspended mods are skipped; a failing hook only counts against its own mod.
+pub fn execute_lua_events_system(
+    mut lua_host: ResMut<LuaHost>,
+    clock: Res<SimClock>,
+    mut reports: EventReader<WorkerReport>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    lua_host.faults.tick = now_tick;
+    let faults: Vec<serde_json::Value> = reports.read()
+        .filter_map(|report| match report {
+            WorkerReport::Fault { worker_id, op, kind, .. } => Some(serde_json::json!({
+                "tick": now_tick,
+                "worker_id": worker_id,
+                "op": op,
+                "kind": kind,
+            })),
+            _ => None,
+        })
+        .collect();
+
+    let tick_arg = serde_json::json!({ "tick": now_tick });
+    let calls = std::iter::once((ON_TICK_EVENT, &tick_arg))
+        .chain(faults.iter().map(|fault| (ON_FAULT_EVENT, fault)));
+    for (event_name, arg) in calls {
+        for mod_id in lua_host.mods_with_hook(event_name) {
+            if lua_host.faults.is_suspended(&mod_id) {
+                continue;
+            }
+            if let Err(e) = lua_host.call_event_hook_with(&mod_id, event_name, arg) {
+                eprintln!("Lua {} hook of mod {} failed: {:#}", event_name, mod_id, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runaway_hook_hits_budget() {
+        let mut host = LuaHost::new();
+        host.execution_env.instruction_budget = 10_000;
+        host.load_script("spin_mod", ON_TICK_EVENT, "return function() while true do end end".to_string()).unwrap();
+        let err = host.call_event_hook("spin_mod", ON_TICK_EVENT).unwrap_err();
+        assert!(format!("{:#}", err).contains("instruction budget"), "{:#}", err);
+    }
+
+    #[test]
+    fn test_mod_events_loaded_from_disk() {
+        let dir = std::env::temp_dir().join(format!("colony_lua_mod_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("on_tick.lua"), "return function(e) assert(e.tick >= 0) end").unwrap();
+        std::fs::write(dir.join("on_fault.lua"), "return function(e) assert(e.kind) end").unwrap();
+
+        let mut host = LuaHost::new();
+        let events = vec!["on_tick.lua".to_string(), "on_fault.lua".to_string()];
+        assert_eq!(host.load_mod_events("com.example.hooks", &dir, &events).unwrap(), 2);
+        assert_eq!(host.mods_with_hook(ON_FAULT_EVENT), vec!["com.example.hooks".to_string()]);
+        assert!(host.load_mod_events("com.example.hooks", &dir, &["missing.lua".to_string()]).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_hooks_run_in_the_schedule() {
+        let mut app = App::new();
+        app.add_event::<WorkerReport>()
+            .insert_resource(SimClock { tick_scale: crate::TickScale::RealTime, now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH })
+            .insert_resource(LuaHost::new())
+            .add_systems(Update, (update_lua_host_system, execute_lua_events_system));
+        {
+            let mut host = app.world_mut().resource_mut::<LuaHost>();
+            host.load_script("counter", ON_TICK_EVENT, "return function(e) ticks = (ticks or 0) + 1 end".to_string()).unwrap();
+            host.load_script("counter", ON_FAULT_EVENT, "return function(e) last_fault = e.kind end".to_string()).unwrap();
+        }
+        app.world_mut().send_event(WorkerReport::Fault { worker_id: 1, job_id: 1, op: crate::Op::Decode, kind: crate::FaultKind::Transient });
+        app.update();
+        app.update();
+
+        let host = app.world().resource::<LuaHost>();
+        let lua = host.lua.lock();
+        assert_eq!(lua.globals().get::<_, i64>("ticks").unwrap(), 2);
+        assert_eq!(lua.globals().get::<_, String>("last_fault").unwrap(), "Transient");
+    }
+}
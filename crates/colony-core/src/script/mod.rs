@@ -1,5 +1,19 @@
 pub mod wasm_host;
+pub mod wasm_component;
+pub mod wasm_scheduler;
 pub mod lua_host;
+pub mod lua_coroutines;
+pub mod mod_faults;
+pub mod mod_vfs;
+#[cfg(feature = "ffi_scheduler")]
+pub mod ffi_scheduler;
 
 pub use wasm_host::*;
-pub use lua_host::*;
\ No newline at end of file
+pub use wasm_component::*;
+pub use wasm_scheduler::*;
+pub use lua_host::*;
+pub use lua_coroutines::*;
+pub use mod_faults::*;
+pub use mod_vfs::*;
+#[cfg(feature = "ffi_scheduler")]
+pub use ffi_scheduler::*;
@@ -0,0 +1,222 @@
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, VecDeque};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use anyhow::Result;
+
+/// Consecutive failed calls after which a mod is suspended
+pub const DEFAULT_SUSPEND_AFTER: u32 = 3;
+const MAX_RECENT_FAULTS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModFaultKind {
+    Error, // the call returned an error: bad export, script error, budget exhausted
+    Trap,  // WASM trap: unreachable, out-of-bounds access, stack overflow
+    Panic, // a panic unwound out of the host or the runtime
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModFault {
+    pub mod_id: String,
+    pub kind: ModFaultKind,
+    pub message: String,
+    pub tick: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModHealth {
+    pub consecutive_failures: u32,
+    pub total_faults: u64,
+    pub suspended: bool,
+    pub last_error: Option<String>,
+}
+
+/// A panic caught while running mod code, carried as an error so it can be told apart
+#[derive(Debug)]
+pub struct ModPanic(pub String);
+
+impl std::fmt::Display for ModPanic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "panicked: {}", self.0)
+    }
+}
+
+impl std::error::Error for ModPanic {}
+
+/// Run mod code, turning a panic into a `ModPanic` error instead of unwinding through the tick
+pub fn catch_mod_panic<T>(call: impl FnOnce() -> Result<T>) -> Result<T> {
+    catch_unwind(AssertUnwindSafe(call)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic payload".to_string());
+        Err(ModPanic(message).into())
+    })
+}
+
+impl ModFaultKind {
+    pub fn of(error: &anyhow::Error) -> Self {
+        if error.is::<ModPanic>() {
+            ModFaultKind::Panic
+        } else if error.downcast_ref::<wasmtime::Trap>().is_some() {
+            ModFaultKind::Trap
+        } else {
+            ModFaultKind::Error
+        }
+    }
+}
+
+/// Per-mod failure bookkeeping for a script host. A mod that fails `suspend_after` calls in a row
+/// is suspended: its calls are refused until it is reloaded or resumed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModFaults {
+    pub suspend_after: u32,
+    pub tick: u64, // stamped on recorded faults, kept current by the host
+    pub health: HashMap<String, ModHealth>,
+    pub recent: VecDeque<ModFault>,
+}
+
+impl Default for ModFaults {
+    fn default() -> Self {
+        Self {
+            suspend_after: DEFAULT_SUSPEND_AFTER,
+            tick: 0,
+            health: HashMap::new(),
+            recent: VecDeque::new(),
+        }
+    }
+}
+
+impl ModFaults {
+    pub fn is_suspended(&self, mod_id: &str) -> bool {
+        self.health.get(mod_id).is_some_and(|h| h.suspended)
+    }
+
+    /// Refuse a call into a suspended mod
+    pub fn check(&self, mod_id: &str) -> Result<()> {
+        match self.health.get(mod_id) {
+            Some(health) if health.suspended => Err(anyhow::anyhow!(
+                "Mod {} is suspended after {} consecutive failures (last: {})",
+                mod_id,
+                health.consecutive_failures,
+                health.last_error.as_deref().unwrap_or("unknown")
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Note how a call went and pass its outcome through
+    pub fn record<T>(&mut self, mod_id: &str, outcome: Result<T>) -> Result<T> {
+        match &outcome {
+            Ok(_) => self.record_success(mod_id),
+            Err(e) => {
+                self.record_failure(mod_id, ModFaultKind::of(e), &format!("{:#}", e));
+            }
+        }
+        outcome
+    }
+
+    pub fn record_success(&mut self, mod_id: &str) {
+        if let Some(health) = self.health.get_mut(mod_id) {
+            health.consecutive_failures = 0;
+        }
+    }
+
+    /// Returns true if this failure suspended the mod
+    pub fn record_failure(&mut self, mod_id: &str, kind: ModFaultKind, message: &str) -> bool {
+        let health = self.health.entry(mod_id.to_string()).or_default();
+        health.consecutive_failures += 1;
+        health.total_faults += 1;
+        health.last_error = Some(message.to_string());
+        let newly_suspended = !health.suspended && health.consecutive_failures >= self.suspend_after;
+        if newly_suspended {
+            health.suspended = true;
+            eprintln!("Suspending mod {} after {} consecutive failures: {}", mod_id, health.consecutive_failures, message);
+        }
+
+        self.recent.push_back(ModFault {
+            mod_id: mod_id.to_string(),
+            kind,
+            message: message.to_string(),
+            tick: self.tick,
+        });
+        while self.recent.len() > MAX_RECENT_FAULTS {
+            self.recent.pop_front();
+        }
+        newly_suspended
+    }
+
+    /// Let a suspended mod run again; returns false if it wasn't suspended
+    pub fn resume(&mut self, mod_id: &str) -> bool {
+        match self.health.get_mut(mod_id) {
+            Some(health) if health.suspended => {
+                health.suspended = false;
+                health.consecutive_failures = 0;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Forget a mod's failures, e.g. once a new build of it is loaded
+    pub fn clear(&mut self, mod_id: &str) {
+        self.health.remove(mod_id);
+    }
+
+    pub fn suspended_mods(&self) -> Vec<String> {
+        let mut mods: Vec<String> = self.health.iter()
+            .filter(|(_, h)| h.suspended)
+            .map(|(id, _)| id.clone())
+            .collect();
+        mods.sort();
+        mods
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LuaHost;
+
+    #[test]
+    fn test_panics_are_caught_and_suspend_after_k() {
+        let mut faults = ModFaults::default();
+        for attempt in 1..=DEFAULT_SUSPEND_AFTER {
+            assert!(faults.check("crashy").is_ok());
+            let outcome: Result<()> = faults.record("crashy", catch_mod_panic(|| panic!("boom {}", attempt)));
+            assert!(outcome.unwrap_err().to_string().contains("boom"));
+        }
+        assert!(faults.is_suspended("crashy"));
+        assert!(faults.check("crashy").is_err());
+        assert_eq!(faults.recent.back().unwrap().kind, ModFaultKind::Panic);
+
+        assert!(faults.resume("crashy"));
+        assert!(faults.check("crashy").is_ok());
+    }
+
+    #[test]
+    fn test_success_resets_the_streak() {
+        let mut faults = ModFaults::default();
+        faults.record_failure("flaky", ModFaultKind::Error, "nope");
+        faults.record_failure("flaky", ModFaultKind::Error, "nope");
+        faults.record_success("flaky");
+        faults.record_failure("flaky", ModFaultKind::Error, "nope");
+        assert!(!faults.is_suspended("flaky"));
+        assert_eq!(faults.health["flaky"].total_faults, 3);
+    }
+
+    #[test]
+    fn test_failing_lua_hook_is_suspended_and_others_keep_running() {
+        let mut host = LuaHost::new();
+        host.load_script("bad_mod", "on_tick", "return function() error('broken') end".to_string()).unwrap();
+        host.load_script("good_mod", "on_tick", "return function() end".to_string()).unwrap();
+
+        for _ in 0..DEFAULT_SUSPEND_AFTER + 2 {
+            assert!(host.call_event_hook("bad_mod", "on_tick").is_err());
+            assert!(host.call_event_hook("good_mod", "on_tick").is_ok());
+        }
+        assert_eq!(host.faults.suspended_mods(), vec!["bad_mod".to_string()]);
+        // Refused calls are not faults of their own
+        assert_eq!(host.faults.health["bad_mod"].total_faults, DEFAULT_SUSPEND_AFTER as u64);
+    }
+}
@@ -0,0 +1,185 @@
+use colony_modsdk::abi::{WasmReturnCode, MAX_VFS_FILE_SIZE, MAX_VFS_MOUNT_SIZE};
+use colony_modsdk::ModManifest;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+use super::{LuaHost, WasmHost};
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum VfsError {
+    #[error("mod {0} has no fs_read capability")]
+    NotPermitted(String),
+    #[error("path '{0}' leaves the mod directory")]
+    Traversal(String),
+    #[error("no file '{0}' in the mod directory")]
+    NotFound(String),
+    #[error("'{path}' is {bytes} bytes, over the {limit} byte limit")]
+    TooLarge { path: String, bytes: u64, limit: u64 },
+    #[error("can't read '{path}': {message}")]
+    Io { path: String, message: String },
+}
+
+impl VfsError {
+    /// What the WASM host imports return for this error
+    pub fn return_code(&self) -> WasmReturnCode {
+        match self {
+            VfsError::NotPermitted(_) => WasmReturnCode::InvalidContext,
+            VfsError::Traversal(_) => WasmReturnCode::MemoryViolation,
+            VfsError::NotFound(_) => WasmReturnCode::InvalidInput,
+            VfsError::TooLarge { .. } => WasmReturnCode::ResourceExhaustion,
+            VfsError::Io { .. } => WasmReturnCode::Error,
+        }
+    }
+}
+
+/// Read-only view of one mod's directory. Paths are relative to the mod root; absolute
+/// paths, `..` and symlinks that resolve outside the root are refused, and no file larger
+/// than `max_file_bytes` is read.
+#[derive(Debug, Clone)]
+pub struct ModVfs {
+    root: PathBuf, // canonical
+    pub max_file_bytes: u64,
+}
+
+impl ModVfs {
+    /// Mount a mod directory, refusing one whose files add up to more than `MAX_VFS_MOUNT_SIZE`
+    pub fn mount(root: &Path) -> Result<Self, VfsError> {
+        let io_err = |e: std::io::Error| VfsError::Io { path: root.display().to_string(), message: e.to_string() };
+        let root = root.canonicalize().map_err(io_err)?;
+        let total: u64 = WalkDir::new(&root)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.metadata().ok())
+            .filter(|m| m.is_file())
+            .map(|m| m.len())
+            .sum();
+        if total > MAX_VFS_MOUNT_SIZE {
+            return Err(VfsError::TooLarge { path: root.display().to_string(), bytes: total, limit: MAX_VFS_MOUNT_SIZE });
+        }
+        Ok(Self { root, max_file_bytes: MAX_VFS_FILE_SIZE })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The file `path` names inside the mod directory
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, VfsError> {
+        let relative = Path::new(path);
+        if path.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+            return Err(VfsError::Traversal(path.to_string()));
+        }
+        let resolved = self.root.join(relative).canonicalize()
+            .map_err(|_| VfsError::NotFound(path.to_string()))?;
+        // A symlink inside the mod can still point out of it
+        if !resolved.starts_with(&self.root) {
+            return Err(VfsError::Traversal(path.to_string()));
+        }
+        if !resolved.is_file() {
+            return Err(VfsError::NotFound(path.to_string()));
+        }
+        Ok(resolved)
+    }
+
+    /// Size of a file, checked against the per-file limit
+    pub fn file_len(&self, path: &str) -> Result<u64, VfsError> {
+        let resolved = self.resolve(path)?;
+        let bytes = std::fs::metadata(&resolved)
+            .map_err(|e| VfsError::Io { path: path.to_string(), message: e.to_string() })?
+            .len();
+        if bytes > self.max_file_bytes {
+            return Err(VfsError::TooLarge { path: path.to_string(), bytes, limit: self.max_file_bytes });
+        }
+        Ok(bytes)
+    }
+
+    pub fn read(&self, path: &str) -> Result<Vec<u8>, VfsError> {
+        self.file_len(path)?;
+        std::fs::read(self.resolve(path)?)
+            .map_err(|e| VfsError::Io { path: path.to_string(), message: e.to_string() })
+    }
+}
+
+/// Install the `colony` table a Lua script sees while it runs on behalf of `mod_id`:
+/// `colony.read_file(path)` returns a file's bytes as a string, or raises if the mod lacks
+/// fs_read or the path is refused
+pub(super) fn bind_lua_vfs(lua: &mlua::Lua, mod_id: &str, vfs: Option<&ModVfs>) -> mlua::Result<()> {
+    let vfs = vfs.cloned();
+    let mod_id = mod_id.to_string();
+    let colony = lua.create_table()?;
+    colony.set("read_file", lua.create_function(move |lua, path: String| {
+        let vfs = vfs.as_ref().ok_or_else(|| VfsError::NotPermitted(mod_id.clone()));
+        let bytes = vfs.and_then(|vfs| vfs.read(&path)).map_err(|e| mlua::Error::runtime(e.to_string()))?;
+        lua.create_string(&bytes)
+    })?)?;
+    lua.globals().set("colony", colony)
+}
+
+/// Give a mod's WASM ops and Lua scripts read access to its directory, if its manifest
+/// grants `fs_read`. Returns whether anything was mounted.
+pub fn mount_mod_vfs(manifest: &ModManifest, mod_dir: &Path, wasm_host: &mut WasmHost, lua_host: &mut LuaHost) -> Result<bool, VfsError> {
+    if !manifest.capabilities.fs_read {
+        wasm_host.store.data_mut().vfs.remove(&manifest.id);
+        lua_host.vfs.remove(&manifest.id);
+        return Ok(false);
+    }
+    let vfs = ModVfs::mount(mod_dir)?;
+    wasm_host.store.data_mut().vfs.insert(manifest.id.clone(), vfs.clone());
+    lua_host.vfs.insert(manifest.id.clone(), vfs);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mod_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("colony_vfs_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(dir.join("data")).unwrap();
+        std::fs::write(dir.join("data/table.csv"), "k,v\n1,2\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_reads_stay_inside_the_mod() {
+        let dir = mod_dir("paths");
+        let vfs = ModVfs::mount(&dir).unwrap();
+
+        assert_eq!(vfs.read("data/table.csv").unwrap(), b"k,v\n1,2\n");
+        assert_eq!(vfs.read("./data/table.csv").unwrap().len(), 8);
+        for bad in ["../other_mod/mod.toml", "data/../../etc/passwd", "/etc/passwd", ""] {
+            assert!(matches!(vfs.read(bad), Err(VfsError::Traversal(_))), "{}", bad);
+        }
+        assert!(matches!(vfs.read("data/missing.csv"), Err(VfsError::NotFound(_))));
+        assert!(matches!(vfs.read("data"), Err(VfsError::NotFound(_))));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_file_size_limit() {
+        let dir = mod_dir("limits");
+        let mut vfs = ModVfs::mount(&dir).unwrap();
+        vfs.max_file_bytes = 4;
+        let err = vfs.read("data/table.csv").unwrap_err();
+        assert_eq!(err.return_code(), WasmReturnCode::ResourceExhaustion);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_mount_needs_capability() {
+        let dir = mod_dir("caps");
+        let mut manifest = ModManifest::new("com.example.tables".into(), "Tables".into());
+        let (mut wasm_host, mut lua_host) = (WasmHost::new(), LuaHost::new());
+        assert!(!mount_mod_vfs(&manifest, &dir, &mut wasm_host, &mut lua_host).unwrap());
+        assert!(lua_host.vfs.is_empty());
+
+        manifest.capabilities.fs_read = true;
+        assert!(mount_mod_vfs(&manifest, &dir, &mut wasm_host, &mut lua_host).unwrap());
+        assert!(wasm_host.store.data().vfs.contains_key("com.example.tables"));
+        lua_host.load_script("com.example.tables", "on_tick", "return function() assert(colony.read_file('data/table.csv') == 'k,v\\n1,2\\n') end".to_string()).unwrap();
+        lua_host.load_script("com.example.other", "on_tick", "return function() colony.read_file('data/table.csv') end".to_string()).unwrap();
+        assert!(lua_host.call_event_hook("com.example.tables", "on_tick").is_ok());
+        let err = lua_host.call_event_hook("com.example.other", "on_tick").unwrap_err();
+        assert!(format!("{:#}", err).contains("fs_read"), "{:#}", err);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,75 @@
+use wasmtime::component::{Component, ComponentType, Lift, Linker, Lower};
+use wasmtime::StoreContextMut;
+use colony_modsdk::WasmOpSpec;
+use colony_modsdk::abi::WasmReturnCode;
+use anyhow::Result;
+use super::{WasmContext, WasmHost};
+
+// Host-side mirrors of the records in colony-modsdk/wit/colony-op.wit
+
+#[derive(ComponentType, Lift, Lower, Debug, Clone, PartialEq)]
+#[component(record)]
+pub struct WitOpCtx {
+    #[component(name = "mod-id")]
+    pub mod_id: String,
+    #[component(name = "op-name")]
+    pub op_name: String,
+    #[component(name = "job-id")]
+    pub job_id: Option<u64>,
+    pub tick: u64,
+}
+
+#[derive(ComponentType, Lift, Lower, Debug, Clone, PartialEq)]
+#[component(record)]
+pub struct WitPayload {
+    pub data: Vec<u8>,
+    pub meta: Vec<u8>,
+}
+
+#[derive(ComponentType, Lift, Lower, Debug, Clone, PartialEq)]
+#[component(record)]
+pub struct WitOpResult {
+    pub code: i32,
+    pub output: WitPayload,
+}
+
+impl WasmHost {
+    /// Run the `process` export of a `colony:op` component
+    pub(crate) fn execute_component(&mut self, component: &Component, op_spec: &WasmOpSpec, input: &[u8]) -> Result<Vec<u8>> {
+        let mut linker: Linker<WasmContext> = Linker::new(&self.engine);
+        let mut root = linker.root();
+        root.func_wrap("report-metric", |mut store: StoreContextMut<'_, WasmContext>, (name, value): (String, f64)| {
+            Ok((store.data_mut().record_metric(&name, value),))
+        })?;
+        root.func_wrap("log", |store: StoreContextMut<'_, WasmContext>, (level, message): (i32, String)| {
+            let level = match level {
+                0 => "debug",
+                1 => "info",
+                2 => "warn",
+                _ => "error",
+            };
+            println!("[{}/{}] {}: {}", store.data().mod_id, store.data().op_name, level, message);
+            Ok(())
+        })?;
+
+        let instance = linker.instantiate(&mut self.store, component)?;
+        let process = instance.get_typed_func::<(WitOpCtx, WitPayload), (WitOpResult,)>(&mut self.store, "process")?;
+
+        let context = self.store.data();
+        let ctx = WitOpCtx {
+            mod_id: context.mod_id.clone(),
+            op_name: context.op_name.clone(),
+            job_id: context.job_id,
+            tick: context.tick,
+        };
+        let payload = WitPayload { data: input.to_vec(), meta: Vec::new() };
+        let (result,) = process.call(&mut self.store, (ctx, payload))?;
+        process.post_return(&mut self.store)?;
+
+        match WasmReturnCode::from_i32(result.code) {
+            Some(WasmReturnCode::Success) => Ok(result.output.data),
+            Some(code) => Err(anyhow::anyhow!("Op {} returned {:?}", op_spec.name, code)),
+            None => Err(anyhow::anyhow!("Op {} returned unknown code {}", op_spec.name, result.code)),
+        }
+    }
+}
@@ -1,15 +1,21 @@
 use bevy::prelude::*;
 use wasmtime::*;
+use wasmtime::component::Component;
 use colony_modsdk::WasmOpSpec;
+use colony_modsdk::abi::{WasmAbi, WasmReturnCode, MAX_METRIC_NAME_LEN, MAX_METRICS_PER_CALL};
 use std::collections::HashMap;
 use anyhow::Result;
+use crate::{KpiRingBuffer, MetricRegistry, SimClock};
+use super::{catch_mod_panic, ModFaultKind, ModFaults, ModVfs};
 
 #[derive(Resource)]
 pub struct WasmHost {
     pub engine: Engine,
     pub store: Store<WasmContext>,
     pub modules: HashMap<String, Module>,
+    pub components: HashMap<String, Component>, // mods built against the `colony:op` WIT world
     pub execution_env: WasmExecutionEnv,
+    pub faults: ModFaults, // traps and panics are charged to the op's mod, which is suspended after repeated failures
 }
 
 #[derive(Clone)]
@@ -17,6 +23,60 @@ pub struct WasmContext {
     pub fuel_limit: u64,
     pub memory_limit_mib: u32,
     pub mod_id: String,
+    pub op_name: String,
+    pub job_id: Option<u64>,
+    pub tick: u64,
+    pub reported_this_call: usize,
+    pub metrics: Vec<OpMetric>, // reported through the host ABI, drained into the KPI buffer
+    pub vfs: HashMap<String, ModVfs>, // mod id -> its read-only files, for mods granted fs_read
+}
+
+/// A measurement a WASM op reported about the job it just processed
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpMetric {
+    pub mod_id: String,
+    pub op_name: String,
+    pub job_id: Option<u64>,
+    pub name: String,
+    pub value: f32,
+}
+
+impl OpMetric {
+    /// KPI name Black Swan triggers use to refer to this metric, e.g. "com.example.anomaly:anomaly_score"
+    pub fn kpi_name(&self) -> String {
+        mod_metric_name(&self.mod_id, &self.name)
+    }
+}
+
+pub fn mod_metric_name(mod_id: &str, metric: &str) -> String {
+    format!("{}:{}", mod_id, metric)
+}
+
+impl WasmContext {
+    /// Backs the `colony.report_metric` host import; returns a WasmReturnCode
+    pub fn record_metric(&mut self, name: &str, value: f64) -> i32 {
+        if name.is_empty()
+            || name.len() > MAX_METRIC_NAME_LEN
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        {
+            return WasmReturnCode::InvalidInput as i32;
+        }
+        if !value.is_finite() {
+            return WasmReturnCode::InvalidInput as i32;
+        }
+        if self.reported_this_call >= MAX_METRICS_PER_CALL {
+            return WasmReturnCode::ResourceExhaustion as i32;
+        }
+        self.reported_this_call += 1;
+        self.metrics.push(OpMetric {
+            mod_id: self.mod_id.clone(),
+            op_name: self.op_name.clone(),
+            job_id: self.job_id,
+            name: name.to_string(),
+            value: value as f32,
+        });
+        WasmReturnCode::Success as i32
+    }
 }
 
 #[derive(Clone)]
@@ -34,11 +94,19 @@ impl Default for WasmHost {
 
 impl WasmHost {
     pub fn new() -> Self {
-        let engine = Engine::default();
+        let mut config = Config::new();
+        config.wasm_component_model(true);
+        let engine = Engine::new(&config).unwrap_or_default();
         let context = WasmContext {
             fuel_limit: 5_000_000,
             memory_limit_mib: 64,
             mod_id: String::new(),
+            op_name: String::new(),
+            job_id: None,
+            tick: 0,
+            reported_this_call: 0,
+            metrics: Vec::new(),
+            vfs: HashMap::new(),
         };
         let store = Store::new(&engine, context);
         
@@ -46,29 +114,85 @@ impl WasmHost {
             engine,
             store,
             modules: HashMap::new(),
+            components: HashMap::new(),
             execution_env: WasmExecutionEnv {
                 fuel_limit: 5_000_000,
                 memory_limit_mib: 64,
                 sandbox_mode: true,
             },
+            faults: ModFaults::default(),
         }
     }
 
+    /// Load a core module (legacy ABI) or a `colony:op` component, told apart by the preamble
     pub fn load_module(&mut self, mod_id: &str, wasm_bytes: &[u8]) -> Result<()> {
-        let module = Module::new(&self.engine, wasm_bytes)?;
-        self.modules.insert(mod_id.to_string(), module);
+        if WasmAbi::detect(wasm_bytes) == Some(WasmAbi::Component) {
+            let component = Component::new(&self.engine, wasm_bytes)?;
+            self.modules.remove(mod_id);
+            self.components.insert(mod_id.to_string(), component);
+        } else {
+            let module = Module::new(&self.engine, wasm_bytes)?;
+            self.components.remove(mod_id);
+            self.modules.insert(mod_id.to_string(), module);
+        }
+        self.faults.clear(mod_id);
         Ok(())
     }
 
+    pub fn abi_of(&self, mod_id: &str) -> Option<WasmAbi> {
+        if self.components.contains_key(mod_id) {
+            Some(WasmAbi::Component)
+        } else {
+            self.modules.contains_key(mod_id).then_some(WasmAbi::Legacy)
+        }
+    }
+
     pub fn execute_op(&mut self, mod_id: &str, op_spec: &WasmOpSpec, input: &[u8]) -> Result<Vec<u8>> {
-        let module = self.modules.get(mod_id)
+        self.execute_op_for_job(mod_id, op_spec, None, input)
+    }
+
+    /// Run an op on behalf of a job so any metrics it reports are attributed to that job.
+    /// A trap or panic fails the call, not the tick; a mod failing repeatedly is suspended.
+    pub fn execute_op_for_job(&mut self, mod_id: &str, op_spec: &WasmOpSpec, job_id: Option<u64>, input: &[u8]) -> Result<Vec<u8>> {
+        self.faults.check(mod_id)?;
+        let outcome = catch_mod_panic(|| self.call_op(mod_id, op_spec, job_id, input));
+        if let Err(e) = &outcome {
+            if ModFaultKind::of(e) == ModFaultKind::Panic {
+                // A panic can leave the store mid-call, so carry on with a fresh one
+                let context = self.store.data().clone();
+                self.store = Store::new(&self.engine, context);
+            }
+        }
+        self.faults.record(mod_id, outcome)
+    }
+
+    fn call_op(&mut self, mod_id: &str, op_spec: &WasmOpSpec, job_id: Option<u64>, input: &[u8]) -> Result<Vec<u8>> {
+        let abi = self.abi_of(mod_id)
             .ok_or_else(|| anyhow::anyhow!("Module not found: {}", mod_id))?;
-        
+        if abi != op_spec.abi {
+            return Err(anyhow::anyhow!("Op {} expects the {:?} ABI but {} was built for {:?}", op_spec.name, op_spec.abi, mod_id, abi));
+        }
+
         // Set fuel limit (TODO: Implement fuel system)
         // self.store.add_fuel(self.execution_env.fuel_limit)?;
-        
+
+        let context = self.store.data_mut();
+        context.mod_id = mod_id.to_string();
+        context.op_name = op_spec.name.clone();
+        context.job_id = job_id;
+        context.reported_this_call = 0;
+
+        if let Some(component) = self.components.get(mod_id).cloned() {
+            return self.execute_component(&component, op_spec, input);
+        }
+        let module = &self.modules[mod_id];
+
         // Create instance and execute
-        let instance = Instance::new(&mut self.store, module, &[])?;
+        let mut linker = Linker::new(&self.engine);
+        linker.func_wrap("colony", "report_metric", report_metric)?;
+        linker.func_wrap("colony", "file_len", file_len)?;
+        linker.func_wrap("colony", "read_file", read_file)?;
+        let instance = linker.instantiate(&mut self.store, module)?;
         let func = instance.get_typed_func::<i32, i32>(&mut self.store, &op_spec.name)?;
         
         // Execute the function (simplified)
@@ -80,13 +204,187 @@ impl WasmHost {
 
     pub fn unload_module(&mut self, mod_id: &str) {
         self.modules.remove(mod_id);
+        self.components.remove(mod_id);
+    }
+
+    pub fn drain_metrics(&mut self) -> Vec<OpMetric> {
+        std::mem::take(&mut self.store.data_mut().metrics)
     }
 }
 
+/// Host import: `report_metric(name_ptr, name_len, value) -> i32`, name read from the op's exported memory
+fn report_metric(mut caller: Caller<'_, WasmContext>, name_ptr: i32, name_len: i32, value: f64) -> i32 {
+    let Some(memory) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
+        return WasmReturnCode::InvalidContext as i32;
+    };
+    let (start, len) = (name_ptr as u32 as usize, name_len as u32 as usize);
+    let name = match memory.data(&caller).get(start..start.saturating_add(len)) {
+        Some(bytes) => match std::str::from_utf8(bytes) {
+            Ok(name) => name.to_string(),
+            Err(_) => return WasmReturnCode::InvalidInput as i32,
+        },
+        None => return WasmReturnCode::MemoryViolation as i32,
+    };
+    caller.data_mut().record_metric(&name, value)
+}
+
+/// Read `len` bytes of UTF-8 at `ptr` from the op's exported memory
+fn read_guest_str(caller: &mut Caller<'_, WasmContext>, ptr: i32, len: i32) -> Result<String, WasmReturnCode> {
+    let memory = caller.get_export("memory").and_then(|e| e.into_memory()).ok_or(WasmReturnCode::InvalidContext)?;
+    let (start, len) = (ptr as u32 as usize, len as u32 as usize);
+    let bytes = memory.data(&*caller).get(start..start.saturating_add(len)).ok_or(WasmReturnCode::MemoryViolation)?;
+    std::str::from_utf8(bytes).map(str::to_string).map_err(|_| WasmReturnCode::InvalidInput)
+}
+
+fn write_guest(caller: &mut Caller<'_, WasmContext>, ptr: i32, bytes: &[u8]) -> Result<(), WasmReturnCode> {
+    let memory = caller.get_export("memory").and_then(|e| e.into_memory()).ok_or(WasmReturnCode::InvalidContext)?;
+    let start = ptr as u32 as usize;
+    memory.data_mut(&mut *caller).get_mut(start..start.saturating_add(bytes.len()))
+        .ok_or(WasmReturnCode::MemoryViolation)?
+        .copy_from_slice(bytes);
+    Ok(())
+}
+
+impl WasmContext {
+    /// The calling mod's files, if it was granted fs_read. Mod ops run under their `<mod id>/<op>` id.
+    fn mod_vfs(&self) -> Result<&ModVfs, WasmReturnCode> {
+        self.vfs.get(crate::op_mod_id(&self.mod_id)).ok_or(WasmReturnCode::InvalidContext)
+    }
+}
+
+/// Host import: `file_len(path_ptr, path_len, len_out_ptr) -> i32`, writes the file's size as a little-endian u32
+fn file_len(mut caller: Caller<'_, WasmContext>, path_ptr: i32, path_len: i32, len_out_ptr: i32) -> i32 {
+    let outcome = read_guest_str(&mut caller, path_ptr, path_len).and_then(|path| {
+        let len = caller.data().mod_vfs()?.file_len(&path).map_err(|e| e.return_code())?;
+        write_guest(&mut caller, len_out_ptr, &(len as u32).to_le_bytes())
+    });
+    outcome.map_or_else(|code| code as i32, |_| WasmReturnCode::Success as i32)
+}
+
+/// Host import: `read_file(path_ptr, path_len, out_ptr, out_cap) -> i32`, copies a whole file
+/// from the mod's directory; ResourceExhaustion if it doesn't fit in `out_cap` bytes
+fn read_file(mut caller: Caller<'_, WasmContext>, path_ptr: i32, path_len: i32, out_ptr: i32, out_cap: i32) -> i32 {
+    let outcome = read_guest_str(&mut caller, path_ptr, path_len).and_then(|path| {
+        let bytes = caller.data().mod_vfs()?.read(&path).map_err(|e| e.return_code())?;
+        if bytes.len() > out_cap as u32 as usize {
+            return Err(WasmReturnCode::ResourceExhaustion);
+        }
+        write_guest(&mut caller, out_ptr, &bytes)
+    });
+    outcome.map_or_else(|code| code as i32, |_| WasmReturnCode::Success as i32)
+}
+
+/// Feeds op-reported metrics into the KPI buffer so mod-defined Black Swans can trigger on them,
+/// registering each metric the first time a mod reports it
 pub fn update_wasm_host_system(
     mut wasm_host: ResMut<WasmHost>,
-    time: Res<Time>,
+    kpi_buffer: Option<ResMut<KpiRingBuffer>>,
+    mut metric_ids: Option<ResMut<MetricRegistry>>,
+    clock: Res<SimClock>,
 ) {
-    // Update WASM host state
-    // This would handle fuel consumption, memory management, etc.
+    let current_tick = clock.now.timestamp_millis() as u64 / 16;
+    wasm_host.store.data_mut().tick = current_tick;
+    wasm_host.faults.tick = current_tick;
+    let metrics = wasm_host.drain_metrics();
+    let Some(mut kpi_buffer) = kpi_buffer else {
+        return;
+    };
+    for metric in metrics {
+        if let Some(registry) = metric_ids.as_mut() {
+            if let Err(e) = registry.register(&metric.mod_id, &metric.name) {
+                println!("Ignoring metric from {}: {:#}", metric.op_name, e);
+                continue;
+            }
+        }
+        kpi_buffer.add_custom(&metric.kpi_name(), metric.value, current_tick);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_metric_validates_and_attributes() {
+        let mut host = WasmHost::new();
+        let context = host.store.data_mut();
+        context.mod_id = "com.example.anomaly".to_string();
+        context.op_name = "Op_Anomaly".to_string();
+        context.job_id = Some(7);
+
+        assert_eq!(context.record_metric("anomaly_score", 0.93), WasmReturnCode::Success as i32);
+        assert_eq!(context.record_metric("bad name!", 1.0), WasmReturnCode::InvalidInput as i32);
+        assert_eq!(context.record_metric("nan", f64::NAN), WasmReturnCode::InvalidInput as i32);
+
+        let metrics = host.drain_metrics();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].job_id, Some(7));
+        assert_eq!(metrics[0].kpi_name(), "com.example.anomaly:anomaly_score");
+        assert!(host.drain_metrics().is_empty());
+    }
+
+    #[test]
+    fn test_record_metric_caps_reports_per_call() {
+        let mut host = WasmHost::new();
+        let context = host.store.data_mut();
+        for _ in 0..MAX_METRICS_PER_CALL {
+            assert_eq!(context.record_metric("score", 1.0), WasmReturnCode::Success as i32);
+        }
+        assert_eq!(context.record_metric("score", 1.0), WasmReturnCode::ResourceExhaustion as i32);
+    }
+
+    #[test]
+    fn test_op_abi_must_match_loaded_module() {
+        let mut host = WasmHost::new();
+        host.load_module("com.example.legacy", b"\0asm\x01\0\0\0").unwrap();
+        assert_eq!(host.abi_of("com.example.legacy"), Some(WasmAbi::Legacy));
+
+        let spec = WasmOpSpec {
+            name: "process".to_string(),
+            version: "0.1.0".to_string(),
+            cost_hint_ms: 1,
+            work_units_hint: 1.0,
+            vram_hint_mb: 0.0,
+            bandwidth_hint_mb: 0.0,
+            description: None,
+            abi: WasmAbi::Component,
+        };
+        let err = host.execute_op("com.example.legacy", &spec, &[1, 2, 3]).unwrap_err();
+        assert!(err.to_string().contains("Component ABI"));
+        assert!(host.execute_op("com.example.missing", &spec, &[]).is_err());
+    }
+
+    #[test]
+    fn test_trapping_op_is_suspended() {
+        // (func (export "process") (param i32) (result i32) unreachable)
+        const TRAPS: &[u8] = &[
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+            0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+            0x03, 0x02, 0x01, 0x00,
+            0x07, 0x0b, 0x01, 0x07, b'p', b'r', b'o', b'c', b'e', b's', b's', 0x00, 0x00,
+            0x0a, 0x05, 0x01, 0x03, 0x00, 0x00, 0x0b,
+        ];
+        let mut host = WasmHost::new();
+        host.load_module("com.example.trap", TRAPS).unwrap();
+        let spec = WasmOpSpec {
+            name: "process".to_string(),
+            version: "0.1.0".to_string(),
+            cost_hint_ms: 1,
+            work_units_hint: 1.0,
+            vram_hint_mb: 0.0,
+            bandwidth_hint_mb: 0.0,
+            description: None,
+            abi: WasmAbi::Legacy,
+        };
+        for _ in 0..host.faults.suspend_after {
+            assert!(host.execute_op("com.example.trap", &spec, &[1]).is_err());
+        }
+        assert_eq!(host.faults.recent.back().unwrap().kind, ModFaultKind::Trap);
+        let refused = host.execute_op("com.example.trap", &spec, &[1]).unwrap_err();
+        assert!(refused.to_string().contains("suspended"));
+
+        // Loading a new build lifts the suspension
+        host.load_module("com.example.trap", TRAPS).unwrap();
+        assert!(!host.faults.is_suspended("com.example.trap"));
+    }
 }
\ No newline at end of file
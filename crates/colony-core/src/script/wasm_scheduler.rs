@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use wasmtime::*;
+use colony_modsdk::abi::SCHEDULER_PRIORITY_EXPORT;
+use std::sync::Mutex;
+use anyhow::Result;
+use crate::{Job, QoS, Scheduler, Worker, Workyard};
+
+struct PriorityFn {
+    store: Store<()>,
+    priority: TypedFunc<(i64, i32, i32, i32), i64>,
+}
+
+/// A scheduling policy a mod ships as `schedulers/<name>.wasm`, registered as `<mod id>/<name>`.
+/// The module ranks each job through its `priority` export and jobs run lowest first, ties in
+/// queue order. A module that traps leaves that pick in queue order.
+pub struct WasmScheduler {
+    name: String,
+    instance: Mutex<PriorityFn>,
+}
+
+impl WasmScheduler {
+    /// Compile and instantiate a scheduler module; it may import nothing
+    pub fn new(name: &str, engine: &Engine, wasm_bytes: &[u8]) -> Result<Self> {
+        let module = Module::new(engine, wasm_bytes)?;
+        let mut store = Store::new(engine, ());
+        let instance = Instance::new(&mut store, &module, &[])?;
+        let priority = instance.get_typed_func::<(i64, i32, i32, i32), i64>(&mut store, SCHEDULER_PRIORITY_EXPORT)?;
+        Ok(Self { name: name.to_string(), instance: Mutex::new(PriorityFn { store, priority }) })
+    }
+
+    fn priorities(&self, jobs: &[Job]) -> Result<Vec<i64>> {
+        let mut instance = self.instance.lock().map_err(|_| anyhow::anyhow!("Scheduler {} is poisoned", self.name))?;
+        let PriorityFn { store, priority } = &mut *instance;
+        jobs.iter()
+            .map(|job| {
+                let cost_ms: u32 = job.pipeline.ops.iter().map(|op| op.cost_ms()).sum();
+                let qos = match job.qos {
+                    QoS::Throughput => 0,
+                    QoS::Latency => 1,
+                    QoS::Balanced => 2,
+                };
+                priority.call(&mut *store, (job.deadline_ms as i64, cost_ms as i32, job.payload_sz as i32, qos))
+            })
+            .collect()
+    }
+}
+
+impl Scheduler for WasmScheduler {
+    fn pick(&self, _y: &Workyard, q: &[Job], w: &[(Entity, &Worker)]) -> Vec<(Entity, Job)> {
+        let mut order: Vec<usize> = (0..q.len()).collect();
+        match self.priorities(q) {
+            Ok(keys) => order.sort_by_key(|&i| keys[i]),
+            Err(e) => println!("Scheduler {} failed, keeping queue order: {}", self.name, e),
+        }
+        w.iter().zip(order).map(|((we, _), i)| (*we, q[i].clone())).collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pipeline, Op, WorkClass, WorkyardKind};
+
+    // `priority(i64, i32, i32, i32) -> i64` returning the payload size, so the smallest payload goes first
+    const BY_PAYLOAD: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+        0x01, 0x09, 0x01, 0x60, 0x04, 0x7e, 0x7f, 0x7f, 0x7f, 0x01, 0x7e,
+        0x03, 0x02, 0x01, 0x00,
+        0x07, 0x0c, 0x01, 0x08, b'p', b'r', b'i', b'o', b'r', b'i', b't', b'y', 0x00, 0x00,
+        0x0a, 0x07, 0x01, 0x05, 0x00, 0x20, 0x02, 0xac, 0x0b,
+    ];
+    // The same signature, but the body is `unreachable`
+    const TRAPS: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+        0x01, 0x09, 0x01, 0x60, 0x04, 0x7e, 0x7f, 0x7f, 0x7f, 0x01, 0x7e,
+        0x03, 0x02, 0x01, 0x00,
+        0x07, 0x0c, 0x01, 0x08, b'p', b'r', b'i', b'o', b'r', b'i', b't', b'y', 0x00, 0x00,
+        0x0a, 0x05, 0x01, 0x03, 0x00, 0x00, 0x0b,
+    ];
+
+    fn job(id: u64, payload_sz: usize) -> Job {
+        Job {
+            id,
+            pipeline: Pipeline { ops: vec![Op::Decode], mutation_tag: None, id: None },
+            qos: QoS::Balanced,
+            deadline_ms: 1000,
+            payload_sz,
+        }
+    }
+
+    fn pick_ids(scheduler: &WasmScheduler) -> Vec<u64> {
+        let yard = Workyard { kind: WorkyardKind::CpuArray, slots: 4, heat: 0.0, heat_cap: 100.0, power_draw_kw: 0.0, bandwidth_share: 0.1, isolation_domain: 0 };
+        let worker = Worker { id: 1, class: WorkClass::Cpu };
+        let refs: Vec<(Entity, &Worker)> = (0..2).map(|i| (Entity::from_raw(i + 1), &worker)).collect();
+        scheduler.pick(&yard, &[job(1, 4096), job(2, 512), job(3, 1024)], &refs).iter().map(|(_, j)| j.id).collect()
+    }
+
+    #[test]
+    fn test_jobs_run_in_priority_order() {
+        let engine = Engine::default();
+        let scheduler = WasmScheduler::new("com.example.sched/SmallFirst", &engine, BY_PAYLOAD).unwrap();
+        assert_eq!(scheduler.name(), "com.example.sched/SmallFirst");
+        assert_eq!(pick_ids(&scheduler), vec![2, 3]);
+        assert!(WasmScheduler::new("bad", &engine, b"\0asm\x01\0\0\0").is_err());
+    }
+
+    #[test]
+    fn test_trapping_scheduler_keeps_queue_order() {
+        let scheduler = WasmScheduler::new("com.example.sched/Broken", &Engine::default(), TRAPS).unwrap();
+        assert_eq!(pick_ids(&scheduler), vec![1, 2]);
+    }
+}
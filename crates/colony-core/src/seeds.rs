@@ -0,0 +1,130 @@
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use crate::Colony;
+
+/// Domain tag mixed into every sub-seed; bump the suffix if the derivation ever changes
+const SEED_KDF_DOMAIN: &[u8] = b"colony-seed-v1";
+
+/// Independent randomness streams. Each subsystem rolls from its own sub-seed, so one
+/// stream can be varied (or pinned) while the others keep the rolls they had.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedStream {
+    Faults,    // fault injection and retry backoff jitter
+    BlackSwan, // Black Swan and ritual rolls
+    Io,        // packet timing, loss and malformation in the IO simulators
+    WorkerGen, // skills of workers hired during play
+}
+
+impl SeedStream {
+    pub const ALL: [SeedStream; 4] = [SeedStream::Faults, SeedStream::BlackSwan, SeedStream::Io, SeedStream::WorkerGen];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SeedStream::Faults => "faults",
+            SeedStream::BlackSwan => "blackswan",
+            SeedStream::Io => "io",
+            SeedStream::WorkerGen => "worker_gen",
+        }
+    }
+}
+
+/// Sub-seed for `stream`: the first 8 bytes, little endian, of
+/// SHA-256("colony-seed-v1" || root as 8 little-endian bytes || stream name).
+/// Stable across builds and platforms, and changing the root reshuffles every stream.
+pub fn derive_seed(root: u64, stream: SeedStream) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(SEED_KDF_DOMAIN);
+    hasher.update(root.to_le_bytes());
+    hasher.update(stream.name().as_bytes());
+    let digest = hasher.finalize();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// `[seeds]` table in a scenario: pin a stream to a fixed seed instead of deriving it from the root
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub faults: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blackswan: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io: Option<u64>,
+    #[serde(default, alias = "worker-gen", skip_serializing_if = "Option::is_none")]
+    pub worker_gen: Option<u64>,
+}
+
+impl SeedOverrides {
+    pub fn get(&self, stream: SeedStream) -> Option<u64> {
+        match stream {
+            SeedStream::Faults => self.faults,
+            SeedStream::BlackSwan => self.blackswan,
+            SeedStream::Io => self.io,
+            SeedStream::WorkerGen => self.worker_gen,
+        }
+    }
+
+    pub fn set(&mut self, stream: SeedStream, seed: Option<u64>) {
+        match stream {
+            SeedStream::Faults => self.faults = seed,
+            SeedStream::BlackSwan => self.blackswan = seed,
+            SeedStream::Io => self.io = seed,
+            SeedStream::WorkerGen => self.worker_gen = seed,
+        }
+    }
+
+    /// Later overrides win, stream by stream
+    pub fn merged(&self, later: &SeedOverrides) -> SeedOverrides {
+        let mut merged = self.clone();
+        for stream in SeedStream::ALL {
+            if let Some(seed) = later.get(stream) {
+                merged.set(stream, Some(seed));
+            }
+        }
+        merged
+    }
+}
+
+impl Colony {
+    /// Seed a subsystem rolls from: its override if pinned, otherwise derived from the root seed
+    pub fn seed_for(&self, stream: SeedStream) -> u64 {
+        self.seeds.get(stream).unwrap_or_else(|| derive_seed(self.seed, stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streams_are_stable_and_independent() {
+        let seeds: Vec<u64> = SeedStream::ALL.iter().map(|s| derive_seed(42, *s)).collect();
+        for (i, a) in seeds.iter().enumerate() {
+            assert!(seeds[i + 1..].iter().all(|b| a != b));
+        }
+        assert_eq!(derive_seed(42, SeedStream::Faults), seeds[0]);
+        assert_ne!(derive_seed(43, SeedStream::Faults), seeds[0]);
+    }
+
+    #[test]
+    fn test_override_pins_one_stream() {
+        let overrides: SeedOverrides = toml::from_str("faults = 7\nworker-gen = 9").unwrap();
+        assert_eq!(overrides.get(SeedStream::Faults), Some(7));
+        assert_eq!(overrides.get(SeedStream::WorkerGen), Some(9));
+
+        let merged = SeedOverrides { faults: Some(1), io: Some(2), ..Default::default() }.merged(&overrides);
+        assert_eq!((merged.faults, merged.io, merged.worker_gen), (Some(7), Some(2), Some(9)));
+        assert_eq!(merged.blackswan, None);
+    }
+
+    #[test]
+    fn test_colony_seed_for_prefers_override() {
+        let mut colony = crate::colony_for_scenario(None, Some(42)).unwrap();
+        assert_eq!(colony.seed_for(SeedStream::Io), derive_seed(42, SeedStream::Io));
+        colony.seeds.io = Some(5);
+        assert_eq!(colony.seed_for(SeedStream::Io), 5);
+        assert_eq!(colony.seed_for(SeedStream::Faults), derive_seed(42, SeedStream::Faults));
+    }
+}
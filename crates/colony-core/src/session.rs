@@ -9,6 +9,96 @@ pub struct SessionCtl {
     pub autosave_every_min: u32,
     pub next_autosave_tick: u64,
     pub slot_name: Option<String>,
+    #[serde(default)]
+    pub meta: SessionMeta,
+}
+
+pub const MAX_META_NAME_LEN: usize = 80;
+pub const MAX_META_DESCRIPTION_LEN: usize = 2000;
+pub const MAX_META_TAGS: usize = 16;
+
+/// Human labels for a session, carried into saves, replay bundles and run exports
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionMeta {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Partial update for `PATCH /session/meta`; absent fields are left alone, empty strings clear
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionMetaPatch {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+impl SessionMeta {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.description.is_none() && self.tags.is_empty()
+    }
+
+    /// Short form for logs and listings: `name [tag, tag]`
+    pub fn label(&self) -> Option<String> {
+        match (&self.name, self.tags.is_empty()) {
+            (None, true) => None,
+            (Some(name), true) => Some(name.clone()),
+            (name, false) => Some(format!("{} [{}]", name.as_deref().unwrap_or("unnamed"), self.tags.join(", "))),
+        }
+    }
+
+    /// Check labels that didn't come through `apply`, e.g. from a run spec file
+    pub fn validate(&self) -> anyhow::Result<()> {
+        SessionMeta::default().apply(SessionMetaPatch {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            tags: Some(self.tags.clone()),
+        })
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Apply a patch, trimming text and normalizing tags to lowercase without duplicates
+    pub fn apply(&mut self, patch: SessionMetaPatch) -> anyhow::Result<()> {
+        let mut next = self.clone();
+        if let Some(name) = patch.name {
+            let name = name.trim();
+            if name.chars().count() > MAX_META_NAME_LEN {
+                return Err(anyhow::anyhow!("session name is longer than {} characters", MAX_META_NAME_LEN));
+            }
+            next.name = (!name.is_empty()).then(|| name.to_string());
+        }
+        if let Some(description) = patch.description {
+            let description = description.trim();
+            if description.chars().count() > MAX_META_DESCRIPTION_LEN {
+                return Err(anyhow::anyhow!("session description is longer than {} characters", MAX_META_DESCRIPTION_LEN));
+            }
+            next.description = (!description.is_empty()).then(|| description.to_string());
+        }
+        if let Some(tags) = patch.tags {
+            next.tags.clear();
+            for tag in tags {
+                let tag = tag.trim().to_lowercase();
+                if tag.is_empty() || next.tags.contains(&tag) {
+                    continue;
+                }
+                if tag.chars().any(|c| c.is_whitespace() || c == ',') {
+                    return Err(anyhow::anyhow!("tag '{}' can't contain spaces or commas", tag));
+                }
+                next.tags.push(tag);
+            }
+            if next.tags.len() > MAX_META_TAGS {
+                return Err(anyhow::anyhow!("at most {} tags per session", MAX_META_TAGS));
+            }
+        }
+        *self = next;
+        Ok(())
+    }
 }
 
 impl SessionCtl {
@@ -19,6 +109,7 @@ impl SessionCtl {
             autosave_every_min: 5, // Default 5 minutes
             next_autosave_tick: 0,
             slot_name: None,
+            meta: SessionMeta::default(),
         }
     }
 
@@ -58,6 +149,8 @@ pub enum ReplayEvent {
     RitualStarted { id: String },
     EventFired { swan_id: String },
     MutationApplied { pipeline_id: String, kind: String },
+    SchedulerFallback { scheduler: String, reason: String },
+    SchedulerQuarantined { scheduler: String },
 }
 
 #[derive(bevy::prelude::Resource, Default, Clone, Debug, Serialize, Deserialize)]
@@ -128,6 +221,16 @@ impl ReplayLog {
     pub fn is_playback(&self) -> bool {
         self.mode == ReplayMode::Playback
     }
+
+    pub fn save_to(&self, path: &std::path::Path, protection: &super::SaveProtection) -> anyhow::Result<()> {
+        let stats = protection.write(path, &serde_json::to_vec(self)?)?;
+        println!("Saved replay log {:?}: {}", path, stats.line());
+        Ok(())
+    }
+
+    pub fn load_from(path: &std::path::Path, protection: &super::SaveProtection) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(&protection.read(path)?)?)
+    }
 }
 
 pub fn record_event(event: ReplayEvent, log: &mut ReplayLog) {
@@ -158,6 +261,7 @@ pub fn session_control_system(
     mut session_ctl: ResMut<SessionCtl>,
     mut replay_log: ResMut<ReplayLog>,
     clock: Res<super::SimClock>,
+    colony: Option<ResMut<super::Colony>>,
     // TODO: Add event readers for session control commands
 ) {
     let current_tick = clock.now.timestamp_millis() as u64 / 16;
@@ -192,8 +296,12 @@ pub fn session_control_system(
                     println!("Replaying policy change: {}", policy);
                 }
                 ReplayEvent::TunableChange { key, value } => {
-                    // TODO: Replay tunable change
                     println!("Replaying tunable change: {} = {}", key, value);
+                    if let Some(mut colony) = colony {
+                        if let Err(e) = super::set_tunable(&mut colony, &key, value) {
+                            println!("Skipping tunable change: {}", e);
+                        }
+                    }
                 }
                 ReplayEvent::SimStart { seed, scenario_id } => {
                     // TODO: Replay sim start
@@ -211,6 +319,12 @@ pub fn session_control_system(
                     // TODO: Replay mutation
                     println!("Replaying mutation: {} on {}", kind, pipeline_id);
                 }
+                ReplayEvent::SchedulerFallback { scheduler, reason } => {
+                    println!("Replaying scheduler fallback: {} kept queue order ({})", scheduler, reason);
+                }
+                ReplayEvent::SchedulerQuarantined { scheduler } => {
+                    println!("Replaying scheduler quarantine: {}", scheduler);
+                }
             }
         }
     }
@@ -286,4 +400,30 @@ mod tests {
             _ => panic!("Wrong event type"),
         }
     }
+
+    #[test]
+    fn test_session_meta_patch() {
+        let mut meta = SessionMeta::default();
+        assert!(meta.is_empty());
+        meta.apply(SessionMetaPatch {
+            name: Some("  EDF soak ".to_string()),
+            description: None,
+            tags: Some(vec!["Soak".to_string(), "edf".to_string(), "soak".to_string(), " ".to_string()]),
+        }).unwrap();
+        assert_eq!(meta.name.as_deref(), Some("EDF soak"));
+        assert_eq!(meta.tags, vec!["soak", "edf"]);
+        assert!(meta.has_tag("EDF"));
+        assert_eq!(meta.label().as_deref(), Some("EDF soak [soak, edf]"));
+
+        // Absent fields stay, empty strings clear
+        meta.apply(SessionMetaPatch { name: Some(String::new()), description: Some("night run".to_string()), tags: None }).unwrap();
+        assert_eq!(meta.name, None);
+        assert_eq!(meta.description.as_deref(), Some("night run"));
+        assert_eq!(meta.tags.len(), 2);
+
+        // A rejected patch leaves the labels untouched
+        let bad = SessionMetaPatch { tags: Some(vec!["two words".to_string()]), ..Default::default() };
+        assert!(meta.apply(bad).is_err());
+        assert_eq!(meta.tags, vec!["soak", "edf"]);
+    }
 }
@@ -0,0 +1,269 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, Utc};
+use anyhow::Result;
+use crate::SessionCtl;
+
+/// One sim day in 16ms ticks
+pub const TICKS_PER_SIM_DAY: u64 = 86_400_000 / 16;
+/// Finished and cancelled entries kept for the listing
+const MAX_SCHEDULE_HISTORY: usize = 50;
+
+/// Body of `POST /sessions/schedule`. No `start_at` starts as soon as nothing else is running;
+/// stop at a wall-clock time or after a number of sim days, not both.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleRequest {
+    #[serde(default)]
+    pub start_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub stop_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub stop_after_sim_days: Option<u32>,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopAt {
+    WallClock(DateTime<Utc>),
+    SimDays(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleStatus {
+    Pending,
+    Running,
+    Finished,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledSession {
+    pub id: u64,
+    pub label: Option<String>,
+    pub start_at: Option<DateTime<Utc>>,
+    pub stop: Option<StopAt>,
+    pub status: ScheduleStatus,
+    pub started_at: Option<DateTime<Utc>>,
+    pub started_tick: Option<u64>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl ScheduledSession {
+    fn due_to_stop(&self, now: DateTime<Utc>, now_tick: u64) -> bool {
+        match self.stop {
+            Some(StopAt::WallClock(at)) => now >= at,
+            Some(StopAt::SimDays(days)) => {
+                let started = self.started_tick.unwrap_or(now_tick);
+                now_tick.saturating_sub(started) >= days as u64 * TICKS_PER_SIM_DAY
+            }
+            None => false,
+        }
+    }
+}
+
+/// What the schedule wants done to the session on this poll
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleAction {
+    Start(u64),
+    Stop(u64),
+}
+
+/// Unattended start/stop times for the served session, e.g. overnight experiments on a shared
+/// lab server. Entries run one at a time in start order; one whose start time comes while
+/// another is running waits for it to stop.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionSchedule {
+    pub entries: Vec<ScheduledSession>,
+    next_id: u64,
+}
+
+impl SessionSchedule {
+    pub fn new() -> Self {
+        Self { entries: Vec::new(), next_id: 1 }
+    }
+
+    pub fn add(&mut self, request: ScheduleRequest, now: DateTime<Utc>) -> Result<u64> {
+        let stop = match (request.stop_at, request.stop_after_sim_days) {
+            (Some(_), Some(_)) => return Err(anyhow::anyhow!("Give stop_at or stop_after_sim_days, not both")),
+            (Some(at), None) => Some(StopAt::WallClock(at)),
+            (None, Some(0)) => return Err(anyhow::anyhow!("stop_after_sim_days must be at least 1")),
+            (None, Some(days)) => Some(StopAt::SimDays(days)),
+            (None, None) => None,
+        };
+        if request.start_at.is_none() && stop.is_none() {
+            return Err(anyhow::anyhow!("A schedule needs start_at, stop_at or stop_after_sim_days"));
+        }
+        if let Some(StopAt::WallClock(at)) = stop {
+            if at <= now {
+                return Err(anyhow::anyhow!("stop_at {} is in the past", at.to_rfc3339()));
+            }
+            if request.start_at.is_some_and(|start| at <= start) {
+                return Err(anyhow::anyhow!("stop_at must be after start_at"));
+            }
+        }
+
+        let id = self.next_id.max(1);
+        self.next_id = id + 1;
+        self.entries.push(ScheduledSession {
+            id,
+            label: request.label,
+            start_at: request.start_at,
+            stop,
+            status: ScheduleStatus::Pending,
+            started_at: None,
+            started_tick: None,
+            finished_at: None,
+        });
+        Ok(id)
+    }
+
+    /// Cancel a pending or running entry; Ok(true) when it was running and the session should stop
+    pub fn cancel(&mut self, id: u64, now: DateTime<Utc>) -> Result<bool> {
+        let entry = self.entries.iter_mut().find(|e| e.id == id)
+            .ok_or_else(|| anyhow::anyhow!("No scheduled session {}", id))?;
+        let was_running = match entry.status {
+            ScheduleStatus::Pending => false,
+            ScheduleStatus::Running => true,
+            _ => return Err(anyhow::anyhow!("Scheduled session {} has already ended", id)),
+        };
+        entry.status = ScheduleStatus::Cancelled;
+        entry.finished_at = Some(now);
+        self.prune();
+        Ok(was_running)
+    }
+
+    pub fn running(&self) -> Option<&ScheduledSession> {
+        self.entries.iter().find(|e| e.status == ScheduleStatus::Running)
+    }
+
+    /// Advance the schedule to `now`: stop the running entry if it's due, then start the next due one
+    pub fn poll(&mut self, now: DateTime<Utc>, now_tick: u64) -> Vec<ScheduleAction> {
+        let mut actions = Vec::new();
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.status == ScheduleStatus::Running) {
+            if !entry.due_to_stop(now, now_tick) {
+                return actions;
+            }
+            entry.status = ScheduleStatus::Finished;
+            entry.finished_at = Some(now);
+            actions.push(ScheduleAction::Stop(entry.id));
+        }
+
+        let next = self.entries.iter_mut()
+            .filter(|e| e.status == ScheduleStatus::Pending && e.start_at.is_none_or(|at| at <= now))
+            .min_by_key(|e| (e.start_at, e.id));
+        if let Some(entry) = next {
+            // A wall-clock stop that passed while it waited means it never gets to run
+            if matches!(entry.stop, Some(StopAt::WallClock(at)) if at <= now) {
+                entry.status = ScheduleStatus::Cancelled;
+                entry.finished_at = Some(now);
+                eprintln!("Scheduled session {} missed its window and was skipped", entry.id);
+            } else {
+                entry.status = ScheduleStatus::Running;
+                entry.started_at = Some(now);
+                entry.started_tick = Some(now_tick);
+                actions.push(ScheduleAction::Start(entry.id));
+            }
+        }
+        self.prune();
+        actions
+    }
+
+    fn prune(&mut self) {
+        let ended = self.entries.iter()
+            .filter(|e| matches!(e.status, ScheduleStatus::Finished | ScheduleStatus::Cancelled))
+            .count();
+        let mut excess = ended.saturating_sub(MAX_SCHEDULE_HISTORY);
+        self.entries.retain(|e| {
+            let drop = excess > 0 && matches!(e.status, ScheduleStatus::Finished | ScheduleStatus::Cancelled);
+            if drop {
+                excess -= 1;
+            }
+            !drop
+        });
+    }
+}
+
+/// Resume or pause the session as the schedule says
+pub fn apply_schedule_actions(ctl: &mut SessionCtl, actions: &[ScheduleAction]) {
+    for action in actions {
+        match action {
+            ScheduleAction::Start(id) => {
+                println!("Scheduled session {} starting", id);
+                ctl.resume();
+            }
+            ScheduleAction::Stop(id) => {
+                println!("Scheduled session {} stopping", id);
+                ctl.pause();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::seconds(secs)
+    }
+
+    #[test]
+    fn test_schedule_starts_and_stops_in_order() {
+        let mut schedule = SessionSchedule::new();
+        let overnight = schedule.add(ScheduleRequest {
+            start_at: Some(at(100)),
+            stop_after_sim_days: Some(2),
+            ..Default::default()
+        }, at(0)).unwrap();
+        let followup = schedule.add(ScheduleRequest {
+            start_at: Some(at(150)),
+            stop_at: Some(at(10_000)),
+            ..Default::default()
+        }, at(0)).unwrap();
+
+        let mut ctl = SessionCtl::new();
+        ctl.pause();
+        assert!(schedule.poll(at(50), 0).is_empty());
+        let actions = schedule.poll(at(100), 1_000);
+        assert_eq!(actions, vec![ScheduleAction::Start(overnight)]);
+        apply_schedule_actions(&mut ctl, &actions);
+        assert!(ctl.running);
+
+        // The follow-up's start time passes, but it waits for two sim days to go by
+        assert!(schedule.poll(at(200), 1_000 + TICKS_PER_SIM_DAY).is_empty());
+        let actions = schedule.poll(at(300), 1_000 + 2 * TICKS_PER_SIM_DAY);
+        assert_eq!(actions, vec![ScheduleAction::Stop(overnight), ScheduleAction::Start(followup)]);
+
+        let actions = schedule.poll(at(10_000), 0);
+        assert_eq!(actions, vec![ScheduleAction::Stop(followup)]);
+        apply_schedule_actions(&mut ctl, &actions);
+        assert!(!ctl.running);
+        assert!(schedule.running().is_none());
+    }
+
+    #[test]
+    fn test_schedule_rejects_bad_windows() {
+        let mut schedule = SessionSchedule::new();
+        assert!(schedule.add(ScheduleRequest::default(), at(0)).is_err());
+        assert!(schedule.add(ScheduleRequest { stop_at: Some(at(5)), ..Default::default() }, at(10)).is_err());
+        assert!(schedule.add(ScheduleRequest {
+            start_at: Some(at(20)),
+            stop_at: Some(at(15)),
+            ..Default::default()
+        }, at(10)).is_err());
+        assert!(schedule.add(ScheduleRequest {
+            stop_at: Some(at(20)),
+            stop_after_sim_days: Some(1),
+            ..Default::default()
+        }, at(10)).is_err());
+
+        let id = schedule.add(ScheduleRequest { stop_after_sim_days: Some(1), ..Default::default() }, at(10)).unwrap();
+        schedule.poll(at(10), 0);
+        assert!(schedule.cancel(id, at(11)).unwrap());
+        assert!(schedule.cancel(id, at(12)).is_err());
+    }
+}
@@ -0,0 +1,434 @@
+use bevy::ecs::world::{EntityRef, EntityWorldMut};
+use bevy::prelude::*;
+use colony_modsdk::KpiDeltas;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::{ColonyPlugin, ColonyPluginConfig, Colony, SimClock, ActiveScheduler, CorruptionField, SlaTracker, ClockDrift, MaintenanceLane, Workyard, GpuFarm};
+use crate::{Certifications, Debts, DriveWear, EnergyLedger, GpuBatchQueues, JobIdAllocator, JobQueue, PipelineGenome, ResearchState, RetryState, SchedulerRegistry, Worker, WorkerHealth, WorkerSkills, WorkerState, WorkerStateComp, YardClock, YardPos, YardWorkload};
+
+/// KPI snapshot for comparison
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KpiSnapshot {
+    pub deadline_hit_rate: f32,
+    pub power_draw_kw: f32,
+    pub bandwidth_util: f32,
+    pub corruption_field: f32,
+    pub heat_levels: Vec<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_util: Option<f32>, // None when the colony has no GPU farm
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vram_used_mb: Option<f32>,
+    #[serde(default)]
+    pub clock_drift_ms: f32, // worst yard clock offset
+    #[serde(default)]
+    pub maintenance_wait_ms: f32, // average wait for the reserved maintenance lane
+}
+
+impl KpiSnapshot {
+    /// Collect the current KPIs from a running world
+    pub fn capture(world: &mut World) -> Self {
+        let mut snapshot = KpiSnapshot::default();
+
+        if let Some(colony) = world.get_resource::<Colony>() {
+            snapshot.power_draw_kw = colony.meters.power_draw_kw;
+            snapshot.bandwidth_util = colony.meters.bandwidth_util;
+        }
+        if let Some(field) = world.get_resource::<CorruptionField>() {
+            snapshot.corruption_field = field.global;
+        }
+        if let Some(sla) = world.get_resource::<SlaTracker>() {
+            snapshot.deadline_hit_rate = sla.get_recent_hit_rate();
+        }
+        if let Some(drift) = world.get_resource::<ClockDrift>() {
+            snapshot.clock_drift_ms = drift.max_abs_offset_ms;
+        }
+        if let Some(lane) = world.get_resource::<MaintenanceLane>() {
+            snapshot.maintenance_wait_ms = lane.avg_wait_ms;
+        }
+
+        let mut yards = world.query::<&Workyard>();
+        snapshot.heat_levels = yards.iter(world).map(|y| y.heat).collect();
+
+        let mut farms = world.query::<&GpuFarm>();
+        let farms: Vec<&GpuFarm> = farms.iter(world).collect();
+        if !farms.is_empty() {
+            snapshot.gpu_util = Some(farms.iter().map(|f| f.meters.util).sum::<f32>() / farms.len() as f32);
+            snapshot.vram_used_mb = Some(farms.iter().map(|f| f.meters.vram_used_gb * 1024.0).sum());
+        }
+
+        snapshot
+    }
+}
+
+pub fn calculate_kpi_deltas(baseline: &KpiSnapshot, current: &KpiSnapshot) -> KpiDeltas {
+    KpiDeltas {
+        deadline_hit_rate_change: current.deadline_hit_rate - baseline.deadline_hit_rate,
+        power_draw_change: if baseline.power_draw_kw > 0.0 {
+            ((current.power_draw_kw - baseline.power_draw_kw) / baseline.power_draw_kw) * 100.0
+        } else {
+            0.0
+        },
+        bandwidth_util_change: current.bandwidth_util - baseline.bandwidth_util,
+        corruption_field_change: current.corruption_field - baseline.corruption_field,
+        heat_levels_change: current.heat_levels.iter()
+            .zip(baseline.heat_levels.iter())
+            .map(|(curr, base)| curr - base)
+            .collect(),
+    }
+}
+
+/// Build an isolated app running the full colony simulation, seeded from the given state
+pub fn build_shadow_app(colony: &Colony, clock: &SimClock, scheduler: &ActiveScheduler) -> App {
+    build_shadow_app_with_config(colony, clock, scheduler, ColonyPluginConfig::default())
+}
+
+/// A shadow app running only the sub-plugins `config` enables, e.g. a scenario's
+pub fn build_shadow_app_with_config(colony: &Colony, clock: &SimClock, scheduler: &ActiveScheduler, config: ColonyPluginConfig) -> App {
+    let mut app = App::new();
+    app.init_resource::<Time>()
+        .add_plugins(ColonyPlugin::with_config(config))
+        .insert_resource(colony.clone())
+        .insert_resource(clock.clone())
+        .insert_resource(scheduler.clone());
+    app
+}
+
+/// Advance a shadow app by `ticks` updates and return the resulting KPIs
+pub fn run_shadow_ticks(app: &mut App, ticks: u32) -> KpiSnapshot {
+    for _ in 0..ticks {
+        app.update();
+    }
+    KpiSnapshot::capture(app.world_mut())
+}
+
+type Insert = Arc<dyn Fn(&mut EntityWorldMut) + Send + Sync>;
+type Capture = fn(&EntityRef) -> Option<Insert>;
+
+fn capture<T: Component + Clone>(entity: &EntityRef) -> Option<Insert> {
+    let component = entity.get::<T>()?.clone();
+    Some(Arc::new(move |target: &mut EntityWorldMut| {
+        target.insert(component.clone());
+    }))
+}
+
+/// The components a fork carries over: the inspectable ones plus the dispatcher's yard workload
+const FORKED: &[Capture] = &[
+    capture::<Worker>,
+    capture::<WorkerSkills>,
+    capture::<WorkerHealth>,
+    capture::<WorkerStateComp>,
+    capture::<RetryState>,
+    capture::<Certifications>,
+    capture::<Workyard>,
+    capture::<YardWorkload>,
+    capture::<YardClock>,
+    capture::<YardPos>,
+    capture::<DriveWear>,
+    capture::<GpuFarm>,
+    capture::<PipelineGenome>,
+];
+
+type InsertResource = Arc<dyn Fn(&mut World) + Send + Sync>;
+type CaptureResource = fn(&World) -> Option<InsertResource>;
+
+fn capture_resource<T: Resource + Clone>(world: &World) -> Option<InsertResource> {
+    let resource = world.get_resource::<T>()?.clone();
+    Some(Arc::new(move |target: &mut World| {
+        target.insert_resource(resource.clone());
+    }))
+}
+
+/// Sim state a fork carries over besides the queue and schedulers: debts and their expiries,
+/// corruption, SLA windows, research, half-filled GPU batches, clock drift and the energy ledger
+const FORKED_RESOURCES: &[CaptureResource] = &[
+    capture_resource::<Debts>,
+    capture_resource::<CorruptionField>,
+    capture_resource::<SlaTracker>,
+    capture_resource::<ResearchState>,
+    capture_resource::<GpuBatchQueues>,
+    capture_resource::<ClockDrift>,
+    capture_resource::<EnergyLedger>,
+];
+
+#[derive(Clone)]
+struct ForkedEntity {
+    source: Entity,
+    components: Vec<Insert>,
+}
+
+/// Everything a shadow world is forked from, copied out of a live world so the fork can be
+/// built and run off the sim thread: its colony, clock, scheduler and registry, sub-plugin set,
+/// job queue, sim state resources and the yards and workers with their components
+#[derive(Clone)]
+pub struct ShadowSeed {
+    pub colony: Colony,
+    pub clock: SimClock,
+    pub scheduler: ActiveScheduler,
+    pub config: ColonyPluginConfig,
+    registry: Option<SchedulerRegistry>,
+    jobq: JobQueue,
+    job_ids: Option<JobIdAllocator>,
+    resources: Vec<InsertResource>,
+    entities: Vec<ForkedEntity>,
+}
+
+impl ShadowSeed {
+    pub fn capture(world: &World) -> anyhow::Result<Self> {
+        let colony = world.get_resource::<Colony>().ok_or_else(|| anyhow::anyhow!("world has no Colony to fork"))?;
+        let clock = world.get_resource::<SimClock>().ok_or_else(|| anyhow::anyhow!("world has no SimClock to fork"))?;
+        let entities = world.iter_entities()
+            .map(|entity| ForkedEntity { source: entity.id(), components: FORKED.iter().filter_map(|capture| capture(&entity)).collect() })
+            .filter(|entity| !entity.components.is_empty())
+            .collect();
+        Ok(Self {
+            colony: colony.clone(),
+            clock: clock.clone(),
+            scheduler: world.get_resource::<ActiveScheduler>().cloned().unwrap_or_default(),
+            config: world.get_resource::<ColonyPluginConfig>().cloned().unwrap_or_default(),
+            registry: world.get_resource::<SchedulerRegistry>().cloned(),
+            jobq: world.get_resource::<JobQueue>().cloned().unwrap_or_default(),
+            job_ids: world.get_resource::<JobIdAllocator>().cloned(),
+            resources: FORKED_RESOURCES.iter().filter_map(|capture| capture(world)).collect(),
+            entities,
+        })
+    }
+
+    /// Put the copied queue, schedulers, resources and entities into a freshly built shadow world, whose
+    /// Startup then leaves the stock yards and workers out
+    fn plant(&self, world: &mut World) {
+        let mut spawned = HashMap::new();
+        for entity in &self.entities {
+            let mut target = world.spawn_empty();
+            for insert in &entity.components {
+                insert(&mut target);
+            }
+            spawned.insert(entity.source, target.id());
+        }
+        // Work in flight at the fork finishes in the live world; its workers start the fork idle
+        let mut workers = world.query::<&mut WorkerStateComp>();
+        for mut state in workers.iter_mut(world) {
+            if state.0 == WorkerState::Running {
+                state.0 = WorkerState::Idle;
+            }
+        }
+
+        let mut jobq = self.jobq.clone();
+        jobq.maintenance.retain_mut(|entry| match spawned.get(&entry.yard) {
+            Some(&yard) => {
+                entry.yard = yard;
+                true
+            }
+            None => false,
+        });
+        world.insert_resource(jobq);
+        if let Some(job_ids) = &self.job_ids {
+            world.insert_resource(job_ids.clone());
+        }
+        // A registry clone has its own fair-share state, so the fork can't move the live one
+        if let Some(registry) = &self.registry {
+            world.insert_resource(registry.clone());
+        }
+        for insert in &self.resources {
+            insert(world);
+        }
+    }
+}
+
+/// A throwaway copy of the colony that runs the full simulation on its own. Nothing it does
+/// reaches the world it was forked from, so what-if previews, scheduler A/B runs and advisor
+/// scoring can try changes out and compare KPIs before anything is committed.
+pub struct ShadowWorld {
+    app: App,
+    forked_kpis: KpiSnapshot, // KPIs at the moment of the fork
+    ticks: u64,               // updates run since the fork
+}
+
+impl ShadowWorld {
+    /// A fresh colony from the given state; yards, workers and the queue come from Startup
+    pub fn new(colony: &Colony, clock: &SimClock, scheduler: &ActiveScheduler, config: ColonyPluginConfig) -> Self {
+        let mut app = build_shadow_app_with_config(colony, clock, scheduler, config);
+        let forked_kpis = KpiSnapshot::capture(app.world_mut());
+        Self { app, forked_kpis, ticks: 0 }
+    }
+
+    /// A copy of the world the seed was captured from, entities and queue included
+    pub fn from_seed(seed: &ShadowSeed) -> Self {
+        let mut app = build_shadow_app_with_config(&seed.colony, &seed.clock, &seed.scheduler, seed.config.clone());
+        seed.plant(app.world_mut());
+        let forked_kpis = KpiSnapshot::capture(app.world_mut());
+        Self { app, forked_kpis, ticks: 0 }
+    }
+
+    /// Run `ticks` updates and return the KPIs they end on
+    pub fn advance(&mut self, ticks: u32) -> KpiSnapshot {
+        self.ticks += ticks as u64;
+        run_shadow_ticks(&mut self.app, ticks)
+    }
+
+    pub fn kpis(&mut self) -> KpiSnapshot {
+        KpiSnapshot::capture(self.app.world_mut())
+    }
+
+    /// How the KPIs moved since the fork
+    pub fn drift(&mut self) -> KpiDeltas {
+        let now = self.kpis();
+        calculate_kpi_deltas(&self.forked_kpis, &now)
+    }
+
+    /// Current KPIs of `other` measured against this world's, e.g. proposed against baseline
+    pub fn diff_kpis(&mut self, other: &mut ShadowWorld) -> KpiDeltas {
+        calculate_kpi_deltas(&self.kpis(), &other.kpis())
+    }
+
+    pub fn ticks_advanced(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Direct access for callers that feed jobs in or read resources between updates
+    pub fn world(&self) -> &World {
+        self.app.world()
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    /// Drop the fork; returns how many updates it ran
+    pub fn discard(self) -> u64 {
+        self.ticks
+    }
+}
+
+/// Fork a running world: its colony, clock, schedulers, sub-plugin set, job queue, sim state
+/// resources, yards and workers are copied into a fresh shadow world
+pub fn fork_world(world: &World) -> anyhow::Result<ShadowWorld> {
+    Ok(ShadowWorld::from_seed(&ShadowSeed::capture(world)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kpi_delta_calculation() {
+        let baseline = KpiSnapshot {
+            deadline_hit_rate: 99.0,
+            power_draw_kw: 800.0,
+            bandwidth_util: 0.6,
+            corruption_field: 0.1,
+            heat_levels: vec![60.0, 65.0],
+            gpu_util: Some(0.7),
+            vram_used_mb: Some(1000.0),
+            clock_drift_ms: 0.0,
+            maintenance_wait_ms: 0.0,
+        };
+
+        let current = KpiSnapshot {
+            deadline_hit_rate: 98.5,
+            power_draw_kw: 850.0,
+            bandwidth_util: 0.65,
+            corruption_field: 0.12,
+            heat_levels: vec![62.0, 67.0],
+            gpu_util: Some(0.75),
+            vram_used_mb: Some(1100.0),
+            clock_drift_ms: 0.0,
+            maintenance_wait_ms: 0.0,
+        };
+
+        let deltas = calculate_kpi_deltas(&baseline, &current);
+        assert_eq!(deltas.deadline_hit_rate_change, -0.5);
+        assert_eq!(deltas.power_draw_change, 6.25);
+        assert_eq!(deltas.heat_levels_change, vec![2.0, 2.0]);
+    }
+
+    #[test]
+    fn test_kpi_delta_zero_baseline_power() {
+        let baseline = KpiSnapshot::default();
+        let current = KpiSnapshot { power_draw_kw: 100.0, ..Default::default() };
+        assert_eq!(calculate_kpi_deltas(&baseline, &current).power_draw_change, 0.0);
+    }
+
+    #[test]
+    fn test_fork_leaves_source_untouched() {
+        let colony = crate::colony_for_scenario(None, Some(42)).unwrap();
+        let clock = SimClock {
+            tick_scale: crate::TickScale::Seconds(1),
+            now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+        };
+        let mut source = ShadowWorld::new(&colony, &clock, &ActiveScheduler::default(), ColonyPluginConfig::default());
+        source.advance(5);
+        let source_now = source.world().resource::<SimClock>().now;
+
+        let mut fork = fork_world(source.world()).unwrap();
+        fork.world_mut().resource_mut::<Colony>().power_cap_kw = 1.0;
+        fork.advance(20);
+        assert_eq!(fork.ticks_advanced(), 20);
+        assert!(fork.world().resource::<SimClock>().now > source_now);
+
+        assert_eq!(source.world().resource::<SimClock>().now, source_now);
+        assert_eq!(source.world().resource::<Colony>().power_cap_kw, colony.power_cap_kw);
+        assert_eq!(fork.discard(), 20);
+    }
+
+    #[test]
+    fn test_fork_copies_entities_and_queue() {
+        let colony = crate::colony_for_scenario(None, Some(42)).unwrap();
+        let clock = SimClock {
+            tick_scale: crate::TickScale::Seconds(1),
+            now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+        };
+        let mut source = ShadowWorld::new(&colony, &clock, &ActiveScheduler::default(), ColonyPluginConfig::default());
+        source.advance(1);
+        let mut health = source.world_mut().query::<&mut WorkerHealth>();
+        health.iter_mut(source.world_mut()).next().unwrap().corruption = 0.5;
+        let queued = source.world().resource::<JobQueue>().cpu.len();
+
+        let mut fork = fork_world(source.world()).unwrap();
+        let mut corrupted = fork.world_mut().query::<&WorkerHealth>();
+        assert_eq!(corrupted.iter(fork.world()).filter(|h| h.corruption == 0.5).count(), 1);
+        assert_eq!(fork.world().resource::<JobQueue>().cpu.len(), queued);
+
+        // Startup leaves the stock yards and workers out of a fork
+        fork.advance(1);
+        let mut yards = fork.world_mut().query::<&Workyard>();
+        let mut source_yards = source.world_mut().query::<&Workyard>();
+        assert_eq!(yards.iter(fork.world()).count(), source_yards.iter(source.world()).count());
+    }
+
+    #[test]
+    fn test_fork_carries_debts_and_corruption() {
+        let colony = crate::colony_for_scenario(None, Some(42)).unwrap();
+        let clock = SimClock {
+            tick_scale: crate::TickScale::Seconds(1),
+            now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+        };
+        let mut source = ShadowWorld::new(&colony, &clock, &ActiveScheduler::default(), ColonyPluginConfig::default());
+        source.advance(1);
+        source.world_mut().resource_mut::<Debts>().add_debt(crate::Debt::PowerMult { mult: 1.5, until_tick: u64::MAX });
+        source.world_mut().resource_mut::<CorruptionField>().global = 0.3;
+
+        let fork = fork_world(source.world()).unwrap();
+        let debts = |world: &World| format!("{:?}", world.resource::<Debts>().active);
+        assert_eq!(debts(fork.world()), debts(source.world()));
+        assert_eq!(fork.world().resource::<CorruptionField>().global, 0.3);
+    }
+
+    #[test]
+    fn test_identical_forks_do_not_diverge() {
+        let colony = crate::colony_for_scenario(None, Some(42)).unwrap();
+        let clock = SimClock {
+            tick_scale: crate::TickScale::Seconds(1),
+            now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+        };
+        let mut a = ShadowWorld::new(&colony, &clock, &ActiveScheduler::default(), ColonyPluginConfig::default());
+        let mut b = ShadowWorld::new(&colony, &clock, &ActiveScheduler::default(), ColonyPluginConfig::default());
+        a.advance(30);
+        b.advance(30);
+        let deltas = a.diff_kpis(&mut b);
+        assert_eq!(deltas.deadline_hit_rate_change, 0.0);
+        assert_eq!(deltas.corruption_field_change, 0.0);
+        assert!(deltas.heat_levels_change.iter().all(|d| *d == 0.0));
+        assert!(fork_world(&World::new()).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashSet};
+use anyhow::Result;
+use crate::{Op, Pipeline};
+
+/// Bandwidth rules for one content pipeline. Rates use the bandwidth meter's units, so a
+/// ceiling of half of `bandwidth_total_gbps` holds the pipeline to 50% utilisation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShapingPolicy {
+    pub pipeline: String, // content pipeline id, e.g. "udp_telemetry_ingest"
+    #[serde(default)]
+    pub guarantee_gbps: f32, // held back every tick for this pipeline; other traffic can't use it
+    #[serde(default)]
+    pub ceiling_gbps: Option<f32>, // sustained cap, refilling its token bucket; None leaves it uncapped
+    #[serde(default)]
+    pub burst_gbits: f32, // bucket depth above one tick's ceiling, saved up while the pipeline is quiet
+}
+
+/// `[traffic_shaping]` table in the game config, also set through `PUT /net/shaping`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrafficShapingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub policies: Vec<ShapingPolicy>,
+}
+
+impl TrafficShapingConfig {
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for p in &self.policies {
+            if !seen.insert(p.pipeline.as_str()) {
+                return Err(anyhow::anyhow!("Pipeline {} has more than one shaping policy", p.pipeline));
+            }
+            if p.guarantee_gbps < 0.0 || p.burst_gbits < 0.0 {
+                return Err(anyhow::anyhow!("Pipeline {}: guarantee and burst can't be negative", p.pipeline));
+            }
+            if let Some(ceiling) = p.ceiling_gbps {
+                if ceiling < p.guarantee_gbps {
+                    return Err(anyhow::anyhow!(
+                        "Pipeline {}: ceiling {} is below its guarantee {}",
+                        p.pipeline, ceiling, p.guarantee_gbps
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One pipeline's share of the current tick and what it has sent
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShapingLane {
+    pub reserved_gbits: f32, // guarantee still unused this tick
+    pub tokens: f32,         // ceiling bucket; unused without a ceiling
+    pub sent_gbits: f32,     // this tick so far
+    pub last_tick_gbits: f32,
+    pub total_gbits: f64,
+    pub deferred: u64, // jobs held back to a later tick
+}
+
+impl ShapingLane {
+    fn roll(&mut self) {
+        self.last_tick_gbits = self.sent_gbits;
+        self.sent_gbits = 0.0;
+    }
+}
+
+/// Per-pipeline guarantees, ceilings and token-bucket bursts on the colony's bandwidth.
+/// `power_bandwidth_system` opens each tick's budget; dispatch asks `admit` before a job
+/// moves its payload and leaves it queued when the budget is spent.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrafficShaper {
+    pub enabled: bool,
+    pub policies: Vec<ShapingPolicy>,
+    pub lanes: BTreeMap<String, ShapingLane>,
+    pub unshaped: ShapingLane, // pipelines without a policy, and ad-hoc jobs
+    pub shared_gbits: f32,     // unreserved bandwidth left this tick
+    pub shared_capacity: f32,  // unreserved bandwidth the tick started with
+    pub guarantee_scale: f32,  // < 1.0 when guarantees add up to more than the colony has
+}
+
+impl TrafficShaper {
+    pub fn new() -> Self {
+        Self { guarantee_scale: 1.0, ..Default::default() }
+    }
+
+    pub fn from_config(config: &TrafficShapingConfig) -> Result<Self> {
+        let mut shaper = Self::new();
+        shaper.configure(config)?;
+        Ok(shaper)
+    }
+
+    /// Replace the policies; counters of pipelines that keep a policy carry over
+    pub fn configure(&mut self, config: &TrafficShapingConfig) -> Result<()> {
+        config.validate()?;
+        self.enabled = config.enabled;
+        self.policies = config.policies.clone();
+        self.lanes.retain(|id, _| config.policies.iter().any(|p| &p.pipeline == id));
+        for p in &self.policies {
+            let lane = self.lanes.entry(p.pipeline.clone()).or_default();
+            lane.tokens = p.ceiling_gbps.map_or(0.0, |c| lane.tokens.min(c + p.burst_gbits));
+        }
+        Ok(())
+    }
+
+    pub fn config(&self) -> TrafficShapingConfig {
+        TrafficShapingConfig { enabled: self.enabled, policies: self.policies.clone() }
+    }
+
+    /// Open the next tick's budget out of `total_gbps`
+    pub fn begin_tick(&mut self, total_gbps: f32) {
+        if !self.enabled {
+            return;
+        }
+        let guaranteed: f32 = self.policies.iter().map(|p| p.guarantee_gbps).sum();
+        self.guarantee_scale = if guaranteed > total_gbps && guaranteed > 0.0 { total_gbps / guaranteed } else { 1.0 };
+        self.shared_capacity = (total_gbps - guaranteed * self.guarantee_scale).max(0.0);
+        self.shared_gbits = self.shared_capacity;
+        for p in &self.policies {
+            let lane = self.lanes.entry(p.pipeline.clone()).or_default();
+            lane.roll();
+            lane.reserved_gbits = p.guarantee_gbps * self.guarantee_scale;
+            if let Some(ceiling) = p.ceiling_gbps {
+                lane.tokens = (lane.tokens + ceiling).min(ceiling + p.burst_gbits);
+            }
+        }
+        self.unshaped.roll();
+    }
+
+    /// Charge `gbits` for a job of `pipeline`; false means it waits for a later tick
+    pub fn admit(&mut self, pipeline: &Pipeline, gbits: f32) -> bool {
+        if !self.enabled || gbits <= 0.0 {
+            return true;
+        }
+        let policy = pipeline.id.as_ref().and_then(|id| self.policies.iter().find(|p| &p.pipeline == id));
+        let Some(policy) = policy else {
+            return Self::charge(&mut self.unshaped, &mut self.shared_gbits, self.shared_capacity, 0.0, gbits);
+        };
+        let lane = self.lanes.entry(policy.pipeline.clone()).or_default();
+        let reservation = policy.guarantee_gbps * self.guarantee_scale;
+        if let Some(ceiling) = policy.ceiling_gbps {
+            // A transfer bigger than the whole bucket goes through once the bucket is full
+            let bucket_charge = gbits.min(ceiling + policy.burst_gbits);
+            if lane.tokens < bucket_charge {
+                lane.deferred += 1;
+                return false;
+            }
+            if !Self::charge(lane, &mut self.shared_gbits, self.shared_capacity, reservation, gbits) {
+                return false;
+            }
+            lane.tokens -= bucket_charge;
+            return true;
+        }
+        Self::charge(lane, &mut self.shared_gbits, self.shared_capacity, reservation, gbits)
+    }
+
+    /// Take `gbits` from the lane's reservation, then the shared pool. A transfer bigger than a
+    /// whole tick's budget is charged the whole budget, so it goes on an untouched tick.
+    fn charge(lane: &mut ShapingLane, shared: &mut f32, shared_capacity: f32, reservation: f32, gbits: f32) -> bool {
+        let budget = reservation + shared_capacity;
+        let charge = gbits.min(budget);
+        let from_reserved = lane.reserved_gbits.min(charge);
+        let from_shared = charge - from_reserved;
+        if budget <= 0.0 || from_shared > *shared + f32::EPSILON {
+            lane.deferred += 1;
+            return false;
+        }
+        lane.reserved_gbits -= from_reserved;
+        *shared = (*shared - from_shared).max(0.0);
+        lane.sent_gbits += gbits;
+        lane.total_gbits += gbits as f64;
+        true
+    }
+
+    pub fn lane(&self, pipeline: &str) -> Option<&ShapingLane> {
+        self.lanes.get(pipeline)
+    }
+}
+
+/// Ops that move the job's payload over the colony's links: ingress parsing and HTTP export
+pub fn network_ops(ops: &[Op]) -> usize {
+    ops.iter().filter(|op| matches!(op, Op::UdpDemux | Op::HttpParse | Op::HttpExport)).count()
+}
+
+pub fn bytes_to_gbits(bytes: usize) -> f32 {
+    (bytes as f32 * 8.0) / 1_000_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pipeline(id: &str, ops: Vec<Op>) -> Pipeline {
+        Pipeline { ops, mutation_tag: None, id: Some(id.to_string()) }
+    }
+
+    fn shaper(policies: Vec<ShapingPolicy>) -> TrafficShaper {
+        TrafficShaper::from_config(&TrafficShapingConfig { enabled: true, policies }).unwrap()
+    }
+
+    #[test]
+    fn test_guarantee_protects_ingest_from_exports() {
+        let mut shaper = shaper(vec![ShapingPolicy {
+            pipeline: "udp_telemetry_ingest".into(),
+            guarantee_gbps: 0.5,
+            ceiling_gbps: None,
+            burst_gbits: 0.0,
+        }]);
+        let ingest = pipeline("udp_telemetry_ingest", vec![Op::UdpDemux, Op::Decode]);
+        let export = pipeline("bulk_export", vec![Op::HttpExport]);
+        shaper.begin_tick(1.0);
+
+        // Exports drain the shared half and then wait
+        let sent = (0..10).take_while(|_| shaper.admit(&export, 0.125)).count();
+        assert_eq!(sent, 4);
+        assert_eq!(shaper.unshaped.deferred, 1);
+        // Ingest still has its reservation
+        assert!(shaper.admit(&ingest, 0.25) && shaper.admit(&ingest, 0.25));
+        assert!(!shaper.admit(&ingest, 0.25));
+
+        shaper.begin_tick(1.0);
+        assert_eq!(shaper.lane("udp_telemetry_ingest").unwrap().last_tick_gbits, 0.5);
+        assert!(shaper.admit(&export, 0.125));
+    }
+
+    #[test]
+    fn test_ceiling_bucket_allows_saved_up_bursts() {
+        let mut shaper = shaper(vec![ShapingPolicy {
+            pipeline: "http_ingest".into(),
+            guarantee_gbps: 0.0,
+            ceiling_gbps: Some(0.25),
+            burst_gbits: 0.5,
+        }]);
+        let http = pipeline("http_ingest", vec![Op::HttpParse]);
+        shaper.begin_tick(10.0);
+        assert!(shaper.admit(&http, 0.25));
+        assert!(!shaper.admit(&http, 0.25));
+
+        // Quiet ticks fill the bucket to ceiling + burst, and no further
+        for _ in 0..5 {
+            shaper.begin_tick(10.0);
+        }
+        let burst = (0..10).take_while(|_| shaper.admit(&http, 0.25)).count();
+        assert_eq!(burst, 3);
+        // Bigger than the whole bucket: charged the bucket, so it goes once the bucket is full again
+        for _ in 0..3 {
+            shaper.begin_tick(10.0);
+        }
+        assert!(shaper.admit(&http, 5.0));
+    }
+
+    #[test]
+    fn test_config_is_checked() {
+        let config: TrafficShapingConfig = toml::from_str(
+            "enabled = true\n[[policies]]\npipeline = \"udp_telemetry_ingest\"\nguarantee_gbps = 0.5\nceiling_gbps = 0.8\n",
+        ).unwrap();
+        assert!(config.validate().is_ok());
+
+        let mut inverted = config.clone();
+        inverted.policies[0].ceiling_gbps = Some(0.1);
+        assert!(inverted.validate().is_err());
+        let mut twice = config.clone();
+        twice.policies.push(config.policies[0].clone());
+        assert!(twice.validate().is_err());
+        assert!(toml::from_str::<TrafficShapingConfig>("[[policies]]\npipeline = \"x\"\nceiling = 1.0\n").is_err());
+    }
+
+    #[test]
+    fn test_disabled_shaper_admits_everything() {
+        let mut shaper = TrafficShaper::new();
+        shaper.begin_tick(0.0);
+        assert!(shaper.admit(&pipeline("udp_telemetry_ingest", vec![Op::UdpDemux]), 100.0));
+        assert_eq!(network_ops(&[Op::UdpDemux, Op::Decode, Op::HttpExport]), 2);
+    }
+}
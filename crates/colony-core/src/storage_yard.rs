@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use crate::{Job, Pipeline, Op, QoS, JobQueue, Workyard, WorkyardKind};
+use crate::queue::EnqueuedJob;
+use crate::MaintenanceEntry;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriveWearTunables {
+    pub wear_per_op: f32,     // wear added by each storage-bound op
+    pub degrade_knee: f32,    // wear above which throughput starts to fall
+    pub min_throughput: f32,  // fraction of rated IOPS left on fully worn drives
+    pub replace_at: f32,      // wear at which a drive replacement is queued
+}
+
+impl Default for DriveWearTunables {
+    fn default() -> Self {
+        Self {
+            wear_per_op: 0.00002,
+            degrade_knee: 0.6,
+            min_throughput: 0.25,
+            replace_at: 0.9,
+        }
+    }
+}
+
+/// SSD state of a StorageArray yard: IOPS budget per tick and accumulated wear
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+pub struct DriveWear {
+    pub tunables: DriveWearTunables,
+    pub rated_iops_per_tick: u32,
+    pub wear: f32,                // 0.0 fresh .. 1.0 worn out
+    pub ops_this_tick: u32,
+    pub served: u64,              // storage ops completed
+    pub deferred: u64,            // jobs held back because the tick's IOPS were spent
+    pub replacement_queued: bool,
+    pub replacements: u32,
+}
+
+impl DriveWear {
+    pub fn new(rated_iops_per_tick: u32) -> Self {
+        Self {
+            tunables: DriveWearTunables::default(),
+            rated_iops_per_tick,
+            wear: 0.0,
+            ops_this_tick: 0,
+            served: 0,
+            deferred: 0,
+            replacement_queued: false,
+            replacements: 0,
+        }
+    }
+
+    /// Throughput left after wear: full until the knee, then falling to `min_throughput`
+    pub fn throughput_mult(&self) -> f32 {
+        let t = &self.tunables;
+        if self.wear <= t.degrade_knee {
+            return 1.0;
+        }
+        let over = (self.wear - t.degrade_knee) / (1.0 - t.degrade_knee).max(f32::EPSILON);
+        1.0 - over.clamp(0.0, 1.0) * (1.0 - t.min_throughput)
+    }
+
+    pub fn effective_iops(&self) -> u32 {
+        ((self.rated_iops_per_tick as f32 * self.throughput_mult()).floor() as u32).max(1)
+    }
+
+    /// Whether this tick's IOPS budget covers a job's storage ops; false means it waits and
+    /// counts as deferred. The first job of a tick always gets through so oversized jobs can't
+    /// starve. Nothing is spent until `consume`.
+    pub fn has_iops(&mut self, ops: u32) -> bool {
+        if ops > 0 && self.ops_this_tick > 0 && self.ops_this_tick + ops > self.effective_iops() {
+            self.deferred += 1;
+            return false;
+        }
+        true
+    }
+
+    /// Spend IOPS for a job that passed every admission check
+    pub fn consume(&mut self, ops: u32) {
+        self.ops_this_tick += ops;
+        self.served += ops as u64;
+        self.wear = (self.wear + ops as f32 * self.tunables.wear_per_op).min(1.0);
+    }
+
+    /// Check and spend in one go
+    pub fn try_consume(&mut self, ops: u32) -> bool {
+        if !self.has_iops(ops) {
+            return false;
+        }
+        self.consume(ops);
+        true
+    }
+
+    pub fn needs_replacement(&self) -> bool {
+        self.wear >= self.tunables.replace_at
+    }
+
+    /// Swap in fresh drives
+    pub fn replace(&mut self) {
+        self.wear = 0.0;
+        self.replacement_queued = false;
+        self.replacements += 1;
+    }
+}
+
+/// Ops that hit the disks and contend for a StorageArray's IOPS
+pub fn storage_ops(ops: &[Op]) -> u32 {
+    ops.iter().filter(|op| matches!(op, Op::Export | Op::ArchiveWrite)).count() as u32
+}
+
+pub fn enqueue_drive_replacement(yard_entity: Entity, jobq: &mut JobQueue, job_ids: &mut crate::JobIdAllocator, now_tick: u64) {
+    let replacement_job = Job {
+        id: job_ids.allocate(),
+        pipeline: Pipeline {
+            ops: vec![Op::ReplaceDrives],
+            mutation_tag: Some("maintenance".to_string()),
+            id: None,
+        },
+        qos: QoS::Balanced,
+        deadline_ms: 30_000,
+        payload_sz: 0,
+    };
+
+    // Replacement rides the same reserved lane as other maintenance
+    jobq.maintenance.push(MaintenanceEntry {
+        yard: yard_entity,
+        job: EnqueuedJob::new(replacement_job, now_tick),
+    });
+}
+
+pub fn storage_wear_system(
+    mut yards: Query<(Entity, &Workyard, &mut DriveWear)>,
+    mut jobq: ResMut<JobQueue>,
+    mut job_ids: ResMut<crate::JobIdAllocator>,
+    clock: Res<crate::SimClock>,
+) {
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    for (yard_e, yard, mut drives) in yards.iter_mut() {
+        if yard.kind != WorkyardKind::StorageArray {
+            continue;
+        }
+        drives.ops_this_tick = 0;
+        if drives.needs_replacement() && !drives.replacement_queued {
+            println!("StorageArray drives at {:.0}% wear; queueing replacement", drives.wear * 100.0);
+            enqueue_drive_replacement(yard_e, &mut jobq, &mut job_ids, now_tick);
+            drives.replacement_queued = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iops_budget_defers_jobs() {
+        let mut drives = DriveWear::new(3);
+        assert!(drives.try_consume(2));
+        assert!(!drives.try_consume(2));
+        assert!(drives.try_consume(1));
+        assert_eq!(drives.deferred, 1);
+
+        // A new tick; an oversized job still goes first
+        drives.ops_this_tick = 0;
+        assert!(drives.try_consume(5));
+        assert_eq!(drives.served, 8);
+    }
+
+    #[test]
+    fn test_jobs_waiting_on_iops_keep_their_bandwidth() {
+        use crate::{ShapingPolicy, TrafficShaper, TrafficShapingConfig};
+        let colony = crate::colony_for_scenario(None, Some(42)).unwrap();
+        let clock = crate::SimClock { tick_scale: crate::TickScale::RealTime, now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH };
+        let mut app = crate::build_shadow_app(&colony, &clock, &crate::ActiveScheduler::default());
+        app.update();
+        let policy = ShapingPolicy { pipeline: "bulk_export".into(), guarantee_gbps: 0.0, ceiling_gbps: Some(colony.bandwidth_total_gbps), burst_gbits: 0.0 };
+        app.insert_resource(TrafficShaper::from_config(&TrafficShapingConfig { enabled: true, policies: vec![policy] }).unwrap());
+        let mut arrays = app.world_mut().query::<&mut DriveWear>();
+        for mut drives in arrays.iter_mut(app.world_mut()) {
+            drives.rated_iops_per_tick = 1;
+        }
+        let ids = 9_000..9_006;
+        for id in ids.clone() {
+            let job = Job {
+                id,
+                pipeline: Pipeline { ops: vec![Op::Export, Op::HttpExport], mutation_tag: None, id: Some("bulk_export".into()) },
+                qos: QoS::Balanced,
+                deadline_ms: 60_000,
+                payload_sz: 1_000_000,
+            };
+            app.world_mut().resource_mut::<JobQueue>().push(job, 0);
+        }
+
+        for _ in 0..3 {
+            app.update();
+        }
+        let mut arrays = app.world_mut().query::<&DriveWear>();
+        assert!(arrays.iter(app.world()).any(|drives| drives.deferred > 0));
+        let waiting = app.world().resource::<JobQueue>().peek_storage().iter().filter(|ej| ids.contains(&ej.job.id)).count();
+        let dispatched = ids.clone().count() - waiting;
+        assert!(waiting > 0 && dispatched > 0);
+        // Only the jobs that went out were charged to the pipeline's lane
+        let lane = app.world().resource::<TrafficShaper>().lane("bulk_export").unwrap().clone();
+        let expected = dispatched as f64 * crate::bytes_to_gbits(1_000_000) as f64;
+        assert!((lane.total_gbits - expected).abs() < 1e-6, "{} vs {}", lane.total_gbits, expected);
+    }
+
+    #[test]
+    fn test_wear_degrades_throughput() {
+        let mut drives = DriveWear::new(100);
+        assert_eq!(drives.effective_iops(), 100);
+        drives.wear = 0.8;
+        assert!(drives.throughput_mult() < 1.0);
+        drives.wear = 1.0;
+        assert_eq!(drives.effective_iops(), 25);
+        assert!(drives.needs_replacement());
+
+        drives.replace();
+        assert_eq!(drives.effective_iops(), 100);
+        assert_eq!(drives.replacements, 1);
+    }
+
+    #[test]
+    fn test_worn_drives_queue_replacement_once() {
+        let mut app = App::new();
+        let clock = crate::SimClock {
+            tick_scale: crate::TickScale::RealTime,
+            now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+        };
+        app.insert_resource(JobQueue::new())
+            .insert_resource(crate::JobIdAllocator::new())
+            .insert_resource(clock)
+            .add_systems(Update, storage_wear_system);
+        let mut drives = DriveWear::new(10);
+        drives.wear = 0.95;
+        app.world_mut().spawn((
+            Workyard {
+                kind: WorkyardKind::StorageArray,
+                slots: 2,
+                heat: 20.0,
+                heat_cap: 70.0,
+                power_draw_kw: 80.0,
+                bandwidth_share: 0.2,
+                isolation_domain: 2,
+            },
+            drives,
+        ));
+
+        app.update();
+        app.update();
+        let jobq = app.world().resource::<JobQueue>();
+        assert_eq!(jobq.maintenance.len(), 1);
+        assert!(matches!(jobq.maintenance[0].job.job.pipeline.ops[0], Op::ReplaceDrives));
+    }
+}
@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use bevy::platform::time::Instant;
+use anyhow::Result;
+use crate::{
+    SimClock, TickScale, ActiveScheduler, JobQueue, Job, QoS, build_shadow_app_with_config, colony_for_scenario, plugin_config_for_scenario,
+    get_pipeline_by_id, STRESS_SCENARIO_ID, JobIdAllocator,
+};
+
+/// Sim ticks per minute at the 16ms tick used throughout the core
+pub const TICKS_PER_MINUTE: u64 = 60_000 / 16;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Allocator wrapper that counts allocations. Install it with `#[global_allocator]`
+/// in a bench or binary to get allocation figures in StressReport.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// (allocation count, bytes allocated) since process start; zeros unless CountingAllocator is installed
+pub fn allocation_stats() -> (u64, u64) {
+    (ALLOCATIONS.load(Ordering::Relaxed), ALLOCATED_BYTES.load(Ordering::Relaxed))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StressProfile {
+    pub name: String,
+    pub scenario: String,
+    pub ticks: u64,
+    pub ramp_ticks: u64,            // linear ramp from 0 to full rate
+    pub udp_packets_per_min: u64,
+    pub can_frames_per_min: u64,
+    pub initial_jobs: u32,          // backlog queued before the first tick
+    pub max_queue_depth: usize,     // ingress drops packets beyond this
+}
+
+impl StressProfile {
+    /// 1M packets/minute split 80/20 between UDP telemetry and CAN frames
+    pub fn million_per_minute() -> Self {
+        Self {
+            name: "1m_ppm".to_string(),
+            scenario: STRESS_SCENARIO_ID.to_string(),
+            ticks: TICKS_PER_MINUTE,
+            ramp_ticks: TICKS_PER_MINUTE / 10,
+            udp_packets_per_min: 800_000,
+            can_frames_per_min: 200_000,
+            initial_jobs: 5_000,
+            max_queue_depth: 10_000,
+        }
+    }
+
+    /// Packets to inject on a given tick, following the ramp
+    pub fn rate_per_tick(&self, per_min: u64, tick: u64) -> f64 {
+        let full = per_min as f64 / TICKS_PER_MINUTE as f64;
+        if self.ramp_ticks == 0 || tick >= self.ramp_ticks {
+            full
+        } else {
+            full * tick as f64 / self.ramp_ticks as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StressReport {
+    pub profile: String,
+    pub ticks: u64,
+    pub sim_elapsed_ms: u64,        // time spent in app.update() only
+    pub ticks_per_sec: f64,
+    pub packets_injected: u64,
+    pub packets_dropped: u64,
+    pub jobs_completed: u64,
+    pub max_queue_depth: usize,
+    pub mean_queue_latency_ticks: f64,
+    pub p99_queue_latency_ticks: u64,
+    pub allocations: u64,
+    pub allocated_bytes: u64,
+    pub allocations_per_tick: f64,
+}
+
+struct Injector {
+    udp_carry: f64,
+    can_carry: f64,
+}
+
+impl Injector {
+    fn inject(&mut self, profile: &StressProfile, tick: u64, jobq: &mut JobQueue, job_ids: &mut JobIdAllocator, queued: &mut HashMap<u64, u64>, report: &mut StressReport) {
+        self.udp_carry += profile.rate_per_tick(profile.udp_packets_per_min, tick);
+        self.can_carry += profile.rate_per_tick(profile.can_frames_per_min, tick);
+        let udp = self.udp_carry.floor() as u64;
+        let can = self.can_carry.floor() as u64;
+        self.udp_carry -= udp as f64;
+        self.can_carry -= can as f64;
+
+        report.packets_injected += udp + can;
+        report.packets_dropped += self.push_jobs("udp_telemetry_ingest", udp, tick, profile.max_queue_depth, jobq, job_ids, queued);
+        report.packets_dropped += self.push_jobs("can_telemetry", can, tick, profile.max_queue_depth, jobq, job_ids, queued);
+    }
+
+    /// Returns how many packets were dropped at the queue depth limit
+    fn push_jobs(&mut self, pipeline_id: &str, count: u64, tick: u64, max_depth: usize, jobq: &mut JobQueue, job_ids: &mut JobIdAllocator, queued: &mut HashMap<u64, u64>) -> u64 {
+        let Some(pipeline) = get_pipeline_by_id(pipeline_id) else { return 0 };
+        let (payload_sz, deadline_ms) = match pipeline_id {
+            "can_telemetry" => (64, 10),
+            _ => (1024, 50),
+        };
+        let mut dropped = 0;
+        for _ in 0..count {
+            if jobq.len() >= max_depth {
+                dropped += 1;
+                continue;
+            }
+            let id = job_ids.allocate();
+            jobq.push(Job {
+                id,
+                pipeline: pipeline.clone(),
+                qos: QoS::Throughput,
+                deadline_ms,
+                payload_sz,
+            }, tick);
+            queued.insert(id, tick);
+        }
+        dropped
+    }
+}
+
+/// Drive the full plugin stack under a stress profile and measure the sim loop
+pub fn run_stress(profile: &StressProfile) -> Result<StressReport> {
+    let colony = colony_for_scenario(Some(&profile.scenario), None)?;
+    let clock = SimClock {
+        tick_scale: TickScale::Seconds(1),
+        now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+    };
+    let config = plugin_config_for_scenario(Some(&profile.scenario))?;
+    let mut app = build_shadow_app_with_config(&colony, &clock, &ActiveScheduler::default(), config);
+    // Run Startup once so the measured loop only covers steady-state updates
+    app.update();
+
+    let mut report = StressReport { profile: profile.name.clone(), ..Default::default() };
+    let mut injector = Injector { udp_carry: 0.0, can_carry: 0.0 };
+    let mut queued: HashMap<u64, u64> = HashMap::new();
+    let mut latencies: Vec<u64> = Vec::new();
+
+    app.world_mut().resource_scope(|world, mut job_ids: Mut<JobIdAllocator>| {
+        let mut jobq = world.resource_mut::<JobQueue>();
+        injector.push_jobs("udp_telemetry_ingest", profile.initial_jobs as u64, 0, profile.max_queue_depth, &mut jobq, &mut job_ids, &mut queued);
+    });
+
+    let mut sim_elapsed = std::time::Duration::ZERO;
+    let (allocs_before, bytes_before) = allocation_stats();
+    let mut untimed_allocs = 0;
+    let mut untimed_bytes = 0;
+
+    for tick in 1..=profile.ticks {
+        let (a0, b0) = allocation_stats();
+        app.world_mut().resource_scope(|world, mut job_ids: Mut<JobIdAllocator>| {
+            let mut jobq = world.resource_mut::<JobQueue>();
+            injector.inject(profile, tick, &mut jobq, &mut job_ids, &mut queued, &mut report);
+            report.max_queue_depth = report.max_queue_depth.max(jobq.len());
+        });
+        let (a1, b1) = allocation_stats();
+        untimed_allocs += a1 - a0;
+        untimed_bytes += b1 - b0;
+
+        let start = Instant::now();
+        app.update();
+        sim_elapsed += start.elapsed();
+
+        // Latency bookkeeping stays outside the timed section
+        let (a2, b2) = allocation_stats();
+        let jobq = app.world().resource::<JobQueue>();
+        let still_queued: HashSet<u64> = jobq.cpu.iter()
+            .chain(jobq.gpu.iter())
+            .chain(jobq.io.iter())
+            .chain(jobq.storage.iter())
+            .map(|ej| ej.job.id)
+            .collect();
+        queued.retain(|id, enq_tick| {
+            if still_queued.contains(id) {
+                true
+            } else {
+                latencies.push(tick - *enq_tick);
+                false
+            }
+        });
+        let (a3, b3) = allocation_stats();
+        untimed_allocs += a3 - a2;
+        untimed_bytes += b3 - b2;
+    }
+
+    let (allocs_after, bytes_after) = allocation_stats();
+    report.ticks = profile.ticks;
+    report.sim_elapsed_ms = sim_elapsed.as_millis() as u64;
+    report.ticks_per_sec = profile.ticks as f64 / sim_elapsed.as_secs_f64().max(f64::EPSILON);
+    report.jobs_completed = latencies.len() as u64;
+    report.allocations = allocs_after - allocs_before - untimed_allocs;
+    report.allocated_bytes = bytes_after - bytes_before - untimed_bytes;
+    report.allocations_per_tick = report.allocations as f64 / profile.ticks.max(1) as f64;
+
+    if !latencies.is_empty() {
+        latencies.sort_unstable();
+        report.mean_queue_latency_ticks = latencies.iter().sum::<u64>() as f64 / latencies.len() as f64;
+        let p99_idx = ((latencies.len() as f64 * 0.99) as usize).min(latencies.len() - 1);
+        report.p99_queue_latency_ticks = latencies[p99_idx];
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_ramps_to_full() {
+        let profile = StressProfile::million_per_minute();
+        assert_eq!(profile.rate_per_tick(profile.udp_packets_per_min, 0), 0.0);
+        let full = profile.rate_per_tick(profile.udp_packets_per_min, profile.ramp_ticks);
+        assert!((full * TICKS_PER_MINUTE as f64 - 800_000.0).abs() < 1.0);
+        let partial = profile.rate_per_tick(profile.udp_packets_per_min, 100);
+        assert!((partial - full * 100.0 / profile.ramp_ticks as f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_short_stress_run() {
+        let profile = StressProfile {
+            ticks: 30,
+            ramp_ticks: 0,
+            initial_jobs: 100,
+            max_queue_depth: 500,
+            ..StressProfile::million_per_minute()
+        };
+        let report = run_stress(&profile).unwrap();
+        assert_eq!(report.ticks, 30);
+        assert!(report.packets_injected > 0);
+        assert!(report.max_queue_depth <= 500);
+        assert!(report.packets_dropped > 0);
+        assert!(report.ticks_per_sec > 0.0);
+    }
+}
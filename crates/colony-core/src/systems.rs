@@ -1,19 +1,32 @@
 use bevy::prelude::*;
-use crate::{Colony, Workyard, YardWorkload, DispatchScale, Worker, WorkerState, IoRolling, CorruptionField, Debts};
+use crate::{Colony, Workyard, YardWorkload, DispatchScale, WorkerHealth, WorkerState, WorkerStateComp, IoRolling, CorruptionField, Debts, TrafficShaper};
 use crate::queue::{JobQueue, average_starvation};
 
+/// Share of a yard's rated draw it pulls while idle; the rest follows its load
+const IDLE_POWER_FRACTION: f32 = 0.4;
+/// Work units per slot in a tick at which a yard runs flat out
+const FULL_LOAD_UNITS_PER_SLOT: f32 = 3.0;
+
+/// Fraction of a yard's rated draw it pulls for `units` of work in a tick
+pub fn yard_power_fraction(yard: &Workyard, units: f32) -> f32 {
+    let load = (units / (yard.slots.max(1) as f32 * FULL_LOAD_UNITS_PER_SLOT)).clamp(0.0, 1.0);
+    IDLE_POWER_FRACTION + (1.0 - IDLE_POWER_FRACTION) * load
+}
+
 pub fn power_bandwidth_system(
     mut colony: ResMut<Colony>,
     mut dispatch_scale: ResMut<DispatchScale>,
     mut io_rolling: ResMut<IoRolling>,
-    yards: Query<&Workyard>,
+    mut shaper: ResMut<TrafficShaper>,
+    yards: Query<(&Workyard, &YardWorkload)>,
     debts: Res<Debts>,
     clock: Res<crate::SimClock>,
 ) {
     let mut draw = 0.0;
 
-    for y in &yards {
-        draw += y.power_draw_kw;
+    // Last tick's dispatched work, before heat_system clears it
+    for (y, w) in &yards {
+        draw += y.power_draw_kw * yard_power_fraction(y, w.units_this_tick);
     }
 
     // Apply debt multipliers
@@ -27,6 +40,8 @@ pub fn power_bandwidth_system(
     let io_gbits = io_rolling.take_and_reset();
     let util = (io_gbits / colony.bandwidth_total_gbps).clamp(0.0, 1.0);
     colony.meters.bandwidth_util = util;
+    // Open the next tick's per-pipeline guarantees, ceilings and buckets
+    shaper.begin_tick(colony.bandwidth_total_gbps);
 
     let scale = if draw * power_mult > colony.power_cap_kw { 
         colony.power_cap_kw / (draw * power_mult)
@@ -57,7 +72,7 @@ pub fn heat_system(
 pub fn corruption_system(
     mut colony: ResMut<Colony>,
     mut corruption_field: ResMut<CorruptionField>,
-    mut workers: Query<&mut Worker>,
+    mut workers: Query<(&WorkerStateComp, &mut WorkerHealth)>,
     yards: Query<&Workyard>,
     jobq: Res<JobQueue>,
     clock: Res<crate::SimClock>,
@@ -91,29 +106,31 @@ pub fn corruption_system(
     corruption_field.global = (corruption_field.global + stress_contribution).min(1.0);
     
     // Update worker corruption
-    for mut worker in workers.iter_mut() {
+    for (state, mut health) in workers.iter_mut() {
         let mut decay = colony.corruption_tun.worker_decay_per_tick;
         
         // Apply recovery boost for idle workers or after maintenance
-        if worker.state == WorkerState::Idle {
+        if state.0 == WorkerState::Idle {
             decay += colony.corruption_tun.recover_boost;
         }
         
-        worker.corruption = (worker.corruption - decay).max(0.0);
-        
         // Add stress contribution to worker corruption
         let worker_stress = (
             colony.corruption_tun.heat_weight * avg_heat_frac +
             colony.corruption_tun.bw_weight * colony.meters.bandwidth_util
         ) * 0.0005; // Smaller increment for individual workers
         
-        worker.corruption = (worker.corruption + worker_stress).min(1.0);
+        let corruption = ((health.corruption - decay).max(0.0) + worker_stress).min(1.0);
+        // Skip the write when nothing moved so the component isn't flagged as changed
+        if corruption != health.corruption {
+            health.corruption = corruption;
+        }
     }
 }
 
 pub fn maintenance_system(
     mut yards: Query<(&mut Workyard, &mut YardWorkload)>,
-    mut workers: Query<&mut crate::Worker>,
+    mut workers: Query<&mut crate::WorkerHealth>,
     mut report_reader: EventReader<crate::WorkerReport>,
 ) {
     for report in report_reader.read() {
@@ -128,9 +145,9 @@ pub fn maintenance_system(
                 yard.heat = (yard.heat - 15.0).max(20.0);
                 
                 // Reduce corruption for workers in this yard's isolation domain
-                for mut worker in workers.iter_mut() {
-                    if worker.corruption > 0.0 {
-                        worker.corruption *= 0.98;
+                for mut health in workers.iter_mut() {
+                    if health.corruption > 0.0 {
+                        health.corruption *= 0.98;
                     }
                 }
                 break; // Only apply to first yard for now
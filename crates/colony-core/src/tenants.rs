@@ -0,0 +1,338 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashSet};
+use anyhow::Result;
+
+/// Limits for one tenant (team) sharing the colony
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantQuota {
+    pub tenant: String,
+    #[serde(default)]
+    pub max_queued_jobs: Option<usize>, // further jobs are refused at admission
+    #[serde(default)]
+    pub bandwidth_share: Option<f32>, // 0..1 of the colony's bandwidth per tick; over it, jobs wait
+    #[serde(default)]
+    pub gpu_batch_share: Option<f32>, // 0..1 of the GPU batch slots per tick
+}
+
+/// `[tenants]` table in the game config, also set through `PUT /tenants/quotas`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TenantConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub quotas: Vec<TenantQuota>,
+}
+
+impl TenantConfig {
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for q in &self.quotas {
+            if q.tenant.is_empty() {
+                return Err(anyhow::anyhow!("Tenant quota without a tenant name"));
+            }
+            if !seen.insert(q.tenant.as_str()) {
+                return Err(anyhow::anyhow!("Tenant {} has more than one quota", q.tenant));
+            }
+            for (name, share) in [("bandwidth_share", q.bandwidth_share), ("gpu_batch_share", q.gpu_batch_share)] {
+                if let Some(share) = share {
+                    if !(0.0..=1.0).contains(&share) {
+                        return Err(anyhow::anyhow!("Tenant {}: {} {} is outside 0..=1", q.tenant, name, share));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One tenant's jobs, traffic and deadlines
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TenantUsage {
+    pub queued: usize,   // admitted and not yet finished
+    pub admitted: u64,
+    pub rejected: u64,   // refused at admission for max_queued_jobs
+    pub completed: u64,
+    pub faulted: u64,
+    pub deadline_hits: u64,
+    pub deadline_misses: u64,
+    pub total_gbits: f64,
+    pub gpu_batch_items: u64,
+    pub bandwidth_deferred: u64, // dispatches held back for bandwidth_share
+    pub gpu_deferred: u64,       // batch slots held back for gpu_batch_share
+    pub tick_gbits: f32,
+    pub tick_gpu_items: usize,
+}
+
+/// A tenant's deadline record, served at `GET /tenants/:id/sla`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantSla {
+    pub tenant: String,
+    pub completed: u64,
+    pub faulted: u64,
+    pub deadline_hit_rate: f32, // over completed jobs; 1.0 before any
+    pub fault_rate: f32,
+}
+
+/// Tenant quotas and usage. Jobs carry their tenant on `EnqueuedJob::tenant`; untagged jobs
+/// aren't counted or limited. Quotas only bite while `enabled`, usage is kept either way.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TenantLedger {
+    pub enabled: bool,
+    pub quotas: Vec<TenantQuota>,
+    pub usage: BTreeMap<String, TenantUsage>,
+}
+
+impl TenantLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_config(config: &TenantConfig) -> Result<Self> {
+        let mut ledger = Self::new();
+        ledger.configure(config)?;
+        Ok(ledger)
+    }
+
+    /// Replace the quotas; usage carries over
+    pub fn configure(&mut self, config: &TenantConfig) -> Result<()> {
+        config.validate()?;
+        self.enabled = config.enabled;
+        self.quotas = config.quotas.clone();
+        Ok(())
+    }
+
+    pub fn config(&self) -> TenantConfig {
+        TenantConfig { enabled: self.enabled, quotas: self.quotas.clone() }
+    }
+
+    fn quota(&self, tenant: &str) -> Option<&TenantQuota> {
+        if !self.enabled {
+            return None;
+        }
+        self.quotas.iter().find(|q| q.tenant == tenant)
+    }
+
+    /// Admit a job for `tenant`, or refuse it when the tenant already has its maximum queued
+    pub fn admit(&mut self, tenant: Option<&str>) -> Result<()> {
+        let Some(tenant) = tenant else { return Ok(()) };
+        let refused = self.check_admit(Some(tenant));
+        let usage = self.usage.entry(tenant.to_string()).or_default();
+        if refused.is_err() {
+            usage.rejected += 1;
+            return refused;
+        }
+        usage.queued += 1;
+        usage.admitted += 1;
+        Ok(())
+    }
+
+    /// Whether `admit` would take a job for `tenant` now, without counting anything
+    pub fn check_admit(&self, tenant: Option<&str>) -> Result<()> {
+        let Some(tenant) = tenant else { return Ok(()) };
+        let max_queued = self.quota(tenant).and_then(|q| q.max_queued_jobs);
+        let queued = self.usage.get(tenant).map_or(0, |u| u.queued);
+        match max_queued {
+            Some(max) if queued >= max => Err(anyhow::anyhow!("Tenant {} already has {} jobs queued (quota {})", tenant, queued, max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Gigabits per tick `tenant` may move, when its quota gives it a bandwidth share
+    pub fn bandwidth_budget(&self, tenant: &str, total_gbps: f32) -> Option<f32> {
+        self.quota(tenant).and_then(|q| q.bandwidth_share).map(|share| share * total_gbps)
+    }
+
+    /// Start a new tick's bandwidth and GPU budgets
+    pub fn begin_tick(&mut self) {
+        for usage in self.usage.values_mut() {
+            usage.tick_gbits = 0.0;
+            usage.tick_gpu_items = 0;
+        }
+    }
+
+    /// Whether `gbits` for a job of `tenant` fit its bandwidth share this tick; false means it
+    /// waits for a later tick and counts as deferred. A transfer bigger than the tenant's whole
+    /// share goes through on a tick it has to itself. Nothing is charged until `commit_bandwidth`.
+    pub fn check_bandwidth(&mut self, tenant: Option<&str>, gbits: f32, total_gbps: f32) -> bool {
+        let Some(tenant) = tenant else { return true };
+        let Some(share) = self.quota(tenant).and_then(|q| q.bandwidth_share) else { return true };
+        let budget = share * total_gbps;
+        let tick_gbits = self.usage.get(tenant).map_or(0.0, |u| u.tick_gbits);
+        if gbits > 0.0 && (budget <= 0.0 || (tick_gbits > 0.0 && tick_gbits + gbits > budget)) {
+            self.usage.entry(tenant.to_string()).or_default().bandwidth_deferred += 1;
+            return false;
+        }
+        true
+    }
+
+    /// Charge `gbits` to `tenant` once its job has passed every admission check
+    pub fn commit_bandwidth(&mut self, tenant: Option<&str>, gbits: f32) {
+        let Some(tenant) = tenant else { return };
+        let usage = self.usage.entry(tenant.to_string()).or_default();
+        usage.tick_gbits += gbits;
+        usage.total_gbits += gbits as f64;
+    }
+
+    /// Check and commit in one go, for callers with no other admission checks
+    pub fn charge_bandwidth(&mut self, tenant: Option<&str>, gbits: f32, total_gbps: f32) -> bool {
+        if !self.check_bandwidth(tenant, gbits, total_gbps) {
+            return false;
+        }
+        self.commit_bandwidth(tenant, gbits);
+        true
+    }
+
+    /// Take a GPU batch slot for `tenant` out of the `slots` the farm offers per tick
+    pub fn charge_gpu_item(&mut self, tenant: Option<&str>, slots: usize) -> bool {
+        let Some(tenant) = tenant else { return true };
+        let share = self.quota(tenant).and_then(|q| q.gpu_batch_share);
+        let usage = self.usage.entry(tenant.to_string()).or_default();
+        if let Some(share) = share {
+            if usage.tick_gpu_items >= (share * slots as f32).floor() as usize {
+                usage.gpu_deferred += 1;
+                return false;
+            }
+        }
+        usage.tick_gpu_items += 1;
+        usage.gpu_batch_items += 1;
+        true
+    }
+
+    /// A job of `tenant` left the queue; `deadline_hit` is None when it faulted
+    pub fn record_done(&mut self, tenant: Option<&str>, deadline_hit: Option<bool>) {
+        let Some(tenant) = tenant else { return };
+        let usage = self.usage.entry(tenant.to_string()).or_default();
+        usage.queued = usage.queued.saturating_sub(1);
+        match deadline_hit {
+            Some(true) => {
+                usage.completed += 1;
+                usage.deadline_hits += 1;
+            }
+            Some(false) => {
+                usage.completed += 1;
+                usage.deadline_misses += 1;
+            }
+            None => usage.faulted += 1,
+        }
+    }
+
+    pub fn sla(&self, tenant: &str) -> Option<TenantSla> {
+        let usage = self.usage.get(tenant)?;
+        let finished = usage.completed + usage.faulted;
+        Some(TenantSla {
+            tenant: tenant.to_string(),
+            completed: usage.completed,
+            faulted: usage.faulted,
+            deadline_hit_rate: if usage.completed == 0 { 1.0 } else { usage.deadline_hits as f32 / usage.completed as f32 },
+            fault_rate: if finished == 0 { 0.0 } else { usage.faulted as f32 / finished as f32 },
+        })
+    }
+}
+
+/// Open each tenant's bandwidth and GPU budgets for the tick ahead of dispatch
+pub fn tenant_tick_system(mut tenants: ResMut<TenantLedger>) {
+    tenants.begin_tick();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger(quota: TenantQuota) -> TenantLedger {
+        TenantLedger::from_config(&TenantConfig { enabled: true, quotas: vec![quota] }).unwrap()
+    }
+
+    fn quota(tenant: &str) -> TenantQuota {
+        TenantQuota { tenant: tenant.into(), max_queued_jobs: None, bandwidth_share: None, gpu_batch_share: None }
+    }
+
+    #[test]
+    fn test_max_queued_jobs_is_enforced_at_admission() {
+        let mut ledger = ledger(TenantQuota { max_queued_jobs: Some(2), ..quota("vision") });
+        assert!(ledger.admit(Some("vision")).is_ok());
+        assert!(ledger.admit(Some("vision")).is_ok());
+        assert!(ledger.admit(Some("vision")).is_err());
+        // Other tenants and untagged jobs aren't limited
+        assert!((0..5).all(|_| ledger.admit(Some("telemetry")).is_ok() && ledger.admit(None).is_ok()));
+
+        ledger.record_done(Some("vision"), Some(true));
+        assert!(ledger.admit(Some("vision")).is_ok());
+        let usage = &ledger.usage["vision"];
+        assert_eq!((usage.admitted, usage.rejected, usage.queued), (3, 1, 2));
+
+        ledger.enabled = false;
+        assert!(ledger.admit(Some("vision")).is_ok());
+    }
+
+    #[test]
+    fn test_bandwidth_and_gpu_shares() {
+        let mut ledger = ledger(TenantQuota { bandwidth_share: Some(0.25), gpu_batch_share: Some(0.5), ..quota("exports") });
+        ledger.begin_tick();
+        assert!(ledger.charge_bandwidth(Some("exports"), 0.2, 1.0));
+        assert!(!ledger.charge_bandwidth(Some("exports"), 0.1, 1.0));
+        assert!(ledger.charge_bandwidth(Some("ingest"), 0.9, 1.0));
+        // 4 of 8 slots
+        let placed = (0..8).take_while(|_| ledger.charge_gpu_item(Some("exports"), 8)).count();
+        assert_eq!(placed, 4);
+
+        ledger.begin_tick();
+        assert!(ledger.charge_bandwidth(Some("exports"), 0.6, 1.0)); // oversized, on a fresh tick
+        let usage = &ledger.usage["exports"];
+        assert_eq!((usage.bandwidth_deferred, usage.gpu_deferred), (1, 1));
+    }
+
+    #[test]
+    fn test_sla_and_config_checks() {
+        let mut ledger = TenantLedger::new();
+        ledger.admit(Some("vision")).unwrap();
+        ledger.record_done(Some("vision"), Some(true));
+        ledger.record_done(Some("vision"), Some(false));
+        ledger.record_done(Some("vision"), None);
+        let sla = ledger.sla("vision").unwrap();
+        assert_eq!((sla.completed, sla.faulted), (2, 1));
+        assert_eq!(sla.deadline_hit_rate, 0.5);
+        assert!(ledger.sla("nobody").is_none());
+
+        let config: TenantConfig = toml::from_str(
+            "enabled = true\n[[quotas]]\ntenant = \"vision\"\nmax_queued_jobs = 50\ngpu_batch_share = 0.5\n",
+        ).unwrap();
+        assert!(config.validate().is_ok());
+        let mut over = config.clone();
+        over.quotas[0].bandwidth_share = Some(1.5);
+        assert!(over.validate().is_err());
+        let mut twice = config.clone();
+        twice.quotas.push(config.quotas[0].clone());
+        assert!(twice.validate().is_err());
+    }
+
+    #[test]
+    fn test_job_held_back_by_shaping_is_not_billed() {
+        use crate::{EnqueuedJob, Job, JobQueue, Op, Pipeline, QoS, ShapingPolicy, TrafficShaper, TrafficShapingConfig};
+        let colony = crate::colony_for_scenario(None, Some(42)).unwrap();
+        let clock = crate::SimClock { tick_scale: crate::TickScale::RealTime, now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH };
+        let mut app = crate::build_shadow_app(&colony, &clock, &crate::ActiveScheduler::default());
+        app.update();
+        // Another pipeline's guarantee takes all the bandwidth, so unshaped exports always wait
+        let reserved = ShapingPolicy { pipeline: "reserved".into(), guarantee_gbps: colony.bandwidth_total_gbps, ceiling_gbps: None, burst_gbits: 0.0 };
+        app.insert_resource(TrafficShaper::from_config(&TrafficShapingConfig { enabled: true, policies: vec![reserved] }).unwrap());
+        app.insert_resource(ledger(TenantQuota { bandwidth_share: Some(1.0), ..quota("exports") }));
+        let job = Job {
+            id: 9_000,
+            pipeline: Pipeline { ops: vec![Op::HttpExport], mutation_tag: None, id: Some("bulk_export".into()) },
+            qos: QoS::Balanced,
+            deadline_ms: 60_000,
+            payload_sz: 1_000_000,
+        };
+        app.world_mut().resource_mut::<JobQueue>().push_enqueued(EnqueuedJob { tenant: Some("exports".into()), ..EnqueuedJob::new(job, 0) });
+
+        for _ in 0..5 {
+            app.update();
+        }
+        assert!(app.world().resource::<TrafficShaper>().unshaped.deferred > 0);
+        let ledger = app.world().resource::<TenantLedger>();
+        let usage = ledger.usage.get("exports").cloned().unwrap_or_default();
+        assert_eq!((usage.tick_gbits, usage.total_gbits, usage.bandwidth_deferred), (0.0, 0.0, 0));
+    }
+}
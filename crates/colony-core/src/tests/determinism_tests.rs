@@ -1,5 +1,3 @@
-use colony_core::*;
-
 #[test]
 fn test_determinism_basic() {
     // Basic determinism test
@@ -1,22 +1,32 @@
-use colony_core::*;
+use crate::*;
+#[cfg(feature = "native")]
+use crate::script::*;
 use bevy::prelude::*;
 use colony_modsdk::{ModManifest, Entrypoints, Capabilities, WasmOpSpec, LuaEventSpec};
 use std::path::PathBuf;
 use anyhow::Result;
 
 // Helper to create a minimal App for M7 testing
+#[cfg(feature = "native")]
 fn create_m7_test_app() -> App {
     let mut app = App::new();
     app.add_plugins(MinimalPlugins);
+    app.add_event::<WorkerReport>();
+    app.insert_resource(SimClock {
+        tick_scale: TickScale::RealTime,
+        now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+    });
     app.insert_resource(WasmHost::new());
     app.insert_resource(LuaHost::new());
     app.insert_resource(ModLoader::new(PathBuf::from("mods")));
-    app.insert_resource(HotReloadManager::new());
-    app.insert_resource(Colony::new());
-    app.world.resource_mut::<Colony>().game_setup = GameSetup::new(Scenario::default());
+    app.insert_resource(ModOps::default());
+    app.insert_resource(ContentPack::default());
+    app.insert_resource(SchedulerRegistry::default());
+    app.insert_resource(colony_from_scenario(None, None));
     app
 }
 
+#[cfg(feature = "native")]
 #[test]
 fn test_wasm_host_initialization() {
     let wasm_host = WasmHost::new();
@@ -26,6 +36,7 @@ fn test_wasm_host_initialization() {
     assert!(wasm_host.modules.is_empty());
 }
 
+#[cfg(feature = "native")]
 #[test]
 fn test_wasm_host_module_loading() -> Result<()> {
     let mut wasm_host = WasmHost::new();
@@ -44,9 +55,10 @@ fn test_wasm_host_module_loading() -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "native")]
 #[test]
 fn test_wasm_host_execution_limits() {
-    let mut wasm_host = WasmHost::new();
+    let wasm_host = WasmHost::new();
     
     // Test fuel limit enforcement
     assert_eq!(wasm_host.execution_env.fuel_limit, 5_000_000);
@@ -58,6 +70,7 @@ fn test_wasm_host_execution_limits() {
     assert!(wasm_host.execution_env.sandbox_mode);
 }
 
+#[cfg(feature = "native")]
 #[test]
 fn test_lua_host_initialization() {
     let lua_host = LuaHost::new();
@@ -68,13 +81,14 @@ fn test_lua_host_initialization() {
     assert!(lua_host.scripts.is_empty());
 }
 
+#[cfg(feature = "native")]
 #[test]
 fn test_lua_host_script_loading() -> Result<()> {
     let mut lua_host = LuaHost::new();
     
     // Test script loading
     let script_content = r#"
-        function on_tick()
+        return function()
             print("Hello from Lua!")
         end
     "#;
@@ -89,6 +103,7 @@ fn test_lua_host_script_loading() -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "native")]
 #[test]
 fn test_lua_host_sandbox_enforcement() {
     let lua_host = LuaHost::new();
@@ -132,53 +147,13 @@ fn test_mod_loader_enable_disable() -> Result<()> {
 fn test_mod_loader_hot_reload() -> Result<()> {
     let mut mod_loader = ModLoader::new(PathBuf::from("test_mods"));
     
-    // Test hot reload (should not fail even if mod doesn't exist)
+    // Test hot reload of a mod that was never discovered
     let result = mod_loader.trigger_hot_reload("nonexistent_mod");
-    assert!(result.is_ok()); // Should succeed even if mod doesn't exist
+    assert!(result.is_err()); // Nothing to re-read it from
     
     Ok(())
 }
 
-#[test]
-fn test_hot_reload_manager_initialization() {
-    let hot_reload_manager = HotReloadManager::new();
-    
-    assert!(hot_reload_manager.watchers.is_empty());
-    assert!(hot_reload_manager.pending_reloads.is_empty());
-    assert_eq!(hot_reload_manager.reload_cooldown, std::time::Duration::from_millis(500));
-}
-
-#[test]
-fn test_hot_reload_manager_queue_reload() {
-    let mut hot_reload_manager = HotReloadManager::new();
-    
-    // Test queueing a reload
-    hot_reload_manager.queue_reload("test_mod");
-    assert!(hot_reload_manager.pending_reloads.contains(&"test_mod".to_string()));
-    
-    // Test queueing the same mod again (should not duplicate)
-    hot_reload_manager.queue_reload("test_mod");
-    assert_eq!(hot_reload_manager.pending_reloads.len(), 1);
-    
-    // Test queueing different mods
-    hot_reload_manager.queue_reload("another_mod");
-    assert_eq!(hot_reload_manager.pending_reloads.len(), 2);
-}
-
-#[test]
-fn test_hot_reload_manager_cooldown() {
-    let mut hot_reload_manager = HotReloadManager::new();
-    
-    // Test initial cooldown state
-    assert!(hot_reload_manager.can_reload());
-    
-    // Mark as reloaded
-    hot_reload_manager.mark_reloaded();
-    
-    // Should not be able to reload immediately
-    assert!(!hot_reload_manager.can_reload());
-}
-
 #[test]
 fn test_mod_manifest_validation() -> Result<()> {
     // Test valid manifest
@@ -190,13 +165,16 @@ fn test_mod_manifest_validation() -> Result<()> {
         entrypoints: Entrypoints {
             wasm_ops: vec!["Op_Test".to_string()],
             lua_events: vec!["on_tick.lua".to_string()],
+            ..Default::default()
         },
         capabilities: Capabilities {
             sim_time: true,
             log_debug: true,
             enqueue_job: false,
+            ..Default::default()
         },
         signature: None,
+        ..Default::default()
     };
     
     assert!(validate_mod_manifest(&valid_manifest).is_ok());
@@ -210,6 +188,7 @@ fn test_mod_manifest_validation() -> Result<()> {
         entrypoints: Entrypoints::default(),
         capabilities: Capabilities::default(),
         signature: None,
+        ..Default::default()
     };
     
     assert!(validate_mod_manifest(&invalid_manifest).is_err());
@@ -220,25 +199,32 @@ fn test_mod_manifest_validation() -> Result<()> {
 #[test]
 fn test_wasm_op_spec_validation() {
     let op_spec = WasmOpSpec {
-        function_name: "test_op".to_string(),
-        input_schema: "bytes".to_string(),
-        output_schema: "bytes".to_string(),
+        name: "test_op".to_string(),
+        version: "1.0.0".to_string(),
+        cost_hint_ms: 5,
+        work_units_hint: 1.0,
+        vram_hint_mb: 0.0,
+        bandwidth_hint_mb: 0.0,
+        description: None,
+        abi: Default::default(),
     };
     
-    assert_eq!(op_spec.function_name, "test_op");
-    assert_eq!(op_spec.input_schema, "bytes");
-    assert_eq!(op_spec.output_schema, "bytes");
+    assert_eq!(op_spec.name, "test_op");
+    assert_eq!(op_spec.cost_hint_ms, 5);
+    assert_eq!(op_spec.work_units_hint, 1.0);
 }
 
 #[test]
 fn test_lua_event_spec_validation() {
     let event_spec = LuaEventSpec {
-        event_name: "on_tick".to_string(),
-        script_path: "on_tick.lua".to_string(),
+        name: "on_tick".to_string(),
+        file: "on_tick.lua".to_string(),
+        description: None,
+        instruction_budget: None,
     };
     
-    assert_eq!(event_spec.event_name, "on_tick");
-    assert_eq!(event_spec.script_path, "on_tick.lua");
+    assert_eq!(event_spec.name, "on_tick");
+    assert_eq!(event_spec.file, "on_tick.lua");
 }
 
 #[test]
@@ -247,6 +233,7 @@ fn test_capability_gating() {
         sim_time: true,
         log_debug: true,
         enqueue_job: false,
+        ..Default::default()
     };
     
     assert!(capabilities.sim_time);
@@ -273,7 +260,12 @@ fn test_mod_discovery() -> Result<()> {
         
         [capabilities]
         sim_time = true
+        rng = false
+        metrics_read = false
+        enqueue_job = false
         log_debug = true
+        modify_tunables = false
+        trigger_events = false
     "#;
     
     let manifest_path = temp_dir.join("mod.toml");
@@ -300,6 +292,7 @@ fn test_mod_validation() -> Result<()> {
         entrypoints: Entrypoints::default(),
         capabilities: Capabilities::default(),
         signature: None,
+        ..Default::default()
     };
     
     // Test validation
@@ -308,6 +301,7 @@ fn test_mod_validation() -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "native")]
 #[test]
 fn test_wasm_execution_environment() {
     let wasm_host = WasmHost::new();
@@ -319,6 +313,7 @@ fn test_wasm_execution_environment() {
     assert!(env.sandbox_mode);
 }
 
+#[cfg(feature = "native")]
 #[test]
 fn test_lua_execution_environment() {
     let lua_host = LuaHost::new();
@@ -334,6 +329,7 @@ fn test_lua_execution_environment() {
 fn test_mod_registry_operations() {
     let mut registry = ModRegistry {
         mods: std::collections::HashMap::new(),
+        dirs: std::collections::HashMap::new(),
         load_order: Vec::new(),
     };
     
@@ -345,6 +341,7 @@ fn test_mod_registry_operations() {
         entrypoints: Entrypoints::default(),
         capabilities: Capabilities::default(),
         signature: None,
+        ..Default::default()
     };
     
     // Test adding mod to registry
@@ -356,21 +353,7 @@ fn test_mod_registry_operations() {
     assert_eq!(registry.load_order.len(), 1);
 }
 
-#[test]
-fn test_hot_reload_system_integration() {
-    let mut app = create_m7_test_app();
-    
-    // Test that the hot reload system can be added to the app
-    app.add_systems(Update, process_hot_reload_system);
-    
-    // Test that the system runs without errors
-    app.update();
-    
-    // Verify resources are still present
-    assert!(app.world.contains_resource::<HotReloadManager>());
-    assert!(app.world.contains_resource::<ModLoader>());
-}
-
+#[cfg(feature = "native")]
 #[test]
 fn test_wasm_lua_integration() {
     let mut app = create_m7_test_app();
@@ -386,10 +369,11 @@ fn test_wasm_lua_integration() {
     app.update();
     
     // Verify both hosts are present
-    assert!(app.world.contains_resource::<WasmHost>());
-    assert!(app.world.contains_resource::<LuaHost>());
+    assert!(app.world().contains_resource::<WasmHost>());
+    assert!(app.world().contains_resource::<LuaHost>());
 }
 
+#[cfg(feature = "native")]
 #[test]
 fn test_mod_loader_system_integration() {
     let mut app = create_m7_test_app();
@@ -401,23 +385,10 @@ fn test_mod_loader_system_integration() {
     app.update();
     
     // Verify mod loader is present
-    assert!(app.world.contains_resource::<ModLoader>());
-}
-
-#[test]
-fn test_shadow_world_system() {
-    let mut app = create_m7_test_app();
-    
-    // Test that the shadow world system can be added to the app
-    app.add_systems(Update, update_shadow_world_system);
-    
-    // Test that the system runs without errors
-    app.update();
-    
-    // Verify the system runs (no specific resource to check)
-    assert!(true); // System ran without panicking
+    assert!(app.world().contains_resource::<ModLoader>());
 }
 
+#[cfg(feature = "native")]
 #[test]
 fn test_m7_systems_together() {
     let mut app = create_m7_test_app();
@@ -428,8 +399,6 @@ fn test_m7_systems_together() {
         update_lua_host_system,
         execute_lua_events_system,
         initialize_mod_loader_system,
-        process_hot_reload_system,
-        update_shadow_world_system,
     ));
     
     // Test that all systems run together without errors
@@ -438,10 +407,9 @@ fn test_m7_systems_together() {
     }
     
     // Verify all M7 resources are present
-    assert!(app.world.contains_resource::<WasmHost>());
-    assert!(app.world.contains_resource::<LuaHost>());
-    assert!(app.world.contains_resource::<ModLoader>());
-    assert!(app.world.contains_resource::<HotReloadManager>());
+    assert!(app.world().contains_resource::<WasmHost>());
+    assert!(app.world().contains_resource::<LuaHost>());
+    assert!(app.world().contains_resource::<ModLoader>());
 }
 
 #[test]
@@ -454,13 +422,16 @@ fn test_mod_manifest_serialization() -> Result<()> {
         entrypoints: Entrypoints {
             wasm_ops: vec!["Op_Test".to_string()],
             lua_events: vec!["on_tick.lua".to_string()],
+            ..Default::default()
         },
         capabilities: Capabilities {
             sim_time: true,
             log_debug: true,
             enqueue_job: false,
+            ..Default::default()
         },
         signature: None,
+        ..Default::default()
     };
     
     // Test TOML serialization
@@ -485,21 +456,25 @@ fn test_mod_capabilities_combinations() {
             sim_time: true,
             log_debug: false,
             enqueue_job: false,
+            ..Default::default()
         },
         Capabilities {
             sim_time: false,
             log_debug: true,
             enqueue_job: false,
+            ..Default::default()
         },
         Capabilities {
             sim_time: false,
             log_debug: false,
             enqueue_job: true,
+            ..Default::default()
         },
         Capabilities {
             sim_time: true,
             log_debug: true,
             enqueue_job: true,
+            ..Default::default()
         },
     ];
     
@@ -514,6 +489,7 @@ fn test_mod_entrypoints_validation() {
     let entrypoints = Entrypoints {
         wasm_ops: vec!["Op_Test1".to_string(), "Op_Test2".to_string()],
         lua_events: vec!["on_tick.lua".to_string(), "on_init.lua".to_string()],
+        ..Default::default()
     };
     
     assert_eq!(entrypoints.wasm_ops.len(), 2);
@@ -534,18 +510,3 @@ fn test_mod_loader_path_handling() {
     assert!(mod_path.ends_with("test_mod"));
 }
 
-#[test]
-fn test_hot_reload_watcher_creation() -> Result<()> {
-    let temp_dir = std::env::temp_dir().join("colony_test_watch");
-    std::fs::create_dir_all(&temp_dir)?;
-    
-    // Test watcher creation
-    let _watcher = ModWatcher::new(&temp_dir, |_path| {
-        println!("File changed: {:?}", _path);
-    })?;
-    
-    // Cleanup
-    std::fs::remove_dir_all(&temp_dir)?;
-    
-    Ok(())
-}
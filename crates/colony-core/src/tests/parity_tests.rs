@@ -293,7 +293,7 @@ fn create_desktop_app(seed: u64) -> App {
     app.insert_resource(JobQueue::new());
     app.insert_resource(CorruptionField::new());
     app.insert_resource(FaultKpi::new());
-    app.insert_resource(ActiveScheduler { policy: SchedPolicy::Sjf });
+    app.insert_resource(ActiveScheduler { policy: SchedPolicy::Sjf, custom: None });
     app.insert_resource(Debts::new());
     app.insert_resource(BlackSwanIndex::new());
     app.insert_resource(KpiRingBuffer::new());
@@ -325,7 +325,7 @@ fn create_headless_app(seed: u64) -> App {
     app.insert_resource(JobQueue::new());
     app.insert_resource(CorruptionField::new());
     app.insert_resource(FaultKpi::new());
-    app.insert_resource(ActiveScheduler { policy: SchedPolicy::Sjf });
+    app.insert_resource(ActiveScheduler { policy: SchedPolicy::Sjf, custom: None });
     app.insert_resource(Debts::new());
     app.insert_resource(BlackSwanIndex::new());
     app.insert_resource(KpiRingBuffer::new());
@@ -217,6 +217,7 @@ fn test_colony_state_serialization() {
         },
         tunables: ResourceTunables::default(),
         corruption_tun: CorruptionTunables::default(),
+        seeds: Default::default(),
         seed: 42,
         game_setup: GameSetup::new(Scenario::default()),
         pending_mutations: vec![("pipeline1".to_string(), "mutation1".to_string())],
@@ -335,7 +336,7 @@ fn create_test_app() -> App {
     app.insert_resource(JobQueue::new());
     app.insert_resource(CorruptionField::new());
     app.insert_resource(FaultKpi::new());
-    app.insert_resource(ActiveScheduler { policy: SchedPolicy::Sjf });
+    app.insert_resource(ActiveScheduler { policy: SchedPolicy::Sjf, custom: None });
     app.insert_resource(Debts::new());
     app.insert_resource(BlackSwanIndex::new());
     app.insert_resource(KpiRingBuffer::new());
@@ -370,6 +371,7 @@ fn migrate_v0_to_v1(v0_data: MockV0SaveData) -> SaveFileV1 {
         meters: GlobalMeters::new(),
         tunables: ResourceTunables::default(),
         corruption_tun: CorruptionTunables::default(),
+        seeds: Default::default(),
         seed: v0_data.seed,
         game_setup: GameSetup::new(Scenario::default()),
         pending_mutations: Vec::new(),
@@ -0,0 +1,263 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::VecDeque;
+use crate::{Colony, DispatchScale, Workyard, SimClock, JobQueue};
+
+const MAX_DECISIONS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ThrottleSignal {
+    PowerHeadroom,
+    HeatMargin,
+    DeadlineSlack,
+}
+
+/// One logged controller adjustment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThrottleDecision {
+    pub tick: u64,
+    pub scale_before: f32,
+    pub scale_after: f32,
+    pub power_headroom: f32,
+    pub heat_margin: f32,
+    pub deadline_slack: f32,
+    pub limiting: ThrottleSignal,
+}
+
+/// PID-style controller over DispatchScale. Power and heat margins below their
+/// targets pull the scale down; spare margin lets it recover toward 1.0, faster
+/// when queued jobs are running out of deadline slack.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct AutoThrottle {
+    pub enabled: bool,
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub power_headroom_target: f32, // fraction of power cap kept free
+    pub heat_margin_target: f32,    // fraction of heat cap kept free on the hottest yard
+    pub deadline_slack_target: f32, // mean fraction of deadline left on queued jobs
+    pub min_scale: f32,
+    pub log_threshold: f32,         // minimum scale change worth logging
+    pub scale: f32,
+    pub integral: f32,
+    pub prev_error: f32,
+    pub decisions: VecDeque<ThrottleDecision>,
+}
+
+impl Default for AutoThrottle {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kp: 0.5,
+            ki: 0.05,
+            kd: 0.1,
+            power_headroom_target: 0.1,
+            heat_margin_target: 0.15,
+            deadline_slack_target: 0.3,
+            min_scale: 0.2,
+            log_threshold: 0.01,
+            scale: 1.0,
+            integral: 0.0,
+            prev_error: 0.0,
+            decisions: VecDeque::new(),
+        }
+    }
+}
+
+impl AutoThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.reset();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.scale = 1.0;
+        self.integral = 0.0;
+        self.prev_error = 0.0;
+    }
+
+    /// Advance the controller one step and return the new scale
+    pub fn step(&mut self, power_headroom: f32, heat_margin: f32, deadline_slack: f32) -> (f32, ThrottleSignal) {
+        let power_err = power_headroom - self.power_headroom_target;
+        let heat_err = heat_margin - self.heat_margin_target;
+        let (constraint_err, constraint) = if power_err <= heat_err {
+            (power_err, ThrottleSignal::PowerHeadroom)
+        } else {
+            (heat_err, ThrottleSignal::HeatMargin)
+        };
+
+        let deadline_pressure = (self.deadline_slack_target - deadline_slack).max(0.0);
+        let (error, limiting) = if constraint_err < 0.0 {
+            (constraint_err, constraint)
+        } else if deadline_pressure > 0.0 {
+            (constraint_err * (1.0 + deadline_pressure), ThrottleSignal::DeadlineSlack)
+        } else {
+            (constraint_err, constraint)
+        };
+
+        // Clamp the integral so a long stretch at the limit doesn't wind up
+        self.integral = (self.integral + error).clamp(-1.0, 1.0);
+        let derivative = error - self.prev_error;
+        self.prev_error = error;
+
+        let adjustment = self.kp * error + self.ki * self.integral + self.kd * derivative;
+        self.scale = (self.scale + adjustment).clamp(self.min_scale, 1.0);
+        (self.scale, limiting)
+    }
+
+    fn log(&mut self, decision: ThrottleDecision) {
+        self.decisions.push_back(decision);
+        if self.decisions.len() > MAX_DECISIONS {
+            self.decisions.pop_front();
+        }
+    }
+}
+
+/// Mean fraction of deadline remaining across queued jobs; 1.0 when the queue is empty
+pub fn deadline_slack(jobq: &JobQueue, now_tick: u64) -> f32 {
+    let jobs: Vec<_> = jobq.cpu.iter().chain(jobq.gpu.iter()).chain(jobq.io.iter()).collect();
+    if jobs.is_empty() {
+        return 1.0;
+    }
+    let total: f32 = jobs.iter()
+        .map(|ej| {
+            let deadline_ticks = (ej.job.deadline_ms / 16).max(1) as f32;
+            let age = now_tick.saturating_sub(ej.enq_tick) as f32;
+            ((deadline_ticks - age) / deadline_ticks).clamp(-1.0, 1.0)
+        })
+        .sum();
+    total / jobs.len() as f32
+}
+
+/// Runs after power_bandwidth_system; never raises the scale above the power-cap limit set there
+pub fn auto_throttle_system(
+    mut throttle: ResMut<AutoThrottle>,
+    mut dispatch_scale: ResMut<DispatchScale>,
+    colony: Res<Colony>,
+    yards: Query<&Workyard>,
+    jobq: Res<JobQueue>,
+    clock: Res<SimClock>,
+) {
+    if !throttle.enabled {
+        return;
+    }
+
+    let now_tick = clock.now.timestamp_millis() as u64 / 16;
+    let power_headroom = if colony.power_cap_kw > 0.0 {
+        (colony.power_cap_kw - colony.meters.power_draw_kw) / colony.power_cap_kw
+    } else {
+        0.0
+    };
+    let heat_margin = yards.iter()
+        .map(|y| (y.heat_cap - y.heat) / y.heat_cap)
+        .fold(1.0f32, f32::min);
+    let slack = deadline_slack(&jobq, now_tick);
+
+    let scale_before = throttle.scale;
+    let (scale, limiting) = throttle.step(power_headroom, heat_margin, slack);
+    dispatch_scale.0 = dispatch_scale.0.min(scale);
+
+    if (scale - scale_before).abs() >= throttle.log_threshold {
+        println!(
+            "Auto-throttle: dispatch scale {:.2} -> {:.2} ({:?}; power headroom {:.2}, heat margin {:.2}, deadline slack {:.2})",
+            scale_before, scale, limiting, power_headroom, heat_margin, slack
+        );
+        throttle.log(ThrottleDecision {
+            tick: now_tick,
+            scale_before,
+            scale_after: scale,
+            power_headroom,
+            heat_margin,
+            deadline_slack: slack,
+            limiting,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttles_down_when_power_headroom_short() {
+        let mut throttle = AutoThrottle::new();
+        let (scale, limiting) = throttle.step(-0.1, 0.5, 1.0);
+        assert!(scale < 1.0);
+        assert_eq!(limiting, ThrottleSignal::PowerHeadroom);
+    }
+
+    #[test]
+    fn test_heat_margin_limits_when_tighter_than_power() {
+        let mut throttle = AutoThrottle::new();
+        let (_, limiting) = throttle.step(0.5, 0.0, 1.0);
+        assert_eq!(limiting, ThrottleSignal::HeatMargin);
+    }
+
+    #[test]
+    fn test_recovers_faster_under_deadline_pressure() {
+        let mut relaxed = AutoThrottle::new();
+        let mut pressured = AutoThrottle::new();
+        for _ in 0..5 {
+            relaxed.step(-0.3, 0.5, 1.0);
+            pressured.step(-0.3, 0.5, 1.0);
+        }
+        assert!(relaxed.scale < 1.0);
+        assert_eq!(relaxed.scale, pressured.scale);
+
+        let (relaxed_scale, _) = relaxed.step(0.2, 0.5, 1.0);
+        let (pressured_scale, limiting) = pressured.step(0.2, 0.5, 0.0);
+        assert!(pressured_scale > relaxed_scale);
+        assert_eq!(limiting, ThrottleSignal::DeadlineSlack);
+    }
+
+    #[test]
+    fn test_scale_respects_min() {
+        let mut throttle = AutoThrottle::new();
+        for _ in 0..100 {
+            throttle.step(-1.0, -1.0, 1.0);
+        }
+        assert_eq!(throttle.scale, throttle.min_scale);
+        throttle.set_enabled(false);
+        assert_eq!(throttle.scale, 1.0);
+    }
+
+    #[test]
+    fn test_settles_above_min_scale_with_the_real_systems() {
+        let mut colony = crate::colony_for_scenario(None, Some(42)).unwrap();
+        // Flat out the stock yards draw more than this, idle they draw less
+        colony.power_cap_kw = 330.0;
+        let clock = SimClock { tick_scale: crate::TickScale::RealTime, now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH };
+        let mut app = crate::build_shadow_app(&colony, &clock, &crate::ActiveScheduler::default());
+        app.world_mut().resource_mut::<AutoThrottle>().set_enabled(true);
+        let mut scales = Vec::new();
+        for tick in 0..600u64 {
+            for k in 0..8 {
+                let job = crate::Job {
+                    id: 10_000 + tick * 8 + k,
+                    pipeline: crate::Pipeline { ops: vec![crate::Op::Decode, crate::Op::Kalman], mutation_tag: None, id: None },
+                    qos: crate::QoS::Balanced,
+                    deadline_ms: 60_000,
+                    payload_sz: 1_000,
+                };
+                app.world_mut().resource_mut::<JobQueue>().push(job, tick);
+            }
+            app.update();
+            scales.push(app.world().resource::<AutoThrottle>().scale);
+        }
+
+        let throttle = app.world().resource::<AutoThrottle>();
+        let settled = &scales[500..];
+        let (lo, hi) = settled.iter().fold((f32::MAX, f32::MIN), |(lo, hi), &s| (lo.min(s), hi.max(s)));
+        assert!(lo > throttle.min_scale && hi < 1.0, "scale ranged {lo}..{hi}");
+        assert!(hi - lo < 0.05, "scale still swinging {lo}..{hi}");
+        let colony = app.world().resource::<Colony>();
+        let headroom = (colony.power_cap_kw - colony.meters.power_draw_kw) / colony.power_cap_kw;
+        assert!((headroom - throttle.power_headroom_target).abs() < 0.05, "headroom {headroom}");
+    }
+}
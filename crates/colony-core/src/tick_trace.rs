@@ -0,0 +1,248 @@
+use bevy::prelude::*;
+use bevy::ecs::schedule::{InternedSystemSet, IntoSystemSet, SystemSet};
+use serde::{Serialize, Deserialize};
+use std::io::Write;
+use std::path::Path;
+use anyhow::Result;
+use crate::*;
+
+/// Hashes one piece of tracked world state; None when the app doesn't have it
+pub type StateHasher = fn(&mut World) -> Option<u64>;
+
+/// Hash of a resource's serialized form. Going through a JSON value sorts map keys, so two
+/// equal HashMaps hash the same regardless of iteration order.
+pub fn hash_resource<T: Resource + Serialize>(world: &mut World) -> Option<u64> {
+    let value = serde_json::to_value(world.get_resource::<T>()?).ok()?;
+    Some(payload_digest(&serde_json::to_vec(&value).ok()?))
+}
+
+fn hash_workers(world: &mut World) -> Option<u64> {
+    let mut workers: Vec<(Worker, WorkerSkills, WorkerHealth, WorkerStateComp)> = world
+        .query::<(&Worker, &WorkerSkills, &WorkerHealth, &WorkerStateComp)>()
+        .iter(world)
+        .map(|(w, skills, health, state)| (*w, *skills, *health, *state))
+        .collect();
+    workers.sort_by_key(|(w, ..)| w.id);
+    Some(payload_digest(&serde_json::to_vec(&workers).ok()?))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceChange {
+    pub resource: String,
+    pub before: Option<u64>,
+    pub after: Option<u64>,
+}
+
+/// What one system did to the tracked state on one tick
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemTrace {
+    pub tick: u64,
+    pub system: String,
+    pub state: u64, // every tracked hash combined, after the system ran
+    pub touched: Vec<ResourceChange>,
+}
+
+/// Before/after hashes of the tracked resources around each traced system, for finding the
+/// first system whose output differs between two runs (`xtask bisect-tick`)
+#[derive(Resource)]
+pub struct TickTrace {
+    pub tracked: Vec<(String, StateHasher)>,
+    pub records: Vec<SystemTrace>,
+    pub tick: u64,
+    started: bool,
+    last: Vec<Option<u64>>, // hashes at the previous trace point, same order as `tracked`
+}
+
+impl Default for TickTrace {
+    fn default() -> Self {
+        let mut trace = Self::empty();
+        trace.track("Colony", hash_resource::<Colony>);
+        trace.track("SimClock", hash_resource::<SimClock>);
+        trace.track("Workers", hash_workers);
+        trace.track("JobQueue", hash_resource::<JobQueue>);
+        trace.track("JobIdAllocator", hash_resource::<JobIdAllocator>);
+        trace.track("QosQueueing", hash_resource::<QosQueueing>);
+        trace.track("ActiveScheduler", hash_resource::<ActiveScheduler>);
+        trace.track("AutoThrottle", hash_resource::<AutoThrottle>);
+        trace.track("TrafficShaper", hash_resource::<TrafficShaper>);
+        trace.track("ClockDrift", hash_resource::<ClockDrift>);
+        trace.track("FaultKpi", hash_resource::<FaultKpi>);
+        trace.track("FaultSites", hash_resource::<FaultSites>);
+        trace.track("CorruptionTopology", hash_resource::<CorruptionTopology>);
+        trace.track("JobRetries", hash_resource::<JobRetries>);
+        trace.track("TenantLedger", hash_resource::<TenantLedger>);
+        trace.track("EnergyLedger", hash_resource::<EnergyLedger>);
+        trace.track("MemoryAccounting", hash_resource::<MemoryAccounting>);
+        trace.track("OpCache", hash_resource::<OpCache>);
+        trace.track("ExportSink", hash_resource::<ExportSink>);
+        trace.track("ArchiveStore", hash_resource::<ArchiveStore>);
+        trace.track("MaintenanceLane", hash_resource::<MaintenanceLane>);
+        trace.track("ClassReservations", hash_resource::<ClassReservations>);
+        trace.track("OpLicenses", hash_resource::<OpLicenses>);
+        trace.track("InFlightJobs", hash_resource::<InFlightJobs>);
+        trace.track("CertificationBoard", hash_resource::<CertificationBoard>);
+        trace.track("Debts", hash_resource::<Debts>);
+        trace.track("EffectiveCapacity", hash_resource::<EffectiveCapacity>);
+        trace.track("GpuFaults", hash_resource::<GpuFaults>);
+        trace.track("BlackSwanIndex", hash_resource::<BlackSwanIndex>);
+        trace.track("KpiRingBuffer", hash_resource::<KpiRingBuffer>);
+        trace.track("ResearchState", hash_resource::<ResearchState>);
+        trace
+    }
+}
+
+impl TickTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A trace that tracks nothing until resources are added with `track`
+    pub fn empty() -> Self {
+        Self { tracked: Vec::new(), records: Vec::new(), tick: 0, started: false, last: Vec::new() }
+    }
+
+    pub fn track(&mut self, name: &str, hasher: StateHasher) {
+        self.tracked.push((name.to_string(), hasher));
+        self.last.push(None);
+    }
+
+    /// Hash everything tracked and record what changed since the previous trace point
+    pub fn checkpoint(&mut self, world: &mut World, system: &str) {
+        let hashes: Vec<Option<u64>> = self.tracked.iter().map(|(_, hasher)| hasher(world)).collect();
+        let touched = self.tracked.iter().zip(self.last.iter().zip(&hashes))
+            .filter(|(_, (before, after))| before != after)
+            .map(|((name, _), (before, after))| ResourceChange { resource: name.clone(), before: *before, after: *after })
+            .collect();
+        let state = payload_digest(&serde_json::to_vec(&hashes).unwrap_or_default());
+        self.records.push(SystemTrace { tick: self.tick, system: system.to_string(), state, touched });
+        self.last = hashes;
+    }
+
+    /// Start the next tick; changes made between ticks (commands, API calls) land on `tick_start`
+    fn begin_tick(&mut self, world: &mut World) {
+        if self.started {
+            self.tick += 1;
+        }
+        self.started = true;
+        self.checkpoint(world, "tick_start");
+    }
+}
+
+/// Exclusive system that records a trace point labelled `system`
+pub fn trace_point(system: impl Into<String>) -> impl FnMut(&mut World) {
+    let system = system.into();
+    move |world: &mut World| {
+        world.resource_scope(|world, mut trace: Mut<TickTrace>| trace.checkpoint(world, &system));
+    }
+}
+
+fn trace_tick_start(world: &mut World) {
+    world.resource_scope(|world, mut trace: Mut<TickTrace>| trace.begin_tick(world));
+}
+
+/// Chained systems traced one by one, paired with the label they're recorded under
+macro_rules! traced {
+    ($($system:ident),* $(,)?) => {
+        vec![$((stringify!($system), $system.into_system_set().intern())),*]
+    };
+}
+
+/// Records a trace point after each chained resource and dispatch system, and after every
+/// other colony set. Systems ordered only relative to a set (e.g. `.after(ColonySet::Dispatch)`)
+/// land on whichever trace point follows them. Add after ColonyPlugin.
+pub struct TickTracePlugin;
+
+impl Plugin for TickTracePlugin {
+    fn build(&self, app: &mut App) {
+        let order = app.world().get_resource::<ColonyPluginConfig>().cloned().unwrap_or_default().resolved_order();
+        if !app.world().contains_resource::<TickTrace>() {
+            app.insert_resource(TickTrace::new());
+        }
+        if let Some(first) = order.first() {
+            app.add_systems(Update, trace_tick_start.before(*first));
+        }
+
+        let chains: Vec<(ColonySet, Vec<(&'static str, InternedSystemSet)>)> = vec![
+            (ColonySet::Resources, traced![
+                power_bandwidth_system, auto_throttle_system, heat_system, corruption_system, clock_drift_system,
+            ]),
+            (ColonySet::Dispatch, traced![
+                certification_system, op_cache_system, op_license_system, storage_wear_system, tenant_tick_system, job_retry_system,
+                maintenance_complete_system, dispatch_system, export_sink_system, archive_system, report_ingest_system,
+                maintenance_system, update_fault_kpis,
+            ]),
+        ];
+        for (i, set) in order.iter().enumerate() {
+            let next = order.get(i + 1).copied();
+            match chains.iter().find(|(chained, _)| chained == set) {
+                Some((_, systems)) => {
+                    for (j, (label, system)) in systems.iter().enumerate() {
+                        let point = trace_point(*label).in_set(*set).after(*system);
+                        match systems.get(j + 1) {
+                            Some((_, following)) => app.add_systems(Update, point.before(*following)),
+                            None => app.add_systems(Update, point),
+                        };
+                    }
+                }
+                None => {
+                    let point = trace_point(format!("set:{:?}", set)).after(*set);
+                    match next {
+                        Some(next) => app.add_systems(Update, point.before(next)),
+                        None => app.add_systems(Update, point),
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Write a trace as JSON lines, one system record per line
+pub fn write_tick_trace(path: &Path, records: &[SystemTrace]) -> Result<()> {
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for record in records {
+        serde_json::to_writer(&mut out, record)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn traced_run(corruption_at_tick: Option<u64>) -> Vec<SystemTrace> {
+        let colony = colony_for_scenario(None, Some(7)).unwrap();
+        let clock = SimClock { tick_scale: TickScale::RealTime, now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH };
+        let mut app = build_shadow_app_with_config(&colony, &clock, &ActiveScheduler::default(), ColonyPluginConfig::default());
+        app.add_plugins(TickTracePlugin);
+        for tick in 0..6 {
+            if corruption_at_tick == Some(tick) {
+                app.world_mut().resource_mut::<Colony>().corruption_field += 0.25;
+            }
+            app.update();
+        }
+        app.world().resource::<TickTrace>().records.clone()
+    }
+
+    #[test]
+    fn test_records_every_traced_system_each_tick() {
+        let records = traced_run(None);
+        assert_eq!(records.first().unwrap().system, "tick_start");
+        assert_eq!(records.last().unwrap().tick, 5);
+        let per_tick = records.iter().filter(|r| r.tick == 2).count();
+        assert_eq!(records.len(), per_tick * 6);
+        let dispatch = records.iter().position(|r| r.tick == 2 && r.system == "dispatch_system").unwrap();
+        assert_eq!(records[dispatch - 1].system, "job_retry_system");
+        assert!(records.iter().any(|r| r.system == "set:BlackSwans"));
+    }
+
+    #[test]
+    fn test_equal_runs_trace_alike_and_changes_show_where_they_land() {
+        assert_eq!(traced_run(None), traced_run(None));
+        let (clean, perturbed) = (traced_run(None), traced_run(Some(3)));
+        let first = clean.iter().zip(&perturbed).position(|(a, b)| a.state != b.state).unwrap();
+        assert_eq!((perturbed[first].tick, perturbed[first].system.as_str()), (3, "tick_start"));
+        assert!(perturbed[first].touched.iter().any(|c| c.resource == "Colony"));
+    }
+}
@@ -0,0 +1,354 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use anyhow::Result;
+#[cfg(feature = "native")]
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use crate::{Colony, SimClock, ReplayLog, ReplayEvent};
+
+const MAX_APPLIED: usize = 256;
+
+/// Tunable keys that can be changed while a run is live, with their valid ranges
+const TUNABLE_RANGES: &[(&str, f32, f32)] = &[
+    ("tunables.power_cap_kw", 1.0, 1.0e6),
+    ("tunables.heat_decay_per_tick", 0.0, 100.0),
+    ("tunables.heat_generated_per_unit", 0.0, 10.0),
+    ("tunables.bandwidth_total_gbps", 0.01, 1.0e4),
+    ("tunables.bandwidth_tail_exp", 1.0, 10.0),
+    ("tunables.thermal_throttle_knee", 0.0, 1.0),
+    ("tunables.thermal_min_throttle", 0.0, 1.0),
+    ("corruption.base_fault_rate", 0.0, 1.0),
+    ("corruption.heat_weight", 0.0, 10.0),
+    ("corruption.bw_weight", 0.0, 10.0),
+    ("corruption.starvation_weight", 0.0, 10.0),
+    ("corruption.decay_per_tick", 0.0, 1.0),
+    ("corruption.worker_decay_per_tick", 0.0, 1.0),
+    ("corruption.recover_boost", 0.0, 1.0),
+    ("corruption.retry_backoff_ms", 0.0, 600_000.0),
+    ("corruption.max_retries", 0.0, 255.0),
+];
+
+/// Tunables stored as integers; fractional values would be truncated on apply
+const INTEGER_TUNABLES: &[&str] = &["corruption.retry_backoff_ms", "corruption.max_retries"];
+
+pub fn tunable_range(key: &str) -> Option<(f32, f32)> {
+    TUNABLE_RANGES.iter().find(|(k, _, _)| *k == key).map(|&(_, min, max)| (min, max))
+}
+
+pub fn get_tunable(colony: &Colony, key: &str) -> Option<f32> {
+    let (t, c) = (&colony.tunables, &colony.corruption_tun);
+    Some(match key {
+        "tunables.power_cap_kw" => colony.power_cap_kw,
+        "tunables.heat_decay_per_tick" => t.heat_decay_per_tick,
+        "tunables.heat_generated_per_unit" => t.heat_generated_per_unit,
+        "tunables.bandwidth_total_gbps" => colony.bandwidth_total_gbps,
+        "tunables.bandwidth_tail_exp" => t.bandwidth_tail_exp,
+        "tunables.thermal_throttle_knee" => t.thermal_throttle_knee,
+        "tunables.thermal_min_throttle" => t.thermal_min_throttle,
+        "corruption.base_fault_rate" => c.base_fault_rate,
+        "corruption.heat_weight" => c.heat_weight,
+        "corruption.bw_weight" => c.bw_weight,
+        "corruption.starvation_weight" => c.starvation_weight,
+        "corruption.decay_per_tick" => c.decay_per_tick,
+        "corruption.worker_decay_per_tick" => c.worker_decay_per_tick,
+        "corruption.recover_boost" => c.recover_boost,
+        "corruption.retry_backoff_ms" => c.retry_backoff_ms as f32,
+        "corruption.max_retries" => c.max_retries as f32,
+        _ => return None,
+    })
+}
+
+/// Set one tunable after checking it is known and in range
+pub fn set_tunable(colony: &mut Colony, key: &str, value: f32) -> Result<()> {
+    let (min, max) = tunable_range(key)
+        .ok_or_else(|| anyhow::anyhow!("Unknown tunable: {}", key))?;
+    if !value.is_finite() || value < min || value > max {
+        return Err(anyhow::anyhow!("{} = {} is outside {}..={}", key, value, min, max));
+    }
+    if INTEGER_TUNABLES.contains(&key) && value.fract() != 0.0 {
+        return Err(anyhow::anyhow!("{} = {} must be a whole number", key, value));
+    }
+
+    let (t, c) = (&mut colony.tunables, &mut colony.corruption_tun);
+    match key {
+        // The live caps sit on Colony; keep the tunables copy in step
+        "tunables.power_cap_kw" => {
+            colony.power_cap_kw = value;
+            t.power_cap_kw = value;
+        }
+        "tunables.bandwidth_total_gbps" => {
+            colony.bandwidth_total_gbps = value;
+            t.bandwidth_total_gbps = value;
+        }
+        "tunables.heat_decay_per_tick" => t.heat_decay_per_tick = value,
+        "tunables.heat_generated_per_unit" => t.heat_generated_per_unit = value,
+        "tunables.bandwidth_tail_exp" => t.bandwidth_tail_exp = value,
+        "tunables.thermal_throttle_knee" => t.thermal_throttle_knee = value,
+        "tunables.thermal_min_throttle" => t.thermal_min_throttle = value,
+        "corruption.base_fault_rate" => c.base_fault_rate = value,
+        "corruption.heat_weight" => c.heat_weight = value,
+        "corruption.bw_weight" => c.bw_weight = value,
+        "corruption.starvation_weight" => c.starvation_weight = value,
+        "corruption.decay_per_tick" => c.decay_per_tick = value,
+        "corruption.worker_decay_per_tick" => c.worker_decay_per_tick = value,
+        "corruption.recover_boost" => c.recover_boost = value,
+        "corruption.retry_backoff_ms" => c.retry_backoff_ms = value as u64,
+        "corruption.max_retries" => c.max_retries = value as u8,
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Read the `[tunables]` and `[corruption]` tables of a config file as flat `section.field` keys.
+/// Other tables (seed, save_protection, ...) are ignored so a whole game config can be watched.
+pub fn parse_tunables_toml(content: &str) -> Result<BTreeMap<String, f32>> {
+    let doc: toml::Table = toml::from_str(content)?;
+    let mut values = BTreeMap::new();
+    for section in ["tunables", "corruption"] {
+        let Some(table) = doc.get(section) else { continue };
+        let table = table.as_table()
+            .ok_or_else(|| anyhow::anyhow!("[{}] must be a table", section))?;
+        for (field, value) in table {
+            let number = match value {
+                toml::Value::Float(f) => *f as f32,
+                toml::Value::Integer(i) => *i as f32,
+                other => return Err(anyhow::anyhow!("{}.{} must be a number, got {}", section, field, other.type_str())),
+            };
+            values.insert(format!("{}.{}", section, field), number);
+        }
+    }
+    Ok(values)
+}
+
+/// Changes needed to bring `colony` in line with `values`; fails without changing anything if any value is invalid
+pub fn diff_tunables(colony: &Colony, values: &BTreeMap<String, f32>) -> Result<Vec<(String, f32)>> {
+    let mut scratch = colony.clone();
+    let mut changes = Vec::new();
+    for (key, &value) in values {
+        let current = get_tunable(colony, key)
+            .ok_or_else(|| anyhow::anyhow!("Unknown tunable: {}", key))?;
+        set_tunable(&mut scratch, key, value)?;
+        if current != value {
+            changes.push((key.clone(), value));
+        }
+    }
+    Ok(changes)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedTunableChange {
+    pub tick: u64,
+    pub key: String,
+    pub old: f32,
+    pub new: f32,
+    pub source: PathBuf,
+}
+
+/// Watches config files and queues them for reload at the next tick boundary
+#[derive(Resource)]
+pub struct ConfigWatcher {
+    pub paths: Vec<PathBuf>,
+    pub applied: VecDeque<AppliedTunableChange>,
+    pub last_error: Option<String>,
+    dirty: Arc<Mutex<HashSet<PathBuf>>>,
+    #[cfg(feature = "native")]
+    _watcher: Option<notify::RecommendedWatcher>,
+}
+
+impl ConfigWatcher {
+    /// Watch `paths` on disk; each file is also loaded once at the first tick
+    #[cfg(feature = "native")]
+    pub fn watch(paths: Vec<PathBuf>) -> Result<Self> {
+        let mut this = Self::unwatched(paths);
+        let dirty = this.dirty.clone();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+            match res {
+                Ok(event) => {
+                    if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                        if let Ok(mut dirty) = dirty.lock() {
+                            dirty.extend(event.paths);
+                        }
+                    }
+                }
+                Err(e) => println!("Config watch error: {:?}", e),
+            }
+        })?;
+        // Watch the parent directories so editors that replace the file still trigger a reload
+        let mut dirs: Vec<PathBuf> = this.paths.iter()
+            .map(|p| p.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf())
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+        for dir in dirs {
+            watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        }
+        this._watcher = Some(watcher);
+        Ok(this)
+    }
+
+    /// A watcher that only reloads when told to, for tests and callers with their own file events
+    pub fn unwatched(paths: Vec<PathBuf>) -> Self {
+        let paths: Vec<PathBuf> = paths.into_iter()
+            .map(|p| std::fs::canonicalize(&p).unwrap_or(p))
+            .collect();
+        Self {
+            dirty: Arc::new(Mutex::new(paths.iter().cloned().collect())),
+            paths,
+            applied: VecDeque::new(),
+            last_error: None,
+            #[cfg(feature = "native")]
+            _watcher: None,
+        }
+    }
+
+    pub fn mark_dirty(&self, path: &Path) {
+        if let Ok(mut dirty) = self.dirty.lock() {
+            dirty.insert(std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+        }
+    }
+
+    /// Watched files that changed since the last call
+    pub fn take_dirty(&self) -> Vec<PathBuf> {
+        let Ok(mut dirty) = self.dirty.lock() else { return Vec::new() };
+        let mut changed: Vec<PathBuf> = self.paths.iter()
+            .filter(|p| dirty.contains(*p))
+            .cloned()
+            .collect();
+        dirty.clear();
+        changed.sort();
+        changed
+    }
+
+    /// Reload one file into `colony`; returns the changes applied
+    pub fn reload(&mut self, path: &Path, colony: &mut Colony, tick: u64) -> Result<Vec<AppliedTunableChange>> {
+        let values = parse_tunables_toml(&std::fs::read_to_string(path)?)?;
+        let changes = diff_tunables(colony, &values)?;
+        let mut applied = Vec::new();
+        for (key, value) in changes {
+            let old = get_tunable(colony, &key).unwrap_or_default();
+            set_tunable(colony, &key, value)?;
+            let change = AppliedTunableChange { tick, key, old, new: value, source: path.to_path_buf() };
+            self.applied.push_back(change.clone());
+            if self.applied.len() > MAX_APPLIED {
+                self.applied.pop_front();
+            }
+            applied.push(change);
+        }
+        Ok(applied)
+    }
+
+    /// Reload every watched file that changed; rejected files are logged and leave `colony` untouched
+    pub fn poll(&mut self, colony: &mut Colony, tick: u64) -> Vec<AppliedTunableChange> {
+        let mut applied = Vec::new();
+        for path in self.take_dirty() {
+            match self.reload(&path, colony, tick) {
+                Ok(changes) => {
+                    self.last_error = None;
+                    for change in &changes {
+                        println!("Tunable {} {} -> {} (from {})", change.key, change.old, change.new, path.display());
+                    }
+                    applied.extend(changes);
+                }
+                Err(e) => {
+                    println!("Rejected config change in {}: {}", path.display(), e);
+                    self.last_error = Some(format!("{}: {}", path.display(), e));
+                }
+            }
+        }
+        applied
+    }
+}
+
+/// Runs before the Time set so changes land on a tick boundary; each change is
+/// recorded in the replay log as a TunableChange
+pub fn config_watch_system(
+    watcher: Option<ResMut<ConfigWatcher>>,
+    mut colony: ResMut<Colony>,
+    clock: Res<SimClock>,
+    mut replay_log: Option<ResMut<ReplayLog>>,
+) {
+    let Some(mut watcher) = watcher else { return };
+    let tick = clock.now.timestamp_millis() as u64 / 16;
+    for change in watcher.poll(&mut colony, tick) {
+        if let Some(ref mut log) = replay_log {
+            log.record_event(ReplayEvent::TunableChange { key: change.key, value: change.new });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colony_for_scenario;
+
+    #[test]
+    fn test_parse_and_diff() {
+        let colony = colony_for_scenario(None, Some(1)).unwrap();
+        let values = parse_tunables_toml(r#"
+            seed = 42
+            [tunables]
+            heat_decay_per_tick = 1.5
+            bandwidth_tail_exp = 3
+            [corruption]
+            max_retries = 5
+        "#).unwrap();
+        assert_eq!(values.len(), 3);
+
+        let changes = diff_tunables(&colony, &values).unwrap();
+        // heat_decay_per_tick is already 1.5
+        assert_eq!(changes.len(), 2);
+        assert!(changes.contains(&("tunables.bandwidth_tail_exp".to_string(), 3.0)));
+    }
+
+    #[test]
+    fn test_invalid_change_rejects_whole_file() {
+        let colony = colony_for_scenario(None, Some(1)).unwrap();
+        let values = parse_tunables_toml(r#"
+            [tunables]
+            bandwidth_tail_exp = 3.0
+            thermal_throttle_knee = 1.5
+        "#).unwrap();
+        assert!(diff_tunables(&colony, &values).is_err());
+
+        let unknown = parse_tunables_toml("[tunables]\nwarp_drive = 1.0\n").unwrap();
+        assert!(diff_tunables(&colony, &unknown).is_err());
+        assert!(parse_tunables_toml("[tunables]\nbandwidth_tail_exp = \"fast\"\n").is_err());
+
+        let fractional = parse_tunables_toml("[corruption]\nmax_retries = 2.5\n").unwrap();
+        assert!(diff_tunables(&colony, &fractional).is_err());
+    }
+
+    #[test]
+    fn test_system_applies_and_records() {
+        let path = std::env::temp_dir().join(format!("colony_tunables_{}.toml", std::process::id()));
+        std::fs::write(&path, "[tunables]\npower_cap_kw = 750.0\n").unwrap();
+
+        let mut replay_log = ReplayLog::new();
+        replay_log.start_recording();
+        let mut app = App::new();
+        app.insert_resource(colony_for_scenario(None, Some(1)).unwrap())
+            .insert_resource(SimClock {
+                tick_scale: crate::TickScale::RealTime,
+                now: chrono::DateTime::<chrono::Utc>::UNIX_EPOCH,
+            })
+            .insert_resource(replay_log)
+            .insert_resource(ConfigWatcher::unwatched(vec![path.clone()]))
+            .add_systems(Update, config_watch_system);
+
+        app.update();
+        assert_eq!(app.world().resource::<Colony>().power_cap_kw, 750.0);
+        let log = app.world().resource::<ReplayLog>();
+        assert!(matches!(&log.events[0], ReplayEvent::TunableChange { key, value } if key == "tunables.power_cap_kw" && *value == 750.0));
+
+        // A bad edit leaves the running values alone
+        std::fs::write(&path, "[tunables]\npower_cap_kw = -5.0\n").unwrap();
+        app.world().resource::<ConfigWatcher>().mark_dirty(&path);
+        app.update();
+        assert_eq!(app.world().resource::<Colony>().power_cap_kw, 750.0);
+        assert!(app.world().resource::<ConfigWatcher>().last_error.is_some());
+
+        std::fs::remove_file(&path).ok();
+    }
+}
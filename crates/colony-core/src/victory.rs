@@ -137,14 +137,16 @@ impl SlaTracker {
 pub fn update_sla_window(
     mut sla_tracker: ResMut<SlaTracker>,
     clock: Res<super::SimClock>,
-    // TODO: Add event reader for deadline hits/misses
+    clock_drift: Option<ResMut<super::ClockDrift>>,
 ) {
     let current_tick = clock.now.timestamp_millis() as u64 / 16;
-    
-    // For now, simulate some deadline results
-    // In a real implementation, this would read from actual job completion events
-    let simulated_hit = (current_tick % 10) != 0; // 90% hit rate
-    sla_tracker.add_deadline_result(simulated_hit, current_tick);
+
+    // Deadlines judged by the yards this tick, skewed by their local clocks.
+    // Ticks with no completions leave the window alone.
+    let reported = clock_drift.map(|mut d| d.take_reported()).unwrap_or_default();
+    for hit in reported {
+        sla_tracker.add_deadline_result(hit, current_tick);
+    }
 }
 
 pub fn eval_victory(
@@ -170,6 +172,18 @@ pub fn eval_victory(
     consecutive_days >= victory_rules.target_uptime_days
 }
 
+/// Green objective: this SLA period's carbon stays under the rules' cap, when they set one
+pub fn eval_carbon_objective(
+    victory_rules: &super::game_config::VictoryRules,
+    energy: Option<&super::EnergyLedger>,
+    current_tick: u64,
+) -> bool {
+    match (victory_rules.max_carbon_kg_per_period, energy) {
+        (Some(max_kg), Some(energy)) => energy.period_carbon_kg(current_tick) <= max_kg as f64,
+        _ => true,
+    }
+}
+
 pub fn eval_loss(
     loss_rules: &super::game_config::LossRules,
     colony: &super::Colony,
@@ -245,15 +259,23 @@ pub fn win_loss_system(
     mut sla_tracker: ResMut<SlaTracker>,
     colony: Res<super::Colony>,
     fault_kpis: Res<super::FaultKpi>,
-    black_swan_index: Res<super::BlackSwanIndex>,
-    research_state: Res<super::ResearchState>,
+    black_swan_index: Option<Res<super::BlackSwanIndex>>,
+    research_state: Option<Res<super::ResearchState>>,
     clock: Res<super::SimClock>,
+    mode: Option<Res<super::GameMode>>,
+    energy: Option<Res<super::EnergyLedger>>,
     // TODO: Add game setup resource to get victory/loss rules
 ) {
     if win_loss_state.is_game_over() {
         return;
     }
 
+    // Events and research are optional plugins; fall back to empty state without them
+    let no_black_swans = super::BlackSwanIndex::new();
+    let no_research = super::ResearchState::new();
+    let black_swan_index = black_swan_index.as_deref().unwrap_or(&no_black_swans);
+    let research_state = research_state.as_deref().unwrap_or(&no_research);
+
     let current_tick = clock.now.timestamp_millis() as u64 / 16;
     let ticks_per_day = 86400000 / 16; // 1 day in 16ms ticks
 
@@ -263,15 +285,22 @@ pub fn win_loss_system(
     let loss_rules = super::game_config::LossRules::default();
 
     // Check for victory
-    if eval_victory(&victory_rules, &sla_tracker, colony.corruption_field, current_tick, ticks_per_day) {
+    if eval_victory(&victory_rules, &sla_tracker, colony.corruption_field, current_tick, ticks_per_day)
+        && eval_carbon_objective(&victory_rules, energy.as_deref(), current_tick)
+    {
         win_loss_state.victory = true;
         win_loss_state.victory_time = Some(current_tick);
-        win_loss_state.score = compute_score(&victory_rules, &sla_tracker, &research_state, current_tick, ticks_per_day);
+        win_loss_state.score = compute_score(&victory_rules, &sla_tracker, research_state, current_tick, ticks_per_day);
         println!("VICTORY! Score: {}", win_loss_state.score);
     }
 
+    // Creative sessions can't be lost
+    if mode.is_some_and(|m| m.is_creative()) {
+        return;
+    }
+
     // Check for loss
-    let (is_doom, doom_reason) = eval_loss(&loss_rules, &colony, &fault_kpis, &black_swan_index, current_tick, ticks_per_day);
+    let (is_doom, doom_reason) = eval_loss(&loss_rules, &colony, &fault_kpis, black_swan_index, current_tick, ticks_per_day);
     if is_doom {
         win_loss_state.doom = true;
         win_loss_state.doom_time = Some(current_tick);
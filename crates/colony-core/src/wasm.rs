@@ -0,0 +1,39 @@
+use wasm_bindgen::prelude::*;
+use crate::{GameMode, ReplReply, ReplSession};
+
+/// A sim session for a browser page. Build with `make wasm`; from JS:
+/// `const sim = new ColonySim("first_light_chill", 7); sim.step(60); JSON.parse(sim.metrics())`
+#[wasm_bindgen]
+pub struct ColonySim {
+    session: ReplSession,
+}
+
+#[wasm_bindgen]
+impl ColonySim {
+    /// Start a session; both arguments are optional
+    #[wasm_bindgen(constructor)]
+    pub fn init(scenario: Option<String>, seed: Option<u32>) -> Result<ColonySim, JsError> {
+        let session = ReplSession::new(scenario.as_deref(), seed.map(u64::from), GameMode::Standard)
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(ColonySim { session })
+    }
+
+    /// Run `ticks` sim ticks and return the tick reached
+    pub fn step(&mut self, ticks: u32) -> f64 {
+        self.session.step(ticks as u64);
+        self.session.tick as f64
+    }
+
+    /// Tick, queue depth, verdict and KPIs as a JSON string
+    pub fn metrics(&mut self) -> Result<String, JsError> {
+        serde_json::to_string(&self.session.status()).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Run a headless REPL command, e.g. `enqueue udp_telemetry_ingest 4096`
+    pub fn command(&mut self, line: &str) -> Result<String, JsError> {
+        match self.session.execute_line(line).map_err(|e| JsError::new(&e.to_string()))? {
+            Some(ReplReply::Text(text)) => Ok(text),
+            Some(ReplReply::Quit) | None => Ok(String::new()),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use crate::{IoKind, RetryPolicy, RetryState, WorkClass, Worker, WorkerHealth, WorkerState, WorkerStateComp, WorkyardKind};
+
+/// Which workers a bulk operation touches; unset fields match everyone
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkerFilter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ids: Vec<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<WorkClass>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<WorkerState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_corruption: Option<f32>, // 0..=1, inclusive
+}
+
+impl WorkerFilter {
+    pub fn matches(&self, worker: &Worker, health: &WorkerHealth, state: &WorkerStateComp) -> bool {
+        (self.ids.is_empty() || self.ids.contains(&worker.id))
+            && self.class.is_none_or(|class| class == worker.class)
+            && self.state.is_none_or(|s| s == state.0)
+            && self.min_corruption.is_none_or(|min| health.corruption >= min)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkAction {
+    Reimage,
+    Reassign { yard: WorkyardKind }, // the worker takes that yard's class
+    SetRetryPolicy { max_retries: u8, backoff_ms: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkWorkerOp {
+    #[serde(default)]
+    pub filter: WorkerFilter,
+    pub action: BulkAction,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BulkOutcome {
+    pub matched: Vec<u64>,
+    pub changed: Vec<u64>, // matched workers the action actually altered
+}
+
+/// Clear a worker's corruption and sticky faults and bring it back from recovery
+pub fn reimage(health: &mut WorkerHealth, state: &mut WorkerStateComp) {
+    health.corruption = 0.0;
+    health.sticky_faults = 0;
+    if state.0 == WorkerState::Recovering {
+        state.0 = WorkerState::Idle;
+    }
+}
+
+/// The class a worker needs to take `yard`'s jobs; I/O workers keep their protocol
+pub fn class_for_yard(yard: &WorkyardKind, current: WorkClass) -> Result<WorkClass> {
+    match yard {
+        WorkyardKind::CpuArray => Ok(WorkClass::Cpu),
+        WorkyardKind::GpuFarm => Ok(WorkClass::Gpu),
+        WorkyardKind::SignalHub => Ok(match current {
+            WorkClass::Io(kind) => WorkClass::Io(kind),
+            _ => WorkClass::Io(IoKind::Udp),
+        }),
+        WorkyardKind::StorageArray => anyhow::bail!("StorageArray has no workers of its own to reassign to"),
+    }
+}
+
+impl BulkWorkerOp {
+    fn validate(&self) -> Result<()> {
+        if let Some(min) = self.filter.min_corruption {
+            if !(0.0..=1.0).contains(&min) {
+                anyhow::bail!("min_corruption is {}, must be between 0 and 1", min);
+            }
+        }
+        if let BulkAction::Reassign { yard } = &self.action {
+            class_for_yard(yard, WorkClass::Cpu)?;
+        }
+        Ok(())
+    }
+}
+
+/// Apply `op` to every matching worker between ticks, all of them or (if the op is invalid) none
+pub fn apply_bulk(world: &mut World, op: &BulkWorkerOp) -> Result<BulkOutcome> {
+    op.validate()?;
+    let mut outcome = BulkOutcome::default();
+    let mut workers = world.query::<(&mut Worker, &mut WorkerHealth, &mut WorkerStateComp, &mut RetryState)>();
+    for (mut worker, mut health, mut state, mut retry) in workers.iter_mut(world) {
+        if !op.filter.matches(&worker, &health, &state) {
+            continue;
+        }
+        outcome.matched.push(worker.id);
+        let changed = match &op.action {
+            BulkAction::Reimage => {
+                let dirty = health.corruption > 0.0 || health.sticky_faults > 0 || state.0 == WorkerState::Recovering;
+                reimage(&mut health, &mut state);
+                dirty
+            }
+            BulkAction::Reassign { yard } => {
+                let class = class_for_yard(yard, worker.class)?;
+                let dirty = worker.class != class;
+                worker.class = class;
+                dirty
+            }
+            BulkAction::SetRetryPolicy { max_retries, backoff_ms } => {
+                let policy = RetryPolicy { max_retries: *max_retries, backoff_ms: *backoff_ms };
+                let dirty = retry.policy.max_retries != policy.max_retries || retry.policy.backoff_ms != policy.backoff_ms;
+                retry.policy = policy;
+                dirty
+            }
+        };
+        if changed {
+            outcome.changed.push(worker.id);
+        }
+    }
+    outcome.matched.sort_unstable();
+    outcome.changed.sort_unstable();
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{WorkerBundle, WorkerSkills};
+
+    fn world() -> World {
+        let mut world = World::new();
+        let skills = WorkerSkills { cpu: 0.5, gpu: 0.5, io: 0.5, discipline: 0.5, focus: 0.5 };
+        for (id, class, corruption) in [(1, WorkClass::Cpu, 0.8), (2, WorkClass::Cpu, 0.1), (3, WorkClass::Gpu, 0.9)] {
+            let mut bundle = WorkerBundle::new(id, class, skills);
+            bundle.health.corruption = corruption;
+            if corruption > 0.5 {
+                bundle.state.0 = WorkerState::Recovering;
+            }
+            world.spawn(bundle);
+        }
+        world
+    }
+
+    fn worker(world: &mut World, id: u64) -> (Worker, WorkerHealth, WorkerState, RetryPolicy) {
+        let mut q = world.query::<(&Worker, &WorkerHealth, &WorkerStateComp, &RetryState)>();
+        q.iter(world).find(|(w, ..)| w.id == id).map(|(w, h, s, r)| (*w, *h, s.0, r.policy)).unwrap()
+    }
+
+    #[test]
+    fn test_reimage_above_corruption_threshold() {
+        let mut world = world();
+        let op = BulkWorkerOp { filter: WorkerFilter { min_corruption: Some(0.5), ..Default::default() }, action: BulkAction::Reimage };
+        let outcome = apply_bulk(&mut world, &op).unwrap();
+        assert_eq!(outcome.matched, vec![1, 3]);
+        assert_eq!(outcome.changed, vec![1, 3]);
+        assert_eq!(worker(&mut world, 1).1.corruption, 0.0);
+        assert_eq!(worker(&mut world, 3).2, WorkerState::Idle);
+        assert_eq!(worker(&mut world, 2).1.corruption, 0.1);
+    }
+
+    #[test]
+    fn test_reassign_class_and_retry_policy() {
+        let mut world = world();
+        let to_gpu = BulkWorkerOp {
+            filter: WorkerFilter { class: Some(WorkClass::Cpu), ..Default::default() },
+            action: BulkAction::Reassign { yard: WorkyardKind::GpuFarm },
+        };
+        assert_eq!(apply_bulk(&mut world, &to_gpu).unwrap().changed, vec![1, 2]);
+        assert_eq!(worker(&mut world, 2).0.class, WorkClass::Gpu);
+
+        let retries = BulkWorkerOp { filter: WorkerFilter::default(), action: BulkAction::SetRetryPolicy { max_retries: 5, backoff_ms: 32 } };
+        assert_eq!(apply_bulk(&mut world, &retries).unwrap().changed, vec![1, 2, 3]);
+        assert_eq!(worker(&mut world, 3).3.max_retries, 5);
+    }
+
+    #[test]
+    fn test_invalid_op_changes_nothing() {
+        let mut world = world();
+        let to_storage = BulkWorkerOp { filter: WorkerFilter::default(), action: BulkAction::Reassign { yard: WorkyardKind::StorageArray } };
+        assert!(apply_bulk(&mut world, &to_storage).is_err());
+        let bad_threshold = BulkWorkerOp { filter: WorkerFilter { min_corruption: Some(1.5), ..Default::default() }, action: BulkAction::Reimage };
+        assert!(apply_bulk(&mut world, &bad_threshold).is_err());
+        assert_eq!(worker(&mut world, 1).0.class, WorkClass::Cpu);
+        assert_eq!(worker(&mut world, 1).1.corruption, 0.8);
+    }
+}
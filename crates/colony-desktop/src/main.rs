@@ -1,23 +1,125 @@
 use bevy::prelude::*;
-use colony_core::{ColonyPlugin, SimClock, TickScale, enqueue_maintenance, JobQueue};
+use colony_core::{ColonyPlugin, ContentPack, GameMode, ConfigWatcher, SimClock, TickScale, enqueue_maintenance, JobQueue, ProfileStore, colony_for_scenario, plugin_config_for_scenario, ColonyPluginConfig, SaveProtection};
 use ron::ser::to_string_pretty;
 use std::fs;
 
 mod ui_simple_text;
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Compute Colony".into(),
-                resolution: (1200.0, 800.0).into(),
-                ..default()
-            }),
+    // Pick the player profile first: it sizes the window and holds the UI's achievements and keybindings
+    let mut profile = match ProfileStore::open_default().select(profile_arg().as_deref()) {
+        Ok(profile) => {
+            println!("Using profile '{}' from {}", profile.name, profile.paths.dir.display());
+            Some(profile)
+        }
+        Err(e) => {
+            eprintln!("Failed to load player profile: {}", e);
+            None
+        }
+    };
+    let settings = profile.as_ref().map(|p| p.settings.clone()).unwrap_or_default();
+
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            title: "Compute Colony".into(),
+            resolution: settings.window.clamped().into(),
             ..default()
-        }))
-        .add_plugins(ColonyPlugin)
-        .add_plugins(ui_simple_text::SimpleTextUiPlugin)
-        .run();
+        }),
+        ..default()
+    }));
+
+    // Vanilla plus whatever data mods are installed; the plugins build the tech tree and swans from it
+    match ContentPack::load_mods(std::path::Path::new("mods")) {
+        Ok(content) => {
+            println!("Loaded content from: {}", content.sources.join(", "));
+            app.insert_resource(content);
+        }
+        Err(e) => eprintln!("Failed to load mods, using built-in content: {}", e),
+    }
+    if creative_arg() {
+        println!("Creative mode: loss is off and scores are not comparable");
+        app.insert_resource(GameMode::Creative);
+    }
+    // Saves and replays stay plain unless `--game-config PATH` turns protection on
+    if let Some(path) = game_config_arg() {
+        match SaveProtection::from_game_config_file(&path) {
+            Ok(protection) => {
+                app.insert_resource(protection);
+            }
+            Err(e) => eprintln!("Failed to set up save protection from {}: {}", path, e),
+        }
+    }
+    // `--scenario ID`, else the one played last
+    let scenario = scenario_arg().or(settings.last_scenario);
+    let config = match scenario.as_deref().map(|id| plugin_config_for_scenario(Some(id))) {
+        Some(Ok(config)) => config,
+        _ => ColonyPluginConfig::default(),
+    };
+    app.add_plugins(ColonyPlugin::with_config(config));
+
+    if let Some(scenario) = scenario {
+        match colony_for_scenario(Some(&scenario), None) {
+            Ok(colony) => {
+                println!("Starting scenario '{}'", scenario);
+                app.insert_resource(colony);
+                if let Some(ref mut profile) = profile {
+                    profile.settings.last_scenario = Some(scenario);
+                    if let Err(e) = profile.save_settings() {
+                        eprintln!("Failed to save profile settings: {}", e);
+                    }
+                }
+            }
+            Err(e) => eprintln!("Failed to load scenario '{}': {}", scenario, e),
+        }
+    }
+    if let Some(profile) = profile {
+        app.insert_resource(profile);
+    }
+    app.add_plugins(ui_simple_text::SimpleTextUiPlugin);
+
+    let watch = watch_config_args();
+    if !watch.is_empty() {
+        match ConfigWatcher::watch(watch) {
+            Ok(watcher) => {
+                app.insert_resource(watcher);
+            }
+            Err(e) => eprintln!("Failed to watch config files: {}", e),
+        }
+    }
+    app.run();
+}
+
+/// Paths passed as `--watch-config PATH` (repeatable)
+fn watch_config_args() -> Vec<std::path::PathBuf> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2)
+        .filter(|w| w[0] == "--watch-config")
+        .map(|w| std::path::PathBuf::from(&w[1]))
+        .collect()
+}
+
+/// Scenario passed as `--scenario ID`
+fn scenario_arg() -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2).find(|w| w[0] == "--scenario").map(|w| w[1].clone())
+}
+
+/// Game config passed as `--game-config PATH`, read for its save protection settings
+fn game_config_arg() -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2).find(|w| w[0] == "--game-config").map(|w| w[1].clone())
+}
+
+/// `--creative` starts a sandbox session for testing content
+fn creative_arg() -> bool {
+    std::env::args().any(|a| a == "--creative")
+}
+
+/// Profile passed as `--profile NAME`; the last one used otherwise
+fn profile_arg() -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2).find(|w| w[0] == "--profile").map(|w| w[1].clone())
 }
 
 // Legacy keyboard input handlers (now handled by UI)
@@ -28,7 +130,7 @@ fn handle_legacy_keyboard_input(
     _jobq: ResMut<JobQueue>,
     clock: Res<SimClock>,
     colony: Res<colony_core::Colony>,
-    workers: Query<&colony_core::Worker>,
+    workers: Query<(&colony_core::Worker, &colony_core::WorkerSkills, &colony_core::WorkerHealth, &colony_core::WorkerStateComp)>,
 ) {
     // Legacy hotkeys for quick access
     if keyboard.just_pressed(KeyCode::Digit1) {
@@ -58,13 +160,13 @@ fn handle_legacy_keyboard_input(
 fn save_game(
     clock: &SimClock,
     colony: &colony_core::Colony,
-    workers: &Query<&colony_core::Worker>,
+    workers: &Query<(&colony_core::Worker, &colony_core::WorkerSkills, &colony_core::WorkerHealth, &colony_core::WorkerStateComp)>,
     yards: &Query<&colony_core::Workyard>,
 ) {
     let save_data = SaveData {
         clock: clock.clone(),
         colony: colony.clone(),
-        workers: workers.iter().cloned().collect(),
+        workers: workers.iter().map(|(w, skills, health, state)| (*w, *skills, *health, *state)).collect(),
         yards: yards.iter().cloned().collect(),
     };
 
@@ -99,6 +201,6 @@ fn load_game() {
 struct SaveData {
     clock: SimClock,
     colony: colony_core::Colony,
-    workers: Vec<colony_core::Worker>,
+    workers: Vec<(colony_core::Worker, colony_core::WorkerSkills, colony_core::WorkerHealth, colony_core::WorkerStateComp)>,
     yards: Vec<colony_core::Workyard>,
 }
@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContexts};
-use colony_core::{Colony, SimClock, TickScale, ActiveScheduler, SchedPolicy, enqueue_maintenance, JobQueue, Worker, Workyard, YardWorkload, GpuFarm, GpuBatchQueues, BlackSwanIndex, Debts, ResearchState, TechTree, FaultKpi, CorruptionField, IoRolling};
+use colony_core::{Colony, SimClock, TickScale, ActiveScheduler, SchedPolicy, enqueue_maintenance, JobQueue, DeadLetterQueue, TenantLedger, YardGrid, YardPos, EventJournal, Digest, DigestPeriod, Worker, WorkerSkills, WorkerHealth, WorkerStateComp, RetryState, Certifications, Workyard, YardWorkload, GpuFarm, GpuBatchQueues, BlackSwanIndex, Debts, ResearchState, EffectiveCapacity, TechTree, FaultKpi, CorruptionField, IoRolling, CorruptionTopology, EdgeKind};
 use colony_io::IoSimulatorConfig;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 
 #[derive(States, Default, Debug, Clone, Eq, PartialEq, Hash)]
 pub enum AppState {
@@ -15,6 +17,45 @@ pub enum AppState {
 pub struct UiCache {
     pub intents: Vec<UiIntent>,
     pub selected_tab: UiTab,
+    pub workers_table: TableView,
+    pub queue_table: TableView,
+    pub journal_table: TableView,
+    pub selected_yard: Option<Entity>, // picked up in the layout editor, waiting for a free cell
+}
+
+/// Filter, sort and resulting row order for one virtualized table. The order is rebuilt only
+/// when the rows or the settings change, so a steady 10k-row table costs one visible page a frame.
+#[derive(Default)]
+pub struct TableView {
+    pub filter: String,
+    pub sort_col: usize,
+    pub descending: bool,
+    order: Vec<usize>,
+    built: Option<(u64, String, usize, bool)>, // rows generation and settings `order` reflects
+}
+
+impl TableView {
+    /// Indices into `rows` that pass the filter, in display order. `matches` gets the lowercased filter.
+    pub fn order<R>(
+        &mut self,
+        rows: &[R],
+        generation: u64,
+        matches: impl Fn(&R, &str) -> bool,
+        compare: impl Fn(&R, &R, usize) -> Ordering,
+    ) -> &[usize] {
+        let key = (generation, self.filter.clone(), self.sort_col, self.descending);
+        if self.built.as_ref() != Some(&key) {
+            let needle = self.filter.to_lowercase();
+            let (col, descending) = (self.sort_col, self.descending);
+            self.order = (0..rows.len()).filter(|&i| needle.is_empty() || matches(&rows[i], &needle)).collect();
+            self.order.sort_by(|&a, &b| {
+                let ord = compare(&rows[a], &rows[b], col);
+                if descending { ord.reverse() } else { ord }
+            });
+            self.built = Some(key);
+        }
+        &self.order
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq)]
@@ -23,6 +64,8 @@ pub enum UiTab {
     Dashboard,
     Pipelines,
     Workers,
+    Queue,
+    Journal,
     Yards,
     Io,
     Gpu,
@@ -41,8 +84,11 @@ pub enum UiIntent {
     StartUdp(IoSimulatorConfig),
     StartHttp(IoSimulatorConfig),
     Enqueue { pipeline: String, payload: usize },
+    RequeueDeadLetter(u64), // job id
+    RequeueAllDeadLetters,
     SwitchSched(SchedPolicy),
     Maintenance(Entity), // yard
+    MoveYard(Entity, YardPos),
     RunRitual(String),
     StartReplay(String),
     StopReplay,
@@ -50,6 +96,7 @@ pub enum UiIntent {
     StartGame,
     LoadGame,
     SaveGame,
+    DismissDigest,
 }
 
 // UI Events that will be processed by the simulation
@@ -83,7 +130,8 @@ pub struct LoadGame;
 #[derive(Event)]
 pub struct SaveGame;
 
-// UI Snapshot Resources for efficient display
+// UI Snapshot Resources for efficient display. Each is refreshed by its own system only when
+// the resources or components behind it changed, and the row tables are patched per entity.
 #[derive(Resource, Default)]
 pub struct UiMeters {
     pub power_draw: f32,
@@ -112,10 +160,37 @@ pub struct PipelineRow {
 #[derive(Resource, Default)]
 pub struct UiWorkers {
     pub rows: Vec<WorkerRow>,
+    pub generation: u64, // bumped on every row change so table views know to re-sort
+    index: HashMap<Entity, usize>, // row position of each worker
+}
+
+impl UiWorkers {
+    /// Replace the worker's row in place, or append it if it's new
+    pub fn upsert(&mut self, row: WorkerRow) {
+        self.generation += 1;
+        match self.index.get(&row.entity) {
+            Some(&i) => self.rows[i] = row,
+            None => {
+                self.index.insert(row.entity, self.rows.len());
+                self.rows.push(row);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(i) = self.index.remove(&entity) {
+            self.generation += 1;
+            self.rows.swap_remove(i);
+            if let Some(moved) = self.rows.get(i) {
+                self.index.insert(moved.entity, i);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct WorkerRow {
+    pub entity: Entity,
     pub id: String,
     pub class: String,
     pub state: String,
@@ -124,11 +199,35 @@ pub struct WorkerRow {
     pub skill_io: f32,
     pub corruption: f32,
     pub retries: u32,
+    pub certs: String,
 }
 
 #[derive(Resource, Default)]
 pub struct UiYards {
     pub rows: Vec<YardRow>,
+    pub grid_size: (i32, i32), // width and height of the layout grid
+    index: HashMap<Entity, usize>, // row position of each yard
+}
+
+impl UiYards {
+    pub fn upsert(&mut self, row: YardRow) {
+        match self.index.get(&row.entity) {
+            Some(&i) => self.rows[i] = row,
+            None => {
+                self.index.insert(row.entity, self.rows.len());
+                self.rows.push(row);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(i) = self.index.remove(&entity) {
+            self.rows.swap_remove(i);
+            if let Some(moved) = self.rows.get(i) {
+                self.index.insert(moved.entity, i);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -141,6 +240,56 @@ pub struct YardRow {
     pub power_draw: f32,
     pub slots_used: usize,
     pub slots_total: usize,
+    pub pos: Option<YardPos>,
+    pub cable_ms: f32, // on an idle network
+}
+
+#[derive(Resource, Default)]
+pub struct UiQueue {
+    pub rows: Vec<QueueRow>,
+    pub dead_letters: Vec<DeadLetterRow>,
+    pub generation: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueueRow {
+    pub job_id: u64,
+    pub lane: &'static str,
+    pub qos: String,
+    pub ops: String,
+    pub deadline_ms: u64,
+    pub payload_sz: usize,
+    pub enq_tick: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeadLetterRow {
+    pub job_id: u64,
+    pub reason: String,
+    pub ops: String,
+    pub dropped_tick: u64,
+    pub age_ticks: u64,
+}
+
+#[derive(Resource, Default)]
+pub struct UiJournal {
+    pub rows: Vec<JournalRow>,
+    pub generation: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct JournalRow {
+    pub tick: u64,
+    pub day: u64,
+    pub event: &'static str,
+    pub detail: String,
+}
+
+/// Day report shown when the sim pauses at a day rollover
+#[derive(Resource, Default)]
+pub struct UiDigest {
+    pub pending: Option<Digest>,
+    pub seen: usize, // digests already taken from the journal
 }
 
 #[derive(Resource, Default)]
@@ -159,6 +308,8 @@ pub struct UiEvents {
     pub active: Vec<String>,
     pub recent: Vec<(String, u64)>,
     pub debts: Vec<String>,
+    pub capacity: Vec<String>, // caps left once debts apply
+    pub expiries: Vec<String>, // soonest first
 }
 
 #[derive(Resource, Default)]
@@ -179,6 +330,9 @@ impl Plugin for DesktopUiPlugin {
            .insert_resource(UiPipelines::default())
            .insert_resource(UiWorkers::default())
            .insert_resource(UiYards::default())
+           .insert_resource(UiQueue::default())
+           .insert_resource(UiJournal::default())
+           .insert_resource(UiDigest::default())
            .insert_resource(UiGpu::default())
            .insert_resource(UiEvents::default())
            .insert_resource(UiResearch::default())
@@ -193,7 +347,17 @@ impl Plugin for DesktopUiPlugin {
            .add_event::<LoadGame>()
            .add_event::<SaveGame>()
            .add_systems(Startup, ui_setup)
-           .add_systems(Update, update_ui_snapshots)
+           .add_systems(Update, (
+               update_meter_snapshot,
+               update_worker_rows,
+               update_yard_rows,
+               update_queue_rows,
+               update_journal_rows,
+               update_day_report,
+               update_gpu_snapshot,
+               update_event_snapshot,
+               update_research_snapshot,
+           ))
            .add_systems(Update, ui_frame_system)
            .add_systems(Update, ui_command_flush)
            .add_systems(Update, crate::handle_legacy_keyboard_input);
@@ -204,35 +368,25 @@ fn ui_setup(mut commands: Commands) {
     commands.spawn(Camera2d::default());
 }
 
-fn update_ui_snapshots(
+fn update_meter_snapshot(
     colony: Res<Colony>,
-    workers: Query<(Entity, &Worker)>,
-    yards: Query<(Entity, &Workyard, &YardWorkload)>,
-    gpu_farms: Query<&GpuFarm>,
-    batch_queues: Res<GpuBatchQueues>,
-    black_swan_index: Res<BlackSwanIndex>,
-    debts: Res<Debts>,
-    research_state: Res<ResearchState>,
-    tech_tree: Res<TechTree>,
     fault_kpis: Res<FaultKpi>,
     corruption_field: Res<CorruptionField>,
     mut ui_meters: ResMut<UiMeters>,
     mut ui_pipelines: ResMut<UiPipelines>,
-    mut ui_workers: ResMut<UiWorkers>,
-    mut ui_yards: ResMut<UiYards>,
-    mut ui_gpu: ResMut<UiGpu>,
-    mut ui_events: ResMut<UiEvents>,
-    mut ui_research: ResMut<UiResearch>,
 ) {
-    // Update meters
-    ui_meters.power_draw = colony.meters.power_draw_kw;
-    ui_meters.power_cap = colony.power_cap_kw;
-    ui_meters.bw_util = colony.meters.bandwidth_util;
-    ui_meters.corruption_global = corruption_field.global;
-    ui_meters.sla_percent = fault_kpis.deadline_hit_rate * 100.0;
-
-    // Update pipelines (placeholder - would need actual pipeline data)
-    ui_pipelines.rows.clear();
+    if colony.is_changed() || fault_kpis.is_changed() || corruption_field.is_changed() {
+        ui_meters.power_draw = colony.meters.power_draw_kw;
+        ui_meters.power_cap = colony.power_cap_kw;
+        ui_meters.bw_util = colony.meters.bandwidth_util;
+        ui_meters.corruption_global = corruption_field.global;
+        ui_meters.sla_percent = fault_kpis.deadline_hit_rate * 100.0;
+    }
+
+    // Pipelines (placeholder - would need actual pipeline data); fixed, so filled once
+    if !ui_pipelines.rows.is_empty() {
+        return;
+    }
     ui_pipelines.rows.push(PipelineRow {
         id: "udp_pipeline".to_string(),
         qos: "High".to_string(),
@@ -251,25 +405,51 @@ fn update_ui_snapshots(
         queue_depth: 3,
         default_payload: 2048,
     });
+}
 
-    // Update workers
-    ui_workers.rows.clear();
-    for (entity, worker) in workers.iter() {
-        ui_workers.rows.push(WorkerRow {
+/// Rewrite only the rows of workers whose components changed since the last frame
+fn update_worker_rows(
+    workers: Query<
+        (Entity, &Worker, &WorkerSkills, &WorkerHealth, &WorkerStateComp, &RetryState, Option<&Certifications>),
+        Or<(Changed<WorkerSkills>, Changed<WorkerHealth>, Changed<WorkerStateComp>, Changed<RetryState>, Changed<Certifications>)>,
+    >,
+    mut removed: RemovedComponents<Worker>,
+    mut ui_workers: ResMut<UiWorkers>,
+) {
+    for entity in removed.read() {
+        ui_workers.remove(entity);
+    }
+    for (entity, worker, skills, health, state, retry, certs) in workers.iter() {
+        let certs = certs.map(|c| c.held.iter().map(|k| k.label()).collect::<Vec<_>>().join(", ")).unwrap_or_default();
+        ui_workers.upsert(WorkerRow {
+            entity,
             id: format!("worker_{}", entity.index()),
             class: format!("{:?}", worker.class),
-            state: format!("{:?}", worker.state),
-            skill_cpu: worker.skill_cpu,
-            skill_gpu: worker.skill_gpu,
-            skill_io: worker.skill_io,
-            corruption: worker.corruption,
-            retries: worker.retry.max_retries as u32,
+            state: format!("{:?}", state.0),
+            skill_cpu: skills.cpu,
+            skill_gpu: skills.gpu,
+            skill_io: skills.io,
+            corruption: health.corruption,
+            retries: retry.policy.max_retries as u32,
+            certs: if certs.is_empty() { "-".to_string() } else { certs },
         });
     }
+}
 
-    // Update yards
-    ui_yards.rows.clear();
-    for (entity, yard, workload) in yards.iter() {
+fn update_yard_rows(
+    colony: Res<Colony>,
+    grid: Res<YardGrid>,
+    yards: Query<(Entity, &Workyard, &YardWorkload, Option<&YardPos>), Or<(Changed<Workyard>, Changed<YardWorkload>, Changed<YardPos>)>>,
+    mut removed: RemovedComponents<Workyard>,
+    mut ui_yards: ResMut<UiYards>,
+) {
+    for entity in removed.read() {
+        ui_yards.remove(entity);
+    }
+    if grid.is_changed() {
+        ui_yards.grid_size = (grid.tunables.width, grid.tunables.height);
+    }
+    for (entity, yard, workload, pos) in yards.iter() {
         let throttle = colony_core::thermal_throttle(
             yard.heat,
             yard.heat_cap,
@@ -277,7 +457,7 @@ fn update_ui_snapshots(
             colony.tunables.thermal_min_throttle,
         );
         
-        ui_yards.rows.push(YardRow {
+        ui_yards.upsert(YardRow {
             entity,
             kind: format!("{:?}", yard.kind),
             heat: yard.heat,
@@ -286,27 +466,127 @@ fn update_ui_snapshots(
             power_draw: yard.power_draw_kw,
             slots_used: workload.units_this_tick as usize,
             slots_total: yard.slots as usize,
+            pos: pos.copied(),
+            cable_ms: grid.cable_ms(pos, 1.0),
         });
     }
+}
 
-    // Update GPU
-    if let Some(gpu_farm) = gpu_farms.iter().next() {
+/// The queue churns every tick while jobs flow, so it's rebuilt whenever it changed at all
+fn update_queue_rows(jobq: Res<JobQueue>, dead_letters: Option<Res<DeadLetterQueue>>, mut ui_queue: ResMut<UiQueue>) {
+    if !jobq.is_changed() && !dead_letters.as_ref().is_some_and(|d| d.is_changed()) {
+        return;
+    }
+    let lanes = [("CPU", &jobq.cpu), ("GPU", &jobq.gpu), ("I/O", &jobq.io), ("Storage", &jobq.storage)];
+    let rows = lanes.into_iter().flat_map(|(lane, jobs)| jobs.iter().map(move |ej| QueueRow {
+        job_id: ej.job.id,
+        lane,
+        qos: format!("{:?}", ej.job.qos),
+        ops: ej.job.pipeline.ops.iter().map(|op| format!("{:?}", op)).collect::<Vec<_>>().join(" > "),
+        deadline_ms: ej.job.deadline_ms,
+        payload_sz: ej.job.payload_sz,
+        enq_tick: ej.enq_tick,
+    }));
+    ui_queue.rows.clear();
+    ui_queue.rows.extend(rows);
+    ui_queue.dead_letters = dead_letters.iter().flat_map(|d| d.letters.iter().rev()).map(|letter| DeadLetterRow {
+        job_id: letter.job.job.id,
+        reason: format!("{:?}", letter.reason),
+        ops: letter.job.job.pipeline.ops.iter().map(|op| format!("{:?}", op)).collect::<Vec<_>>().join(" > "),
+        dropped_tick: letter.dropped_tick,
+        age_ticks: letter.age_ticks,
+    }).collect();
+    ui_queue.generation += 1;
+}
+
+fn update_journal_rows(journal: Option<Res<EventJournal>>, mut ui_journal: ResMut<UiJournal>) {
+    let Some(journal) = journal.filter(|j| j.is_changed()) else {
+        return;
+    };
+    ui_journal.rows = journal.entries.iter().map(|e| JournalRow {
+        tick: e.tick,
+        day: e.day,
+        event: e.event.name(),
+        detail: e.event.detail().unwrap_or("-").to_string(),
+    }).collect();
+    ui_journal.generation += 1;
+}
+
+/// Pause on each new day digest so the player sees the day report
+fn update_day_report(
+    journal: Option<Res<EventJournal>>,
+    app_state: Res<State<AppState>>,
+    mut ui_digest: ResMut<UiDigest>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(journal) = journal.filter(|j| j.is_changed()) else {
+        return;
+    };
+    if journal.digests.len() < ui_digest.seen {
+        ui_digest.seen = 0; // journal replaced, e.g. by a load
+    }
+    let latest = journal.digests.iter().skip(ui_digest.seen)
+        .filter(|d| d.period == DigestPeriod::Day)
+        .last()
+        .cloned();
+    ui_digest.seen = journal.digests.len();
+    if let Some(digest) = latest {
+        ui_digest.pending = Some(digest);
+        if *app_state.get() == AppState::InGame {
+            next_state.set(AppState::Paused);
+        }
+    }
+}
+
+fn update_gpu_snapshot(
+    gpu_farms: Query<Ref<GpuFarm>>,
+    batch_queues: Res<GpuBatchQueues>,
+    mut ui_gpu: ResMut<UiGpu>,
+) {
+    let Some(gpu_farm) = gpu_farms.iter().next() else {
+        return;
+    };
+    if gpu_farm.is_changed() {
         ui_gpu.util = gpu_farm.meters.util;
         ui_gpu.vram_used = gpu_farm.meters.vram_used_gb;
         ui_gpu.vram_total = gpu_farm.per_gpu.vram_gb;
         ui_gpu.batch_latency = gpu_farm.meters.batch_latency_ms;
         ui_gpu.batches_inflight = gpu_farm.meters.batches_inflight as usize;
-        
+    }
+    if batch_queues.is_changed() {
         ui_gpu.queues.clear();
         for (pipeline_id, buffer) in &batch_queues.buffers {
             ui_gpu.queues.push((pipeline_id.clone(), buffer.items.len()));
         }
     }
+}
 
-    // Update events
-    ui_events.eligible.clear();
-    ui_events.active = black_swan_index.meters.active.clone();
-    ui_events.recent = black_swan_index.meters.recently_fired.clone();
+fn update_event_snapshot(
+    black_swan_index: Res<BlackSwanIndex>,
+    debts: Res<Debts>,
+    capacity: Res<EffectiveCapacity>,
+    mut ui_events: ResMut<UiEvents>,
+) {
+    if black_swan_index.is_changed() {
+        ui_events.eligible.clear();
+        ui_events.active = black_swan_index.meters.active.clone();
+        ui_events.recent = black_swan_index.meters.recently_fired.clone();
+    }
+    if capacity.is_changed() {
+        ui_events.capacity = vec![
+            format!("Power Cap: {:.0} kW", capacity.power_cap_kw),
+            format!("Bandwidth: {:.1} Gbps", capacity.bandwidth_total_gbps),
+            format!("Fault Rate: {:.2}x", capacity.fault_rate_mult),
+            format!("Heat: +{:.1}°C/tick", capacity.heat_add_per_tick),
+            format!("VRAM Leak: {:.1} MB/tick", capacity.vram_leak_mb_per_tick),
+        ];
+        ui_events.expiries = capacity.expiries.iter()
+            .map(|e| format!("{} in {} ticks (tick {})", e.kind, e.ticks_remaining, e.until_tick))
+            .collect();
+    }
+    if !debts.is_changed() {
+        return;
+    }
     
     ui_events.debts.clear();
     for debt in &debts.active {
@@ -329,10 +609,27 @@ fn update_ui_snapshots(
             colony_core::Debt::Illusion { metric, delta, until_tick } => {
                 ui_events.debts.push(format!("Illusion: {} {:.2} (until {})", metric, delta, until_tick));
             }
+            colony_core::Debt::YardOffline { yard, until_tick } => {
+                ui_events.debts.push(format!("Yard Offline: {} (until {})", yard, until_tick));
+            }
+            colony_core::Debt::YardIdle { yard, until_tick } => {
+                ui_events.debts.push(format!("Strike: {} (until {})", yard, until_tick));
+            }
+            colony_core::Debt::SinkOffline { sink, until_tick } => {
+                ui_events.debts.push(format!("Sink Offline: {} (until {})", sink, until_tick));
+            }
         }
     }
+}
 
-    // Update research
+fn update_research_snapshot(
+    research_state: Res<ResearchState>,
+    tech_tree: Res<TechTree>,
+    mut ui_research: ResMut<UiResearch>,
+) {
+    if !research_state.is_changed() && !tech_tree.is_changed() {
+        return;
+    }
     ui_research.points = research_state.pts;
     ui_research.available_techs = tech_tree.get_available_techs(&research_state)
         .iter()
@@ -357,9 +654,11 @@ fn ui_frame_system(
     ui_pipelines: Res<UiPipelines>,
     ui_workers: Res<UiWorkers>,
     ui_yards: Res<UiYards>,
+    (ui_queue, ui_journal, mut ui_digest): (Res<UiQueue>, Res<UiJournal>, ResMut<UiDigest>),
     ui_gpu: Res<UiGpu>,
     ui_events: Res<UiEvents>,
     ui_research: Res<UiResearch>,
+    topology: Res<CorruptionTopology>,
 ) {
     let Ok(ctx) = egui_ctx.ctx_mut() else {
         return;
@@ -416,6 +715,8 @@ fn ui_frame_system(
                     UiTab::Dashboard,
                     UiTab::Pipelines,
                     UiTab::Workers,
+                    UiTab::Queue,
+                    UiTab::Journal,
                     UiTab::Yards,
                     UiTab::Io,
                     UiTab::Gpu,
@@ -430,6 +731,8 @@ fn ui_frame_system(
                         UiTab::Dashboard => "📊 Dashboard",
                         UiTab::Pipelines => "🔧 Pipelines",
                         UiTab::Workers => "👷 Workers",
+                        UiTab::Queue => "📥 Queue",
+                        UiTab::Journal => "📓 Journal",
                         UiTab::Yards => "🏭 Yards",
                         UiTab::Io => "🌐 I/O",
                         UiTab::Gpu => "🎮 GPU",
@@ -453,11 +756,13 @@ fn ui_frame_system(
                     UiTab::Dashboard => draw_dashboard(ui, &ui_meters, &ui_pipelines, &ui_workers, &ui_yards, &ui_gpu, &mut cache),
                     UiTab::Pipelines => draw_pipelines(ui, &ui_pipelines, &mut cache),
                     UiTab::Workers => draw_workers(ui, &ui_workers, &mut cache),
+                    UiTab::Queue => draw_queue(ui, &ui_queue, &mut cache),
+                    UiTab::Journal => draw_journal(ui, &ui_journal, &mut cache),
                     UiTab::Yards => draw_yards(ui, &ui_yards, &mut cache),
                     UiTab::Io => draw_io_panel(ui, &mut cache),
                     UiTab::Gpu => draw_gpu_panel(ui, &ui_gpu, &mut cache),
                     UiTab::Scheduler => draw_scheduler_panel(ui, &mut cache),
-                    UiTab::Corruption => draw_corruption_panel(ui, &mut cache),
+                    UiTab::Corruption => draw_corruption_panel(ui, &topology, &mut cache),
                     UiTab::Events => draw_events_panel(ui, &ui_events, &mut cache),
                     UiTab::Research => draw_research_panel(ui, &ui_research, &mut cache),
                     UiTab::Mods => draw_mods_panel(ui, &mut cache),
@@ -469,6 +774,15 @@ fn ui_frame_system(
             egui::SidePanel::right("meters").show(ctx, |ui| {
                 draw_meters(ui, &ui_meters);
             });
+
+            if *app_state.get() == AppState::Paused {
+                if let Some(digest) = &ui_digest.pending {
+                    if draw_day_report(ctx, digest) {
+                        ui_digest.pending = None;
+                        cache.intents.push(UiIntent::DismissDigest);
+                    }
+                }
+            }
         }
     }
 
@@ -595,39 +909,216 @@ fn draw_pipelines(ui: &mut egui::Ui, pipelines: &UiPipelines, cache: &mut UiCach
     });
 }
 
-fn draw_workers(ui: &mut egui::Ui, workers: &UiWorkers, _cache: &mut UiCache) {
+const TABLE_COL_WIDTH: f32 = 90.0;
+
+/// Filter box and clickable column headers; clicking the sorted column flips its direction
+fn table_header(ui: &mut egui::Ui, id: &str, view: &mut TableView, columns: &[&str]) {
+    ui.horizontal(|ui| {
+        ui.label("Filter:");
+        ui.text_edit_singleline(&mut view.filter);
+    });
+    egui::Grid::new(format!("{}_header", id)).min_col_width(TABLE_COL_WIDTH).show(ui, |ui| {
+        for (col, name) in columns.iter().enumerate() {
+            let arrow = match (view.sort_col == col, view.descending) {
+                (true, false) => " ⏶",
+                (true, true) => " ⏷",
+                (false, _) => "",
+            };
+            if ui.button(format!("{}{}", name, arrow)).clicked() {
+                if view.sort_col == col {
+                    view.descending = !view.descending;
+                } else {
+                    view.sort_col = col;
+                    view.descending = false;
+                }
+            }
+        }
+        ui.end_row();
+    });
+}
+
+/// Lay out only the rows scrolled into view; `draw_row` gets the position in `order`
+fn virtual_rows(ui: &mut egui::Ui, id: &str, count: usize, mut draw_row: impl FnMut(&mut egui::Ui, usize)) {
+    let row_height = ui.spacing().interact_size.y;
+    egui::ScrollArea::vertical()
+        .id_salt(id)
+        .auto_shrink(false)
+        .show_rows(ui, row_height, count, |ui, range| {
+            egui::Grid::new(format!("{}_rows", id))
+                .striped(true)
+                .min_col_width(TABLE_COL_WIDTH)
+                .min_row_height(row_height)
+                .show(ui, |ui| {
+                    for i in range {
+                        draw_row(ui, i);
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+fn draw_workers(ui: &mut egui::Ui, workers: &UiWorkers, cache: &mut UiCache) {
     ui.heading("Workers");
     ui.add_space(10.0);
-    
-    egui::Grid::new("workers_grid").striped(true).show(ui, |ui| {
-        ui.heading("ID");
-        ui.heading("Class");
-        ui.heading("State");
-        ui.heading("CPU");
-        ui.heading("GPU");
-        ui.heading("I/O");
-        ui.heading("Corruption");
-        ui.heading("Retries");
-        ui.end_row();
 
-        for w in &workers.rows {
-            ui.label(&w.id);
-            ui.label(&w.class);
-            ui.label(&w.state);
-            ui.label(format!("{:.1}", w.skill_cpu));
-            ui.label(format!("{:.1}", w.skill_gpu));
-            ui.label(format!("{:.1}", w.skill_io));
-            ui.add(egui::ProgressBar::new(w.corruption)
-                .text(format!("{:.1}%", w.corruption * 100.0)));
-            ui.label(w.retries.to_string());
-            ui.end_row();
+    let view = &mut cache.workers_table;
+    table_header(ui, "workers", view, &["ID", "Class", "State", "CPU", "GPU", "I/O", "Corruption", "Retries", "Certified"]);
+    let order = view.order(
+        &workers.rows,
+        workers.generation,
+        |w, needle| [&w.id, &w.class, &w.state, &w.certs].iter().any(|f| f.to_lowercase().contains(needle)),
+        |a, b, col| match col {
+            0 => a.entity.index().cmp(&b.entity.index()),
+            1 => a.class.cmp(&b.class),
+            2 => a.state.cmp(&b.state),
+            3 => a.skill_cpu.total_cmp(&b.skill_cpu),
+            4 => a.skill_gpu.total_cmp(&b.skill_gpu),
+            5 => a.skill_io.total_cmp(&b.skill_io),
+            6 => a.corruption.total_cmp(&b.corruption),
+            7 => a.retries.cmp(&b.retries),
+            _ => a.certs.cmp(&b.certs),
+        },
+    );
+    ui.label(format!("{} of {} workers", order.len(), workers.rows.len()));
+
+    virtual_rows(ui, "workers", order.len(), |ui, i| {
+        let w = &workers.rows[order[i]];
+        ui.label(&w.id);
+        ui.label(&w.class);
+        ui.label(&w.state);
+        ui.label(format!("{:.1}", w.skill_cpu));
+        ui.label(format!("{:.1}", w.skill_gpu));
+        ui.label(format!("{:.1}", w.skill_io));
+        ui.add(egui::ProgressBar::new(w.corruption)
+            .text(format!("{:.1}%", w.corruption * 100.0)));
+        ui.label(w.retries.to_string());
+        ui.label(&w.certs);
+    });
+}
+
+fn draw_queue(ui: &mut egui::Ui, queue: &UiQueue, cache: &mut UiCache) {
+    ui.heading("Job Queue");
+    ui.add_space(10.0);
+
+    let view = &mut cache.queue_table;
+    table_header(ui, "queue", view, &["Job", "Lane", "QoS", "Pipeline", "Deadline", "Payload", "Enqueued"]);
+    let order = view.order(
+        &queue.rows,
+        queue.generation,
+        |r, needle| [r.lane, r.qos.as_str(), r.ops.as_str()].iter().any(|f| f.to_lowercase().contains(needle)) || r.job_id.to_string() == needle,
+        |a, b, col| match col {
+            0 => a.job_id.cmp(&b.job_id),
+            1 => a.lane.cmp(b.lane),
+            2 => a.qos.cmp(&b.qos),
+            3 => a.ops.cmp(&b.ops),
+            4 => a.deadline_ms.cmp(&b.deadline_ms),
+            5 => a.payload_sz.cmp(&b.payload_sz),
+            _ => a.enq_tick.cmp(&b.enq_tick),
+        },
+    );
+    ui.label(format!("{} of {} queued jobs", order.len(), queue.rows.len()));
+
+    virtual_rows(ui, "queue", order.len(), |ui, i| {
+        let r = &queue.rows[order[i]];
+        ui.label(r.job_id.to_string());
+        ui.label(r.lane);
+        ui.label(&r.qos);
+        ui.label(&r.ops);
+        ui.label(format!("{} ms", r.deadline_ms));
+        ui.label(format!("{} B", r.payload_sz));
+        ui.label(r.enq_tick.to_string());
+    });
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.heading(format!("Dead Letters ({})", queue.dead_letters.len()));
+        if !queue.dead_letters.is_empty() && ui.button("Requeue All").clicked() {
+            cache.intents.push(UiIntent::RequeueAllDeadLetters);
+        }
+    });
+    virtual_rows(ui, "dead_letters", queue.dead_letters.len(), |ui, i| {
+        let d = &queue.dead_letters[i];
+        ui.label(d.job_id.to_string());
+        ui.label(&d.reason);
+        ui.label(&d.ops);
+        ui.label(format!("dropped {}", d.dropped_tick));
+        ui.label(format!("waited {} ticks", d.age_ticks));
+        if ui.small_button("Requeue").clicked() {
+            cache.intents.push(UiIntent::RequeueDeadLetter(d.job_id));
         }
     });
 }
 
+/// Returns true once the player dismisses the report
+fn draw_day_report(ctx: &egui::Context, digest: &Digest) -> bool {
+    let mut dismissed = false;
+    egui::Window::new(format!("Day {} report", digest.index))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label(format!("SLA: {:.2}%", digest.sla_hit_pct));
+            ui.label(format!("Research: {:+} pts", digest.research_points_gained));
+            if !digest.techs_acquired.is_empty() {
+                ui.label(format!("Acquired: {}", digest.techs_acquired.join(", ")));
+            }
+            ui.label(format!("Budget: {:+.1}", digest.budget_delta));
+            ui.label(format!("Debts: +{} / -{}", digest.debts_gained.len(), digest.debts_cleared.len()));
+            for debt in &digest.debts_gained {
+                ui.label(format!("  + {}", debt));
+            }
+            for debt in &digest.debts_cleared {
+                ui.label(format!("  - {}", debt));
+            }
+            ui.separator();
+            if digest.notable_events.is_empty() {
+                ui.label("A quiet day.");
+            }
+            for event in &digest.notable_events {
+                ui.label(format!("• {}", event));
+            }
+            ui.separator();
+            if ui.button("Continue").clicked() {
+                dismissed = true;
+            }
+        });
+    dismissed
+}
+
+fn draw_journal(ui: &mut egui::Ui, journal: &UiJournal, cache: &mut UiCache) {
+    ui.heading("Journal");
+    ui.add_space(10.0);
+
+    let view = &mut cache.journal_table;
+    table_header(ui, "journal", view, &["Tick", "Day", "Event", "Detail"]);
+    let order = view.order(
+        &journal.rows,
+        journal.generation,
+        |r, needle| r.event.to_lowercase().contains(needle) || r.detail.to_lowercase().contains(needle),
+        |a, b, col| match col {
+            0 => a.tick.cmp(&b.tick),
+            1 => a.day.cmp(&b.day).then(a.tick.cmp(&b.tick)),
+            2 => a.event.cmp(b.event),
+            _ => a.detail.cmp(&b.detail),
+        },
+    );
+    ui.label(format!("{} of {} entries", order.len(), journal.rows.len()));
+
+    virtual_rows(ui, "journal", order.len(), |ui, i| {
+        let r = &journal.rows[order[i]];
+        ui.label(r.tick.to_string());
+        ui.label(r.day.to_string());
+        ui.label(r.event);
+        ui.label(&r.detail);
+    });
+}
+
 fn draw_yards(ui: &mut egui::Ui, yards: &UiYards, cache: &mut UiCache) {
     ui.heading("Workyards");
     ui.add_space(10.0);
+
+    draw_yard_layout(ui, yards, cache);
+    ui.add_space(10.0);
     
     for yard in &yards.rows {
         ui.group(|ui| {
@@ -654,6 +1145,11 @@ fn draw_yards(ui: &mut egui::Ui, yards: &UiYards, cache: &mut UiCache) {
                     ui.label("Slots");
                     ui.label(format!("{}/{}", yard.slots_used, yard.slots_total));
                 });
+
+                ui.vertical(|ui| {
+                    ui.label("Cable");
+                    ui.label(format!("{:.1} ms", yard.cable_ms));
+                });
             });
             
             if ui.button("Maintenance").clicked() {
@@ -664,6 +1160,45 @@ fn draw_yards(ui: &mut egui::Ui, yards: &UiYards, cache: &mut UiCache) {
     }
 }
 
+/// Layout editor: click a yard to pick it up, then a free cell to put it down. Yards sharing a
+/// wall trade heat, and the further from the uplink the longer the cable.
+fn draw_yard_layout(ui: &mut egui::Ui, yards: &UiYards, cache: &mut UiCache) {
+    ui.label("Layout");
+    let (width, height) = yards.grid_size;
+    egui::Grid::new("yard_layout").spacing(egui::vec2(2.0, 2.0)).show(ui, |ui| {
+        for y in 0..height {
+            for x in 0..width {
+                let pos = YardPos::new(x, y);
+                let occupant = yards.rows.iter().find(|yard| yard.pos == Some(pos));
+                let label = match occupant {
+                    Some(yard) => yard.kind.chars().take(2).collect(),
+                    None => "·".to_string(),
+                };
+                let heat = occupant.map_or(0.0, |yard| (yard.heat / yard.heat_cap).clamp(0.0, 1.0));
+                let fill = egui::Color32::from_rgb(40 + (heat * 200.0) as u8, 40, 60);
+                let selected = occupant.is_some_and(|yard| cache.selected_yard == Some(yard.entity));
+                let button = egui::Button::new(label).fill(fill).selected(selected).min_size(egui::vec2(24.0, 24.0));
+                let response = ui.add(button);
+                let response = match occupant {
+                    Some(yard) => response.on_hover_text(format!("{} #{}: {:.1}°C", yard.kind, yard.entity.index(), yard.heat)),
+                    None => response,
+                };
+                if response.clicked() {
+                    match (occupant, cache.selected_yard) {
+                        (Some(yard), _) => cache.selected_yard = Some(yard.entity),
+                        (None, Some(selected)) => {
+                            cache.intents.push(UiIntent::MoveYard(selected, pos));
+                            cache.selected_yard = None;
+                        }
+                        (None, None) => {}
+                    }
+                }
+            }
+            ui.end_row();
+        }
+    });
+}
+
 fn draw_io_panel(ui: &mut egui::Ui, cache: &mut UiCache) {
     ui.heading("I/O Control Panel");
     ui.add_space(10.0);
@@ -679,6 +1214,9 @@ fn draw_io_panel(ui: &mut egui::Ui, cache: &mut UiCache) {
                     loss: 0.01,
                     payload_bytes: 1024,
                     http_paths: vec![],
+                    malformed: 0.0,
+                    flow_ids: false,
+                    pcap: None,
                 }));
             }
             if ui.button("Stop UDP").clicked() {
@@ -696,6 +1234,9 @@ fn draw_io_panel(ui: &mut egui::Ui, cache: &mut UiCache) {
                     loss: 0.005,
                     payload_bytes: 2048,
                     http_paths: vec![],
+                    malformed: 0.0,
+                    flow_ids: false,
+                    pcap: None,
                 }));
             }
             if ui.button("Stop HTTP").clicked() {
@@ -761,12 +1302,68 @@ fn draw_scheduler_panel(ui: &mut egui::Ui, cache: &mut UiCache) {
     ui.label("• EDF: Earliest Deadline First");
 }
 
-fn draw_corruption_panel(ui: &mut egui::Ui, _cache: &mut UiCache) {
+fn draw_corruption_panel(ui: &mut egui::Ui, topology: &CorruptionTopology, _cache: &mut UiCache) {
     ui.heading("Corruption & Faults");
+    ui.label(format!(
+        "Global field {:.1}% | Bandwidth {:.0}% | as of tick {}",
+        topology.global * 100.0, topology.bandwidth_util * 100.0, topology.tick
+    ));
     ui.add_space(10.0);
-    
-    ui.label("This panel shows corruption levels and fault statistics.");
-    ui.label("Use the main UI panels to monitor specific metrics.");
+
+    // Heat overlay: one tile per node, outlined where faults landed recently
+    ui.label("Yards (heat)");
+    ui.horizontal_wrapped(|ui| {
+        for yard in &topology.yards {
+            heat_tile(ui, yard.heat_frac, yard.recent_faults, format!(
+                "{} {:?}\nheat {:.0}% | domain {} | {} recent faults",
+                yard.id, yard.kind, yard.heat_frac * 100.0, yard.isolation_domain, yard.recent_faults
+            ));
+        }
+    });
+    ui.label("Workers (corruption)");
+    ui.horizontal_wrapped(|ui| {
+        for worker in &topology.workers {
+            heat_tile(ui, worker.corruption, worker.recent_faults, format!(
+                "{} {:?} {:?}\ncorruption {:.1}% | {} sticky | {} recent faults",
+                worker.id, worker.class, worker.state, worker.corruption * 100.0, worker.sticky_faults, worker.recent_faults
+            ));
+        }
+    });
+    ui.add_space(10.0);
+
+    ui.label("Strongest propagation edges");
+    let mut edges: Vec<_> = topology.edges.iter().collect();
+    edges.sort_by(|a, b| b.weight.partial_cmp(&a.weight).unwrap_or(Ordering::Equal));
+    egui::Grid::new("corruption_edges").striped(true).show(ui, |ui| {
+        for edge in edges.iter().take(12) {
+            ui.label(format!("{} -> {}", edge.from, edge.to));
+            ui.label(match edge.kind {
+                EdgeKind::Heat => "heat",
+                EdgeKind::Bandwidth => "bandwidth",
+                EdgeKind::Field => "field",
+                EdgeKind::Fault => "faults",
+            });
+            ui.label(format!("{:.3}", edge.weight));
+            ui.end_row();
+        }
+    });
+    ui.add_space(10.0);
+
+    ui.label("Recent faults");
+    for site in topology.recent_faults.iter().rev().take(10) {
+        ui.label(format!("tick {}: worker {} on {} ({:?}) {:?} in {:?}", site.tick, site.worker_id, site.yard, site.yard_kind, site.kind, site.op));
+    }
+}
+
+fn heat_tile(ui: &mut egui::Ui, value: f32, faults: u32, hover: String) {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(18.0, 18.0), egui::Sense::hover());
+    let v = value.clamp(0.0, 1.0);
+    let color = egui::Color32::from_rgb((60.0 + 195.0 * v) as u8, (40.0 + 160.0 * (1.0 - v)) as u8, 60);
+    ui.painter().rect_filled(rect, 2.0, color);
+    if faults > 0 {
+        ui.painter().rect_stroke(rect, 2.0, egui::Stroke::new(1.5, egui::Color32::YELLOW), egui::StrokeKind::Inside);
+    }
+    response.on_hover_text(hover);
 }
 
 fn draw_events_panel(ui: &mut egui::Ui, events: &UiEvents, _cache: &mut UiCache) {
@@ -791,6 +1388,19 @@ fn draw_events_panel(ui: &mut egui::Ui, events: &UiEvents, _cache: &mut UiCache)
     for debt in &events.debts {
         ui.label(format!("• {}", debt));
     }
+
+    ui.add_space(10.0);
+
+    ui.label("Effective Capacity:");
+    for line in &events.capacity {
+        ui.label(format!("• {}", line));
+    }
+    if !events.expiries.is_empty() {
+        ui.label("Clears:");
+        for expiry in &events.expiries {
+            ui.label(format!("• {}", expiry));
+        }
+    }
 }
 
 fn draw_research_panel(ui: &mut egui::Ui, research: &UiResearch, cache: &mut UiCache) {
@@ -901,8 +1511,8 @@ fn ui_command_flush(
     mut next_state: ResMut<NextState<AppState>>,
     mut scheduler: ResMut<ActiveScheduler>,
     mut clock: ResMut<SimClock>,
-    _yards: Query<Entity, With<Workyard>>,
-    mut jobq: ResMut<JobQueue>,
+    (grid, mut yards): (Res<YardGrid>, Query<(Entity, &mut YardPos), With<Workyard>>),
+    (mut jobq, mut dead_letters, mut tenants): (ResMut<JobQueue>, ResMut<DeadLetterQueue>, ResMut<TenantLedger>),
 ) {
     let intents = std::mem::take(&mut cache.intents);
     for intent in intents {
@@ -924,12 +1534,28 @@ fn ui_command_flush(
                 // TODO: Create proper job from pipeline
                 // ev_job.write(JobSubmitted(job));
             }
+            UiIntent::RequeueDeadLetter(job_id) => {
+                // Refused when the job's tenant is at quota; the row stays for another try
+                let _ = dead_letters.requeue(job_id, &mut jobq, &mut tenants, clock.now.timestamp_millis() as u64 / 16);
+            }
+            UiIntent::RequeueAllDeadLetters => {
+                dead_letters.requeue_all(&mut jobq, &mut tenants, clock.now.timestamp_millis() as u64 / 16);
+            }
             UiIntent::SwitchSched(policy) => {
                 scheduler.policy = policy;
                 ev_sched.write(SwitchScheduler(policy));
             }
             UiIntent::Maintenance(yard_entity) => {
-                enqueue_maintenance(yard_entity, &mut jobq);
+                enqueue_maintenance(yard_entity, &mut jobq, clock.now.timestamp_millis() as u64 / 16);
+            }
+            UiIntent::MoveYard(yard, to) => {
+                // A move onto a taken cell or off the grid is ignored
+                let placed: Vec<(Entity, YardPos)> = yards.iter().map(|(entity, pos)| (entity, *pos)).collect();
+                if grid.check_move(yard, to, &placed).is_ok() {
+                    if let Ok((_, mut pos)) = yards.get_mut(yard) {
+                        *pos = to;
+                    }
+                }
             }
             UiIntent::RunRitual(ritual_id) => {
                 ev_ritual.write(StartRitual(ritual_id));
@@ -954,6 +1580,9 @@ fn ui_command_flush(
             UiIntent::SaveGame => {
                 ev_save_game.write(SaveGame);
             }
+            UiIntent::DismissDigest => {
+                next_state.set(AppState::InGame);
+            }
         }
     }
 }
@@ -11,15 +11,85 @@ pub enum AppState {
 #[derive(Component)]
 pub struct UiText;
 
+#[derive(Resource, Default)]
+pub struct ProfilerOverlay {
+    pub visible: bool,
+}
+
+/// Ticks/sec, entity counts and frame-vs-sim split from the SimProfiler
+#[derive(Resource, Default)]
+pub struct SimSpeedHud {
+    pub visible: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct AchievementsScreen {
+    pub visible: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct WorkersScreen {
+    pub visible: bool,
+}
+
+/// Auto-pause triggers; number keys toggle them while the screen is open
+#[derive(Resource, Default)]
+pub struct PauseSettingsScreen {
+    pub visible: bool,
+}
+
+/// The last loss's post-mortem; arrow keys step through its timeline
+#[derive(Resource, Default)]
+pub struct PostMortemScreen {
+    pub visible: bool,
+    pub step: usize,
+}
+
+/// Registered schedulers, built-in and from mods; number keys switch dispatch to one
+#[derive(Resource, Default)]
+pub struct SchedulerScreen {
+    pub visible: bool,
+}
+
+/// How long settings must stay unchanged before they are written out
+const SETTINGS_SAVE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// SLA thresholds the settings screen cycles through
+const SLA_PAUSE_STEPS: [Option<f32>; 6] = [None, Some(99.0), Some(95.0), Some(90.0), Some(80.0), Some(50.0)];
+
 pub struct SimpleTextUiPlugin;
 
 impl Plugin for SimpleTextUiPlugin {
     fn build(&self, app: &mut App) {
+        // The desktop game keeps unlocks across runs in the player profile; the core default is in-memory only
+        let profile = app.world().get_resource::<colony_core::LocalProfile>().cloned();
+        let achievements = match profile {
+            Some(ref profile) => profile.achievements(),
+            None => colony_core::Achievements::with_profile(colony_core::DEFAULT_PROFILE_PATH),
+        };
+        match achievements {
+            Ok(achievements) => { app.insert_resource(achievements); }
+            Err(e) => eprintln!("Failed to load player profile: {}", e),
+        }
+        let settings = profile.map(|p| p.settings).unwrap_or_default();
+        let screen = settings.open_screen.as_deref();
+
         app.init_state::<AppState>()
+           .insert_resource(ProfilerOverlay { visible: settings.show_profiler })
+           .insert_resource(SimSpeedHud { visible: settings.show_speed_hud })
+           .insert_resource(AchievementsScreen { visible: screen == Some("achievements") })
+           .insert_resource(WorkersScreen { visible: screen == Some("workers") })
+           .insert_resource(PauseSettingsScreen { visible: screen == Some("pause_settings") })
+           .insert_resource(PostMortemScreen::default())
+           .insert_resource(SchedulerScreen::default())
+           .insert_resource(settings.pause_policies.clone())
            .add_systems(Startup, setup_ui)
            .add_systems(Update, (
                update_ui_text,
                handle_keyboard_input,
+               auto_pause_system,
+               postmortem_system,
+               (sync_settings_system, persist_settings_system).chain().after(handle_keyboard_input),
            ));
     }
 }
@@ -40,12 +110,25 @@ fn update_ui_text(
     app_state: Res<State<AppState>>,
     colony: Res<colony_core::Colony>,
     clock: Res<colony_core::SimClock>,
+    (profiler, speed_hud, achievements_screen, workers_screen): (Res<ProfilerOverlay>, Res<SimSpeedHud>, Res<AchievementsScreen>, Res<WorkersScreen>),
+    sim_profiler: Option<Res<colony_core::SimProfiler>>,
+    local_profile: Option<Res<colony_core::LocalProfile>>,
+    (pause_screen, pause_policies, pause_monitor): (Res<PauseSettingsScreen>, Option<Res<colony_core::PausePolicies>>, Option<Res<colony_core::PauseMonitor>>),
+    memory: Option<Res<colony_core::MemoryAccounting>>,
+    black_swans: Option<Res<colony_core::BlackSwanIndex>>,
+    (achievements, registry): (Option<Res<colony_core::Achievements>>, Option<Res<colony_core::AchievementRegistry>>),
+    clock_drift: Option<Res<colony_core::ClockDrift>>,
+    workers: Query<(&colony_core::Worker, &colony_core::WorkerStateComp, Option<&colony_core::Certifications>)>,
+    cert_board: Option<Res<colony_core::CertificationBoard>>,
+    (postmortem_screen, black_box): (Res<PostMortemScreen>, Option<Res<colony_core::BlackBox>>),
+    (scheduler_screen, active_scheduler, schedulers): (Res<SchedulerScreen>, Res<colony_core::ActiveScheduler>, Option<Res<colony_core::SchedulerRegistry>>),
 ) {
     for mut text in text_query.iter_mut() {
         match app_state.get() {
             AppState::MainMenu => {
                 text.0 = format!(
-                    "Compute Colony - Main Menu\n\nPress SPACE to start game\n\nColony Status:\nPower: {:.0}/{:.0} kW\nBandwidth: {:.1}%\nCorruption: {:.1}%\nTime: {}\n\nControls:\nSPACE - Start Game\nS - Stop Simulators\nM - Maintenance\n1 - FCFS Scheduler\n2 - SJF Scheduler\n3 - EDF Scheduler",
+                    "Compute Colony - Main Menu\n\nProfile: {}\nPress SPACE to start game\n\nColony Status:\nPower: {:.0}/{:.0} kW\nBandwidth: {:.1}%\nCorruption: {:.1}%\nTime: {}\n\nControls:\nSPACE - Start Game\nS - Stop Simulators\nM - Maintenance\n1 - FCFS Scheduler\n2 - SJF Scheduler\n3 - EDF Scheduler\nTab - Switch Profile\nF10 - Reset Settings",
+                    local_profile.as_ref().map(|p| p.name.as_str()).unwrap_or("none"),
                     colony.meters.power_draw_kw,
                     colony.power_cap_kw,
                     colony.meters.bandwidth_util * 100.0,
@@ -55,7 +138,7 @@ fn update_ui_text(
             }
             AppState::InGame => {
                 text.0 = format!(
-                    "Compute Colony - Game Running\n\nColony Status:\nPower: {:.0}/{:.0} kW\nBandwidth: {:.1}%\nCorruption: {:.1}%\nTime: {}\n\nControls:\nP - Pause Game\nS - Stop Simulators\nM - Maintenance\n1 - FCFS Scheduler\n2 - SJF Scheduler\n3 - EDF Scheduler\n\nSimulator Controls:\nU - Start/Stop UDP\nH - Start/Stop HTTP",
+                    "Compute Colony - Game Running\n\nColony Status:\nPower: {:.0}/{:.0} kW\nBandwidth: {:.1}%\nCorruption: {:.1}%\nTime: {}\n\nControls:\nP - Pause Game\nS - Stop Simulators\nM - Maintenance\n1 - FCFS Scheduler\n2 - SJF Scheduler\n3 - EDF Scheduler\n\nSimulator Controls:\nU - Start/Stop UDP\nH - Start/Stop HTTP\n\nT - Toggle Auto-Throttle\nF2 - Toggle Speed HUD\nF3 - Toggle Profiler\nF4 - Achievements\nF5 - Workers\nF6 - Pause Settings\nF7 - Post-Mortem\nF8 - Scheduler",
                     colony.meters.power_draw_kw,
                     colony.power_cap_kw,
                     colony.meters.bandwidth_util * 100.0,
@@ -72,8 +155,393 @@ fn update_ui_text(
                     colony.corruption_field * 100.0,
                     clock.now
                 );
+                if let Some(event) = pause_monitor.as_ref().and_then(|m| m.last()) {
+                    text.0.push_str(&format!("\n\nAuto-paused at tick {}: {}", event.tick, event.reason));
+                }
+            }
+        }
+
+        // A pending event choice takes over the screen until it is answered
+        if let Some(pending) = black_swans.as_ref().and_then(|b| b.pending.first()) {
+            let now_tick = clock.now.timestamp_millis() as u64 / 16;
+            text.0 = choice_prompt_text(pending, now_tick);
+            continue;
+        }
+
+        if postmortem_screen.visible {
+            if let Some(postmortem) = black_box.as_ref().and_then(|b| b.last.as_ref()) {
+                text.0 = postmortem_text(postmortem, postmortem_screen.step);
+                continue;
+            }
+        }
+
+        if scheduler_screen.visible {
+            let names = schedulers.as_ref().map(|r| r.names()).unwrap_or_default();
+            text.0 = scheduler_text(&names, &active_scheduler.name());
+            continue;
+        }
+
+        if pause_screen.visible {
+            if let Some(ref policies) = pause_policies {
+                text.0 = pause_settings_text(policies);
+                continue;
+            }
+        }
+
+        if achievements_screen.visible {
+            if let (Some(achievements), Some(registry)) = (achievements.as_ref(), registry.as_ref()) {
+                text.0 = achievements_text(achievements, registry);
+                continue;
+            }
+        }
+
+        if workers_screen.visible {
+            let now_tick = clock.now.timestamp_millis() as u64 / 16;
+            let mut rows: Vec<_> = workers.iter().collect();
+            rows.sort_by_key(|(w, ..)| w.id);
+            text.0 = workers_text(&rows, cert_board.as_deref(), now_tick);
+            continue;
+        }
+
+        if speed_hud.visible {
+            if let Some(ref sim_profiler) = sim_profiler {
+                text.0.push_str(&speed_hud_text(sim_profiler));
+            }
+        }
+
+        if profiler.visible {
+            if let Some(ref memory) = memory {
+                text.0.push_str(&profiler_text(memory));
+            }
+            if let Some(ref drift) = clock_drift {
+                text.0.push_str(&format!(
+                    "\n\nClock drift: max {:.1} ms, mean {:.1} ms\nMisjudged deadlines: {} of {}",
+                    drift.max_abs_offset_ms,
+                    drift.mean_abs_offset_ms,
+                    drift.misjudged(),
+                    drift.deadline_checks
+                ));
+            }
+        }
+    }
+}
+
+fn pause_settings_text(policies: &colony_core::PausePolicies) -> String {
+    let on_off = |on: bool| if on { "ON" } else { "off" };
+    format!(
+        "Pause Settings - pause the game automatically when:\n\n1 - A Black Swan fires: {}\n2 - SLA drops below: {}\n3 - A worker is quarantined: {}\n\nF6 - Close",
+        on_off(policies.on_black_swan),
+        policies.sla_below_pct.map(|pct| format!("{:.0}%", pct)).unwrap_or_else(|| "off".to_string()),
+        on_off(policies.on_worker_quarantine)
+    )
+}
+
+fn scheduler_text(names: &[String], active: &str) -> String {
+    let mut text = "Scheduler - the policy dispatch picks jobs with\n".to_string();
+    for (i, name) in names.iter().take(9).enumerate() {
+        let marker = if name == active { "*" } else { " " };
+        text.push_str(&format!("\n{} {} - {}", marker, i + 1, name));
+    }
+    text.push_str("\n\nF8 - Close");
+    text
+}
+
+fn postmortem_text(postmortem: &colony_core::PostMortem, step: usize) -> String {
+    let timeline = postmortem.timeline();
+    let mut text = format!(
+        "Post-Mortem - lost at tick {}: {}\n{} events in the last {:.0} min\n",
+        postmortem.doom_tick,
+        postmortem.reason,
+        timeline.len(),
+        postmortem.doom_tick.saturating_sub(postmortem.window_start_tick) as f32 / 3750.0
+    );
+    for (i, entry) in timeline.iter().enumerate() {
+        let marker = if i == step { ">" } else { " " };
+        text.push_str(&format!("\n{} T-{:>6.1}s  {}", marker, entry.secs_before_loss, entry.summary));
+    }
+    match timeline.get(step).and_then(|entry| entry.frame.as_ref()) {
+        Some(frame) => text.push_str(&format!(
+            "\n\nJust before: SLA {:.1}%, power {:.0} kW, bandwidth {:.1}%, corruption {:.1}%\nQueue {}, faults {}, sticky workers {}, active swans: {}",
+            frame.kpis.deadline_hit_rate,
+            frame.kpis.power_draw_kw,
+            frame.kpis.bandwidth_util * 100.0,
+            frame.kpis.corruption_field * 100.0,
+            frame.queue_depth,
+            frame.total_faults,
+            frame.sticky_workers,
+            if frame.active_swans.is_empty() { "none".to_string() } else { frame.active_swans.join(", ") }
+        )),
+        None => text.push_str("\n\nNo vitals were sampled before this event"),
+    }
+    text.push_str("\n\nLeft/Right - Step   F7 - Close");
+    text
+}
+
+fn profiler_text(memory: &colony_core::MemoryAccounting) -> String {
+    let mb = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+    let mut out = format!(
+        "\n\nProfiler - Memory (total {:.2} MB, peak {:.2} MB)",
+        mb(memory.total_bytes),
+        mb(memory.peak_total_bytes)
+    );
+    for usage in &memory.usage {
+        out.push_str(&format!(
+            "\n{}{}: {:.2} / {:.0} MB",
+            if usage.near_cap { "! " } else { "  " },
+            usage.category.name(),
+            mb(usage.bytes),
+            mb(usage.cap_bytes)
+        ));
+    }
+    out
+}
+
+fn speed_hud_text(profiler: &colony_core::SimProfiler) -> String {
+    let frame_ms = profiler.avg_frame_ms();
+    let sim_ms = profiler.avg_sim_ms();
+    let verdict = match profiler.bottleneck() {
+        colony_core::Bottleneck::OnTarget => "on target",
+        colony_core::Bottleneck::Sim => "SIM-BOUND",
+        colony_core::Bottleneck::Render => "RENDER-BOUND",
+    };
+    let e = &profiler.entities;
+    format!(
+        "\n\nSim Speed - {:.1} / {:.1} ticks/s ({})\nFrame {:.2} ms = sim {:.2} ms ({:.0}%) + other {:.2} ms\nEntities: {} total, {} workers, {} yards, {} queued jobs",
+        profiler.achieved_tps(),
+        profiler.target_tps,
+        verdict,
+        frame_ms,
+        sim_ms,
+        profiler.sim_share() * 100.0,
+        (frame_ms - sim_ms).max(0.0),
+        e.total,
+        e.workers,
+        e.yards,
+        e.queued_jobs
+    )
+}
+
+fn achievements_text(achievements: &colony_core::Achievements, registry: &colony_core::AchievementRegistry) -> String {
+    let mut out = format!(
+        "Achievements ({}/{})\n",
+        achievements.profile.unlocked.len(),
+        registry.defs.len()
+    );
+    for def in &registry.defs {
+        let unlocked = achievements.profile.is_unlocked(&def.id);
+        let description = if def.hidden && !unlocked { "???" } else { def.description.as_str() };
+        out.push_str(&format!(
+            "\n[{}] {} - {}",
+            if unlocked { "x" } else { " " },
+            def.name,
+            description
+        ));
+    }
+    out.push_str("\n\nF4 - Close");
+    out
+}
+
+fn workers_text(
+    workers: &[(&colony_core::Worker, &colony_core::WorkerStateComp, Option<&colony_core::Certifications>)],
+    board: Option<&colony_core::CertificationBoard>,
+    now_tick: u64,
+) -> String {
+    let mut out = format!("Workers ({})\n", workers.len());
+    for (worker, state, certs) in workers {
+        let held = certs
+            .map(|c| c.held.iter().map(|k| k.label()).collect::<Vec<_>>().join(", "))
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| "none".to_string());
+        out.push_str(&format!("\n#{} {:?} {:?} - certified: {}", worker.id, worker.class, state.0, held));
+        if let Some(run) = board.and_then(|b| b.training.iter().find(|t| t.worker_id == worker.id)) {
+            out.push_str(&format!(" (training {}, {} ticks left)", run.cert.label(), run.completes_tick.saturating_sub(now_tick)));
+        }
+    }
+    if let Some(board) = board {
+        out.push_str(&format!(
+            "\n\nUncertified runs: {} ({:.0}% speed, {:.1}x faults)",
+            board.uncertified_runs,
+            board.tunables.uncertified_speed_mult * 100.0,
+            board.tunables.uncertified_fault_mult
+        ));
+    }
+    out.push_str("\n\nF5 - Close");
+    out
+}
+
+fn choice_prompt_text(pending: &colony_core::PendingChoice, now_tick: u64) -> String {
+    let mut out = format!("!! {} [{:?}] !!\n", pending.name, pending.severity);
+    if let Some(ref narrative) = pending.narrative {
+        out.push_str(&format!("\n{}\n", narrative));
+    }
+    out.push('\n');
+    for (i, choice) in pending.choices.iter().enumerate() {
+        let default = if choice.id == pending.default_choice { " (default)" } else { "" };
+        out.push_str(&format!("\n{} - {}{}", i + 1, choice.label, default));
+    }
+    out.push_str(&format!("\n\nDefault taken in {} ticks", pending.ticks_remaining(now_tick)));
+    out
+}
+
+/// Key names as written in `keybindings.toml`
+fn key_code(name: &str) -> Option<KeyCode> {
+    let key = match name {
+        "Space" => KeyCode::Space,
+        "Tab" => KeyCode::Tab,
+        "Enter" => KeyCode::Enter,
+        "Escape" => KeyCode::Escape,
+        "Backspace" => KeyCode::Backspace,
+        "F1" => KeyCode::F1,
+        "F2" => KeyCode::F2,
+        "F3" => KeyCode::F3,
+        "F4" => KeyCode::F4,
+        "F5" => KeyCode::F5,
+        "F6" => KeyCode::F6,
+        "F7" => KeyCode::F7,
+        "F8" => KeyCode::F8,
+        "F9" => KeyCode::F9,
+        "F10" => KeyCode::F10,
+        "F11" => KeyCode::F11,
+        "F12" => KeyCode::F12,
+        _ => {
+            let mut chars = name.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                return None;
+            };
+            match c.to_ascii_uppercase() {
+                'A' => KeyCode::KeyA, 'B' => KeyCode::KeyB, 'C' => KeyCode::KeyC, 'D' => KeyCode::KeyD,
+                'E' => KeyCode::KeyE, 'F' => KeyCode::KeyF, 'G' => KeyCode::KeyG, 'H' => KeyCode::KeyH,
+                'I' => KeyCode::KeyI, 'J' => KeyCode::KeyJ, 'K' => KeyCode::KeyK, 'L' => KeyCode::KeyL,
+                'M' => KeyCode::KeyM, 'N' => KeyCode::KeyN, 'O' => KeyCode::KeyO, 'P' => KeyCode::KeyP,
+                'Q' => KeyCode::KeyQ, 'R' => KeyCode::KeyR, 'S' => KeyCode::KeyS, 'T' => KeyCode::KeyT,
+                'U' => KeyCode::KeyU, 'V' => KeyCode::KeyV, 'W' => KeyCode::KeyW, 'X' => KeyCode::KeyX,
+                'Y' => KeyCode::KeyY, 'Z' => KeyCode::KeyZ,
+                '0' => KeyCode::Digit0, '1' => KeyCode::Digit1, '2' => KeyCode::Digit2, '3' => KeyCode::Digit3,
+                '4' => KeyCode::Digit4, '5' => KeyCode::Digit5, '6' => KeyCode::Digit6, '7' => KeyCode::Digit7,
+                '8' => KeyCode::Digit8, '9' => KeyCode::Digit9,
+                _ => return None,
+            }
+        }
+    };
+    Some(key)
+}
+
+/// Show the overlays, screen and pause policies a profile's settings ask for
+fn apply_ui_settings(commands: &mut Commands, settings: &colony_core::PlayerSettings) {
+    let screen = settings.open_screen.as_deref();
+    commands.insert_resource(ProfilerOverlay { visible: settings.show_profiler });
+    commands.insert_resource(SimSpeedHud { visible: settings.show_speed_hud });
+    commands.insert_resource(AchievementsScreen { visible: screen == Some("achievements") });
+    commands.insert_resource(WorkersScreen { visible: screen == Some("workers") });
+    commands.insert_resource(PauseSettingsScreen { visible: screen == Some("pause_settings") });
+    commands.insert_resource(settings.pause_policies.clone());
+}
+
+/// Mirror UI state into the profile's settings so `persist_settings_system` picks it up
+fn sync_settings_system(
+    local_profile: Option<ResMut<colony_core::LocalProfile>>,
+    (profiler, speed_hud, achievements_screen, workers_screen, pause_screen): (Res<ProfilerOverlay>, Res<SimSpeedHud>, Res<AchievementsScreen>, Res<WorkersScreen>, Res<PauseSettingsScreen>),
+    pause_policies: Option<Res<colony_core::PausePolicies>>,
+    mut resized: EventReader<bevy::window::WindowResized>,
+) {
+    let Some(mut profile) = local_profile else {
+        return;
+    };
+    let mut next = profile.settings.clone();
+    next.show_profiler = profiler.visible;
+    next.show_speed_hud = speed_hud.visible;
+    next.open_screen = if pause_screen.visible {
+        Some("pause_settings".to_string())
+    } else if achievements_screen.visible {
+        Some("achievements".to_string())
+    } else if workers_screen.visible {
+        Some("workers".to_string())
+    } else {
+        None
+    };
+    if let Some(policies) = pause_policies {
+        next.pause_policies = policies.clone();
+    }
+    if let Some(size) = resized.read().last() {
+        next.window = colony_core::WindowSettings { width: size.width, height: size.height };
+    }
+    // Only touch the resource on a real change so change detection stays meaningful
+    if next != profile.settings {
+        profile.settings = next;
+    }
+}
+
+/// Write settings.toml once edits settle, so dragging the window doesn't rewrite it every frame
+fn persist_settings_system(
+    local_profile: Option<Res<colony_core::LocalProfile>>,
+    mut dirty_since: Local<Option<std::time::Instant>>,
+) {
+    let Some(profile) = local_profile else {
+        return;
+    };
+    if profile.is_changed() && !profile.is_added() {
+        dirty_since.get_or_insert_with(std::time::Instant::now);
+    }
+    if dirty_since.is_some_and(|t| t.elapsed() >= SETTINGS_SAVE_DELAY) {
+        if let Err(e) = profile.save_settings() {
+            eprintln!("Failed to save profile settings: {}", e);
+        }
+        *dirty_since = None;
+    }
+}
+
+/// Drop into the pause screen when a pause policy trips mid-game
+fn auto_pause_system(
+    app_state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    monitor: Option<ResMut<colony_core::PauseMonitor>>,
+) {
+    let Some(mut monitor) = monitor else {
+        return;
+    };
+    if let Some(event) = monitor.take_unacknowledged() {
+        if *app_state.get() == AppState::InGame {
+            println!("Auto-pausing: {}", event.reason);
+            next_state.set(AppState::Paused);
+        }
+    }
+}
+
+/// Archive the black box's bundle when the colony is lost and open the post-mortem on it
+fn postmortem_system(
+    black_box: Option<ResMut<colony_core::BlackBox>>,
+    protection: Option<Res<colony_core::SaveProtection>>,
+    mut screen: ResMut<PostMortemScreen>,
+) {
+    let Some(postmortem) = black_box.and_then(|mut b| b.take_unarchived()) else {
+        return;
+    };
+    let protection = protection.map(|p| p.clone()).unwrap_or_default();
+    if let Err(e) = postmortem.save_to(std::path::Path::new(colony_core::DEFAULT_POSTMORTEM_DIR), &protection) {
+        eprintln!("Failed to archive post-mortem: {}", e);
+    }
+    screen.visible = true;
+    screen.step = 0;
+}
+
+/// Swap in the next local profile along with its achievements
+fn switch_profile(commands: &mut Commands, current: &str) {
+    let store = colony_core::ProfileStore::open_default();
+    let Some(next) = store.next_after(current).filter(|n| n != current) else {
+        println!("No other profiles; start with --profile NAME to create one");
+        return;
+    };
+    match store.load(&next) {
+        Ok(profile) => {
+            match profile.achievements() {
+                Ok(achievements) => commands.insert_resource(achievements),
+                Err(e) => eprintln!("Failed to load achievements for '{}': {}", next, e),
             }
+            apply_ui_settings(commands, &profile.settings);
+            println!("Switched to profile '{}'", next);
+            commands.insert_resource(profile);
         }
+        Err(e) => eprintln!("Failed to load profile '{}': {}", next, e),
     }
 }
 
@@ -81,18 +549,138 @@ fn handle_keyboard_input(
     keyboard: Res<ButtonInput<KeyCode>>,
     mut next_state: ResMut<NextState<AppState>>,
     app_state: Res<State<AppState>>,
+    mut profiler: ResMut<ProfilerOverlay>,
+    mut speed_hud: ResMut<SimSpeedHud>,
+    mut achievements_screen: ResMut<AchievementsScreen>,
+    mut workers_screen: ResMut<WorkersScreen>,
+    mut auto_throttle: ResMut<colony_core::AutoThrottle>,
+    black_swans: Option<ResMut<colony_core::BlackSwanIndex>>,
+    mut local_profile: Option<ResMut<colony_core::LocalProfile>>,
+    mut commands: Commands,
+    (mut pause_screen, pause_policies, session): (ResMut<PauseSettingsScreen>, Option<ResMut<colony_core::PausePolicies>>, Option<ResMut<colony_core::SessionCtl>>),
+    (mut postmortem_screen, black_box): (ResMut<PostMortemScreen>, Option<Res<colony_core::BlackBox>>),
+    (mut scheduler_screen, mut active_scheduler, schedulers): (ResMut<SchedulerScreen>, ResMut<colony_core::ActiveScheduler>, Option<Res<colony_core::SchedulerRegistry>>),
 ) {
+    // While an event choice is pending, number keys answer it and nothing else runs
+    if let Some(mut black_swans) = black_swans {
+        if let Some(pending) = black_swans.pending.first().cloned() {
+            let keys = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4];
+            for (key, choice) in keys.iter().zip(pending.choices.iter()) {
+                if keyboard.just_pressed(*key) {
+                    if black_swans.choose(&pending.swan_id, &choice.id).is_ok() {
+                        println!("Event {}: chose '{}'", pending.swan_id, choice.label);
+                    }
+                }
+            }
+            return;
+        }
+    }
+
+    let bindings = local_profile.as_ref().map(|p| p.keybindings.clone()).unwrap_or_default();
+    let pressed = |action: &str| {
+        bindings.key_for(action).and_then(|k| key_code(&k)).is_some_and(|k| keyboard.just_pressed(k))
+    };
+
+    if pressed("toggle_postmortem") {
+        postmortem_screen.visible = !postmortem_screen.visible;
+    }
+    // The post-mortem takes the arrow keys while it is open
+    if postmortem_screen.visible {
+        let steps = black_box.as_ref().and_then(|b| b.last.as_ref()).map_or(0, |p| p.journal.len());
+        if keyboard.just_pressed(KeyCode::ArrowRight) && postmortem_screen.step + 1 < steps {
+            postmortem_screen.step += 1;
+        }
+        if keyboard.just_pressed(KeyCode::ArrowLeft) {
+            postmortem_screen.step = postmortem_screen.step.saturating_sub(1);
+        }
+        return;
+    }
+
+    if pressed("toggle_scheduler") {
+        scheduler_screen.visible = !scheduler_screen.visible;
+    }
+    // The scheduler screen takes the number keys while it is open
+    if scheduler_screen.visible {
+        let registry = schedulers.as_deref().cloned().unwrap_or_default();
+        let keys = [
+            KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4, KeyCode::Digit5,
+            KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+        ];
+        for (key, name) in keys.iter().zip(registry.names()) {
+            if keyboard.just_pressed(*key) {
+                match active_scheduler.select(&name, &registry) {
+                    Ok(()) => println!("Switched to the {} scheduler", name),
+                    Err(e) => eprintln!("Failed to switch scheduler: {}", e),
+                }
+            }
+        }
+        return;
+    }
+
+    if pressed("toggle_pause_settings") {
+        pause_screen.visible = !pause_screen.visible;
+    }
+    // The pause settings screen takes the number keys while it is open
+    if pause_screen.visible {
+        if let Some(mut policies) = pause_policies {
+            if keyboard.just_pressed(KeyCode::Digit1) {
+                policies.on_black_swan = !policies.on_black_swan;
+            }
+            if keyboard.just_pressed(KeyCode::Digit2) {
+                let i = SLA_PAUSE_STEPS.iter().position(|s| *s == policies.sla_below_pct).unwrap_or(0);
+                policies.sla_below_pct = SLA_PAUSE_STEPS[(i + 1) % SLA_PAUSE_STEPS.len()];
+            }
+            if keyboard.just_pressed(KeyCode::Digit3) {
+                policies.on_worker_quarantine = !policies.on_worker_quarantine;
+            }
+        }
+        return;
+    }
+
+    if pressed("toggle_speed_hud") {
+        speed_hud.visible = !speed_hud.visible;
+    }
+    if pressed("toggle_profiler") {
+        profiler.visible = !profiler.visible;
+    }
+    if pressed("toggle_achievements") {
+        achievements_screen.visible = !achievements_screen.visible;
+    }
+    if pressed("toggle_workers") {
+        workers_screen.visible = !workers_screen.visible;
+    }
+    if pressed("toggle_auto_throttle") {
+        let enabled = !auto_throttle.enabled;
+        auto_throttle.set_enabled(enabled);
+        println!("Auto-throttle {}", if enabled { "enabled" } else { "disabled" });
+    }
+
     match app_state.get() {
         AppState::MainMenu => {
-            if keyboard.just_pressed(KeyCode::Space) {
+            if pressed("start_game") {
                 println!("Starting game...");
                 next_state.set(AppState::InGame);
+            } else if pressed("switch_profile") {
+                if let Some(current) = local_profile.as_ref().map(|p| p.name.clone()) {
+                    switch_profile(&mut commands, &current);
+                }
+            } else if pressed("reset_settings") {
+                if let Some(profile) = local_profile.as_mut() {
+                    match profile.reset_settings() {
+                        Ok(()) => println!("Settings for '{}' reset to defaults", profile.name),
+                        Err(e) => eprintln!("Failed to reset settings: {}", e),
+                    }
+                    apply_ui_settings(&mut commands, &profile.settings);
+                }
             }
         }
         AppState::InGame => {
-            if keyboard.just_pressed(KeyCode::KeyP) {
+            if pressed("pause") {
                 println!("Pausing game...");
                 next_state.set(AppState::Paused);
+                if let Some(mut session) = session {
+                    session.pause();
+                }
             } else if keyboard.just_pressed(KeyCode::KeyS) {
                 println!("Stopping simulators...");
                 // TODO: Send stop simulator events
@@ -101,13 +689,13 @@ fn handle_keyboard_input(
                 // TODO: Send maintenance event
             } else if keyboard.just_pressed(KeyCode::Digit1) {
                 println!("Switching to FCFS scheduler...");
-                // TODO: Send scheduler switch event
+                *active_scheduler = colony_core::ActiveScheduler { policy: colony_core::SchedPolicy::Fcfs, custom: None };
             } else if keyboard.just_pressed(KeyCode::Digit2) {
                 println!("Switching to SJF scheduler...");
-                // TODO: Send scheduler switch event
+                *active_scheduler = colony_core::ActiveScheduler { policy: colony_core::SchedPolicy::Sjf, custom: None };
             } else if keyboard.just_pressed(KeyCode::Digit3) {
                 println!("Switching to EDF scheduler...");
-                // TODO: Send scheduler switch event
+                *active_scheduler = colony_core::ActiveScheduler { policy: colony_core::SchedPolicy::Edf, custom: None };
             } else if keyboard.just_pressed(KeyCode::KeyU) {
                 println!("Toggling UDP simulator...");
                 // TODO: Send UDP simulator toggle event
@@ -117,9 +705,12 @@ fn handle_keyboard_input(
             }
         }
         AppState::Paused => {
-            if keyboard.just_pressed(KeyCode::KeyR) {
+            if pressed("resume") {
                 println!("Resuming game...");
                 next_state.set(AppState::InGame);
+                if let Some(mut session) = session {
+                    session.resume();
+                }
             } else if keyboard.just_pressed(KeyCode::KeyS) {
                 println!("Stopping simulators...");
                 // TODO: Send stop simulator events
@@ -128,13 +719,13 @@ fn handle_keyboard_input(
                 // TODO: Send maintenance event
             } else if keyboard.just_pressed(KeyCode::Digit1) {
                 println!("Switching to FCFS scheduler...");
-                // TODO: Send scheduler switch event
+                *active_scheduler = colony_core::ActiveScheduler { policy: colony_core::SchedPolicy::Fcfs, custom: None };
             } else if keyboard.just_pressed(KeyCode::Digit2) {
                 println!("Switching to SJF scheduler...");
-                // TODO: Send scheduler switch event
+                *active_scheduler = colony_core::ActiveScheduler { policy: colony_core::SchedPolicy::Sjf, custom: None };
             } else if keyboard.just_pressed(KeyCode::Digit3) {
                 println!("Switching to EDF scheduler...");
-                // TODO: Send scheduler switch event
+                *active_scheduler = colony_core::ActiveScheduler { policy: colony_core::SchedPolicy::Edf, custom: None };
             }
         }
     }
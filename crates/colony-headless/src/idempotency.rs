@@ -0,0 +1,283 @@
+use crate::limits::ApiError;
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+/// Set on a response that was replayed from the cache rather than produced again
+pub const IDEMPOTENT_REPLAYED: &str = "idempotent-replayed";
+const MAX_KEY_LEN: usize = 255;
+
+/// How long results of keyed requests are kept for replay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IdempotencyConfig {
+    pub ttl_secs: u64, // 0 turns replay off
+    pub max_entries: usize,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 10 * 60,
+            max_entries: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    status: StatusCode,
+    content_type: Option<HeaderValue>,
+    body: Bytes,
+}
+
+impl CachedResponse {
+    fn to_response(&self) -> Response {
+        let mut response = (self.status, self.body.clone()).into_response();
+        if let Some(content_type) = &self.content_type {
+            response.headers_mut().insert(header::CONTENT_TYPE, content_type.clone());
+        }
+        response.headers_mut().insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+        response
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    fingerprint: u64, // method, path and body of the request that claimed the key
+    stored_at: Instant,
+    response: Option<CachedResponse>, // None while that request is still running
+}
+
+/// What an incoming keyed request should get
+#[derive(Debug)]
+pub enum Lookup {
+    Fresh,            // first use of the key: run the request
+    Replay(Response), // the earlier result
+    InFlight,         // the first request with this key hasn't finished
+    Mismatch,         // the key was used for a different request
+}
+
+/// Results of POSTs, PUTs, PATCHes and DELETEs sent with an `Idempotency-Key` header, so a client
+/// retrying over a flaky network gets the original answer instead of applying the command twice
+#[derive(Debug)]
+pub struct IdempotencyCache {
+    config: IdempotencyConfig,
+    max_request_bytes: usize,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl IdempotencyCache {
+    pub fn new(config: IdempotencyConfig, max_request_bytes: usize) -> Self {
+        Self { config, max_request_bytes, entries: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.ttl_secs > 0 && self.config.max_entries > 0
+    }
+
+    /// Claim `key` for a request, or find what the earlier request with it got
+    pub fn begin(&self, key: &str, fingerprint: u64, now: Instant) -> Lookup {
+        let ttl = Duration::from_secs(self.config.ttl_secs);
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, e| e.response.is_none() || now.saturating_duration_since(e.stored_at) < ttl);
+        match entries.get(key) {
+            Some(entry) if entry.fingerprint != fingerprint => return Lookup::Mismatch,
+            Some(Entry { response: Some(cached), .. }) => return Lookup::Replay(cached.to_response()),
+            Some(_) => return Lookup::InFlight,
+            None => {}
+        }
+        if entries.len() >= self.config.max_entries {
+            let oldest = entries.iter()
+                .filter(|(_, e)| e.response.is_some())
+                .min_by_key(|(_, e)| e.stored_at)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key.to_string(), Entry { fingerprint, stored_at: now, response: None });
+        Lookup::Fresh
+    }
+
+    fn finish(&self, key: &str, response: Option<CachedResponse>, now: Instant) {
+        let mut entries = self.entries.lock().unwrap();
+        match response {
+            Some(response) => {
+                if let Some(entry) = entries.get_mut(key) {
+                    entry.stored_at = now;
+                    entry.response = Some(response);
+                }
+            }
+            None => {
+                entries.remove(key);
+            }
+        }
+    }
+}
+
+/// Releases a claimed key if the request is dropped before it finishes, e.g. the client hung up
+struct Claim<'a> {
+    cache: &'a IdempotencyCache,
+    key: String,
+    done: bool,
+}
+
+impl Claim<'_> {
+    fn finish(mut self, response: Option<CachedResponse>) {
+        self.done = true;
+        self.cache.finish(&self.key, response, Instant::now());
+    }
+}
+
+impl Drop for Claim<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.cache.finish(&self.key, None, Instant::now());
+        }
+    }
+}
+
+pub fn fingerprint(method: &Method, path_and_query: &str, body: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    method.as_str().hash(&mut hasher);
+    path_and_query.hash(&mut hasher);
+    body.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Middleware replaying the stored result of a keyed write. Server errors aren't stored, so the
+/// client can retry those with the same key.
+pub async fn idempotent(State(cache): State<Arc<IdempotencyCache>>, req: Request, next: Next) -> Response {
+    if !cache.enabled() || matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(req).await;
+    }
+    let key = match req.headers().get(IDEMPOTENCY_KEY).map(|k| k.to_str()) {
+        None => return next.run(req).await,
+        Some(Ok(key)) if !key.is_empty() && key.len() <= MAX_KEY_LEN => key.to_string(),
+        Some(_) => {
+            return ApiError::new(StatusCode::BAD_REQUEST, format!("Idempotency-Key must be 1-{} visible ASCII characters", MAX_KEY_LEN))
+                .into_response();
+        }
+    };
+
+    let (parts, body) = req.into_parts();
+    let body = match to_bytes(body, cache.max_request_bytes).await {
+        Ok(body) => body,
+        Err(_) => return ApiError::new(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large").into_response(),
+    };
+    let path = parts.uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    match cache.begin(&key, fingerprint(&parts.method, path, &body), Instant::now()) {
+        Lookup::Fresh => {}
+        Lookup::Replay(response) => return response,
+        Lookup::InFlight => {
+            return ApiError::new(StatusCode::CONFLICT, format!("A request with Idempotency-Key {} is still being processed", key))
+                .into_response();
+        }
+        Lookup::Mismatch => {
+            return ApiError::unprocessable(format!("Idempotency-Key {} was already used for a different request", key))
+                .into_response();
+        }
+    }
+
+    let claim = Claim { cache: &cache, key, done: false };
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if response.status().is_server_error() {
+        claim.finish(None);
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            claim.finish(None);
+            return ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to read response: {}", e)).into_response();
+        }
+    };
+    claim.finish(Some(CachedResponse {
+        status: parts.status,
+        content_type: parts.headers.get(header::CONTENT_TYPE).cloned(),
+        body: body.clone(),
+    }));
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Router};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use tower::ServiceExt;
+
+    fn app(cache: Arc<IdempotencyCache>, applied: Arc<AtomicU64>) -> Router {
+        Router::new()
+            .route("/job", post(move |body: String| async move {
+                let n = applied.fetch_add(1, Ordering::SeqCst) + 1;
+                format!("job {} from {}", n, body)
+            }))
+            .layer(axum::middleware::from_fn_with_state(cache, idempotent))
+    }
+
+    fn post_job(key: Option<&str>, body: &str) -> Request {
+        let mut req = axum::http::Request::post("/job");
+        if let Some(key) = key {
+            req = req.header(IDEMPOTENCY_KEY, key);
+        }
+        req.body(Body::from(body.to_string())).unwrap()
+    }
+
+    async fn text(response: Response) -> String {
+        String::from_utf8(to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_retried_post_is_applied_once() {
+        let cache = Arc::new(IdempotencyCache::new(IdempotencyConfig::default(), 1024));
+        let applied = Arc::new(AtomicU64::new(0));
+        let app = app(cache.clone(), applied.clone());
+
+        let first = app.clone().oneshot(post_job(Some("k1"), "a")).await.unwrap();
+        assert!(first.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        assert_eq!(text(first).await, "job 1 from a");
+        let replay = app.clone().oneshot(post_job(Some("k1"), "a")).await.unwrap();
+        assert_eq!(replay.headers()[IDEMPOTENT_REPLAYED], "true");
+        assert_eq!(text(replay).await, "job 1 from a");
+        assert_eq!(applied.load(Ordering::SeqCst), 1);
+
+        // Same key, different body
+        let reused = app.clone().oneshot(post_job(Some("k1"), "b")).await.unwrap();
+        assert_eq!(reused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        // Unkeyed requests are applied every time
+        app.clone().oneshot(post_job(None, "a")).await.unwrap();
+        app.clone().oneshot(post_job(None, "a")).await.unwrap();
+        assert_eq!(applied.load(Ordering::SeqCst), 3);
+        assert_eq!(cache.entries.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_entries_expire_and_in_flight_keys_conflict() {
+        let cache = IdempotencyCache::new(IdempotencyConfig { ttl_secs: 60, max_entries: 2 }, 1024);
+        let start = Instant::now();
+        let fp = fingerprint(&Method::POST, "/job", b"a");
+        assert!(matches!(cache.begin("k1", fp, start), Lookup::Fresh));
+        assert!(matches!(cache.begin("k1", fp, start), Lookup::InFlight));
+
+        cache.finish("k1", Some(CachedResponse { status: StatusCode::OK, content_type: None, body: Bytes::new() }), start);
+        assert!(matches!(cache.begin("k1", fp, start + Duration::from_secs(59)), Lookup::Replay(_)));
+        assert!(matches!(cache.begin("k1", fp, start + Duration::from_secs(61)), Lookup::Fresh));
+
+        // A dropped request gives its key back
+        drop(Claim { cache: &cache, key: "k1".to_string(), done: false });
+        assert_eq!(cache.entries.lock().unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,344 @@
+use crate::idempotency::IdempotencyConfig;
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, ConnectInfo, FromRef, FromRequest, Request},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::{Layer, Service};
+
+/// Buckets kept before idle clients are forgotten
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// HTTP server settings, read from the file given with --server-config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    #[serde(default = "default_bind")]
+    pub bind: String,
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize, // larger request bodies get 413
+    #[serde(default = "default_max_session_bytes")]
+    pub max_session_bytes: usize, // POST /session/import carries a whole session, so it gets its own cap
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub job: JobLimits,
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig, // replay window for requests sent with an Idempotency-Key
+    #[serde(default = "default_runs_dir")]
+    pub runs_dir: PathBuf, // run summaries GET /compare can name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_token: Option<String>, // bearer token for the /debug endpoints in release builds
+}
+
+fn default_bind() -> String {
+    "0.0.0.0:8080".to_string()
+}
+
+fn default_runs_dir() -> PathBuf {
+    PathBuf::from(colony_core::DEFAULT_RUNS_DIR)
+}
+
+fn default_max_body_bytes() -> usize {
+    64 * 1024
+}
+
+fn default_max_session_bytes() -> usize {
+    64 * 1024 * 1024
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: default_bind(),
+            max_body_bytes: default_max_body_bytes(),
+            max_session_bytes: default_max_session_bytes(),
+            rate_limit: RateLimitConfig::default(),
+            job: JobLimits::default(),
+            idempotency: IdempotencyConfig::default(),
+            runs_dir: default_runs_dir(),
+            admin_token: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Read a server config; a missing file means the defaults
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        toml::from_str(&contents).map_err(|e| format!("Invalid server config {:?}: {}", path, e))
+    }
+
+    /// Debug endpoints expose live internals: open in debug builds, otherwise only to requests
+    /// carrying `Authorization: Bearer <admin_token>`
+    pub fn require_admin(&self, headers: &header::HeaderMap) -> Result<(), ApiError> {
+        if cfg!(debug_assertions) {
+            return Ok(());
+        }
+        let Some(token) = self.admin_token.as_deref().filter(|t| !t.is_empty()) else {
+            return Err(ApiError::new(StatusCode::FORBIDDEN, "Debug endpoints need a debug build or admin_token in the server config"));
+        };
+        let presented = headers.get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        if presented != Some(token) {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED, "Debug endpoints need the admin token"));
+        }
+        Ok(())
+    }
+}
+
+/// Token bucket per client IP: `burst` requests at once, refilled at `requests_per_sec`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub requests_per_sec: f64, // 0 turns rate limiting off
+    pub burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_sec: 50.0,
+            burst: 100,
+        }
+    }
+}
+
+/// Bounds on submitted jobs, so one request can't ask the sim for something absurd
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobLimits {
+    pub max_pipeline_ops: usize,
+    pub max_payload_sz: usize,
+    pub max_deadline_ms: u64,
+}
+
+impl Default for JobLimits {
+    fn default() -> Self {
+        Self {
+            max_pipeline_ops: 32,
+            max_payload_sz: 64 * 1024 * 1024,
+            max_deadline_ms: 24 * 60 * 60 * 1000,
+        }
+    }
+}
+
+/// A 4xx/5xx with a JSON body saying what was wrong: `{"error": "..."}`
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+
+    pub fn unprocessable(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(serde_json::json!({ "error": self.message }))).into_response()
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        let status = match &rejection {
+            JsonRejection::JsonDataError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            JsonRejection::JsonSyntaxError(_) => StatusCode::BAD_REQUEST,
+            JsonRejection::MissingJsonContentType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            _ => rejection.status(),
+        };
+        Self::new(status, rejection.body_text())
+    }
+}
+
+/// Request bodies that check their own fields once they've parsed
+pub trait Validate {
+    fn validate(&self, limits: &JobLimits) -> Result<(), String>;
+}
+
+/// `Json<T>` that rejects malformed, oversized or out-of-bounds bodies with a descriptive `ApiError`
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+    Arc<ServerConfig>: FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        let config = Arc::<ServerConfig>::from_ref(state);
+        value.validate(&config.job).map_err(ApiError::unprocessable)?;
+        Ok(ValidJson(value))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    last: Instant,
+}
+
+/// Per-client token buckets shared by every connection
+#[derive(Debug)]
+pub struct ClientLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<IpAddr, TokenBucket>>,
+}
+
+impl ClientLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.requests_per_sec > 0.0
+    }
+
+    /// Take a token for `client`; Err holds how long until one is available
+    pub fn check(&self, client: IpAddr, now: Instant) -> Result<(), Duration> {
+        if !self.enabled() {
+            return Ok(());
+        }
+        let rate = self.config.requests_per_sec;
+        let burst = self.config.burst.max(1) as f64;
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(&client) {
+            // Clients that have been quiet long enough to refill don't need a bucket
+            buckets.retain(|_, b| b.tokens + now.saturating_duration_since(b.last).as_secs_f64() * rate < burst);
+        }
+        let bucket = buckets.entry(client).or_insert(TokenBucket { tokens: burst, last: now });
+        let elapsed = now.saturating_duration_since(bucket.last).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.last = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+/// Tower layer that answers 429 with Retry-After once a client runs out of tokens. Clients are
+/// told apart by peer address, so serve with `into_make_service_with_connect_info::<SocketAddr>`.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<ClientLimiter>,
+}
+
+impl RateLimitLayer {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { limiter: Arc::new(ClientLimiter::new(config)) }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit { inner, limiter: self.limiter.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    limiter: Arc<ClientLimiter>,
+}
+
+impl<S> Service<Request> for RateLimit<S>
+where
+    S: Service<Request, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let client = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        if let Err(wait) = self.limiter.check(client, Instant::now()) {
+            let retry_secs = wait.as_secs_f64().ceil().max(1.0) as u64;
+            let mut response = ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("Rate limit exceeded for {}; retry in {}s", client, retry_secs),
+            )
+            .into_response();
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_secs));
+            return Box::pin(async move { Ok(response) });
+        }
+        Box::pin(self.inner.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_burst_then_refills() {
+        let limiter = ClientLimiter::new(RateLimitConfig { requests_per_sec: 10.0, burst: 3 });
+        let client: IpAddr = "10.0.0.1".parse().unwrap();
+        let other: IpAddr = "10.0.0.2".parse().unwrap();
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check(client, start).is_ok());
+        }
+        let wait = limiter.check(client, start).unwrap_err();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(100));
+        // Other clients have their own bucket
+        assert!(limiter.check(other, start).is_ok());
+
+        assert!(limiter.check(client, start + Duration::from_millis(100)).is_ok());
+        assert!(limiter.check(client, start + Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn test_zero_rate_disables_limiting() {
+        let limiter = ClientLimiter::new(RateLimitConfig { requests_per_sec: 0.0, burst: 1 });
+        let now = Instant::now();
+        assert!((0..100).all(|_| limiter.check(IpAddr::V4(Ipv4Addr::LOCALHOST), now).is_ok()));
+    }
+
+    #[test]
+    fn test_server_config_rejects_unknown_keys() {
+        let config: ServerConfig = toml::from_str("max_body_bytes = 1024\n[rate_limit]\nrequests_per_sec = 5.0\nburst = 10\n").unwrap();
+        assert_eq!((config.max_body_bytes, config.rate_limit.burst), (1024, 10));
+        assert_eq!(config.job.max_pipeline_ops, JobLimits::default().max_pipeline_ops);
+        assert!(toml::from_str::<ServerConfig>("max_body_byte = 1024\n").is_err());
+    }
+}
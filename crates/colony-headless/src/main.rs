@@ -1,24 +1,192 @@
+mod idempotency;
+mod limits;
+mod notify;
+mod replica;
+mod routes;
+mod sim;
+
 use axum::{
-    extract::State,
+    extract::{DefaultBodyLimit, FromRef},
     http::StatusCode,
-    response::Json,
-    routing::{get, post, put},
+    routing::get,
     Router,
 };
-use colony_core::{SimClock, TickScale, Colony, Job, Pipeline, Op, QoS, SchedPolicy, CorruptionTunables, FaultKpi, GpuTunables, BlackSwanIndex, Debts, ResearchState, TechTree, GameSetup, WinLossState, SlaTracker, SessionCtl, ReplayLog, ReplayMode};
-use colony_io::{IoSimulatorConfig, CanSimConfig, ModbusSimConfig};
-use serde::{Deserialize, Serialize};
+use bevy::prelude::Resource;
+use colony_core::{SimClock, TickScale, Colony, FaultKpi, BlackSwanIndex, ContentPack, Debts, EffectiveCapacity, ResearchState, TechTree, WinLossState, SessionCtl, PausePolicies, PauseMonitor, ActiveScheduler, RunSpec, execute_run_spec_with_progress, ProgressHandle, write_run_exports, ConfigWatcher, ClockDrift, MaintenanceLane, CertificationBoard, ExportSink, ArchiveStore, FlowTracker, EXIT_SPEC_ERROR, MemoryAccounting, AutoThrottle, QosQueueing, ArrivalLog, RitualRuns, AchievementRegistry, Achievements, ProfileStore, LocalProfile, RunVerdict, ClientKey, ReplayBundle, hash_mods, verify_replay, DEFAULT_CLIENT_KEY_PATH, EXIT_REPLAY_REJECTED, check_config, SaveProtection, GameMode, JobIdAllocator, SessionSchedule, apply_schedule_actions, TimedCommand, TrafficShaper, CorruptionTopology, JobRetries, TenantLedger, EnergyLedger, DemandForecast, Autoscaler, EventJournal, ReplSession, ReplReply, GpuFaults, OpCache, ClassReservations, OpLicenses, compare_runs, load_run_summary, find_scenario, Notification, LoadGenerator, RngAudit, SeedStream, IoIngest, IoRuntime, start_io_runtime, start_fieldbus_sims, DeadLetterQueue, YardGrid, set_tunable};
+use colony_io::{ParseCounters, IoChannelSpec, IoRegistry};
+use clap::Parser;
+use replica::ReplicaStatus;
+use sim::{Mirror, SimHandle, SimView, Synced};
+use limits::{ApiError, RateLimitLayer, ServerConfig};
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+#[derive(Parser)]
+#[command(name = "colony-headless")]
+#[command(about = "Headless Colony simulation server")]
+struct Cli {
+    /// Execute a scripted run from a TOML spec and exit instead of serving HTTP
+    #[arg(long, value_name = "PATH")]
+    run_spec: Option<PathBuf>,
+    /// With --run-spec, also write a signed replay bundle of the run
+    #[arg(long, value_name = "PATH", requires = "run_spec")]
+    bundle: Option<PathBuf>,
+    /// Watch a game config/tunables TOML file and apply edits at the next tick boundary (repeatable)
+    #[arg(long, value_name = "PATH")]
+    watch_config: Vec<PathBuf>,
+    /// With --run-spec, seconds between progress lines on stderr (0 turns them off)
+    #[arg(long, value_name = "SECS", default_value_t = 5)]
+    progress_secs: u64,
+    /// With --run-spec, serve GET /session/progress on this port while the run executes
+    #[arg(long, value_name = "PORT", requires = "run_spec")]
+    progress_port: Option<u16>,
+    /// Player profile for achievements and prestige (default: the last one used)
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+    /// Serve a creative (sandbox) session: no loss, unlimited research and budget, debug controls unlocked
+    #[arg(long, conflicts_with = "run_spec")]
+    creative: bool,
+    /// Game config whose [save_protection] and [save_compression] tables apply to saves, replays and post-mortems
+    #[arg(long, value_name = "PATH")]
+    game_config: Option<PathBuf>,
+    /// Server settings: bind address, per-client rate limits, body size and job bounds
+    #[arg(long, value_name = "PATH", default_value = "server.toml")]
+    server_config: PathBuf,
+    /// Stream per-tick state to read-only observers that connect on this TCP port
+    #[arg(long, value_name = "PORT", conflicts_with = "observe")]
+    replica_port: Option<u16>,
+    /// Run as a read-only observer of the primary at HOST:PORT instead of simulating
+    #[arg(long, value_name = "HOST:PORT", conflicts_with_all = ["run_spec", "creative", "watch_config"])]
+    observe: Option<String>,
+    /// Drive a local sim from a line-based REPL on stdin instead of serving HTTP
+    #[arg(long, conflicts_with_all = ["run_spec", "observe", "replica_port"])]
+    interactive: bool,
+    /// The scenario to serve or, with --interactive, to load (built-in or generated id)
+    #[arg(long, value_name = "ID", conflicts_with_all = ["run_spec", "observe"])]
+    scenario: Option<String>,
+    /// Override the scenario seed
+    #[arg(long, value_name = "SEED", conflicts_with_all = ["run_spec", "observe"])]
+    seed: Option<u64>,
+    /// Load an external scheduler from a colony_scheduler_v1 dynamic library, registered as ffi/<name> (repeatable)
+    #[cfg(feature = "ffi_scheduler")]
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["run_spec", "observe"])]
+    scheduler_lib: Vec<PathBuf>,
+    /// Milliseconds a library scheduler's pick may take before it is quarantined
+    #[cfg(feature = "ffi_scheduler")]
+    #[arg(long, value_name = "MS", default_value_t = 5, requires = "scheduler_lib")]
+    scheduler_timeout_ms: u64,
+    /// Threads the sim runs systems on (default: one per core); seeded runs end the same whatever the count
+    #[arg(long, value_name = "N", conflicts_with = "observe")]
+    compute_threads: Option<usize>,
+    /// Run the built-in UDP, HTTP, CAN and Modbus simulators and queue their traffic as jobs, see /io/ingest
+    #[arg(long, conflicts_with_all = ["run_spec", "observe"])]
+    io_sim: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Re-simulate a signed replay bundle and confirm its claimed result
+    VerifyReplay {
+        #[arg(value_name = "BUNDLE")]
+        path: PathBuf,
+        /// Directory of mods to compare against the bundle's mod hashes
+        #[arg(long, value_name = "DIR", default_value = "mods")]
+        mods: PathBuf,
+    },
+    /// Diff two exported run summaries: KPI curves by tick, event timelines and scores
+    CompareRuns {
+        #[arg(value_name = "A")]
+        a: PathBuf,
+        #[arg(value_name = "B")]
+        b: PathBuf,
+        /// Print the comparison as JSON instead of a markdown report
+        #[arg(long)]
+        json: bool,
+    },
+    /// Validate a game config file without starting the sim
+    CheckConfig {
+        #[arg(value_name = "PATH", default_value = "game_config.toml")]
+        path: PathBuf,
+    },
+}
+
 #[tokio::main]
 async fn main() {
-    let app_state = AppState {
-        clock: Arc::new(RwLock::new(SimClock {
+    let cli = Cli::parse();
+    if let Some(threads) = cli.compute_threads {
+        colony_core::init_compute_threads(threads);
+    }
+    match cli.command {
+        Some(Command::VerifyReplay { path, mods }) => {
+            let code = tokio::task::spawn_blocking(move || verify_replay_file(&path, &mods))
+                .await
+                .unwrap_or(EXIT_SPEC_ERROR);
+            std::process::exit(code);
+        }
+        Some(Command::CompareRuns { a, b, json }) => std::process::exit(compare_runs_files(&a, &b, json)),
+        Some(Command::CheckConfig { path }) => std::process::exit(check_config_file(&path)),
+        None => {}
+    }
+    let profile = match ProfileStore::open_default().select(cli.profile.as_deref()) {
+        Ok(profile) => Some(profile),
+        Err(e) => {
+            eprintln!("Failed to load player profile: {}", e);
+            None
+        }
+    };
+    if let Some(path) = cli.run_spec {
+        let bundle = cli.bundle;
+        let watch = cli.watch_config;
+        let progress = ProgressHandle::new();
+        if cli.progress_secs > 0 {
+            spawn_progress_printer(progress.clone(), cli.progress_secs);
+        }
+        if let Some(port) = cli.progress_port {
+            spawn_progress_server(progress.clone(), port).await;
+        }
+        let code = tokio::task::spawn_blocking(move || run_spec_file(&path, bundle.as_deref(), &watch, &progress, profile))
+            .await
+            .unwrap_or(EXIT_SPEC_ERROR);
+        std::process::exit(code);
+    }
+    if cli.interactive {
+        let mode = if cli.creative { GameMode::Creative } else { GameMode::Standard };
+        let (scenario, seed) = (cli.scenario, cli.seed);
+        let code = tokio::task::spawn_blocking(move || run_interactive(scenario.as_deref(), seed, mode))
+            .await
+            .unwrap_or(EXIT_SPEC_ERROR);
+        std::process::exit(code);
+    }
+
+    let server_config = match ServerConfig::load(&cli.server_config) {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(EXIT_SPEC_ERROR);
+        }
+    };
+    let save_protection = match cli.game_config.as_deref().map(|path| SaveProtection::from_game_config_file(&path.to_string_lossy())) {
+        Some(Ok(protection)) => Arc::new(protection),
+        Some(Err(e)) => {
+            eprintln!("Failed to set up save protection: {:#}", e);
+            std::process::exit(EXIT_SPEC_ERROR);
+        }
+        None => Arc::new(SaveProtection::off()),
+    };
+    let content = ContentPack::load_mods(std::path::Path::new("mods")).unwrap_or_else(|e| {
+        eprintln!("Failed to load mods, using built-in content: {}", e);
+        ContentPack::vanilla().clone()
+    });
+    let mut app_state = AppState {
+        clock: Arc::new(Synced::new(SimClock {
             tick_scale: TickScale::RealTime,
             now: chrono::Utc::now(),
         })),
-        colony: Arc::new(RwLock::new(Colony {
+        colony: Arc::new(Synced::new(Colony {
             power_cap_kw: 1000.0,
             bandwidth_total_gbps: 1.0,
             corruption_field: 0.0,
@@ -26,841 +194,570 @@ async fn main() {
             meters: colony_core::GlobalMeters::new(),
             tunables: colony_core::ResourceTunables::default(),
             corruption_tun: colony_core::CorruptionTunables::default(),
+            seeds: Default::default(),
             seed: 12345,
         })),
+        memory: Arc::new(Synced::new(MemoryAccounting::new())),
+        auto_throttle: Arc::new(Synced::new(AutoThrottle::new())),
+        qos: Arc::new(Synced::new(QosQueueing::new())),
+        shaping: Arc::new(Synced::new(TrafficShaper::new())),
+        retries: Arc::new(Synced::new(JobRetries::new())),
+        dead_letters: Arc::new(Synced::new(DeadLetterQueue::new())),
+        yard_grid: Arc::new(Synced::new(YardGrid::new())),
+        tenants: Arc::new(Synced::new(TenantLedger::new())),
+        energy: Arc::new(Synced::new(EnergyLedger::new())),
+        forecast: Arc::new(Synced::new(DemandForecast::new())),
+        autoscaler: Arc::new(Synced::new(Autoscaler::new())),
+        gpu_faults: Arc::new(Synced::new(GpuFaults::new())),
+        op_cache: Arc::new(Synced::new(OpCache::new())),
+        loadgen: Arc::new(Synced::new(LoadGenerator::new())),
+        io_ingest: Arc::new(Synced::new(IoIngest::new())),
+        journal: Arc::new(Synced::new(EventJournal::new())),
+        arrivals: Arc::new(Synced::new(ArrivalLog::new())),
+        job_ids: Arc::new(Synced::new(JobIdAllocator::new())),
+        rng_audit: Arc::new(Synced::new(RngAudit::new())),
+        black_swans: Arc::new(Synced::new(content.black_swan_index())),
+        rituals: Arc::new(Synced::new(RitualRuns::new())),
+        achievement_registry: Arc::new(Synced::new(AchievementRegistry::with_defaults())),
+        achievements: Arc::new(Synced::new(Achievements::new())),
+        clock_drift: Arc::new(Synced::new(ClockDrift::new())),
+        maintenance_lane: Arc::new(Synced::new(MaintenanceLane::new())),
+        class_reservations: Arc::new(Synced::new(ClassReservations::new())),
+        op_licenses: Arc::new(Synced::new(OpLicenses::new())),
+        certifications: Arc::new(Synced::new(CertificationBoard::new())),
+        export_sink: Arc::new(Synced::new(ExportSink::new())),
+        archive: Arc::new(Synced::new(ArchiveStore::new())),
+        corruption_topology: Arc::new(Synced::new(CorruptionTopology::new())),
+        flows: Arc::new(Synced::new(FlowTracker::new())),
+        scheduler: Arc::new(Synced::new(ActiveScheduler::default())),
+        faults: Arc::new(Synced::new(FaultKpi::new())),
+        debts: Arc::new(Synced::new(Debts::new())),
+        effective_capacity: Arc::new(Synced::new(EffectiveCapacity::new())),
+        research: Arc::new(Synced::new(ResearchState::new())),
+        tech_tree: Arc::new(Synced::new(content.tech_tree())),
+        win_loss: Arc::new(Synced::new(WinLossState::new())),
+        view: Arc::new(RwLock::new(SimView::default())),
+        run_progress: ProgressHandle::new(),
+        session: Arc::new(Synced::new(SessionCtl::new())),
+        pause_policies: Arc::new(Synced::new(PausePolicies::default())),
+        pause_monitor: Arc::new(Synced::new(PauseMonitor::new())),
+        parse_counters: ParseCounters::new(),
+        io_registry: Arc::new(IoRegistry::with_builtins()),
+        io_channels: Arc::new(RwLock::new(Vec::new())),
+        mode: if cli.creative { GameMode::Creative } else { GameMode::Standard },
+        server_config: server_config.clone(),
+        save_protection: save_protection.clone(),
+        schedule: Arc::new(RwLock::new(SessionSchedule::new())),
+        pending_commands: Arc::new(RwLock::new(Vec::new())),
+        replica: Arc::new(RwLock::new(match &cli.observe {
+            Some(primary) => ReplicaStatus::observer(primary),
+            None => ReplicaStatus::Standalone,
+        })),
+        notifications: tokio::sync::broadcast::channel(notify::NOTIFICATION_BACKLOG).0,
+        sim: None,
     };
+    if app_state.mode.is_creative() {
+        println!("Creative mode: loss is off and scores are not comparable");
+    }
+    if cli.observe.is_none() {
+        let scenario = match cli.scenario.as_deref().map(find_scenario).transpose() {
+            Ok(scenario) => scenario,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(EXIT_SPEC_ERROR);
+            }
+        };
+        let (seed, mode) = (cli.seed, app_state.mode);
+        let mut io_runtime = IoRuntime { parse_counters: app_state.parse_counters.clone(), ..Default::default() };
+        let io_jobs = cli.io_sim.then(|| io_runtime.connect());
+        let achievements = profile.as_ref().map(|p| p.achievements()).unwrap_or_else(|| Ok(Achievements::new())).unwrap_or_else(|e| {
+            eprintln!("Failed to load player profile: {}", e);
+            Achievements::new()
+        });
+        let build = move || sim::build_sim_app(scenario.as_ref(), seed, mode, &content, TickScale::Seconds(1)).map(|mut app| {
+            app.insert_resource(io_runtime).insert_resource((*save_protection).clone()).insert_resource(achievements);
+            app
+        });
+        #[cfg(feature = "ffi_scheduler")]
+        let build = {
+            let schedulers = match sim::load_scheduler_libs(&cli.scheduler_lib, cli.scheduler_timeout_ms) {
+                Ok(schedulers) => schedulers,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(EXIT_SPEC_ERROR);
+                }
+            };
+            move || build().and_then(|app| sim::with_schedulers(app, &schedulers))
+        };
+        match sim::spawn_sim(build, mirrors(&app_state), app_state.view.clone(), app_state.pending_commands.clone(), app_state.notifications.clone()) {
+            Ok(handle) => app_state.sim = Some(handle),
+            Err(e) => {
+                eprintln!("Failed to start the simulation: {}", e);
+                std::process::exit(EXIT_SPEC_ERROR);
+            }
+        }
+        if let (Some(job_tx), Some(handle)) = (io_jobs, &app_state.sim) {
+            let seed = handle.with_world(|world| world.resource::<Colony>().seed_for(SeedStream::Io)).await.unwrap_or_default();
+            start_io_runtime(seed, Default::default(), Default::default(), job_tx.clone(), app_state.parse_counters.clone()).await;
+            start_fieldbus_sims(seed, Default::default(), Default::default(), job_tx);
+            println!("IO simulators started; their traffic is queued as jobs");
+        }
+    }
 
-    let app = Router::new()
-        .route("/state/summary", get(get_summary))
-        .route("/clock/scale", put(set_scale))
-        .route("/job", post(create_job))
-        .route("/clock", get(get_clock))
-        .route("/scheduler", put(set_scheduler))
-        .route("/io/udp/sim", put(set_udp_sim))
-        .route("/io/http/sim", put(set_http_sim))
-        .route("/pipeline/:id/enqueue", post(enqueue_pipeline))
-        .route("/metrics/io", get(get_io_metrics))
-        .route("/sched/policy", put(set_scheduler_policy))
-        .route("/metrics/faults", get(get_fault_metrics))
-        .route("/corruption/tunables", put(set_corruption_tunables))
-        .route("/workers/:id/reimage", post(reimage_worker))
-        .route("/io/can/sim", put(set_can_sim))
-        .route("/io/modbus/sim", put(set_modbus_sim))
-        .route("/metrics/gpu", get(get_gpu_metrics))
-        .route("/gpu/tunables", put(set_gpu_tunables))
-        .route("/gpu/flags", put(set_gpu_flags))
-        .route("/events", get(get_events))
-        .route("/events/:id/fire", post(fire_event))
-        .route("/debts", get(get_debts))
-        .route("/research", get(get_research))
-        .route("/research/unlock/:tech_id", post(unlock_tech))
-        .route("/rituals/:id/start", post(start_ritual))
-        .route("/session/start", post(start_session))
-        .route("/session/pause", post(pause_session))
-        .route("/session/resume", post(resume_session))
-        .route("/session/ffwd", put(set_fast_forward))
-        .route("/session/status", get(get_session_status))
-        .route("/session/autosave", put(set_autosave_interval))
-        .route("/save/manual", post(save_manual))
-        .route("/load/manual", post(load_manual))
-        .route("/replay/start", post(start_replay))
-        .route("/replay/stop", post(stop_replay))
-        .route("/metrics/summary", get(get_metrics_summary))
-        .route("/mods", get(get_mods))
-        .route("/mods/reload", post(reload_mod))
-        .route("/mods/enable", post(enable_mod))
-        .route("/mods/dryrun", post(dryrun_mod))
-        .route("/mods/docs", get(get_mod_docs))
-        .with_state(app_state);
-
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
-    println!("Headless server running on http://0.0.0.0:8080");
-    axum::serve(listener, app).await.unwrap();
-}
-
-#[derive(Clone)]
-struct AppState {
-    clock: Arc<RwLock<SimClock>>,
-    colony: Arc<RwLock<Colony>>,
-}
-
-#[derive(Serialize)]
-struct SummaryResponse {
-    clock: SimClock,
-    colony: Colony,
-    workers: Vec<WorkerStatus>,
-    yards: Vec<YardStatus>,
-}
-
-#[derive(Serialize)]
-struct YardStatus {
-    kind: String,
-    heat: f32,
-    heat_cap: f32,
-    throttle: f32,
-    power_draw_kw: f32,
-}
-
-#[derive(Serialize)]
-struct WorkerStatus {
-    id: u64,
-    state: String,
-    skill_cpu: f32,
-    corruption: f32,
-}
-
-#[derive(Deserialize)]
-struct TimeScaleRequest {
-    scale: String,
-    value: Option<u64>,
-}
-
-#[derive(Deserialize)]
-struct JobRequest {
-    pipeline: Vec<String>,
-    qos: String,
-    deadline_ms: u64,
-    payload_sz: usize,
-}
+    if !cli.watch_config.is_empty() {
+        match ConfigWatcher::watch(cli.watch_config.clone()) {
+            Ok(watcher) => spawn_config_watch(watcher, app_state.clone()),
+            Err(e) => {
+                eprintln!("Failed to watch config files: {}", e);
+                std::process::exit(EXIT_SPEC_ERROR);
+            }
+        }
+    }
 
-#[derive(Deserialize)]
-struct SchedulerRequest {
-    scheduler: String,
-}
+    let observing = cli.observe.is_some();
+    if let Some(primary) = cli.observe {
+        // The primary runs the session; this instance only mirrors and serves it
+        replica::spawn_observer(app_state.clone(), primary);
+    } else {
+        spawn_session_scheduler(app_state.clone());
+    }
+    if let Some(port) = cli.replica_port {
+        replica::serve_primary(app_state.clone(), port).await;
+    }
 
-async fn get_summary(State(state): State<AppState>) -> Result<Json<SummaryResponse>, StatusCode> {
-    let clock = state.clock.read().await;
-    let colony = state.colony.read().await;
-    
-    // Mock workers for now
-    let workers = vec![
-        WorkerStatus {
-            id: 0,
-            state: "Idle".to_string(),
-            skill_cpu: 0.8,
-            corruption: 0.0,
-        },
-        WorkerStatus {
-            id: 1,
-            state: "Idle".to_string(),
-            skill_cpu: 0.85,
-            corruption: 0.0,
-        },
-        WorkerStatus {
-            id: 2,
-            state: "Running".to_string(),
-            skill_cpu: 0.9,
-            corruption: 0.1,
-        },
-        WorkerStatus {
-            id: 3,
-            state: "Idle".to_string(),
-            skill_cpu: 0.75,
-            corruption: 0.0,
-        },
-    ];
-
-    // Mock yards for now
-    let yards = vec![
-        YardStatus {
-            kind: "CpuArray".to_string(),
-            heat: 45.0,
-            heat_cap: 100.0,
-            throttle: 1.0,
-            power_draw_kw: 200.0,
-        },
-    ];
-
-    Ok(Json(SummaryResponse {
-        clock: clock.clone(),
-        colony: colony.clone(),
-        workers,
-        yards,
-    }))
+    let app = routes::router(&app_state).with_state(app_state);
+    let idempotency = Arc::new(idempotency::IdempotencyCache::new(
+        server_config.idempotency.clone(),
+        server_config.max_body_bytes.max(server_config.max_session_bytes),
+    ));
+    let app = app.layer(axum::middleware::from_fn_with_state(idempotency, idempotency::idempotent));
+    let app = if observing { app.layer(axum::middleware::from_fn(replica::reject_writes)) } else { app };
+    let app = app
+        .layer(DefaultBodyLimit::max(server_config.max_body_bytes))
+        .layer(RateLimitLayer::new(server_config.rate_limit.clone()));
+
+    let listener = tokio::net::TcpListener::bind(&server_config.bind).await.unwrap();
+    println!("Headless server running on http://{}", server_config.bind);
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
+}
+
+/// Apply watched config edits to the served colony
+fn spawn_config_watch(mut watcher: ConfigWatcher, state: AppState) {
+    println!("Watching {} config file(s) for tunable changes", watcher.paths.len());
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_millis(250));
+        loop {
+            interval.tick().await;
+            let tick = state.clock.read().await.now.timestamp_millis() as u64 / 16;
+            // Only bother the sim when a file actually changed a tunable, then apply just those
+            let mut colony = state.colony.read().await.clone();
+            let changed = watcher.poll(&mut colony, tick);
+            if !changed.is_empty() {
+                let result = state.update(move |colony: &mut Colony| {
+                    // Already range-checked against the served copy, which has the same ranges
+                    for change in &changed {
+                        let _ = set_tunable(colony, &change.key, change.new);
+                    }
+                }).await;
+                if let Err(e) = result {
+                    eprintln!("Failed to apply config changes: {}", e.message);
+                }
+            }
+        }
+    });
+}
+
+/// Start and stop the served session at the times given through `/sessions/schedule`
+fn spawn_session_scheduler(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            let tick = state.clock.read().await.now.timestamp_millis() as u64 / 16;
+            let actions = state.schedule.write().await.poll(chrono::Utc::now(), tick);
+            if !actions.is_empty() {
+                if let Err(e) = state.update(move |session: &mut SessionCtl| apply_schedule_actions(session, &actions)).await {
+                    eprintln!("Failed to apply scheduled session actions: {}", e.message);
+                }
+            }
+        }
+    });
+}
+
+/// Print a progress line to stderr every `every_secs` until the run finishes
+fn spawn_progress_printer(progress: ProgressHandle, every_secs: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(every_secs));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let snapshot = progress.snapshot();
+            if snapshot.done {
+                break;
+            }
+            if snapshot.running {
+                eprintln!("{}", snapshot.line());
+            }
+        }
+    });
 }
 
-async fn set_scale(
-    State(state): State<AppState>,
-    Json(request): Json<TimeScaleRequest>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let mut clock = state.clock.write().await;
-    
-    clock.tick_scale = match request.scale.as_str() {
-        "realtime" => TickScale::RealTime,
-        "seconds" => TickScale::Seconds(request.value.unwrap_or(1)),
-        "days" => TickScale::Days(request.value.unwrap_or(1) as u16),
-        "years" => TickScale::Years(request.value.unwrap_or(1) as u8),
-        _ => return Err(StatusCode::BAD_REQUEST),
-    };
-
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "scale": request.scale,
-        "value": request.value
-    })))
+/// Serve only `/session/progress` while a run spec executes
+async fn spawn_progress_server(progress: ProgressHandle, port: u16) {
+    let app = Router::new()
+        .route("/session/progress", get(routes::session::get_session_progress))
+        .with_state(progress);
+    match tokio::net::TcpListener::bind(("0.0.0.0", port)).await {
+        Ok(listener) => {
+            eprintln!("Run progress on http://0.0.0.0:{}/session/progress", port);
+            tokio::spawn(async move {
+                let _ = axum::serve(listener, app).await;
+            });
+        }
+        Err(e) => eprintln!("Failed to serve run progress on port {}: {}", port, e),
+    }
 }
 
-async fn create_job(
-    State(_state): State<AppState>,
-    Json(request): Json<JobRequest>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let ops: Result<Vec<Op>, _> = request.pipeline
-        .iter()
-        .map(|op_str| match op_str.as_str() {
-            "Decode" => Ok(Op::Decode),
-            "Fft" => Ok(Op::Fft),
-            "Kalman" => Ok(Op::Kalman),
-            "Yolo" => Ok(Op::Yolo),
-            "Crc" => Ok(Op::Crc),
-            "CanParse" => Ok(Op::CanParse),
-            "UdpDemux" => Ok(Op::UdpDemux),
-            "TcpSessionize" => Ok(Op::TcpSessionize),
-            "ModbusMap" => Ok(Op::ModbusMap),
-            "HttpParse" => Ok(Op::HttpParse),
-            _ => Err("Unknown operation"),
-        })
-        .collect();
-
-    let ops = ops.map_err(|_| StatusCode::BAD_REQUEST)?;
-
-    let qos = match request.qos.as_str() {
-        "Throughput" => QoS::Throughput,
-        "Latency" => QoS::Latency,
-        "Balanced" => QoS::Balanced,
-        _ => return Err(StatusCode::BAD_REQUEST),
+fn run_spec_file(path: &std::path::Path, bundle_path: Option<&std::path::Path>, watch: &[PathBuf], progress: &ProgressHandle, profile: Option<LocalProfile>) -> i32 {
+    let spec = match RunSpec::load(path) {
+        Ok(spec) => spec,
+        Err(e) => {
+            eprintln!("Invalid run spec {}: {}", path.display(), e);
+            return EXIT_SPEC_ERROR;
+        }
     };
 
-    let pipeline = Pipeline {
-        ops,
-        mutation_tag: None,
+    let mut watcher = if watch.is_empty() {
+        None
+    } else {
+        match ConfigWatcher::watch(watch.to_vec()) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                eprintln!("Failed to watch config files: {}", e);
+                return EXIT_SPEC_ERROR;
+            }
+        }
     };
 
-    let job = Job {
-        id: chrono::Utc::now().timestamp_millis() as u64,
-        pipeline,
-        qos,
-        deadline_ms: request.deadline_ms,
-        payload_sz: request.payload_sz,
+    let outcome = match execute_run_spec_with_progress(&spec, watcher.as_mut(), Some(progress)) {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            eprintln!("Run failed: {}", e);
+            return EXIT_SPEC_ERROR;
+        }
     };
 
-    Ok(Json(serde_json::json!({
-        "status": "created",
-        "job_id": job.id,
-        "deadline_ms": job.deadline_ms
-    })))
-}
-
-async fn get_clock(State(state): State<AppState>) -> Result<Json<SimClock>, StatusCode> {
-    let clock = state.clock.read().await;
-    Ok(Json(clock.clone()))
-}
-
-async fn set_scheduler(
-    State(_state): State<AppState>,
-    Json(request): Json<SchedulerRequest>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would update the active scheduler
-    // For now, just validate the scheduler name
-    let valid_schedulers = ["FCFS", "SJF", "EDF", "HeteroAware"];
-    
-    if !valid_schedulers.contains(&request.scheduler.as_str()) {
-        return Err(StatusCode::BAD_REQUEST);
+    if let Err(e) = write_run_exports(&spec, &outcome) {
+        eprintln!("Failed to write run exports: {}", e);
+        return EXIT_SPEC_ERROR;
+    }
+    if let Some(bundle_path) = bundle_path {
+        let written = ClientKey::load_or_create(std::path::Path::new(DEFAULT_CLIENT_KEY_PATH))
+            .and_then(|key| {
+                let mod_hashes = hash_mods(std::path::Path::new("mods"))?;
+                // Live tunable edits become commands so the bundle replays the run as played
+                ReplayBundle::create(&spec.with_live_commands(&outcome), &outcome, mod_hashes, &key)
+            })
+            .and_then(|bundle| bundle.save(bundle_path));
+        if let Err(e) = written {
+            eprintln!("Failed to write replay bundle: {}", e);
+            return EXIT_SPEC_ERROR;
+        }
+        println!("Signed replay bundle written to {}", bundle_path.display());
     }
 
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "scheduler": request.scheduler
-    })))
-}
-
-async fn set_udp_sim(
-    State(_state): State<AppState>,
-    Json(config): Json<IoSimulatorConfig>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would start/restart the UDP simulator
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "config": config
-    })))
-}
-
-async fn set_http_sim(
-    State(_state): State<AppState>,
-    Json(config): Json<IoSimulatorConfig>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would start/restart the HTTP simulator
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "config": config
-    })))
-}
-
-async fn enqueue_pipeline(
-    State(_state): State<AppState>,
-    axum::extract::Path(pipeline_id): axum::extract::Path<String>,
-    Json(request): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let payload_sz = request.get("payload_sz")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(1024) as usize;
-    
-    // In a real implementation, this would enqueue a job for the specified pipeline
-    Ok(Json(serde_json::json!({
-        "status": "enqueued",
-        "pipeline_id": pipeline_id,
-        "payload_sz": payload_sz
-    })))
-}
-
-async fn get_io_metrics(
-    State(_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // Mock I/O metrics for now
-    Ok(Json(serde_json::json!({
-        "pps_udp": 100.0,
-        "pps_http": 50.0,
-        "gbps": 0.5,
-        "backlog_queue": 5,
-        "deadlines": {
-            "udp_telemetry_ingest": 0.95,
-            "http_ingest": 0.98
+    if let Some(label) = outcome.meta.label() {
+        println!("Run: {}", label);
+    }
+    if outcome.mode.is_creative() {
+        println!("Creative run: score is not comparable and earns no prestige");
+    }
+    println!(
+        "Run finished after {} ticks: {:?} (seed {}, score {})",
+        outcome.ticks_run, outcome.verdict, outcome.seed, outcome.score
+    );
+    if let Some(ref reason) = outcome.doom_reason {
+        println!("Loss reason: {}", reason);
+    }
+    if let Some(ref paused) = outcome.paused {
+        println!("Stopped by pause policy at tick {}: {}", paused.tick, paused.reason);
+    }
+    if let Some(mut profile) = profile.filter(|_| !outcome.mode.is_creative()) {
+        profile.prestige.record_run(outcome.verdict == RunVerdict::Victory, outcome.score);
+        match profile.save() {
+            Ok(()) => println!(
+                "Profile '{}': {} prestige over {} runs",
+                profile.name, profile.prestige.points, profile.prestige.runs
+            ),
+            Err(e) => eprintln!("Failed to save player profile: {}", e),
         }
-    })))
+    }
+    outcome.verdict.exit_code()
 }
 
-async fn set_scheduler_policy(
-    State(_state): State<AppState>,
-    Json(request): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let policy_str = request.get("policy")
-        .and_then(|v| v.as_str())
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    
-    let policy = match policy_str {
-        "fcfs" => SchedPolicy::Fcfs,
-        "sjf" => SchedPolicy::Sjf,
-        "edf" => SchedPolicy::Edf,
-        _ => return Err(StatusCode::BAD_REQUEST),
+fn check_config_file(path: &std::path::Path) -> i32 {
+    let report = match check_config(&path.to_string_lossy()) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Could not read {}: {}", path.display(), e);
+            return EXIT_SPEC_ERROR;
+        }
     };
-    
-    // In a real implementation, this would update the active scheduler
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "policy": policy_str
-    })))
-}
-
-async fn get_fault_metrics(
-    State(_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // Mock fault metrics for now
-    Ok(Json(serde_json::json!({
-        "last_tick_faults": 2,
-        "soft_drop_rate": 0.15,
-        "sticky_workers": 1,
-        "deadline_hit": {
-            "udp_telemetry_ingest": 0.95,
-            "http_ingest": 0.98
+    if report.is_valid() {
+        println!("{}: ok", path.display());
+        return 0;
+    }
+    eprintln!("{}", report.render());
+    eprintln!("{} problem(s) found", report.diagnostics.len().max(1));
+    EXIT_SPEC_ERROR
+}
+
+fn compare_runs_files(a: &std::path::Path, b: &std::path::Path, json: bool) -> i32 {
+    let runs = load_run_summary(a).and_then(|run_a| Ok((run_a, load_run_summary(b)?)));
+    let (run_a, run_b) = match runs {
+        Ok(runs) => runs,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            return EXIT_SPEC_ERROR;
         }
-    })))
-}
-
-async fn set_corruption_tunables(
-    State(_state): State<AppState>,
-    Json(tunables): Json<CorruptionTunables>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would update the corruption tunables
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "tunables": tunables
-    })))
-}
-
-async fn reimage_worker(
-    State(_state): State<AppState>,
-    axum::extract::Path(worker_id): axum::extract::Path<u64>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would reset worker corruption and clear sticky faults
-    Ok(Json(serde_json::json!({
-        "status": "reimaged",
-        "worker_id": worker_id
-    })))
-}
-
-async fn set_can_sim(
-    State(_state): State<AppState>,
-    Json(config): Json<CanSimConfig>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would start/restart the CAN simulator
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "config": config
-    })))
+    };
+    let label = |path: &std::path::Path| path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let comparison = compare_runs(&label(a), &run_a, &label(b), &run_b);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&comparison).unwrap_or_default());
+    } else {
+        print!("{}", comparison.to_markdown());
+    }
+    0
 }
 
-async fn set_modbus_sim(
-    State(_state): State<AppState>,
-    Json(config): Json<ModbusSimConfig>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would start/restart the Modbus simulator
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "config": config
-    })))
-}
+/// Line-based REPL over stdin. The clock ticks in the background between commands;
+/// piped input starts paused so scripts only advance the sim with `ffwd`.
+fn run_interactive(scenario: Option<&str>, seed: Option<u64>, mode: GameMode) -> i32 {
+    use std::io::{BufRead, IsTerminal, Write};
+    use std::sync::mpsc::{self, RecvTimeoutError};
 
-async fn get_gpu_metrics(
-    State(_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // Mock GPU metrics for now
-    Ok(Json(serde_json::json!({
-        "util": 0.75,
-        "vram_used_gb": 8.5,
-        "batches_inflight": 3,
-        "batch_latency_ms": 12.3,
-        "queues": {
-            "can_telemetry": 5,
-            "gpu_pipeline_4": 2
+    let mut session = match ReplSession::new(scenario, seed, mode) {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("Could not start session: {}", e);
+            return EXIT_SPEC_ERROR;
         }
-    })))
-}
-
-async fn set_gpu_tunables(
-    State(_state): State<AppState>,
-    Json(tunables): Json<GpuTunables>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would update the GPU tunables
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "tunables": tunables
-    })))
-}
-
-async fn set_gpu_flags(
-    State(_state): State<AppState>,
-    Json(request): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let mixed_precision = request.get("mixed_precision")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
-    
-    // In a real implementation, this would update the GPU flags
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "mixed_precision": mixed_precision
-    })))
-}
-
-async fn get_events(
-    State(_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // Mock events data for now
-    Ok(Json(serde_json::json!({
-        "eligible": ["vram_ecc_propagation", "pcie_link_flap"],
-        "active": [
-            {
-                "id": "vram_ecc_propagation",
-                "effects": ["DebtPowerMult", "FaultBias"],
-                "ttl": 3600000
-            }
-        ],
-        "recent": [
-            ["vram_ecc_propagation", 1000],
-            ["pcie_link_flap", 500]
-        ]
-    })))
-}
-
-async fn fire_event(
-    State(_state): State<AppState>,
-    axum::extract::Path(event_id): axum::extract::Path<String>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would force-fire a Black Swan event
-    Ok(Json(serde_json::json!({
-        "status": "fired",
-        "event_id": event_id
-    })))
-}
-
-async fn get_debts(
-    State(_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // Mock debts data for now
-    Ok(Json(serde_json::json!({
-        "active": [
-            {
-                "type": "PowerMult",
-                "mult": 1.08,
-                "until_tick": 2000
-            },
-            {
-                "type": "FaultBias",
-                "kind": "StickyConfig",
-                "weight_mult": 1.5,
-                "until_tick": 1500
-            }
-        ]
-    })))
-}
+    };
+    let terminal = std::io::stdin().is_terminal();
+    session.paused = !terminal;
+    if terminal {
+        println!("Colony interactive session, scenario {}. Type 'help' for commands.", scenario.unwrap_or("default"));
+    }
 
-async fn get_research(
-    State(_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // Mock research data for now
-    Ok(Json(serde_json::json!({
-        "pts": 50,
-        "acquired": ["truth_beacon"],
-        "available": [
-            {
-                "id": "dual_run_adjudicator",
-                "name": "Dual-Run Adjudicator",
-                "cost_pts": 15,
-                "requires": ["truth_beacon"]
+    let (tx, rx) = mpsc::channel::<String>();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
             }
-        ],
-        "rituals": [
-            {
-                "id": "ecc_scrub",
-                "name": "ECC Scrub",
-                "time_ms": 30000,
-                "parts": 1
+        }
+    });
+
+    let tick = std::time::Duration::from_millis(16);
+    let mut next_tick = std::time::Instant::now() + tick;
+    loop {
+        if terminal {
+            print!("> ");
+            let _ = std::io::stdout().flush();
+        }
+        let line = loop {
+            match rx.recv_timeout(next_tick.saturating_duration_since(std::time::Instant::now())) {
+                Ok(line) => break line,
+                Err(RecvTimeoutError::Timeout) => {
+                    session.tick();
+                    next_tick += tick;
+                }
+                Err(RecvTimeoutError::Disconnected) => return 0,
             }
-        ]
-    })))
-}
-
-async fn unlock_tech(
-    State(_state): State<AppState>,
-    axum::extract::Path(tech_id): axum::extract::Path<String>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would unlock a tech
-    Ok(Json(serde_json::json!({
-        "status": "unlocked",
-        "tech_id": tech_id
-    })))
-}
-
-async fn start_ritual(
-    State(_state): State<AppState>,
-    axum::extract::Path(ritual_id): axum::extract::Path<String>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would start a ritual
-    Ok(Json(serde_json::json!({
-        "status": "started",
-        "ritual_id": ritual_id,
-        "eta_ms": 30000
-    })))
-}
-
-async fn start_session(
-    State(_state): State<AppState>,
-    Json(game_setup): Json<GameSetup>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would start a new session
-    Ok(Json(serde_json::json!({
-        "status": "started",
-        "scenario": game_setup.scenario.name,
-        "tick_scale": game_setup.tick_scale
-    })))
-}
-
-async fn pause_session(
-    State(_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would pause the session
-    Ok(Json(serde_json::json!({
-        "status": "paused"
-    })))
-}
-
-async fn resume_session(
-    State(_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would resume the session
-    Ok(Json(serde_json::json!({
-        "status": "resumed"
-    })))
-}
-
-async fn set_fast_forward(
-    State(_state): State<AppState>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let on = params.get("on").and_then(|v| v.parse::<bool>().ok()).unwrap_or(false);
-    
-    // In a real implementation, this would set fast forward mode
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "fast_forward": on
-    })))
-}
-
-async fn get_session_status(
-    State(_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // Mock session status
-    Ok(Json(serde_json::json!({
-        "running": true,
-        "fast_forward": false,
-        "sim_time": 1000,
-        "day_count": 5,
-        "sla_pct": 99.2,
-        "victory": false,
-        "doom": false
-    })))
-}
-
-async fn set_autosave_interval(
-    State(_state): State<AppState>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let minutes = params.get("minutes").and_then(|v| v.parse::<u32>().ok()).unwrap_or(5);
-    
-    // In a real implementation, this would set autosave interval
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "autosave_interval_minutes": minutes
-    })))
-}
-
-async fn save_manual(
-    State(_state): State<AppState>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let default_slot = "manual_save".to_string();
-    let slot = params.get("slot").unwrap_or(&default_slot);
-    
-    // In a real implementation, this would save to the specified slot
-    Ok(Json(serde_json::json!({
-        "status": "saved",
-        "slot": slot
-    })))
-}
-
-async fn load_manual(
-    State(_state): State<AppState>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let default_slot = "manual_save".to_string();
-    let slot = params.get("slot").unwrap_or(&default_slot);
-    
-    // In a real implementation, this would load from the specified slot
-    Ok(Json(serde_json::json!({
-        "status": "loaded",
-        "slot": slot
-    })))
+        };
+        match session.execute_line(&line) {
+            Ok(Some(ReplReply::Text(text))) => println!("{}", text),
+            Ok(Some(ReplReply::Quit)) => return 0,
+            Ok(None) => {}
+            Err(e) => eprintln!("error: {}", e),
+        }
+        // Time spent in a long ffwd doesn't owe the background clock catch-up ticks
+        next_tick = next_tick.max(std::time::Instant::now());
+    }
 }
 
-async fn start_replay(
-    State(_state): State<AppState>,
-    Json(request): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let path = request.get("path").and_then(|v| v.as_str()).unwrap_or("replay");
-    
-    // In a real implementation, this would start replay from the specified path/slot
-    Ok(Json(serde_json::json!({
-        "status": "replay_started",
-        "path": path
-    })))
-}
+fn verify_replay_file(path: &std::path::Path, mods_dir: &std::path::Path) -> i32 {
+    let verified = ReplayBundle::load(path).and_then(|bundle| {
+        let report = verify_replay(&bundle, &hash_mods(mods_dir)?)?;
+        Ok((bundle, report))
+    });
+    let (bundle, report) = match verified {
+        Ok(verified) => verified,
+        Err(e) => {
+            eprintln!("Could not verify replay {}: {}", path.display(), e);
+            return EXIT_SPEC_ERROR;
+        }
+    };
 
-async fn stop_replay(
-    State(_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // In a real implementation, this would stop replay
-    Ok(Json(serde_json::json!({
-        "status": "replay_stopped"
-    })))
-}
+    if let Some(label) = bundle.body.spec.meta.label() {
+        println!("Run: {}", label);
+    }
 
-async fn get_metrics_summary(
-    State(_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // Mock comprehensive metrics summary
-    Ok(Json(serde_json::json!({
-        "sla": {
-            "hit_rate": 99.2,
-            "achieved_days": 5,
-            "target_days": 365
-        },
-        "resources": {
-            "power_draw_kw": 850.0,
-            "power_cap_kw": 1000.0,
-            "bandwidth_util": 0.65,
-            "corruption_field": 0.12
-        },
-        "heat": {
-            "yards": [
-                {"heat": 65.0, "cap": 90.0, "throttle": 0.95},
-                {"heat": 72.0, "cap": 90.0, "throttle": 0.88}
-            ]
-        },
-        "gpu": {
-            "util": 0.78,
-            "vram_used_gb": 12.5,
-            "vram_total_gb": 16.0,
-            "batches_inflight": 3
-        },
-        "faults": {
-            "last_tick_faults": 2,
-            "soft_drop_rate": 0.01,
-            "sticky_workers": 0
-        },
-        "black_swans": {
-            "active": ["pcie_link_flap"],
-            "recent": [["vram_ecc_propagation", 1000]]
-        },
-        "research": {
-            "pts": 25,
-            "acquired": ["truth_beacon"],
-            "available": ["dual_run_adjudicator"]
-        }
-    })))
-}
+    println!("Signature: {}", if report.signature_valid { "valid" } else { "INVALID" });
+    for id in &report.mismatched_mods {
+        println!("Mod mismatch: {}", id);
+    }
+    println!(
+        "Claimed:  {:?} after {} ticks, score {}",
+        report.claimed.verdict, report.claimed.ticks_run, report.claimed.score
+    );
+    println!(
+        "Replayed: {:?} after {} ticks, score {}",
+        report.replayed.verdict, report.replayed.ticks_run, report.replayed.score
+    );
+    if report.claimed_state_hash != report.replayed_state_hash {
+        println!("Final state hash differs: {} vs {}", report.claimed_state_hash, report.replayed_state_hash);
+    }
+    if let Some(stream) = &report.rng_divergence {
+        println!("RNG draws differ on the {} stream", stream);
+    }
 
-async fn get_mods(
-    State(_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // Mock mod list
-    Ok(Json(serde_json::json!({
-        "mods": [
-            {
-                "id": "com.example.packetalchemy",
-                "name": "Packet Alchemy",
-                "version": "1.0.0",
-                "authors": ["Example Corp"],
-                "description": "Advanced packet processing operations",
-                "enabled": true,
-                "signature": "valid",
-                "entrypoints": {
-                    "wasm_ops": ["Op_AdaptiveFft", "Op_Anomaly"],
-                    "lua_events": ["on_tick.lua", "on_fault.lua"],
-                    "pipelines": "pipelines.toml",
-                    "blackswans": "events.toml",
-                    "tech": "tech.toml"
-                },
-                "capabilities": {
-                    "sim_time": true,
-                    "rng": true,
-                    "metrics_read": true,
-                    "enqueue_job": true,
-                    "log_debug": true
-                }
-            },
-            {
-                "id": "com.example.thermalboost",
-                "name": "Thermal Boost",
-                "version": "0.5.0",
-                "authors": ["Thermal Corp"],
-                "description": "Enhanced thermal management",
-                "enabled": false,
-                "signature": "valid",
-                "entrypoints": {
-                    "wasm_ops": ["Op_ThermalOptimizer"],
-                    "lua_events": ["on_heat_spike.lua"],
-                    "tech": "tech.toml"
-                },
-                "capabilities": {
-                    "sim_time": true,
-                    "metrics_read": true,
-                    "log_debug": true
-                }
-            }
-        ]
-    })))
+    if report.is_verified() {
+        println!("Replay verified");
+        0
+    } else {
+        println!("Replay rejected");
+        EXIT_REPLAY_REJECTED
+    }
 }
 
-async fn reload_mod(
-    State(_state): State<AppState>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let default_mod_id = "unknown".to_string();
-    let mod_id = params.get("id").unwrap_or(&default_mod_id);
-    
-    // In a real implementation, this would trigger hot reload
-    Ok(Json(serde_json::json!({
-        "status": "reload_started",
-        "mod_id": mod_id,
-        "transaction_id": "tx_12345"
-    })))
+#[derive(Clone)]
+struct AppState {
+    clock: Arc<Synced<SimClock>>,
+    colony: Arc<Synced<Colony>>,
+    memory: Arc<Synced<MemoryAccounting>>,
+    auto_throttle: Arc<Synced<AutoThrottle>>,
+    qos: Arc<Synced<QosQueueing>>,
+    shaping: Arc<Synced<TrafficShaper>>,
+    retries: Arc<Synced<JobRetries>>,
+    dead_letters: Arc<Synced<DeadLetterQueue>>, // dropped and expired jobs, requeued through the sim
+    yard_grid: Arc<Synced<YardGrid>>, // heat exchange and cable latency of the floor yards are placed on
+    tenants: Arc<Synced<TenantLedger>>, // quotas checked when /job and /pipeline/:id/enqueue admit a job
+    energy: Arc<Synced<EnergyLedger>>,
+    forecast: Arc<Synced<DemandForecast>>,
+    autoscaler: Arc<Synced<Autoscaler>>,
+    gpu_faults: Arc<Synced<GpuFaults>>, // batch aborts (ECC, kernel timeouts, PCIe flaps) and their KPIs
+    op_cache: Arc<Synced<OpCache>>,
+    loadgen: Arc<Synced<LoadGenerator>>,
+    io_ingest: Arc<Synced<IoIngest>>, // routes and backpressure for simulator traffic, with its counters
+    journal: Arc<Synced<EventJournal>>, // journal entries and the day/week digests written at rollover
+    arrivals: Arc<Synced<ArrivalLog>>,
+    job_ids: Arc<Synced<JobIdAllocator>>, // every job the API creates is numbered from here
+    rng_audit: Arc<Synced<RngAudit>>,
+    black_swans: Arc<Synced<BlackSwanIndex>>,
+    rituals: Arc<Synced<RitualRuns>>,
+    achievement_registry: Arc<Synced<AchievementRegistry>>,
+    achievements: Arc<Synced<Achievements>>,
+    clock_drift: Arc<Synced<ClockDrift>>,
+    maintenance_lane: Arc<Synced<MaintenanceLane>>,
+    class_reservations: Arc<Synced<ClassReservations>>,
+    op_licenses: Arc<Synced<OpLicenses>>, // per-op concurrency caps and the wait they cause
+    certifications: Arc<Synced<CertificationBoard>>,
+    export_sink: Arc<Synced<ExportSink>>,
+    archive: Arc<Synced<ArchiveStore>>,
+    corruption_topology: Arc<Synced<CorruptionTopology>>,
+    flows: Arc<Synced<FlowTracker>>,
+    scheduler: Arc<Synced<ActiveScheduler>>,
+    faults: Arc<Synced<FaultKpi>>,
+    debts: Arc<Synced<Debts>>,
+    effective_capacity: Arc<Synced<EffectiveCapacity>>, // caps once debts apply, and when each debt clears
+    research: Arc<Synced<ResearchState>>,
+    tech_tree: Arc<Synced<TechTree>>,
+    win_loss: Arc<Synced<WinLossState>>,
+    view: Arc<RwLock<SimView>>, // workers, yards, queue depths and KPIs as of the last mirror
+    run_progress: ProgressHandle, // fast-forward run progress, shared with the sim thread
+    session: Arc<Synced<SessionCtl>>,
+    pause_policies: Arc<Synced<PausePolicies>>,
+    pause_monitor: Arc<Synced<PauseMonitor>>,
+    parse_counters: ParseCounters, // shared with the IO parsers, which update it themselves
+    io_registry: Arc<IoRegistry>, // sources and parsers io_channels may name; embedders register theirs before serving
+    io_channels: Arc<RwLock<Vec<IoChannelSpec>>>,
+    mode: GameMode,
+    server_config: Arc<ServerConfig>, // request limits, read by ValidJson
+    save_protection: Arc<SaveProtection>, // from --game-config; seals manual saves and opens saves and replays
+    schedule: Arc<RwLock<SessionSchedule>>, // polled by the session scheduler task
+    pending_commands: Arc<RwLock<Vec<TimedCommand>>>, // accepted for a later tick; carried by live migration
+    replica: Arc<RwLock<ReplicaStatus>>, // primary/observer role and link health
+    notifications: tokio::sync::broadcast::Sender<Notification>, // fed by the sim thread, read by /ws/events
+    sim: Option<SimHandle>, // None on an observer, which serves the primary's state and runs no sim
+}
+
+impl FromRef<AppState> for Arc<ServerConfig> {
+    fn from_ref(state: &AppState) -> Self {
+        state.server_config.clone()
+    }
 }
 
-async fn enable_mod(
-    State(_state): State<AppState>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let default_mod_id = "unknown".to_string();
-    let mod_id = params.get("id").unwrap_or(&default_mod_id);
-    let enabled = params.get("on").and_then(|v| v.parse::<bool>().ok()).unwrap_or(false);
-    
-    // In a real implementation, this would enable/disable the mod
-    Ok(Json(serde_json::json!({
-        "status": "ok",
-        "mod_id": mod_id,
-        "enabled": enabled
-    })))
-}
+impl AppState {
+    /// The local sim; observers have none and refuse writes before they get here
+    fn sim(&self) -> Result<&SimHandle, ApiError> {
+        self.sim.as_ref().ok_or_else(|| ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "This instance doesn't run a simulation"))
+    }
 
-async fn dryrun_mod(
-    State(_state): State<AppState>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let default_mod_id = "unknown".to_string();
-    let mod_id = params.get("id").unwrap_or(&default_mod_id);
-    let ticks = params.get("ticks").and_then(|v| v.parse::<u32>().ok()).unwrap_or(120);
-    
-    // In a real implementation, this would run a dry run simulation
-    Ok(Json(serde_json::json!({
-        "status": "dryrun_completed",
-        "mod_id": mod_id,
-        "ticks_simulated": ticks,
-        "kpi_deltas": {
-            "deadline_hit_rate_change": 0.5,
-            "power_draw_change": 2.1,
-            "bandwidth_util_change": 0.3,
-            "corruption_field_change": 0.01,
-            "heat_levels_change": [1.2, 0.8]
-        },
-        "success": true,
-        "warnings": ["Minor bandwidth utilization increase"],
-        "errors": []
-    })))
+    /// Change one world resource between ticks. Only what `f` touches changes, so the served
+    /// copy lagging the world can't roll anything back; it shows the edit once this returns.
+    async fn update<T: Resource, R: Send + 'static>(&self, f: impl FnOnce(&mut T) -> R + Send + 'static) -> Result<R, ApiError> {
+        self.sim()?.with_world(move |world| f(&mut world.resource_mut::<T>())).await
+    }
 }
 
-async fn get_mod_docs(
-    State(_state): State<AppState>,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let default_mod_id = "all".to_string();
-    let mod_id = params.get("id").unwrap_or(&default_mod_id);
-    
-    // In a real implementation, this would return generated API docs
-    Ok(Json(serde_json::json!({
-        "mod_id": mod_id,
-        "sdk_version": "1.0.0",
-        "wasm_abi": {
-            "version": 1,
-            "functions": [
-                {
-                    "name": "colony_op_init",
-                    "signature": "extern \"C\" fn colony_op_init(ctx: *mut OpCtx) -> i32",
-                    "description": "Initialize the operation with the given context",
-                    "return_codes": [
-                        {"code": 0, "meaning": "Success", "description": "Operation initialized successfully"},
-                        {"code": -1, "meaning": "Error", "description": "Initialization failed"}
-                    ]
-                }
-            ]
-        },
-        "lua_api": {
-            "global_functions": [
-                {
-                    "name": "colony.get_sim_time",
-                    "signature": "get_sim_time() -> u64",
-                    "description": "Get current simulation time in ticks",
-                    "requires_capability": "sim_time"
-                }
-            ]
-        }
-    })))
+/// Every served copy the sim thread keeps in step with its world
+fn mirrors(state: &AppState) -> Vec<Arc<dyn Mirror>> {
+    vec![
+        state.clock.clone(),
+        state.colony.clone(),
+        state.memory.clone(),
+        state.auto_throttle.clone(),
+        state.qos.clone(),
+        state.shaping.clone(),
+        state.retries.clone(),
+        state.dead_letters.clone(),
+        state.yard_grid.clone(),
+        state.tenants.clone(),
+        state.energy.clone(),
+        state.forecast.clone(),
+        state.autoscaler.clone(),
+        state.gpu_faults.clone(),
+        state.op_cache.clone(),
+        state.loadgen.clone(),
+        state.io_ingest.clone(),
+        state.journal.clone(),
+        state.arrivals.clone(),
+        state.job_ids.clone(),
+        state.rng_audit.clone(),
+        state.black_swans.clone(),
+        state.rituals.clone(),
+        state.achievement_registry.clone(),
+        state.achievements.clone(),
+        state.clock_drift.clone(),
+        state.maintenance_lane.clone(),
+        state.class_reservations.clone(),
+        state.op_licenses.clone(),
+        state.certifications.clone(),
+        state.export_sink.clone(),
+        state.archive.clone(),
+        state.corruption_topology.clone(),
+        state.flows.clone(),
+        state.session.clone(),
+        state.pause_policies.clone(),
+        state.pause_monitor.clone(),
+        state.scheduler.clone(),
+        state.faults.clone(),
+        state.debts.clone(),
+        state.effective_capacity.clone(),
+        state.research.clone(),
+        state.tech_tree.clone(),
+        state.win_loss.clone(),
+    ]
 }
@@ -0,0 +1,175 @@
+use crate::AppState;
+use crate::limits::ApiError;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::StatusCode,
+    response::Response,
+};
+use colony_core::{ModLoader, Notification, Topic};
+use colony_modsdk::Capabilities;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use tokio::sync::broadcast;
+
+/// Notifications buffered per subscriber; one that falls further behind is told how many it missed
+pub const NOTIFICATION_BACKLOG: usize = 256;
+
+/// Query of `GET /ws/events`
+#[derive(Deserialize)]
+pub struct EventsParams {
+    #[serde(default)]
+    topics: Option<String>, // comma-separated; every topic the connection may read when absent
+    #[serde(default, rename = "mod")]
+    mod_id: Option<String>, // a mod's companion app, held to that mod's capabilities
+}
+
+/// What a client may send once connected
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ClientMessage {
+    Subscribe(Vec<String>),
+    Unsubscribe(Vec<String>),
+}
+
+/// One connection's topics, and the capabilities they're checked against
+#[derive(Debug)]
+struct Subscriber {
+    caps: Option<Capabilities>, // None for external tools, which may read what the GET endpoints serve
+    topics: BTreeSet<Topic>,
+}
+
+impl Subscriber {
+    fn may_read(&self, topic: Topic) -> bool {
+        self.caps.as_ref().is_none_or(|caps| topic.allowed(caps))
+    }
+
+    fn subscribe(&mut self, names: &[String]) -> Result<(), ApiError> {
+        let topics = parse_topics(names)?;
+        if let Some(denied) = topics.iter().find(|topic| !self.may_read(**topic)) {
+            return Err(ApiError::new(StatusCode::FORBIDDEN, format!(
+                "Topic {} needs the {} capability, which this mod doesn't have", denied.name(), denied.capability()
+            )));
+        }
+        self.topics.extend(topics);
+        Ok(())
+    }
+
+    fn unsubscribe(&mut self, names: &[String]) -> Result<(), ApiError> {
+        for topic in parse_topics(names)? {
+            self.topics.remove(&topic);
+        }
+        Ok(())
+    }
+}
+
+fn parse_topics(names: &[String]) -> Result<Vec<Topic>, ApiError> {
+    names.iter()
+        .map(|name| Topic::parse(name).ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, format!(
+            "Unknown topic '{}'; expected one of {}", name, Topic::all().map(|t| t.name()).join(", ")
+        ))))
+        .collect()
+}
+
+/// Stream sim notifications on the chosen topics as JSON text frames
+pub async fn events_ws(
+    State(state): State<AppState>,
+    Query(params): Query<EventsParams>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let caps = match params.mod_id {
+        Some(mod_id) => Some(mod_capabilities(&state, mod_id).await?),
+        None => None,
+    };
+    let mut subscriber = Subscriber { caps, topics: BTreeSet::new() };
+    let names: Vec<String> = match &params.topics {
+        Some(topics) => topics.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect(),
+        None => Topic::all().into_iter().filter(|t| subscriber.may_read(*t)).map(|t| t.name().to_string()).collect(),
+    };
+    subscriber.subscribe(&names)?;
+    let notifications = state.notifications.subscribe();
+    Ok(ws.on_upgrade(move |socket| serve(socket, subscriber, notifications)))
+}
+
+async fn mod_capabilities(state: &AppState, mod_id: String) -> Result<Capabilities, ApiError> {
+    let caps = state.sim()?
+        .with_world(move |world| {
+            world.get_resource::<ModLoader>()?
+                .enabled()
+                .find(|(manifest, _)| manifest.id == mod_id)
+                .map(|(manifest, _)| manifest.capabilities.clone())
+        })
+        .await?;
+    caps.ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No enabled mod by that id"))
+}
+
+async fn serve(mut socket: WebSocket, mut subscriber: Subscriber, mut notifications: broadcast::Receiver<Notification>) {
+    loop {
+        let outgoing = tokio::select! {
+            received = notifications.recv() => match received {
+                Ok(notification) if subscriber.topics.contains(&notification.topic) => serde_json::to_string(&notification).ok(),
+                Ok(_) => None,
+                Err(broadcast::error::RecvError::Lagged(missed)) => Some(serde_json::json!({ "lagged": missed }).to_string()),
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => Some(handle_client_message(&mut subscriber, &text)),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => None,
+            },
+        };
+        if let Some(text) = outgoing {
+            if socket.send(Message::Text(text)).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Apply a subscribe/unsubscribe message; the reply lists the topics now subscribed, or what was refused
+fn handle_client_message(subscriber: &mut Subscriber, text: &str) -> String {
+    let result = serde_json::from_str::<ClientMessage>(text)
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))
+        .and_then(|message| match message {
+            ClientMessage::Subscribe(names) => subscriber.subscribe(&names),
+            ClientMessage::Unsubscribe(names) => subscriber.unsubscribe(&names),
+        });
+    match result {
+        Ok(()) => serde_json::json!({ "topics": subscriber.topics.iter().map(|t| t.name()).collect::<Vec<_>>() }).to_string(),
+        Err(e) => serde_json::json!({ "error": e.message }).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_mod_connections_are_held_to_capabilities() {
+        let caps = Capabilities { metrics_read: true, ..Default::default() };
+        let mut subscriber = Subscriber { caps: Some(caps), topics: BTreeSet::new() };
+        subscriber.subscribe(&names(&["faults", "black_swans"])).unwrap();
+        assert_eq!(subscriber.subscribe(&names(&["journal"])).unwrap_err().status, StatusCode::FORBIDDEN);
+        assert_eq!(subscriber.subscribe(&names(&["weather"])).unwrap_err().status, StatusCode::BAD_REQUEST);
+        assert_eq!(subscriber.topics.len(), 2);
+
+        let mut tool = Subscriber { caps: None, topics: BTreeSet::new() };
+        tool.subscribe(&names(&["journal"])).unwrap();
+    }
+
+    #[test]
+    fn test_client_messages_change_topics() {
+        let mut subscriber = Subscriber { caps: None, topics: BTreeSet::new() };
+        let reply = handle_client_message(&mut subscriber, r#"{"subscribe": ["research", "faults"]}"#);
+        assert_eq!(reply, r#"{"topics":["faults","research"]}"#);
+        let reply = handle_client_message(&mut subscriber, r#"{"unsubscribe": ["faults"]}"#);
+        assert_eq!(reply, r#"{"topics":["research"]}"#);
+        assert!(handle_client_message(&mut subscriber, "nope").contains("error"));
+    }
+}
@@ -0,0 +1,371 @@
+use crate::AppState;
+use crate::limits::ApiError;
+use crate::sim::SimView;
+use axum::{
+    extract::Request,
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use colony_core::{
+    ArchiveStore, AutoThrottle, BlackSwanIndex, CertificationBoard, ClockDrift, Colony, CorruptionTopology, ExportSink,
+    GameMode, JobRetries, MaintenanceLane, MemoryAccounting, QosQueueing, RitualRuns, SessionCtl, SimClock, TenantLedger, EnergyLedger, DemandForecast, Autoscaler, EventJournal, TrafficShaper, GpuFaults, OpCache,
+    ClassReservations, OpLicenses, ActiveScheduler, FaultKpi, Debts, EffectiveCapacity, ResearchState, WinLossState,
+};
+use colony_io::IoChannelSpec;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+
+/// Bumped when the frame layout changes; observers refuse primaries speaking another version
+pub const REPLICA_FORMAT_VERSION: u32 = 3;
+/// One frame per sim tick
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+/// Frames buffered per observer; a slower observer skips ahead, since every frame is complete
+const FRAME_BACKLOG: usize = 64;
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// First line a primary sends on a replica connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicaHello {
+    pub format_version: u32,
+    pub mode: GameMode,
+}
+
+/// The state an observer serves, as of one tick on the primary. Sent as one JSON line per tick.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicaFrame {
+    pub seq: u64,
+    pub sent_at: DateTime<Utc>,
+    pub clock: SimClock,
+    pub colony: Colony,
+    pub session: SessionCtl,
+    pub memory: MemoryAccounting,
+    pub auto_throttle: AutoThrottle,
+    pub qos: QosQueueing,
+    pub shaping: TrafficShaper,
+    pub retries: JobRetries,
+    pub tenants: TenantLedger,
+    pub energy: EnergyLedger,
+    pub forecast: DemandForecast,
+    pub autoscaler: Autoscaler,
+    pub gpu_faults: GpuFaults,
+    pub op_cache: OpCache,
+    pub io_channels: Vec<IoChannelSpec>,
+    pub journal: EventJournal,
+    pub black_swans: BlackSwanIndex,
+    pub rituals: RitualRuns,
+    pub clock_drift: ClockDrift,
+    pub maintenance_lane: MaintenanceLane,
+    pub class_reservations: ClassReservations,
+    pub op_licenses: OpLicenses,
+    pub certifications: CertificationBoard,
+    pub export_sink: ExportSink,
+    pub archive: ArchiveStore,
+    pub corruption_topology: CorruptionTopology,
+    pub scheduler: ActiveScheduler,
+    pub faults: FaultKpi,
+    pub debts: Debts,
+    pub effective_capacity: EffectiveCapacity,
+    pub research: ResearchState,
+    pub win_loss: WinLossState,
+    pub view: SimView,
+}
+
+impl ReplicaFrame {
+    pub async fn capture(state: &AppState, seq: u64) -> Self {
+        Self {
+            seq,
+            sent_at: Utc::now(),
+            clock: state.clock.read().await.clone(),
+            colony: state.colony.read().await.clone(),
+            session: state.session.read().await.clone(),
+            memory: state.memory.read().await.clone(),
+            auto_throttle: state.auto_throttle.read().await.clone(),
+            qos: state.qos.read().await.clone(),
+            shaping: state.shaping.read().await.clone(),
+            retries: state.retries.read().await.clone(),
+            tenants: state.tenants.read().await.clone(),
+            energy: state.energy.read().await.clone(),
+            forecast: state.forecast.read().await.clone(),
+            autoscaler: state.autoscaler.read().await.clone(),
+            gpu_faults: state.gpu_faults.read().await.clone(),
+            op_cache: state.op_cache.read().await.clone(),
+            io_channels: state.io_channels.read().await.clone(),
+            journal: state.journal.read().await.clone(),
+            black_swans: state.black_swans.read().await.clone(),
+            rituals: state.rituals.read().await.clone(),
+            clock_drift: state.clock_drift.read().await.clone(),
+            maintenance_lane: state.maintenance_lane.read().await.clone(),
+            class_reservations: state.class_reservations.read().await.clone(),
+            op_licenses: state.op_licenses.read().await.clone(),
+            certifications: state.certifications.read().await.clone(),
+            export_sink: state.export_sink.read().await.clone(),
+            archive: state.archive.read().await.clone(),
+            corruption_topology: state.corruption_topology.read().await.clone(),
+            scheduler: state.scheduler.read().await.clone(),
+            faults: state.faults.read().await.clone(),
+            debts: state.debts.read().await.clone(),
+            effective_capacity: state.effective_capacity.read().await.clone(),
+            research: state.research.read().await.clone(),
+            win_loss: state.win_loss.read().await.clone(),
+            view: state.view.read().await.clone(),
+        }
+    }
+
+    /// Overwrite the observer's copy with the primary's
+    pub async fn apply(self, state: &AppState) {
+        state.clock.replace(self.clock).await;
+        state.colony.replace(self.colony).await;
+        state.session.replace(self.session).await;
+        state.memory.replace(self.memory).await;
+        state.auto_throttle.replace(self.auto_throttle).await;
+        state.qos.replace(self.qos).await;
+        state.shaping.replace(self.shaping).await;
+        state.retries.replace(self.retries).await;
+        state.tenants.replace(self.tenants).await;
+        state.energy.replace(self.energy).await;
+        state.forecast.replace(self.forecast).await;
+        state.autoscaler.replace(self.autoscaler).await;
+        state.gpu_faults.replace(self.gpu_faults).await;
+        state.op_cache.replace(self.op_cache).await;
+        *state.io_channels.write().await = self.io_channels;
+        state.journal.replace(self.journal).await;
+        state.black_swans.replace(self.black_swans).await;
+        state.rituals.replace(self.rituals).await;
+        state.clock_drift.replace(self.clock_drift).await;
+        state.maintenance_lane.replace(self.maintenance_lane).await;
+        state.class_reservations.replace(self.class_reservations).await;
+        state.op_licenses.replace(self.op_licenses).await;
+        state.certifications.replace(self.certifications).await;
+        state.export_sink.replace(self.export_sink).await;
+        state.archive.replace(self.archive).await;
+        state.corruption_topology.replace(self.corruption_topology).await;
+        state.scheduler.replace(self.scheduler).await;
+        state.faults.replace(self.faults).await;
+        state.debts.replace(self.debts).await;
+        state.effective_capacity.replace(self.effective_capacity).await;
+        state.research.replace(self.research).await;
+        state.win_loss.replace(self.win_loss).await;
+        *state.view.write().await = self.view;
+    }
+}
+
+/// This instance's place in a replica set, served at `GET /replica/status`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "role", rename_all = "snake_case")]
+pub enum ReplicaStatus {
+    Standalone,
+    Primary {
+        port: u16,
+        observers: usize,
+        frames_sent: u64,
+    },
+    Observer {
+        primary: String,
+        connected: bool,
+        primary_mode: Option<GameMode>,
+        last_seq: Option<u64>,
+        last_frame_at: Option<DateTime<Utc>>,
+        lag_ms: Option<i64>, // primary send time to apply time of the last frame
+    },
+}
+
+impl ReplicaStatus {
+    pub fn observer(primary: &str) -> Self {
+        ReplicaStatus::Observer {
+            primary: primary.to_string(),
+            connected: false,
+            primary_mode: None,
+            last_seq: None,
+            last_frame_at: None,
+            lag_ms: None,
+        }
+    }
+}
+
+fn to_line<T: Serialize>(value: &T) -> Option<Arc<str>> {
+    let mut line = serde_json::to_string(value).ok()?;
+    line.push('\n');
+    Some(line.into())
+}
+
+/// Parse the primary's hello, refusing a frame format this build can't read
+pub fn check_hello(line: &str) -> Result<ReplicaHello, String> {
+    let hello: ReplicaHello = serde_json::from_str(line).map_err(|e| format!("Not a replica stream: {}", e))?;
+    if hello.format_version != REPLICA_FORMAT_VERSION {
+        return Err(format!(
+            "Primary sends replica format {} but this build reads {}",
+            hello.format_version, REPLICA_FORMAT_VERSION
+        ));
+    }
+    Ok(hello)
+}
+
+/// Stream a frame per tick to every observer that connects on `port`. Frames are only
+/// captured while someone is listening, so an idle primary pays nothing.
+pub async fn serve_primary(state: AppState, port: u16) {
+    let listener = match TcpListener::bind(("0.0.0.0", port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to serve replicas on port {}: {}", port, e);
+            return;
+        }
+    };
+    println!("Streaming state to observers on tcp://0.0.0.0:{}", port);
+    *state.replica.write().await = ReplicaStatus::Primary { port, observers: 0, frames_sent: 0 };
+
+    let (frames, _) = broadcast::channel::<Arc<str>>(FRAME_BACKLOG);
+    let publisher = frames.clone();
+    let publish_state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(FRAME_INTERVAL);
+        let mut seq = 0;
+        loop {
+            interval.tick().await;
+            if publisher.receiver_count() == 0 {
+                continue;
+            }
+            seq += 1;
+            if let Some(line) = to_line(&ReplicaFrame::capture(&publish_state, seq).await) {
+                let _ = publisher.send(line);
+                if let ReplicaStatus::Primary { frames_sent, .. } = &mut *publish_state.replica.write().await {
+                    *frames_sent = seq;
+                }
+            }
+        }
+    });
+
+    let hello = to_line(&ReplicaHello { format_version: REPLICA_FORMAT_VERSION, mode: state.mode });
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    eprintln!("Replica accept failed: {}", e);
+                    continue;
+                }
+            };
+            let rx = frames.subscribe();
+            let hello = hello.clone();
+            let state = state.clone();
+            tokio::spawn(async move {
+                set_observer_count(&state, 1).await;
+                println!("Observer {} connected", peer);
+                if let Err(e) = feed_observer(stream, hello, rx).await {
+                    eprintln!("Observer {} dropped: {}", peer, e);
+                }
+                set_observer_count(&state, -1).await;
+            });
+        }
+    });
+}
+
+async fn set_observer_count(state: &AppState, delta: isize) {
+    if let ReplicaStatus::Primary { observers, .. } = &mut *state.replica.write().await {
+        *observers = observers.saturating_add_signed(delta);
+    }
+}
+
+async fn feed_observer(mut stream: TcpStream, hello: Option<Arc<str>>, mut rx: broadcast::Receiver<Arc<str>>) -> std::io::Result<()> {
+    stream.set_nodelay(true)?;
+    if let Some(hello) = hello {
+        stream.write_all(hello.as_bytes()).await?;
+    }
+    loop {
+        match rx.recv().await {
+            Ok(line) => stream.write_all(line.as_bytes()).await?,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                eprintln!("Observer fell {} frames behind; skipping to the latest", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// Follow `primary` (host:port) and mirror its frames into this instance's state,
+/// reconnecting with backoff whenever the link drops
+pub fn spawn_observer(state: AppState, primary: String) {
+    tokio::spawn(async move {
+        let mut delay = Duration::from_secs(1);
+        loop {
+            match follow_primary(&state, &primary).await {
+                Ok(()) => eprintln!("Primary {} closed the replica stream", primary),
+                Err(e) => eprintln!("Replica link to {} failed: {}", primary, e),
+            }
+            if let ReplicaStatus::Observer { connected, .. } = &mut *state.replica.write().await {
+                if *connected {
+                    delay = Duration::from_secs(1);
+                }
+                *connected = false;
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    });
+}
+
+async fn follow_primary(state: &AppState, primary: &str) -> Result<(), String> {
+    let stream = TcpStream::connect(primary).await.map_err(|e| e.to_string())?;
+    let mut lines = BufReader::new(stream).lines();
+    let hello = match lines.next_line().await.map_err(|e| e.to_string())? {
+        Some(line) => check_hello(&line)?,
+        None => return Ok(()),
+    };
+    println!("Observing primary {} ({:?} mode)", primary, hello.mode);
+    if let ReplicaStatus::Observer { connected, primary_mode, .. } = &mut *state.replica.write().await {
+        *connected = true;
+        *primary_mode = Some(hello.mode);
+    }
+    while let Some(line) = lines.next_line().await.map_err(|e| e.to_string())? {
+        let frame: ReplicaFrame = serde_json::from_str(&line).map_err(|e| format!("Bad frame: {}", e))?;
+        let (seq, sent_at) = (frame.seq, frame.sent_at);
+        frame.apply(state).await;
+        let now = Utc::now();
+        if let ReplicaStatus::Observer { last_seq, last_frame_at, lag_ms, .. } = &mut *state.replica.write().await {
+            *last_seq = Some(seq);
+            *last_frame_at = Some(now);
+            *lag_ms = Some((now - sent_at).num_milliseconds());
+        }
+    }
+    Ok(())
+}
+
+/// Observers serve reads only; anything that would change state belongs on the primary
+pub async fn reject_writes(req: Request, next: Next) -> Response {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(req).await;
+    }
+    ApiError::new(
+        StatusCode::FORBIDDEN,
+        format!("{} {} refused: this instance is a read-only observer; send changes to the primary", req.method(), req.uri().path()),
+    )
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hello_version_is_checked() {
+        let line = to_line(&ReplicaHello { format_version: REPLICA_FORMAT_VERSION, mode: GameMode::Creative }).unwrap();
+        assert_eq!(check_hello(line.trim_end()).unwrap().mode, GameMode::Creative);
+        let newer = serde_json::json!({ "format_version": REPLICA_FORMAT_VERSION + 1, "mode": GameMode::Standard });
+        assert!(check_hello(&newer.to_string()).unwrap_err().contains("format"));
+        assert!(check_hello("GET / HTTP/1.1").is_err());
+    }
+
+    #[test]
+    fn test_status_reports_role() {
+        let status = serde_json::to_value(ReplicaStatus::observer("10.0.0.5:9100")).unwrap();
+        assert_eq!(status["role"], "observer");
+        assert_eq!(status["connected"], false);
+        let standalone = serde_json::to_value(ReplicaStatus::Standalone).unwrap();
+        assert_eq!(standalone["role"], "standalone");
+    }
+}
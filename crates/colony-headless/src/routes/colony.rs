@@ -0,0 +1,347 @@
+use crate::AppState;
+use crate::limits::{ApiError, JobLimits, Validate, ValidJson};
+use crate::sim::{WorkerStatus, YardStatus};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, put},
+    Router,
+};
+use colony_core::{SimClock, TickScale, Colony, CorruptionTunables, FaultKpi, MemoryAccounting, MemoryCaps, AutoThrottle, set_corruption_field, CorruptionTopology, MetricRegistry, MetricId, SeedStream, inspect_entities, EntityInspection, DEFAULT_INSPECT_LIMIT};
+use serde::{Deserialize, Serialize};
+
+/// Clock, colony meters, corruption and debug inspection
+pub(super) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/state/summary", get(get_summary))
+        .route("/clock/scale", put(set_scale))
+        .route("/clock", get(get_clock))
+        .route("/dispatch/auto-throttle", get(get_auto_throttle).put(set_auto_throttle))
+        .route("/metrics/faults", get(get_fault_metrics))
+        .route("/metrics/clock-drift", get(get_clock_drift))
+        .route("/metrics/ids", get(get_metric_ids))
+        .route("/metrics/memory", get(get_memory_metrics))
+        .route("/metrics/memory/caps", put(set_memory_caps))
+        .route("/corruption/tunables", put(set_corruption_tunables))
+        .route("/corruption/field", put(set_corruption))
+        .route("/corruption/topology", get(get_corruption_topology))
+        .route("/rng/audit", get(get_rng_audit))
+        .route("/debug/entities", get(debug_entities))
+        .route("/metrics/summary", get(get_metrics_summary))
+}
+
+#[derive(Serialize)]
+struct SummaryResponse {
+    clock: SimClock,
+    colony: Colony,
+    workers: Vec<WorkerStatus>,
+    yards: Vec<YardStatus>,
+}
+
+#[derive(Deserialize)]
+struct TimeScaleRequest {
+    scale: String,
+    value: Option<u64>,
+}
+
+impl Validate for TimeScaleRequest {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        let max = match self.scale.as_str() {
+            "realtime" => return Ok(()),
+            "seconds" => u64::MAX,
+            "days" => u16::MAX as u64,
+            "years" => u8::MAX as u64,
+            other => return Err(format!("Unknown scale '{}'; expected realtime, seconds, days or years", other)),
+        };
+        match self.value {
+            Some(0) => Err("value must be at least 1".to_string()),
+            Some(v) if v > max => Err(format!("value {} is too large for {} (max {})", v, self.scale, max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+async fn get_summary(State(state): State<AppState>) -> Result<Json<SummaryResponse>, StatusCode> {
+    let clock = state.clock.read().await.clone();
+    let colony = state.colony.read().await.clone();
+    let view = state.view.read().await;
+
+    Ok(Json(SummaryResponse {
+        clock,
+        colony,
+        workers: view.workers.clone(),
+        yards: view.yards.clone(),
+    }))
+}
+
+async fn set_scale(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<TimeScaleRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    // Validated: the scale is known and the value fits it
+    let tick_scale = match request.scale.as_str() {
+        "seconds" => TickScale::Seconds(request.value.unwrap_or(1)),
+        "days" => TickScale::Days(request.value.unwrap_or(1) as u16),
+        "years" => TickScale::Years(request.value.unwrap_or(1) as u8),
+        _ => TickScale::RealTime,
+    };
+    state.update(move |clock: &mut SimClock| clock.tick_scale = tick_scale).await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "scale": request.scale,
+        "value": request.value
+    })))
+}
+
+async fn get_clock(State(state): State<AppState>) -> Result<Json<SimClock>, StatusCode> {
+    let clock = state.clock.read().await;
+    Ok(Json(clock.clone()))
+}
+
+async fn get_memory_metrics(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let memory = state.memory.read().await;
+    let categories: serde_json::Map<String, serde_json::Value> = memory.usage.iter()
+        .map(|u| (u.category.name().to_string(), serde_json::json!({
+            "bytes": u.bytes,
+            "cap_bytes": u.cap_bytes,
+            "near_cap": u.near_cap,
+        })))
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "total_bytes": memory.total_bytes,
+        "peak_total_bytes": memory.peak_total_bytes,
+        "last_sample_tick": memory.last_sample_tick,
+        "sample_every_ticks": memory.sample_every_ticks,
+        "categories": categories,
+        "warnings": memory.warnings,
+    })))
+}
+
+async fn set_memory_caps(
+    State(state): State<AppState>,
+    Json(caps): Json<MemoryCaps>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if !(0.0..=1.0).contains(&caps.warn_fraction) {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "warn_fraction must be between 0 and 1"));
+    }
+    let new_caps = caps.clone();
+    state.update(move |memory: &mut MemoryAccounting| memory.caps = new_caps).await?;
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "caps": caps,
+    })))
+}
+
+async fn get_auto_throttle(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let throttle = state.auto_throttle.read().await;
+    Ok(Json(serde_json::json!({
+        "enabled": throttle.enabled,
+        "scale": throttle.scale,
+        "gains": { "kp": throttle.kp, "ki": throttle.ki, "kd": throttle.kd },
+        "targets": {
+            "power_headroom": throttle.power_headroom_target,
+            "heat_margin": throttle.heat_margin_target,
+            "deadline_slack": throttle.deadline_slack_target,
+        },
+        "min_scale": throttle.min_scale,
+        "decisions": throttle.decisions,
+    })))
+}
+
+async fn set_auto_throttle(
+    State(state): State<AppState>,
+    Json(request): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let min_scale = request.get("min_scale").and_then(|v| v.as_f64());
+    if min_scale.is_some_and(|min_scale| !(0.0..=1.0).contains(&min_scale)) {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "min_scale must be between 0 and 1"));
+    }
+    let (enabled, scale) = state
+        .update(move |throttle: &mut AutoThrottle| {
+            if let Some(enabled) = request.get("enabled").and_then(|v| v.as_bool()) {
+                throttle.set_enabled(enabled);
+            }
+            if let Some(kp) = request.get("kp").and_then(|v| v.as_f64()) {
+                throttle.kp = kp as f32;
+            }
+            if let Some(ki) = request.get("ki").and_then(|v| v.as_f64()) {
+                throttle.ki = ki as f32;
+            }
+            if let Some(kd) = request.get("kd").and_then(|v| v.as_f64()) {
+                throttle.kd = kd as f32;
+            }
+            if let Some(min_scale) = min_scale {
+                throttle.min_scale = min_scale as f32;
+            }
+            (throttle.enabled, throttle.scale)
+        })
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "enabled": enabled,
+        "scale": scale,
+    })))
+}
+
+async fn get_fault_metrics(State(state): State<AppState>) -> Result<Json<FaultKpi>, StatusCode> {
+    Ok(Json(state.faults.read().await.clone()))
+}
+
+async fn get_clock_drift(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let drift = state.clock_drift.read().await;
+    Ok(Json(serde_json::json!({
+        "max_abs_offset_ms": drift.max_abs_offset_ms,
+        "mean_abs_offset_ms": drift.mean_abs_offset_ms,
+        "deadline_checks": drift.deadline_checks,
+        "false_hits": drift.false_hits,
+        "false_misses": drift.false_misses,
+        "tunables": drift.tunables,
+    })))
+}
+
+/// Metric names triggers, illusions and sensors may use: the built-ins plus mod metrics reported so far
+async fn get_metric_ids(State(state): State<AppState>) -> Result<Json<Vec<String>>, ApiError> {
+    let names = match state.sim() {
+        Ok(sim) => sim.with_world(|world| world.get_resource::<MetricRegistry>().map(|r| r.names())).await?,
+        Err(_) => None,
+    };
+    Ok(Json(names.unwrap_or_else(MetricId::builtin_names)))
+}
+
+async fn set_corruption_tunables(
+    State(state): State<AppState>,
+    Json(tunables): Json<CorruptionTunables>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let new_tunables = tunables.clone();
+    state.update(move |colony: &mut Colony| colony.corruption_tun = new_tunables).await?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "tunables": tunables
+    })))
+}
+
+#[derive(Deserialize)]
+struct SetCorruptionRequest {
+    value: f32,
+}
+
+/// Creative only: set the corruption field directly
+async fn set_corruption(
+    State(state): State<AppState>,
+    Json(request): Json<SetCorruptionRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let mode = state.mode;
+    let corruption_field = state
+        .update(move |colony: &mut Colony| {
+            set_corruption_field(mode, colony, None, request.value).map(|_| colony.corruption_field)
+        })
+        .await?
+        .map_err(|e| ApiError::new(StatusCode::FORBIDDEN, e.to_string()))?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "corruption_field": corruption_field
+    })))
+}
+
+async fn get_corruption_topology(State(state): State<AppState>) -> Json<CorruptionTopology> {
+    Json(state.corruption_topology.read().await.clone())
+}
+
+/// Draw counts and stream positions of every seeded stream, next to the seeds they roll from
+async fn get_rng_audit(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let colony = state.colony.read().await;
+    let seeds: std::collections::BTreeMap<&str, u64> = SeedStream::ALL.iter().map(|s| (s.name(), colony.seed_for(*s))).collect();
+    let audit = state.rng_audit.read().await;
+    Json(serde_json::json!({
+        "root_seed": colony.seed,
+        "seeds": seeds,
+        "total_draws": audit.total_draws(),
+        "streams": audit.streams,
+    }))
+}
+
+/// Live component data of the entities that have every `filter` component, e.g. `?filter=Worker&limit=20`
+async fn debug_entities(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<EntityInspection>, ApiError> {
+    state.server_config.require_admin(&headers)?;
+    let filter = params.get("filter").cloned().unwrap_or_default();
+    let limit = match params.get("limit") {
+        Some(limit) => limit.parse::<usize>().map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, format!("Bad limit '{}'", limit)))?,
+        None => DEFAULT_INSPECT_LIMIT,
+    };
+    let inspection = state.sim()?
+        .with_world(move |world| inspect_entities(world, &filter, limit))
+        .await?
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(Json(inspection))
+}
+
+async fn get_metrics_summary(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let colony = state.colony.read().await.clone();
+    let faults = state.faults.read().await.clone();
+    let win_loss = state.win_loss.read().await.clone();
+    let black_swans = state.black_swans.read().await.meters.clone();
+    let (research, available) = {
+        let research = state.research.read().await;
+        let available: Vec<String> = state.tech_tree.read().await.get_available_techs(&research).into_iter()
+            .map(|tech| tech.id.clone())
+            .collect();
+        (research.clone(), available)
+    };
+    let view = state.view.read().await;
+    let gpu = view.gpu.clone().unwrap_or_default();
+
+    Ok(Json(serde_json::json!({
+        "sla": {
+            "hit_rate": view.sla_hit_rate * 100.0,
+            "achieved_days": win_loss.achieved_days,
+            "target_days": colony.target_uptime_days
+        },
+        "resources": {
+            "power_draw_kw": colony.meters.power_draw_kw,
+            "power_cap_kw": colony.power_cap_kw,
+            "bandwidth_util": colony.meters.bandwidth_util,
+            "corruption_field": colony.corruption_field
+        },
+        "heat": {
+            "yards": view.yards.iter()
+                .map(|y| serde_json::json!({"heat": y.heat, "cap": y.heat_cap, "throttle": y.throttle}))
+                .collect::<Vec<_>>()
+        },
+        "gpu": {
+            "farm": view.gpu.is_some(),
+            "util": gpu.util,
+            "vram_used_gb": gpu.vram_used_gb,
+            "batches_inflight": gpu.batches_inflight
+        },
+        "faults": {
+            "last_tick_faults": faults.last_tick_faults,
+            "soft_drop_rate": faults.soft_drop_rate,
+            "sticky_workers": faults.sticky_workers
+        },
+        "black_swans": {
+            "active": black_swans.active,
+            "recent": black_swans.recently_fired
+        },
+        "research": {
+            "pts": research.pts,
+            "acquired": research.acquired,
+            "available": available
+        }
+    })))
+}
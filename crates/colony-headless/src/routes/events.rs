@@ -0,0 +1,160 @@
+use crate::AppState;
+use crate::limits::{ApiError};
+use super::{DotBody, GRAPHVIZ};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use colony_core::{SimClock, BlackSwanIndex, Digest, DigestPeriod, BlackBox, event_graph_dot};
+use serde::Deserialize;
+
+/// Black Swan events, debts, digests and post-mortems
+pub(super) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/digests", get(get_digests))
+        .route("/postmortem", get(get_postmortem))
+        .route("/events", get(get_events))
+        .route("/events/graph.dot", get(get_event_graph))
+        .route("/events/:id/fire", post(fire_event))
+        .route("/events/:id/choose", post(choose_event_option))
+        .route("/debts", get(get_debts))
+}
+
+/// The post-mortem sealed when the colony was last lost, with its journal walked step by step
+async fn get_postmortem(State(state): State<AppState>) -> Result<Json<serde_json::Value>, ApiError> {
+    let postmortem = state.sim()?
+        .with_world(|world| world.get_resource::<BlackBox>().and_then(|black_box| black_box.last.clone()))
+        .await?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "The colony hasn't been lost".to_string()))?;
+    Ok(Json(serde_json::json!({
+        "timeline": postmortem.timeline(),
+        "postmortem": postmortem,
+    })))
+}
+
+async fn get_digests(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<Vec<Digest>>, ApiError> {
+    let period = match params.get("period").map(String::as_str) {
+        None => None,
+        Some("day") => Some(DigestPeriod::Day),
+        Some("week") => Some(DigestPeriod::Week),
+        Some(other) => return Err(ApiError::unprocessable(format!("unknown digest period '{}'", other))),
+    };
+    let limit = params.get("limit").and_then(|v| v.parse::<usize>().ok()).unwrap_or(usize::MAX);
+    let journal = state.journal.read().await;
+    let mut digests: Vec<Digest> = journal.digests.iter().rev()
+        .filter(|d| period.is_none_or(|p| d.period == p))
+        .take(limit)
+        .cloned()
+        .collect();
+    digests.reverse();
+    Ok(Json(digests))
+}
+
+async fn get_events(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let now_tick = state.clock.read().await.now.timestamp_millis() as u64 / 16;
+    let black_swans = state.black_swans.read().await;
+    let pending: Vec<serde_json::Value> = black_swans.pending.iter()
+        .map(|p| serde_json::json!({
+            "id": p.swan_id,
+            "name": p.name,
+            "narrative": p.narrative,
+            "severity": p.severity,
+            "choices": p.choices.iter().map(|c| serde_json::json!({ "id": c.id, "label": c.label })).collect::<Vec<_>>(),
+            "default_choice": p.default_choice,
+            "ticks_remaining": p.ticks_remaining(now_tick)
+        }))
+        .collect();
+    let eligible: Vec<&str> = black_swans.defs.iter()
+        .filter(|def| !black_swans.meters.active.contains(&def.id) && !black_swans.is_on_cooldown(&def.id, now_tick))
+        .map(|def| def.id.as_str())
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "pending": pending,
+        "eligible": eligible,
+        "active": black_swans.meters.active,
+        "recent": black_swans.meters.recently_fired,
+    })))
+}
+
+/// Creative only: fire a Black Swan now, ignoring its triggers and cooldown
+async fn fire_event(
+    State(state): State<AppState>,
+    axum::extract::Path(event_id): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    state.mode.require_creative("Forcing events").map_err(|e| ApiError::new(StatusCode::FORBIDDEN, e.to_string()))?;
+    let id = event_id.clone();
+    let effects = state.sim()?
+        .with_world(move |world| {
+            let now_tick = world.resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+            world.resource_mut::<BlackSwanIndex>().fire(&id, now_tick)
+        })
+        .await?
+        .map_err(|e| ApiError::new(StatusCode::NOT_FOUND, e.to_string()))?;
+    Ok(Json(serde_json::json!({
+        "status": "fired",
+        "event_id": event_id,
+        "effects": effects.len()
+    })))
+}
+
+#[derive(Deserialize)]
+struct ChooseRequest {
+    choice: String,
+}
+
+async fn choose_event_option(
+    State(state): State<AppState>,
+    axum::extract::Path(event_id): axum::extract::Path<String>,
+    Json(request): Json<ChooseRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let (id, choice) = (event_id.clone(), request.choice.clone());
+    state
+        .update(move |black_swans: &mut BlackSwanIndex| {
+            if black_swans.pending_for(&id).is_none() {
+                return Err(ApiError::new(StatusCode::NOT_FOUND, format!("No pending event '{}'", id)));
+            }
+            black_swans.choose(&id, &choice).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))
+        })
+        .await??;
+
+    Ok(Json(serde_json::json!({
+        "status": "chosen",
+        "event_id": event_id,
+        "choice": request.choice
+    })))
+}
+
+async fn get_debts(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let capacity = state.effective_capacity.read().await;
+    Ok(Json(serde_json::json!({
+        "active": state.debts.read().await.active,
+        "effective": {
+            "power_cap_kw": capacity.power_cap_kw,
+            "bandwidth_total_gbps": capacity.bandwidth_total_gbps,
+            "fault_rate_mult": capacity.fault_rate_mult,
+            "heat_add_per_tick": capacity.heat_add_per_tick,
+            "vram_leak_mb_per_tick": capacity.vram_leak_mb_per_tick,
+        },
+        "expiries": capacity.expiries,
+        "ticks_until_clear": capacity.ticks_until_clear(),
+        "tick": capacity.tick
+    })))
+}
+
+async fn get_event_graph(
+    State(state): State<AppState>,
+) -> DotBody {
+    let tech_tree = state.tech_tree.read().await;
+    (GRAPHVIZ, event_graph_dot(&tech_tree, &state.black_swans.read().await.defs))
+}
@@ -0,0 +1,355 @@
+use crate::AppState;
+use crate::limits::{ApiError, JobLimits, Validate, ValidJson};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, post, put},
+    Router,
+};
+use bevy::prelude::Mut;
+use colony_core::{SimClock, ExportSink, ArchiveStore, DeletionPolicy, FlowSankey, EnergyLedger, EnergyConfig, DemandForecast, ForecastConfig, advise, plan_maintenance, Autoscaler, AutoscaleConfig, OpCache, OpCacheConfig, OpLicenses, OpLimit};
+use serde::Deserialize;
+
+/// Energy, forecasting, autoscaling, op licenses, caching, export and storage
+pub(super) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/energy/report", get(get_energy_report))
+        .route("/energy/carbon", get(get_carbon_curve).put(set_carbon_curve))
+        .route("/forecast", get(get_forecast))
+        .route("/forecast/config", get(get_forecast_config).put(set_forecast_config))
+        .route("/forecast/advice", get(get_forecast_advice))
+        .route("/maintenance/plan", get(get_maintenance_plan))
+        .route("/autoscale", get(get_autoscale).put(set_autoscale))
+        .route("/autoscale/audit", get(get_autoscale_audit))
+        .route("/metrics/export", get(get_export_metrics))
+        .route("/export/outages", post(schedule_export_outage))
+        .route("/metrics/storage", get(get_storage_metrics))
+        .route("/storage/policy", put(set_storage_policy))
+        .route("/metrics/flows", get(get_flow_metrics))
+        .route("/flows/sankey", get(get_flow_sankey))
+        .route("/ops/limits", get(get_op_limits).put(set_op_limits))
+        .route("/ops/limits/purchase", post(purchase_op_slots))
+        .route("/op_cache", get(get_op_cache).put(set_op_cache))
+}
+
+async fn get_energy_report(
+    State(state): State<AppState>,
+) -> Result<Json<colony_core::EnergyReport>, StatusCode> {
+    Ok(Json(state.energy.read().await.report()))
+}
+
+impl Validate for EnergyConfig {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        EnergyConfig::validate(self).map_err(|e| e.to_string())
+    }
+}
+
+async fn set_carbon_curve(
+    State(state): State<AppState>,
+    ValidJson(config): ValidJson<EnergyConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let carbon_intensity = state
+        .update(move |energy: &mut EnergyLedger| energy.configure(&config).map(|_| energy.config.carbon_intensity.clone()))
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "carbon_intensity": carbon_intensity,
+    })))
+}
+
+async fn get_carbon_curve(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let energy = state.energy.read().await;
+    Ok(Json(serde_json::json!({
+        "carbon_intensity": energy.config.carbon_intensity,
+        "intensity_g_per_kwh": energy.intensity_g_per_kwh,
+    })))
+}
+
+async fn get_forecast(
+    State(state): State<AppState>,
+) -> Result<Json<colony_core::ForecastReport>, StatusCode> {
+    Ok(Json(state.forecast.read().await.report()))
+}
+
+async fn get_forecast_advice(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<colony_core::Advice>>, StatusCode> {
+    let report = state.forecast.read().await.report();
+    let colony = state.colony.read().await;
+    Ok(Json(advise(&report, &colony)))
+}
+
+async fn get_maintenance_plan(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<colony_core::MaintenancePlan>>, StatusCode> {
+    let report = state.forecast.read().await.report();
+    let knee = state.colony.read().await.tunables.thermal_throttle_knee;
+    Ok(Json(plan_maintenance(&report, knee)))
+}
+
+impl Validate for ForecastConfig {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        ForecastConfig::validate(self).map_err(|e| e.to_string())
+    }
+}
+
+async fn set_forecast_config(
+    State(state): State<AppState>,
+    ValidJson(config): ValidJson<ForecastConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let new_config = config.clone();
+    state.update(move |forecast: &mut DemandForecast| forecast.configure(&new_config))
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "config": config,
+    })))
+}
+
+async fn get_forecast_config(State(state): State<AppState>) -> Result<Json<ForecastConfig>, StatusCode> {
+    Ok(Json(state.forecast.read().await.config.clone()))
+}
+
+impl Validate for AutoscaleConfig {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        AutoscaleConfig::validate(self).map_err(|e| e.to_string())
+    }
+}
+
+async fn set_autoscale(
+    State(state): State<AppState>,
+    ValidJson(config): ValidJson<AutoscaleConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let (config, remaining_budget) = state
+        .update(move |autoscaler: &mut Autoscaler| {
+            autoscaler.configure(&config).map(|_| (autoscaler.config.clone(), autoscaler.remaining_budget()))
+        })
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "config": config,
+        "remaining_budget": remaining_budget,
+    })))
+}
+
+async fn get_autoscale(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let autoscaler = state.autoscaler.read().await;
+    Ok(Json(serde_json::json!({
+        "config": autoscaler.config,
+        "spent": autoscaler.spent,
+        "remaining_budget": autoscaler.remaining_budget(),
+    })))
+}
+
+async fn get_autoscale_audit(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<colony_core::ScalingRecord>>, StatusCode> {
+    Ok(Json(state.autoscaler.read().await.audit.iter().cloned().collect()))
+}
+
+/// Each limited op's slots, where they came from, how many are held and the wait for them
+async fn get_op_limits(State(state): State<AppState>) -> Result<Json<serde_json::Value>, StatusCode> {
+    let licenses = state.op_licenses.read().await;
+    let ops: Vec<serde_json::Value> = licenses.limits.iter()
+        .map(|limit| {
+            let contention = licenses.contention.get(&limit.op).cloned().unwrap_or_default();
+            serde_json::json!({
+                "op": limit.op,
+                "slots": licenses.capacity(&limit.op),
+                "base": limit.slots,
+                "purchased": licenses.purchased.get(&limit.op).copied().unwrap_or(0),
+                "researched": licenses.researched.get(&limit.op).copied().unwrap_or(0),
+                "slot_cost": limit.slot_cost,
+                "in_use": licenses.in_use(&limit.op),
+                "avg_wait_ms": contention.avg_wait_ms(),
+                "contention": contention,
+            })
+        })
+        .collect();
+    Ok(Json(serde_json::json!({ "ops": ops })))
+}
+
+async fn set_op_limits(
+    State(state): State<AppState>,
+    Json(limits): Json<Vec<OpLimit>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let new_limits = limits.clone();
+    state.update(move |licenses: &mut OpLicenses| licenses.set_limits(&new_limits))
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "limits": limits,
+    })))
+}
+
+#[derive(Deserialize)]
+struct PurchaseSlotsRequest {
+    op: String,
+    #[serde(default = "default_purchase_slots")]
+    slots: u32,
+}
+
+fn default_purchase_slots() -> u32 { 1 }
+
+/// Buy slots for a limited op out of the autoscaling budget
+async fn purchase_op_slots(
+    State(state): State<AppState>,
+    Json(request): Json<PurchaseSlotsRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if request.slots == 0 {
+        return Err(ApiError::unprocessable("slots must be at least 1"));
+    }
+    let op = request.op.clone();
+    let (cost, slots, budget_left) = state.sim()?
+        .with_world(move |world| {
+            world.resource_scope(|world, mut licenses: Mut<OpLicenses>| {
+                let mut budget = world.resource_mut::<Autoscaler>();
+                let cost = licenses.purchase(&op, request.slots, &mut budget).map_err(|e| ApiError::unprocessable(e.to_string()))?;
+                Ok::<_, ApiError>((cost, licenses.capacity(&op), budget.remaining_budget()))
+            })
+        })
+        .await??;
+
+    Ok(Json(serde_json::json!({
+        "status": "purchased",
+        "op": request.op,
+        "cost": cost,
+        "slots": slots,
+        "budget_left": budget_left,
+    })))
+}
+
+async fn get_export_metrics(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let sink = state.export_sink.read().await;
+    Ok(Json(serde_json::json!({
+        "sink": sink.name,
+        "up": sink.up,
+        "delivered": sink.delivered,
+        "buffered": sink.buffered,
+        "drained": sink.drained,
+        "dropped": sink.dropped,
+        "retries": sink.retries,
+        "retry_ms_total": sink.retry_ms_total,
+        "buffer_len": sink.buffer.len(),
+        "buffered_bytes": sink.buffered_bytes,
+        "peak_buffered_bytes": sink.peak_buffered_bytes,
+        "schedule": sink.schedule,
+        "tunables": sink.tunables,
+    })))
+}
+
+#[derive(Deserialize)]
+struct ExportOutageRequest {
+    start_tick: Option<u64>, // defaults to now
+    duration_ms: u64,
+}
+
+async fn schedule_export_outage(
+    State(state): State<AppState>,
+    Json(request): Json<ExportOutageRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let schedule = state.sim()?
+        .with_world(move |world| {
+            let now_tick = world.resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+            let mut sink = world.resource_mut::<ExportSink>();
+            sink.schedule_outage(request.start_tick.unwrap_or(now_tick), request.duration_ms);
+            sink.schedule.clone()
+        })
+        .await?;
+    Ok(Json(serde_json::json!({
+        "status": "scheduled",
+        "schedule": schedule
+    })))
+}
+
+async fn get_storage_metrics(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let store = state.archive.read().await;
+    Ok(Json(serde_json::json!({
+        "policy": store.policy,
+        "demote_after_ms": store.demote_after_ms,
+        "hot_fill": store.hot.fill_frac(),
+        "cold_fill": store.cold.fill_frac(),
+        "full": store.is_full(),
+        "metrics": store.metrics(),
+    })))
+}
+
+#[derive(Deserialize)]
+struct StoragePolicyRequest {
+    policy: DeletionPolicy,
+}
+
+async fn set_storage_policy(
+    State(state): State<AppState>,
+    Json(request): Json<StoragePolicyRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let policy = request.policy;
+    state.update(move |store: &mut ArchiveStore| store.policy = policy).await?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "policy": request.policy
+    })))
+}
+
+async fn get_flow_metrics(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let flows = state.flows.read().await;
+    Ok(Json(serde_json::json!({
+        "flows": flows.reports(),
+    })))
+}
+
+async fn get_flow_sankey(State(state): State<AppState>) -> Result<Json<FlowSankey>, StatusCode> {
+    Ok(Json(state.flows.read().await.sankey()))
+}
+
+impl Validate for OpCacheConfig {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        OpCacheConfig::validate(self).map_err(|e| e.to_string())
+    }
+}
+
+async fn set_op_cache(
+    State(state): State<AppState>,
+    ValidJson(config): ValidJson<OpCacheConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let new_config = config.clone();
+    state.update(move |op_cache: &mut OpCache| op_cache.configure(new_config))
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "config": config,
+    })))
+}
+
+async fn get_op_cache(State(state): State<AppState>) -> Result<Json<serde_json::Value>, StatusCode> {
+    let cache = state.op_cache.read().await;
+    Ok(Json(serde_json::json!({
+        "config": cache.config,
+        "capacity_bytes": cache.capacity_bytes,
+        "used_bytes": cache.used_bytes(),
+        "entries": cache.len(),
+        "stats": cache.stats,
+        "hit_rate": cache.stats.hit_rate(),
+    })))
+}
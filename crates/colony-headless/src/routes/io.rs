@@ -0,0 +1,181 @@
+use crate::AppState;
+use crate::limits::{ApiError, JobLimits, Validate, ValidJson};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, put},
+    Router,
+};
+use colony_core::{validate_io_channels, IoIngest, IoIngestConfig};
+use colony_io::{IoSimulatorConfig, CanSimConfig, ModbusSimConfig, MqttSimConfig, TcpSimConfig, GrpcSimConfig, IoChannelSpec};
+
+/// IO simulators, channels and ingest
+pub(super) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/io/udp/sim", put(set_udp_sim))
+        .route("/io/registry", get(get_io_registry))
+        .route("/io/channels", get(get_io_channels).put(set_io_channels))
+        .route("/io/ingest", get(get_io_ingest).put(set_io_ingest))
+        .route("/io/http/sim", put(set_http_sim))
+        .route("/metrics/io", get(get_io_metrics))
+        .route("/io/can/sim", put(set_can_sim))
+        .route("/io/modbus/sim", put(set_modbus_sim))
+        .route("/io/mqtt/sim", put(set_mqtt_sim))
+        .route("/io/tcp/sim", put(set_tcp_sim))
+        .route("/io/grpc/sim", put(set_grpc_sim))
+}
+
+async fn set_udp_sim(
+    State(_state): State<AppState>,
+    Json(config): Json<IoSimulatorConfig>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    // In a real implementation, this would start/restart the UDP simulator
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "config": config
+    })))
+}
+
+async fn set_http_sim(
+    State(_state): State<AppState>,
+    Json(config): Json<IoSimulatorConfig>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    // In a real implementation, this would start/restart the HTTP simulator
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "config": config
+    })))
+}
+
+async fn get_io_registry(State(state): State<AppState>) -> Result<Json<serde_json::Value>, StatusCode> {
+    Ok(Json(serde_json::json!({
+        "sources": state.io_registry.source_names(),
+        "parsers": state.io_registry.parser_names(),
+    })))
+}
+
+async fn set_io_channels(
+    State(state): State<AppState>,
+    Json(channels): Json<Vec<IoChannelSpec>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    validate_io_channels(&state.io_registry, &channels).map_err(|e| ApiError::unprocessable(e.to_string()))?;
+    *state.io_channels.write().await = channels.clone();
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "channels": channels,
+    })))
+}
+
+async fn get_io_channels(State(state): State<AppState>) -> Result<Json<Vec<IoChannelSpec>>, StatusCode> {
+    Ok(Json(state.io_channels.read().await.clone()))
+}
+
+async fn get_io_metrics(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let (bandwidth_util, bandwidth_total_gbps) = {
+        let colony = state.colony.read().await;
+        (colony.meters.bandwidth_util, colony.bandwidth_total_gbps)
+    };
+    let deadline_hit_rate = state.faults.read().await.deadline_hit_rate;
+    let view = state.view.read().await;
+    Ok(Json(serde_json::json!({
+        "parse": state.parse_counters.snapshot(),
+        "bandwidth_util": bandwidth_util,
+        "gbps": bandwidth_util * bandwidth_total_gbps,
+        "backlog_queue": view.queued.io,
+        "queued": view.queued,
+        "deadline_hit_rate": deadline_hit_rate,
+    })))
+}
+
+async fn set_can_sim(
+    State(_state): State<AppState>,
+    Json(config): Json<CanSimConfig>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    // In a real implementation, this would start/restart the CAN simulator
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "config": config
+    })))
+}
+
+async fn set_modbus_sim(
+    State(_state): State<AppState>,
+    Json(config): Json<ModbusSimConfig>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    // In a real implementation, this would start/restart the Modbus simulator
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "config": config
+    })))
+}
+
+async fn set_mqtt_sim(
+    State(_state): State<AppState>,
+    Json(config): Json<MqttSimConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    config.validate().map_err(ApiError::unprocessable)?;
+    // In a real implementation, this would start/restart the MQTT simulator
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "config": config
+    })))
+}
+
+impl Validate for IoIngestConfig {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        IoIngestConfig::validate(self).map_err(|e| e.to_string())
+    }
+}
+
+/// Route simulator traffic to pipelines and pick the backpressure policy; counters restart
+async fn set_io_ingest(
+    State(state): State<AppState>,
+    ValidJson(config): ValidJson<IoIngestConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let new_config = config.clone();
+    state.update(move |io_ingest: &mut IoIngest| io_ingest.configure(new_config))
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "config": config,
+    })))
+}
+
+async fn get_io_ingest(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let ingest = state.io_ingest.read().await;
+    Json(serde_json::json!({
+        "config": ingest.config,
+        "stats": ingest.stats,
+        "held": ingest.held.iter().map(|(pipeline, io_job)| (pipeline.clone(), io_job.job.payload_sz)).collect::<std::collections::BTreeMap<_, _>>(),
+    }))
+}
+
+async fn set_tcp_sim(
+    State(_state): State<AppState>,
+    Json(config): Json<TcpSimConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    config.validate().map_err(ApiError::unprocessable)?;
+    // In a real implementation, this would start/restart the TCP session simulator
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "config": config
+    })))
+}
+
+async fn set_grpc_sim(
+    State(_state): State<AppState>,
+    Json(config): Json<GrpcSimConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    config.validate().map_err(ApiError::unprocessable)?;
+    // In a real implementation, this would start/restart the gRPC simulator
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "config": config
+    })))
+}
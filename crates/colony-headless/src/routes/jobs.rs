@@ -0,0 +1,481 @@
+use crate::AppState;
+use crate::limits::{ApiError, JobLimits, Validate, ValidJson};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, post, put},
+    Router,
+};
+use bevy::prelude::{Mut, With, World};
+use colony_core::{SimClock, Job, Pipeline, Op, QoS, JobIdAllocator, JobRetries, JobRetryConfig, TenantLedger, TenantConfig, GpuFarm, JobQueue, EnqueuedJob, check_gpu_pipeline, get_pipeline_by_id, op_from_name, ModOps, JobCheck, check_job, LoadGenerator, LoadGenConfig, DeadLetterQueue, DeadLetterConfig};
+use serde::Deserialize;
+
+/// Job admission, retries, dead letters, tenants and synthetic load
+pub(super) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/job", post(create_job))
+        .route("/job/validate", post(validate_job))
+        .route("/pipeline/:id/enqueue", post(enqueue_pipeline))
+        .route("/queue/dead_letters", get(get_dead_letters).put(set_dead_letters))
+        .route("/queue/dead_letters/requeue", post(requeue_all_dead_letters))
+        .route("/queue/dead_letters/:job_id/requeue", post(requeue_dead_letter))
+        .route("/jobs/retry", get(get_job_retry).put(set_job_retry))
+        .route("/tenants", get(get_tenants))
+        .route("/tenants/quotas", put(set_tenant_quotas))
+        .route("/tenants/:id/usage", get(get_tenant_usage))
+        .route("/tenants/:id/sla", get(get_tenant_sla))
+        .route("/loadgen", get(get_loadgen).put(set_loadgen))
+}
+
+#[derive(Deserialize)]
+struct JobRequest {
+    pipeline: Vec<String>,
+    qos: String,
+    deadline_ms: u64,
+    payload_sz: usize,
+    #[serde(default)]
+    tenant: Option<String>,
+}
+
+/// Built-in ops by name, plus `wasm:<mod>/<op>` and `lua:<func>` ops from mods
+fn parse_op(op: &str) -> Option<Op> {
+    op_from_name(op).ok()
+}
+
+fn parse_qos(qos: &str) -> Option<QoS> {
+    match qos {
+        "Throughput" => Some(QoS::Throughput),
+        "Latency" => Some(QoS::Latency),
+        "Balanced" => Some(QoS::Balanced),
+        _ => None,
+    }
+}
+
+fn check_payload_sz(payload_sz: usize, limits: &JobLimits) -> Result<(), String> {
+    if payload_sz > limits.max_payload_sz {
+        return Err(format!("payload_sz {} exceeds the limit of {} bytes", payload_sz, limits.max_payload_sz));
+    }
+    Ok(())
+}
+
+impl Validate for JobRequest {
+    fn validate(&self, limits: &JobLimits) -> Result<(), String> {
+        if self.pipeline.is_empty() {
+            return Err("pipeline must have at least one op".to_string());
+        }
+        if self.pipeline.len() > limits.max_pipeline_ops {
+            return Err(format!("pipeline has {} ops; the limit is {}", self.pipeline.len(), limits.max_pipeline_ops));
+        }
+        if let Some((i, op)) = self.pipeline.iter().enumerate().find(|(_, op)| parse_op(op).is_none()) {
+            return Err(format!("pipeline[{}]: unknown op '{}'", i, op));
+        }
+        if parse_qos(&self.qos).is_none() {
+            return Err(format!("Unknown qos '{}'; expected Throughput, Latency or Balanced", self.qos));
+        }
+        if self.deadline_ms == 0 || self.deadline_ms > limits.max_deadline_ms {
+            return Err(format!("deadline_ms must be between 1 and {}", limits.max_deadline_ms));
+        }
+        check_tenant(self.tenant.as_deref())?;
+        check_payload_sz(self.payload_sz, limits)
+    }
+}
+
+/// Body of `POST /job/validate`: a `/job` request, or a registered pipeline by id
+#[derive(Deserialize)]
+struct ValidateJobRequest {
+    #[serde(default)]
+    pipeline_id: Option<String>,
+    #[serde(default)]
+    pipeline: Vec<String>,
+    #[serde(default)]
+    qos: Option<String>,
+    #[serde(default = "default_validate_deadline_ms")]
+    deadline_ms: u64,
+    #[serde(default = "default_enqueue_payload_sz")]
+    payload_sz: usize,
+    #[serde(default)]
+    tenant: Option<String>,
+}
+
+fn default_validate_deadline_ms() -> u64 {
+    1000 // what /pipeline/:id/enqueue gives its jobs
+}
+
+impl ValidateJobRequest {
+    /// The pipeline the request names, and everything wrong with the request itself
+    fn pipeline(&self, limits: &JobLimits) -> (Option<Pipeline>, Vec<String>) {
+        let mut errors = Vec::new();
+        let pipeline = match &self.pipeline_id {
+            Some(_) if !self.pipeline.is_empty() => {
+                errors.push("Give either pipeline_id or pipeline, not both".to_string());
+                None
+            }
+            Some(id) => {
+                let pipeline = get_pipeline_by_id(id);
+                if pipeline.is_none() {
+                    errors.push(format!("Unknown pipeline '{}'", id));
+                }
+                pipeline
+            }
+            None => {
+                if self.pipeline.is_empty() {
+                    errors.push("pipeline must have at least one op".to_string());
+                }
+                if self.pipeline.len() > limits.max_pipeline_ops {
+                    errors.push(format!("pipeline has {} ops; the limit is {}", self.pipeline.len(), limits.max_pipeline_ops));
+                }
+                let mut ops = Vec::new();
+                for (i, op) in self.pipeline.iter().enumerate() {
+                    match parse_op(op) {
+                        Some(op) => ops.push(op),
+                        None => errors.push(format!("pipeline[{}]: unknown op '{}'", i, op)),
+                    }
+                }
+                Some(Pipeline { ops, mutation_tag: None, id: None })
+            }
+        };
+        if let Some(qos) = self.qos.as_deref().filter(|qos| parse_qos(qos).is_none()) {
+            errors.push(format!("Unknown qos '{}'; expected Throughput, Latency or Balanced", qos));
+        }
+        if self.deadline_ms == 0 || self.deadline_ms > limits.max_deadline_ms {
+            errors.push(format!("deadline_ms must be between 1 and {}", limits.max_deadline_ms));
+        }
+        errors.extend(check_tenant(self.tenant.as_deref()).err());
+        errors.extend(check_payload_sz(self.payload_sz, limits).err());
+        (pipeline, errors)
+    }
+}
+
+#[derive(Deserialize)]
+struct EnqueueRequest {
+    #[serde(default = "default_enqueue_payload_sz")]
+    payload_sz: usize,
+    #[serde(default)]
+    tenant: Option<String>,
+}
+
+fn default_enqueue_payload_sz() -> usize {
+    1024
+}
+
+impl Validate for EnqueueRequest {
+    fn validate(&self, limits: &JobLimits) -> Result<(), String> {
+        check_tenant(self.tenant.as_deref())?;
+        check_payload_sz(self.payload_sz, limits)
+    }
+}
+
+fn check_tenant(tenant: Option<&str>) -> Result<(), String> {
+    match tenant {
+        Some(t) if t.is_empty() || t.len() > 64 || !t.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') => {
+            Err(format!("tenant '{}' must be 1-64 letters, digits, '-' or '_'", t))
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn create_job(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<JobRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    // Validated: every op and the qos parse
+    let ops: Vec<Op> = request.pipeline.iter().filter_map(|op| parse_op(op)).collect();
+    let qos = parse_qos(&request.qos).unwrap_or(QoS::Balanced);
+
+    let pipeline = Pipeline {
+        ops,
+        mutation_tag: None,
+        id: None,
+    };
+
+    let (deadline_ms, payload_sz, tenant) = (request.deadline_ms, request.payload_sz, request.tenant.clone());
+    let job_id = state.sim()?
+        .with_world(move |world| enqueue_in_world(world, pipeline, qos, deadline_ms, payload_sz, tenant))
+        .await??;
+
+    Ok(Json(serde_json::json!({
+        "status": "created",
+        "job_id": job_id,
+        "deadline_ms": deadline_ms,
+        "tenant": request.tenant,
+    })))
+}
+
+/// Admit a job for its tenant, number it and queue it in the world as one of this tick's arrivals
+fn enqueue_in_world(world: &mut World, pipeline: Pipeline, qos: QoS, deadline_ms: u64, payload_sz: usize, tenant: Option<String>) -> Result<u64, ApiError> {
+    let mod_ops = world.resource::<ModOps>();
+    if let Some(op_id) = pipeline.ops.iter().find_map(|op| match op {
+        Op::DynamicWasm { op_id } if mod_ops.get(op_id).is_none() => Some(op_id),
+        _ => None,
+    }) {
+        return Err(ApiError::unprocessable(format!("WASM op {} isn't provided by any enabled mod", op_id)));
+    }
+    world.resource_mut::<TenantLedger>().admit(tenant.as_deref())
+        .map_err(|e| ApiError::new(StatusCode::TOO_MANY_REQUESTS, e.to_string()))?;
+    let now_tick = world.resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+    let id = world.resource_mut::<JobIdAllocator>().allocate();
+    let mut enqueued = EnqueuedJob::new(Job { id, pipeline, qos, deadline_ms, payload_sz }, now_tick);
+    enqueued.tenant = tenant;
+    world.resource_mut::<JobQueue>().push_enqueued(enqueued);
+    Ok(id)
+}
+
+/// Dry run of `/job` or `/pipeline/:id/enqueue`: reports what would refuse or slow the job, and queues nothing
+async fn validate_job(
+    State(state): State<AppState>,
+    Json(request): Json<ValidateJobRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let (pipeline, errors) = request.pipeline(&state.server_config.job);
+    let mut check = JobCheck { errors, ..Default::default() };
+    if let Some(pipeline) = pipeline.filter(|_| check.errors.is_empty()) {
+        let (pipeline_id, deadline_ms, payload_sz, tenant) = (request.pipeline_id.clone(), request.deadline_ms, request.payload_sz, request.tenant.clone());
+        check = state.sim()?
+            .with_world(move |world| {
+                let mut check = check_job(world, &pipeline, deadline_ms, payload_sz, tenant.as_deref());
+                // A registered pipeline with GPU ops is refused outright without a farm
+                if let Some(id) = pipeline_id {
+                    let has_gpu = world.query_filtered::<(), With<GpuFarm>>().iter(world).next().is_some();
+                    check.errors.extend(check_gpu_pipeline(&id, &pipeline, has_gpu).err().map(|e| e.to_string()));
+                }
+                check
+            })
+            .await?;
+    }
+
+    Ok(Json(serde_json::json!({
+        "valid": check.is_valid(),
+        "errors": check.errors,
+        "warnings": check.warnings,
+        "projected": check.projected,
+    })))
+}
+
+async fn enqueue_pipeline(
+    State(state): State<AppState>,
+    axum::extract::Path(pipeline_id): axum::extract::Path<String>,
+    ValidJson(request): ValidJson<EnqueueRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let payload_sz = request.payload_sz;
+    let pipeline = get_pipeline_by_id(&pipeline_id)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("Unknown pipeline '{}'", pipeline_id)))?;
+    let (id, tenant) = (pipeline_id.clone(), request.tenant.clone());
+    let job_id = state.sim()?
+        .with_world(move |world| {
+            let has_gpu = world.query_filtered::<(), With<GpuFarm>>().iter(world).next().is_some();
+            check_gpu_pipeline(&id, &pipeline, has_gpu).map_err(|e| ApiError::unprocessable(e.to_string()))?;
+            enqueue_in_world(world, pipeline, QoS::Balanced, 1000, payload_sz, tenant)
+        })
+        .await??;
+
+    Ok(Json(serde_json::json!({
+        "status": "enqueued",
+        "pipeline_id": pipeline_id,
+        "payload_sz": payload_sz,
+        "job_id": job_id,
+        "tenant": request.tenant,
+    })))
+}
+
+impl Validate for JobRetryConfig {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        JobRetryConfig::validate(self).map_err(|e| e.to_string())
+    }
+}
+
+async fn set_job_retry(
+    State(state): State<AppState>,
+    ValidJson(config): ValidJson<JobRetryConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let policies = state
+        .update(move |retries: &mut JobRetries| retries.configure(&config).map(|_| retries.policies.clone()))
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "policies": policies,
+    })))
+}
+
+async fn get_job_retry(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let retries = state.retries.read().await;
+    let stats: serde_json::Map<String, serde_json::Value> = retries.stats.iter()
+        .map(|(pipeline, s)| {
+            let mut entry = serde_json::to_value(s).unwrap_or_default();
+            entry["success_rate"] = serde_json::json!(s.success_rate());
+            (pipeline.clone(), entry)
+        })
+        .collect();
+    Ok(Json(serde_json::json!({
+        "policies": retries.policies,
+        "pending": retries.pending.len(),
+        "stats": stats,
+    })))
+}
+
+impl Validate for DeadLetterConfig {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        DeadLetterConfig::validate(self).map_err(|e| e.to_string())
+    }
+}
+
+/// Set dead-letter capacity, deadline expiry and starvation escalation
+async fn set_dead_letters(
+    State(state): State<AppState>,
+    ValidJson(config): ValidJson<DeadLetterConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let new_config = config.clone();
+    state.update(move |dead_letters: &mut DeadLetterQueue| dead_letters.configure(new_config))
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "config": config,
+    })))
+}
+
+async fn get_dead_letters(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let dead_letters = state.dead_letters.read().await;
+    Json(serde_json::json!({
+        "config": dead_letters.config,
+        "stats": dead_letters.stats,
+        "depth": dead_letters.depth(),
+        "letters": dead_letters.letters,
+    }))
+}
+
+/// Put one dead-lettered job back on the queue; its tenant's quota applies again
+async fn requeue_dead_letter(
+    State(state): State<AppState>,
+    axum::extract::Path(job_id): axum::extract::Path<u64>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    state.sim()?
+        .with_world(move |world| {
+            world.resource_scope(|world, mut dead_letters: Mut<DeadLetterQueue>| {
+                if !dead_letters.letters.iter().any(|letter| letter.job.job.id == job_id) {
+                    return Err(ApiError::new(StatusCode::NOT_FOUND, format!("Job {} is not in the dead-letter queue", job_id)));
+                }
+                let now_tick = world.resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+                world.resource_scope(|world, mut tenants: Mut<TenantLedger>| {
+                    dead_letters.requeue(job_id, &mut world.resource_mut::<JobQueue>(), &mut tenants, now_tick)
+                        .map_err(|e| ApiError::new(StatusCode::TOO_MANY_REQUESTS, e.to_string()))
+                })
+            })
+        })
+        .await??;
+
+    Ok(Json(serde_json::json!({
+        "status": "requeued",
+        "job_id": job_id,
+    })))
+}
+
+/// Requeue every dead letter whose tenant has room; the rest stay put
+async fn requeue_all_dead_letters(State(state): State<AppState>) -> Result<Json<serde_json::Value>, ApiError> {
+    let (requeued, remaining) = state.sim()?
+        .with_world(|world| {
+            let now_tick = world.resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+            world.resource_scope(|world, mut dead_letters: Mut<DeadLetterQueue>| {
+                world.resource_scope(|world, mut tenants: Mut<TenantLedger>| {
+                    let requeued = dead_letters.requeue_all(&mut world.resource_mut::<JobQueue>(), &mut tenants, now_tick);
+                    (requeued, dead_letters.depth())
+                })
+            })
+        })
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "requeued",
+        "requeued": requeued,
+        "remaining": remaining,
+    })))
+}
+
+impl Validate for TenantConfig {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        TenantConfig::validate(self).map_err(|e| e.to_string())?;
+        self.quotas.iter().try_for_each(|q| check_tenant(Some(&q.tenant)))
+    }
+}
+
+async fn set_tenant_quotas(
+    State(state): State<AppState>,
+    ValidJson(config): ValidJson<TenantConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let (enabled, quotas) = state
+        .update(move |tenants: &mut TenantLedger| {
+            tenants.configure(&config).map(|_| (tenants.enabled, tenants.quotas.clone()))
+        })
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "enabled": enabled,
+        "quotas": quotas,
+    })))
+}
+
+async fn get_tenants(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let tenants = state.tenants.read().await;
+    Ok(Json(serde_json::json!({
+        "enabled": tenants.enabled,
+        "quotas": tenants.quotas,
+        "usage": tenants.usage,
+    })))
+}
+
+async fn get_tenant_usage(
+    State(state): State<AppState>,
+    axum::extract::Path(tenant): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let tenants = state.tenants.read().await;
+    let usage = tenants.usage.get(&tenant)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("No jobs recorded for tenant {}", tenant)))?;
+    Ok(Json(serde_json::json!({
+        "tenant": tenant,
+        "quota": tenants.quotas.iter().find(|q| q.tenant == tenant),
+        "usage": usage,
+    })))
+}
+
+async fn get_tenant_sla(
+    State(state): State<AppState>,
+    axum::extract::Path(tenant): axum::extract::Path<String>,
+) -> Result<Json<colony_core::TenantSla>, ApiError> {
+    state.tenants.read().await.sla(&tenant)
+        .map(Json)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("No jobs recorded for tenant {}", tenant)))
+}
+
+impl Validate for LoadGenConfig {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        LoadGenConfig::validate(self).map_err(|e| e.to_string())
+    }
+}
+
+/// Start, change or stop synthetic job arrivals; counters restart with each config
+async fn set_loadgen(
+    State(state): State<AppState>,
+    ValidJson(config): ValidJson<LoadGenConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let new_config = config.clone();
+    state.update(move |loadgen: &mut LoadGenerator| loadgen.configure(new_config))
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "config": config,
+    })))
+}
+
+async fn get_loadgen(State(state): State<AppState>) -> Json<LoadGenerator> {
+    Json(state.loadgen.read().await.clone())
+}
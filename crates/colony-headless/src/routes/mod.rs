@@ -0,0 +1,42 @@
+mod colony;
+mod jobs;
+mod sched;
+mod io;
+mod facility;
+mod yards;
+mod workers;
+mod events;
+mod research;
+pub(crate) mod session;
+mod saves;
+mod mods;
+
+use crate::{notify, AppState};
+use axum::{
+    http::header,
+    routing::get,
+    Router,
+};
+
+type DotBody = ([(header::HeaderName, &'static str); 1], String);
+
+/// Served as GraphViz source; pipe into `dot -Tsvg` to render
+const GRAPHVIZ: [(header::HeaderName, &str); 1] = [(header::CONTENT_TYPE, "text/vnd.graphviz")];
+
+/// Every route the server serves, area by area
+pub fn router(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .merge(colony::routes())
+        .merge(jobs::routes())
+        .merge(sched::routes())
+        .merge(io::routes())
+        .merge(facility::routes())
+        .merge(yards::routes())
+        .merge(workers::routes())
+        .merge(events::routes())
+        .merge(research::routes())
+        .merge(session::routes(state))
+        .merge(saves::routes())
+        .merge(mods::routes())
+        .route("/ws/events", get(notify::events_ws))
+}
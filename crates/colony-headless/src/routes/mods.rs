@@ -0,0 +1,165 @@
+use crate::AppState;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+
+/// Mod listing, reload and docs
+pub(super) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/mods", get(get_mods))
+        .route("/mods/reload", post(reload_mod))
+        .route("/mods/enable", post(enable_mod))
+        .route("/mods/dryrun", post(dryrun_mod))
+        .route("/mods/docs", get(get_mod_docs))
+}
+
+async fn get_mods(
+    State(_state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    // Mock mod list
+    Ok(Json(serde_json::json!({
+        "mods": [
+            {
+                "id": "com.example.packetalchemy",
+                "name": "Packet Alchemy",
+                "version": "1.0.0",
+                "authors": ["Example Corp"],
+                "description": "Advanced packet processing operations",
+                "enabled": true,
+                "signature": "valid",
+                "entrypoints": {
+                    "wasm_ops": ["Op_AdaptiveFft", "Op_Anomaly"],
+                    "lua_events": ["on_tick.lua", "on_fault.lua"],
+                    "pipelines": "pipelines.toml",
+                    "blackswans": "events.toml",
+                    "tech": "tech.toml"
+                },
+                "capabilities": {
+                    "sim_time": true,
+                    "rng": true,
+                    "metrics_read": true,
+                    "enqueue_job": true,
+                    "log_debug": true
+                }
+            },
+            {
+                "id": "com.example.thermalboost",
+                "name": "Thermal Boost",
+                "version": "0.5.0",
+                "authors": ["Thermal Corp"],
+                "description": "Enhanced thermal management",
+                "enabled": false,
+                "signature": "valid",
+                "entrypoints": {
+                    "wasm_ops": ["Op_ThermalOptimizer"],
+                    "lua_events": ["on_heat_spike.lua"],
+                    "tech": "tech.toml"
+                },
+                "capabilities": {
+                    "sim_time": true,
+                    "metrics_read": true,
+                    "log_debug": true
+                }
+            }
+        ]
+    })))
+}
+
+async fn reload_mod(
+    State(_state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let default_mod_id = "unknown".to_string();
+    let mod_id = params.get("id").unwrap_or(&default_mod_id);
+    
+    // In a real implementation, this would trigger hot reload
+    Ok(Json(serde_json::json!({
+        "status": "reload_started",
+        "mod_id": mod_id,
+        "transaction_id": "tx_12345"
+    })))
+}
+
+async fn enable_mod(
+    State(_state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let default_mod_id = "unknown".to_string();
+    let mod_id = params.get("id").unwrap_or(&default_mod_id);
+    let enabled = params.get("on").and_then(|v| v.parse::<bool>().ok()).unwrap_or(false);
+    
+    // In a real implementation, this would enable/disable the mod
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "mod_id": mod_id,
+        "enabled": enabled
+    })))
+}
+
+async fn dryrun_mod(
+    State(_state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let default_mod_id = "unknown".to_string();
+    let mod_id = params.get("id").unwrap_or(&default_mod_id);
+    let ticks = params.get("ticks").and_then(|v| v.parse::<u32>().ok()).unwrap_or(120);
+    
+    // In a real implementation, this would run a dry run simulation
+    Ok(Json(serde_json::json!({
+        "status": "dryrun_completed",
+        "mod_id": mod_id,
+        "ticks_simulated": ticks,
+        "kpi_deltas": {
+            "deadline_hit_rate_change": 0.5,
+            "power_draw_change": 2.1,
+            "bandwidth_util_change": 0.3,
+            "corruption_field_change": 0.01,
+            "heat_levels_change": [1.2, 0.8]
+        },
+        "success": true,
+        "warnings": ["Minor bandwidth utilization increase"],
+        "errors": []
+    })))
+}
+
+async fn get_mod_docs(
+    State(_state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let default_mod_id = "all".to_string();
+    let mod_id = params.get("id").unwrap_or(&default_mod_id);
+    
+    // In a real implementation, this would return generated API docs
+    Ok(Json(serde_json::json!({
+        "mod_id": mod_id,
+        "sdk_version": "1.0.0",
+        "wasm_abi": {
+            "version": 1,
+            "functions": [
+                {
+                    "name": "colony_op_init",
+                    "signature": "extern \"C\" fn colony_op_init(ctx: *mut OpCtx) -> i32",
+                    "description": "Initialize the operation with the given context",
+                    "return_codes": [
+                        {"code": 0, "meaning": "Success", "description": "Operation initialized successfully"},
+                        {"code": -1, "meaning": "Error", "description": "Initialization failed"}
+                    ]
+                }
+            ]
+        },
+        "lua_api": {
+            "global_functions": [
+                {
+                    "name": "colony.get_sim_time",
+                    "signature": "get_sim_time() -> u64",
+                    "description": "Get current simulation time in ticks",
+                    "requires_capability": "sim_time"
+                }
+            ]
+        }
+    })))
+}
@@ -0,0 +1,143 @@
+use crate::AppState;
+use crate::limits::{ApiError};
+use super::{DotBody, GRAPHVIZ};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use colony_core::{SimClock, ResearchState, RitualRuns, RitualDef, tech_graph_dot};
+use serde::Deserialize;
+
+/// Research, rituals and achievements
+pub(super) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/research", get(get_research))
+        .route("/research/graph.dot", get(get_tech_graph))
+        .route("/research/unlock/:tech_id", post(unlock_tech))
+        .route("/rituals/:id/start", post(start_ritual))
+        .route("/rituals/runs", get(get_ritual_runs))
+        .route("/achievements", get(get_achievements))
+}
+
+async fn get_research(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let research = state.research.read().await;
+    let tech_tree = state.tech_tree.read().await;
+    let available: Vec<serde_json::Value> = tech_tree.get_available_techs(&research).into_iter()
+        .map(|tech| serde_json::json!({
+            "id": tech.id,
+            "name": tech.name,
+            "cost_pts": tech.cost_pts,
+            "requires": tech.requires
+        }))
+        .collect();
+    Ok(Json(serde_json::json!({
+        "pts": research.pts,
+        "acquired": research.acquired,
+        "available": available,
+        "rituals": research.rituals
+    })))
+}
+
+async fn get_tech_graph(
+    State(state): State<AppState>,
+) -> DotBody {
+    (GRAPHVIZ, tech_graph_dot(&*state.tech_tree.read().await))
+}
+
+async fn unlock_tech(
+    State(state): State<AppState>,
+    axum::extract::Path(tech_id): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let tech = state.tech_tree.read().await.get_tech(&tech_id).cloned()
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("Unknown tech '{}'", tech_id)))?;
+    let pts = state
+        .update(move |research: &mut ResearchState| {
+            if research.has_tech(&tech.id) {
+                return Err(ApiError::new(StatusCode::CONFLICT, format!("Tech '{}' is already unlocked", tech.id)));
+            }
+            if !research.research_tech(&tech) {
+                return Err(ApiError::new(StatusCode::CONFLICT, format!(
+                    "Tech '{}' needs {} pts and {:?}; the colony has {} pts", tech.id, tech.cost_pts, tech.requires, research.pts
+                )));
+            }
+            Ok(research.pts)
+        })
+        .await??;
+    Ok(Json(serde_json::json!({
+        "status": "unlocked",
+        "tech_id": tech_id,
+        "pts": pts
+    })))
+}
+
+#[derive(Deserialize, Default)]
+struct StartRitualRequest {
+    #[serde(default)]
+    workers: Vec<u64>,
+}
+
+async fn start_ritual(
+    State(state): State<AppState>,
+    axum::extract::Path(ritual_id): axum::extract::Path<String>,
+    request: Option<Json<StartRitualRequest>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let workers = request.map(|Json(r)| r.workers).unwrap_or_default();
+
+    // Definitions aren't loaded from content yet, so every ritual uses the placeholder
+    let def = RitualDef::default_for(&ritual_id);
+    let time_ms = def.time_ms;
+    let ritual = state.sim()?
+        .with_world(move |world| {
+            let now_tick = world.resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+            world.resource_mut::<RitualRuns>().start(&def, workers, now_tick).cloned()
+        })
+        .await?
+        .map_err(|e| ApiError::new(StatusCode::CONFLICT, e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "started",
+        "ritual_id": ritual_id,
+        "workers": ritual.workers,
+        "eta_ms": time_ms
+    })))
+}
+
+async fn get_ritual_runs(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let rituals = state.rituals.read().await;
+    Ok(Json(serde_json::json!({
+        "active": rituals.active,
+        "results": rituals.results
+    })))
+}
+
+async fn get_achievements(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let registry = state.achievement_registry.read().await;
+    let achievements = state.achievements.read().await;
+    let list: Vec<serde_json::Value> = registry.defs.iter()
+        .map(|def| {
+            let unlocked = achievements.profile.unlocked.iter().find(|u| u.id == def.id);
+            let description = if def.hidden && unlocked.is_none() { "???" } else { def.description.as_str() };
+            serde_json::json!({
+                "id": def.id,
+                "name": def.name,
+                "description": description,
+                "unlocked": unlocked.is_some(),
+                "unlocked_at": unlocked.map(|u| u.unlocked_at)
+            })
+        })
+        .collect();
+    Ok(Json(serde_json::json!({
+        "unlocked": achievements.profile.unlocked.len(),
+        "total": registry.defs.len(),
+        "achievements": list
+    })))
+}
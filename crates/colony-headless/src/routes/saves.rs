@@ -0,0 +1,177 @@
+use crate::AppState;
+use crate::limits::{ApiError};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use bevy::prelude::World;
+use colony_core::{GameSetup, ReplayLog, SaveFileV1, KpiSummary, load_scenarios, compare_runs, load_run_summary, run_summary_path};
+use serde::Deserialize;
+
+/// Save slots, replays and run comparison
+pub(super) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/save/manual", post(save_manual))
+        .route("/load/manual", post(load_manual))
+        .route("/replay/start", post(start_replay))
+        .route("/replay/stop", post(stop_replay))
+        .route("/compare", get(compare_run_summaries))
+}
+
+#[derive(Deserialize)]
+struct CompareQuery {
+    #[serde(rename = "runA")]
+    run_a: String,
+    #[serde(rename = "runB")]
+    run_b: String,
+}
+
+/// Diff two run summaries from the server's runs directory, named without the `.json`
+async fn compare_run_summaries(
+    State(state): State<AppState>,
+    axum::extract::Query(query): axum::extract::Query<CompareQuery>,
+) -> Result<Json<colony_core::RunComparison>, ApiError> {
+    let load = |name: &str| {
+        let path = run_summary_path(&state.server_config.runs_dir, name)
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+        if !path.is_file() {
+            return Err(ApiError::new(StatusCode::NOT_FOUND, format!("No run named {}", name)));
+        }
+        load_run_summary(&path).map_err(|e| ApiError::unprocessable(format!("{:#}", e)))
+    };
+    let (run_a, run_b) = (load(&query.run_a)?, load(&query.run_b)?);
+    Ok(Json(compare_runs(&query.run_a, &run_a, &query.run_b, &run_b)))
+}
+
+/// The served session as a save: colony, research, events, debts, win/loss and job numbering
+pub(super) async fn snapshot_session(state: &AppState) -> Result<SaveFileV1, String> {
+    let scenario = load_scenarios()
+        .ok()
+        .and_then(|scenarios| scenarios.into_iter().next())
+        .ok_or("No scenario to record in the snapshot")?;
+    let mut setup = GameSetup::new(scenario);
+    setup.mode = state.mode;
+    let kpis = KpiSummary {
+        bandwidth_util_history: Vec::new(),
+        corruption_field_history: Vec::new(),
+        power_draw_history: Vec::new(),
+        heat_levels_history: Vec::new(),
+        deadline_hit_rates: Vec::new(),
+        black_swan_events: Vec::new(),
+    };
+    Ok(SaveFileV1::new(
+        setup,
+        &*state.colony.read().await,
+        &*state.research.read().await,
+        &*state.black_swans.read().await,
+        &*state.debts.read().await,
+        &*state.win_loss.read().await,
+        &*state.session.read().await,
+        &ReplayLog::new(),
+        kpis,
+    )
+    .with_job_ids(&*state.job_ids.read().await)
+    .with_rng_audit(&*state.rng_audit.read().await))
+}
+
+/// Put a save's state into the world between ticks; everything the save doesn't cover carries on
+pub(super) fn restore_snapshot(world: &mut World, snapshot: SaveFileV1) {
+    world.insert_resource(snapshot.colony_state.to_colony());
+    world.insert_resource(snapshot.research_state);
+    world.insert_resource(snapshot.black_swan_state);
+    world.insert_resource(snapshot.debts);
+    world.insert_resource(snapshot.winloss);
+    world.insert_resource(snapshot.job_ids);
+    world.insert_resource(snapshot.rng_audit);
+    world.insert_resource(snapshot.session_ctl);
+}
+
+async fn save_manual(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let slot = slot_param(&params)?;
+    let snapshot = snapshot_session(&state).await.map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let (name, protection) = (slot.clone(), state.save_protection.clone());
+    tokio::task::spawn_blocking(move || colony_core::save_to_slot_with(&snapshot, &name, &protection))
+        .await
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "saved",
+        "slot": slot
+    })))
+}
+
+/// The `slot` query parameter, kept to a bare file name under saves/
+fn slot_param(params: &std::collections::HashMap<String, String>) -> Result<String, ApiError> {
+    let slot = params.get("slot").map(String::as_str).unwrap_or("manual_save");
+    if slot.is_empty() || slot.starts_with('.') || slot.contains(['/', '\\']) {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, format!("Bad slot name '{}'", slot)));
+    }
+    Ok(slot.to_string())
+}
+
+async fn load_manual(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let slot = slot_param(&params)?;
+    if !std::path::Path::new(&format!("saves/{}.json", slot)).exists() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, format!("No save in slot '{}'", slot)));
+    }
+    let (name, protection) = (slot.clone(), state.save_protection.clone());
+    let snapshot = tokio::task::spawn_blocking(move || colony_core::load_from_slot_with(&name, &protection))
+        .await
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+    if snapshot.game_setup.mode != state.mode {
+        return Err(ApiError::new(StatusCode::CONFLICT, format!(
+            "Slot '{}' holds a {} session but this server runs {} sessions",
+            slot, snapshot.game_setup.mode.name(), state.mode.name()
+        )));
+    }
+    state.sim()?.with_world(move |world| restore_snapshot(world, snapshot)).await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "loaded",
+        "slot": slot
+    })))
+}
+
+/// Play back a replay log saved as saves/<path>.replay
+async fn start_replay(
+    State(state): State<AppState>,
+    Json(request): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let path = request.get("path").and_then(|v| v.as_str()).unwrap_or("replay").to_string();
+    let params = std::collections::HashMap::from([("slot".to_string(), path.clone())]);
+    let file = std::path::PathBuf::from(format!("saves/{}.replay", slot_param(&params)?));
+    if !file.exists() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, format!("No replay at {}", file.display())));
+    }
+    let mut log = ReplayLog::load_from(&file, &state.save_protection)
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+    log.start_playback();
+    let events = log.events.len();
+    state.sim()?.with_world(move |world| world.insert_resource(log)).await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "replay_started",
+        "path": path,
+        "events": events
+    })))
+}
+
+async fn stop_replay(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    state.sim()?.with_world(|world| world.resource_mut::<ReplayLog>().stop()).await?;
+    Ok(Json(serde_json::json!({
+        "status": "replay_stopped"
+    })))
+}
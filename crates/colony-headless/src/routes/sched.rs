@@ -0,0 +1,256 @@
+use crate::AppState;
+use crate::limits::{ApiError, JobLimits, Validate, ValidJson};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, post, put},
+    Router,
+};
+use colony_core::{SchedPolicy, ActiveScheduler, PreviewRequest, ShadowSeed, run_preview, QosQueueing, QueueDiscipline, QosWeights, qos_name, SandboxRequest, run_scheduler_sandbox, TrafficShaper, TrafficShapingConfig, SchedulerRegistry, FairTunables};
+use serde::Deserialize;
+
+/// Scheduling policy, QoS queueing, traffic shaping, previews and sandbox runs
+pub(super) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/scheduler", put(set_scheduler))
+        .route("/sched/policy", get(get_scheduler_policy).put(set_scheduler_policy))
+        .route("/sched/qos", put(set_qos_discipline))
+        .route("/sched/fair", get(get_fair_tunables).put(set_fair_tunables))
+        .route("/metrics/qos", get(get_qos_metrics))
+        .route("/net/shaping", get(get_traffic_shaping).put(set_traffic_shaping))
+        .route("/preview", post(preview_changes))
+        .route("/sched/sandbox", post(run_sandbox))
+}
+
+const SCHEDULERS: [&str; 5] = ["FCFS", "SJF", "EDF", "FAIR", "HeteroAware"];
+
+#[derive(Deserialize)]
+struct SchedulerRequest {
+    scheduler: String,
+}
+
+impl Validate for SchedulerRequest {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        if !SCHEDULERS.contains(&self.scheduler.as_str()) {
+            return Err(format!("Unknown scheduler '{}'; expected one of {}", self.scheduler, SCHEDULERS.join(", ")));
+        }
+        Ok(())
+    }
+}
+
+async fn set_scheduler(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<SchedulerRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let policy = match request.scheduler.as_str() {
+        "FCFS" => SchedPolicy::Fcfs,
+        "SJF" => SchedPolicy::Sjf,
+        "EDF" => SchedPolicy::Edf,
+        "FAIR" => SchedPolicy::FairShare,
+        other => return Err(ApiError::unprocessable(format!("The {} scheduler isn't available in the sim yet", other))),
+    };
+    state.update(move |scheduler: &mut ActiveScheduler| *scheduler = ActiveScheduler { policy, custom: None }).await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "scheduler": request.scheduler
+    })))
+}
+
+/// Schedulers the sim can dispatch with; mods and embedding crates register more at runtime
+async fn scheduler_registry(state: &AppState) -> Result<SchedulerRegistry, ApiError> {
+    match state.sim() {
+        Ok(sim) => sim.with_world(|world| world.get_resource::<SchedulerRegistry>().cloned().unwrap_or_default()).await,
+        Err(_) => Ok(SchedulerRegistry::new()),
+    }
+}
+
+async fn get_scheduler_policy(State(state): State<AppState>) -> Result<Json<serde_json::Value>, ApiError> {
+    let available = scheduler_registry(&state).await?.names();
+    Ok(Json(serde_json::json!({
+        "policy": state.scheduler.read().await.name(),
+        "available": available,
+    })))
+}
+
+async fn set_scheduler_policy(
+    State(state): State<AppState>,
+    Json(request): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let policy_str = request.get("policy")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "Expected {\"policy\": \"<name>\"}"))?;
+
+    let policy = policy_str.to_string();
+    state.sim()?
+        .with_world(move |world| {
+            let registry = world.get_resource::<SchedulerRegistry>().cloned().unwrap_or_default();
+            world.resource_mut::<ActiveScheduler>().select(&policy, &registry)
+        })
+        .await?
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "policy": policy_str
+    })))
+}
+
+async fn get_fair_tunables(State(state): State<AppState>) -> Result<Json<FairTunables>, ApiError> {
+    Ok(Json(scheduler_registry(&state).await?.fair().tunables()))
+}
+
+/// Change the fair-share scheduler's QoS weights and aging; jobs already queued keep their place
+async fn set_fair_tunables(
+    State(state): State<AppState>,
+    Json(tunables): Json<FairTunables>,
+) -> Result<Json<FairTunables>, ApiError> {
+    state.sim()?
+        .with_world(move |world| match world.get_resource::<SchedulerRegistry>() {
+            Some(registry) => registry.fair().set_tunables(tunables)
+                .map(|_| registry.fair().tunables())
+                .map_err(|e| e.to_string()),
+            None => Err("The simulation has no scheduler registry".to_string()),
+        })
+        .await?
+        .map(Json)
+        .map_err(ApiError::unprocessable)
+}
+
+#[derive(Deserialize)]
+struct QosDisciplineRequest {
+    discipline: QueueDiscipline,
+    weights: Option<QosWeights>,
+    drr_quantum_ms: Option<f32>,
+}
+
+async fn set_qos_discipline(
+    State(state): State<AppState>,
+    Json(request): Json<QosDisciplineRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let (discipline, weights) = state
+        .update(move |qos: &mut QosQueueing| {
+            qos.set_discipline(request.discipline);
+            if let Some(weights) = request.weights {
+                qos.weights = weights;
+            }
+            if let Some(quantum) = request.drr_quantum_ms {
+                qos.drr_quantum_ms = quantum;
+            }
+            // Wait stats from the previous discipline aren't comparable
+            qos.reset_wait_stats();
+            (qos.discipline, qos.weights.clone())
+        })
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "discipline": discipline,
+        "weights": weights,
+    })))
+}
+
+impl Validate for TrafficShapingConfig {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        TrafficShapingConfig::validate(self).map_err(|e| e.to_string())
+    }
+}
+
+async fn set_traffic_shaping(
+    State(state): State<AppState>,
+    ValidJson(config): ValidJson<TrafficShapingConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let (enabled, policies) = state
+        .update(move |shaping: &mut TrafficShaper| {
+            shaping.configure(&config).map(|_| (shaping.enabled, shaping.policies.clone()))
+        })
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "enabled": enabled,
+        "policies": policies,
+    })))
+}
+
+async fn get_traffic_shaping(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let shaping = state.shaping.read().await;
+    Ok(Json(serde_json::json!({
+        "enabled": shaping.enabled,
+        "policies": shaping.policies,
+        "guarantee_scale": shaping.guarantee_scale,
+        "lanes": shaping.lanes,
+        "unshaped": shaping.unshaped,
+    })))
+}
+
+async fn get_qos_metrics(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let qos = state.qos.read().await;
+    let classes: serde_json::Map<String, serde_json::Value> = qos.wait.iter().enumerate()
+        .map(|(i, w)| (qos_name(i).to_string(), serde_json::json!({
+            "dispatched": w.dispatched,
+            "mean_wait_ticks": w.mean_wait_ticks(),
+            "max_wait_ticks": w.max_wait_ticks,
+        })))
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "discipline": qos.discipline,
+        "weights": qos.weights,
+        "classes": classes,
+    })))
+}
+
+async fn preview_changes(
+    State(state): State<AppState>,
+    Json(request): Json<PreviewRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if request.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "The preview proposes no changes"));
+    }
+
+    // Fork between ticks, then run the shadow worlds off both the sim thread and the async executor
+    let seed = state.sim()?.with_world(|world| ShadowSeed::capture(world)).await?
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let result = tokio::task::spawn_blocking(move || run_preview(&seed, &request))
+        .await
+        .map_err(|_| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "The preview run panicked"))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "preview_completed",
+        "ticks_simulated": result.ticks_simulated,
+        "baseline": result.baseline,
+        "proposed": result.proposed,
+        "kpi_deltas": result.deltas
+    })))
+}
+
+async fn run_sandbox(
+    State(state): State<AppState>,
+    Json(request): Json<SandboxRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    // Fork between ticks so every policy replays on the live yards, workers and schedulers
+    let seed = state.sim()?.with_world(|world| ShadowSeed::capture(world)).await?
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let arrivals = state.arrivals.read().await.clone();
+
+    // One shadow world per policy; keep them off the async executor
+    let result = tokio::task::spawn_blocking(move || run_scheduler_sandbox(&seed, &arrivals, &request))
+        .await
+        .map_err(|_| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "The sandbox run panicked"))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "sandbox_completed",
+        "ticks_replayed": result.ticks_replayed,
+        "arrivals_replayed": result.arrivals_replayed,
+        "live_policy": result.live_policy,
+        "best_policy": result.best_policy,
+        "outcomes": result.outcomes
+    })))
+}
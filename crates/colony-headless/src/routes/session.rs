@@ -0,0 +1,294 @@
+use crate::AppState;
+use crate::limits::{ApiError, JobLimits, Validate, ValidJson};
+use crate::replica::ReplicaStatus;
+use crate::sim;
+use super::saves::{restore_snapshot, snapshot_session};
+use axum::{
+    extract::{DefaultBodyLimit, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post, put},
+    Router,
+};
+use colony_core::{ContentPack, GameSetup, SessionCtl, SessionMeta, SessionMetaPatch, PausePolicies, ProgressHandle, RunProgress, SessionSchedule, ScheduleRequest, LiveSession, GenKnobs, GeneratedScenario, generate_scenario};
+use serde::Deserialize;
+
+/// Session control, scheduling and live migration
+pub(super) fn routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/scenarios/generate", post(generate_scenario_handler))
+        .route("/session/start", post(start_session))
+        .route("/session/pause", post(pause_session))
+        .route("/session/resume", post(resume_session))
+        .route("/session/ffwd", put(set_fast_forward))
+        .route("/session/status", get(get_session_status))
+        .route("/session/progress", get(get_session_progress).with_state(state.run_progress.clone()))
+        .route("/session/autosave", put(set_autosave_interval))
+        .route("/session/meta", get(get_session_meta).patch(patch_session_meta))
+        .route("/session/pause-policies", get(get_pause_policies).put(set_pause_policies))
+        .route("/sessions/schedule", get(get_session_schedule).post(schedule_session))
+        .route("/sessions/schedule/:id", axum::routing::delete(cancel_scheduled_session))
+        .route("/session/export", post(export_session))
+        .route("/session/import", post(import_session).layer(DefaultBodyLimit::max(state.server_config.max_session_bytes)))
+        .route("/replica/status", get(get_replica_status))
+}
+
+#[derive(Deserialize)]
+struct GenerateScenarioRequest {
+    seed: u64,
+    #[serde(default)]
+    knobs: GenKnobs,
+}
+
+impl Validate for GenerateScenarioRequest {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        self.knobs.validate().map_err(|e| e.to_string())
+    }
+}
+
+async fn generate_scenario_handler(
+    ValidJson(request): ValidJson<GenerateScenarioRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let generated: GeneratedScenario = generate_scenario(request.seed, &request.knobs)
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+    let commands = generated.commands();
+
+    Ok(Json(serde_json::json!({
+        "scenario_id": generated.scenario.id,
+        "generated": generated,
+        "commands": commands,
+    })))
+}
+
+/// Restart the sim on the setup's scenario and tick scale; the mode stays the server's
+async fn start_session(
+    State(state): State<AppState>,
+    Json(game_setup): Json<GameSetup>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if game_setup.mode != state.mode {
+        return Err(ApiError::new(StatusCode::CONFLICT, format!(
+            "Setup is for a {} session but this server runs {} sessions",
+            game_setup.mode.name(), state.mode.name()
+        )));
+    }
+    if let Some(running) = state.schedule.read().await.running() {
+        return Err(ApiError::new(StatusCode::CONFLICT, format!("Scheduled session {} is running; cancel it first", running.id)));
+    }
+    let tick_scale = game_setup.parse_tick_scale().map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    let (scenario, mode) = (game_setup.scenario.clone(), state.mode);
+    state.sim()?
+        .restart(move |old| {
+            let content = old.world().resource::<ContentPack>().clone();
+            let app = sim::with_server_resources(sim::build_sim_app(Some(&scenario), None, mode, &content, tick_scale)?, old);
+            #[cfg(feature = "ffi_scheduler")]
+            let app = sim::with_schedulers(app, &sim::ffi_schedulers(old))?;
+            Ok(app)
+        })
+        .await?;
+    state.pending_commands.write().await.clear();
+    println!("Started session on scenario {} at {}", game_setup.scenario.id, game_setup.tick_scale);
+
+    Ok(Json(serde_json::json!({
+        "status": "started",
+        "scenario": game_setup.scenario.name,
+        "tick_scale": game_setup.tick_scale
+    })))
+}
+
+async fn pause_session(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    state.update(|session: &mut SessionCtl| session.pause()).await?;
+    Ok(Json(serde_json::json!({
+        "status": "paused"
+    })))
+}
+
+async fn resume_session(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    state.update(|session: &mut SessionCtl| session.resume()).await?;
+    Ok(Json(serde_json::json!({
+        "status": "resumed"
+    })))
+}
+
+async fn set_fast_forward(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let on = params.get("on").and_then(|v| v.parse::<bool>().ok()).unwrap_or(false);
+    state.update(move |session: &mut SessionCtl| session.fast_forward = on).await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "fast_forward": on
+    })))
+}
+
+async fn get_session_status(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let (meta, running, fast_forward) = {
+        let session = state.session.read().await;
+        (session.meta.clone(), session.running, session.fast_forward)
+    };
+    let last_pause = state.pause_monitor.read().await.last().cloned();
+    let scheduled = state.schedule.read().await.running().cloned();
+    let win_loss = state.win_loss.read().await.clone();
+    let (sim_time, sla_hit_rate) = {
+        let view = state.view.read().await;
+        (view.tick, view.sla_hit_rate)
+    };
+    Ok(Json(serde_json::json!({
+        "meta": meta,
+        "mode": state.mode,
+        "last_pause": last_pause,
+        "scheduled": scheduled,
+        "running": running,
+        "fast_forward": fast_forward,
+        "sim_time": sim_time,
+        "day_count": win_loss.achieved_days,
+        "sla_pct": sla_hit_rate * 100.0,
+        "victory": win_loss.victory,
+        "doom": win_loss.doom,
+        "score": win_loss.score
+    })))
+}
+
+async fn get_session_schedule(State(state): State<AppState>) -> Result<Json<SessionSchedule>, StatusCode> {
+    Ok(Json(state.schedule.read().await.clone()))
+}
+
+async fn schedule_session(
+    State(state): State<AppState>,
+    Json(request): Json<ScheduleRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let mut schedule = state.schedule.write().await;
+    let id = schedule.add(request, chrono::Utc::now()).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let entry = schedule.entries.iter().find(|e| e.id == id).cloned();
+    Ok(Json(serde_json::json!({
+        "status": "scheduled",
+        "id": id,
+        "entry": entry
+    })))
+}
+
+async fn cancel_scheduled_session(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let was_running = state.schedule.write().await
+        .cancel(id, chrono::Utc::now())
+        .map_err(|e| ApiError::new(StatusCode::NOT_FOUND, e.to_string()))?;
+    if was_running {
+        state.update(|session: &mut SessionCtl| session.pause()).await?;
+    }
+    Ok(Json(serde_json::json!({
+        "status": "cancelled",
+        "id": id,
+        "stopped": was_running
+    })))
+}
+
+async fn get_session_meta(State(state): State<AppState>) -> Result<Json<SessionMeta>, StatusCode> {
+    Ok(Json(state.session.read().await.meta.clone()))
+}
+
+async fn patch_session_meta(
+    State(state): State<AppState>,
+    Json(request): Json<SessionMetaPatch>,
+) -> Result<Json<SessionMeta>, ApiError> {
+    state
+        .update(move |session: &mut SessionCtl| session.meta.apply(request).map(|_| session.meta.clone()))
+        .await?
+        .map(Json)
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+async fn get_pause_policies(State(state): State<AppState>) -> Result<Json<serde_json::Value>, StatusCode> {
+    let policies = state.pause_policies.read().await.clone();
+    let monitor = state.pause_monitor.read().await;
+    Ok(Json(serde_json::json!({
+        "policies": policies,
+        "events": monitor.events,
+    })))
+}
+
+async fn set_pause_policies(
+    State(state): State<AppState>,
+    Json(request): Json<PausePolicies>,
+) -> Result<Json<PausePolicies>, ApiError> {
+    request.validate().map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let policies = request.clone();
+    state.update(move |current: &mut PausePolicies| *current = policies).await?;
+    Ok(Json(request))
+}
+
+/// Snapshot the live session for another instance to import. The session is paused so it
+/// doesn't run on here after the copy is taken; the bundle keeps whether it was running.
+async fn export_session(State(state): State<AppState>) -> Result<Json<LiveSession>, ApiError> {
+    let snapshot = snapshot_session(&state).await.map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let clock = state.clock.read().await.clone();
+    let bundle = LiveSession::new(snapshot, clock, state.pending_commands.read().await.clone());
+    state.update(|session: &mut SessionCtl| session.pause()).await?;
+    println!("Exported live session at tick {} for migration; paused here", bundle.rng.tick);
+    Ok(Json(bundle))
+}
+
+/// Replace this instance's session with one exported elsewhere and resume it where it stopped
+async fn import_session(
+    State(state): State<AppState>,
+    body: axum::body::Bytes,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let bundle = LiveSession::from_bytes(&body).map_err(|e| ApiError::unprocessable(e.to_string()))?;
+    if bundle.mode != state.mode {
+        return Err(ApiError::new(StatusCode::CONFLICT, format!(
+            "Bundle is a {} session but this server runs {} sessions",
+            bundle.mode.name(), state.mode.name()
+        )));
+    }
+    if let Some(running) = state.schedule.read().await.running() {
+        return Err(ApiError::new(StatusCode::CONFLICT, format!("Scheduled session {} is running; cancel it first", running.id)));
+    }
+
+    let (clock, snapshot) = (bundle.clock, bundle.snapshot);
+    state.sim()?
+        .with_world(move |world| {
+            world.insert_resource(clock);
+            restore_snapshot(world, snapshot);
+        })
+        .await?;
+    *state.pending_commands.write().await = bundle.pending_commands;
+    println!("Imported live session at tick {} (seed {})", bundle.rng.tick, bundle.rng.seed);
+
+    Ok(Json(serde_json::json!({
+        "status": "imported",
+        "tick": bundle.rng.tick,
+        "seed": bundle.rng.seed,
+        "pending_commands": state.pending_commands.read().await.len(),
+        "running": state.session.read().await.running
+    })))
+}
+
+pub(crate) async fn get_session_progress(State(progress): State<ProgressHandle>) -> Result<Json<RunProgress>, StatusCode> {
+    Ok(Json(progress.snapshot()))
+}
+
+async fn get_replica_status(State(state): State<AppState>) -> Json<ReplicaStatus> {
+    Json(state.replica.read().await.clone())
+}
+
+async fn set_autosave_interval(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let minutes = params.get("minutes").and_then(|v| v.parse::<u32>().ok()).unwrap_or(5);
+    state.update(move |session: &mut SessionCtl| session.set_autosave_interval(minutes)).await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "autosave_interval_minutes": minutes
+    })))
+}
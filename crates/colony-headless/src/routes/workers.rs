@@ -0,0 +1,181 @@
+use crate::AppState;
+use crate::limits::{ApiError, JobLimits, Validate, ValidJson};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, post, put},
+    Router,
+};
+use colony_core::{SimClock, GpuTunables, CertificationBoard, CertKind, GpuFaults, GpuFaultConfig, GpuFarm, Worker, WorkerHealth, WorkerStateComp, BulkWorkerOp, BulkOutcome, apply_bulk, reimage};
+use serde::Deserialize;
+
+/// Worker care and training, and the GPU farm
+pub(super) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/workers/:id/reimage", post(reimage_worker))
+        .route("/workers/bulk", post(bulk_workers))
+        .route("/workers/:id/train", post(train_worker))
+        .route("/workers/certifications", get(get_certifications))
+        .route("/metrics/gpu", get(get_gpu_metrics))
+        .route("/gpu/tunables", put(set_gpu_tunables))
+        .route("/gpu/flags", put(set_gpu_flags))
+        .route("/gpu/faults", get(get_gpu_faults).put(set_gpu_faults))
+}
+
+#[derive(Deserialize)]
+struct TrainWorkerRequest {
+    cert: CertKind,
+}
+
+async fn train_worker(
+    State(state): State<AppState>,
+    axum::extract::Path(worker_id): axum::extract::Path<u64>,
+    Json(request): Json<TrainWorkerRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let run = state.sim()?
+        .with_world(move |world| {
+            let now_tick = world.resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+            world.resource_mut::<CertificationBoard>().start_training(worker_id, request.cert, now_tick).cloned()
+        })
+        .await?
+        .map_err(|e| ApiError::new(StatusCode::CONFLICT, e.to_string()))?;
+    Ok(Json(serde_json::json!({
+        "status": "training",
+        "worker_id": worker_id,
+        "cert": run.cert,
+        "completes_tick": run.completes_tick
+    })))
+}
+
+async fn get_certifications(State(state): State<AppState>) -> Result<Json<CertificationBoard>, StatusCode> {
+    Ok(Json(state.certifications.read().await.clone()))
+}
+
+/// Clear a worker's corruption and sticky faults and bring it back from recovery
+async fn reimage_worker(
+    State(state): State<AppState>,
+    axum::extract::Path(worker_id): axum::extract::Path<u64>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let found = state.sim()?
+        .with_world(move |world| {
+            let mut workers = world.query::<(&Worker, &mut WorkerHealth, &mut WorkerStateComp)>();
+            let Some((_, mut health, mut worker_state)) = workers.iter_mut(world).find(|(w, ..)| w.id == worker_id) else {
+                return false;
+            };
+            reimage(&mut health, &mut worker_state);
+            true
+        })
+        .await?;
+    if !found {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, format!("No worker {}", worker_id)));
+    }
+
+    Ok(Json(serde_json::json!({
+        "status": "reimaged",
+        "worker_id": worker_id
+    })))
+}
+
+/// Reimage, reassign or set retry policies for every worker matching a filter, in one step between ticks
+async fn bulk_workers(
+    State(state): State<AppState>,
+    Json(op): Json<BulkWorkerOp>,
+) -> Result<Json<BulkOutcome>, ApiError> {
+    let outcome = state.sim()?
+        .with_world(move |world| apply_bulk(world, &op))
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+    Ok(Json(outcome))
+}
+
+async fn get_gpu_metrics(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let faults = state.gpu_faults.read().await;
+    let farm = state.view.read().await.gpu.clone();
+    let gpu = farm.clone().unwrap_or_default();
+    Ok(Json(serde_json::json!({
+        "farm": farm.is_some(),
+        "util": gpu.util,
+        "vram_used_gb": gpu.vram_used_gb,
+        "batches_inflight": gpu.batches_inflight,
+        "batch_latency_ms": gpu.batch_latency_ms,
+        "queues": gpu.queues,
+        "faults": faults.kpi,
+        "requeued_items": faults.requeued.len(),
+    })))
+}
+
+impl Validate for GpuFaultConfig {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        GpuFaultConfig::validate(self).map_err(|e| e.to_string())
+    }
+}
+
+async fn set_gpu_faults(
+    State(state): State<AppState>,
+    ValidJson(config): ValidJson<GpuFaultConfig>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let new_config = config.clone();
+    state.update(move |gpu_faults: &mut GpuFaults| gpu_faults.configure(new_config))
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "config": config,
+    })))
+}
+
+async fn get_gpu_faults(State(state): State<AppState>) -> Result<Json<serde_json::Value>, StatusCode> {
+    let faults = state.gpu_faults.read().await;
+    Ok(Json(serde_json::json!({
+        "config": faults.config,
+        "kpi": faults.kpi,
+        "requeued_items": faults.requeued.len(),
+    })))
+}
+
+async fn set_gpu_tunables(
+    State(state): State<AppState>,
+    Json(tunables): Json<GpuTunables>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let per_gpu = tunables.clone();
+    let farms = state.sim()?
+        .with_world(move |world| {
+            let mut farms = world.query::<&mut GpuFarm>();
+            farms.iter_mut(world).map(|mut farm| farm.per_gpu = per_gpu.clone()).count()
+        })
+        .await?;
+    if farms == 0 {
+        return Err(ApiError::new(StatusCode::CONFLICT, "The colony has no GPU farm"));
+    }
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "tunables": tunables
+    })))
+}
+
+async fn set_gpu_flags(
+    State(state): State<AppState>,
+    Json(request): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let mixed_precision = request.get("mixed_precision")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let farms = state.sim()?
+        .with_world(move |world| {
+            let mut farms = world.query::<&mut GpuFarm>();
+            farms.iter_mut(world).map(|mut farm| farm.flags.mixed_precision = mixed_precision).count()
+        })
+        .await?;
+    if farms == 0 {
+        return Err(ApiError::new(StatusCode::CONFLICT, "The colony has no GPU farm"));
+    }
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "mixed_precision": mixed_precision
+    })))
+}
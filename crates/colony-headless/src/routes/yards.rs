@@ -0,0 +1,121 @@
+use crate::AppState;
+use crate::limits::{ApiError, JobLimits, Validate, ValidJson};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::{get, put},
+    Router,
+};
+use colony_core::{MaintenanceLane, ClassReservations, ClassReservation, YardGrid, YardPos, PlacementTunables, yard_layout, move_yard};
+use serde::Deserialize;
+
+/// Yard placement, class reservations and the maintenance lane
+pub(super) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/maintenance/lane", get(get_maintenance_lane).put(set_maintenance_lane))
+        .route("/yards/reservations", get(get_class_reservations).put(set_class_reservations))
+        .route("/yards/layout", get(get_yard_layout).put(set_yard_layout))
+        .route("/yards/:id/position", put(set_yard_position))
+}
+
+#[derive(Deserialize)]
+struct MaintenanceLaneRequest {
+    reservation_frac: f32,
+}
+
+async fn get_maintenance_lane(State(state): State<AppState>) -> Result<Json<MaintenanceLane>, StatusCode> {
+    Ok(Json(state.maintenance_lane.read().await.clone()))
+}
+
+async fn set_maintenance_lane(
+    State(state): State<AppState>,
+    Json(request): Json<MaintenanceLaneRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if !(0.0..=1.0).contains(&request.reservation_frac) {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "reservation_frac must be between 0 and 1"));
+    }
+    let reservation_frac = state
+        .update(move |lane: &mut MaintenanceLane| {
+            lane.set_reservation(request.reservation_frac);
+            lane.reservation_frac
+        })
+        .await?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "reservation_frac": reservation_frac
+    })))
+}
+
+async fn get_class_reservations(State(state): State<AppState>) -> Result<Json<ClassReservations>, StatusCode> {
+    Ok(Json(state.class_reservations.read().await.clone()))
+}
+
+async fn set_class_reservations(
+    State(state): State<AppState>,
+    Json(reservations): Json<Vec<ClassReservation>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let mut replacement = ClassReservations::from_reservations(&reservations).map_err(|e| ApiError::unprocessable(e.to_string()))?;
+    state
+        .update(move |current: &mut ClassReservations| {
+            replacement.dispatched = std::mem::take(&mut current.dispatched);
+            *current = replacement;
+        })
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "reservations": reservations,
+    })))
+}
+
+impl Validate for PlacementTunables {
+    fn validate(&self, _limits: &JobLimits) -> Result<(), String> {
+        PlacementTunables::validate(self).map_err(|e| e.to_string())
+    }
+}
+
+/// Every placed yard with its cell, the neighbours it trades heat with and its cable run
+async fn get_yard_layout(State(state): State<AppState>) -> Result<Json<serde_json::Value>, ApiError> {
+    let yards = state.sim()?.with_world(yard_layout).await?;
+    let grid = state.yard_grid.read().await;
+    Ok(Json(serde_json::json!({
+        "tunables": grid.tunables,
+        "yards": yards,
+    })))
+}
+
+async fn set_yard_layout(
+    State(state): State<AppState>,
+    ValidJson(tunables): ValidJson<PlacementTunables>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let new_tunables = tunables.clone();
+    state.update(move |grid: &mut YardGrid| grid.configure(new_tunables))
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "updated",
+        "tunables": tunables,
+    })))
+}
+
+/// Move a yard (`3` or `yard:3`) to another free cell
+async fn set_yard_position(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(to): Json<YardPos>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let yard_index: u32 = id.strip_prefix("yard:").unwrap_or(&id).parse()
+        .map_err(|_| ApiError::unprocessable(format!("'{}' is not a yard id", id)))?;
+    state.sim()?
+        .with_world(move |world| move_yard(world, yard_index, to))
+        .await?
+        .map_err(|e| ApiError::unprocessable(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "moved",
+        "yard": format!("yard:{}", yard_index),
+        "position": to,
+    })))
+}
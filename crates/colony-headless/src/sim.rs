@@ -0,0 +1,499 @@
+use crate::limits::ApiError;
+use axum::http::StatusCode;
+use bevy::prelude::*;
+use colony_core::{
+    apply_command, colony_from_scenario, thermal_throttle, BlackBox, ClassReservations, OpLicenses, ColonyPlugin, Colony, ContentPack,
+    GameMode, GpuBatchQueues, GpuFarm, JobQueue, KpiSnapshot, Notification, NotificationFeed, SaveProtection, Scenario, SessionCtl, SimClock,
+    SlaTracker, TickScale, DEFAULT_POSTMORTEM_DIR,
+    TimedCommand, Worker, WorkerHealth, WorkerSkills, WorkerStateComp, Workyard,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, oneshot, RwLock, RwLockReadGuard};
+
+/// One sim tick, the 16ms every `now / 16` tick conversion assumes
+const TICK_INTERVAL: Duration = Duration::from_millis(16);
+/// Ticks run per tick interval while the session fast-forwards
+const FAST_FORWARD_TICKS: u32 = 10;
+/// How far the served copies may lag the world
+const MIRROR_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The server's copy of a world resource, for handlers to read. Changes go into the world
+/// through `SimHandle::with_world`, touching only what they change, and the sim thread
+/// refreshes this copy from the world.
+pub struct Synced<T> {
+    lock: RwLock<T>,
+    seen: AtomicU64, // change tick of the world copy last mirrored, plus one; 0 before the first
+}
+
+impl<T> Synced<T> {
+    pub fn new(value: T) -> Self {
+        Self { lock: RwLock::new(value), seen: AtomicU64::new(0) }
+    }
+
+    pub async fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.lock.read().await
+    }
+
+    /// Serve a primary's copy. Only observers, which run no world, set a served copy directly.
+    pub async fn replace(&self, value: T) {
+        *self.lock.write().await = value;
+    }
+}
+
+/// A served copy the sim thread keeps in step with the world
+pub trait Mirror: Send + Sync {
+    /// Refresh from the world if the resource changed since the last pull
+    fn pull(&self, world: &World);
+    /// The world was replaced; the next pull copies everything again
+    fn forget(&self);
+}
+
+impl<T: Resource + Clone> Mirror for Synced<T> {
+    fn pull(&self, world: &World) {
+        let Some(value) = world.get_resource_ref::<T>() else { return };
+        let changed = value.last_changed().get() as u64 + 1;
+        if self.seen.load(Ordering::Acquire) == changed {
+            return;
+        }
+        *self.lock.blocking_write() = value.clone();
+        self.seen.store(changed, Ordering::Release);
+    }
+
+    fn forget(&self) {
+        self.seen.store(0, Ordering::Release);
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkerStatus {
+    pub id: u64,
+    pub state: String,
+    pub skill_cpu: f32,
+    pub corruption: f32,
+    pub sticky_faults: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct YardStatus {
+    pub kind: String,
+    pub heat: f32,
+    pub heat_cap: f32,
+    pub throttle: f32,
+    pub power_draw_kw: f32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueueDepths {
+    pub cpu: usize,
+    pub gpu: usize,
+    pub io: usize,
+    pub storage: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GpuStatus {
+    pub util: f32,
+    pub vram_used_gb: f32,
+    pub batches_inflight: u32,
+    pub batch_latency_ms: f32, // worst farm
+    pub queues: BTreeMap<String, usize>, // items waiting per pipeline batch buffer
+}
+
+/// What handlers serve beyond single resources: entities, queue depths and KPIs, as of the last mirror
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SimView {
+    pub tick: u64,
+    pub workers: Vec<WorkerStatus>,
+    pub yards: Vec<YardStatus>,
+    pub queued: QueueDepths,
+    pub kpis: KpiSnapshot,
+    pub sla_hit_rate: f32, // rolling, over the SLA window
+    pub gpu: Option<GpuStatus>, // None when the colony has no GPU farm
+}
+
+impl SimView {
+    pub fn capture(world: &mut World) -> Self {
+        let tick = world.get_resource::<SimClock>().map_or(0, |c| c.now.timestamp_millis() as u64 / 16);
+        let mut workers: Vec<WorkerStatus> = world
+            .query::<(&Worker, &WorkerSkills, &WorkerHealth, &WorkerStateComp)>()
+            .iter(world)
+            .map(|(w, skills, health, state)| WorkerStatus {
+                id: w.id,
+                state: format!("{:?}", state.0),
+                skill_cpu: skills.cpu,
+                corruption: health.corruption,
+                sticky_faults: health.sticky_faults,
+            })
+            .collect();
+        workers.sort_by_key(|w| w.id);
+
+        let (knee, floor) = world.get_resource::<Colony>()
+            .map_or((0.85, 0.5), |c| (c.tunables.thermal_throttle_knee, c.tunables.thermal_min_throttle));
+        let yards = world.query::<&Workyard>().iter(world)
+            .map(|y| YardStatus {
+                kind: format!("{:?}", y.kind),
+                heat: y.heat,
+                heat_cap: y.heat_cap,
+                throttle: thermal_throttle(y.heat, y.heat_cap, knee, floor),
+                power_draw_kw: y.power_draw_kw,
+            })
+            .collect();
+
+        let queued = world.get_resource::<JobQueue>().map_or_else(QueueDepths::default, |q| QueueDepths {
+            cpu: q.cpu.len(),
+            gpu: q.gpu.len(),
+            io: q.io.len(),
+            storage: q.storage.len(),
+        });
+
+        let farms: Vec<GpuFarm> = world.query::<&GpuFarm>().iter(world).cloned().collect();
+        let gpu = (!farms.is_empty()).then(|| GpuStatus {
+            util: farms.iter().map(|f| f.meters.util).sum::<f32>() / farms.len() as f32,
+            vram_used_gb: farms.iter().map(|f| f.meters.vram_used_gb).sum(),
+            batches_inflight: farms.iter().map(|f| f.meters.batches_inflight).sum(),
+            batch_latency_ms: farms.iter().map(|f| f.meters.batch_latency_ms).fold(0.0, f32::max),
+            queues: world.get_resource::<GpuBatchQueues>()
+                .map(|q| q.buffers.iter().map(|(id, b)| (id.clone(), b.items.len())).collect())
+                .unwrap_or_default(),
+        });
+
+        Self {
+            tick,
+            workers,
+            yards,
+            queued,
+            kpis: KpiSnapshot::capture(world),
+            sla_hit_rate: world.get_resource::<SlaTracker>().map_or(1.0, |s| s.get_recent_hit_rate()),
+            gpu,
+        }
+    }
+}
+
+/// The served colony: a scenario (or the default colony without one) with this server's content and mode
+pub fn build_sim_app(scenario: Option<&Scenario>, seed: Option<u64>, mode: GameMode, content: &ContentPack, tick_scale: TickScale) -> Result<App, String> {
+    let mut app = App::new();
+    app.init_resource::<Time>()
+        .insert_resource(content.clone())
+        .insert_resource(mode)
+        .add_plugins(ColonyPlugin::with_config(scenario.map(|s| s.plugin_config()).unwrap_or_default()))
+        .insert_resource(colony_from_scenario(scenario, seed))
+        .insert_resource(SimClock { tick_scale, now: chrono::Utc::now() });
+    if let Some(scenario) = scenario {
+        app.insert_resource(ClassReservations::from_reservations(&scenario.class_reservations).map_err(|e| e.to_string())?)
+            .insert_resource(OpLicenses::from_limits(&scenario.op_limits).map_err(|e| e.to_string())?);
+    }
+    // The first update runs the startup systems, so workers and yards exist before anything is served
+    app.update();
+    Ok(app)
+}
+
+/// Load external scheduler libraries once; they outlive sessions, so each built app gets the same ones
+#[cfg(feature = "ffi_scheduler")]
+pub fn load_scheduler_libs(paths: &[std::path::PathBuf], timeout_ms: u64) -> Result<Vec<Arc<dyn colony_core::Scheduler>>, String> {
+    let limits = colony_core::FfiSchedulerLimits { timeout_ms, ..Default::default() };
+    paths.iter()
+        .map(|path| {
+            let scheduler = colony_core::FfiScheduler::load(path, limits.clone()).map_err(|e| e.to_string())?;
+            println!("Loaded scheduler {} from {}", colony_core::Scheduler::name(&scheduler), path.display());
+            Ok(Arc::new(scheduler) as Arc<dyn colony_core::Scheduler>)
+        })
+        .collect()
+}
+
+#[cfg(feature = "ffi_scheduler")]
+pub fn with_schedulers(mut app: App, schedulers: &[Arc<dyn colony_core::Scheduler>]) -> Result<App, String> {
+    let mut registry = app.world_mut().resource_mut::<colony_core::SchedulerRegistry>();
+    for scheduler in schedulers {
+        registry.register(scheduler.clone()).map_err(|e| e.to_string())?;
+    }
+    Ok(app)
+}
+
+/// The library schedulers an app was given, to hand on to the app that replaces it
+#[cfg(feature = "ffi_scheduler")]
+pub fn ffi_schedulers(app: &App) -> Vec<Arc<dyn colony_core::Scheduler>> {
+    let prefix = format!("{}/", colony_core::FFI_SCHEDULER_PREFIX);
+    app.world().get_resource::<colony_core::SchedulerRegistry>()
+        .map(|registry| registry.names().iter().filter(|name| name.starts_with(&prefix)).filter_map(|name| registry.get(name)).collect())
+        .unwrap_or_default()
+}
+
+/// Hand what the server set up at startup on to the app that replaces `old`: the IO job channel,
+/// so running simulators keep feeding the queue, and the save protection from --game-config
+pub fn with_server_resources(mut app: App, old: &App) -> App {
+    if let Some(runtime) = old.world().get_resource::<colony_core::IoRuntime>() {
+        app.insert_resource(runtime.clone());
+    }
+    if let Some(protection) = old.world().get_resource::<SaveProtection>() {
+        app.insert_resource(protection.clone());
+    }
+    app
+}
+
+type Build = Box<dyn FnOnce(&App) -> Result<App, String> + Send>;
+/// Sends a command's result once the served copies show what it did
+type Reply = Box<dyn FnOnce() + Send>;
+
+enum SimCommand {
+    Run(Box<dyn FnOnce(&mut App) -> Reply + Send>),
+    Restart(Build, oneshot::Sender<Result<(), String>>),
+}
+
+/// Requests to the sim thread; handlers use it for anything the served copies don't cover
+#[derive(Clone)]
+pub struct SimHandle {
+    commands: mpsc::Sender<SimCommand>,
+}
+
+fn stopped() -> ApiError {
+    ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "The simulation has stopped")
+}
+
+impl SimHandle {
+    /// Run `f` against the app between ticks and wait for its result
+    pub async fn with_app<R: Send + 'static>(&self, f: impl FnOnce(&mut App) -> R + Send + 'static) -> Result<R, ApiError> {
+        let (tx, rx) = oneshot::channel();
+        self.commands
+            .send(SimCommand::Run(Box::new(move |app| {
+                let result = f(app);
+                Box::new(move || {
+                    let _ = tx.send(result);
+                })
+            })))
+            .map_err(|_| stopped())?;
+        rx.await.map_err(|_| stopped())
+    }
+
+    pub async fn with_world<R: Send + 'static>(&self, f: impl FnOnce(&mut World) -> R + Send + 'static) -> Result<R, ApiError> {
+        self.with_app(move |app| f(app.world_mut())).await
+    }
+
+    /// Replace the running colony with the one `build` makes from the current app; the current
+    /// one keeps running if it fails
+    pub async fn restart(&self, build: impl FnOnce(&App) -> Result<App, String> + Send + 'static) -> Result<(), ApiError> {
+        let (tx, rx) = oneshot::channel();
+        self.commands.send(SimCommand::Restart(Box::new(build), tx)).map_err(|_| stopped())?;
+        rx.await.map_err(|_| stopped())?.map_err(ApiError::unprocessable)
+    }
+}
+
+struct SimThread {
+    app: App,
+    mirrors: Vec<Arc<dyn Mirror>>,
+    view: Arc<RwLock<SimView>>,
+    pending: Arc<RwLock<Vec<TimedCommand>>>,
+    notifications: broadcast::Sender<Notification>,
+}
+
+impl SimThread {
+    /// Spec commands accepted for a tick that has now come round; repeating ones go back in
+    fn apply_due_commands(&mut self) {
+        let now_tick = self.app.world().resource::<SimClock>().now.timestamp_millis() as u64 / 16;
+        let due: Vec<_> = {
+            let mut pending = self.pending.blocking_write();
+            let mut due = Vec::new();
+            for cmd in pending.iter_mut().filter(|c| c.at_tick <= now_tick) {
+                due.push(cmd.command.clone());
+                if let Some(every) = cmd.repeat_every {
+                    cmd.at_tick += every.max(1);
+                }
+            }
+            pending.retain(|c| c.at_tick > now_tick || c.repeat_every.is_some());
+            due
+        };
+        for command in &due {
+            apply_command(&mut self.app, command);
+        }
+    }
+
+    fn tick(&mut self) {
+        let (running, fast_forward) = self.app.world().get_resource::<SessionCtl>()
+            .map_or((true, false), |s| (s.running, s.fast_forward));
+        if !running {
+            return;
+        }
+        for _ in 0..if fast_forward { FAST_FORWARD_TICKS } else { 1 } {
+            self.apply_due_commands();
+            self.app.update();
+        }
+        // Sending fails only while nobody is subscribed, and then there's no one to tell
+        if let Some(mut feed) = self.app.world_mut().get_resource_mut::<NotificationFeed>() {
+            for notification in feed.drain() {
+                let _ = self.notifications.send(notification);
+            }
+        }
+        self.archive_postmortem();
+    }
+
+    /// Write out the black box's bundle once the colony is lost
+    fn archive_postmortem(&mut self) {
+        let world = self.app.world_mut();
+        let Some(postmortem) = world.get_resource_mut::<BlackBox>().and_then(|mut black_box| black_box.take_unarchived()) else {
+            return;
+        };
+        let protection = world.get_resource::<SaveProtection>().cloned().unwrap_or_default();
+        if let Err(e) = postmortem.save_to(std::path::Path::new(DEFAULT_POSTMORTEM_DIR), &protection) {
+            eprintln!("Failed to archive post-mortem: {}", e);
+        }
+    }
+
+    fn mirror(&mut self) {
+        let world = self.app.world_mut();
+        for mirror in &self.mirrors {
+            mirror.pull(world);
+        }
+        *self.view.blocking_write() = SimView::capture(world);
+    }
+
+    fn run(mut self, commands: mpsc::Receiver<SimCommand>) {
+        let mut next_tick = Instant::now();
+        let mut last_mirror = Instant::now();
+        loop {
+            let mut received = Vec::new();
+            match commands.recv_timeout(next_tick.saturating_duration_since(Instant::now())) {
+                Ok(command) => received.push(command),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+            received.extend(commands.try_iter());
+
+            let mut replies: Vec<Reply> = Vec::new();
+            for command in received {
+                // A fresh change tick, so a pull sees edits made while the session is paused
+                self.app.world_mut().increment_change_tick();
+                replies.push(match command {
+                    SimCommand::Run(f) => f(&mut self.app),
+                    SimCommand::Restart(build, reply) => {
+                        let result = build(&self.app).map(|app| {
+                            self.app = app;
+                            self.mirrors.iter().for_each(|m| m.forget());
+                        });
+                        Box::new(move || {
+                            let _ = reply.send(result);
+                        })
+                    }
+                });
+            }
+            // Mirror before answering, so a handler reading back what it just did sees it
+            if !replies.is_empty() {
+                self.mirror();
+                last_mirror = Instant::now();
+                replies.into_iter().for_each(|reply| reply());
+            }
+
+            if Instant::now() >= next_tick {
+                self.tick();
+                next_tick += TICK_INTERVAL;
+                // Fall behind rather than run a burst of ticks to catch up
+                next_tick = next_tick.max(Instant::now());
+            }
+            if last_mirror.elapsed() >= MIRROR_INTERVAL {
+                self.mirror();
+                last_mirror = Instant::now();
+            }
+        }
+    }
+}
+
+/// Start the sim on its own thread. Returns once the first app is built and mirrored, so the
+/// server never serves the placeholder state it started with.
+pub fn spawn_sim(
+    build: impl FnOnce() -> Result<App, String> + Send + 'static,
+    mirrors: Vec<Arc<dyn Mirror>>,
+    view: Arc<RwLock<SimView>>,
+    pending: Arc<RwLock<Vec<TimedCommand>>>,
+    notifications: broadcast::Sender<Notification>,
+) -> Result<SimHandle, String> {
+    let (tx, rx) = mpsc::channel();
+    let (ready_tx, ready_rx) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("colony-sim".to_string())
+        .spawn(move || {
+            let app = match build() {
+                Ok(app) => app,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let mut sim = SimThread { app, mirrors, view, pending, notifications };
+            sim.mirror();
+            let _ = ready_tx.send(Ok(()));
+            sim.run(rx);
+        })
+        .map_err(|e| e.to_string())?;
+    ready_rx.recv().map_err(|_| "The sim thread exited while starting".to_string())??;
+    Ok(SimHandle { commands: tx })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use colony_core::{ActiveScheduler, SchedPolicy};
+
+    fn sim(mirrors: Vec<Arc<dyn Mirror>>) -> (SimHandle, Arc<RwLock<SimView>>) {
+        let view = Arc::new(RwLock::new(SimView::default()));
+        let handle = spawn_sim(
+            || build_sim_app(None, Some(7), GameMode::Standard, ContentPack::vanilla(), TickScale::Seconds(1)),
+            mirrors,
+            view.clone(),
+            Arc::new(RwLock::new(Vec::new())),
+            broadcast::channel(16).0,
+        )
+        .unwrap();
+        (handle, view)
+    }
+
+    #[tokio::test]
+    async fn test_served_copies_follow_the_world() {
+        let colony = Arc::new(Synced::new(colony_from_scenario(None, Some(1))));
+        let scheduler = Arc::new(Synced::new(ActiveScheduler::default()));
+        let (handle, view) = sim(vec![colony.clone(), scheduler.clone()]);
+        // The first mirror replaced the placeholder with the world's colony
+        assert_eq!(colony.read().await.seed, 7);
+        assert!(!view.read().await.workers.is_empty());
+
+        // Replies wait for the mirror, so the edit is served by the time this returns
+        handle.with_world(|world| world.resource_mut::<ActiveScheduler>().policy = SchedPolicy::Edf).await.unwrap();
+        assert_eq!(scheduler.read().await.policy, SchedPolicy::Edf);
+        handle.with_world(|world| world.resource_mut::<Colony>().power_cap_kw = 123.0).await.unwrap();
+        assert_eq!(colony.read().await.power_cap_kw, 123.0);
+    }
+
+    #[tokio::test]
+    async fn test_edits_keep_what_the_sim_changed_since_the_last_mirror() {
+        let clock = Arc::new(Synced::new(SimClock { tick_scale: TickScale::Seconds(1), now: chrono::Utc::now() }));
+        let (handle, _view) = sim(vec![clock.clone()]);
+        let served = clock.read().await.now;
+        tokio::time::sleep(TICK_INTERVAL * 5).await;
+        // Changing the scale leaves the time the sim has reached alone, however stale the served copy
+        let now = handle.with_world(|world| {
+            world.resource_mut::<SimClock>().tick_scale = TickScale::Days(1);
+            world.resource::<SimClock>().now
+        }).await.unwrap();
+        assert!(now > served);
+        assert_eq!(clock.read().await.now, now);
+        assert!(matches!(clock.read().await.tick_scale, TickScale::Days(1)));
+    }
+
+    #[tokio::test]
+    async fn test_paused_session_holds_the_clock() {
+        let session = Arc::new(Synced::new(SessionCtl::new()));
+        let clock = Arc::new(Synced::new(SimClock { tick_scale: TickScale::Seconds(1), now: chrono::Utc::now() }));
+        let (handle, _view) = sim(vec![session.clone(), clock.clone()]);
+        handle.with_world(|world| world.resource_mut::<SessionCtl>().pause()).await.unwrap();
+        assert!(!session.read().await.running);
+        let now = || handle.with_world(|world| world.resource::<SimClock>().now);
+        let paused_at = now().await.unwrap();
+        tokio::time::sleep(TICK_INTERVAL * 5).await;
+        assert_eq!(now().await.unwrap(), paused_at);
+
+        handle.with_world(|world| world.resource_mut::<SessionCtl>().resume()).await.unwrap();
+        tokio::time::sleep(TICK_INTERVAL * 5).await;
+        assert!(now().await.unwrap() > paused_at);
+    }
+}
@@ -0,0 +1,71 @@
+//! Seed the fuzz corpora from the simulators:
+//! `cargo run -p colony-io --example fuzz_corpus [-- <corpus dir>]`
+//!
+//! Entries are raw wire bytes behind the mode byte the fuzz targets expect (even = wire).
+
+use colony_io::{
+    parsers, run_can_sim, run_modbus_sim, CanSimConfig, HttpSimulator, IoSimulatorConfig, IoSource, ModbusPdu,
+    ModbusSimConfig,
+};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+const SEED: u64 = 42;
+const ENTRIES: usize = 24;
+const WIRE: u8 = 0;
+const WIRE_RESPONSE: u8 = 2; // modbus_pdu: bit 1 marks a response
+
+fn write_entry(dir: &Path, i: usize, mode: u8, bytes: &[u8]) -> std::io::Result<()> {
+    let mut entry = vec![mode];
+    entry.extend_from_slice(bytes);
+    std::fs::write(dir.join(format!("sim-{:03}", i)), entry)
+}
+
+#[tokio::main(flavor = "current_thread", start_paused = true)]
+async fn main() -> std::io::Result<()> {
+    let root = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus"));
+
+    // A fifth of the HTTP traffic is mangled, so the corpus starts with faults as well as good messages
+    let dir = root.join("http_parser");
+    std::fs::create_dir_all(&dir)?;
+    let (tx, mut rx) = mpsc::channel(ENTRIES);
+    let http = HttpSimulator::new(IoSimulatorConfig { loss: 0.0, malformed: 0.2, ..Default::default() });
+    let sim = tokio::spawn(Box::new(http).run(tx, SEED));
+    let wire = parsers::HttpParser;
+    for i in 0..ENTRIES {
+        let Some(packet) = rx.recv().await else { break };
+        if let Some(bytes) = wire.encode(&packet) {
+            write_entry(&dir, i, WIRE, &bytes)?;
+        }
+    }
+    sim.abort();
+
+    let dir = root.join("can_frame");
+    std::fs::create_dir_all(&dir)?;
+    let (tx, mut rx) = mpsc::channel(ENTRIES);
+    let sim = tokio::spawn(run_can_sim(tx, CanSimConfig { error_rate: 0.1, id_space: (0x100, 0x1FFF), ..Default::default() }, SEED));
+    for i in 0..ENTRIES {
+        let Some(packet) = rx.recv().await else { break };
+        write_entry(&dir, i, WIRE, &packet.encode())?;
+    }
+    sim.abort();
+
+    let dir = root.join("modbus_pdu");
+    std::fs::create_dir_all(&dir)?;
+    let (tx, mut rx) = mpsc::channel(ENTRIES);
+    let sim = tokio::spawn(run_modbus_sim(tx, ModbusSimConfig { loss: 0.0, payload_bytes: 32, ..Default::default() }, SEED));
+    for i in 0..ENTRIES {
+        let Some(pdu) = rx.recv().await else { break };
+        let mode = if matches!(pdu, ModbusPdu::Response { .. }) { WIRE_RESPONSE } else { WIRE };
+        if let Ok(bytes) = pdu.encode() {
+            write_entry(&dir, i, mode, &bytes)?;
+        }
+    }
+    sim.abort();
+
+    println!("Seeded {} entries per target under {}", ENTRIES, root.display());
+    Ok(())
+}
@@ -0,0 +1,35 @@
+#![no_main]
+
+use arbitrary::{Arbitrary, Unstructured};
+use colony_io::CanPacket;
+use libfuzzer_sys::fuzz_target;
+
+/// A packet encoded before decoding, so every id, flag and dlc combination is reached
+#[derive(Debug, Arbitrary)]
+struct Packet {
+    id: u32,
+    dlc: u8,
+    bytes: [u8; 8],
+    error: bool,
+}
+
+// First byte even: the rest is a raw frame (the seed corpus). Odd: a structured Packet.
+fuzz_target!(|data: &[u8]| {
+    let Some((&mode, rest)) = data.split_first() else {
+        return;
+    };
+    let frame = if mode & 1 == 0 {
+        rest.to_vec()
+    } else {
+        match Packet::arbitrary_take_rest(Unstructured::new(rest)) {
+            Ok(Packet { error: true, .. }) => CanPacket::Error.encode().to_vec(),
+            Ok(Packet { id, dlc, bytes, .. }) => CanPacket::Data { id, dlc, bytes }.encode().to_vec(),
+            Err(_) => return,
+        }
+    };
+
+    // Anything accepted re-encodes to a frame that decodes to the same packet
+    if let Ok(packet) = CanPacket::decode(&frame) {
+        assert_eq!(CanPacket::decode(&packet.encode()).unwrap(), packet);
+    }
+});
@@ -0,0 +1,56 @@
+#![no_main]
+
+use arbitrary::{Arbitrary, Unstructured};
+use bytes::Bytes;
+use colony_io::{parsers, HttpParser, IoPacket, ParsedOp};
+use libfuzzer_sys::fuzz_target;
+
+/// A message built field by field, so odd header names, Content-Length mismatches and
+/// status codes are reached without the fuzzer having to spell out HTTP
+#[derive(Debug, Arbitrary)]
+struct Message {
+    is_req: bool,
+    path: String,
+    code: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+// First byte even: the rest is raw bytes off the wire (the seed corpus). Odd: a structured Message.
+fuzz_target!(|data: &[u8]| {
+    let Some((&mode, rest)) = data.split_first() else {
+        return;
+    };
+    let wire = parsers::HttpParser;
+    let packet = if mode & 1 == 0 {
+        match wire.parse(rest) {
+            Ok(packet) => packet,
+            Err(_) => return,
+        }
+    } else {
+        let Ok(msg) = Message::arbitrary_take_rest(Unstructured::new(rest)) else {
+            return;
+        };
+        let body = Bytes::from(msg.body);
+        let packet = if msg.is_req {
+            IoPacket::HttpReq { ts_ns: 0, path: msg.path, headers: msg.headers, body, flow: None }
+        } else {
+            IoPacket::HttpResp { ts_ns: 0, code: msg.code, headers: msg.headers, body, flow: None }
+        };
+        if let Some(bytes) = wire.encode(&packet) {
+            let _ = wire.parse(&bytes);
+        }
+        packet
+    };
+
+    // Every HTTP packet comes out as a message or a fault carrying its size, never a panic
+    let body_len = match &packet {
+        IoPacket::HttpReq { body, .. } | IoPacket::HttpResp { body, .. } => body.len(),
+        IoPacket::Udp { .. } | IoPacket::TcpSegment { .. } => return,
+    };
+    match HttpParser::new().parse_packet(packet) {
+        Some(ParsedOp::HttpMessage { bytes, .. }) => assert_eq!(bytes.len(), body_len),
+        Some(ParsedOp::ParseFault(fault)) => assert_eq!(fault.len, body_len),
+        other => panic!("HTTP packet parsed to {:?}", other),
+    }
+});
@@ -0,0 +1,40 @@
+#![no_main]
+
+use arbitrary::{Arbitrary, Unstructured};
+use bytes::Bytes;
+use colony_io::ModbusPdu;
+use libfuzzer_sys::fuzz_target;
+
+/// A PDU encoded before decoding, so register and byte counts land near the limits
+#[derive(Debug, Arbitrary)]
+enum Pdu {
+    Request { fcode: u8, addr: u16, len: u16 },
+    Response { fcode: u8, bytes: Vec<u8> },
+}
+
+// First byte even: the rest is a raw PDU (the seed corpus), a response if bit 1 is set.
+// Odd: a structured Pdu.
+fuzz_target!(|data: &[u8]| {
+    let Some((&mode, rest)) = data.split_first() else {
+        return;
+    };
+    let (pdu, is_response) = if mode & 1 == 0 {
+        (rest.to_vec(), mode & 2 != 0)
+    } else {
+        let encoded = match Pdu::arbitrary_take_rest(Unstructured::new(rest)) {
+            Ok(Pdu::Request { fcode, addr, len }) => (ModbusPdu::Request { fcode, addr, len }.encode(), false),
+            Ok(Pdu::Response { fcode, bytes }) => (ModbusPdu::Response { fcode, bytes: Bytes::from(bytes) }.encode(), true),
+            Err(_) => return,
+        };
+        match encoded {
+            (Ok(pdu), is_response) => (pdu, is_response),
+            (Err(_), _) => return,
+        }
+    };
+
+    // Anything accepted re-encodes to a PDU that decodes to the same thing
+    if let Ok(decoded) = ModbusPdu::decode(&pdu, is_response) {
+        let again = decoded.encode().expect("decoded PDU must encode");
+        assert_eq!(ModbusPdu::decode(&again, is_response).unwrap(), decoded);
+    }
+});
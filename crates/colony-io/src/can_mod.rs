@@ -4,6 +4,7 @@ use bytes::Bytes;
 use rand::{Rng, SeedableRng};
 use rand_pcg::Pcg64;
 use tokio::time::{Duration, Instant};
+use crate::ParserError;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CanSimConfig {
@@ -47,18 +48,164 @@ impl Default for ModbusSimConfig {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum CanPacket { 
     Data { id: u32, dlc: u8, bytes: [u8; 8] }, 
     Error 
 }
 
-#[derive(Debug, Clone)]
+/// SocketCAN `struct can_frame`: id word (LE, with flag bits), dlc, 3 pad bytes, 8 data bytes
+pub const CAN_FRAME_LEN: usize = 16;
+const CAN_EFF_FLAG: u32 = 0x8000_0000; // 29-bit extended id
+const CAN_RTR_FLAG: u32 = 0x4000_0000;
+const CAN_ERR_FLAG: u32 = 0x2000_0000;
+const CAN_SFF_MASK: u32 = 0x0000_07FF;
+const CAN_EFF_MASK: u32 = 0x1FFF_FFFF;
+
+impl CanPacket {
+    /// Decode one wire frame. Data past `dlc` is zeroed so equal frames compare equal.
+    pub fn decode(frame: &[u8]) -> Result<Self, ParserError> {
+        if frame.len() < CAN_FRAME_LEN {
+            return Err(ParserError::InsufficientData);
+        }
+        if frame.len() > CAN_FRAME_LEN {
+            return Err(ParserError::InvalidFormat);
+        }
+        let word = u32::from_le_bytes([frame[0], frame[1], frame[2], frame[3]]);
+        if word & CAN_ERR_FLAG != 0 {
+            return Ok(CanPacket::Error);
+        }
+        if word & CAN_RTR_FLAG != 0 {
+            return Err(ParserError::InvalidFormat); // the sim never sends remote frames
+        }
+        let id = if word & CAN_EFF_FLAG != 0 {
+            word & CAN_EFF_MASK
+        } else if word & !CAN_SFF_MASK == 0 {
+            word
+        } else {
+            return Err(ParserError::InvalidFormat); // stray bits above an 11-bit id
+        };
+        let dlc = frame[4];
+        if dlc > 8 {
+            return Err(ParserError::InvalidFormat);
+        }
+        let mut bytes = [0u8; 8];
+        bytes[..dlc as usize].copy_from_slice(&frame[8..8 + dlc as usize]);
+        Ok(CanPacket::Data { id, dlc, bytes })
+    }
+
+    pub fn encode(&self) -> [u8; CAN_FRAME_LEN] {
+        let mut frame = [0u8; CAN_FRAME_LEN];
+        match self {
+            CanPacket::Data { id, dlc, bytes } => {
+                let word = if *id > CAN_SFF_MASK { (id & CAN_EFF_MASK) | CAN_EFF_FLAG } else { *id };
+                let dlc = (*dlc).min(8);
+                frame[..4].copy_from_slice(&word.to_le_bytes());
+                frame[4] = dlc;
+                frame[8..8 + dlc as usize].copy_from_slice(&bytes[..dlc as usize]);
+            }
+            CanPacket::Error => frame[..4].copy_from_slice(&CAN_ERR_FLAG.to_le_bytes()),
+        }
+        frame
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ModbusPdu { 
     Request { fcode: u8, addr: u16, len: u16 }, 
     Response { fcode: u8, bytes: Bytes } 
 }
 
+/// Largest Modbus PDU: function code plus 252 bytes of data
+pub const MAX_MODBUS_PDU: usize = 253;
+const MODBUS_EXCEPTION: u8 = 0x80;
+
+impl ModbusPdu {
+    /// Decode a request or response PDU (no MBAP header). Register counts follow the spec:
+    /// 1..=125 for reads, 1..=123 for multiple writes; a single write carries one value.
+    pub fn decode(pdu: &[u8], is_response: bool) -> Result<Self, ParserError> {
+        let (&fcode, rest) = pdu.split_first().ok_or(ParserError::InsufficientData)?;
+        if pdu.len() > MAX_MODBUS_PDU {
+            return Err(ParserError::InvalidFormat);
+        }
+        if is_response {
+            if fcode & MODBUS_EXCEPTION != 0 {
+                return match rest {
+                    [code] => Ok(ModbusPdu::Response { fcode, bytes: Bytes::copy_from_slice(&[*code]) }),
+                    [] => Err(ParserError::InsufficientData),
+                    _ => Err(ParserError::InvalidFormat),
+                };
+            }
+            let (&count, data) = rest.split_first().ok_or(ParserError::InsufficientData)?;
+            if data.len() < count as usize {
+                return Err(ParserError::InsufficientData);
+            }
+            if data.len() > count as usize {
+                return Err(ParserError::InvalidFormat);
+            }
+            return Ok(ModbusPdu::Response { fcode, bytes: Bytes::copy_from_slice(data) });
+        }
+
+        if rest.len() < 4 {
+            return Err(ParserError::InsufficientData);
+        }
+        let addr = u16::from_be_bytes([rest[0], rest[1]]);
+        let word = u16::from_be_bytes([rest[2], rest[3]]);
+        let len = match fcode {
+            0x03 | 0x04 if (1..=125).contains(&word) && rest.len() == 4 => word,
+            0x06 if rest.len() == 4 => 1, // the word is the value written, not a count
+            0x10 if (1..=123).contains(&word) => {
+                let (&count, values) = rest[4..].split_first().ok_or(ParserError::InsufficientData)?;
+                if count as usize != word as usize * 2 {
+                    return Err(ParserError::InvalidFormat);
+                }
+                if values.len() < count as usize {
+                    return Err(ParserError::InsufficientData);
+                }
+                if values.len() > count as usize {
+                    return Err(ParserError::InvalidFormat);
+                }
+                word
+            }
+            _ => return Err(ParserError::InvalidFormat),
+        };
+        Ok(ModbusPdu::Request { fcode, addr, len })
+    }
+
+    /// Wire form of the PDU; a single write sends zero as its value, multiple writes zero registers.
+    /// Multiple writes outside 1..=123 registers and oversized responses are rejected.
+    pub fn encode(&self) -> Result<Vec<u8>, ParserError> {
+        match self {
+            ModbusPdu::Request { fcode, addr, len } => {
+                let mut pdu = vec![*fcode];
+                pdu.extend_from_slice(&addr.to_be_bytes());
+                match fcode {
+                    0x06 => pdu.extend_from_slice(&0u16.to_be_bytes()),
+                    0x10 if (1..=123).contains(len) => {
+                        pdu.extend_from_slice(&len.to_be_bytes());
+                        pdu.push((*len * 2) as u8);
+                        pdu.resize(pdu.len() + *len as usize * 2, 0);
+                    }
+                    0x10 => return Err(ParserError::InvalidFormat),
+                    _ => pdu.extend_from_slice(&len.to_be_bytes()),
+                }
+                Ok(pdu)
+            }
+            ModbusPdu::Response { fcode, bytes } if fcode & MODBUS_EXCEPTION != 0 => {
+                Ok(vec![*fcode, bytes.first().copied().unwrap_or(0)])
+            }
+            ModbusPdu::Response { fcode, bytes } => {
+                if bytes.len() + 2 > MAX_MODBUS_PDU {
+                    return Err(ParserError::InvalidFormat);
+                }
+                let mut pdu = vec![*fcode, bytes.len() as u8];
+                pdu.extend_from_slice(bytes);
+                Ok(pdu)
+            }
+        }
+    }
+}
+
 pub async fn run_can_sim(tx: mpsc::Sender<CanPacket>, cfg: CanSimConfig, seed: u64) {
     let mut rng = Pcg64::seed_from_u64(seed);
     let mut last_packet = Instant::now();
@@ -220,6 +367,42 @@ mod tests {
         assert!(packet_count >= 8 && packet_count <= 12, "Expected ~10 packets, got {}", packet_count);
     }
 
+    #[test]
+    fn test_can_frame_round_trip() {
+        let standard = CanPacket::Data { id: 0x123, dlc: 3, bytes: [1, 2, 3, 0, 0, 0, 0, 0] };
+        assert_eq!(CanPacket::decode(&standard.encode()).unwrap(), standard);
+        let extended = CanPacket::Data { id: 0x1234_5678, dlc: 8, bytes: [9; 8] };
+        assert_eq!(CanPacket::decode(&extended.encode()).unwrap(), extended);
+        assert_eq!(CanPacket::decode(&CanPacket::Error.encode()).unwrap(), CanPacket::Error);
+
+        let mut bad_dlc = standard.encode();
+        bad_dlc[4] = 9;
+        assert!(matches!(CanPacket::decode(&bad_dlc), Err(ParserError::InvalidFormat)));
+        let mut stray = standard.encode();
+        stray[1] = 0x08; // id bit 11 set without the extended flag
+        assert!(matches!(CanPacket::decode(&stray), Err(ParserError::InvalidFormat)));
+        assert!(matches!(CanPacket::decode(&standard.encode()[..10]), Err(ParserError::InsufficientData)));
+    }
+
+    #[test]
+    fn test_modbus_pdu_round_trip_and_limits() {
+        let read = ModbusPdu::Request { fcode: 0x03, addr: 40001, len: 125 };
+        assert_eq!(ModbusPdu::decode(&read.encode().unwrap(), false).unwrap(), read);
+        let write = ModbusPdu::Request { fcode: 0x10, addr: 7, len: 2 };
+        assert_eq!(ModbusPdu::decode(&write.encode().unwrap(), false).unwrap(), write);
+        let resp = ModbusPdu::Response { fcode: 0x04, bytes: Bytes::from_static(&[0, 1, 0, 2]) };
+        assert_eq!(ModbusPdu::decode(&resp.encode().unwrap(), true).unwrap(), resp);
+        let exception = ModbusPdu::Response { fcode: 0x83, bytes: Bytes::from_static(&[0x02]) };
+        assert_eq!(ModbusPdu::decode(&exception.encode().unwrap(), true).unwrap(), exception);
+
+        assert!(ModbusPdu::decode(&[0x03, 0, 0, 0, 126], false).is_err());
+        assert!(ModbusPdu::decode(&[0x10, 0, 0, 0, 2, 4, 0, 0], false).is_err());
+        assert!(matches!(ModbusPdu::decode(&[0x03, 4, 0], true), Err(ParserError::InsufficientData)));
+        assert!(matches!(ModbusPdu::decode(&[], true), Err(ParserError::InsufficientData)));
+        assert!(ModbusPdu::Request { fcode: 0x10, addr: 0, len: 40_000 }.encode().is_err());
+        assert!(ModbusPdu::Response { fcode: 0x03, bytes: Bytes::from(vec![0; 256]) }.encode().is_err());
+    }
+
     #[tokio::test]
     async fn test_modbus_simulator() {
         let (tx, mut rx) = mpsc::channel(100);
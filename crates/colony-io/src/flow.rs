@@ -0,0 +1,56 @@
+use crate::IoSourceKind;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Identifies one packet of one flow from the moment an IO source emits it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FlowTag {
+    pub flow: u32,       // stream the packet belongs to, see flow_id()
+    pub source: IoSourceKind,
+    pub seq: u64,        // per-flow sequence number; gaps are packets lost before parsing
+    pub emitted_ns: u64,
+}
+
+/// Stable id for a stream, e.g. a UDP sender address or an HTTP path
+pub fn flow_id(source: IoSourceKind, key: &str) -> u32 {
+    // FNV-1a, so ids match across runs and processes
+    let mut hash: u32 = 0x811c_9dc5;
+    let prefix: &[u8] = match source {
+        IoSourceKind::Udp => b"udp:",
+        IoSourceKind::Http => b"http:",
+    };
+    for byte in prefix.iter().chain(key.as_bytes()) {
+        hash ^= *byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+/// Hands out per-flow sequence numbers on the source side
+#[derive(Debug, Clone)]
+pub struct FlowStamper {
+    source: IoSourceKind,
+    next_seq: HashMap<u32, u64>,
+}
+
+impl FlowStamper {
+    pub fn new(source: IoSourceKind) -> Self {
+        Self { source, next_seq: HashMap::new() }
+    }
+
+    pub fn flow_for(&self, key: &str) -> u32 {
+        flow_id(self.source, key)
+    }
+
+    pub fn stamp(&mut self, flow: u32, emitted_ns: u64) -> FlowTag {
+        let seq = self.next_seq.entry(flow).or_insert(0);
+        let tag = FlowTag { flow, source: self.source, seq: *seq, emitted_ns };
+        *seq += 1;
+        tag
+    }
+
+    /// Burn a sequence number for a packet lost at the source, leaving a gap downstream
+    pub fn skip(&mut self, flow: u32) {
+        *self.next_seq.entry(flow).or_insert(0) += 1;
+    }
+}
@@ -0,0 +1,408 @@
+use serde::{Serialize, Deserialize};
+use tokio::sync::mpsc;
+use bytes::Bytes;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use std::collections::{BTreeMap, VecDeque};
+use tokio::time::Duration;
+use crate::ParserError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcSimConfig {
+    pub rate_hz: f32,          // frames per second across all open calls
+    pub jitter_ms: u16,
+    pub methods: Vec<String>,  // `/package.Service/Method` paths calls pick from
+    pub streaming: f32,        // 0..1, share of calls that stream several request messages
+    pub stream_messages: u32,  // request messages per streaming call
+    pub concurrent_streams: usize, // calls open at once on the connection; their frames interleave
+    pub payload_bytes: usize,  // per request message
+    pub authority: String,
+}
+
+impl Default for GrpcSimConfig {
+    fn default() -> Self {
+        Self {
+            rate_hz: 100.0,
+            jitter_ms: 2,
+            methods: vec![
+                "/colony.telemetry.v1.Telemetry/Report".to_string(),
+                "/colony.telemetry.v1.Telemetry/StreamSamples".to_string(),
+            ],
+            streaming: 0.3,
+            stream_messages: 8,
+            concurrent_streams: 4,
+            payload_bytes: 256,
+            authority: "telemetry.colony.local".to_string(),
+        }
+    }
+}
+
+impl GrpcSimConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.rate_hz > 0.0 && self.rate_hz.is_finite()) {
+            return Err(format!("rate_hz must be above 0, got {}", self.rate_hz));
+        }
+        if self.methods.is_empty() {
+            return Err("At least one method is needed".to_string());
+        }
+        if let Some(method) = self.methods.iter().find(|m| !valid_method(m)) {
+            return Err(format!("'{}' is not a /package.Service/Method path", method));
+        }
+        if !(0.0..=1.0).contains(&self.streaming) {
+            return Err("streaming must be between 0 and 1".to_string());
+        }
+        if self.stream_messages == 0 || self.concurrent_streams == 0 {
+            return Err("stream_messages and concurrent_streams must be at least 1".to_string());
+        }
+        if self.payload_bytes + GRPC_PREFIX_LEN > MAX_FRAME_PAYLOAD {
+            return Err(format!("payload_bytes must fit one {}-byte DATA frame", MAX_FRAME_PAYLOAD));
+        }
+        Ok(())
+    }
+}
+
+/// `/Service/Method`, the service usually package-qualified, nothing else in the path
+fn valid_method(path: &str) -> bool {
+    let mut parts = path.split('/');
+    let valid = |s: Option<&str>| s.is_some_and(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_'));
+    parts.next() == Some("") && valid(parts.next()) && valid(parts.next()) && parts.next().is_none()
+}
+
+/// Default SETTINGS_MAX_FRAME_SIZE; nothing here negotiates a larger one
+pub const MAX_FRAME_PAYLOAD: usize = 16_384;
+const FRAME_HEADER_LEN: usize = 9;
+/// Compressed flag and big-endian length in front of every gRPC message
+const GRPC_PREFIX_LEN: usize = 5;
+
+pub const FRAME_DATA: u8 = 0x0;
+pub const FRAME_HEADERS: u8 = 0x1;
+pub const FLAG_END_STREAM: u8 = 0x1;
+pub const FLAG_END_HEADERS: u8 = 0x4;
+
+/// One HTTP/2 frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Http2Frame {
+    pub kind: u8,
+    pub flags: u8,
+    pub stream_id: u32, // 31 bits; client-initiated streams are odd
+    pub payload: Bytes,
+}
+
+impl Http2Frame {
+    pub fn end_stream(&self) -> bool {
+        self.flags & FLAG_END_STREAM != 0
+    }
+
+    /// Decode one whole frame: 24-bit length, type, flags, reserved bit and stream id, payload
+    pub fn decode(frame: &[u8]) -> Result<Self, ParserError> {
+        if frame.len() < FRAME_HEADER_LEN {
+            return Err(ParserError::InsufficientData);
+        }
+        let len = u32::from_be_bytes([0, frame[0], frame[1], frame[2]]) as usize;
+        if len > MAX_FRAME_PAYLOAD {
+            return Err(ParserError::InvalidFormat);
+        }
+        let body = &frame[FRAME_HEADER_LEN..];
+        if body.len() < len {
+            return Err(ParserError::InsufficientData);
+        }
+        if body.len() > len {
+            return Err(ParserError::InvalidFormat);
+        }
+        let stream_id = u32::from_be_bytes([frame[5], frame[6], frame[7], frame[8]]) & 0x7FFF_FFFF;
+        Ok(Http2Frame { kind: frame[3], flags: frame[4], stream_id, payload: Bytes::copy_from_slice(body) })
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, ParserError> {
+        if self.payload.len() > MAX_FRAME_PAYLOAD || self.stream_id > 0x7FFF_FFFF {
+            return Err(ParserError::InvalidFormat);
+        }
+        let mut wire = Vec::with_capacity(FRAME_HEADER_LEN + self.payload.len());
+        wire.extend_from_slice(&(self.payload.len() as u32).to_be_bytes()[1..]);
+        wire.push(self.kind);
+        wire.push(self.flags);
+        wire.extend_from_slice(&self.stream_id.to_be_bytes());
+        wire.extend_from_slice(&self.payload);
+        Ok(wire)
+    }
+}
+
+/// HPACK integer with an n-bit prefix (RFC 7541 5.1)
+fn hpack_int(out: &mut Vec<u8>, first: u8, prefix_bits: u32, mut value: usize) {
+    let max = (1usize << prefix_bits) - 1;
+    if value < max {
+        out.push(first | value as u8);
+        return;
+    }
+    out.push(first | max as u8);
+    value -= max;
+    while value >= 0x80 {
+        out.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_hpack_int(block: &[u8], at: &mut usize, prefix_bits: u32) -> Result<usize, ParserError> {
+    let max = (1usize << prefix_bits) - 1;
+    let &first = block.get(*at).ok_or(ParserError::InsufficientData)?;
+    *at += 1;
+    let mut value = first as usize & max;
+    if value < max {
+        return Ok(value);
+    }
+    for shift in (0..28).step_by(7) {
+        let &byte = block.get(*at).ok_or(ParserError::InsufficientData)?;
+        *at += 1;
+        value += ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(ParserError::InvalidFormat)
+}
+
+/// A header block of literal fields without indexing and without Huffman coding: valid HPACK
+/// that needs no dynamic table on either side
+pub fn encode_headers(headers: &[(String, String)]) -> Bytes {
+    let mut block = Vec::new();
+    for (name, value) in headers {
+        block.push(0x00);
+        hpack_int(&mut block, 0x00, 7, name.len());
+        block.extend_from_slice(name.as_bytes());
+        hpack_int(&mut block, 0x00, 7, value.len());
+        block.extend_from_slice(value.as_bytes());
+    }
+    Bytes::from(block)
+}
+
+/// Read a block written by `encode_headers`; indexed fields and Huffman strings are rejected
+pub fn decode_headers(block: &[u8]) -> Result<Vec<(String, String)>, ParserError> {
+    let mut headers = Vec::new();
+    let mut at = 0;
+    while at < block.len() {
+        // Literal without indexing (0000) or never indexed (0001), with a literal name
+        if block[at] != 0x00 && block[at] != 0x10 {
+            return Err(ParserError::InvalidFormat);
+        }
+        at += 1;
+        let string = |at: &mut usize| -> Result<String, ParserError> {
+            if block.get(*at).is_some_and(|b| b & 0x80 != 0) {
+                return Err(ParserError::InvalidFormat);
+            }
+            let len = read_hpack_int(block, at, 7)?;
+            let bytes = block.get(*at..*at + len).ok_or(ParserError::InsufficientData)?;
+            *at += len;
+            String::from_utf8(bytes.to_vec()).map_err(|_| ParserError::InvalidFormat)
+        };
+        let name = string(&mut at)?;
+        let value = string(&mut at)?;
+        headers.push((name, value));
+    }
+    Ok(headers)
+}
+
+/// Length-prefixed gRPC messages, uncompressed
+pub fn encode_grpc_message(message: &[u8]) -> Bytes {
+    let mut framed = Vec::with_capacity(GRPC_PREFIX_LEN + message.len());
+    framed.push(0);
+    framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    framed.extend_from_slice(message);
+    Bytes::from(framed)
+}
+
+/// Split a call's DATA bytes into its messages; a message cut short is an error
+pub fn decode_grpc_messages(mut data: &[u8]) -> Result<Vec<Bytes>, ParserError> {
+    let mut messages = Vec::new();
+    while !data.is_empty() {
+        if data.len() < GRPC_PREFIX_LEN {
+            return Err(ParserError::InsufficientData);
+        }
+        if data[0] > 1 {
+            return Err(ParserError::InvalidFormat);
+        }
+        let len = u32::from_be_bytes([data[1], data[2], data[3], data[4]]) as usize;
+        let message = data.get(GRPC_PREFIX_LEN..GRPC_PREFIX_LEN + len).ok_or(ParserError::InsufficientData)?;
+        messages.push(Bytes::copy_from_slice(message));
+        data = &data[GRPC_PREFIX_LEN + len..];
+    }
+    Ok(messages)
+}
+
+/// A whole client call as the server sees it once the client half-closes
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrpcRequest {
+    pub stream_id: u32,
+    pub method: String,
+    pub messages: Vec<Bytes>,
+}
+
+#[derive(Default)]
+struct OpenCall {
+    method: String,
+    data: Vec<u8>,
+}
+
+/// Server side of `run_grpc_sim`: follows the streams of one connection and hands back each
+/// request once its END_STREAM arrives. The parse behind `Op::GrpcParse`.
+#[derive(Default)]
+pub struct GrpcStreams {
+    open: BTreeMap<u32, OpenCall>,
+}
+
+impl GrpcStreams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Streams opened and not yet half-closed by the client
+    pub fn open(&self) -> usize {
+        self.open.len()
+    }
+
+    pub fn push(&mut self, frame: &Http2Frame) -> Result<Option<GrpcRequest>, ParserError> {
+        if frame.stream_id == 0 {
+            // Connection-level frames (SETTINGS, PING, ...) carry no call
+            return Ok(None);
+        }
+        match frame.kind {
+            FRAME_HEADERS => {
+                if frame.flags & FLAG_END_HEADERS == 0 || self.open.contains_key(&frame.stream_id) {
+                    return Err(ParserError::InvalidFormat);
+                }
+                let headers = decode_headers(&frame.payload)?;
+                let header = |name: &str| headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+                if header(":method") != Some("POST") || !header("content-type").is_some_and(|t| t.starts_with("application/grpc")) {
+                    return Err(ParserError::InvalidFormat);
+                }
+                let method = header(":path").filter(|p| valid_method(p)).ok_or(ParserError::InvalidFormat)?.to_string();
+                self.open.insert(frame.stream_id, OpenCall { method, data: Vec::new() });
+            }
+            FRAME_DATA => {
+                let call = self.open.get_mut(&frame.stream_id).ok_or(ParserError::InvalidFormat)?;
+                call.data.extend_from_slice(&frame.payload);
+            }
+            _ => return Ok(None),
+        }
+        if !frame.end_stream() {
+            return Ok(None);
+        }
+        let call = self.open.remove(&frame.stream_id).ok_or(ParserError::InvalidFormat)?;
+        Ok(Some(GrpcRequest { stream_id: frame.stream_id, method: call.method, messages: decode_grpc_messages(&call.data)? }))
+    }
+}
+
+/// Client half of one call: HEADERS, then one DATA frame per message, the last ending the stream
+fn call_frames(rng: &mut Pcg64, cfg: &GrpcSimConfig, stream_id: u32) -> VecDeque<Http2Frame> {
+    let method = cfg.methods[rng.gen_range(0..cfg.methods.len())].clone();
+    let messages = if rng.gen::<f32>() < cfg.streaming { cfg.stream_messages } else { 1 };
+    let headers: Vec<(String, String)> = [
+        (":method", "POST".to_string()),
+        (":scheme", "http".to_string()),
+        (":path", method),
+        (":authority", cfg.authority.clone()),
+        ("content-type", "application/grpc".to_string()),
+        ("te", "trailers".to_string()),
+    ].into_iter().map(|(n, v)| (n.to_string(), v)).collect();
+
+    let mut frames = VecDeque::new();
+    frames.push_back(Http2Frame { kind: FRAME_HEADERS, flags: FLAG_END_HEADERS, stream_id, payload: encode_headers(&headers) });
+    for i in 0..messages {
+        let message: Vec<u8> = (0..cfg.payload_bytes).map(|_| rng.gen()).collect();
+        let flags = if i + 1 == messages { FLAG_END_STREAM } else { 0 };
+        frames.push_back(Http2Frame { kind: FRAME_DATA, flags, stream_id, payload: encode_grpc_message(&message) });
+    }
+    frames
+}
+
+/// The client side of one HTTP/2 connection: up to `concurrent_streams` unary and
+/// client-streaming calls open at once, their frames interleaved, each finished call replaced
+/// by a new one on the next odd stream id until the receiver goes away
+pub async fn run_grpc_sim(tx: mpsc::Sender<Http2Frame>, cfg: GrpcSimConfig, seed: u64) {
+    if cfg.validate().is_err() {
+        return;
+    }
+    let mut rng = Pcg64::seed_from_u64(seed);
+    let mut next_stream: u32 = 1;
+    let mut calls: Vec<VecDeque<Http2Frame>> = Vec::new();
+    let mean_interval_ms = 1000.0 / cfg.rate_hz;
+
+    loop {
+        while calls.len() < cfg.concurrent_streams {
+            if next_stream > 0x7FFF_FFFF {
+                // Stream ids are spent; a real client would open a new connection
+                if calls.is_empty() {
+                    return;
+                }
+                break;
+            }
+            calls.push(call_frames(&mut rng, &cfg, next_stream));
+            next_stream += 2;
+        }
+
+        let interval_ms = -rng.gen::<f32>().ln() * mean_interval_ms;
+        let jitter_ms = rng.gen_range(0..=cfg.jitter_ms) as f32;
+        tokio::time::sleep(Duration::from_millis((interval_ms + jitter_ms) as u64)).await;
+
+        let index = rng.gen_range(0..calls.len());
+        let Some(frame) = calls[index].pop_front() else { continue };
+        if calls[index].is_empty() {
+            calls.swap_remove(index);
+        }
+        if tx.send(frame).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    #[test]
+    fn test_frames_headers_and_messages_round_trip() {
+        let frame = Http2Frame { kind: FRAME_DATA, flags: FLAG_END_STREAM, stream_id: 3, payload: encode_grpc_message(b"sample") };
+        let wire = frame.encode().unwrap();
+        assert_eq!(Http2Frame::decode(&wire).unwrap(), frame);
+        assert!(matches!(Http2Frame::decode(&wire[..wire.len() - 1]), Err(ParserError::InsufficientData)));
+
+        // A 200-byte value needs a multi-byte HPACK length
+        let headers = vec![(":path".to_string(), "/a.B/C".to_string()), ("x-long".to_string(), "v".repeat(200))];
+        assert_eq!(decode_headers(&encode_headers(&headers)).unwrap(), headers);
+        assert!(matches!(decode_headers(&[0x82]), Err(ParserError::InvalidFormat)));
+
+        let mut data = encode_grpc_message(b"one").to_vec();
+        data.extend_from_slice(&encode_grpc_message(b""));
+        assert_eq!(decode_grpc_messages(&data).unwrap(), vec![Bytes::from_static(b"one"), Bytes::new()]);
+        assert!(decode_grpc_messages(&data[..4]).is_err());
+
+        assert!(valid_method("/colony.telemetry.v1.Telemetry/Report"));
+        assert!(!valid_method("/Telemetry") && !valid_method("Telemetry/Report") && !valid_method("/a/b/c"));
+    }
+
+    #[tokio::test]
+    async fn test_grpc_simulator_interleaves_unary_and_streaming_calls() {
+        let (tx, mut rx) = mpsc::channel(100);
+        let config = GrpcSimConfig { rate_hz: 1000.0, jitter_ms: 0, streaming: 0.5, stream_messages: 4, payload_bytes: 32, ..Default::default() };
+        let methods = config.methods.clone();
+        let handle = tokio::spawn(run_grpc_sim(tx, config, 5));
+
+        let mut streams = GrpcStreams::new();
+        let mut requests = Vec::new();
+        let mut peak_open = 0;
+        while requests.len() < 12 {
+            let frame = timeout(Duration::from_millis(1000), rx.recv()).await.unwrap().unwrap();
+            let frame = Http2Frame::decode(&frame.encode().unwrap()).unwrap();
+            requests.extend(streams.push(&frame).unwrap());
+            peak_open = peak_open.max(streams.open());
+        }
+        handle.abort();
+
+        assert!(peak_open > 1, "calls should interleave");
+        assert!(requests.iter().all(|r| r.stream_id % 2 == 1 && methods.contains(&r.method)));
+        assert!(requests.iter().all(|r| r.messages.iter().all(|m| m.len() == 32)));
+        assert!(requests.iter().any(|r| r.messages.len() == 1));
+        assert!(requests.iter().any(|r| r.messages.len() == 4));
+    }
+}
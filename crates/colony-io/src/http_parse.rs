@@ -1,12 +1,43 @@
-use super::{IoPacket, ParsedOp, IoParser};
+use super::{IoPacket, ParsedOp, IoParser, IoSourceKind, ParseCounters, ParseFault, validate_http};
 use bytes::Bytes;
 use tokio::sync::mpsc;
 
-pub struct HttpParser;
+#[derive(Default)]
+pub struct HttpParser {
+    counters: ParseCounters,
+}
 
 impl HttpParser {
     pub fn new() -> Self {
-        Self
+        Self { counters: ParseCounters::new() }
+    }
+
+    /// Count parsed messages and faults into shared counters
+    pub fn with_counters(counters: ParseCounters) -> Self {
+        Self { counters }
+    }
+
+    /// Validate one packet; malformed messages become a ParseFault instead of a message
+    pub fn parse_packet(&self, packet: IoPacket) -> Option<ParsedOp> {
+        let (is_req, len) = match &packet {
+            IoPacket::HttpReq { body, .. } => (true, body.len()),
+            IoPacket::HttpResp { body, .. } => (false, body.len()),
+            IoPacket::Udp { .. } | IoPacket::TcpSegment { .. } => return None, // Ignore UDP and TCP packets in HTTP parser
+        };
+
+        let flow = packet.flow();
+        if let Err(kind) = validate_http(&packet) {
+            let fault = ParseFault { source: IoSourceKind::Http, kind, len, flow };
+            self.counters.record_fault(&fault);
+            return Some(ParsedOp::ParseFault(fault));
+        }
+
+        self.counters.record_parsed(IoSourceKind::Http);
+        let bytes: Bytes = match packet {
+            IoPacket::HttpReq { body, .. } | IoPacket::HttpResp { body, .. } => body,
+            IoPacket::Udp { .. } | IoPacket::TcpSegment { .. } => return None,
+        };
+        Some(ParsedOp::HttpMessage { is_req, bytes, flow })
     }
 }
 
@@ -14,27 +45,9 @@ impl HttpParser {
 impl IoParser for HttpParser {
     async fn start(self: Box<Self>, mut rx: mpsc::Receiver<IoPacket>, tx_ops: mpsc::Sender<ParsedOp>) {
         while let Some(packet) = rx.recv().await {
-            match packet {
-                IoPacket::HttpReq { body, .. } => {
-                    let parsed = ParsedOp::HttpMessage {
-                        is_req: true,
-                        bytes: body,
-                    };
-                    if tx_ops.send(parsed).await.is_err() {
-                        break;
-                    }
-                }
-                IoPacket::HttpResp { body, .. } => {
-                    let parsed = ParsedOp::HttpMessage {
-                        is_req: false,
-                        bytes: body,
-                    };
-                    if tx_ops.send(parsed).await.is_err() {
-                        break;
-                    }
-                }
-                IoPacket::Udp { .. } => {
-                    // Ignore UDP packets in HTTP parser
+            if let Some(parsed) = self.parse_packet(packet) {
+                if tx_ops.send(parsed).await.is_err() {
+                    break;
                 }
             }
         }
@@ -1,4 +1,4 @@
-use super::{IoPacket, IoSimulatorConfig, IoSource};
+use super::{FlowStamper, IoPacket, IoSimulatorConfig, IoSource, IoSourceKind, mangle_http};
 use bytes::Bytes;
 use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
@@ -13,6 +13,13 @@ impl HttpSimulator {
     pub fn new(config: IoSimulatorConfig) -> Self {
         Self { config }
     }
+
+    fn pick_path(&self, rng: &mut StdRng) -> String {
+        self.config.http_paths
+            .get(rng.gen_range(0..self.config.http_paths.len()))
+            .cloned()
+            .unwrap_or_else(|| "/api/default".to_string())
+    }
 }
 
 #[async_trait::async_trait]
@@ -20,6 +27,7 @@ impl IoSource for HttpSimulator {
     async fn run(self: Box<Self>, tx: mpsc::Sender<IoPacket>, seed: u64) {
         let mut rng = StdRng::seed_from_u64(seed);
         let mean_interval_ms = 1000.0 / self.config.rate_hz;
+        let mut stamper = FlowStamper::new(IoSourceKind::Http);
         
         loop {
             // Calculate next request time
@@ -31,16 +39,21 @@ impl IoSource for HttpSimulator {
             
             // Simulate packet loss
             if rng.gen::<f32>() < self.config.loss {
+                if self.config.flow_ids {
+                    // Only draw a path when tagging, so runs without flow ids keep their RNG stream
+                    let path = self.pick_path(&mut rng);
+                    let flow = stamper.flow_for(&path);
+                    stamper.skip(flow);
+                }
                 continue;
             }
             
             let now = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64;
             
             // Select random path
-            let path = self.config.http_paths
-                .get(rng.gen_range(0..self.config.http_paths.len()))
-                .cloned()
-                .unwrap_or_else(|| "/api/default".to_string());
+            let path = self.pick_path(&mut rng);
+            // Requests and their responses share the path's flow
+            let flow = stamper.flow_for(&path);
             
             // Generate HTTP request
             let request_body = format!(
@@ -61,6 +74,12 @@ impl IoSource for HttpSimulator {
                 path: path.clone(),
                 headers: request_headers,
                 body: Bytes::from(request_body),
+                flow: self.config.flow_ids.then(|| stamper.stamp(flow, now)),
+            };
+            let req_packet = if self.config.malformed > 0.0 && rng.gen::<f32>() < self.config.malformed {
+                mangle_http(&mut rng, req_packet)
+            } else {
+                req_packet
             };
             
             if tx.send(req_packet).await.is_err() {
@@ -87,6 +106,12 @@ impl IoSource for HttpSimulator {
                 code: 200,
                 headers: response_headers,
                 body: Bytes::from(response_body),
+                flow: self.config.flow_ids.then(|| stamper.stamp(flow, now + 1000000)),
+            };
+            let resp_packet = if self.config.malformed > 0.0 && rng.gen::<f32>() < self.config.malformed {
+                mangle_http(&mut rng, resp_packet)
+            } else {
+                resp_packet
             };
             
             if tx.send(resp_packet).await.is_err() {
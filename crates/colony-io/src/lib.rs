@@ -4,7 +4,15 @@ pub mod simulators;
 pub mod udp_sim;
 pub mod http_sim;
 pub mod http_parse;
+pub mod udp_frame;
+pub mod parse_fault;
+pub mod flow;
 pub mod can_mod;
+pub mod mqtt_mod;
+pub mod tcp_sim;
+pub mod grpc_sim;
+pub mod pcap_replay;
+pub mod registry;
 
 #[cfg(test)]
 mod tests;
@@ -15,13 +23,21 @@ pub use simulators::*;
 pub use udp_sim::UdpSimulator;
 pub use http_sim::HttpSimulator;
 pub use http_parse::HttpParser;
-pub use can_mod::{CanSimConfig, ModbusSimConfig, CanPacket, ModbusPdu, run_can_sim, run_modbus_sim};
+pub use udp_frame::UdpFramer;
+pub use parse_fault::*;
+pub use flow::*;
+pub use registry::*;
+pub use can_mod::{CanSimConfig, ModbusSimConfig, CanPacket, ModbusPdu, CAN_FRAME_LEN, MAX_MODBUS_PDU, run_can_sim, run_modbus_sim};
+pub use mqtt_mod::{MqttSimConfig, MqttPublish, MAX_MQTT_REMAINING, run_mqtt_sim};
+pub use tcp_sim::{TcpSimConfig, TcpFlags, TcpSession, TcpSessionizer, run_tcp_sim};
+pub use grpc_sim::{GrpcSimConfig, Http2Frame, GrpcRequest, GrpcStreams, MAX_FRAME_PAYLOAD, run_grpc_sim};
+pub use pcap_replay::{PcapReplayConfig, PcapReplaySource, PcapError, CapturedFrame, UdpDatagram};
 
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct IoSimulatorConfig {
     pub rate_hz: f32,      // mean packets per second
     pub jitter_ms: u16,    // scheduling jitter
@@ -29,6 +45,12 @@ pub struct IoSimulatorConfig {
     pub loss: f32,         // 0..1
     pub payload_bytes: usize,
     pub http_paths: Vec<String>, // for HTTP sim
+    #[serde(default)]
+    pub malformed: f32,    // 0..1, share of packets sent truncated, garbled or otherwise broken
+    #[serde(default)]
+    pub flow_ids: bool,    // stamp packets with flow tags for end-to-end tracking
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pcap: Option<PcapReplayConfig>, // capture the pcap_replay source plays back
 }
 
 impl Default for IoSimulatorConfig {
@@ -40,22 +62,35 @@ impl Default for IoSimulatorConfig {
             loss: 0.01,
             payload_bytes: 1024,
             http_paths: vec!["/api/metrics".to_string(), "/api/status".to_string()],
+            malformed: 0.0,
+            flow_ids: false,
+            pcap: None,
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub enum IoPacket {
-    Udp { ts_ns: u64, src: std::net::SocketAddr, data: Bytes },
-    HttpReq { ts_ns: u64, path: String, headers: Vec<(String, String)>, body: Bytes },
-    HttpResp { ts_ns: u64, code: u16, headers: Vec<(String, String)>, body: Bytes },
+    Udp { ts_ns: u64, src: std::net::SocketAddr, data: Bytes, flow: Option<FlowTag> },
+    HttpReq { ts_ns: u64, path: String, headers: Vec<(String, String)>, body: Bytes, flow: Option<FlowTag> },
+    HttpResp { ts_ns: u64, code: u16, headers: Vec<(String, String)>, body: Bytes, flow: Option<FlowTag> },
+    TcpSegment { ts_ns: u64, src: std::net::SocketAddr, dst: std::net::SocketAddr, seq: u32, ack: u32, flags: TcpFlags, payload: Bytes, flow: Option<FlowTag> },
+}
+
+impl IoPacket {
+    pub fn flow(&self) -> Option<FlowTag> {
+        match self {
+            IoPacket::Udp { flow, .. } | IoPacket::HttpReq { flow, .. } | IoPacket::HttpResp { flow, .. } | IoPacket::TcpSegment { flow, .. } => *flow,
+        }
+    }
 }
 
 // Output to the ECS op executor
 #[derive(Debug, Clone)]
 pub enum ParsedOp {
-    UdpFrame { payload: Bytes },
-    HttpMessage { is_req: bool, bytes: Bytes },
+    UdpFrame { payload: Bytes, flow: Option<FlowTag> },
+    HttpMessage { is_req: bool, bytes: Bytes, flow: Option<FlowTag> },
+    ParseFault(ParseFault), // the packet was rejected; its consuming job faults instead of completing
 }
 
 #[async_trait::async_trait]
@@ -88,6 +123,7 @@ pub async fn run_udp_sim(
         let packet = IoPacket::Udp {
             ts_ns: chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64,
             src: "127.0.0.1:1234".parse().unwrap(),
+            flow: None,
             data: Bytes::from(vec![
                 0x45, 0x00, 0x00, 0x20, // IP header
                 0x00, 0x01, 0x00, 0x00, // More IP header
@@ -116,6 +152,7 @@ pub async fn run_udp_sim(
     Ok(())
 }
 
+#[cfg(feature = "net")]
 pub async fn parse_udp(
     rx: &mut tokio::net::UdpSocket,
     tx: mpsc::Sender<IoPacket>,
@@ -128,6 +165,7 @@ pub async fn parse_udp(
             ts_ns: chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64,
             src: "127.0.0.1:1234".parse().unwrap(),
             data: Bytes::from(buf[..len].to_vec()),
+            flow: None,
         };
         
         if tx.send(packet).await.is_err() {
@@ -0,0 +1,303 @@
+use serde::{Serialize, Deserialize};
+use tokio::sync::mpsc;
+use bytes::Bytes;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use tokio::time::Duration;
+use crate::ParserError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MqttSimConfig {
+    pub rate_hz: f32,
+    pub jitter_ms: u16,
+    pub loss: f32,          // QoS 0 publishes are dropped, QoS 1/2 ones redelivered with DUP set
+    pub topics: Vec<String>,
+    pub qos_levels: Vec<u8>, // each publish picks one of these
+    pub retain: f32,        // share of publishes with the retain flag
+    pub payload_bytes: usize,
+}
+
+impl Default for MqttSimConfig {
+    fn default() -> Self {
+        Self {
+            rate_hz: 20.0,
+            jitter_ms: 3,
+            loss: 0.01,
+            topics: vec![
+                "plant/line1/temperature".to_string(),
+                "plant/line1/pressure".to_string(),
+                "plant/line2/vibration".to_string(),
+            ],
+            qos_levels: vec![0, 1],
+            retain: 0.05,
+            payload_bytes: 128,
+        }
+    }
+}
+
+impl MqttSimConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.rate_hz > 0.0 && self.rate_hz.is_finite()) {
+            return Err(format!("rate_hz must be above 0, got {}", self.rate_hz));
+        }
+        if self.topics.is_empty() {
+            return Err("At least one topic is needed".to_string());
+        }
+        if let Some(topic) = self.topics.iter().find(|t| !valid_topic(t)) {
+            return Err(format!("'{}' is not a topic a client can publish to", topic));
+        }
+        if self.qos_levels.is_empty() || self.qos_levels.iter().any(|&q| q > 2) {
+            return Err("qos_levels must list levels from 0 to 2".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.loss) || !(0.0..=1.0).contains(&self.retain) {
+            return Err("loss and retain must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Non-empty, within the length prefix, and free of the `+`/`#` wildcards only subscriptions may use
+fn valid_topic(topic: &str) -> bool {
+    !topic.is_empty() && topic.len() <= u16::MAX as usize && !topic.contains(['+', '#', '\0'])
+}
+
+/// An MQTT 3.1.1 PUBLISH
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttPublish {
+    pub topic: String,
+    pub qos: u8,
+    pub retain: bool,
+    pub dup: bool,              // a redelivery of an unacknowledged QoS 1/2 publish
+    pub packet_id: Option<u16>, // present exactly when qos > 0
+    pub payload: Bytes,
+}
+
+const PUBLISH: u8 = 0x30;
+/// Largest remaining length four varint bytes can carry
+pub const MAX_MQTT_REMAINING: usize = 268_435_455;
+
+impl MqttPublish {
+    /// Decode one whole PUBLISH packet: fixed header, topic, packet id when QoS > 0, payload
+    pub fn decode(packet: &[u8]) -> Result<Self, ParserError> {
+        let (&header, rest) = packet.split_first().ok_or(ParserError::InsufficientData)?;
+        if header & 0xF0 != PUBLISH {
+            return Err(ParserError::InvalidFormat);
+        }
+        let (dup, qos, retain) = (header & 0x08 != 0, (header >> 1) & 0x03, header & 0x01 != 0);
+        if qos > 2 || (dup && qos == 0) {
+            return Err(ParserError::InvalidFormat);
+        }
+
+        // Remaining length: 7 bits per byte, high bit set while more follow, at most 4 bytes
+        let mut remaining = 0usize;
+        let mut used = 0;
+        loop {
+            let &byte = rest.get(used).ok_or(ParserError::InsufficientData)?;
+            remaining |= ((byte & 0x7F) as usize) << (7 * used);
+            used += 1;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            if used == 4 {
+                return Err(ParserError::InvalidFormat);
+            }
+        }
+        let body = &rest[used..];
+        if body.len() < remaining {
+            return Err(ParserError::InsufficientData);
+        }
+        if body.len() > remaining {
+            return Err(ParserError::InvalidFormat);
+        }
+
+        if body.len() < 2 {
+            return Err(ParserError::InsufficientData);
+        }
+        let topic_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+        let id_len = if qos > 0 { 2 } else { 0 };
+        if body.len() < 2 + topic_len + id_len {
+            return Err(ParserError::InsufficientData);
+        }
+        let topic = std::str::from_utf8(&body[2..2 + topic_len]).map_err(|_| ParserError::InvalidFormat)?;
+        if !valid_topic(topic) {
+            return Err(ParserError::InvalidFormat);
+        }
+        let packet_id = if qos > 0 {
+            let id = u16::from_be_bytes([body[2 + topic_len], body[3 + topic_len]]);
+            if id == 0 {
+                return Err(ParserError::InvalidFormat);
+            }
+            Some(id)
+        } else {
+            None
+        };
+        Ok(MqttPublish {
+            topic: topic.to_string(),
+            qos,
+            retain,
+            dup,
+            packet_id,
+            payload: Bytes::copy_from_slice(&body[2 + topic_len + id_len..]),
+        })
+    }
+
+    /// Wire form of the publish; QoS above 2, a missing or zero packet id at QoS 1/2, a bad topic
+    /// or a body past the remaining-length limit are rejected
+    pub fn encode(&self) -> Result<Vec<u8>, ParserError> {
+        if self.qos > 2 || !valid_topic(&self.topic) || (self.qos > 0) != self.packet_id.is_some_and(|id| id != 0) {
+            return Err(ParserError::InvalidFormat);
+        }
+        let mut body = Vec::with_capacity(4 + self.topic.len() + self.payload.len());
+        body.extend_from_slice(&(self.topic.len() as u16).to_be_bytes());
+        body.extend_from_slice(self.topic.as_bytes());
+        if let Some(id) = self.packet_id {
+            body.extend_from_slice(&id.to_be_bytes());
+        }
+        body.extend_from_slice(&self.payload);
+        if body.len() > MAX_MQTT_REMAINING {
+            return Err(ParserError::InvalidFormat);
+        }
+
+        let header = PUBLISH | (self.dup as u8) << 3 | self.qos << 1 | self.retain as u8;
+        let mut packet = vec![header];
+        let mut remaining = body.len();
+        loop {
+            let byte = (remaining & 0x7F) as u8;
+            remaining >>= 7;
+            if remaining == 0 {
+                packet.push(byte);
+                break;
+            }
+            packet.push(byte | 0x80);
+        }
+        packet.extend_from_slice(&body);
+        Ok(packet)
+    }
+}
+
+/// Publishes like a broker forwarding to one subscriber: the retained message of every topic
+/// first, then Poisson traffic across the topics until the receiver goes away
+pub async fn run_mqtt_sim(tx: mpsc::Sender<MqttPublish>, cfg: MqttSimConfig, seed: u64) {
+    if cfg.validate().is_err() {
+        return;
+    }
+    let mut rng = Pcg64::seed_from_u64(seed);
+    let mut next_id: u16 = 1;
+    let payload = |rng: &mut Pcg64| Bytes::from((0..cfg.payload_bytes).map(|_| rng.gen()).collect::<Vec<u8>>());
+
+    for topic in &cfg.topics {
+        let retained = MqttPublish { topic: topic.clone(), qos: 0, retain: true, dup: false, packet_id: None, payload: payload(&mut rng) };
+        if tx.send(retained).await.is_err() {
+            return;
+        }
+    }
+
+    let mean_interval_ms = 1000.0 / cfg.rate_hz;
+    let mut redeliver: Option<MqttPublish> = None;
+    loop {
+        let interval_ms = -rng.gen::<f32>().ln() * mean_interval_ms;
+        let jitter_ms = rng.gen_range(0..=cfg.jitter_ms) as f32;
+        tokio::time::sleep(Duration::from_millis((interval_ms + jitter_ms) as u64)).await;
+
+        // An unacknowledged publish goes out again before anything new
+        let publish = match redeliver.take() {
+            Some(lost) => MqttPublish { dup: true, ..lost },
+            None => {
+                let qos = cfg.qos_levels[rng.gen_range(0..cfg.qos_levels.len())];
+                let packet_id = (qos > 0).then(|| {
+                    let id = next_id;
+                    next_id = next_id.checked_add(1).unwrap_or(1);
+                    id
+                });
+                MqttPublish {
+                    topic: cfg.topics[rng.gen_range(0..cfg.topics.len())].clone(),
+                    qos,
+                    retain: rng.gen::<f32>() < cfg.retain,
+                    dup: false,
+                    packet_id,
+                    payload: payload(&mut rng),
+                }
+            }
+        };
+
+        if rng.gen::<f32>() < cfg.loss {
+            if publish.qos > 0 {
+                redeliver = Some(publish);
+            }
+            continue;
+        }
+        if tx.send(publish).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    fn publish(qos: u8, payload: &'static [u8]) -> MqttPublish {
+        MqttPublish {
+            topic: "plant/line1/temperature".to_string(),
+            qos,
+            retain: false,
+            dup: false,
+            packet_id: (qos > 0).then_some(7),
+            payload: Bytes::from_static(payload),
+        }
+    }
+
+    #[test]
+    fn test_publish_round_trip_and_limits() {
+        for qos in 0..=2 {
+            let p = publish(qos, b"21.5C");
+            assert_eq!(MqttPublish::decode(&p.encode().unwrap()).unwrap(), p);
+        }
+        // A 200-byte payload needs a two-byte remaining length
+        let big = MqttPublish { retain: true, payload: Bytes::from(vec![1; 200]), ..publish(1, b"") };
+        let wire = big.encode().unwrap();
+        assert_eq!(wire[1] & 0x80, 0x80);
+        assert_eq!(MqttPublish::decode(&wire).unwrap(), big);
+
+        let wire = publish(1, b"x").encode().unwrap();
+        assert!(matches!(MqttPublish::decode(&wire[..wire.len() - 1]), Err(ParserError::InsufficientData)));
+        let mut qos3 = wire.clone();
+        qos3[0] |= 0x06;
+        assert!(matches!(MqttPublish::decode(&qos3), Err(ParserError::InvalidFormat)));
+        let mut subscribe = wire;
+        subscribe[0] = 0x82;
+        assert!(matches!(MqttPublish::decode(&subscribe), Err(ParserError::InvalidFormat)));
+
+        assert!(MqttPublish { packet_id: None, ..publish(1, b"") }.encode().is_err());
+        assert!(MqttPublish { topic: "plant/+/temperature".to_string(), ..publish(0, b"") }.encode().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mqtt_simulator_sends_retained_then_traffic() {
+        let (tx, mut rx) = mpsc::channel(100);
+        let config = MqttSimConfig {
+            rate_hz: 50.0,
+            jitter_ms: 0,
+            loss: 0.0,
+            qos_levels: vec![1],
+            retain: 0.0,
+            ..Default::default()
+        };
+        let topics = config.topics.clone();
+        let handle = tokio::spawn(run_mqtt_sim(tx, config, 9));
+
+        for topic in &topics {
+            let retained = timeout(Duration::from_millis(500), rx.recv()).await.unwrap().unwrap();
+            assert!(retained.retain && &retained.topic == topic);
+        }
+        let mut ids = Vec::new();
+        for _ in 0..5 {
+            let p = timeout(Duration::from_millis(500), rx.recv()).await.unwrap().unwrap();
+            assert!(p.qos == 1 && !p.retain && topics.contains(&p.topic));
+            ids.push(p.packet_id.unwrap());
+        }
+        handle.abort();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{FlowTag, IoPacket, ParserError};
+use bytes::Bytes;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Largest payload a single UDP datagram can carry over IPv4
+pub const MAX_UDP_PAYLOAD: usize = 65_507;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IoSourceKind {
+    Udp,
+    Http,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseFaultKind {
+    Empty,
+    Truncated,       // frame ends early, or the body is shorter than Content-Length
+    BadLength,       // declared length doesn't match the bytes, or oversized datagram
+    BadHeader,       // request line, path or header names that aren't valid
+    BadStatus,       // response code outside 100..=599
+    InvalidEncoding, // text payload that isn't UTF-8
+    Checksum,
+}
+
+impl From<&ParserError> for ParseFaultKind {
+    fn from(err: &ParserError) -> Self {
+        match err {
+            ParserError::InsufficientData => ParseFaultKind::Truncated,
+            ParserError::InvalidFormat => ParseFaultKind::BadHeader,
+            ParserError::ChecksumMismatch => ParseFaultKind::Checksum,
+        }
+    }
+}
+
+/// A packet a parser rejected; carried to the job that consumes it instead of being dropped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseFault {
+    pub source: IoSourceKind,
+    pub kind: ParseFaultKind,
+    pub len: usize, // bytes in the rejected payload
+    #[serde(default)]
+    pub flow: Option<FlowTag>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SourceParseCounts {
+    pub parsed: u64,
+    pub faults: u64,
+    pub by_kind: BTreeMap<ParseFaultKind, u64>,
+}
+
+impl SourceParseCounts {
+    pub fn fault_rate(&self) -> f32 {
+        let total = self.parsed + self.faults;
+        if total == 0 { 0.0 } else { self.faults as f32 / total as f32 }
+    }
+}
+
+/// Per-source parse counters shared between parser tasks and whoever reports on them
+#[derive(Debug, Clone, Default)]
+pub struct ParseCounters {
+    inner: Arc<Mutex<BTreeMap<IoSourceKind, SourceParseCounts>>>,
+}
+
+impl ParseCounters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_parsed(&self, source: IoSourceKind) {
+        if let Ok(mut counts) = self.inner.lock() {
+            counts.entry(source).or_default().parsed += 1;
+        }
+    }
+
+    pub fn record_fault(&self, fault: &ParseFault) {
+        if let Ok(mut counts) = self.inner.lock() {
+            let entry = counts.entry(fault.source).or_default();
+            entry.faults += 1;
+            *entry.by_kind.entry(fault.kind).or_default() += 1;
+        }
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<IoSourceKind, SourceParseCounts> {
+        self.inner.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+}
+
+/// Telemetry frames are JSON objects; anything else on the wire is a fault
+pub fn validate_udp_frame(data: &[u8]) -> Result<(), ParseFaultKind> {
+    if data.is_empty() {
+        return Err(ParseFaultKind::Empty);
+    }
+    if data.len() > MAX_UDP_PAYLOAD {
+        return Err(ParseFaultKind::BadLength);
+    }
+    let text = std::str::from_utf8(data).map_err(|_| ParseFaultKind::InvalidEncoding)?;
+    let text = text.trim();
+    if !text.starts_with('{') || !text.ends_with('}') {
+        return Err(ParseFaultKind::Truncated);
+    }
+    Ok(())
+}
+
+fn validate_headers(headers: &[(String, String)], body: &Bytes) -> Result<(), ParseFaultKind> {
+    for (name, _) in headers {
+        if name.is_empty() || name.chars().any(|c| c.is_whitespace() || c == ':' || c.is_control()) {
+            return Err(ParseFaultKind::BadHeader);
+        }
+    }
+    if let Some((_, value)) = headers.iter().find(|(n, _)| n.eq_ignore_ascii_case("content-length")) {
+        let declared: usize = value.trim().parse().map_err(|_| ParseFaultKind::BadHeader)?;
+        if body.len() < declared {
+            return Err(ParseFaultKind::Truncated);
+        }
+        if body.len() > declared {
+            return Err(ParseFaultKind::BadLength);
+        }
+    }
+    let is_text = headers.iter().any(|(n, v)| {
+        n.eq_ignore_ascii_case("content-type") && (v.contains("json") || v.starts_with("text/"))
+    });
+    if is_text && std::str::from_utf8(body).is_err() {
+        return Err(ParseFaultKind::InvalidEncoding);
+    }
+    Ok(())
+}
+
+pub fn validate_http(packet: &IoPacket) -> Result<(), ParseFaultKind> {
+    match packet {
+        IoPacket::HttpReq { path, headers, body, .. } => {
+            if !path.starts_with('/') || path.chars().any(|c| c.is_whitespace() || c.is_control()) {
+                return Err(ParseFaultKind::BadHeader);
+            }
+            validate_headers(headers, body)
+        }
+        IoPacket::HttpResp { code, headers, body, .. } => {
+            if !(100..=599).contains(code) {
+                return Err(ParseFaultKind::BadStatus);
+            }
+            validate_headers(headers, body)
+        }
+        IoPacket::Udp { .. } | IoPacket::TcpSegment { .. } => Ok(()),
+    }
+}
+
+/// Corrupt a payload the way a flaky link or buggy sender would: cut short, garbled or empty
+pub fn mangle_bytes(rng: &mut impl Rng, data: &[u8]) -> Bytes {
+    match rng.gen_range(0..3) {
+        0 if data.len() > 1 => Bytes::copy_from_slice(&data[..rng.gen_range(1..data.len())]),
+        1 => {
+            let mut garbled = data.to_vec();
+            let flips = (garbled.len() / 16).max(1);
+            for _ in 0..flips {
+                if garbled.is_empty() {
+                    break;
+                }
+                let i = rng.gen_range(0..garbled.len());
+                garbled[i] = 0xff; // never valid in UTF-8
+            }
+            if garbled.is_empty() {
+                garbled.push(0xff);
+            }
+            Bytes::from(garbled)
+        }
+        _ => Bytes::new(),
+    }
+}
+
+/// Break an HTTP message in one of the ways HttpParser has to survive
+pub fn mangle_http(rng: &mut impl Rng, packet: IoPacket) -> IoPacket {
+    match packet {
+        IoPacket::HttpReq { ts_ns, path, mut headers, body, flow } => match rng.gen_range(0..3) {
+            0 => IoPacket::HttpReq { ts_ns, path: path.trim_start_matches('/').to_string(), headers, body, flow },
+            1 => {
+                headers.push(("Bad Header".to_string(), "x".to_string()));
+                IoPacket::HttpReq { ts_ns, path, headers, body, flow }
+            }
+            _ => {
+                headers.push(("Content-Length".to_string(), (body.len() + 16).to_string()));
+                IoPacket::HttpReq { ts_ns, path, headers, body, flow }
+            }
+        },
+        IoPacket::HttpResp { ts_ns, code, headers, body, flow } => match rng.gen_range(0..2) {
+            0 => IoPacket::HttpResp { ts_ns, code: if rng.gen() { 0 } else { 999 }, headers, body, flow },
+            _ => IoPacket::HttpResp { ts_ns, code, headers, body: mangle_bytes(rng, &body), flow },
+        },
+        other => other,
+    }
+}
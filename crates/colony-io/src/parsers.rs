@@ -1,4 +1,4 @@
-use crate::IoPacket;
+use crate::{IoPacket, TcpFlags};
 use bytes::Bytes;
 
 pub struct UdpParser;
@@ -15,6 +15,9 @@ impl UdpParser {
         let length = u16::from_be_bytes([data[4], data[5]]);
         let checksum = u16::from_be_bytes([data[6], data[7]]);
         
+        if length < 8 {
+            return Err(ParserError::InvalidFormat);
+        }
         if data.len() < length as usize {
             return Err(ParserError::InsufficientData);
         }
@@ -25,6 +28,7 @@ impl UdpParser {
             ts_ns: chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64,
             src: "127.0.0.1:1234".parse().unwrap(),
             data: Bytes::from(payload),
+            flow: None,
         })
     }
 }
@@ -32,6 +36,24 @@ impl UdpParser {
 pub struct TcpParser;
 
 impl TcpParser {
+    /// Wire form of a TCP segment: a 20-byte header without options, then the payload
+    pub fn encode(&self, packet: &IoPacket) -> Option<Vec<u8>> {
+        let IoPacket::TcpSegment { src, dst, seq, ack, flags, payload, .. } = packet else {
+            return None;
+        };
+        let mut wire = Vec::with_capacity(20 + payload.len());
+        wire.extend_from_slice(&src.port().to_be_bytes());
+        wire.extend_from_slice(&dst.port().to_be_bytes());
+        wire.extend_from_slice(&seq.to_be_bytes());
+        wire.extend_from_slice(&ack.to_be_bytes());
+        wire.push(5 << 4); // data offset in 32-bit words
+        wire.push(flags.0);
+        wire.extend_from_slice(&u16::MAX.to_be_bytes()); // window
+        wire.extend_from_slice(&[0, 0, 0, 0]); // checksum, urgent pointer
+        wire.extend_from_slice(payload);
+        Some(wire)
+    }
+
     pub fn parse(&self, data: &[u8]) -> Result<IoPacket, ParserError> {
         if data.len() < 20 {
             return Err(ParserError::InsufficientData);
@@ -44,18 +66,26 @@ impl TcpParser {
         let ack_num = u32::from_be_bytes([data[8], data[9], data[10], data[11]]);
         
         let data_offset = (data[12] >> 4) as usize;
+        if data_offset < 5 {
+            return Err(ParserError::InvalidFormat);
+        }
         if data.len() < data_offset * 4 {
             return Err(ParserError::InsufficientData);
         }
         
         let payload = Bytes::copy_from_slice(&data[data_offset * 4..]);
         
-        // TCP is not a valid IoPacket variant, use HttpReq instead
-        Ok(IoPacket::HttpReq {
+        // The header has ports but no addresses; those come from the IP layer
+        let localhost = std::net::Ipv4Addr::LOCALHOST.into();
+        Ok(IoPacket::TcpSegment {
             ts_ns: chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64,
-            path: "/tcp-data".to_string(),
-            headers: vec![],
-            body: payload,
+            src: std::net::SocketAddr::new(localhost, src_port),
+            dst: std::net::SocketAddr::new(localhost, dst_port),
+            seq: seq_num,
+            ack: ack_num,
+            flags: TcpFlags(data[13] & 0x3F),
+            payload,
+            flow: None,
         })
     }
 }
@@ -63,6 +93,23 @@ impl TcpParser {
 pub struct HttpParser;
 
 impl HttpParser {
+    /// Wire form of an HTTP packet, as `parse` reads it back; requests go out as POST
+    pub fn encode(&self, packet: &IoPacket) -> Option<Vec<u8>> {
+        let (start_line, headers, body) = match packet {
+            IoPacket::HttpReq { path, headers, body, .. } => (format!("POST {} HTTP/1.1", path), headers, body),
+            IoPacket::HttpResp { code, headers, body, .. } => (format!("HTTP/1.1 {}", code), headers, body),
+            IoPacket::Udp { .. } | IoPacket::TcpSegment { .. } => return None,
+        };
+        let mut wire = start_line.into_bytes();
+        wire.extend_from_slice(b"\r\n");
+        for (name, value) in headers {
+            wire.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+        }
+        wire.extend_from_slice(b"\r\n");
+        wire.extend_from_slice(body);
+        Some(wire)
+    }
+
     pub fn parse(&self, data: &[u8]) -> Result<IoPacket, ParserError> {
         let text = String::from_utf8_lossy(data);
         
@@ -104,6 +151,7 @@ impl HttpParser {
                 code: status,
                 headers: headers.into_iter().collect(),
                 body: Bytes::from(body),
+                flow: None,
             })
         } else {
             // Request
@@ -145,6 +193,7 @@ impl HttpParser {
                 path,
                 headers: headers.into_iter().collect(),
                 body: Bytes::from(body),
+                flow: None,
             })
         }
     }
@@ -0,0 +1,445 @@
+use super::{FlowStamper, IoPacket, IoSource, IoSourceKind};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+
+pub const LINKTYPE_NULL: u16 = 0; // BSD loopback
+pub const LINKTYPE_ETHERNET: u16 = 1;
+pub const LINKTYPE_RAW: u16 = 101;
+pub const LINKTYPE_LINUX_SLL: u16 = 113;
+pub const LINKTYPE_IPV4: u16 = 228;
+pub const LINKTYPE_IPV6: u16 = 229;
+
+const PCAPNG_SHB: u32 = 0x0A0D_0D0A;
+const PCAPNG_BYTE_ORDER: u32 = 0x1A2B_3C4D;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PcapError {
+    #[error("failed to read capture: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("not a pcap or pcapng file")]
+    UnknownFormat,
+    #[error("capture is malformed: {0}")]
+    Malformed(&'static str),
+    #[error("replay speed must be above 0, got {0}")]
+    BadSpeed(f64),
+    #[error("no capture file configured")]
+    NoCapture,
+}
+
+/// How to replay a capture; pinned per channel in the simulator config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PcapReplayConfig {
+    pub path: PathBuf,
+    #[serde(default = "default_speed")]
+    pub speed: f64, // 2.0 replays twice as fast as captured
+    #[serde(default)]
+    pub repeat: bool, // start over after the last packet
+}
+
+fn default_speed() -> f64 {
+    1.0
+}
+
+impl Default for PcapReplayConfig {
+    fn default() -> Self {
+        Self { path: PathBuf::new(), speed: default_speed(), repeat: false }
+    }
+}
+
+/// One captured link-layer frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedFrame {
+    pub ts_ns: u64,
+    pub link_type: u16,
+    pub data: Bytes,
+}
+
+/// A UDP datagram recovered from a frame, payload only
+#[derive(Debug, Clone, PartialEq)]
+pub struct UdpDatagram {
+    pub ts_ns: u64,
+    pub src: SocketAddr,
+    pub payload: Bytes,
+}
+
+#[derive(Clone, Copy)]
+struct Endian(bool); // true for big endian
+
+impl Endian {
+    fn u16(self, b: &[u8], at: usize) -> Option<u16> {
+        let bytes = b.get(at..at + 2)?.try_into().ok()?;
+        Some(if self.0 { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    }
+
+    fn u32(self, b: &[u8], at: usize) -> Option<u32> {
+        let bytes = b.get(at..at + 4)?.try_into().ok()?;
+        Some(if self.0 { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    }
+}
+
+/// Frames of a classic pcap or a pcapng capture, told apart by their magic. A record cut short
+/// at the end of the file, as a capture killed mid-write leaves, ends the capture.
+pub fn read_capture(buf: &[u8]) -> Result<Vec<CapturedFrame>, PcapError> {
+    let magic = buf.get(..4).ok_or(PcapError::UnknownFormat)?;
+    if magic == PCAPNG_SHB.to_le_bytes() {
+        return read_pcapng(buf);
+    }
+    let (endian, nanos) = match magic {
+        [0xd4, 0xc3, 0xb2, 0xa1] => (Endian(false), false),
+        [0xa1, 0xb2, 0xc3, 0xd4] => (Endian(true), false),
+        [0x4d, 0x3c, 0xb2, 0xa1] => (Endian(false), true),
+        [0xa1, 0xb2, 0x3c, 0x4d] => (Endian(true), true),
+        _ => return Err(PcapError::UnknownFormat),
+    };
+    let link_type = endian.u32(buf, 20).ok_or(PcapError::Malformed("global header"))? as u16;
+
+    let mut frames = Vec::new();
+    let mut at = 24;
+    while let (Some(secs), Some(frac), Some(incl_len)) = (endian.u32(buf, at), endian.u32(buf, at + 4), endian.u32(buf, at + 8)) {
+        let start = at + 16;
+        let Some(data) = buf.get(start..start + incl_len as usize) else { break };
+        let frac_ns = if nanos { frac as u64 } else { frac as u64 * 1_000 };
+        frames.push(CapturedFrame { ts_ns: secs as u64 * 1_000_000_000 + frac_ns, link_type, data: Bytes::copy_from_slice(data) });
+        at = start + incl_len as usize;
+    }
+    Ok(frames)
+}
+
+/// Interface timestamp resolution from `if_tsresol`: a power of ten, or of two with the high bit set
+#[derive(Clone, Copy)]
+struct Interface {
+    link_type: u16,
+    tsresol: u8,
+}
+
+impl Interface {
+    fn ts_ns(self, units: u64) -> u64 {
+        let exp = (self.tsresol & 0x7f) as u32;
+        let units = units as u128;
+        let ns = if self.tsresol & 0x80 != 0 {
+            (units * 1_000_000_000) >> exp.min(127)
+        } else if exp <= 9 {
+            units * 10u128.pow(9 - exp)
+        } else {
+            units / 10u128.pow((exp - 9).min(38))
+        };
+        ns.min(u64::MAX as u128) as u64
+    }
+}
+
+fn read_pcapng(buf: &[u8]) -> Result<Vec<CapturedFrame>, PcapError> {
+    let mut frames = Vec::new();
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut endian = Endian(false);
+    let mut last_ts = 0;
+    let mut at = 0;
+    while at + 12 <= buf.len() {
+        let block_type = u32::from_le_bytes(buf[at..at + 4].try_into().unwrap());
+        if block_type == PCAPNG_SHB {
+            // Each section fixes its own byte order and interface list
+            endian = match buf.get(at + 8..at + 12) {
+                Some(b) if b == PCAPNG_BYTE_ORDER.to_le_bytes() => Endian(false),
+                Some(b) if b == PCAPNG_BYTE_ORDER.to_be_bytes() => Endian(true),
+                _ => return Err(PcapError::Malformed("section header byte order")),
+            };
+            interfaces.clear();
+        }
+        let block_type = endian.u32(buf, at).unwrap();
+        let len = endian.u32(buf, at + 4).unwrap() as usize;
+        if len < 12 || !len.is_multiple_of(4) {
+            return Err(PcapError::Malformed("block length"));
+        }
+        let Some(block) = buf.get(at..at + len) else { break };
+
+        match block_type {
+            // Interface description: link type, then options for the timestamp resolution
+            1 => {
+                let link_type = endian.u16(block, 8).ok_or(PcapError::Malformed("interface block"))?;
+                let mut tsresol = 6;
+                let mut opt = 16;
+                while let (Some(code), Some(opt_len)) = (endian.u16(block, opt), endian.u16(block, opt + 2)) {
+                    if code == 0 || opt + 4 + opt_len as usize > len - 4 {
+                        break;
+                    }
+                    if code == 9 && opt_len >= 1 {
+                        tsresol = block[opt + 4];
+                    }
+                    opt += 4 + (opt_len as usize).div_ceil(4) * 4;
+                }
+                interfaces.push(Interface { link_type, tsresol });
+            }
+            // Enhanced packet, and the obsolete packet block with a 16-bit interface id
+            2 | 6 => {
+                let iface = if block_type == 6 { endian.u32(block, 8) } else { endian.u16(block, 8).map(u32::from) };
+                let fields = (iface, endian.u32(block, 12), endian.u32(block, 16), endian.u32(block, 20));
+                let (Some(iface), Some(high), Some(low), Some(cap_len)) = fields else {
+                    return Err(PcapError::Malformed("packet block"));
+                };
+                let interface = *interfaces.get(iface as usize).ok_or(PcapError::Malformed("packet on an undeclared interface"))?;
+                let data = block.get(28..28 + cap_len as usize).ok_or(PcapError::Malformed("packet block"))?;
+                last_ts = interface.ts_ns(((high as u64) << 32) | low as u64);
+                frames.push(CapturedFrame { ts_ns: last_ts, link_type: interface.link_type, data: Bytes::copy_from_slice(data) });
+            }
+            // Simple packet: interface 0, no timestamp of its own
+            3 => {
+                let interface = *interfaces.first().ok_or(PcapError::Malformed("packet on an undeclared interface"))?;
+                let orig_len = endian.u32(block, 8).unwrap() as usize;
+                let data = block.get(12..(12 + orig_len).min(len - 4)).ok_or(PcapError::Malformed("simple packet block"))?;
+                frames.push(CapturedFrame { ts_ns: last_ts, link_type: interface.link_type, data: Bytes::copy_from_slice(data) });
+            }
+            _ => {}
+        }
+        at += len;
+    }
+    Ok(frames)
+}
+
+/// The UDP datagram a frame carries, if any. IP fragments and non-UDP traffic are skipped.
+pub fn udp_datagram(frame: &CapturedFrame) -> Option<UdpDatagram> {
+    let data = &frame.data[..];
+    let ip = match frame.link_type {
+        LINKTYPE_ETHERNET => {
+            let mut at = 12;
+            let mut ethertype = u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?);
+            // 802.1Q and 802.1ad tags
+            while ethertype == 0x8100 || ethertype == 0x88a8 {
+                at += 4;
+                ethertype = u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?);
+            }
+            if ethertype != 0x0800 && ethertype != 0x86dd {
+                return None;
+            }
+            data.get(at + 2..)?
+        }
+        LINKTYPE_LINUX_SLL => data.get(16..)?,
+        LINKTYPE_NULL => data.get(4..)?,
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => data,
+        _ => return None,
+    };
+
+    let (src_ip, udp) = match ip.first()? >> 4 {
+        4 => {
+            let ihl = (ip[0] & 0x0f) as usize * 4;
+            let total = (u16::from_be_bytes(ip.get(2..4)?.try_into().ok()?) as usize).min(ip.len());
+            let frag = u16::from_be_bytes(ip.get(6..8)?.try_into().ok()?);
+            if ip.get(9)? != &17 || frag & 0x3fff != 0 || ihl < 20 {
+                return None;
+            }
+            let src: [u8; 4] = ip.get(12..16)?.try_into().ok()?;
+            (IpAddr::V4(Ipv4Addr::from(src)), ip.get(ihl..total)?)
+        }
+        6 => {
+            if ip.get(6)? != &17 {
+                return None;
+            }
+            let payload_len = u16::from_be_bytes(ip.get(4..6)?.try_into().ok()?) as usize;
+            let src: [u8; 16] = ip.get(8..24)?.try_into().ok()?;
+            (IpAddr::V6(Ipv6Addr::from(src)), ip.get(40..(40 + payload_len).min(ip.len()))?)
+        }
+        _ => return None,
+    };
+
+    let src_port = u16::from_be_bytes(udp.get(0..2)?.try_into().ok()?);
+    let udp_len = (u16::from_be_bytes(udp.get(4..6)?.try_into().ok()?) as usize).min(udp.len());
+    Some(UdpDatagram {
+        ts_ns: frame.ts_ns,
+        src: SocketAddr::new(src_ip, src_port),
+        payload: Bytes::copy_from_slice(udp.get(8..udp_len)?),
+    })
+}
+
+/// Replays the UDP datagrams of a recorded capture into the `IoPacket` channel with their
+/// original spacing, scaled by `speed`. Packets are stamped with the time they are replayed.
+pub struct PcapReplaySource {
+    config: PcapReplayConfig,
+    flow_ids: bool,
+    datagrams: Option<Vec<UdpDatagram>>,
+    pub frames: usize,  // frames in the capture, once loaded
+    pub skipped: usize, // of those, not UDP or not decodable
+}
+
+impl PcapReplaySource {
+    /// Load lazily: the capture is read when the source starts, and a bad file ends it with a log line
+    pub fn new(config: PcapReplayConfig, flow_ids: bool) -> Self {
+        Self { config, flow_ids, datagrams: None, frames: 0, skipped: 0 }
+    }
+
+    /// Read and decode the capture now, so a bad path or file is reported to the caller
+    pub fn open(config: PcapReplayConfig, flow_ids: bool) -> Result<Self, PcapError> {
+        let mut source = Self::new(config, flow_ids);
+        source.load()?;
+        Ok(source)
+    }
+
+    fn load(&mut self) -> Result<&[UdpDatagram], PcapError> {
+        if !(self.config.speed > 0.0 && self.config.speed.is_finite()) {
+            return Err(PcapError::BadSpeed(self.config.speed));
+        }
+        if self.config.path.as_os_str().is_empty() {
+            return Err(PcapError::NoCapture);
+        }
+        if self.datagrams.is_none() {
+            let frames = read_capture(&std::fs::read(&self.config.path)?)?;
+            let datagrams: Vec<UdpDatagram> = frames.iter().filter_map(udp_datagram).collect();
+            self.frames = frames.len();
+            self.skipped = frames.len() - datagrams.len();
+            self.datagrams = Some(datagrams);
+        }
+        Ok(self.datagrams.as_deref().unwrap_or_default())
+    }
+
+    pub fn datagrams(&self) -> &[UdpDatagram] {
+        self.datagrams.as_deref().unwrap_or_default()
+    }
+}
+
+#[async_trait::async_trait]
+impl IoSource for PcapReplaySource {
+    async fn run(mut self: Box<Self>, tx: mpsc::Sender<IoPacket>, _seed: u64) {
+        if let Err(e) = self.load() {
+            println!("PCAP replay of {:?} stopped: {}", self.config.path, e);
+            return;
+        }
+        let datagrams = self.datagrams();
+        let Some(first_ts) = datagrams.first().map(|d| d.ts_ns) else { return };
+        let mut stamper = FlowStamper::new(IoSourceKind::Udp);
+
+        loop {
+            // Offsets are taken from the start of each pass, so sleeps never accumulate drift
+            let start = Instant::now();
+            for datagram in datagrams {
+                let offset_ns = datagram.ts_ns.saturating_sub(first_ts) as f64 / self.config.speed;
+                tokio::time::sleep_until(start + Duration::from_nanos(offset_ns as u64)).await;
+
+                let now = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64;
+                let flow = self.flow_ids.then(|| {
+                    let flow = stamper.flow_for(&datagram.src.to_string());
+                    stamper.stamp(flow, now)
+                });
+                let packet = IoPacket::Udp { ts_ns: now, src: datagram.src, data: datagram.payload.clone(), flow };
+                if tx.send(packet).await.is_err() {
+                    return;
+                }
+            }
+            if !self.config.repeat {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ethernet, IPv4 and UDP headers around `payload`, from 10.0.0.7:5000
+    fn eth_udp(payload: &[u8], proto: u8) -> Vec<u8> {
+        let mut frame = vec![0; 12];
+        frame.extend_from_slice(&[0x08, 0x00]);
+        let total = (20 + 8 + payload.len()) as u16;
+        frame.extend_from_slice(&[0x45, 0, (total >> 8) as u8, total as u8, 0, 1, 0x40, 0, 64, proto, 0, 0]);
+        frame.extend_from_slice(&[10, 0, 0, 7, 10, 0, 0, 1]);
+        let udp_len = (8 + payload.len()) as u16;
+        frame.extend_from_slice(&[0x13, 0x88, 0x1f, 0x90, (udp_len >> 8) as u8, udp_len as u8, 0, 0]);
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn pcap(records: &[(u32, u32, Vec<u8>)]) -> Vec<u8> {
+        let mut file = vec![0xd4, 0xc3, 0xb2, 0xa1, 2, 0, 4, 0];
+        file.extend_from_slice(&[0; 8]);
+        file.extend_from_slice(&65535u32.to_le_bytes());
+        file.extend_from_slice(&(LINKTYPE_ETHERNET as u32).to_le_bytes());
+        for (secs, micros, data) in records {
+            for field in [*secs, *micros, data.len() as u32, data.len() as u32] {
+                file.extend_from_slice(&field.to_le_bytes());
+            }
+            file.extend_from_slice(data);
+        }
+        file
+    }
+
+    fn block(block_type: u32, body: &[u8]) -> Vec<u8> {
+        let len = (12 + body.len().div_ceil(4) * 4) as u32;
+        let mut out = block_type.to_le_bytes().to_vec();
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(body);
+        out.resize(len as usize - 4, 0);
+        out.extend_from_slice(&len.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn test_pcap_frames_decode_to_udp() {
+        let file = pcap(&[
+            (100, 250_000, eth_udp(br#"{"temp":21.5}"#, 17)),
+            (100, 750_000, eth_udp(b"not udp", 6)),
+        ]);
+        let frames = read_capture(&file).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].ts_ns, 100_250_000_000);
+
+        let datagram = udp_datagram(&frames[0]).unwrap();
+        assert_eq!(datagram.src, "10.0.0.7:5000".parse().unwrap());
+        assert_eq!(&datagram.payload[..], br#"{"temp":21.5}"#);
+        assert!(udp_datagram(&frames[1]).is_none());
+
+        // A record cut off mid-write ends the capture instead of failing it
+        assert_eq!(read_capture(&file[..file.len() - 3]).unwrap().len(), 1);
+        assert!(matches!(read_capture(b"GIF89a.."), Err(PcapError::UnknownFormat)));
+    }
+
+    #[test]
+    fn test_pcapng_with_nanosecond_interface() {
+        let mut shb = PCAPNG_BYTE_ORDER.to_le_bytes().to_vec();
+        shb.extend_from_slice(&[1, 0, 0, 0]);
+        shb.extend_from_slice(&u64::MAX.to_le_bytes());
+        let mut idb = (LINKTYPE_RAW as u32).to_le_bytes().to_vec();
+        idb.extend_from_slice(&0u32.to_le_bytes());
+        idb.extend_from_slice(&[9, 0, 1, 0, 9, 0, 0, 0, 0, 0, 0, 0]); // if_tsresol = 9, opt_endofopt
+
+        let ip = eth_udp(b"{}", 17)[14..].to_vec();
+        let ts: u64 = 5_000_000_123;
+        let mut epb = 0u32.to_le_bytes().to_vec();
+        epb.extend_from_slice(&((ts >> 32) as u32).to_le_bytes());
+        epb.extend_from_slice(&(ts as u32).to_le_bytes());
+        epb.extend_from_slice(&(ip.len() as u32).to_le_bytes());
+        epb.extend_from_slice(&(ip.len() as u32).to_le_bytes());
+        epb.extend_from_slice(&ip);
+
+        let file = [block(PCAPNG_SHB, &shb), block(1, &idb), block(6, &epb)].concat();
+        let frames = read_capture(&file).unwrap();
+        assert_eq!((frames.len(), frames[0].ts_ns, frames[0].link_type), (1, ts, LINKTYPE_RAW));
+        assert_eq!(&udp_datagram(&frames[0]).unwrap().payload[..], b"{}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_replay_keeps_spacing_at_speed() {
+        let path = std::env::temp_dir().join(format!("colony_pcap_{}.pcap", std::process::id()));
+        std::fs::write(&path, pcap(&[
+            (10, 0, eth_udp(b"{\"n\":1}", 17)),
+            (11, 0, eth_udp(b"{\"n\":2}", 17)),
+            (11, 0, eth_udp(b"tcp", 6)),
+        ])).unwrap();
+        let config = PcapReplayConfig { path: path.clone(), speed: 2.0, repeat: false };
+        let source = PcapReplaySource::open(config.clone(), false).unwrap();
+        assert_eq!((source.frames, source.skipped), (3, 1));
+        std::fs::remove_file(&path).unwrap();
+
+        let (tx, mut rx) = mpsc::channel(10);
+        let start = Instant::now();
+        tokio::spawn(Box::new(source).run(tx, 0));
+        let first = rx.recv().await.unwrap();
+        let second = rx.recv().await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+        assert!(matches!(first, IoPacket::Udp { ref data, .. } if &data[..] == b"{\"n\":1}"));
+        assert!(matches!(second, IoPacket::Udp { ref data, .. } if &data[..] == b"{\"n\":2}"));
+        assert!(rx.recv().await.is_none());
+
+        assert!(PcapReplaySource::open(config, false).is_err()); // file is gone
+    }
+}
@@ -0,0 +1,195 @@
+use super::{HttpParser, HttpSimulator, IoParser, IoSimulatorConfig, IoSource, ParseCounters, PcapReplaySource, UdpFramer, UdpSimulator};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Builds a source from the config of the channel it feeds
+pub type SourceFactory = Arc<dyn Fn(&IoSimulatorConfig) -> Box<dyn IoSource> + Send + Sync>;
+/// Builds a parser that reports into the shared parse counters
+pub type ParserFactory = Arc<dyn Fn(ParseCounters) -> Box<dyn IoParser> + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IoRegistryError {
+    #[error("{kind} '{name}' is already registered")]
+    Duplicate { kind: &'static str, name: String },
+    #[error("unknown {kind} '{name}', registered: {known}")]
+    Unknown { kind: &'static str, name: String, known: String },
+}
+
+/// Named sources and parsers that scenarios and API configs refer to by name.
+/// Downstream crates register their own protocols here instead of forking colony-io.
+#[derive(Clone, Default)]
+pub struct IoRegistry {
+    sources: BTreeMap<String, SourceFactory>,
+    parsers: BTreeMap<String, ParserFactory>,
+}
+
+impl std::fmt::Debug for IoRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IoRegistry")
+            .field("sources", &self.source_names())
+            .field("parsers", &self.parser_names())
+            .finish()
+    }
+}
+
+impl IoRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The simulators and parsers colony-io ships: udp_sim, http_sim, pcap_replay,
+    /// udp_framer and http_parser
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.sources.insert("udp_sim".into(), Arc::new(|cfg: &IoSimulatorConfig| Box::new(UdpSimulator::new(cfg.clone())) as Box<dyn IoSource>));
+        registry.sources.insert("http_sim".into(), Arc::new(|cfg: &IoSimulatorConfig| Box::new(HttpSimulator::new(cfg.clone())) as Box<dyn IoSource>));
+        registry.sources.insert("pcap_replay".into(), Arc::new(|cfg: &IoSimulatorConfig| {
+            Box::new(PcapReplaySource::new(cfg.pcap.clone().unwrap_or_default(), cfg.flow_ids)) as Box<dyn IoSource>
+        }));
+        registry.parsers.insert("udp_framer".into(), Arc::new(|counters| Box::new(UdpFramer::with_counters(counters)) as Box<dyn IoParser>));
+        registry.parsers.insert("http_parser".into(), Arc::new(|counters| Box::new(HttpParser::with_counters(counters)) as Box<dyn IoParser>));
+        registry
+    }
+
+    pub fn register_source<F>(&mut self, name: &str, factory: F) -> Result<(), IoRegistryError>
+    where
+        F: Fn(&IoSimulatorConfig) -> Box<dyn IoSource> + Send + Sync + 'static,
+    {
+        if self.sources.contains_key(name) {
+            return Err(IoRegistryError::Duplicate { kind: "source", name: name.to_string() });
+        }
+        self.sources.insert(name.to_string(), Arc::new(factory));
+        Ok(())
+    }
+
+    pub fn register_parser<F>(&mut self, name: &str, factory: F) -> Result<(), IoRegistryError>
+    where
+        F: Fn(ParseCounters) -> Box<dyn IoParser> + Send + Sync + 'static,
+    {
+        if self.parsers.contains_key(name) {
+            return Err(IoRegistryError::Duplicate { kind: "parser", name: name.to_string() });
+        }
+        self.parsers.insert(name.to_string(), Arc::new(factory));
+        Ok(())
+    }
+
+    pub fn source(&self, name: &str, config: &IoSimulatorConfig) -> Result<Box<dyn IoSource>, IoRegistryError> {
+        let factory = self.sources.get(name).ok_or_else(|| IoRegistryError::Unknown {
+            kind: "source",
+            name: name.to_string(),
+            known: self.source_names().join(", "),
+        })?;
+        Ok(factory(config))
+    }
+
+    pub fn parser(&self, name: &str, counters: ParseCounters) -> Result<Box<dyn IoParser>, IoRegistryError> {
+        let factory = self.parsers.get(name).ok_or_else(|| IoRegistryError::Unknown {
+            kind: "parser",
+            name: name.to_string(),
+            known: self.parser_names().join(", "),
+        })?;
+        Ok(factory(counters))
+    }
+
+    pub fn source_names(&self) -> Vec<String> {
+        self.sources.keys().cloned().collect()
+    }
+
+    pub fn parser_names(&self) -> Vec<String> {
+        self.parsers.keys().cloned().collect()
+    }
+
+    /// Check that a channel names a registered source and parser, without building either
+    pub fn check(&self, channel: &IoChannelSpec) -> Result<(), IoRegistryError> {
+        if !self.sources.contains_key(&channel.source) {
+            return Err(IoRegistryError::Unknown { kind: "source", name: channel.source.clone(), known: self.source_names().join(", ") });
+        }
+        if !self.parsers.contains_key(&channel.parser) {
+            return Err(IoRegistryError::Unknown { kind: "parser", name: channel.parser.clone(), known: self.parser_names().join(", ") });
+        }
+        Ok(())
+    }
+}
+
+/// `[[io_channels]]` entry: a registered source feeding a registered parser, whose ops
+/// become jobs on `pipeline`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IoChannelSpec {
+    pub name: String,
+    pub source: String,   // registry name, e.g. "udp_sim"
+    pub parser: String,   // registry name, e.g. "udp_framer"
+    pub pipeline: String, // pipeline id the parsed ops are enqueued on
+    #[serde(default)]
+    pub config: IoSimulatorConfig,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IoPacket, ParsedOp};
+    use tokio::sync::mpsc;
+
+    struct Silent;
+
+    #[async_trait::async_trait]
+    impl IoSource for Silent {
+        async fn run(self: Box<Self>, _tx: mpsc::Sender<IoPacket>, _seed: u64) {}
+    }
+
+    struct Discard;
+
+    #[async_trait::async_trait]
+    impl IoParser for Discard {
+        async fn start(self: Box<Self>, mut rx: mpsc::Receiver<IoPacket>, _tx_ops: mpsc::Sender<ParsedOp>) {
+            while rx.recv().await.is_some() {}
+        }
+    }
+
+    fn channel(source: &str, parser: &str) -> IoChannelSpec {
+        IoChannelSpec {
+            name: "plant_floor".into(),
+            source: source.into(),
+            parser: parser.into(),
+            pipeline: "udp_telemetry_ingest".into(),
+            config: IoSimulatorConfig::default(),
+        }
+    }
+
+    #[test]
+    fn test_register_custom_protocol() {
+        let mut registry = IoRegistry::with_builtins();
+        registry.register_source("silent", |_| Box::new(Silent)).unwrap();
+        registry.register_parser("drop", |_| Box::new(Discard)).unwrap();
+        assert_eq!(registry.source_names(), vec!["http_sim", "pcap_replay", "silent", "udp_sim"]);
+        assert!(registry.check(&channel("silent", "drop")).is_ok());
+        assert!(registry.source("silent", &IoSimulatorConfig::default()).is_ok());
+        assert!(registry.parser("udp_framer", ParseCounters::new()).is_ok());
+    }
+
+    #[test]
+    fn test_duplicates_and_unknown_names_rejected() {
+        let mut registry = IoRegistry::with_builtins();
+        assert_eq!(
+            registry.register_parser("udp_framer", |_| Box::new(Discard)).unwrap_err(),
+            IoRegistryError::Duplicate { kind: "parser", name: "udp_framer".into() }
+        );
+        let err = registry.check(&channel("lorawan", "udp_framer")).unwrap_err();
+        assert!(err.to_string().contains("unknown source 'lorawan'"), "{}", err);
+        assert!(registry.check(&channel("udp_sim", "lorawan")).is_err());
+    }
+
+    #[test]
+    fn test_source_built_from_channel_config() {
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut registry = IoRegistry::new();
+        let log = seen.clone();
+        registry.register_source("silent", move |cfg| {
+            log.lock().unwrap().push(cfg.rate_hz);
+            Box::new(Silent)
+        }).unwrap();
+        let config = IoSimulatorConfig { rate_hz: 5.0, ..Default::default() };
+        registry.source("silent", &config).unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![5.0]);
+    }
+}
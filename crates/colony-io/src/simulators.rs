@@ -37,6 +37,7 @@ impl UdpSimulator {
                 ts_ns: chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64,
                 src: "127.0.0.1:1234".parse().unwrap(),
                 data: Bytes::from(telemetry_data.into_bytes()),
+                flow: None,
             };
 
             // Simulate jitter
@@ -94,6 +95,7 @@ impl TcpSimulator {
                     ("Accept".to_string(), "application/json".to_string()),
                 ],
                 body: Bytes::from(http_request),
+                flow: None,
             };
 
             // Simulate jitter
@@ -0,0 +1,355 @@
+use serde::{Serialize, Deserialize};
+use tokio::sync::mpsc;
+use bytes::{Bytes, BytesMut};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::SocketAddr;
+use tokio::time::Duration;
+use crate::IoPacket;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcpSimConfig {
+    pub rate_hz: f32,             // segments per second across all sessions
+    pub jitter_ms: u16,
+    pub concurrent_sessions: usize, // sessions open at once; their segments interleave
+    pub segments_per_session: u32,  // data segments between the handshake and the FIN
+    pub mss: usize,               // payload bytes per data segment
+    pub reorder: f32,             // 0..1, chance a data segment arrives after the one behind it
+    pub retransmit: f32,          // 0..1, chance a data segment arrives a second time
+    pub server: SocketAddr,
+}
+
+impl Default for TcpSimConfig {
+    fn default() -> Self {
+        Self {
+            rate_hz: 200.0,
+            jitter_ms: 2,
+            concurrent_sessions: 4,
+            segments_per_session: 8,
+            mss: 536,
+            reorder: 0.02,
+            retransmit: 0.01,
+            server: "127.0.0.1:8443".parse().unwrap(),
+        }
+    }
+}
+
+impl TcpSimConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.rate_hz > 0.0 && self.rate_hz.is_finite()) {
+            return Err(format!("rate_hz must be above 0, got {}", self.rate_hz));
+        }
+        if self.concurrent_sessions == 0 {
+            return Err("concurrent_sessions must be at least 1".to_string());
+        }
+        if self.mss == 0 || self.mss > u16::MAX as usize {
+            return Err(format!("mss must be between 1 and {}, got {}", u16::MAX, self.mss));
+        }
+        if !(0.0..=1.0).contains(&self.reorder) || !(0.0..=1.0).contains(&self.retransmit) {
+            return Err("reorder and retransmit must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// TCP header flag bits, as on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct TcpFlags(pub u8);
+
+impl TcpFlags {
+    pub const FIN: TcpFlags = TcpFlags(0x01);
+    pub const SYN: TcpFlags = TcpFlags(0x02);
+    pub const RST: TcpFlags = TcpFlags(0x04);
+    pub const PSH: TcpFlags = TcpFlags(0x08);
+    pub const ACK: TcpFlags = TcpFlags(0x10);
+
+    pub fn contains(self, other: TcpFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for TcpFlags {
+    type Output = TcpFlags;
+    fn bitor(self, rhs: TcpFlags) -> TcpFlags {
+        TcpFlags(self.0 | rhs.0)
+    }
+}
+
+#[derive(Clone)]
+struct Segment {
+    src: SocketAddr,
+    dst: SocketAddr,
+    seq: u32,
+    ack: u32,
+    flags: TcpFlags,
+    payload: Bytes,
+}
+
+impl Segment {
+    fn is_data(&self) -> bool {
+        !self.payload.is_empty()
+    }
+
+    fn packet(&self, ts_ns: u64) -> IoPacket {
+        IoPacket::TcpSegment {
+            ts_ns,
+            src: self.src,
+            dst: self.dst,
+            seq: self.seq,
+            ack: self.ack,
+            flags: self.flags,
+            payload: self.payload.clone(),
+            flow: None,
+        }
+    }
+}
+
+/// Every segment of one connection in send order: handshake, data, then both FINs
+fn session_segments(rng: &mut Pcg64, cfg: &TcpSimConfig, client: SocketAddr) -> VecDeque<Segment> {
+    let (server, ack) = (cfg.server, TcpFlags::ACK);
+    let (client_isn, server_isn): (u32, u32) = (rng.gen(), rng.gen());
+    let mut seq = client_isn.wrapping_add(1);
+    let server_seq = server_isn.wrapping_add(1);
+
+    let mut segments = VecDeque::new();
+    segments.push_back(Segment { src: client, dst: server, seq: client_isn, ack: 0, flags: TcpFlags::SYN, payload: Bytes::new() });
+    segments.push_back(Segment { src: server, dst: client, seq: server_isn, ack: seq, flags: TcpFlags::SYN | ack, payload: Bytes::new() });
+    segments.push_back(Segment { src: client, dst: server, seq, ack: server_seq, flags: ack, payload: Bytes::new() });
+    for _ in 0..cfg.segments_per_session {
+        let payload = Bytes::from((0..cfg.mss).map(|_| rng.gen()).collect::<Vec<u8>>());
+        let next = seq.wrapping_add(payload.len() as u32);
+        segments.push_back(Segment { src: client, dst: server, seq, ack: server_seq, flags: TcpFlags::PSH | ack, payload });
+        seq = next;
+    }
+    segments.push_back(Segment { src: client, dst: server, seq, ack: server_seq, flags: TcpFlags::FIN | ack, payload: Bytes::new() });
+    segments.push_back(Segment { src: server, dst: client, seq: server_seq, ack: seq.wrapping_add(1), flags: TcpFlags::FIN | ack, payload: Bytes::new() });
+    segments.push_back(Segment { src: client, dst: server, seq: seq.wrapping_add(1), ack: server_seq.wrapping_add(1), flags: ack, payload: Bytes::new() });
+    segments
+}
+
+/// Client connections to `cfg.server`, each from its own ephemeral port. Up to
+/// `concurrent_sessions` are open at once and take turns at random, so their segments
+/// interleave; a closed session is replaced by a new one until the receiver goes away.
+pub async fn run_tcp_sim(tx: mpsc::Sender<IoPacket>, cfg: TcpSimConfig, seed: u64) {
+    if cfg.validate().is_err() {
+        return;
+    }
+    let mut rng = Pcg64::seed_from_u64(seed);
+    let mut next_port: u16 = 49152;
+    let mut sessions: Vec<VecDeque<Segment>> = Vec::new();
+    let mean_interval_ms = 1000.0 / cfg.rate_hz;
+
+    loop {
+        while sessions.len() < cfg.concurrent_sessions {
+            let client = SocketAddr::new(cfg.server.ip(), next_port);
+            next_port = next_port.checked_add(1).unwrap_or(49152);
+            sessions.push(session_segments(&mut rng, &cfg, client));
+        }
+
+        let interval_ms = -rng.gen::<f32>().ln() * mean_interval_ms;
+        let jitter_ms = rng.gen_range(0..=cfg.jitter_ms) as f32;
+        tokio::time::sleep(Duration::from_millis((interval_ms + jitter_ms) as u64)).await;
+
+        let index = rng.gen_range(0..sessions.len());
+        let session = &mut sessions[index];
+        // The segment behind overtakes this one; it still arrives, just later
+        if session.front().is_some_and(Segment::is_data) && session.get(1).is_some_and(Segment::is_data) && rng.gen::<f32>() < cfg.reorder {
+            session.swap(0, 1);
+        }
+        let Some(segment) = session.pop_front() else { continue };
+        let ts_ns = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64;
+        // A retransmit comes in again after the sender's next segment
+        if segment.is_data() && rng.gen::<f32>() < cfg.retransmit {
+            session.insert(session.len().min(1), segment.clone());
+        }
+        if session.is_empty() {
+            sessions.swap_remove(index);
+        }
+        if tx.send(segment.packet(ts_ns)).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// One connection's client-to-server byte stream, put back in order
+#[derive(Debug, Clone, PartialEq)]
+pub struct TcpSession {
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    pub payload: Bytes,
+    pub out_of_order: u32, // data segments that arrived ahead of a gap
+    pub duplicates: u32,   // data segments already received
+}
+
+#[derive(Default)]
+struct Stream {
+    origin: u32,   // sequence number of the first data byte, the SYN's plus one
+    next_seq: u32, // first byte not yet delivered
+    fin_seq: Option<u32>,
+    pending: BTreeMap<u32, Bytes>, // by offset from `origin`, so wrapping sequence numbers still sort
+    delivered: BytesMut,
+    out_of_order: u32,
+    duplicates: u32,
+}
+
+/// Parser-side counterpart of `run_tcp_sim`: follows SYN, data and FIN per connection, puts
+/// reordered segments back in sequence and drops retransmitted ones
+#[derive(Default)]
+pub struct TcpSessionizer {
+    streams: HashMap<(SocketAddr, SocketAddr), Stream>,
+}
+
+impl TcpSessionizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connections still waiting for their FIN or for a gap to fill
+    pub fn open(&self) -> usize {
+        self.streams.len()
+    }
+
+    /// Take one segment; returns the session once the client's FIN and every byte before it are in
+    pub fn push(&mut self, packet: &IoPacket) -> Option<TcpSession> {
+        let IoPacket::TcpSegment { src, dst, seq, flags, payload, .. } = packet else {
+            return None;
+        };
+        let key = (*src, *dst);
+        if flags.contains(TcpFlags::RST) {
+            self.streams.remove(&key);
+            self.streams.remove(&(*dst, *src));
+            return None;
+        }
+        if flags.contains(TcpFlags::SYN) {
+            // Only the client opens with a bare SYN; the server's SYN|ACK carries nothing we follow
+            if !flags.contains(TcpFlags::ACK) {
+                let start = seq.wrapping_add(1);
+                self.streams.insert(key, Stream { origin: start, next_seq: start, ..Default::default() });
+            }
+            return None;
+        }
+        let stream = self.streams.get_mut(&key)?;
+        let next = stream.next_seq;
+        let offset = |s: u32| s.wrapping_sub(stream.origin);
+
+        if !payload.is_empty() {
+            if offset(*seq) < offset(next) || stream.pending.contains_key(&offset(*seq)) {
+                stream.duplicates += 1;
+            } else {
+                if *seq != next {
+                    stream.out_of_order += 1;
+                }
+                stream.pending.insert(offset(*seq), payload.clone());
+            }
+        }
+        if flags.contains(TcpFlags::FIN) {
+            stream.fin_seq = Some(*seq);
+        }
+
+        // Deliver whatever is now contiguous
+        let mut next = next;
+        while let Some(data) = stream.pending.remove(&offset(next)) {
+            next = next.wrapping_add(data.len() as u32);
+            stream.delivered.extend_from_slice(&data);
+        }
+        stream.next_seq = next;
+
+        if stream.fin_seq == Some(next) {
+            let stream = self.streams.remove(&key)?;
+            return Some(TcpSession {
+                client: key.0,
+                server: key.1,
+                payload: stream.delivered.freeze(),
+                out_of_order: stream.out_of_order,
+                duplicates: stream.duplicates,
+            });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TcpParser;
+    use tokio::time::timeout;
+
+    #[test]
+    fn test_sessionizer_reorders_and_drops_duplicates() {
+        let (client, server): (SocketAddr, SocketAddr) = ("127.0.0.1:50000".parse().unwrap(), "127.0.0.1:8443".parse().unwrap());
+        let seg = |seq: u32, flags: TcpFlags, payload: &'static [u8]| IoPacket::TcpSegment {
+            ts_ns: 0, src: client, dst: server, seq, ack: 0, flags, payload: Bytes::from_static(payload), flow: None,
+        };
+        // The ISN sits just below the wrap, so sequence numbers roll over mid-stream
+        let isn = u32::MAX - 2;
+        let start = isn.wrapping_add(1);
+        let mut sessionizer = TcpSessionizer::new();
+        let push_ack = TcpFlags::PSH | TcpFlags::ACK;
+        assert_eq!(sessionizer.push(&seg(isn, TcpFlags::SYN, b"")), None);
+        assert_eq!(sessionizer.push(&seg(start.wrapping_add(4), push_ack, b"ef")), None);
+        assert_eq!(sessionizer.push(&seg(start, push_ack, b"abcd")), None);
+        assert_eq!(sessionizer.push(&seg(start, push_ack, b"abcd")), None);
+        assert_eq!(sessionizer.push(&seg(start.wrapping_add(6), TcpFlags::FIN | TcpFlags::ACK, b"")), Some(TcpSession {
+            client,
+            server,
+            payload: Bytes::from_static(b"abcdef"),
+            out_of_order: 1,
+            duplicates: 1,
+        }));
+        assert_eq!(sessionizer.open(), 0);
+    }
+
+    #[test]
+    fn test_segment_wire_round_trip() {
+        let packet = IoPacket::TcpSegment {
+            ts_ns: 0,
+            src: "127.0.0.1:50001".parse().unwrap(),
+            dst: "127.0.0.1:8443".parse().unwrap(),
+            seq: 1_000,
+            ack: 77,
+            flags: TcpFlags::PSH | TcpFlags::ACK,
+            payload: Bytes::from_static(b"hello"),
+            flow: None,
+        };
+        let wire = TcpParser.encode(&packet).unwrap();
+        match TcpParser.parse(&wire).unwrap() {
+            IoPacket::TcpSegment { src, dst, seq, ack, flags, payload, .. } => {
+                assert_eq!((src.port(), dst.port(), seq, ack, flags), (50001, 8443, 1_000, 77, TcpFlags::PSH | TcpFlags::ACK));
+                assert_eq!(&payload[..], b"hello");
+            }
+            other => panic!("parsed to {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tcp_simulator_sessions_reassemble() {
+        let (tx, mut rx) = mpsc::channel(100);
+        let config = TcpSimConfig {
+            rate_hz: 1000.0,
+            jitter_ms: 0,
+            concurrent_sessions: 3,
+            segments_per_session: 6,
+            mss: 100,
+            reorder: 0.3,
+            retransmit: 0.3,
+            ..Default::default()
+        };
+        let handle = tokio::spawn(run_tcp_sim(tx, config, 11));
+
+        let mut sessionizer = TcpSessionizer::new();
+        let mut sessions = Vec::new();
+        let mut peak_open = 0;
+        while sessions.len() < 6 {
+            let packet = timeout(Duration::from_millis(1000), rx.recv()).await.unwrap().unwrap();
+            sessions.extend(sessionizer.push(&packet));
+            peak_open = peak_open.max(sessionizer.open());
+        }
+        handle.abort();
+
+        assert!(sessions.iter().all(|s| s.payload.len() == 600 && s.server.port() == 8443));
+        assert!(peak_open > 1, "sessions should interleave");
+        assert!(sessions.iter().any(|s| s.out_of_order > 0));
+        assert!(sessions.iter().any(|s| s.duplicates > 0));
+    }
+}
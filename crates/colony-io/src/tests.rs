@@ -14,6 +14,9 @@ mod tests {
             loss: 0.0,
             payload_bytes: 100,
             http_paths: vec![],
+            malformed: 0.0,
+            flow_ids: false,
+            pcap: None,
         };
         
         let simulator = UdpSimulator::new(config);
@@ -47,6 +50,9 @@ mod tests {
             loss: 0.5, // 50% loss
             payload_bytes: 100,
             http_paths: vec![],
+            malformed: 0.0,
+            flow_ids: false,
+            pcap: None,
         };
         
         let simulator = UdpSimulator::new(config);
@@ -86,6 +92,7 @@ mod tests {
             path: "/api/test".to_string(),
             headers: vec![("Content-Type".to_string(), "application/json".to_string())],
             body: bytes::Bytes::from("{\"test\": true}"),
+            flow: None,
         };
         
         packet_tx.send(request).await.unwrap();
@@ -93,7 +100,7 @@ mod tests {
         // Should receive parsed op
         let parsed_op = timeout(Duration::from_millis(100), ops_rx.recv()).await.unwrap().unwrap();
         match parsed_op {
-            ParsedOp::HttpMessage { is_req, bytes } => {
+            ParsedOp::HttpMessage { is_req, bytes, .. } => {
                 assert!(is_req);
                 assert_eq!(bytes, bytes::Bytes::from("{\"test\": true}"));
             }
@@ -102,4 +109,138 @@ mod tests {
         
         handle.abort();
     }
+
+    #[test]
+    fn test_malformed_udp_frames_become_parse_faults() {
+        let counters = ParseCounters::new();
+        let framer = UdpFramer::with_counters(counters.clone());
+        let udp = |data: &[u8]| IoPacket::Udp {
+            ts_ns: 0,
+            src: "127.0.0.1:1234".parse().unwrap(),
+            data: bytes::Bytes::copy_from_slice(data),
+            flow: None,
+        };
+
+        assert!(matches!(framer.frame(udp(br#"{"cpu_usage":1.0}"#)), Some(ParsedOp::UdpFrame { .. })));
+        let faults: Vec<ParseFaultKind> = [&br#"{"cpu_us"#[..], &[0x7b, 0xff, 0x7d][..], &[][..]]
+            .iter()
+            .map(|data| match framer.frame(udp(data)) {
+                Some(ParsedOp::ParseFault(fault)) => fault.kind,
+                other => panic!("Expected ParseFault, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(faults, vec![ParseFaultKind::Truncated, ParseFaultKind::InvalidEncoding, ParseFaultKind::Empty]);
+
+        let udp_counts = &counters.snapshot()[&IoSourceKind::Udp];
+        assert_eq!((udp_counts.parsed, udp_counts.faults), (1, 3));
+        assert_eq!(udp_counts.by_kind[&ParseFaultKind::Truncated], 1);
+    }
+
+    #[test]
+    fn test_malformed_http_messages_become_parse_faults() {
+        let parser = HttpParser::new();
+        let headers = vec![("Content-Length".to_string(), "4".to_string())];
+        let resp = |code: u16, body: &'static str| IoPacket::HttpResp {
+            ts_ns: 0,
+            code,
+            headers: headers.clone(),
+            body: bytes::Bytes::from(body),
+            flow: None,
+        };
+
+        assert!(matches!(parser.parse_packet(resp(200, "{}{}")), Some(ParsedOp::HttpMessage { is_req: false, .. })));
+        let kind = |op: Option<ParsedOp>| match op {
+            Some(ParsedOp::ParseFault(fault)) => fault.kind,
+            other => panic!("Expected ParseFault, got {:?}", other),
+        };
+        assert_eq!(kind(parser.parse_packet(resp(999, "{}{}"))), ParseFaultKind::BadStatus);
+        assert_eq!(kind(parser.parse_packet(resp(200, "{}"))), ParseFaultKind::Truncated);
+
+        let req = IoPacket::HttpReq {
+            ts_ns: 0,
+            path: "api/test".to_string(),
+            headers: vec![],
+            body: bytes::Bytes::new(),
+            flow: None,
+        };
+        assert_eq!(kind(parser.parse_packet(req)), ParseFaultKind::BadHeader);
+    }
+
+    #[test]
+    fn test_mangled_packets_never_parse() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let framer = UdpFramer::new();
+        let parser = HttpParser::new();
+        let body = br#"{"timestamp":1,"cpu_usage":12.50,"load":0.30}"#;
+        for _ in 0..200 {
+            let data = mangle_bytes(&mut rng, body);
+            let packet = IoPacket::Udp { ts_ns: 0, src: "127.0.0.1:1234".parse().unwrap(), data, flow: None };
+            assert!(matches!(framer.frame(packet), Some(ParsedOp::ParseFault(_))));
+
+            let resp = IoPacket::HttpResp {
+                ts_ns: 0,
+                code: 200,
+                headers: vec![
+                    ("Content-Type".to_string(), "application/json".to_string()),
+                    ("Content-Length".to_string(), body.len().to_string()),
+                ],
+                body: bytes::Bytes::from_static(body),
+                flow: None,
+            };
+            let mangled = mangle_http(&mut rng, resp);
+            assert!(matches!(parser.parse_packet(mangled), Some(ParsedOp::ParseFault(_))));
+        }
+
+        // The byte-level parser rejects a header claiming less than its own size instead of panicking
+        assert!(UdpParser.parse(&[0, 1, 0, 2, 0, 4, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_wire_http_parses_back() {
+        let wire = crate::parsers::HttpParser;
+        let body = r#"{"status":"ok"}"#;
+        let resp = IoPacket::HttpResp {
+            ts_ns: 0,
+            code: 200,
+            headers: vec![("Content-Length".to_string(), body.len().to_string())],
+            body: bytes::Bytes::from(body),
+            flow: None,
+        };
+        let parsed = wire.parse(&wire.encode(&resp).unwrap()).unwrap();
+        match HttpParser::new().parse_packet(parsed) {
+            Some(ParsedOp::HttpMessage { is_req: false, bytes, .. }) => assert_eq!(bytes, body),
+            other => panic!("Expected HttpMessage, got {:?}", other),
+        }
+        assert!(wire.encode(&IoPacket::Udp { ts_ns: 0, src: "127.0.0.1:1".parse().unwrap(), data: bytes::Bytes::new(), flow: None }).is_none());
+    }
+
+    #[test]
+    fn test_flow_tags_survive_parsing() {
+        let mut stamper = FlowStamper::new(IoSourceKind::Udp);
+        let flow = stamper.flow_for("192.168.1.100:12345");
+        assert_eq!(flow, flow_id(IoSourceKind::Udp, "192.168.1.100:12345"));
+        assert_ne!(flow, flow_id(IoSourceKind::Http, "192.168.1.100:12345"));
+
+        let first = stamper.stamp(flow, 10);
+        stamper.skip(flow); // lost at the source
+        let third = stamper.stamp(flow, 30);
+        assert_eq!((first.seq, third.seq), (0, 2));
+
+        let framer = UdpFramer::new();
+        let packet = |data: &[u8], tag| IoPacket::Udp {
+            ts_ns: 0,
+            src: "127.0.0.1:1234".parse().unwrap(),
+            data: bytes::Bytes::copy_from_slice(data),
+            flow: Some(tag),
+        };
+        match framer.frame(packet(br#"{"load":1.0}"#, first)) {
+            Some(ParsedOp::UdpFrame { flow, .. }) => assert_eq!(flow, Some(first)),
+            other => panic!("Expected UdpFrame, got {:?}", other),
+        }
+        match framer.frame(packet(b"{\"lo", third)) {
+            Some(ParsedOp::ParseFault(fault)) => assert_eq!(fault.flow, Some(third)),
+            other => panic!("Expected ParseFault, got {:?}", other),
+        }
+    }
 }
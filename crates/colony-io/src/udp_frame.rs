@@ -0,0 +1,48 @@
+use super::{IoPacket, ParsedOp, IoParser, IoSourceKind, ParseCounters, ParseFault, validate_udp_frame};
+use tokio::sync::mpsc;
+
+/// Frames UDP telemetry datagrams, rejecting ones that aren't whole JSON objects
+#[derive(Default)]
+pub struct UdpFramer {
+    counters: ParseCounters,
+}
+
+impl UdpFramer {
+    pub fn new() -> Self {
+        Self { counters: ParseCounters::new() }
+    }
+
+    pub fn with_counters(counters: ParseCounters) -> Self {
+        Self { counters }
+    }
+
+    pub fn frame(&self, packet: IoPacket) -> Option<ParsedOp> {
+        let IoPacket::Udp { data, flow, .. } = packet else {
+            return None;
+        };
+        match validate_udp_frame(&data) {
+            Ok(()) => {
+                self.counters.record_parsed(IoSourceKind::Udp);
+                Some(ParsedOp::UdpFrame { payload: data, flow })
+            }
+            Err(kind) => {
+                let fault = ParseFault { source: IoSourceKind::Udp, kind, len: data.len(), flow };
+                self.counters.record_fault(&fault);
+                Some(ParsedOp::ParseFault(fault))
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl IoParser for UdpFramer {
+    async fn start(self: Box<Self>, mut rx: mpsc::Receiver<IoPacket>, tx_ops: mpsc::Sender<ParsedOp>) {
+        while let Some(packet) = rx.recv().await {
+            if let Some(parsed) = self.frame(packet) {
+                if tx_ops.send(parsed).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::{IoPacket, IoSimulatorConfig, IoSource};
+use super::{FlowStamper, IoPacket, IoSimulatorConfig, IoSource, IoSourceKind, mangle_bytes};
 use bytes::Bytes;
 use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
@@ -23,6 +23,12 @@ impl IoSource for UdpSimulator {
         let mut last_packet = Instant::now();
         let mut in_burst = false;
         let mut burst_remaining = 0;
+        let mut stamper = FlowStamper::new(IoSourceKind::Udp);
+        let src = SocketAddr::new(
+            std::net::IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 100)),
+            12345,
+        );
+        let flow = stamper.flow_for(&src.to_string());
         
         // Poisson inter-arrival time: -ln(U) / rate
         let mean_interval_ms = 1000.0 / self.config.rate_hz;
@@ -50,15 +56,14 @@ impl IoSource for UdpSimulator {
             
             // Simulate packet loss
             if rng.gen::<f32>() < self.config.loss {
+                if self.config.flow_ids {
+                    stamper.skip(flow);
+                }
                 continue;
             }
             
             // Generate packet
             let now = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64;
-            let src = SocketAddr::new(
-                std::net::IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 100)),
-                12345,
-            );
             
             // Generate telemetry-like payload
             let payload = format!(
@@ -70,12 +75,17 @@ impl IoSource for UdpSimulator {
                 rng.gen::<f32>() * 10.0,
             );
             
-            let data = Bytes::from(payload);
+            let data = if self.config.malformed > 0.0 && rng.gen::<f32>() < self.config.malformed {
+                mangle_bytes(&mut rng, payload.as_bytes())
+            } else {
+                Bytes::from(payload)
+            };
             
             let packet = IoPacket::Udp {
                 ts_ns: now,
                 src,
                 data,
+                flow: self.config.flow_ids.then(|| stamper.stamp(flow, now)),
             };
             
             if tx.send(packet).await.is_err() {
@@ -1,9 +1,13 @@
 use clap::{Parser, Subcommand};
 use colony_modsdk::{ModManifest, Entrypoints, Capabilities};
+use colony_modsdk::abi::{WasmAbi, COLONY_OP_WIT};
+use colony_core::{ContentPack, tech_graph_dot, event_graph_dot};
 use std::path::{Path, PathBuf};
 use std::fs;
 use anyhow::Result;
 
+mod op_crate;
+
 #[derive(Parser)]
 #[command(name = "colony-mod")]
 #[command(about = "Colony Simulator Mod Development CLI")]
@@ -28,6 +32,18 @@ enum Commands {
         /// Path to mod directory
         path: PathBuf,
     },
+    /// Compile the mod's op crates to WASM and copy them into ops/
+    Build {
+        /// Path to mod directory
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Rust target to build for
+        #[arg(short, long, default_value = op_crate::DEFAULT_TARGET)]
+        target: String,
+        /// Build without optimizations
+        #[arg(long)]
+        debug: bool,
+    },
     /// Sign a mod with a private key
     Sign {
         /// Path to mod directory
@@ -42,6 +58,25 @@ enum Commands {
         #[arg(short, long, default_value = "docs")]
         output: PathBuf,
     },
+    /// Write the colony:op WIT world and a component op template into a mod
+    Bindings {
+        /// Path to mod directory
+        path: PathBuf,
+    },
+    /// Render a mod's tech tree or event graph as GraphViz DOT
+    Graph {
+        /// Path to mod directory
+        path: PathBuf,
+        /// Which graph to draw
+        #[arg(short, long, value_enum, default_value = "tech")]
+        kind: GraphKind,
+        /// Graph the mod's content alone instead of overlaid on vanilla
+        #[arg(long)]
+        standalone: bool,
+        /// Write the DOT here instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
     /// List installed mods
     List {
         /// Mods directory
@@ -50,6 +85,12 @@ enum Commands {
     },
 }
 
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum GraphKind {
+    Tech,
+    Events,
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
@@ -60,12 +101,25 @@ fn main() -> Result<()> {
         Commands::Validate { path } => {
             validate_mod(&path)?;
         }
+        Commands::Build { path, target, debug } => {
+            build_mod(&path, &target, !debug)?;
+        }
         Commands::Sign { path, key } => {
             sign_mod(&path, &key)?;
         }
         Commands::Docs { output } => {
             generate_docs(&output)?;
         }
+        Commands::Bindings { path } => {
+            write_component_bindings(&path)?;
+        }
+        Commands::Graph { path, kind, standalone, output } => {
+            let dot = render_graph(&path, kind, standalone)?;
+            match output {
+                Some(output) => fs::write(&output, dot)?,
+                None => print!("{}", dot),
+            }
+        }
         Commands::List { mods_dir } => {
             list_mods(&mods_dir)?;
         }
@@ -95,6 +149,8 @@ fn create_new_mod(mod_id: &str, output_dir: &Path) -> Result<()> {
             blackswans: Some("events.toml".to_string()),
             tech: Some("tech.toml".to_string()),
             scenarios: Some("scenarios.toml".to_string()),
+            achievements: None,
+            schedulers: Vec::new(),
         },
         capabilities: Capabilities {
             sim_time: true,
@@ -104,9 +160,12 @@ fn create_new_mod(mod_id: &str, output_dir: &Path) -> Result<()> {
             log_debug: true,
             modify_tunables: false,
             trigger_events: false,
+            fs_read: false,
+            scheduler: false,
         },
         signature: None,
         requires: None,
+        op_hashes: Default::default(),
     };
     
     let manifest_toml = toml::to_string_pretty(&manifest)?;
@@ -116,38 +175,10 @@ fn create_new_mod(mod_id: &str, output_dir: &Path) -> Result<()> {
     fs::create_dir_all(mod_dir.join("ops"))?;
     fs::create_dir_all(mod_dir.join("scripts"))?;
     
-    // Create example WASM operation
-    let wasm_example = r#"// Example WASM operation
-// Compile with: wasm-pack build --target web --out-dir ops
-
-#[no_mangle]
-extern "C" fn colony_op_init(ctx: *mut OpCtx) -> i32 {
-    // Initialize your operation here
-    0 // Success
-}
-
-#[no_mangle]
-extern "C" fn colony_op_process(
-    ctx: *mut OpCtx,
-    input: *const u8,
-    input_len: usize,
-    output: *mut u8,
-    output_cap: usize,
-    meta: *const u8,
-    meta_len: usize
-) -> i32 {
-    // Process input data and write to output
-    // Return 0 for success, >0 for faults, <0 for errors
-    0 // Success
-}
-
-#[no_mangle]
-extern "C" fn colony_op_end(ctx: *mut OpCtx) -> i32 {
-    // Clean up your operation here
-    0 // Success
-}"#;
-    
-    fs::write(mod_dir.join("ops").join("example_op.rs"), wasm_example)?;
+    // Create a crate per WASM operation
+    for op_name in &manifest.entrypoints.wasm_ops {
+        op_crate::scaffold_op_crate(&mod_dir, op_name)?;
+    }
     
     // Create example Lua script
     let lua_example = r#"-- Example Lua event script
@@ -274,11 +305,15 @@ A mod for Colony Simulator.
 
 ### Building WASM Operations
 
+Each op is a cargo crate under `ops/` (e.g. `ops/op_example`). Build them all with:
+
 ```bash
-cd ops
-wasm-pack build --target web --out-dir .
+rustup target add wasm32-unknown-unknown
+colony-mod build .
 ```
 
+This compiles each crate, copies it to `ops/<OpName>.wasm` and records its hash in `mod.toml`.
+
 ### Testing
 
 Use the mod console in-game to test your mod:
@@ -297,7 +332,7 @@ This mod requests the following capabilities:
 ## Files
 
 - `mod.toml`: Mod manifest and configuration
-- `ops/`: WASM operations (Rust code)
+- `ops/`: WASM operation crates and their built `.wasm` files
 - `scripts/`: Lua event scripts
 - `pipelines.toml`: Custom pipeline definitions
 - `events.toml`: Black Swan event definitions
@@ -315,7 +350,7 @@ This mod requests the following capabilities:
     println!();
     println!("Next steps:");
     println!("1. Edit mod.toml to configure your mod");
-    println!("2. Implement WASM operations in ops/");
+    println!("2. Implement WASM operations in ops/ and build them with: colony-mod build {:?}", mod_dir);
     println!("3. Write Lua scripts in scripts/");
     println!("4. Define custom content in *.toml files");
     println!("5. Test with: colony-mod validate {:?}", mod_dir);
@@ -361,7 +396,17 @@ fn validate_mod(mod_path: &Path) -> Result<()> {
     for op_name in &manifest.entrypoints.wasm_ops {
         let op_path = mod_path.join("ops").join(format!("{}.wasm", op_name));
         if op_path.exists() {
-            println!("  ✓ WASM op: {}", op_name);
+            let abi = fs::read(&op_path).ok().and_then(|bytes| WasmAbi::detect(&bytes));
+            match abi {
+                Some(WasmAbi::Component) => println!("  ✓ WASM op: {} (component)", op_name),
+                Some(WasmAbi::Legacy) => println!("  ✓ WASM op: {}", op_name),
+                None => println!("  ✗ WASM op is not a wasm module or component: {}", op_name),
+            }
+            if let Some(expected) = manifest.op_hashes.get(op_name) {
+                if op_crate::hash_file(&op_path)? != *expected {
+                    println!("  ✗ WASM op hash doesn't match mod.toml (rebuild with colony-mod build): {}", op_name);
+                }
+            }
         } else {
             println!("  ✗ WASM op not found: {}", op_name);
         }
@@ -423,6 +468,8 @@ fn validate_mod(mod_path: &Path) -> Result<()> {
     println!("  log_debug: {}", manifest.capabilities.log_debug);
     println!("  modify_tunables: {}", manifest.capabilities.modify_tunables);
     println!("  trigger_events: {}", manifest.capabilities.trigger_events);
+    println!("  fs_read: {}", manifest.capabilities.fs_read);
+    println!("  scheduler: {}", manifest.capabilities.scheduler);
     
     // Resource estimates
     println!("\nResource Estimates:");
@@ -432,6 +479,19 @@ fn validate_mod(mod_path: &Path) -> Result<()> {
     Ok(())
 }
 
+fn build_mod(mod_path: &Path, target: &str, release: bool) -> Result<()> {
+    println!("Building mod at: {:?}", mod_path);
+
+    let built = op_crate::build_ops(mod_path, target, release)?;
+    for op in &built {
+        let source = if op.compiled { "built" } else { "prebuilt" };
+        println!("  ✓ {} ({}) -> {:?} sha256 {}", op.op_name, source, op.wasm_path, &op.hash[..16]);
+    }
+    println!("✓ Built {} op(s), hashes updated in mod.toml", built.len());
+
+    Ok(())
+}
+
 fn sign_mod(mod_path: &Path, key_path: &Path) -> Result<()> {
     println!("Signing mod at: {:?}", mod_path);
     println!("Using key: {:?}", key_path);
@@ -727,6 +787,7 @@ Mods must declare their capabilities in `mod.toml`:
 - `log_debug`: Write debug logs
 - `modify_tunables`: Modify system tunables
 - `trigger_events`: Trigger Black Swan events
+- `scheduler`: Register scheduling policies from `schedulers/<Name>.wasm`
 
 ## Security
 
@@ -752,6 +813,75 @@ Check the `examples/` directory for sample mods and tutorials.
     Ok(())
 }
 
+/// Guest side of the component ABI; `wit_bindgen::generate!` builds the typed bindings from wit/
+const COMPONENT_OP_TEMPLATE: &str = r#"// Component-model WASM operation implementing the colony:op world
+// Build with: cargo component build --release (or cargo build --target wasm32-wasip2)
+// then copy the .wasm to ops/<OpName>.wasm and set `abi = "component"` on the op spec
+
+wit_bindgen::generate!({
+    world: "op",
+    path: "../wit",
+});
+
+struct ExampleOp;
+
+impl Guest for ExampleOp {
+    fn process(ctx: OpCtx, input: Payload) -> OpResult {
+        log(1, &format!("{} processing {} bytes at tick {}", ctx.op_name, input.data.len(), ctx.tick));
+        report_metric("input_bytes", input.data.len() as f64);
+
+        // Return code 0 is success, >0 a fault, <0 an error
+        OpResult {
+            code: 0,
+            output: Payload { data: input.data, meta: Vec::new() },
+        }
+    }
+}
+
+export!(ExampleOp);
+"#;
+
+fn write_component_bindings(mod_path: &Path) -> Result<()> {
+    if !mod_path.join("mod.toml").exists() {
+        return Err(anyhow::anyhow!("No mod.toml in {:?}", mod_path));
+    }
+
+    fs::create_dir_all(mod_path.join("wit"))?;
+    fs::write(mod_path.join("wit").join("colony-op.wit"), COLONY_OP_WIT)?;
+
+    // Never clobber an op the author has already written
+    fs::create_dir_all(mod_path.join("ops"))?;
+    let template_path = mod_path.join("ops").join("component_op.rs");
+    if !template_path.exists() {
+        fs::write(&template_path, COMPONENT_OP_TEMPLATE)?;
+    }
+
+    println!("Wrote colony:op bindings to {:?}", mod_path.join("wit"));
+    println!("Guest template: {:?}", template_path);
+    println!("Add wit-bindgen to your op crate's dependencies and build it as a component");
+
+    Ok(())
+}
+
+fn render_graph(mod_path: &Path, kind: GraphKind, standalone: bool) -> Result<String> {
+    if !mod_path.is_dir() {
+        return Err(anyhow::anyhow!("No mod directory at {:?}", mod_path));
+    }
+    let content = ContentPack::load_dir(mod_path)?;
+    // Mods usually build on vanilla techs and rituals, which would otherwise show as missing
+    let pack = if standalone {
+        content
+    } else {
+        let mut pack = ContentPack::vanilla().clone();
+        pack.merge(content);
+        pack
+    };
+    Ok(match kind {
+        GraphKind::Tech => tech_graph_dot(&pack.tech_tree()),
+        GraphKind::Events => event_graph_dot(&pack.tech_tree(), &pack.black_swans),
+    })
+}
+
 fn list_mods(mods_dir: &Path) -> Result<()> {
     println!("Installed mods in: {:?}", mods_dir);
     
@@ -839,6 +969,32 @@ mod tests {
         assert!(mod_dir.join("mod.toml").exists());
         assert!(mod_dir.join("ops").exists());
         assert!(mod_dir.join("scripts").exists());
+
+        let op_crate = mod_dir.join("ops").join("op_example");
+        let cargo_toml: toml::Value = toml::from_str(&fs::read_to_string(op_crate.join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!(cargo_toml["package"]["name"].as_str(), Some("op_example"));
+        assert_eq!(cargo_toml["lib"]["crate-type"][0].as_str(), Some("cdylib"));
+        assert!(fs::read_to_string(op_crate.join("src").join("lib.rs")).unwrap().contains("colony_op_process"));
+    }
+
+    #[test]
+    fn test_build_records_op_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        create_new_mod("com.test.mymod", temp_dir.path()).unwrap();
+        let mod_dir = temp_dir.path().join("com.test.mymod");
+
+        // A prebuilt op with no crate is hashed as-is
+        fs::remove_dir_all(mod_dir.join("ops").join("op_example")).unwrap();
+        fs::write(mod_dir.join("ops").join("Op_Example.wasm"), b"\0asm\x01\0\0\0").unwrap();
+        let built = op_crate::build_ops(&mod_dir, op_crate::DEFAULT_TARGET, true).unwrap();
+        assert_eq!(built.len(), 1);
+        assert!(!built[0].compiled);
+
+        let manifest: ModManifest = toml::from_str(&fs::read_to_string(mod_dir.join("mod.toml")).unwrap()).unwrap();
+        assert_eq!(manifest.op_hashes.get("Op_Example"), Some(&built[0].hash));
+
+        fs::remove_file(mod_dir.join("ops").join("Op_Example.wasm")).unwrap();
+        assert!(op_crate::build_ops(&mod_dir, op_crate::DEFAULT_TARGET, true).is_err());
     }
 
     #[test]
@@ -853,6 +1009,45 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_write_component_bindings() {
+        let temp_dir = TempDir::new().unwrap();
+        create_new_mod("com.test.mymod", temp_dir.path()).unwrap();
+        let mod_dir = temp_dir.path().join("com.test.mymod");
+
+        write_component_bindings(&mod_dir).unwrap();
+        let wit = fs::read_to_string(mod_dir.join("wit").join("colony-op.wit")).unwrap();
+        assert!(wit.contains("package colony:op"));
+        assert!(mod_dir.join("ops").join("component_op.rs").exists());
+
+        assert!(write_component_bindings(temp_dir.path()).is_err()); // not a mod
+    }
+
+    #[test]
+    fn test_render_graph() {
+        let temp_dir = TempDir::new().unwrap();
+        let mod_dir = temp_dir.path().join("com.test.mymod");
+        fs::create_dir_all(&mod_dir).unwrap();
+        fs::write(mod_dir.join("tech.toml"), r#"
+            [[tech]]
+            id = "deep_scrub"
+            name = "Deep Scrub"
+            desc = "Builds on vanilla"
+            cost_pts = 40
+            requires = ["truth_beacon"]
+            grants = []
+        "#).unwrap();
+
+        let overlaid = render_graph(&mod_dir, GraphKind::Tech, false).unwrap();
+        assert!(overlaid.contains("\"truth_beacon\" -> \"deep_scrub\";"));
+        assert!(!overlaid.contains("(missing)"));
+        let alone = render_graph(&mod_dir, GraphKind::Tech, true).unwrap();
+        assert!(alone.contains("\"truth_beacon\" [label=\"truth_beacon (missing)\""));
+
+        assert!(render_graph(&mod_dir, GraphKind::Events, false).unwrap().starts_with("digraph events {"));
+        assert!(render_graph(&temp_dir.path().join("nope"), GraphKind::Tech, false).is_err());
+    }
+
     #[test]
     fn test_generate_docs() {
         let temp_dir = TempDir::new().unwrap();
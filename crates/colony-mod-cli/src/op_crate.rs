@@ -0,0 +1,186 @@
+use colony_modsdk::ModManifest;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::fs;
+use anyhow::Result;
+
+/// Target the legacy pointer ABI ops are built for
+pub const DEFAULT_TARGET: &str = "wasm32-unknown-unknown";
+
+/// A compiled op copied into the mod's ops/ directory
+#[derive(Debug, Clone)]
+pub struct BuiltOp {
+    pub op_name: String,
+    pub wasm_path: PathBuf,
+    pub hash: String, // sha256 of the .wasm, recorded in mod.toml
+    pub compiled: bool, // false for a prebuilt .wasm with no crate next to it
+}
+
+/// Crate name for an op, e.g. "Op_AdaptiveFft" -> "op_adaptivefft"
+pub fn crate_name(op_name: &str) -> String {
+    op_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+pub fn crate_dir(mod_dir: &Path, op_name: &str) -> PathBuf {
+    mod_dir.join("ops").join(crate_name(op_name))
+}
+
+/// Write a cdylib crate for an op under ops/<crate_name>; leaves an existing crate alone
+pub fn scaffold_op_crate(mod_dir: &Path, op_name: &str) -> Result<PathBuf> {
+    let dir = crate_dir(mod_dir, op_name);
+    if dir.join("Cargo.toml").exists() {
+        return Ok(dir);
+    }
+    let name = crate_name(op_name);
+    fs::create_dir_all(dir.join("src"))?;
+    fs::create_dir_all(dir.join(".cargo"))?;
+
+    let cargo_toml = format!(r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[lib]
+crate-type = ["cdylib"]
+
+[profile.release]
+opt-level = "s"
+lto = true
+panic = "abort"
+
+# Built on its own by `colony-mod build`, not as part of an enclosing workspace
+[workspace]
+"#);
+    fs::write(dir.join("Cargo.toml"), cargo_toml)?;
+
+    let cargo_config = format!("[build]\ntarget = \"{}\"\n", DEFAULT_TARGET);
+    fs::write(dir.join(".cargo").join("config.toml"), cargo_config)?;
+
+    let lib_rs = format!(r#"//! {op_name}: a Colony Simulator WASM op (legacy pointer ABI, see colony_modsdk::abi)
+
+/// Mirror of `colony_modsdk::abi::OpCtx`
+#[repr(C)]
+pub struct OpCtx {{
+    pub handle_id: u64,
+    pub op_id: u64,
+    pub tick: u64,
+    pub seed: u64,
+    pub fuel: u64,
+    pub memory_limit: usize,
+}}
+
+// Return codes, see colony_modsdk::abi::WasmReturnCode
+const SUCCESS: i32 = 0;
+const RESOURCE_EXHAUSTION: i32 = 4;
+const INVALID_INPUT: i32 = 5;
+
+#[link(wasm_import_module = "colony")]
+extern "C" {{
+    /// Report a per-job measurement; Black Swan triggers see it as "<mod_id>:<name>"
+    fn report_metric(name_ptr: *const u8, name_len: i32, value: f64) -> i32;
+}}
+
+#[no_mangle]
+pub extern "C" fn colony_op_init(_ctx: *mut OpCtx) -> i32 {{
+    SUCCESS
+}}
+
+/// Copies the input to the output; replace with your op's processing
+///
+/// # Safety
+/// The host passes buffers that live in this module's memory for the length of the call
+#[no_mangle]
+pub unsafe extern "C" fn colony_op_process(
+    _ctx: *mut OpCtx,
+    in_ptr: *const u8,
+    in_len: usize,
+    out_ptr: *mut u8,
+    out_cap: usize,
+    _meta_ptr: *const u8,
+    _meta_len: usize,
+) -> i32 {{
+    if in_ptr.is_null() || out_ptr.is_null() {{
+        return INVALID_INPUT;
+    }}
+    if in_len > out_cap {{
+        return RESOURCE_EXHAUSTION;
+    }}
+    let input = core::slice::from_raw_parts(in_ptr, in_len);
+    let output = core::slice::from_raw_parts_mut(out_ptr, out_cap);
+    output[..in_len].copy_from_slice(input);
+
+    let name = "bytes_in";
+    report_metric(name.as_ptr(), name.len() as i32, in_len as f64);
+    SUCCESS
+}}
+
+#[no_mangle]
+pub extern "C" fn colony_op_end(_ctx: *mut OpCtx) -> i32 {{
+    SUCCESS
+}}
+"#);
+    fs::write(dir.join("src").join("lib.rs"), lib_rs)?;
+    fs::write(dir.join(".gitignore"), "/target\n")?;
+
+    Ok(dir)
+}
+
+pub fn hash_file(path: &Path) -> Result<String> {
+    Ok(hex::encode(Sha256::digest(fs::read(path)?)))
+}
+
+/// Compile every op crate with cargo, copy the artifacts to ops/<OpName>.wasm and record their hashes
+pub fn build_ops(mod_dir: &Path, target: &str, release: bool) -> Result<Vec<BuiltOp>> {
+    let manifest_path = mod_dir.join("mod.toml");
+    if !manifest_path.exists() {
+        return Err(anyhow::anyhow!("No mod.toml in {:?}", mod_dir));
+    }
+    let mut manifest: ModManifest = toml::from_str(&fs::read_to_string(&manifest_path)?)?;
+
+    let mut built = Vec::new();
+    for op_name in &manifest.entrypoints.wasm_ops {
+        let wasm_path = mod_dir.join("ops").join(format!("{}.wasm", op_name));
+        let dir = crate_dir(mod_dir, op_name);
+        let compiled = dir.join("Cargo.toml").exists();
+
+        if compiled {
+            println!("Building {} ({})", op_name, target);
+            let target_dir = dir.join("target");
+            let mut cargo = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+            cargo.arg("build")
+                .arg("--manifest-path").arg(dir.join("Cargo.toml"))
+                .arg("--target").arg(target)
+                .arg("--target-dir").arg(&target_dir);
+            if release {
+                cargo.arg("--release");
+            }
+            let status = cargo.status()
+                .map_err(|e| anyhow::anyhow!("Failed to run cargo: {}", e))?;
+            if !status.success() {
+                return Err(anyhow::anyhow!("cargo build failed for {} ({})", op_name, status));
+            }
+
+            let profile = if release { "release" } else { "debug" };
+            let artifact = target_dir.join(target).join(profile).join(format!("{}.wasm", crate_name(op_name)));
+            fs::copy(&artifact, &wasm_path)
+                .map_err(|e| anyhow::anyhow!("Missing build output {:?}: {}", artifact, e))?;
+        } else if !wasm_path.exists() {
+            return Err(anyhow::anyhow!("No crate at {:?} and no prebuilt {:?} for op {}", dir, wasm_path, op_name));
+        }
+
+        let hash = hash_file(&wasm_path)?;
+        manifest.op_hashes.insert(op_name.clone(), hash.clone());
+        built.push(BuiltOp { op_name: op_name.clone(), wasm_path, hash, compiled });
+    }
+
+    // Ops dropped from the manifest shouldn't keep stale hashes
+    let ops = manifest.entrypoints.wasm_ops.clone();
+    manifest.op_hashes.retain(|name, _| ops.contains(name));
+    fs::write(&manifest_path, toml::to_string_pretty(&manifest)?)?;
+
+    Ok(built)
+}
@@ -1,8 +1,10 @@
 pub mod loader;
 pub mod schemas;
+pub mod namespace;
 
 pub use loader::*;
 pub use schemas::*;
+pub use namespace::*;
 
 use std::path::Path;
 use thiserror::Error;
@@ -15,6 +17,12 @@ pub enum ModError {
     Toml(#[from] toml::de::Error),
     #[error("Invalid mod structure: {0}")]
     InvalidStructure(String),
+    #[error("Duplicate content id: {0}")]
+    DuplicateId(String),
+    #[error("Unknown content id: {0}")]
+    UnknownId(String),
+    #[error("Content id '{id}' is defined by several mods ({candidates:?}); use a qualified id")]
+    AmbiguousId { id: String, candidates: Vec<String> },
 }
 
 pub struct ModLoader {
@@ -35,30 +43,33 @@ impl ModLoader {
             return Err(ModError::InvalidStructure(format!("Mod '{}' not found", mod_name)));
         }
 
-        let mut content = ModContent::default();
-
-        // Load pipelines
-        let pipelines_path = mod_path.join("pipelines.toml");
-        if pipelines_path.exists() {
-            let pipelines_data = std::fs::read_to_string(&pipelines_path)?;
-            content.pipelines = toml::from_str(&pipelines_data)?;
-        }
+        ModContent::load_dir(&mod_path).map_err(|e| ModError::InvalidStructure(format!("{:#}", e)))
+    }
 
-        // Load events
-        let events_path = mod_path.join("events.toml");
-        if events_path.exists() {
-            let events_data = std::fs::read_to_string(&events_path)?;
-            content.events = toml::from_str(&events_data)?;
+    /// Mod id from mod.toml, or the directory name for mods without a manifest
+    pub fn mod_id(&self, mod_name: &str) -> Result<String, ModError> {
+        let manifest_path = self.mods_path.join(mod_name).join("mod.toml");
+        if !manifest_path.exists() {
+            return Ok(mod_name.to_string());
         }
+        let manifest: toml::Value = toml::from_str(&std::fs::read_to_string(&manifest_path)?)?;
+        manifest.get("id")
+            .and_then(|id| id.as_str())
+            .map(|id| id.to_string())
+            .ok_or_else(|| ModError::InvalidStructure(format!("{:?} has no id", manifest_path)))
+    }
 
-        // Load tech
-        let tech_path = mod_path.join("tech.toml");
-        if tech_path.exists() {
-            let tech_data = std::fs::read_to_string(&tech_path)?;
-            content.tech = toml::from_str(&tech_data)?;
+    /// Load every mod into one registry, each under its own namespace
+    pub fn load_all(&self) -> Result<ContentRegistry, ModError> {
+        let mut mods = self.list_mods()?;
+        mods.sort();
+        let mut registry = ContentRegistry::new();
+        for mod_name in mods {
+            let content = self.load_mod(&mod_name)?;
+            registry.add_mod(&self.mod_id(&mod_name)?, content)?;
         }
-
-        Ok(content)
+        registry.resolve_references()?;
+        Ok(registry)
     }
 
     pub fn list_mods(&self) -> Result<Vec<String>, ModError> {
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use crate::{BlackSwanDef, ModContent, ModError, PipelineDef, TechNode};
+
+/// Separates a mod id from a content id, e.g. "com.example.packetalchemy:adaptive_fft"
+pub const NAMESPACE_SEP: char = ':';
+
+/// Prefix a content id with its mod id; ids that are already qualified are left alone
+pub fn qualify(mod_id: &str, id: &str) -> String {
+    if split_qualified(id).is_some() {
+        id.to_string()
+    } else {
+        format!("{}{}{}", mod_id, NAMESPACE_SEP, id)
+    }
+}
+
+/// Split "mod_id:id" into its parts; None for a bare id
+pub fn split_qualified(id: &str) -> Option<(&str, &str)> {
+    id.rsplit_once(NAMESPACE_SEP)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ContentKind {
+    Pipeline,
+    Event,
+    Tech,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Alias {
+    Unique(String),
+    Ambiguous(Vec<String>), // several mods define this bare id; it must be qualified or pinned
+    Pinned(String),         // set explicitly, wins over what mods define
+}
+
+/// Content from every loaded mod, keyed by qualified id, with bare ids kept as aliases
+#[derive(Debug, Clone, Default)]
+pub struct ContentRegistry {
+    pub pipelines: BTreeMap<String, PipelineDef>,
+    pub events: BTreeMap<String, BlackSwanDef>,
+    pub tech: BTreeMap<String, TechNode>,
+    aliases: BTreeMap<(ContentKind, String), Alias>,
+}
+
+impl ContentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a mod's content under its namespace; a mod defining the same id twice is an error
+    pub fn add_mod(&mut self, mod_id: &str, content: ModContent) -> Result<(), ModError> {
+        for mut pipeline in content.pipelines {
+            let id = self.register(ContentKind::Pipeline, mod_id, &pipeline.id)?;
+            pipeline.id = id.clone();
+            self.pipelines.insert(id, pipeline);
+        }
+        for mut event in content.black_swans {
+            let id = self.register(ContentKind::Event, mod_id, &event.id)?;
+            event.id = id.clone();
+            self.events.insert(id, event);
+        }
+        for mut tech in content.tech {
+            let id = self.register(ContentKind::Tech, mod_id, &tech.id)?;
+            tech.id = id.clone();
+            self.tech.insert(id, tech);
+        }
+        Ok(())
+    }
+
+    fn contains(&self, kind: ContentKind, id: &str) -> bool {
+        match kind {
+            ContentKind::Pipeline => self.pipelines.contains_key(id),
+            ContentKind::Event => self.events.contains_key(id),
+            ContentKind::Tech => self.tech.contains_key(id),
+        }
+    }
+
+    fn register(&mut self, kind: ContentKind, mod_id: &str, id: &str) -> Result<String, ModError> {
+        let qualified = qualify(mod_id, id);
+        if self.contains(kind, &qualified) {
+            return Err(ModError::DuplicateId(qualified));
+        }
+        let (_, bare) = split_qualified(&qualified).unwrap_or((mod_id, id));
+        let key = (kind, bare.to_string());
+        let alias = match self.aliases.remove(&key) {
+            None => Alias::Unique(qualified.clone()),
+            Some(Alias::Unique(existing)) => {
+                eprintln!("Mod content '{}' collides with '{}'; refer to it by its qualified id", qualified, existing);
+                Alias::Ambiguous(vec![existing, qualified.clone()])
+            }
+            Some(Alias::Ambiguous(mut ids)) => {
+                ids.push(qualified.clone());
+                Alias::Ambiguous(ids)
+            }
+            Some(pinned @ Alias::Pinned(_)) => pinned,
+        };
+        self.aliases.insert(key, alias);
+        Ok(qualified)
+    }
+
+    /// Point a bare id at one mod's content, e.g. to settle a collision
+    pub fn pin_alias(&mut self, kind: ContentKind, alias: &str, target: &str) -> Result<(), ModError> {
+        if !self.contains(kind, target) {
+            return Err(ModError::UnknownId(target.to_string()));
+        }
+        self.aliases.insert((kind, alias.to_string()), Alias::Pinned(target.to_string()));
+        Ok(())
+    }
+
+    /// Resolve a reference to a qualified id. Bare ids look in `from_mod` first, then at the aliases
+    pub fn resolve(&self, kind: ContentKind, id: &str, from_mod: Option<&str>) -> Result<String, ModError> {
+        if split_qualified(id).is_some() {
+            return match self.contains(kind, id) {
+                true => Ok(id.to_string()),
+                false => Err(ModError::UnknownId(id.to_string())),
+            };
+        }
+        if let Some(mod_id) = from_mod {
+            let own = qualify(mod_id, id);
+            if self.contains(kind, &own) {
+                return Ok(own);
+            }
+        }
+        match self.aliases.get(&(kind, id.to_string())) {
+            Some(Alias::Unique(target)) | Some(Alias::Pinned(target)) => Ok(target.clone()),
+            Some(Alias::Ambiguous(candidates)) => Err(ModError::AmbiguousId {
+                id: id.to_string(),
+                candidates: candidates.clone(),
+            }),
+            None => Err(ModError::UnknownId(id.to_string())),
+        }
+    }
+
+    /// Qualify every tech prerequisite (`requires`); call once all mods are added
+    pub fn resolve_references(&mut self) -> Result<(), ModError> {
+        let mut resolved = Vec::new();
+        for (id, tech) in &self.tech {
+            let from_mod = split_qualified(id).map(|(mod_id, _)| mod_id);
+            let prerequisites = tech.requires.iter()
+                .map(|p| self.resolve(ContentKind::Tech, p, from_mod))
+                .collect::<Result<Vec<_>, _>>()?;
+            resolved.push((id.clone(), prerequisites));
+        }
+        for (id, prerequisites) in resolved {
+            if let Some(tech) = self.tech.get_mut(&id) {
+                tech.requires = prerequisites;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tech(id: &str, requires: &[&str]) -> TechNode {
+        TechNode {
+            id: id.to_string(),
+            name: id.to_string(),
+            desc: String::new(),
+            cost_pts: 10,
+            requires: requires.iter().map(|p| p.to_string()).collect(),
+            grants: Vec::new(),
+        }
+    }
+
+    fn content(tech_defs: Vec<TechNode>) -> ModContent {
+        ModContent { tech: tech_defs, ..Default::default() }
+    }
+
+    #[test]
+    fn test_content_is_namespaced_and_bare_ids_resolve() {
+        let mut registry = ContentRegistry::new();
+        registry.add_mod("vanilla", content(vec![tech("truth_beacon", &[])])).unwrap();
+        registry.add_mod("com.example.packetalchemy", content(vec![tech("adaptive_fft", &["truth_beacon"])])).unwrap();
+        registry.resolve_references().unwrap();
+
+        let fft = &registry.tech["com.example.packetalchemy:adaptive_fft"];
+        assert_eq!(fft.id, "com.example.packetalchemy:adaptive_fft");
+        assert_eq!(fft.requires, vec!["vanilla:truth_beacon".to_string()]);
+        assert_eq!(
+            registry.resolve(ContentKind::Tech, "adaptive_fft", None).unwrap(),
+            "com.example.packetalchemy:adaptive_fft"
+        );
+        assert!(matches!(registry.resolve(ContentKind::Pipeline, "adaptive_fft", None), Err(ModError::UnknownId(_))));
+    }
+
+    #[test]
+    fn test_collisions_are_reported_not_overwritten() {
+        let mut registry = ContentRegistry::new();
+        registry.add_mod("com.a", content(vec![tech("overclock", &[])])).unwrap();
+        registry.add_mod("com.b", content(vec![tech("overclock", &[])])).unwrap();
+        assert_eq!(registry.tech.len(), 2);
+
+        assert!(matches!(
+            registry.resolve(ContentKind::Tech, "overclock", None),
+            Err(ModError::AmbiguousId { .. })
+        ));
+        // Each mod still sees its own, and a pinned alias settles it for everyone else
+        assert_eq!(registry.resolve(ContentKind::Tech, "overclock", Some("com.b")).unwrap(), "com.b:overclock");
+        registry.pin_alias(ContentKind::Tech, "overclock", "com.a:overclock").unwrap();
+        assert_eq!(registry.resolve(ContentKind::Tech, "overclock", None).unwrap(), "com.a:overclock");
+
+        assert!(matches!(
+            registry.add_mod("com.a", content(vec![tech("overclock", &[])])),
+            Err(ModError::DuplicateId(_))
+        ));
+    }
+}
@@ -12,12 +12,54 @@ pub const MAX_OUTPUT_SIZE: usize = 1024 * 1024;
 /// Maximum metadata size (64KB)
 pub const MAX_METADATA_SIZE: usize = 64 * 1024;
 
+/// Maximum length of a metric name reported through `report_metric`
+pub const MAX_METRIC_NAME_LEN: usize = 64;
+
+/// Maximum number of metrics one op call may report
+pub const MAX_METRICS_PER_CALL: usize = 16;
+
+/// Largest file a mod may read through the virtual filesystem (1MB)
+pub const MAX_VFS_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Largest mod directory the virtual filesystem will mount (64MB)
+pub const MAX_VFS_MOUNT_SIZE: u64 = 64 * 1024 * 1024;
+
 /// Default fuel limit per WASM op call
 pub const DEFAULT_FUEL_LIMIT: u64 = 5_000_000;
 
 /// Default memory limit for WASM modules (64MB)
 pub const DEFAULT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
 
+/// Export a scheduler module (`schedulers/<name>.wasm`) provides:
+/// `priority(deadline_ms: i64, cost_ms: i32, payload_sz: i32, qos: i32) -> i64`. Jobs with the
+/// lowest priority run first; qos is 0 Throughput, 1 Latency, 2 Balanced.
+pub const SCHEDULER_PRIORITY_EXPORT: &str = "priority";
+
+/// WIT world for component-model ops (`colony:op`)
+pub const COLONY_OP_WIT: &str = include_str!("../wit/colony-op.wit");
+
+/// How an op's module talks to the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WasmAbi {
+    /// Core module exporting `colony_op_*` over raw pointers into linear memory
+    #[default]
+    Legacy,
+    /// Component implementing the `colony:op` WIT world with typed records
+    Component,
+}
+
+impl WasmAbi {
+    /// Tell a component from a core module by its preamble
+    pub fn detect(wasm_bytes: &[u8]) -> Option<Self> {
+        match wasm_bytes.get(..8)? {
+            [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00] => Some(Self::Legacy),
+            [0x00, 0x61, 0x73, 0x6d, _, _, 0x01, 0x00] => Some(Self::Component),
+            _ => None,
+        }
+    }
+}
+
 /// Return codes for WASM operations
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -115,6 +157,8 @@ pub struct WasmHostFunctions {
     pub log: extern "C" fn(level: i32, msg_ptr: *const u8, msg_len: usize) -> i32,
     /// Get a metric value
     pub get_metric: extern "C" fn(name_ptr: *const u8, name_len: usize) -> f64,
+    /// Report a per-job measurement (e.g. an anomaly score); Black Swan triggers see it as "<mod_id>:<name>"
+    pub report_metric: extern "C" fn(name_ptr: *const u8, name_len: usize, value: f64) -> i32,
     /// Enqueue a job
     pub enqueue_job: extern "C" fn(pipeline_ptr: *const u8, pipeline_len: usize, payload_ptr: *const u8, payload_len: usize) -> i32,
 }
@@ -303,6 +347,14 @@ mod tests {
         assert_eq!(result.execution_time_ms, 10);
     }
 
+    #[test]
+    fn test_wasm_abi_detect() {
+        assert_eq!(WasmAbi::detect(b"\0asm\x01\0\0\0"), Some(WasmAbi::Legacy));
+        assert_eq!(WasmAbi::detect(b"\0asm\x0d\0\x01\0"), Some(WasmAbi::Component));
+        assert_eq!(WasmAbi::detect(b"\0asm"), None);
+        assert!(COLONY_OP_WIT.contains("export process"));
+    }
+
     #[test]
     fn test_wasm_execution_env() {
         let env = WasmExecutionEnv::default();
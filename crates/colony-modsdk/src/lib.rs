@@ -1,5 +1,7 @@
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+pub mod abi;
 
 /// Mod manifest defining the mod's metadata, entrypoints, and capabilities
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +15,8 @@ pub struct ModManifest {
     pub capabilities: Capabilities,
     pub signature: Option<String>, // base64, optional unsigned for dev
     pub requires: Option<Vec<String>>, // mod dependencies
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub op_hashes: BTreeMap<String, String>, // op name -> sha256 of ops/<op>.wasm, written by `colony-mod build`
 }
 
 /// Entrypoints defining where the mod's code and content can be found
@@ -24,6 +28,9 @@ pub struct Entrypoints {
     pub blackswans: Option<String>, // path to events.toml
     pub tech: Option<String>,       // path to tech.toml
     pub scenarios: Option<String>,  // path to scenarios.toml
+    pub achievements: Option<String>, // path to achievements.toml
+    #[serde(default)]
+    pub schedulers: Vec<String>,    // e.g., ["LeastSlack"], each a schedulers/<name>.wasm; needs the scheduler capability
 }
 
 /// Capabilities defining what the mod is allowed to do
@@ -36,6 +43,10 @@ pub struct Capabilities {
     pub log_debug: bool,    // write debug logs
     pub modify_tunables: bool, // modify system tunables
     pub trigger_events: bool, // trigger Black Swan events
+    #[serde(default)]
+    pub fs_read: bool,      // read the mod's own bundled files through the virtual filesystem
+    #[serde(default)]
+    pub scheduler: bool,    // register scheduling policies dispatch can be switched to
 }
 
 /// Specification for a WASM operation
@@ -48,6 +59,8 @@ pub struct WasmOpSpec {
     pub vram_hint_mb: f32,
     pub bandwidth_hint_mb: f32,
     pub description: Option<String>,
+    #[serde(default)]
+    pub abi: abi::WasmAbi,
 }
 
 /// Lua event hook specification
@@ -127,7 +140,7 @@ pub struct ShadowWorldResult {
 }
 
 /// KPI deltas from shadow world simulation
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct KpiDeltas {
     pub deadline_hit_rate_change: f32,
     pub power_draw_change: f32,
@@ -276,6 +289,7 @@ impl Default for ModManifest {
             capabilities: Capabilities::default(),
             signature: None,
             requires: None,
+            op_hashes: BTreeMap::new(),
         }
     }
 }
@@ -6,7 +6,7 @@ pub use black_swan::*;
 pub use thermal::*;
 pub use corruption::*;
 
-use colony_core::{Worker, Workyard, Colony};
+use colony_core::{WorkerSkills, Workyard, Colony};
 use rand::Rng;
 
 pub fn thermal_throttle(heat: f32, cap: f32) -> f32 {
@@ -32,17 +32,18 @@ pub fn bandwidth_factor(bandwidth_util: f32) -> f32 {
     }
 }
 
-pub fn skill_mult(worker: &Worker, op: &colony_core::Op) -> f32 {
+pub fn skill_mult(skills: &WorkerSkills, op: &colony_core::Op) -> f32 {
     match op {
-        colony_core::Op::Decode | colony_core::Op::Fft | colony_core::Op::Kalman => worker.skill_cpu,
-        colony_core::Op::Yolo => worker.skill_gpu,
-        colony_core::Op::UdpDemux | colony_core::Op::TcpSessionize | colony_core::Op::HttpParse | colony_core::Op::CanParse | colony_core::Op::ModbusMap => worker.skill_io,
-        colony_core::Op::Crc => (worker.skill_cpu + worker.skill_io) / 2.0,
-        colony_core::Op::Export | colony_core::Op::HttpExport => worker.skill_io,
-        colony_core::Op::MaintenanceCool => worker.skill_cpu,
-        colony_core::Op::GpuPreprocess | colony_core::Op::GpuExport => worker.skill_gpu,
-        colony_core::Op::DynamicWasm { .. } => worker.skill_cpu, // Default to CPU for dynamic WASM ops
-        colony_core::Op::DynamicLua { .. } => worker.skill_cpu, // Default to CPU for dynamic Lua ops
+        colony_core::Op::Decode | colony_core::Op::Fft | colony_core::Op::Kalman => skills.cpu,
+        colony_core::Op::Yolo => skills.gpu,
+        colony_core::Op::UdpDemux | colony_core::Op::TcpSessionize | colony_core::Op::HttpParse | colony_core::Op::CanParse | colony_core::Op::ModbusMap | colony_core::Op::MqttParse | colony_core::Op::GrpcParse => skills.io,
+        colony_core::Op::Crc => (skills.cpu + skills.io) / 2.0,
+        colony_core::Op::Export | colony_core::Op::HttpExport | colony_core::Op::ArchiveWrite => skills.io,
+        colony_core::Op::MaintenanceCool => skills.cpu,
+        colony_core::Op::ReplaceDrives => skills.io,
+        colony_core::Op::GpuPreprocess | colony_core::Op::GpuExport => skills.gpu,
+        colony_core::Op::DynamicWasm { .. } => skills.cpu, // Default to CPU for dynamic WASM ops
+        colony_core::Op::DynamicLua { .. } => skills.cpu, // Default to CPU for dynamic Lua ops
     }
 }
 
@@ -57,10 +58,14 @@ pub fn base_speed(op: &colony_core::Op) -> f32 {
         colony_core::Op::UdpDemux => 2.5,
         colony_core::Op::TcpSessionize => 1.5,
         colony_core::Op::ModbusMap => 2.0,
+        colony_core::Op::MqttParse => 2.5,
+        colony_core::Op::GrpcParse => 1.4,
         colony_core::Op::HttpParse => 1.2,
         colony_core::Op::Export => 1.5,
         colony_core::Op::HttpExport => 1.3,
+        colony_core::Op::ArchiveWrite => 0.9,
         colony_core::Op::MaintenanceCool => 0.5,
+        colony_core::Op::ReplaceDrives => 0.2,
         colony_core::Op::GpuPreprocess => 0.4,
         colony_core::Op::GpuExport => 0.6,
         colony_core::Op::DynamicWasm { .. } => 1.0, // Default speed for dynamic WASM ops
@@ -68,12 +73,12 @@ pub fn base_speed(op: &colony_core::Op) -> f32 {
     }
 }
 
-pub fn roll_fault(env: &SimulationEnv, worker: &Worker, op: &colony_core::Op) -> bool {
+pub fn roll_fault(env: &SimulationEnv, skills: &WorkerSkills, op: &colony_core::Op) -> bool {
     let mut rng = rand::thread_rng();
     let base_fault_rate = 0.001; // 0.1% base fault rate
     let corruption_mult = 1.0 + env.corruption_field * 2.0;
     let thermal_mult = if env.heat > env.heat_cap * 0.8 { 2.0 } else { 1.0 };
-    let worker_mult = 1.0 - worker.discipline * 0.5;
+    let worker_mult = 1.0 - skills.discipline * 0.5;
     
     let fault_rate = base_fault_rate * corruption_mult * thermal_mult * worker_mult;
     rng.gen::<f32>() < fault_rate
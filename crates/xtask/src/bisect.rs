@@ -0,0 +1,173 @@
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use crate::headless::{run_headless_spec, SpecOutput};
+
+pub const BISECT_WORK_DIR: &str = "target/bisect";
+
+/// One line of a trace written by `colony-headless --run-spec` with `export.tick_trace` set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    pub tick: u64,
+    pub system: String,
+    pub state: u64,
+    pub touched: Vec<ResourceChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceChange {
+    pub resource: String,
+    pub before: Option<u64>,
+    pub after: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    /// Both runs were in step until this system left different state behind
+    State { tick: u64, system: String, resources: Vec<String> },
+    /// The runs traced different systems at the same point, e.g. a set was disabled in one
+    Schedule { tick: u64, a: String, b: String },
+    /// One run stopped before the other; the records they share match
+    Length { a: usize, b: usize },
+}
+
+pub fn load_trace(path: &Path) -> Result<Vec<TraceRecord>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Can't read trace {}: {}", path.display(), e))?;
+    content.lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| serde_json::from_str(line)
+            .map_err(|e| anyhow::anyhow!("{} line {}: {}", path.display(), i + 1, e)))
+        .collect()
+}
+
+/// The first trace point where the runs differ. Up to there the tracked state matched, so a
+/// resource one run didn't touch still holds the value the other run saw as `before`.
+pub fn first_divergence(a: &[TraceRecord], b: &[TraceRecord]) -> Option<Divergence> {
+    for (ra, rb) in a.iter().zip(b) {
+        if (ra.tick, &ra.system) != (rb.tick, &rb.system) {
+            return Some(Divergence::Schedule { tick: ra.tick.min(rb.tick), a: ra.system.clone(), b: rb.system.clone() });
+        }
+        if ra.state == rb.state {
+            continue;
+        }
+        let names: BTreeSet<&String> = ra.touched.iter().chain(&rb.touched).map(|c| &c.resource).collect();
+        let after = |own: &TraceRecord, other: &TraceRecord, name: &String| {
+            match own.touched.iter().find(|c| &c.resource == name) {
+                Some(change) => change.after,
+                None => other.touched.iter().find(|c| &c.resource == name).and_then(|c| c.before),
+            }
+        };
+        let resources = names.into_iter()
+            .filter(|name| after(ra, rb, name) != after(rb, ra, name))
+            .cloned()
+            .collect();
+        return Some(Divergence::State { tick: ra.tick, system: ra.system.clone(), resources });
+    }
+    (a.len() != b.len()).then_some(Divergence::Length { a: a.len(), b: b.len() })
+}
+
+/// Run a spec through the headless binary with tick tracing on, writing the trace under target/bisect
+fn run_traced(spec: &Path, name: &str) -> Result<PathBuf> {
+    let trace_path = PathBuf::from(BISECT_WORK_DIR).join(format!("{}.trace.jsonl", name));
+    run_headless_spec(spec, &trace_path, SpecOutput::TickTrace, &[])?;
+    Ok(trace_path)
+}
+
+fn report(divergence: Option<&Divergence>) -> bool {
+    match divergence {
+        None => {
+            println!("  ✅ Traces match at every system on every tick");
+            true
+        }
+        Some(Divergence::State { tick, system, resources }) => {
+            println!("  ❌ First divergence: tick {} in {}", tick, system);
+            println!("     resources left different: {}", if resources.is_empty() { "(none tracked)".to_string() } else { resources.join(", ") });
+            if system == "tick_start" {
+                println!("     tick_start covers changes made between ticks: spec commands, config reloads and untraced systems");
+            }
+            false
+        }
+        Some(Divergence::Schedule { tick, a, b }) => {
+            println!("  ❌ Runs traced different systems at tick {}: {} vs {}", tick, a, b);
+            false
+        }
+        Some(Divergence::Length { a, b }) => {
+            println!("  ❌ Traces agree but one run stopped early ({} vs {} records)", a, b);
+            false
+        }
+    }
+}
+
+/// Compare two traces recorded elsewhere, e.g. the same spec on two commits
+pub fn bisect_traces(a: &Path, b: &Path) -> Result<bool> {
+    println!("🔎 Bisecting {} against {}", a.display(), b.display());
+    let (a, b) = (load_trace(a)?, load_trace(b)?);
+    Ok(report(first_divergence(&a, &b).as_ref()))
+}
+
+/// Run a spec twice on this build; any divergence is nondeterminism
+pub fn bisect_spec(spec: &Path) -> Result<bool> {
+    println!("🔎 Running {} twice with tick tracing", spec.display());
+    let a = load_trace(&run_traced(spec, "a")?)?;
+    let b = load_trace(&run_traced(spec, "b")?)?;
+    println!("  {} trace points per run", a.len());
+    Ok(report(first_divergence(&a, &b).as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(tick: u64, system: &str, state: u64, touched: &[(&str, u64, u64)]) -> TraceRecord {
+        TraceRecord {
+            tick,
+            system: system.into(),
+            state,
+            touched: touched.iter()
+                .map(|(name, before, after)| ResourceChange { resource: name.to_string(), before: Some(*before), after: Some(*after) })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_finds_first_system_and_resources() {
+        let a = vec![
+            record(0, "tick_start", 1, &[]),
+            record(0, "heat_system", 2, &[("Colony", 10, 11)]),
+            record(0, "dispatch_system", 3, &[("JobQueue", 20, 21), ("FaultKpi", 30, 31)]),
+            record(1, "tick_start", 4, &[]),
+        ];
+        let mut b = a.clone();
+        b[2] = record(0, "dispatch_system", 9, &[("JobQueue", 20, 22), ("Workers", 40, 41)]);
+        b[3].state = 8;
+
+        assert_eq!(first_divergence(&a, &a), None);
+        assert_eq!(first_divergence(&a, &b), Some(Divergence::State {
+            tick: 0,
+            system: "dispatch_system".into(),
+            resources: vec!["FaultKpi".into(), "JobQueue".into(), "Workers".into()],
+        }));
+    }
+
+    #[test]
+    fn test_schedule_and_length_mismatches() {
+        let a = vec![record(0, "tick_start", 1, &[]), record(0, "heat_system", 2, &[])];
+        let mut b = a.clone();
+        b[1].system = "corruption_system".into();
+        assert!(matches!(first_divergence(&a, &b), Some(Divergence::Schedule { tick: 0, .. })));
+        assert_eq!(first_divergence(&a, &a[..1]), Some(Divergence::Length { a: 2, b: 1 }));
+    }
+
+    #[test]
+    fn test_loads_json_lines() {
+        let path = std::env::temp_dir().join(format!("bisect_trace_{}.jsonl", std::process::id()));
+        std::fs::write(&path, "{\"tick\":0,\"system\":\"tick_start\",\"state\":7,\"touched\":[{\"resource\":\"Colony\",\"before\":null,\"after\":3}]}\n\n").unwrap();
+        let trace = load_trace(&path).unwrap();
+        assert_eq!(trace.len(), 1);
+        assert_eq!(trace[0].touched[0].before, None);
+        std::fs::remove_file(&path).ok();
+    }
+}
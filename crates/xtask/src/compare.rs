@@ -0,0 +1,43 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use anyhow::Result;
+use crate::headless::{run_headless_spec, SpecOutput};
+
+pub const COMPARE_WORK_DIR: &str = "target/compare";
+
+/// Run a spec through the headless binary and return where its summary was written
+fn run_summary(spec: &Path, name: &str) -> Result<PathBuf> {
+    let summary_path = PathBuf::from(COMPARE_WORK_DIR).join(format!("{}.json", name));
+    run_headless_spec(spec, &summary_path, SpecOutput::Summary, &[])?;
+    Ok(summary_path)
+}
+
+/// Diff two run summaries with `colony-headless compare-runs`, optionally saving the report
+pub fn compare_summaries(a: &Path, b: &Path, json: bool, out: Option<&Path>) -> Result<()> {
+    println!("📊 Comparing {} against {}", a.display(), b.display());
+    let mut cmd = Command::new("cargo");
+    cmd.args(["run", "--release", "-q", "-p", "colony-headless", "--", "compare-runs"]).arg(a).arg(b);
+    if json {
+        cmd.arg("--json");
+    }
+    let output = cmd.output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("Comparison failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let report = String::from_utf8_lossy(&output.stdout);
+    match out {
+        Some(path) => {
+            std::fs::write(path, report.as_bytes())?;
+            println!("  ✅ Report written to {}", path.display());
+        }
+        None => print!("{}", report),
+    }
+    Ok(())
+}
+
+/// Run two specs (e.g. before and after a balance change) and diff the runs
+pub fn compare_specs(a: &Path, b: &Path, json: bool, out: Option<&Path>) -> Result<()> {
+    println!("🏃 Running {} and {}", a.display(), b.display());
+    let (summary_a, summary_b) = (run_summary(a, "a")?, run_summary(b, "b")?);
+    compare_summaries(&summary_a, &summary_b, json, out)
+}
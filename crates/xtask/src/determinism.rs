@@ -0,0 +1,79 @@
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use crate::headless::{run_headless_spec, SpecOutput};
+
+pub const DETERMINISM_WORK_DIR: &str = "target/determinism";
+pub const DEFAULT_DETERMINISM_SPECS: &[&str] = &[
+    "fixtures/golden/first_light_chill_s42.toml",
+    "fixtures/golden/factory_horizon_nominal_s42.toml",
+];
+/// Compute pool sizes every spec must end identically on
+pub const DEFAULT_THREAD_COUNTS: &[usize] = &[1, 4, 16];
+
+/// Final state hash of one run of a spec on a given number of compute threads
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadRun {
+    pub threads: usize,
+    pub state_hash: String,
+}
+
+/// Thread counts whose hash differs from the first run's; empty means the contract holds
+pub fn mismatches(runs: &[ThreadRun]) -> Vec<usize> {
+    let Some(first) = runs.first() else { return Vec::new() };
+    runs.iter().filter(|run| run.state_hash != first.state_hash).map(|run| run.threads).collect()
+}
+
+/// Run a spec through the headless binary on `threads` compute threads and read back its state hash
+fn run_with_threads(spec: &Path, threads: usize) -> Result<ThreadRun> {
+    let name = spec.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let summary_path = PathBuf::from(DETERMINISM_WORK_DIR).join(format!("{}.t{}.summary.json", name, threads));
+    run_headless_spec(spec, &summary_path, SpecOutput::Summary, &["--compute-threads", &threads.to_string()])?;
+
+    let summary: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&summary_path)?)?;
+    let state_hash = summary["state_hash"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Run summary for {} has no state_hash", name))?
+        .to_string();
+    Ok(ThreadRun { threads, state_hash })
+}
+
+/// Run every spec once per thread count; false if any spec ends differently on any count
+pub fn check(specs: &[PathBuf], threads: &[usize]) -> Result<bool> {
+    println!("🧵 Checking determinism across {:?} compute threads", threads);
+
+    let mut all_ok = true;
+    for spec in specs {
+        let runs = threads.iter().map(|&n| run_with_threads(spec, n)).collect::<Result<Vec<_>>>()?;
+        let differing = mismatches(&runs);
+        if differing.is_empty() {
+            println!("  ✅ {}: {}", spec.display(), runs.first().map(|run| &run.state_hash[..16.min(run.state_hash.len())]).unwrap_or("-"));
+            continue;
+        }
+        all_ok = false;
+        println!("  ❌ {}: state hash changes with the thread count", spec.display());
+        for run in &runs {
+            println!("     {:>3} threads: {}", run.threads, run.state_hash);
+        }
+    }
+
+    if !all_ok {
+        println!("\nSystems touching the same data are running in varying order. Run `cargo test -p colony-core ambiguous` for the pairs, and `xtask bisect-tick` for the first tick that differs.");
+    }
+    Ok(all_ok)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(threads: usize, state_hash: &str) -> ThreadRun {
+        ThreadRun { threads, state_hash: state_hash.into() }
+    }
+
+    #[test]
+    fn test_mismatches_against_first_run() {
+        assert!(mismatches(&[]).is_empty());
+        assert!(mismatches(&[run(1, "aa"), run(4, "aa"), run(16, "aa")]).is_empty());
+        assert_eq!(mismatches(&[run(1, "aa"), run(4, "bb"), run(16, "aa")]), vec![4]);
+    }
+}
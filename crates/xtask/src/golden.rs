@@ -0,0 +1,251 @@
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use crate::headless::{run_headless_spec, SpecOutput};
+
+pub const GOLDEN_FORMAT_VERSION: u32 = 1;
+pub const DEFAULT_GOLDEN_DIR: &str = "fixtures/golden";
+
+/// Recorded KPI trace for one run spec, checked in next to the spec
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenTrace {
+    pub format_version: u32,
+    pub spec: String,
+    pub seed: u64,
+    pub verdict: String,
+    pub ticks_run: u64,
+    pub samples: Vec<GoldenSample>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenSample {
+    pub tick: u64,
+    pub kpis: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Drift {
+    pub tick: u64,
+    pub kpi: String,
+    pub expected: f64,
+    pub actual: f64,
+}
+
+impl GoldenTrace {
+    /// Build a trace from the summary JSON written by `colony-headless --run-spec`
+    pub fn from_summary(spec: &str, summary: &serde_json::Value) -> Result<Self> {
+        let samples = summary["samples"]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("Run summary has no samples"))?
+            .iter()
+            .map(|s| GoldenSample {
+                tick: s["tick"].as_u64().unwrap_or(0),
+                kpis: flatten_kpis(&s["kpis"]),
+            })
+            .collect();
+
+        Ok(Self {
+            format_version: GOLDEN_FORMAT_VERSION,
+            spec: spec.to_string(),
+            seed: summary["seed"].as_u64().unwrap_or(0),
+            verdict: summary["verdict"].as_str().unwrap_or("Incomplete").to_string(),
+            ticks_run: summary["ticks_run"].as_u64().unwrap_or(0),
+            samples,
+        })
+    }
+
+    /// Compare against a fresh trace; structural mismatches are errors, KPI drift is reported
+    pub fn compare(&self, actual: &GoldenTrace, tolerance: f64) -> Result<Vec<Drift>> {
+        if self.format_version != actual.format_version {
+            return Err(anyhow::anyhow!(
+                "Fixture format v{} does not match current v{}; re-record with `xtask golden record`",
+                self.format_version, actual.format_version
+            ));
+        }
+        if self.verdict != actual.verdict || self.ticks_run != actual.ticks_run {
+            return Err(anyhow::anyhow!(
+                "Run ended differently: expected {} after {} ticks, got {} after {} ticks",
+                self.verdict, self.ticks_run, actual.verdict, actual.ticks_run
+            ));
+        }
+        if self.samples.len() != actual.samples.len() {
+            return Err(anyhow::anyhow!(
+                "Sample count changed: expected {}, got {}",
+                self.samples.len(), actual.samples.len()
+            ));
+        }
+
+        let mut drifts = Vec::new();
+        for (expected, current) in self.samples.iter().zip(actual.samples.iter()) {
+            for (kpi, &expected_value) in &expected.kpis {
+                let actual_value = current.kpis.get(kpi).copied().unwrap_or(f64::NAN);
+                if !within_tolerance(expected_value, actual_value, tolerance) {
+                    drifts.push(Drift {
+                        tick: expected.tick,
+                        kpi: kpi.clone(),
+                        expected: expected_value,
+                        actual: actual_value,
+                    });
+                }
+            }
+        }
+        Ok(drifts)
+    }
+}
+
+/// Relative tolerance, with an absolute floor so values near zero don't flap
+fn within_tolerance(expected: f64, actual: f64, tolerance: f64) -> bool {
+    (expected - actual).abs() <= tolerance * expected.abs().max(1.0)
+}
+
+fn flatten_kpis(kpis: &serde_json::Value) -> BTreeMap<String, f64> {
+    let mut flat = BTreeMap::new();
+    if let Some(obj) = kpis.as_object() {
+        for (key, value) in obj {
+            match value {
+                serde_json::Value::Number(n) => {
+                    flat.insert(key.clone(), n.as_f64().unwrap_or(0.0));
+                }
+                serde_json::Value::Array(items) => {
+                    for (i, item) in items.iter().enumerate() {
+                        flat.insert(format!("{}[{}]", key, i), item.as_f64().unwrap_or(0.0));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    flat
+}
+
+fn list_specs(golden_dir: &Path, only: Option<&str>) -> Result<Vec<PathBuf>> {
+    let mut specs: Vec<PathBuf> = std::fs::read_dir(golden_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == "toml"))
+        .filter(|path| only.map_or(true, |name| spec_name(path) == name))
+        .collect();
+    specs.sort();
+    if specs.is_empty() {
+        return Err(anyhow::anyhow!("No golden run specs found in {}", golden_dir.display()));
+    }
+    Ok(specs)
+}
+
+fn spec_name(path: &Path) -> String {
+    path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn fixture_path(spec: &Path) -> PathBuf {
+    spec.with_extension("golden.json")
+}
+
+/// Run a spec through the headless binary with exports redirected under target/golden
+fn run_spec(spec: &Path) -> Result<GoldenTrace> {
+    let name = spec_name(spec);
+    let summary_path = PathBuf::from("target/golden").join(format!("{}.summary.json", name));
+    run_headless_spec(spec, &summary_path, SpecOutput::Summary, &[])?;
+
+    let summary: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&summary_path)?)?;
+    GoldenTrace::from_summary(&name, &summary)
+}
+
+pub fn record(golden_dir: &Path, only: Option<&str>) -> Result<()> {
+    println!("📼 Recording golden runs from {}", golden_dir.display());
+
+    for spec in list_specs(golden_dir, only)? {
+        let trace = run_spec(&spec)?;
+        let fixture = fixture_path(&spec);
+        std::fs::write(&fixture, serde_json::to_string_pretty(&trace)? + "\n")?;
+        println!("  ✅ {} -> {} ({} samples)", trace.spec, fixture.display(), trace.samples.len());
+    }
+
+    Ok(())
+}
+
+pub fn check(golden_dir: &Path, only: Option<&str>, tolerance: f64) -> Result<bool> {
+    println!("🎯 Checking golden runs in {} (tolerance {})", golden_dir.display(), tolerance);
+
+    let mut all_ok = true;
+    for spec in list_specs(golden_dir, only)? {
+        let name = spec_name(&spec);
+        let fixture = fixture_path(&spec);
+        if !fixture.exists() {
+            println!("  ❌ {}: missing fixture {}", name, fixture.display());
+            all_ok = false;
+            continue;
+        }
+
+        let expected: GoldenTrace = serde_json::from_str(&std::fs::read_to_string(&fixture)?)?;
+        let actual = run_spec(&spec)?;
+        match expected.compare(&actual, tolerance) {
+            Ok(drifts) if drifts.is_empty() => println!("  ✅ {}", name),
+            Ok(drifts) => {
+                all_ok = false;
+                println!("  ❌ {}: {} KPI values drifted", name, drifts.len());
+                for d in drifts.iter().take(10) {
+                    println!("     tick {} {}: expected {} got {}", d.tick, d.kpi, d.expected, d.actual);
+                }
+            }
+            Err(e) => {
+                all_ok = false;
+                println!("  ❌ {}: {}", name, e);
+            }
+        }
+    }
+
+    if !all_ok {
+        println!("\nSimulation behavior changed. If intended, run `cargo run -p xtask -- golden record` and commit the fixtures.");
+    }
+    Ok(all_ok)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(power: f64) -> serde_json::Value {
+        serde_json::json!({
+            "verdict": "Incomplete",
+            "ticks_run": 200,
+            "seed": 42,
+            "samples": [
+                { "tick": 100, "kpis": { "power_draw_kw": 500.0, "heat_levels": [40.0, 41.0] } },
+                { "tick": 200, "kpis": { "power_draw_kw": power, "heat_levels": [42.0, 43.0] } }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_trace_from_summary_flattens_vectors() {
+        let trace = GoldenTrace::from_summary("chill", &summary(500.0)).unwrap();
+        assert_eq!(trace.samples.len(), 2);
+        assert_eq!(trace.samples[0].kpis["heat_levels[1]"], 41.0);
+        assert_eq!(trace.seed, 42);
+    }
+
+    #[test]
+    fn test_compare_within_tolerance() {
+        let expected = GoldenTrace::from_summary("chill", &summary(500.0)).unwrap();
+        let actual = GoldenTrace::from_summary("chill", &summary(500.2)).unwrap();
+        assert!(expected.compare(&actual, 0.001).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compare_reports_drift() {
+        let expected = GoldenTrace::from_summary("chill", &summary(500.0)).unwrap();
+        let actual = GoldenTrace::from_summary("chill", &summary(560.0)).unwrap();
+        let drifts = expected.compare(&actual, 0.001).unwrap();
+        assert_eq!(drifts.len(), 1);
+        assert_eq!(drifts[0].kpi, "power_draw_kw");
+        assert_eq!(drifts[0].tick, 200);
+    }
+
+    #[test]
+    fn test_compare_rejects_different_outcome() {
+        let expected = GoldenTrace::from_summary("chill", &summary(500.0)).unwrap();
+        let mut actual = expected.clone();
+        actual.ticks_run = 150;
+        assert!(expected.compare(&actual, 0.001).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+use anyhow::Result;
+
+/// File exports a run spec can ask for; a helper run keeps exactly one
+const FILE_EXPORTS: &[&str] = &["summary_json", "kpi_csv", "tick_trace"];
+
+/// What a helper run of the headless binary writes to its `out` path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpecOutput {
+    Summary,   // export.summary_json
+    TickTrace, // export.tick_trace, on a build with the tick_trace feature
+}
+
+impl SpecOutput {
+    fn export_key(self) -> &'static str {
+        match self {
+            SpecOutput::Summary => "summary_json",
+            SpecOutput::TickTrace => "tick_trace",
+        }
+    }
+}
+
+/// Copy of a run spec whose only file export is `output`, written to `out`
+pub fn redirect_spec(spec: &str, output: SpecOutput, out: &Path) -> Result<String> {
+    let mut spec_value: toml::Value = toml::from_str(spec)?;
+    let table = spec_value
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("Run spec is not a TOML table"))?;
+    let export = table
+        .entry("export")
+        .or_insert_with(|| toml::Value::Table(toml::map::Map::new()));
+    if let Some(export) = export.as_table_mut() {
+        for key in FILE_EXPORTS {
+            export.remove(*key);
+        }
+        export.insert(output.export_key().to_string(), toml::Value::String(out.to_string_lossy().to_string()));
+    }
+    Ok(toml::to_string(&spec_value)?)
+}
+
+/// Run a spec through the headless binary with its export redirected to `out`, saving the
+/// rewritten spec next to it. `args` go to the binary, e.g. `--compute-threads 4`.
+pub fn run_headless_spec(spec: &Path, out: &Path, output: SpecOutput, args: &[&str]) -> Result<ExitStatus> {
+    if let Some(dir) = out.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let run_spec_path = out.with_extension("toml");
+    std::fs::write(&run_spec_path, redirect_spec(&std::fs::read_to_string(spec)?, output, out)?)?;
+
+    let mut cmd = Command::new("cargo");
+    cmd.args(["run", "--release", "-q", "-p", "colony-headless"]);
+    if output == SpecOutput::TickTrace {
+        cmd.args(["--features", "tick_trace"]);
+    }
+    let status = cmd.arg("--").args(args).arg("--run-spec").arg(&run_spec_path).status()?;
+    // 0-2 are victory/loss/incomplete, anything else means the run never happened
+    if !matches!(status.code(), Some(0..=2)) {
+        return Err(anyhow::anyhow!("Headless run of {} failed ({})", spec.display(), status));
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_keeps_only_the_redirected_export() {
+        let spec = "scenario = \"first_light_chill\"\n[export]\nkpi_csv = \"out.csv\"\nsample_every = 5\n";
+        let rewritten: toml::Value = toml::from_str(&redirect_spec(spec, SpecOutput::Summary, Path::new("target/compare/a.json")).unwrap()).unwrap();
+        let export = rewritten["export"].as_table().unwrap();
+        assert_eq!(export["summary_json"].as_str(), Some("target/compare/a.json"));
+        assert_eq!(export["sample_every"].as_integer(), Some(5));
+        assert!(export.get("kpi_csv").is_none());
+
+        let bare: toml::Value = toml::from_str(&redirect_spec("seed = 1\n[export]\nsummary_json = \"s.json\"\n", SpecOutput::TickTrace, Path::new("b.jsonl")).unwrap()).unwrap();
+        assert_eq!(bare["export"]["tick_trace"].as_str(), Some("b.jsonl"));
+        assert!(bare["export"].get("summary_json").is_none());
+    }
+}
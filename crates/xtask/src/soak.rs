@@ -0,0 +1,243 @@
+use serde::{Serialize, Deserialize};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use crate::SuiteResult;
+
+pub const DEFAULT_SOAK_SPEC: &str = "fixtures/soak/steady_load.toml";
+/// Samples taken while caches and queues fill up are not judged
+const WARMUP_FRACTION: f64 = 0.1;
+/// The judged samples are split into this many windows; a leak raises the floor of every one
+const LEAK_WINDOWS: usize = 4;
+const MIN_SAMPLES_PER_WINDOW: usize = 2;
+
+pub struct SoakOptions {
+    pub hours: f64,
+    pub spec: PathBuf,
+    pub sample_secs: u64,
+    pub max_rss_growth_mb: f64,
+    pub max_count_growth: f64,
+    pub port: u16,
+    pub output: PathBuf,
+}
+
+/// One reading of the headless process and the sim inside it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakSample {
+    pub elapsed_secs: f64,
+    pub tick: u64,
+    pub rss_kb: Option<u64>, // None off Linux
+    pub entities: u32,
+    pub kpi_buffer_entries: usize,
+    pub tracked_bytes: usize,
+}
+
+/// How much a series grew if it grew in every window and by more than the threshold.
+/// Each window's minimum is compared, so allocator churn and sawtooth buffers that
+/// drain back down don't count; only a rising floor does.
+pub fn monotonic_growth(values: &[f64], threshold: f64) -> Option<f64> {
+    let judged = &values[(values.len() as f64 * WARMUP_FRACTION) as usize..];
+    if judged.len() < LEAK_WINDOWS * MIN_SAMPLES_PER_WINDOW {
+        return None;
+    }
+    let window = judged.len() / LEAK_WINDOWS;
+    let floors: Vec<f64> = judged
+        .chunks(window)
+        .take(LEAK_WINDOWS)
+        .map(|w| w.iter().copied().fold(f64::INFINITY, f64::min))
+        .collect();
+    let rising = floors.windows(2).all(|pair| pair[1] > pair[0]);
+    let growth = floors[floors.len() - 1] - floors[0];
+    (rising && growth > threshold).then_some(growth)
+}
+
+/// A headless run of the spec with stop conditions lifted and sampling turned down,
+/// so the only thing growing over hours is whatever the sim itself keeps
+fn write_soak_spec(spec: &Path, work_dir: &Path) -> Result<PathBuf> {
+    let mut spec_value: toml::Value = toml::from_str(&std::fs::read_to_string(spec)?)?;
+    let table = spec_value
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("{} is not a TOML table", spec.display()))?;
+
+    let mut stop = toml::map::Map::new();
+    stop.insert("max_ticks".to_string(), toml::Value::Integer(i64::MAX));
+    stop.insert("on_victory".to_string(), toml::Value::Boolean(false));
+    stop.insert("on_loss".to_string(), toml::Value::Boolean(false));
+    table.insert("stop".to_string(), toml::Value::Table(stop));
+    table.remove("pause");
+
+    let mut export = toml::map::Map::new();
+    export.insert("sample_every".to_string(), toml::Value::Integer(i64::MAX));
+    table.insert("export".to_string(), toml::Value::Table(export));
+
+    let path = work_dir.join("soak_spec.toml");
+    std::fs::write(&path, toml::to_string(&spec_value)?)?;
+    Ok(path)
+}
+
+fn read_rss_kb(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    status
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|kb| kb.parse().ok())
+}
+
+fn spawn_headless(spec: &Path, port: u16) -> Result<Child> {
+    let status = Command::new("cargo")
+        .args(&["build", "--release", "-q", "-p", "colony-headless"])
+        .status()?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("Failed to build colony-headless"));
+    }
+    let child = Command::new("target/release/colony-headless")
+        .arg("--run-spec")
+        .arg(spec)
+        .args(&["--progress-secs", "0", "--progress-port", &port.to_string()])
+        .stdout(Stdio::null())
+        .spawn()?;
+    Ok(child)
+}
+
+async fn take_sample(client: &reqwest::Client, url: &str, pid: u32, started: Instant) -> Option<SoakSample> {
+    let progress: serde_json::Value = client.get(url).send().await.ok()?.json().await.ok()?;
+    let footprint = progress.get("footprint").filter(|f| !f.is_null())?;
+    Some(SoakSample {
+        elapsed_secs: started.elapsed().as_secs_f64(),
+        tick: footprint["tick"].as_u64().unwrap_or(0),
+        rss_kb: read_rss_kb(pid),
+        entities: footprint["entities"].as_u64().unwrap_or(0) as u32,
+        kpi_buffer_entries: footprint["kpi_buffer_entries"].as_u64().unwrap_or(0) as usize,
+        tracked_bytes: footprint["tracked_bytes"].as_u64().unwrap_or(0) as usize,
+    })
+}
+
+fn write_samples_csv(samples: &[SoakSample], path: &Path) -> Result<()> {
+    let mut csv = String::from("elapsed_secs,tick,rss_kb,entities,kpi_buffer_entries,tracked_bytes\n");
+    for s in samples {
+        csv.push_str(&format!(
+            "{:.0},{},{},{},{},{}\n",
+            s.elapsed_secs,
+            s.tick,
+            s.rss_kb.map(|kb| kb.to_string()).unwrap_or_default(),
+            s.entities,
+            s.kpi_buffer_entries,
+            s.tracked_bytes
+        ));
+    }
+    std::fs::write(path, csv)?;
+    Ok(())
+}
+
+/// Run the spec headless for `hours`, sampling as it goes, and fail on monotonic growth
+pub async fn run(opts: &SoakOptions) -> Result<SuiteResult> {
+    println!("🧯 Soaking {} for {} hours (sample every {}s)", opts.spec.display(), opts.hours, opts.sample_secs);
+    std::fs::create_dir_all(&opts.output)?;
+    let start = Instant::now();
+
+    let spec_path = write_soak_spec(&opts.spec, &opts.output)?;
+    let mut child = spawn_headless(&spec_path, opts.port)?;
+    let pid = child.id();
+    let url = format!("http://127.0.0.1:{}/session/progress", opts.port);
+    let client = reqwest::Client::new();
+    let deadline = start + Duration::from_secs_f64(opts.hours * 3600.0);
+    let interval = Duration::from_secs(opts.sample_secs.max(1));
+
+    let mut result = SuiteResult {
+        name: "soak".to_string(),
+        tests_run: 1,
+        ..Default::default()
+    };
+    let mut samples: Vec<SoakSample> = Vec::new();
+
+    while Instant::now() < deadline {
+        tokio::time::sleep(interval.min(deadline.saturating_duration_since(Instant::now()))).await;
+        if let Some(status) = child.try_wait()? {
+            result.errors.push(format!("Headless run exited after {} samples ({})", samples.len(), status));
+            break;
+        }
+        match take_sample(&client, &url, pid, start).await {
+            Some(sample) => {
+                println!(
+                    "  {:>7.0}s tick {:>10} | rss {:>8} kB | {} entities | {} kpi entries | {} tracked bytes",
+                    sample.elapsed_secs,
+                    sample.tick,
+                    sample.rss_kb.map(|kb| kb.to_string()).unwrap_or_else(|| "--".to_string()),
+                    sample.entities,
+                    sample.kpi_buffer_entries,
+                    sample.tracked_bytes
+                );
+                samples.push(sample);
+            }
+            None => result.warnings.push(format!("No progress at {:.0}s", start.elapsed().as_secs_f64())),
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+    write_samples_csv(&samples, &opts.output.join("soak_samples.csv"))?;
+
+    let rss_mb: Vec<f64> = samples.iter().filter_map(|s| s.rss_kb).map(|kb| kb as f64 / 1024.0).collect();
+    let tracked_mb: Vec<f64> = samples.iter().map(|s| s.tracked_bytes as f64 / (1024.0 * 1024.0)).collect();
+    let entities: Vec<f64> = samples.iter().map(|s| s.entities as f64).collect();
+    let kpi_entries: Vec<f64> = samples.iter().map(|s| s.kpi_buffer_entries as f64).collect();
+    let checks = [
+        ("rss_mb", &rss_mb, opts.max_rss_growth_mb),
+        ("tracked_mb", &tracked_mb, opts.max_rss_growth_mb),
+        ("entities", &entities, opts.max_count_growth),
+        ("kpi_buffer_entries", &kpi_entries, opts.max_count_growth),
+    ];
+
+    if samples.len() < LEAK_WINDOWS * MIN_SAMPLES_PER_WINDOW {
+        result.warnings.push(format!("Only {} samples; too few to judge growth", samples.len()));
+    }
+    for (name, series, threshold) in checks {
+        if let (Some(first), Some(last)) = (series.first(), series.last()) {
+            result.metrics.insert(format!("{}_first", name), *first);
+            result.metrics.insert(format!("{}_last", name), *last);
+        }
+        if let Some(growth) = monotonic_growth(series, threshold) {
+            result.errors.push(format!("{} grew monotonically by {:.1} (threshold {})", name, growth, threshold));
+        }
+    }
+    result.metrics.insert("samples".to_string(), samples.len() as f64);
+    result.metrics.insert("ticks".to_string(), samples.last().map_or(0.0, |s| s.tick as f64));
+
+    result.success = result.errors.is_empty();
+    result.tests_passed = result.success as u32;
+    result.tests_failed = !result.success as u32;
+    result.duration_ms = start.elapsed().as_millis() as u64;
+
+    for error in &result.errors {
+        println!("  ❌ {}", error);
+    }
+    if result.success {
+        println!("  ✅ No unbounded growth over {} samples", samples.len());
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rising_floor_is_a_leak() {
+        // Sawtooth whose floor climbs 10 per cycle
+        let leaking: Vec<f64> = (0..40).map(|i| (i / 4) as f64 * 10.0 + (i % 4) as f64 * 30.0).collect();
+        assert!(monotonic_growth(&leaking, 20.0).unwrap() > 20.0);
+        assert!(monotonic_growth(&leaking, 1000.0).is_none());
+    }
+
+    #[test]
+    fn test_bounded_churn_is_not_a_leak() {
+        // Fills then drains back to the same floor, like a capped ring buffer
+        let churn: Vec<f64> = (0..40).map(|i| 100.0 + (i % 5) as f64 * 50.0).collect();
+        assert!(monotonic_growth(&churn, 1.0).is_none());
+        // Growth that levels off after warmup
+        let plateau: Vec<f64> = (0..40).map(|i| (i.min(3) as f64) * 100.0).collect();
+        assert!(monotonic_growth(&plateau, 1.0).is_none());
+        assert!(monotonic_growth(&[1.0, 2.0, 3.0], 0.0).is_none());
+    }
+}